@@ -0,0 +1,54 @@
+/* C interface of msg_parser, built with the "ffi" feature:
+ *
+ *   cargo build --release --features ffi
+ *
+ * which writes libmsg_parser.so (.dylib, .dll) to target/release.
+ * Functions return MSG_OK, one of the codes below, or the code of
+ * the Rust error, msg_parser::Error::code, which never changes once
+ * released, e.g. 2 for a file that is not a valid compound file. */
+
+#ifndef MSG_PARSER_H
+#define MSG_PARSER_H
+
+#include <stddef.h>
+#include <stdint.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+#define MSG_OK 0
+#define MSG_INVALID_ARGUMENT 200 /* Null pointer, or a string holding a NUL byte */
+#define MSG_NO_ATTACHMENT 201    /* Attachment index out of range */
+#define MSG_PANIC 202            /* Parsing panicked, which is a bug to report */
+
+typedef struct MsgMessage MsgMessage;
+
+/* Parses the len bytes at data into JSON written to out, or the
+ * error message on failure. Either is freed with msg_free_string. */
+uint32_t msg_parse_json(const uint8_t *data, size_t len, char **out);
+
+/* Parses the len bytes at data into a message written to out, for
+ * its attachments to be enumerated, freed with msg_free. */
+uint32_t msg_open(const uint8_t *data, size_t len, MsgMessage **out);
+
+size_t msg_attachment_count(const MsgMessage *message);
+
+/* File name and MIME type of the attachment at index, freed with
+ * msg_free_string. */
+uint32_t msg_attachment_name(const MsgMessage *message, size_t index, char **out);
+uint32_t msg_attachment_mime_type(const MsgMessage *message, size_t index, char **out);
+
+/* Data of the attachment at index, freed with msg_free_bytes. out
+ * is NULL for attachments without data, e.g. embedded messages. */
+uint32_t msg_attachment_data(const MsgMessage *message, size_t index, uint8_t **out, size_t *len);
+
+void msg_free(MsgMessage *message);
+void msg_free_string(char *value);
+void msg_free_bytes(uint8_t *data, size_t len);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif
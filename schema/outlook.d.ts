@@ -0,0 +1,422 @@
+// Types of the objects parseToObject returns, written by
+// Outlook::typescript_definitions.
+
+export interface Outlook {
+  appointment?: Appointment | null;
+  attachments: Attachment[];
+  authentication: Authentication;
+  bcc: Person[];
+  body: string;
+  cc: Person[];
+  contact?: Contact | null;
+  conversation_index?: ConversationIndex | null;
+  delivery_time?: string | null;
+  dist_list?: DistList | null;
+  flags: MessageFlags;
+  follow_up?: FollowUpFlag | null;
+  headers: TransportHeaders;
+  importance: Importance;
+  in_reply_to: string;
+  links: Link[];
+  message_class: string;
+  message_id: string;
+  missing: MissingPart[];
+  non_delivery_report?: NonDeliveryReport | null;
+  priority: Priority;
+  protection: ProtectionStatus;
+  receipt?: Receipt | null;
+  recipients: Recipient[];
+  references: string[];
+  reminder?: Reminder | null;
+  reply_to: Person[];
+  rtf_compressed: Uint8Array;
+  sender: Person;
+  sensitivity: Sensitivity;
+  sensitivity_labels: SensitivityLabel[];
+  sent_representing: Person;
+  smime?: Smime | null;
+  sticky_note?: StickyNote | null;
+  subject: string;
+  submit_time?: string | null;
+  task?: Task | null;
+  time_zone?: TimeZone | null;
+  to: Person[];
+  voting?: Voting | null;
+  x_headers: ([string, string])[];
+}
+
+export interface Appointment {
+  all_day: boolean;
+  end?: string | null;
+  location: string;
+  meeting?: Meeting | null;
+  optional_attendees: Person[];
+  organizer: Person;
+  recurrence?: RecurrencePattern | null;
+  required_attendees: Person[];
+  start?: string | null;
+  subject: string;
+  time_zone?: TimeZone | null;
+  uid: string;
+}
+
+export interface Attachment {
+  by_reference: boolean;
+  display_name: string;
+  embedded?: # | null;
+  extension: string;
+  file_name: string;
+  has_macros: boolean;
+  mime_tag: string;
+  pathname: string;
+  payload: Uint8Array;
+}
+
+export interface AuthResult {
+  method: string;
+  properties: ([string, string])[];
+  verdict: Verdict;
+}
+
+export interface Authentication {
+  dkim_signatures: DkimSignature[];
+  received_spf: ReceivedSpf[];
+  results: AuthResult[];
+}
+
+export interface Certificate {
+  email_addresses: string[];
+  issuer: string;
+  not_after?: string | null;
+  not_before?: string | null;
+  serial_number: string;
+  subject: string;
+}
+
+export interface Contact {
+  company: string;
+  department: string;
+  display_name: string;
+  emails: Person[];
+  given_name: string;
+  job_title: string;
+  middle_name: string;
+  nickname: string;
+  phone_numbers: PhoneNumber[];
+  photo?: ContactPhoto | null;
+  prefix: string;
+  suffix: string;
+  surname: string;
+}
+
+export interface ContactPhoto {
+  extension: string;
+  mime_tag: string;
+  payload: Uint8Array;
+}
+
+export interface ConversationIndex {
+  guid: string;
+  replies: (string | null)[];
+  time?: string | null;
+}
+
+export interface DistList {
+  members: DistListMember[];
+  name: string;
+}
+
+export interface DistListMember {
+  address_type: string;
+  email: string;
+  name: string;
+}
+
+export interface DkimSignature {
+  algorithm?: string | null;
+  body_hash?: string | null;
+  canonicalization?: string | null;
+  domain?: string | null;
+  expiration?: string | null;
+  identity?: string | null;
+  selector?: string | null;
+  signature?: string | null;
+  signed_headers: string[];
+  timestamp?: string | null;
+  version?: string | null;
+}
+
+export type FlagStatus = "Complete" | "Flagged";
+
+export interface FollowUpFlag {
+  complete_time?: string | null;
+  due_date?: string | null;
+  reply_time?: string | null;
+  request: string;
+  start_date?: string | null;
+  status: FlagStatus;
+}
+
+export interface Headers {
+  fields: ([string, string])[];
+}
+
+export type Importance = "Low" | "Normal" | "High";
+
+export interface KeyRecipient {
+  issuer: string;
+  serial_number: string;
+}
+
+export interface Link {
+  mismatched: boolean;
+  sources: LinkSource[];
+  texts: string[];
+  url: string;
+}
+
+export type LinkSource = "Body" | "Html" | "Rtf" | "Headers";
+
+export interface Meeting {
+  kind: MeetingKind;
+  proposed_end?: string | null;
+  proposed_start?: string | null;
+  response_status: ResponseStatus;
+  sequence?: number | null;
+}
+
+export type MeetingKind = "Request" | "Accepted" | "Declined" | "Tentative" | "Canceled";
+
+export interface MessageFlags {
+  associated: boolean;
+  from_me: boolean;
+  has_attachments: boolean;
+  read: boolean;
+  resend: boolean;
+  submitted: boolean;
+  unmodified: boolean;
+  unsent: boolean;
+}
+
+export interface MimePart {
+  body: string;
+  headers: Headers;
+  parts: MimePart[];
+}
+
+export type MissingPart = "MessageClass" | "Sender" | "Recipients" | "SubmitTime" | "DeliveryTime";
+
+export interface NdrRecipient {
+  diagnostic_code?: number | null;
+  reason_code?: number | null;
+  recipient: Person;
+  remote_mta: string;
+  status_code?: number | null;
+  supplementary_info: string;
+}
+
+export interface NonDeliveryReport {
+  original: OriginalEnvelope;
+  recipients: NdrRecipient[];
+  report_text: string;
+}
+
+export type NoteColor = "Blue" | "Green" | "Pink" | "Yellow" | "White";
+
+export interface OriginalEnvelope {
+  display_cc: string;
+  display_to: string;
+  sender: Person;
+  subject: string;
+  submit_time?: string | null;
+}
+
+export interface Person {
+  email: string;
+  name: string;
+}
+
+export interface PhoneNumber {
+  kind: string;
+  number: string;
+}
+
+export type Priority = "NonUrgent" | "Normal" | "Urgent";
+
+export type ProtectionKind = "Unprotected" | "Signed" | "Encrypted" | "RightsManaged";
+
+export interface ProtectionStatus {
+  container: string;
+  encryption_algorithm: string;
+  key_recipients: KeyRecipient[];
+  kind: ProtectionKind;
+}
+
+export interface Receipt {
+  kind: ReceiptKind;
+  original: OriginalEnvelope;
+  original_message_id: string;
+  receipt_time?: string | null;
+  reporter: Person;
+}
+
+export type ReceiptKind = "Read" | "NotRead" | "Delivery";
+
+export interface ReceivedSpf {
+  client_ip?: string | null;
+  envelope_from?: string | null;
+  helo?: string | null;
+  receiver?: string | null;
+  verdict: Verdict;
+}
+
+export interface Recipient {
+  address_type: string;
+  display_name: string;
+  email_address: string;
+  entry_id: Uint8Array;
+  flags: number;
+  recipient_type: RecipientType;
+  responsible: boolean;
+  smtp_address: string;
+  track_status: ResponseStatus;
+}
+
+export type RecipientType = "Originator" | "To" | "Cc" | "Bcc";
+
+export type RecurrenceEnd = "Never" | { Date: string; } | { Count: number; };
+
+export interface RecurrenceException {
+  end: string;
+  location?: string | null;
+  original_start: string;
+  start: string;
+  subject?: string | null;
+}
+
+export type RecurrenceFrequency = "Daily" | "Weekly" | "Monthly" | "Yearly";
+
+export interface RecurrencePattern {
+  day_of_month?: number | null;
+  days: ("Mon" | "Tue" | "Wed" | "Thu" | "Fri" | "Sat" | "Sun")[];
+  deleted_dates: string[];
+  end: RecurrenceEnd;
+  end_time_offset: number;
+  exceptions: RecurrenceException[];
+  first_day_of_week: "Mon" | "Tue" | "Wed" | "Thu" | "Fri" | "Sat" | "Sun";
+  frequency: RecurrenceFrequency;
+  interval: number;
+  nth?: number | null;
+  start_date: string;
+  start_time_offset: number;
+}
+
+export interface Reminder {
+  delta_minutes: number;
+  override_default: boolean;
+  play_sound: boolean;
+  set: boolean;
+  signal_time?: string | null;
+  sound_file: string;
+  time?: string | null;
+}
+
+export type ResponseStatus = "None" | "Organized" | "Tentative" | "Accepted" | "Declined" | "NotResponded";
+
+export type Sensitivity = "Normal" | "Personal" | "Private" | "Confidential";
+
+export interface SensitivityLabel {
+  action_id: string;
+  content_bits: number;
+  enabled: boolean;
+  id: string;
+  method: string;
+  name: string;
+  owner: string;
+  parent_id: string;
+  set_date?: string | null;
+  site_id: string;
+}
+
+export interface Smime {
+  certificates: Certificate[];
+  content: MimePart;
+  kind: SmimeKind;
+  signers: SmimeSigner[];
+}
+
+export type SmimeKind = "ClearSigned" | "OpaqueSigned";
+
+export interface SmimeSigner {
+  digest_algorithm: string;
+  digest_matches?: boolean | null;
+  issuer: string;
+  serial_number: string;
+  signing_time?: string | null;
+}
+
+export interface StickyNote {
+  body: string;
+  color: NoteColor;
+  height: number;
+  width: number;
+  x: number;
+  y: number;
+}
+
+export interface Task {
+  assigner: string;
+  complete: boolean;
+  date_completed?: string | null;
+  due_date?: string | null;
+  owner: string;
+  percent_complete: number;
+  request?: TaskRequestKind | null;
+  start_date?: string | null;
+  status: TaskStatus;
+}
+
+export type TaskRequestKind = "Request" | "Accepted" | "Declined" | "Update";
+
+export type TaskStatus = "NotStarted" | "InProgress" | "Complete" | "WaitingOnOthers" | "Deferred";
+
+export interface TimeZone {
+  key_name: string;
+  rules: TimeZoneRule[];
+}
+
+export interface TimeZoneRule {
+  bias: number;
+  daylight_bias: number;
+  daylight_date: TransitionDate;
+  effective: boolean;
+  recur_current: boolean;
+  standard_bias: number;
+  standard_date: TransitionDate;
+  year: number;
+}
+
+export interface TransitionDate {
+  day: number;
+  day_of_week: number;
+  hour: number;
+  milliseconds: number;
+  minute: number;
+  month: number;
+  second: number;
+  year: number;
+}
+
+export interface TransportHeaders {
+  content_type: string;
+  date: string;
+  message_id: string;
+  reply_to: string;
+}
+
+export type Verdict = "Pass" | "Fail" | "SoftFail" | "Neutral" | "None" | "TempError" | "PermError" | "Policy" | "Unknown";
+
+export interface Voting {
+  options: string[];
+  response?: string | null;
+}
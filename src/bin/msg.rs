@@ -0,0 +1,315 @@
+use std::{
+    fs,
+    io::{self, BufWriter, Write},
+    path::{Path, PathBuf},
+    process,
+};
+
+use clap::{Args, Parser, Subcommand};
+
+use msg_parser::{
+    parse_size, AttachmentFilter, BinaryEncoding, Error, HashAlgorithm, JsonOptions, KeyCase,
+    Outlook, Person,
+};
+
+// msg reads Outlook .msg files from the command line.
+#[derive(Debug, Parser)]
+#[command(name = "msg", version, about = "Read Outlook .msg files")]
+struct Cli {
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Debug, Subcommand)]
+enum Command {
+    /// Print the message as JSON
+    Json(JsonArgs),
+    /// Convert the message to RFC 5322 (.eml)
+    Eml {
+        file: PathBuf,
+        /// Write to this file rather than to the standard output
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+    },
+    /// Work with the attachments of the message
+    #[command(subcommand)]
+    Attachments(AttachmentsCommand),
+    /// Print the transport headers of the message
+    Headers { file: PathBuf },
+    /// Print the sender, recipients, subject, date and attachments
+    Summary { file: PathBuf },
+    /// List every entry of the file with its property tag and size, in hex those not decoded
+    Dump {
+        file: PathBuf,
+        /// Print the entries as JSON
+        #[arg(long)]
+        json: bool,
+    },
+}
+
+#[derive(Debug, Args)]
+struct JsonArgs {
+    file: PathBuf,
+    /// Indent the JSON over several lines
+    #[arg(long)]
+    pretty: bool,
+    /// Write keys in camelCase
+    #[arg(long)]
+    camel_case: bool,
+    /// Write binary values in base64 rather than hex
+    #[arg(long)]
+    base64: bool,
+    /// Leave out empty fields
+    #[arg(long)]
+    omit_empty: bool,
+    /// Leave out the bodies
+    #[arg(long)]
+    no_bodies: bool,
+    /// Leave out the data of attachments
+    #[arg(long)]
+    no_attachment_data: bool,
+}
+
+#[derive(Debug, Subcommand)]
+enum AttachmentsCommand {
+    /// Write the attachments to a directory, and print their manifest as JSON
+    Extract {
+        file: PathBuf,
+        /// Directory to write to, created if needed
+        #[arg(short, long, default_value = ".")]
+        dir: PathBuf,
+        /// Only attachments whose name matches this glob, e.g. '*.pdf'
+        #[arg(long)]
+        filter: Option<String>,
+        /// Only attachments of at least this size, e.g. 1k
+        #[arg(long, value_parser = size)]
+        min_size: Option<u64>,
+        /// Only attachments of at most this size, e.g. 50M
+        #[arg(long, value_parser = size)]
+        max_size: Option<u64>,
+        /// Digest of each attachment added to the manifest, e.g. sha256
+        #[arg(long, value_parser = hash)]
+        hash: Option<HashAlgorithm>,
+    },
+}
+
+fn size(value: &str) -> Result<u64, String> {
+    parse_size(value).ok_or_else(|| format!("invalid size {:?}", value))
+}
+
+fn hash(value: &str) -> Result<HashAlgorithm, String> {
+    HashAlgorithm::from_name(value).ok_or_else(|| format!("unknown hash {:?}", value))
+}
+
+fn person(person: &Person) -> String {
+    match (person.name.is_empty(), person.email.is_empty()) {
+        (_, true) => person.name.to_string(),
+        (true, _) => person.email.to_string(),
+        _ if person.name == person.email => person.email.to_string(),
+        _ => format!("{} <{}>", person.name, person.email),
+    }
+}
+
+fn persons(persons: &[Person]) -> String {
+    persons.iter().map(person).collect::<Vec<String>>().join(", ")
+}
+
+fn json(args: &JsonArgs, out: &mut dyn Write) -> Result<(), Error> {
+    let outlook = Outlook::from_path(&args.file)?;
+    let mut options = JsonOptions::new()
+        .pretty(args.pretty)
+        .omit_empty(args.omit_empty)
+        .bodies(!args.no_bodies)
+        .attachment_data(!args.no_attachment_data);
+    if args.camel_case {
+        options = options.key_case(KeyCase::Camel);
+    }
+    if args.base64 {
+        options = options.binary(BinaryEncoding::Base64);
+    }
+    writeln!(out, "{}", outlook.to_json_with(&options)?)?;
+    Ok(())
+}
+
+fn headers(outlook: &Outlook, out: &mut dyn Write) -> Result<(), Error> {
+    for (name, value) in outlook.transport_headers().iter() {
+        writeln!(out, "{}: {}", name, value)?;
+    }
+    Ok(())
+}
+
+fn summary(outlook: &Outlook, out: &mut dyn Write) -> Result<(), Error> {
+    writeln!(out, "Subject: {}", outlook.subject)?;
+    writeln!(out, "From: {}", person(&outlook.sender))?;
+    if outlook.is_delegated() {
+        writeln!(out, "On behalf of: {}", person(&outlook.sent_representing))?;
+    }
+    writeln!(out, "To: {}", persons(&outlook.to))?;
+    if !outlook.cc.is_empty() {
+        writeln!(out, "Cc: {}", persons(&outlook.cc))?;
+    }
+    if !outlook.bcc.is_empty() {
+        writeln!(out, "Bcc: {}", persons(&outlook.bcc))?;
+    }
+    if let Some(date) = outlook.submit_time.or(outlook.delivery_time) {
+        writeln!(out, "Date: {}", date.to_rfc2822())?;
+    }
+    writeln!(out, "Class: {}", outlook.message_class)?;
+    writeln!(out, "Attachments: {}", outlook.attachments.len())?;
+    for attachment in &outlook.attachments {
+        let name = [&attachment.display_name, &attachment.file_name]
+            .iter()
+            .find(|name| !name.is_empty())
+            .map_or("(unnamed)", |name| name.as_str());
+        match &attachment.embedded {
+            Some(_) => writeln!(out, "  {} (message)", name)?,
+            None if attachment.by_reference => {
+                writeln!(out, "  {} (reference to {})", name, attachment.pathname)?
+            }
+            None => writeln!(out, "  {} ({} bytes)", name, attachment.payload.len() / 2)?,
+        }
+    }
+    Ok(())
+}
+
+fn dump(file: &Path, json: bool, out: &mut dyn Write) -> Result<(), Error> {
+    let entries = Outlook::dump(file)?;
+    if json {
+        writeln!(out, "{}", serde_json::to_string_pretty(&entries)?)?;
+        return Ok(());
+    }
+    for entry in &entries {
+        writeln!(out, "{}", entry)?;
+    }
+    Ok(())
+}
+
+fn run(command: Command, out: &mut dyn Write) -> Result<(), Error> {
+    match command {
+        Command::Json(args) => json(&args, out),
+        Command::Eml { file, output } => {
+            let eml = Outlook::from_path(file)?.to_eml();
+            match output {
+                Some(path) => fs::write(path, eml)?,
+                None => out.write_all(eml.as_bytes())?,
+            }
+            Ok(())
+        }
+        Command::Attachments(AttachmentsCommand::Extract {
+            file,
+            dir,
+            filter,
+            min_size,
+            max_size,
+            hash,
+        }) => {
+            let outlook = Outlook::from_path(file)?;
+            let mut attachment_filter = AttachmentFilter::new();
+            if let Some(pattern) = filter {
+                attachment_filter = attachment_filter.pattern(&pattern);
+            }
+            if let Some(size) = min_size {
+                attachment_filter = attachment_filter.min_size(size);
+            }
+            if let Some(size) = max_size {
+                attachment_filter = attachment_filter.max_size(size);
+            }
+            fs::create_dir_all(&dir)?;
+            let manifest = outlook.extract_attachments(&dir, &attachment_filter, hash)?;
+            writeln!(out, "{}", manifest.to_json()?)?;
+            Ok(())
+        }
+        Command::Headers { file } => headers(&Outlook::from_path(file)?, out),
+        Command::Summary { file } => summary(&Outlook::from_path(file)?, out),
+        Command::Dump { file, json } => dump(&file, json, out),
+    }
+}
+
+fn main() {
+    let cli = Cli::parse();
+    let stdout = io::stdout();
+    let mut out = BufWriter::new(stdout.lock());
+    let result = run(cli.command, &mut out).and_then(|_| Ok(out.flush()?));
+    if let Err(err) = result {
+        eprintln!("msg: {}", err);
+        process::exit(1);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use clap::Parser;
+
+    use super::{run, Cli};
+
+    fn output(args: &[&str]) -> String {
+        let cli = Cli::try_parse_from(args).unwrap();
+        let mut out = vec![];
+        run(cli.command, &mut out).unwrap();
+        String::from_utf8(out).unwrap()
+    }
+
+    #[test]
+    fn test_summary() {
+        let text = output(&["msg", "summary", "data/attachment.msg"]);
+        assert!(text.starts_with("Subject: "));
+        assert!(text.contains("\nAttachments: 3\n  loan_proposal.doc ("));
+    }
+
+    #[test]
+    fn test_json_and_headers() {
+        let text = output(&["msg", "json", "data/test_email.msg", "--camel-case", "--no-bodies"]);
+        let json: serde_json::Value = serde_json::from_str(&text).unwrap();
+        assert!(json["messageClass"].is_string());
+        assert!(json.get("body").is_none());
+
+        let text = output(&["msg", "headers", "data/test_email_1.msg"]);
+        assert!(text.lines().all(|line| line.contains(": ")));
+        assert!(!text.is_empty());
+    }
+
+    #[test]
+    fn test_attachments_extract() {
+        let dir = std::env::temp_dir().join(format!("msg-cli-{}", std::process::id()));
+        let dir_arg = dir.to_str().unwrap();
+        let text = output(&[
+            "msg",
+            "attachments",
+            "extract",
+            "data/attachment.msg",
+            "--dir",
+            dir_arg,
+            "--filter",
+            "*.doc",
+            "--hash",
+            "sha256",
+        ]);
+        let manifest: serde_json::Value = serde_json::from_str(&text).unwrap();
+        let attachments = manifest["attachments"].as_array().unwrap();
+        assert_eq!(attachments.len(), 1);
+        assert_eq!(attachments[0]["file_name"], "loan_proposal.doc");
+        assert!(dir.join("loan_proposal.doc").exists());
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        assert!(Cli::try_parse_from(["msg", "attachments", "extract", "a.msg", "--min-size", "1x"]).is_err());
+        assert!(Cli::try_parse_from(["msg", "attachments", "extract", "a.msg", "--hash", "md5"]).is_err());
+    }
+
+    #[test]
+    fn test_dump() {
+        let text = output(&["msg", "dump", "data/test_email.msg"]);
+        assert!(text.starts_with("/\n"));
+        assert!(text.contains("\n__substg1.0_0037001F 0x0037001F "));
+
+        let text = output(&["msg", "dump", "data/test_email.msg", "--json"]);
+        let json: serde_json::Value = serde_json::from_str(&text).unwrap();
+        assert_eq!(json[0]["kind"], "Root");
+    }
+
+    #[test]
+    fn test_eml() {
+        let text = output(&["msg", "eml", "data/unicode.msg"]);
+        assert!(text.contains("\r\nSubject: "));
+        assert!(run(Cli::try_parse_from(["msg", "eml", "data/bad_outlook.msg"]).unwrap().command, &mut vec![]).is_err());
+    }
+}
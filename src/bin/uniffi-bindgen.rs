@@ -0,0 +1,5 @@
+// Generates the Kotlin and Swift bindings of the library
+// built with the "uniffi" feature, see the README.
+fn main() {
+    uniffi::uniffi_bindgen_main()
+}
@@ -1,6 +1,13 @@
-// OLE Reader
+// OLE Reader, public with the "ole" feature
+#[cfg(feature = "ole")]
+pub mod ole;
+#[cfg(not(feature = "ole"))]
 mod ole;
 
 // Outlook Email Message File Parser
 mod parser;
 pub use parser::*;
+
+// Scaffolding of the Kotlin and Swift bindings
+#[cfg(feature = "uniffi")]
+uniffi::setup_scaffolding!();
@@ -26,6 +26,7 @@ impl std::fmt::Display for NodeColour {
   }
 }
 
+/// Type of an entry of the directory.
 #[derive(PartialEq, Debug, Clone, Copy)]
 pub enum EntryType {
   /// Empty entry.
@@ -128,6 +129,9 @@ pub struct Entry {
   /// Last modification time.
   last_modification_time: u64,
 
+  /// SecID of the first sector of the stream, as the directory holds it
+  start_sec_id: u32,
+
   /// Chain of secID which hold the stream or the storage
   sec_id_chain: std::vec::Vec<u32>,
 
@@ -143,7 +147,7 @@ pub struct Entry {
 
 impl Entry {
 
-  fn from_slice(sector: &[u8], dir_id: u32)
+  pub(crate) fn from_slice(sector: &[u8], dir_id: u32)
       -> Result<Entry, super::error::Error> {
     let entry = Entry {
       id: dir_id,
@@ -157,6 +161,7 @@ impl Entry {
       flags: sector[96 .. 100].to_vec(),
       creation_time: u64::from_slice(&sector[100 .. 108]),
       last_modification_time: u64::from_slice(&sector[108 .. 116]),
+      start_sec_id: u32::from_slice(&sector[116 .. 120]),
       sec_id_chain: vec![u32::from_slice(&sector[116 .. 120])],
       size: usize::from_slice(&sector[120 .. 124]),
       children_nodes: std::vec::Vec::new(),
@@ -168,13 +173,35 @@ impl Entry {
 
   }
 
+  /// Returns an empty entry, standing for one which could not be read.
+  pub(crate) fn empty(dir_id: u32) -> Entry {
+    Entry {
+      id: dir_id,
+      name: std::string::String::new(),
+      entry_type: EntryType::Empty,
+      color: NodeColour::Black,
+      left_child_node: super::constants::FREE_SECID_U32,
+      right_child_node: super::constants::FREE_SECID_U32,
+      root_node: super::constants::FREE_SECID_U32,
+      identifier: vec![0u8; 16],
+      flags: vec![0u8; 4],
+      creation_time: 0,
+      last_modification_time: 0,
+      start_sec_id: super::constants::END_OF_CHAIN_SECID_U32,
+      sec_id_chain: std::vec::Vec::new(),
+      size: 0,
+      children_nodes: std::vec::Vec::new(),
+      parent_node: None
+    }
+  }
+
   fn build_name(array: &[u8]) -> std::string::String {
     let mut name = std::string::String::new();
 
     let mut i = 0usize;
     while i < 64 && array[i] != 0 {
       name.push(array[i] as char);
-      i = i + 2;
+      i += 2;
     }
 
     name
@@ -211,6 +238,11 @@ impl Entry {
     self.size
   }
 
+  /// Returns true if the entry has no data
+  pub fn is_empty(&self) -> bool {
+    self.size == 0
+  }
+
   /// Returns the DirID of the left child node
   pub fn left_child_node(&self) -> u32 {
     self.left_child_node
@@ -221,6 +253,29 @@ impl Entry {
     self.right_child_node
   }
 
+  /// Returns the CLSID of the storage, all zeroes when not set
+  pub fn clsid(&self) -> [u8; 16] {
+    let mut clsid = [0u8; 16];
+    clsid.copy_from_slice(&self.identifier);
+    clsid
+  }
+
+  /// Returns the SecID of the first sector of the stream
+  pub(crate) fn start_sec_id(&self) -> u32 {
+    self.start_sec_id
+  }
+
+  /// Sets the chain of SecIDs holding the stream or the storage
+  pub(crate) fn set_sec_id_chain(&mut self, chain: std::vec::Vec<u32>) {
+    self.sec_id_chain = chain;
+  }
+
+  /// Returns the DirID of the root of the tree of the children, for a
+  /// storage
+  pub(crate) fn child_node(&self) -> u32 {
+    self.root_node
+  }
+
   /// Returns the DirID of the parent, if exists
   pub fn parent_node(&self) -> Option<u32> {
     self.parent_node
@@ -230,6 +285,16 @@ impl Entry {
   pub fn children_nodes(&self) -> &std::vec::Vec<u32> {
     &self.children_nodes
   }
+
+  /// Returns the number of bytes allocated for the entry.
+  pub(crate) fn allocated_len(&self) -> usize {
+    std::mem::size_of::<Entry>()
+      + self.name.capacity()
+      + self.identifier.capacity()
+      + self.flags.capacity()
+      + self.sec_id_chain.capacity() * std::mem::size_of::<u32>()
+      + self.children_nodes.capacity() * std::mem::size_of::<u32>()
+  }
 }
 
 impl std::fmt::Display for Entry {
@@ -269,7 +334,7 @@ pub struct EntrySlice<'s> {
   max_chunk_size: usize,
 
   /// List of slices.
-  chunks: std::vec::Vec<&'s [u8]>,
+  chunks: std::vec::Vec<std::borrow::Cow<'s, [u8]>>,
 
   /// How many bytes which have been already read.
   read: usize,
@@ -284,7 +349,7 @@ pub struct EntrySlice<'s> {
 impl<'s> EntrySlice<'s> {
   fn new(max_chunk_size: usize, size: usize) -> EntrySlice<'s> {
     EntrySlice {
-      max_chunk_size: max_chunk_size,
+      max_chunk_size,
       chunks: std::vec::Vec::new(),
       read: 0usize,
       total_size: size,
@@ -292,7 +357,7 @@ impl<'s> EntrySlice<'s> {
     }
   }
 
-  fn add_chunk(&mut self, chunk: &'s [u8]) {
+  fn add_chunk(&mut self, chunk: std::borrow::Cow<'s, [u8]>) {
     self.real_size += chunk.len();
     self.chunks.push(chunk);
   }
@@ -302,6 +367,11 @@ impl<'s> EntrySlice<'s> {
     self.total_size
   }
 
+  /// Returns true if the slice, therefore the entry, is empty.
+  pub fn is_empty(&self) -> bool {
+    self.total_size == 0
+  }
+
   /// Returns the real length of all chunks
   pub fn real_len(&self) -> usize {
     self.real_size
@@ -311,34 +381,60 @@ impl<'s> EntrySlice<'s> {
 impl<'s> std::io::Read for EntrySlice<'s> {
 
   fn read(&mut self, buf: &mut [u8]) -> Result<usize, std::io::Error> {
-    let to_read = std::cmp::min(buf.len(), self.total_size - self.read);
-    let result: Result<usize, std::io::Error>;
-    if to_read == 0 {
-      result = Ok(0usize);
-    } else {
-      let mut offset = self.read;
-      let mut read = 0;
-      while read != to_read {
-        let chunk_index = offset / self.max_chunk_size;
-        if chunk_index >= self.chunks.len() {
-          break;
-        }
-        let chunk = &self.chunks[chunk_index];
-        let local_offset = offset % self.max_chunk_size;
-        let end = std::cmp::min(local_offset + to_read - read,
-        self.max_chunk_size);
-        let slice = &chunk[local_offset .. end];
-        for u in slice {
-          buf[read] = *u;
-          read += 1;
-          self.read += 1;
-        }
-        offset = self.read;
+    use std::io::BufRead;
+    let mut read = 0;
+    while read < buf.len() {
+      let available = self.fill_buf()?;
+      if available.is_empty() {
+        break;
       }
-      result = Ok(read);
+      let len = std::cmp::min(available.len(), buf.len() - read);
+      buf[read .. read + len].copy_from_slice(&available[.. len]);
+      self.consume(len);
+      read += len;
     }
+    Ok(read)
+  }
+}
+
+/// Reads the slice chunk by chunk, each one being a sector of the entry.
+impl<'s> std::io::BufRead for EntrySlice<'s> {
+
+  fn fill_buf(&mut self) -> Result<&[u8], std::io::Error> {
+    let remaining = self.total_size.saturating_sub(self.read);
+    let local_offset = self.read % self.max_chunk_size;
+    match self.chunks.get(self.read / self.max_chunk_size) {
+      Some(chunk) if local_offset < chunk.len() => {
+        let end = std::cmp::min(chunk.len(), local_offset + remaining);
+        Ok(&chunk[local_offset .. end])
+      },
+      _ => Ok(&[])
+    }
+  }
 
-    result
+  fn consume(&mut self, amt: usize) {
+    self.read += amt;
+  }
+}
+
+/// Seeks within the entry, e.g. past the header of a property stream.
+/// Seeking beyond the end is allowed, reads then returning nothing.
+impl<'s> std::io::Seek for EntrySlice<'s> {
+
+  fn seek(&mut self, pos: std::io::SeekFrom) -> Result<u64, std::io::Error> {
+    let position = match pos {
+      std::io::SeekFrom::Start(offset) => Some(offset),
+      std::io::SeekFrom::End(offset) =>
+        (self.total_size as u64).checked_add_signed(offset),
+      std::io::SeekFrom::Current(offset) =>
+        (self.read as u64).checked_add_signed(offset)
+    };
+    let read = position
+      .and_then(|position| std::convert::TryFrom::try_from(position).ok())
+      .ok_or_else(|| std::io::Error::new(std::io::ErrorKind::InvalidInput,
+        "invalid seek to a negative or overflowing position"))?;
+    self.read = read;
+    Ok(read as u64)
   }
 }
 
@@ -349,27 +445,97 @@ impl<'ole> super::ole::Reader<'ole> {
 
 
   /// Returns the slice for the entry.
+  #[cfg_attr(feature = "tracing", tracing::instrument(level = "trace",
+    skip_all, fields(name = entry.name(), size = entry.size)))]
   pub fn get_entry_slice(&self, entry: &Entry) ->
-    Result<EntrySlice, super::error::Error> {
+    Result<EntrySlice<'_>, super::error::Error> {
 
     let entry_slice: EntrySlice;
     let size = entry.size;
     if size == 0 {
       Err(super::error::Error::EmptyEntry)
+    } else if let Some(max) = self.max_stream_size.filter(|max| size > *max) {
+      Err(super::error::Error::StreamLimitExceeded(max))
     } else {
       if &size < self.minimum_standard_stream_size.as_ref().unwrap() {
-        entry_slice = self.get_short_stream_slices(&entry.sec_id_chain, size)?;
+        entry_slice = self.get_short_stream_slices(&entry.sec_id_chain, size,
+          false)?;
       } else {
-        entry_slice = self.get_stream_slices(&entry.sec_id_chain, size)?;
+        entry_slice = self.get_stream_slices(&entry.sec_id_chain, size,
+          false)?;
       }
       Ok(entry_slice)
     }
   }
 
+  /// Same as `get_entry_slice` for a stream of a damaged file, e.g. a
+  /// truncated one: the slice holds the bytes of the stream up to the first
+  /// sector which cannot be read, or its chain ends, and is shorter than
+  /// the entry then.
+  ///
+  /// # Examples
+  ///
+  /// ```ignore
+  /// use msg_parser::ole::Reader;
+  /// let bytes = std::fs::read("data/attachment.msg").unwrap();
+  /// let parser = Reader::salvage(&bytes[.. bytes.len() / 2]).unwrap();
+  /// for entry in parser.iterate() {
+  ///   if let Ok(slice) = parser.get_readable_entry_slice(entry) {
+  ///     println!("{}: {} of {} bytes", entry.name(), slice.len(), entry.len());
+  ///   }
+  /// }
+  /// ```
+  pub fn get_readable_entry_slice(&self, entry: &Entry) ->
+    Result<EntrySlice<'_>, super::error::Error> {
+    let size = entry.size;
+    if size == 0 {
+      Err(super::error::Error::EmptyEntry)
+    } else if let Some(max) = self.max_stream_size.filter(|max| size > *max) {
+      Err(super::error::Error::StreamLimitExceeded(max))
+    } else if &size < self.minimum_standard_stream_size.as_ref().unwrap() {
+      self.get_short_stream_slices(&entry.sec_id_chain, size, true)
+    } else {
+      self.get_stream_slices(&entry.sec_id_chain, size, true)
+    }
+  }
+
+  /// Returns the bytes of the entry, borrowed from the body of the file when
+  /// the entry is stored in consecutive sectors of a body held in memory,
+  /// and copied otherwise.
+  pub fn get_entry_bytes(&self, entry: &Entry) ->
+    Result<std::borrow::Cow<'_, [u8]>, super::error::Error> {
+    use std::io::Read;
+
+    let size = entry.size;
+    let chain = &entry.sec_id_chain;
+    let sector_size = *self.sec_size.as_ref().unwrap();
+    let standard = size >= *self.minimum_standard_stream_size.as_ref()
+      .unwrap();
+    let consecutive = !chain.is_empty() && chain.len() * sector_size >= size
+      && chain.windows(2).all(|ids| ids[1] == ids[0].wrapping_add(1));
+    let allowed = self.max_stream_size.is_none_or(|max| size <= max);
+    if standard && consecutive && allowed {
+      let offset = self.sector_offset(chain[0] as usize);
+      if let Some(body) = self.body.as_ref()
+          .filter(|body| body.len() >= offset + size) {
+        return body.read(offset, size);
+      }
+    }
+    let mut slice = self.get_entry_slice(entry)?;
+    let mut bytes = std::vec::Vec::with_capacity(slice.len());
+    slice.read_to_end(&mut bytes).map_err(super::error::Error::IOError)?;
+    Ok(std::borrow::Cow::Owned(bytes))
+  }
+
+  #[cfg_attr(feature = "tracing", tracing::instrument(level = "debug", skip_all))]
   pub(crate) fn build_directory_entries(&mut self)
       -> Result<(), super::error::Error> {
     let n_entry_by_sector = self.sec_size.as_ref().unwrap()
       / super::constants::DIRECTORY_ENTRY_SIZE;
+    let n_entries = self.dsat.as_ref().unwrap().len() * n_entry_by_sector;
+    if let Some(max) = self.max_entries.filter(|max| n_entries > *max) {
+      return Err(super::error::Error::EntryLimitExceeded(max));
+    }
     let mut entries = std::vec::Vec::<Entry>::with_capacity(
       self.dsat.as_ref().unwrap().len() * n_entry_by_sector);
 
@@ -382,36 +548,47 @@ impl<'ole> super::ole::Reader<'ole> {
           * super::constants::DIRECTORY_ENTRY_SIZE .. (l + 1)
           * super::constants::DIRECTORY_ENTRY_SIZE], k as u32)?;
         entries.push(entry);
-        k = k + 1;
+        k += 1;
       }
     }
     let stream_size = *self.minimum_standard_stream_size.as_ref().unwrap();
-    for i in 0 .. entries.len() {
-      let entry = &mut entries[i];
+    let sector_size = *self.sec_size.as_ref().unwrap();
+    let short_sector_size = *self.short_sec_size.as_ref().unwrap();
+    // Chains are read up to the sectors the size of the entry needs.
+    for (i, entry) in entries.iter_mut().enumerate() {
       match entry.entry_type {
         EntryType::UserStream => {
           let start_index = entry.sec_id_chain.pop().unwrap();
           if entry.size < stream_size {
-            entry.sec_id_chain = self.build_chain_from_ssat(start_index);
+            entry.sec_id_chain = self.build_chain_from_ssat(start_index,
+              Some(entry.size.div_ceil(short_sector_size)));
           } else {
-            entry.sec_id_chain = self.build_chain_from_sat(start_index);
+            entry.sec_id_chain = self.build_chain_from_sat(start_index,
+              Some(entry.size.div_ceil(sector_size)));
           }
         },
         EntryType::RootStorage => {
           self.root_entry = Some(i as u32);
           let start_index = entry.sec_id_chain.pop().unwrap();
-          entry.sec_id_chain = self.build_chain_from_sat(start_index);
+          entry.sec_id_chain = self.build_chain_from_sat(start_index,
+            Some(entry.size.div_ceil(sector_size)));
         },
         _ => {}
       }
     }
     self.entries = Some(entries);
-    self.build_entry_tree(0, None);
+    let mut visited = vec![false; self.entries.as_ref().unwrap().len()];
+    self.build_entry_tree(0, None, &mut visited);
+    #[cfg(feature = "tracing")]
+    tracing::debug!(entries = visited.iter().filter(|v| **v).count(),
+      "directory entries read");
     Ok(())
   }
 
-  fn get_short_stream_slices(&self, chain: &std::vec::Vec<u32>, size: usize)
-  -> Result<EntrySlice, super::error::Error> {
+  /// Reads the short sectors of `chain`, up to the first which cannot be
+  /// read if `partial`, the slice being as long as them.
+  fn get_short_stream_slices(&self, chain: &std::vec::Vec<u32>, size: usize,
+    partial: bool) -> Result<EntrySlice<'_>, super::error::Error> {
     let ssector_size = *self.short_sec_size.as_ref().unwrap();
     let mut entry_slice = EntrySlice::new(ssector_size, size);
     let short_stream_chain =
@@ -420,42 +597,75 @@ impl<'ole> super::ole::Reader<'ole> {
       ssector_size;
     let mut total_read = 0;
     for ssector_id in chain {
-      let sector_index = short_stream_chain[*ssector_id as usize / n_per_sector];
-      let sector = self.read_sector(sector_index as usize)?;
       let ssector_index = *ssector_id as usize % n_per_sector;
-      let start = ssector_index as usize * ssector_size;
+      let start = ssector_index * ssector_size;
       let end = start + std::cmp::min(ssector_size, size - total_read);
-      entry_slice.add_chunk(&sector[start .. end]);
+      let chunk = short_stream_chain
+        .get(*ssector_id as usize / n_per_sector)
+        .ok_or(super::error::Error::BadSizeValue(
+          "Short sector out of the short stream"))
+        .and_then(|sector_index| self.read_sector_part(*sector_index as usize,
+          start, end));
+      match chunk {
+        Ok(chunk) => entry_slice.add_chunk(chunk),
+        Err(_) if partial => break,
+        Err(err) => return Err(err)
+      }
       total_read += end - start;
     }
+    if partial {
+      entry_slice.total_size = total_read;
+    }
     Ok(entry_slice)
   }
 
-  fn get_stream_slices(&self, chain: &std::vec::Vec<u32>, size: usize)
-  -> Result<EntrySlice, super::error::Error> {
+  /// Reads the sectors of `chain`, up to the first which cannot be read if
+  /// `partial`, the slice being as long as them.
+  fn get_stream_slices(&self, chain: &std::vec::Vec<u32>, size: usize,
+    partial: bool) -> Result<EntrySlice<'_>, super::error::Error> {
     let sector_size = *self.sec_size.as_ref().unwrap();
     let mut entry_slice = EntrySlice::new(sector_size, size);
     let mut total_read = 0;
     for sector_id in chain {
-      let sector = self.read_sector(*sector_id as usize)?;
+      let sector = match self.read_sector(*sector_id as usize) {
+        Ok(sector) => sector,
+        Err(_) if partial => break,
+        Err(err) => return Err(err)
+      };
       let start = 0usize;
       let end = std::cmp::min(sector_size, size - total_read);
-      entry_slice.add_chunk(&sector[start .. end]);
+      entry_slice.add_chunk(match sector {
+        std::borrow::Cow::Borrowed(sector) => {
+          std::borrow::Cow::Borrowed(&sector[start .. end])
+        },
+        std::borrow::Cow::Owned(mut sector) => {
+          sector.truncate(end);
+          std::borrow::Cow::Owned(sector)
+        }
+      });
       total_read += end - start;
     }
+    if partial {
+      entry_slice.total_size = total_read;
+    }
     Ok(entry_slice)
   }
 
-  fn build_entry_tree(&mut self, id: u32, parent_id: Option<u32>) {
+  /// Registers the parents and children of the entries from `id`. Entries
+  /// already registered are skipped, as crafted files may link entries in a
+  /// cycle.
+  pub(crate) fn build_entry_tree(&mut self, id: u32, parent_id: Option<u32>,
+      visited: &mut [bool]) {
 
-    if id != super::constants::FREE_SECID_U32 {
+    if (id as usize) < visited.len() && !visited[id as usize] {
+      visited[id as usize] = true;
 
       // Register the parent id for the current node
       self.entries.as_mut().unwrap()[id as usize].parent_node = parent_id;
 
       // Register as child
-      if parent_id.is_some() {
-        self.entries.as_mut().unwrap()[parent_id.unwrap() as usize]
+      if let Some(parent_id) = parent_id {
+        self.entries.as_mut().unwrap()[parent_id as usize]
           .children_nodes.push(id);
       }
 
@@ -464,19 +674,14 @@ impl<'ole> super::ole::Reader<'ole> {
       if node_type == EntryType::RootStorage || node_type ==
         EntryType::UserStorage {
           let child = self.entries.as_mut().unwrap()[id as usize].root_node;
-          self.build_entry_tree(child, Some(id));
+          self.build_entry_tree(child, Some(id), visited);
       }
       let left_child = self.entries.as_mut().unwrap()[id as usize]
           .left_child_node();
       let right_child = self.entries.as_mut().unwrap()[id as usize]
           .right_child_node();
-      let n = self.entries.as_ref().unwrap().len() as u32;
-      if left_child < n {
-        self.build_entry_tree(left_child, parent_id);
-      }
-      if right_child < n {
-        self.build_entry_tree(right_child, parent_id);
-      }
+      self.build_entry_tree(left_child, parent_id, visited);
+      self.build_entry_tree(right_child, parent_id, visited);
     }
   }
 }
@@ -1,4 +1,3 @@
-use std;
 
 /// Errors related to the process of parsing.
 #[derive(Debug)]
@@ -7,6 +6,7 @@ pub enum Error {
   BadFileSize,
 
   /// Classic std::io::Error.
+  #[allow(clippy::enum_variant_names)]
   IOError(std::io::Error),
 
   /// Something is not implemented yet ?
@@ -32,21 +32,31 @@ pub enum Error {
 
   /// User query an empty entry
   EmptyEntry,
+
+  /// The file has more directory entries than allowed.
+  EntryLimitExceeded(usize),
+
+  /// A stream is larger than allowed.
+  StreamLimitExceeded(usize),
 }
 
 impl std::fmt::Display for Error {
   fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
     match *self {
       Error::BadFileSize => write!(f, "Filesize is null or too big."),
-      Error::IOError(ref e) => write!(f, "{}", e.to_string()),
+      Error::IOError(ref e) => write!(f, "{}", e),
       Error::NotImplementedYet => write!(f, "Method not implemented yet"),
       Error::InvalidOLEFile => write!(f, "Invalid OLE File"),
-      Error::BadSizeValue(ref e) => write!(f, "{}", e.to_string()),
+      Error::BadSizeValue(ref e) => write!(f, "{}", e),
       Error::EmptyMasterSectorAllocationTable => write!(f, "MSAT is empty"),
       Error::NotSectorUsedBySAT => write!(f, "Sector is not a sector used by the SAT."),
       Error::NodeTypeUnknown => write!(f, "Unknown node type"),
       Error::BadRootStorageSize => write!(f, "Bad RootStorage size"),
-      Error::EmptyEntry => write!(f, "Empty entry")
+      Error::EmptyEntry => write!(f, "Empty entry"),
+      Error::EntryLimitExceeded(max) =>
+        write!(f, "More than {} directory entries", max),
+      Error::StreamLimitExceeded(max) =>
+        write!(f, "Stream larger than {} bytes", max)
     }
   }
 }
@@ -1,4 +1,3 @@
-use std;
 use std::io::Read;
 use crate::ole::util::FromSlice;
 
@@ -14,7 +13,7 @@ impl<'ole> super::ole::Reader<'ole> {
     let result: Result<(), super::error::Error>;
 
     // Check file identifier
-    if &super::constants::IDENTIFIER != &header[0..8] {
+    if super::constants::IDENTIFIER != header[0..8] {
       result = Err(super::error::Error::InvalidOLEFile);
     } else {
 
@@ -28,10 +27,10 @@ impl<'ole> super::ole::Reader<'ole> {
       self.version_number = Some(rv_number as u16);
 
       // Check little-endianness; big endian not yet supported
-      if &header[28..30] == &super::constants::BIG_ENDIAN_IDENTIFIER {
+      if header[28..30] == super::constants::BIG_ENDIAN_IDENTIFIER {
         result = Err(super::error::Error::NotImplementedYet);
       } else if
-          &header[28..30] != &super::constants::LITTLE_ENDIAN_IDENTIFIER {
+          header[28..30] != super::constants::LITTLE_ENDIAN_IDENTIFIER {
         result = Err(super::error::Error::InvalidOLEFile);
       } else {
 
@@ -58,17 +57,11 @@ impl<'ole> super::ole::Reader<'ole> {
           } else {
             self.short_sec_size = Some(2usize.pow(k as u32));
 
-            let sat: std::vec::Vec<u32>;
-
-
-            // Total number of sectors used for the sector allocation table
-            sat = std::vec::Vec::with_capacity(
-              (*self.sec_size.as_ref().unwrap() / 4)
-              *  usize::from_slice(&header[44..48]));
+            // The sector allocation table, filled by build_sat
+            let sat: std::vec::Vec<u32> = std::vec::Vec::new();
 
             // SecID of the first sector of directory stream
-            let mut dsat: std::vec::Vec<u32> = std::vec::Vec::new();
-            dsat.push(u32::from_slice(&header[48..52]));
+            let dsat: std::vec::Vec<u32> = vec![u32::from_slice(&header[48..52])];
 
             // Minimum size of a standard stream (bytes)
             self.minimum_standard_stream_size =
@@ -80,25 +73,15 @@ impl<'ole> super::ole::Reader<'ole> {
                 < 4096usize {
               result = Err(super::error::Error::InvalidOLEFile);
             } else {
-              let mut ssat: std::vec::Vec<u32>;
-              let mut msat: std::vec::Vec<u32>;
-
-              // secID of the first sector of the SSAT & Total number
-              // of sectors used for the short-sector allocation table
-              ssat = std::vec::Vec::with_capacity(
-                usize::from_slice(&header[64..68])
-                * (*self.sec_size.as_ref().unwrap() / 4));
-              ssat.push(u32::from_slice(&header[60..64]));
-
-              // secID of first sector of the master sector allocation table
-              // & Total number of sectors used for
-              // the master sector allocation table
-              msat = vec![super::constants::FREE_SECID_U32; 109];
-              if &header[68..72] != &super::constants::END_OF_CHAIN_SECID {
-                msat.resize(109usize + usize::from_slice(&header[72..76])
-                  * (*self.sec_size.as_ref().unwrap() / 4),
-                  super::constants::FREE_SECID_U32);
-              }
+              // secID of the first sector of the SSAT, the counts of
+              // sectors of the header being left aside as a crafted file
+              // may claim billions of them
+              let ssat: std::vec::Vec<u32> =
+                vec![u32::from_slice(&header[60..64])];
+
+              // The master sector allocation table, filled by
+              // build_master_sector_allocation_table
+              let msat: std::vec::Vec<u32> = std::vec::Vec::new();
               self.sat = Some(sat);
               self.msat = Some(msat);
               self.dsat = Some(dsat);
@@ -118,63 +101,55 @@ impl<'ole> super::ole::Reader<'ole> {
 
 
   /// Build the Master Sector Allocation Table (MSAT)
-  fn build_master_sector_allocation_table(&mut self, header: &[u8])
+  pub(crate) fn build_master_sector_allocation_table(&mut self, header: &[u8])
       -> Result<(), super::error::Error> {
 
-    // First, we build the master sector allocation table from the header
-    let mut total_sec_id_read = self.read_sec_ids(&header[76 ..], 0);
-
-    // Check if additional sectors are used for building the msat
-    if total_sec_id_read == 109 {
-      let sec_size = *self.sec_size.as_ref().unwrap();
-      let mut sec_id = usize::from_slice(&header[68..72]);
-      let mut buffer = vec![0u8; 0];
-
-
-      while sec_id != super::constants::END_OF_CHAIN_SECID_U32 as usize {
-        let relative_offset = sec_id * sec_size;
-
-        // check if we need to read more data
-        if buffer.len() <= relative_offset + sec_size {
-          let new_len = (sec_id + 1) * sec_size;
-          buffer.resize(new_len, 0xFFu8);
-          self.read(&mut buffer[relative_offset
-            .. relative_offset + sec_size])?;
-        }
-        total_sec_id_read += self.read_sec_ids(&buffer[relative_offset
-          .. relative_offset + sec_size - 4], total_sec_id_read);
-        sec_id = usize::from_slice(&buffer[buffer.len() - 4 ..]);
-      }
-        // save the buffer for later usage
-        self.body = Some(buffer);
+    // The body of a mapped file is there already, otherwise we read
+    // the all file
+    if self.body.is_none() {
+      let mut body = std::vec::Vec::new();
+      self.buf_reader.as_mut().unwrap().read_to_end(&mut body)
+        .map_err(super::error::Error::IOError)?;
+      self.body = Some(super::ole::Body::Owned(body));
     }
-    self.msat.as_mut().unwrap().resize(
-      total_sec_id_read, super::constants::FREE_SECID_U32);
 
-    // Now, we read the all file
-    let mut buf: &mut std::vec::Vec<u8>;
-    if !self.body.is_some() {
-      self.body = Some(std::vec::Vec::new());
+    // First, we build the master sector allocation table from the header
+    let mut complete = self.read_sec_ids(&header[76 ..]);
+
+    // Then from the sectors of the DIFAT, each one ending with the secID
+    // of the next. The chain is bounded by the count of the header and by
+    // the sectors of the body, and a sector met twice ends it, as crafted
+    // files may link them in a cycle.
+    let sec_size = *self.sec_size.as_ref().unwrap();
+    let mut sec_id = u32::from_slice(&header[68..72]);
+    let mut remaining = std::cmp::min(usize::from_slice(&header[72..76]),
+      self.body.as_ref().map_or(0, |body| body.len()) / sec_size);
+    let mut visited = std::collections::HashSet::new();
+    while complete && remaining > 0
+        && sec_id != super::constants::END_OF_CHAIN_SECID_U32
+        && sec_id != super::constants::FREE_SECID_U32
+        && visited.insert(sec_id) {
+      let sector = self.read_sector(sec_id as usize)?.into_owned();
+      complete = self.read_sec_ids(&sector[.. sec_size - 4]);
+      sec_id = u32::from_slice(&sector[sec_size - 4 ..]);
+      remaining -= 1;
     }
-    buf = self.body.as_mut().unwrap();
-
-    self.buf_reader.as_mut().unwrap().read_to_end(&mut
-      buf).map_err(super::error::Error::IOError)?;
+    #[cfg(feature = "tracing")]
+    tracing::debug!(difat_sectors = visited.len(),
+      "master sector allocation table read");
     Ok(())
   }
 
-  fn read_sec_ids(&mut self, buffer: &[u8], msat_offset: usize) -> usize {
-    let mut i = 0usize;
-    let mut offset = 0usize;
-    let max_sec_ids = buffer.len() / 4;
-    let msat = &mut self.msat.as_mut().unwrap()[msat_offset .. ];
-    while i < max_sec_ids && &buffer[offset .. offset + 4]
-      != &super::constants::FREE_SECID {
-      msat[i] = u32::from_slice(&buffer[offset .. offset + 4]);
-      offset += 4;
-      i += 1;
+  /// Appends the secIDs of `buffer` to the MSAT, up to the first free one.
+  /// Returns true if none is free, the MSAT going on after them.
+  fn read_sec_ids(&mut self, buffer: &[u8]) -> bool {
+    let msat = self.msat.as_mut().unwrap();
+    for sec_id in buffer.chunks_exact(4) {
+      if sec_id == super::constants::FREE_SECID {
+        return false;
+      }
+      msat.push(u32::from_slice(sec_id));
     }
-
-    i
+    true
   }
 }
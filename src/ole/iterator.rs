@@ -8,7 +8,7 @@ impl<'a> OLEIterator<'a> {
 
   pub(crate) fn new(ole: &'a super::ole::Reader) -> OLEIterator<'a> {
     OLEIterator {
-      ole: ole,
+      ole,
       curr: 0
     }
   }
@@ -27,3 +27,49 @@ impl<'a> Iterator for OLEIterator<'a> {
     }
   }
 }
+
+/// Iterator over the children of a storage, in the order of their names.
+pub struct ChildrenIterator<'a> {
+  entries: &'a [super::entry::Entry],
+  parent: u32,
+  node: u32,
+  stack: std::vec::Vec<u32>,
+  visited: std::vec::Vec<bool>
+}
+
+impl<'a> ChildrenIterator<'a> {
+
+  pub(crate) fn new(ole: &'a super::ole::Reader, parent: &super::entry::Entry)
+    -> ChildrenIterator<'a> {
+    let entries = ole.entries.as_deref().unwrap_or(&[]);
+    ChildrenIterator {
+      entries,
+      parent: parent.id(),
+      node: parent.child_node(),
+      stack: std::vec::Vec::new(),
+      visited: vec![false; entries.len()]
+    }
+  }
+}
+
+impl<'a> Iterator for ChildrenIterator<'a> {
+  type Item = &'a super::entry::Entry;
+
+  // Walks the red-black tree of the siblings in order, skipping the nodes
+  // already seen, as crafted files may link them in a cycle.
+  fn next(&mut self) -> Option<&'a super::entry::Entry> {
+    loop {
+      while (self.node as usize) < self.entries.len()
+        && !self.visited[self.node as usize] {
+        self.visited[self.node as usize] = true;
+        self.stack.push(self.node);
+        self.node = self.entries[self.node as usize].left_child_node();
+      }
+      let entry = &self.entries[self.stack.pop()? as usize];
+      self.node = entry.right_child_node();
+      if entry.parent_node() == Some(self.parent) {
+        return Some(entry);
+      }
+    }
+  }
+}
@@ -1,3 +1,4 @@
+#![warn(missing_docs)]
 #![allow(unused_imports, dead_code)]
 //! A simple parser and reader for Microsoft Compound Document File.
 //!
@@ -5,12 +6,13 @@
 //! or a given stream.
 //! It includes a reader too, for iterating over entries and for extracting
 //! files inside the OLE storage.
+//! And a writer, `Writer`, building new files from storages and streams.
 //!
 //! ## Example
 //!
 //! ```ignore
-//! use crate::ole::Reader;
-//! use std::io::{Read, Write};
+//! use msg_parser::ole::Reader;
+//! use std::io::Read;
 //!
 //! let mut file = std::fs::File::open("data/Thumbs.db").unwrap();
 //! let mut parser = Reader::new(file).unwrap();
@@ -25,17 +27,18 @@
 //! let mut slice = parser.get_entry_slice(entry).unwrap();
 //! let mut buffer = std::vec::Vec::<u8>::with_capacity(slice.len());
 //! slice.read_to_end(&mut buffer);
-//!
-//! // Saves the extracted file
-//! let mut extracted_file = std::fs::File::create("./file.bin").unwrap();
-//! extracted_file.write_all(&buffer[..]);
 //! ```
+//!
+//! The reader is public with the `ole` feature, and reads any compound
+//! file, e.g. `.doc`, `.xls` or `Thumbs.db`, not only messages.
 
+#[allow(clippy::module_inception)]
 mod ole;
 pub use ole::Reader;
 
 pub(crate) mod iterator;
-pub(crate) use iterator::OLEIterator;
+pub use iterator::OLEIterator;
+pub use iterator::ChildrenIterator;
 
 mod error;
 pub use error::Error;
@@ -45,9 +48,22 @@ pub(crate) mod util;
 pub(crate) mod sat;
 pub(crate) mod constants;
 
+/// Entries of the directory of a compound file, and their streams.
 pub mod entry;
 pub use entry::Entry;
 pub use entry::EntrySlice;
 pub use entry::EntryType;
 
 pub(crate) mod sector;
+
+mod tree;
+pub use tree::Storage;
+pub(crate) use tree::format_clsid;
+
+mod salvage;
+
+mod validate;
+pub use validate::{Chain, Problem, ValidationReport};
+
+pub(crate) mod writer;
+pub use writer::{Writer, ROOT};
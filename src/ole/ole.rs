@@ -1,5 +1,3 @@
-use std;
-
 /// An OLE file reader.
 ///
 /// The parsing method follows the same method described here:
@@ -18,7 +16,6 @@ use std;
 ///   println!("{}", entry);
 /// }
 /// ```
-
 pub struct Reader<'ole> {
 
   /// Buffer for reading from the source.
@@ -55,13 +52,100 @@ pub struct Reader<'ole> {
   pub(crate) msat: Option<std::vec::Vec<u32>>,
 
   /// Body of the file.
-  pub(crate) body: Option<std::vec::Vec<u8>>,
+  pub(crate) body: Option<Body<'ole>>,
 
   /// Directory entries.
   pub(crate) entries: Option<std::vec::Vec<super::entry::Entry>>,
 
   /// DirID of the root entry.
-  pub(crate) root_entry: Option<u32>
+  pub(crate) root_entry: Option<u32>,
+
+  /// Maximum number of directory entries.
+  pub(crate) max_entries: Option<usize>,
+
+  /// Maximum size of a stream.
+  pub(crate) max_stream_size: Option<usize>
+}
+
+/// Source a `Reader` seeks in, see `Reader::from_reader`.
+pub(crate) trait SeekSource {
+
+  /// Fills `buf` with the bytes at `offset` of the source.
+  fn read_at(&self, offset: u64, buf: &mut [u8]) -> std::io::Result<()>;
+}
+
+impl<T: std::io::Read + std::io::Seek> SeekSource for std::cell::RefCell<T> {
+  fn read_at(&self, offset: u64, buf: &mut [u8]) -> std::io::Result<()> {
+    use std::io::{Read, Seek};
+    let mut source = self.borrow_mut();
+    source.seek(std::io::SeekFrom::Start(offset))?;
+    source.read_exact(buf)
+  }
+}
+
+/// Body of the file, the sectors following its header.
+pub(crate) enum Body<'ole> {
+
+  /// Read from the source.
+  Owned(std::vec::Vec<u8>),
+
+  /// The whole file borrowed from the caller, header included.
+  Borrowed(&'ole [u8]),
+
+  /// The whole file mapped in memory, header included.
+  #[cfg(feature = "mmap")]
+  Mapped(memmap2::Mmap),
+
+  /// Left in the source, sectors being read from it as they are needed.
+  Seekable {
+    source: Box<dyn SeekSource + 'ole>,
+    offset: u64, // Position of the body in the source
+    len: usize
+  }
+}
+
+impl<'ole> Body<'ole> {
+
+  /// Returns the length of the body.
+  pub(crate) fn len(&self) -> usize {
+    match self {
+      Body::Owned(body) => body.len(),
+      Body::Borrowed(file) => {
+        file.len().saturating_sub(super::constants::HEADER_SIZE)
+      },
+      #[cfg(feature = "mmap")]
+      Body::Mapped(mmap) => {
+        mmap.len().saturating_sub(super::constants::HEADER_SIZE)
+      },
+      Body::Seekable { len, .. } => *len
+    }
+  }
+
+  /// Returns `size` bytes from `offset`, borrowed unless they are read
+  /// from a seekable source.
+  pub(crate) fn read(&self, offset: usize, size: usize)
+        -> Result<std::borrow::Cow<'_, [u8]>, super::error::Error> {
+    match self {
+      Body::Owned(body) => {
+        Ok(std::borrow::Cow::Borrowed(&body[offset .. offset + size]))
+      },
+      Body::Borrowed(file) => {
+        let start = super::constants::HEADER_SIZE + offset;
+        Ok(std::borrow::Cow::Borrowed(&file[start .. start + size]))
+      },
+      #[cfg(feature = "mmap")]
+      Body::Mapped(mmap) => {
+        let start = super::constants::HEADER_SIZE + offset;
+        Ok(std::borrow::Cow::Borrowed(&mmap[start .. start + size]))
+      },
+      Body::Seekable { source, offset: body_offset, .. } => {
+        let mut buf = vec![0u8; size];
+        source.read_at(body_offset + offset as u64, &mut buf)
+          .map_err(super::error::Error::IOError)?;
+        Ok(std::borrow::Cow::Owned(buf))
+      }
+    }
+  }
 }
 
 impl<'ole> Reader<'ole> {
@@ -75,10 +159,158 @@ impl<'ole> Reader<'ole> {
   /// let mut my_resume = std::fs::File::open("assets/Thumbs.db").unwrap();
   /// let mut parser = ole::Reader::new(my_resume).unwrap();
   /// ```
-  pub fn new<T: 'ole>(readable: T)
+  pub fn new<T>(readable: T)
         -> std::result::Result<Reader<'ole>, super::error::Error>
-    where T: std::io::Read {
-    let mut t = Reader {
+    where T: std::io::Read + 'ole {
+    Reader::with_limits(readable, None, None)
+  }
+
+  /// Constructs a new `Reader` refusing files with more than `max_entries`
+  /// directory entries, and streams larger than `max_stream_size` bytes.
+  ///
+  /// # Examples
+  ///
+  /// ```ignore
+  /// use ole;
+  /// let file = std::fs::File::open("assets/Thumbs.db").unwrap();
+  /// let mut parser = ole::Reader::with_limits(file, Some(1024), None).unwrap();
+  /// ```
+  pub fn with_limits<T>(readable: T, max_entries: Option<usize>,
+      max_stream_size: Option<usize>)
+        -> std::result::Result<Reader<'ole>, super::error::Error>
+    where T: std::io::Read + 'ole {
+    let mut t = Reader::blank(readable, max_entries, max_stream_size);
+    t.parse_header()?;
+    t.build_sat()?;
+    t.build_directory_entries()?;
+    Ok(t)
+  }
+
+  /// Constructs a new `Reader` from a file mapped in memory, whose sectors
+  /// are read from the page cache rather than from a copy of the file.
+  ///
+  /// The file must not be changed while the `Reader` lives, as its mapping
+  /// would change along with it.
+  ///
+  /// # Examples
+  ///
+  /// ```ignore
+  /// use ole;
+  /// let mut parser = ole::Reader::from_mmap("assets/Thumbs.db").unwrap();
+  /// ```
+  #[cfg(feature = "mmap")]
+  pub fn from_mmap<P: AsRef<std::path::Path>>(path: P)
+        -> Result<Reader<'ole>, super::error::Error> {
+    Reader::from_mmap_with_limits(path, None, None)
+  }
+
+  /// Same as `from_mmap`, with the limits of `with_limits`.
+  #[cfg(feature = "mmap")]
+  pub fn from_mmap_with_limits<P: AsRef<std::path::Path>>(path: P,
+      max_entries: Option<usize>, max_stream_size: Option<usize>)
+        -> Result<Reader<'ole>, super::error::Error> {
+    let file = std::fs::File::open(path)
+      .map_err(super::error::Error::IOError)?;
+    // Safety: the mapping is only read, and the caller is told not to
+    // change the file meanwhile.
+    let mmap = unsafe { memmap2::Mmap::map(&file) }
+      .map_err(super::error::Error::IOError)?;
+    let header = Reader::header_of(&mmap);
+    Reader::with_body(header, Body::Mapped(mmap), max_entries,
+      max_stream_size)
+  }
+
+  /// Constructs a new `Reader` borrowing the file from `slice` rather than
+  /// copying it, streams stored in consecutive sectors being borrowed in
+  /// turn by `get_entry_bytes`.
+  ///
+  /// # Examples
+  ///
+  /// ```ignore
+  /// use ole;
+  /// let bytes = std::fs::read("assets/Thumbs.db").unwrap();
+  /// let mut parser = ole::Reader::from_slice(&bytes).unwrap();
+  /// ```
+  pub fn from_slice(slice: &'ole [u8])
+        -> Result<Reader<'ole>, super::error::Error> {
+    Reader::from_slice_with_limits(slice, None, None)
+  }
+
+  /// Same as `from_slice`, with the limits of `with_limits`.
+  pub fn from_slice_with_limits(slice: &'ole [u8],
+      max_entries: Option<usize>, max_stream_size: Option<usize>)
+        -> Result<Reader<'ole>, super::error::Error> {
+    Reader::with_body(Reader::header_of(slice), Body::Borrowed(slice),
+      max_entries, max_stream_size)
+  }
+
+  /// Returns a copy of the header of a whole file.
+  fn header_of(file: &[u8]) -> std::vec::Vec<u8> {
+    file[.. super::constants::HEADER_SIZE.min(file.len())].to_vec()
+  }
+
+  /// Constructs a `Reader` whose body is there already, only the header
+  /// being read from the source.
+  fn with_body(header: std::vec::Vec<u8>, body: Body<'ole>,
+      max_entries: Option<usize>, max_stream_size: Option<usize>)
+        -> Result<Reader<'ole>, super::error::Error> {
+    let mut t = Reader::blank(std::io::Cursor::new(header), max_entries,
+      max_stream_size);
+    t.body = Some(body);
+    t.parse_header()?;
+    t.build_sat()?;
+    t.build_directory_entries()?;
+    Ok(t)
+  }
+
+  /// Constructs a new `Reader` from a seekable source, e.g. a file in an
+  /// archive, which is kept to read sectors from as they are needed rather
+  /// than read whole. The file starts at the current position of the
+  /// source.
+  ///
+  /// # Examples
+  ///
+  /// ```ignore
+  /// use ole;
+  /// let file = std::fs::File::open("assets/Thumbs.db").unwrap();
+  /// let mut parser = ole::Reader::from_reader(file).unwrap();
+  /// ```
+  pub fn from_reader<T>(source: T)
+        -> Result<Reader<'ole>, super::error::Error>
+    where T: std::io::Read + std::io::Seek + 'ole {
+    Reader::from_reader_with_limits(source, None, None)
+  }
+
+  /// Same as `from_reader`, with the limits of `with_limits`.
+  pub fn from_reader_with_limits<T>(mut source: T,
+      max_entries: Option<usize>, max_stream_size: Option<usize>)
+        -> Result<Reader<'ole>, super::error::Error>
+    where T: std::io::Read + std::io::Seek + 'ole {
+    use std::io::{Read, Seek};
+    let (start, end) = source.stream_position()
+      .and_then(|start| Ok((start, source.seek(std::io::SeekFrom::End(0))?)))
+      .and_then(|(start, end)| {
+        source.seek(std::io::SeekFrom::Start(start))?;
+        Ok((start, end))
+      })
+      .map_err(super::error::Error::IOError)?;
+    let mut header = std::vec::Vec::new();
+    source.by_ref().take(super::constants::HEADER_SIZE as u64)
+      .read_to_end(&mut header).map_err(super::error::Error::IOError)?;
+    let len = end.saturating_sub(start + header.len() as u64);
+    let body = Body::Seekable {
+      source: Box::new(std::cell::RefCell::new(source)),
+      offset: start + super::constants::HEADER_SIZE as u64,
+      len: std::convert::TryFrom::try_from(len).unwrap_or(usize::MAX)
+    };
+    Reader::with_body(header, body, max_entries, max_stream_size)
+  }
+
+  /// Constructs a `Reader` holding nothing read yet.
+  pub(crate) fn blank<T>(readable: T, max_entries: Option<usize>,
+      max_stream_size: Option<usize>) -> Reader<'ole>
+    where T: std::io::Read + 'ole {
+    Reader {
       buf_reader: Some(std::io::BufReader::new(Box::new(readable))),
       uid: vec![0u8; super::constants::UID_SIZE],
       revision_number: None,
@@ -92,12 +324,10 @@ impl<'ole> Reader<'ole> {
       msat: None,
       body: None,
       entries: None,
-      root_entry: None
-    };
-    t.parse_header()?;
-    t.build_sat()?;
-    t.build_directory_entries()?;
-    Ok(t)
+      root_entry: None,
+      max_entries,
+      max_stream_size
+    }
   }
 
 
@@ -109,7 +339,7 @@ impl<'ole> Reader<'ole> {
   /// use ole;
   /// let mut parser = ole::Reader::from_path("assets/Thumbs.db").unwrap();
   /// ```
-  pub fn from_path(path: &str) -> Result<Reader, super::error::Error> {
+  pub fn from_path(path: &str) -> Result<Reader<'_>, super::error::Error> {
     let f = std::fs::File::open(path).map_err(super::error::Error::IOError)?;
     Reader::new(f)
   }
@@ -127,10 +357,82 @@ impl<'ole> Reader<'ole> {
   ///   println!("Entry {}", entry.name());
   /// }
   /// ```
-  pub fn iterate(&self) -> super::iterator::OLEIterator {
+  pub fn iterate(&self) -> super::iterator::OLEIterator<'_> {
     super::iterator::OLEIterator::new(self)
   }
 
+  /// Returns an iterator over the children of the storage `entry`, in the
+  /// order of their names: shorter names first, then names compared ignoring
+  /// case. Streams have no children.
+  ///
+  /// # Examples
+  ///
+  /// ```ignore
+  /// use msg_parser::ole::Reader;
+  /// let parser = Reader::from_path("data/attachment.msg").unwrap();
+  /// let attachment = parser
+  ///   .entry_by_path("__attach_version1.0_#00000000").unwrap();
+  /// for entry in parser.children_of(attachment) {
+  ///   println!("Entry {}", entry.name());
+  /// }
+  /// ```
+  pub fn children_of(&self, entry: &super::entry::Entry)
+    -> super::iterator::ChildrenIterator<'_> {
+    super::iterator::ChildrenIterator::new(self, entry)
+  }
+
+  /// Returns the entry at `path`, the names of the storages holding it and
+  /// its own name separated by `/`, from the root storage. Names are
+  /// compared ignoring case, as in the directory of the file. The empty
+  /// path is the root storage.
+  ///
+  /// # Examples
+  ///
+  /// ```ignore
+  /// use msg_parser::ole::Reader;
+  /// let parser = Reader::from_path("data/attachment.msg").unwrap();
+  /// let name = parser
+  ///   .entry_by_path("__attach_version1.0_#00000000/__substg1.0_3707001F");
+  /// ```
+  pub fn entry_by_path(&self, path: &str) -> Option<&super::entry::Entry> {
+    let entries = self.entries.as_ref()?;
+    let mut entry = entries.first()?;
+    for name in path.split('/').filter(|name| !name.is_empty()) {
+      entry = entry.children_nodes().iter()
+        .filter_map(|id| entries.get(*id as usize))
+        .find(|child| Self::same_name(child.name(), name))?;
+    }
+    Some(entry)
+  }
+
+  pub(crate) fn same_name(name: &str, other: &str) -> bool {
+    name.chars().flat_map(char::to_uppercase)
+      .eq(other.chars().flat_map(char::to_uppercase))
+  }
+
+  /// Returns the number of bytes allocated for the body of the file.
+  pub(crate) fn body_allocated_len(&self) -> usize {
+    match self.body.as_ref() {
+      Some(Body::Owned(body)) => body.capacity(),
+      // A borrowed file belongs to the caller, pages of a mapping to the
+      // page cache, and a seekable source is only read sector by sector.
+      _ => 0
+    }
+  }
+
+  /// Returns the number of bytes allocated for the allocation tables
+  /// and the directory entries.
+  pub(crate) fn directory_allocated_len(&self) -> usize {
+    let table_len = |table: &Option<std::vec::Vec<u32>>| {
+      table.as_ref().map_or(0, |table| table.capacity() * std::mem::size_of::<u32>())
+    };
+    let entries_len = self.entries.as_ref().map_or(0, |entries| {
+      entries.iter().map(|entry| entry.allocated_len()).sum()
+    });
+    table_len(&self.sat) + table_len(&self.ssat) + table_len(&self.msat)
+      + table_len(&self.dsat) + entries_len
+  }
+
   /// Read some bytes from the source.
   pub(crate) fn read(&mut self, buf: &mut [u8])
         -> Result<usize, super::error::Error> {
@@ -144,6 +446,8 @@ impl<'ole> Reader<'ole> {
 
 
 #[cfg(test)]
+#[allow(clippy::bool_assert_comparison, clippy::op_ref,
+  clippy::single_component_path_imports, clippy::to_string_in_format_args)]
 mod tests {
   use std;
   use super::Reader;
@@ -251,4 +555,163 @@ mod tests {
       }
     }
   }
+
+  #[test]
+  fn cyclic_entries() {
+    use super::super::writer::{Writer, ROOT};
+    let mut writer = Writer::new();
+    let storage = writer.add_storage(ROOT, "Storage");
+    let inner = writer.add_storage(storage, "Inner");
+    writer.add_stream(inner, "Stream", vec![1u8; 8]);
+    let mut bytes = writer.to_bytes();
+    // Make "Storage" the child of "Inner", its own child.
+    let name: std::vec::Vec<u8> = "Inner".encode_utf16()
+      .flat_map(u16::to_le_bytes).collect();
+    let offset = bytes.windows(name.len()).position(|window| window == name)
+      .unwrap();
+    bytes[offset + 76 .. offset + 80]
+      .copy_from_slice(&(storage as u32).to_le_bytes());
+    let ole = Reader::new(&bytes[..]).unwrap();
+    let parents: std::vec::Vec<(&str, Option<u32>)> = ole.iterate()
+      .map(|entry| (entry.name(), entry.parent_node())).collect();
+    assert!(parents.contains(&("Storage", Some(0))));
+    assert!(parents.contains(&("Inner", Some(storage as u32))));
+    assert!(parents.contains(&("Stream", None)));
+    let inner = ole.entry_by_path("Storage/Inner").unwrap();
+    assert_eq!(ole.children_of(inner).count(), 0);
+  }
+
+  #[test]
+  fn seek_and_buf_read() {
+    use std::io::{BufRead, Read, Seek, SeekFrom};
+    let ole = Reader::from_path("data/attachment.msg").unwrap();
+    for entry in ole.iterate().filter(|entry| entry.len() > 16) {
+      let bytes = ole.get_entry_bytes(entry).unwrap();
+      let mut slice = ole.get_entry_slice(entry).unwrap();
+
+      // Past the header of a property stream.
+      assert_eq!(slice.seek(SeekFrom::Start(8)).unwrap(), 8);
+      let mut rest = vec![];
+      slice.read_to_end(&mut rest).unwrap();
+      assert_eq!(rest, &bytes[8 ..]);
+
+      assert_eq!(slice.seek(SeekFrom::End(-4)).unwrap() as usize,
+        bytes.len() - 4);
+      let mut last = [0u8; 4];
+      slice.read_exact(&mut last).unwrap();
+      assert_eq!(last, bytes[bytes.len() - 4 ..]);
+
+      slice.seek(SeekFrom::Start(2)).unwrap();
+      assert_eq!(slice.seek(SeekFrom::Current(3)).unwrap(), 5);
+      let chunk = slice.fill_buf().unwrap().to_vec();
+      assert!(!chunk.is_empty());
+      assert_eq!(chunk, &bytes[5 .. 5 + chunk.len()]);
+      slice.consume(chunk.len());
+      assert_eq!(slice.stream_position().unwrap() as usize, 5 + chunk.len());
+
+      assert!(slice.seek(SeekFrom::Current(-100_000)).is_err());
+      slice.seek(SeekFrom::End(10)).unwrap();
+      assert_eq!(slice.read(&mut last).unwrap(), 0);
+      assert!(slice.fill_buf().unwrap().is_empty());
+    }
+  }
+
+  #[test]
+  fn children_of() {
+    let ole = Reader::from_path("data/attachment.msg").unwrap();
+    let root = ole.entry_by_path("").unwrap();
+    let names: std::vec::Vec<&str> = ole.children_of(root)
+      .map(|entry| entry.name()).collect();
+    assert_eq!(names.len(), root.children_nodes().len());
+    let mut sorted = names.clone();
+    sorted.sort_by_key(|name| (name.encode_utf16().count(), name.to_uppercase()));
+    assert_eq!(names, sorted);
+    assert!(ole.children_of(root)
+      .all(|entry| entry.parent_node() == Some(root.id())));
+    let stream = ole.entry_by_path("__properties_version1.0").unwrap();
+    assert_eq!(ole.children_of(stream).count(), 0);
+  }
+
+  #[test]
+  fn entry_by_path() {
+    let ole = Reader::from_path("data/attachment.msg").unwrap();
+    let root = ole.entry_by_path("").unwrap();
+    assert_eq!(root.id(), 0);
+    let storage = ole.entry_by_path("__attach_version1.0_#00000001").unwrap();
+    assert_eq!(storage.parent_node(), Some(0));
+    let entry = ole
+      .entry_by_path("/__attach_version1.0_#00000001/__substg1.0_3707001F")
+      .unwrap();
+    assert_eq!(entry.parent_node(), Some(storage.id()));
+    assert_eq!(entry.id(), ole
+      .entry_by_path("__ATTACH_VERSION1.0_#00000001/__substg1.0_3707001f")
+      .unwrap().id());
+    assert!(ole.entry_by_path("__substg1.0_3707001F").is_none());
+    assert!(ole.entry_by_path("__attach_version1.0_#00000009").is_none());
+  }
+
+  #[cfg(feature = "mmap")]
+  #[test]
+  fn mmap() {
+    use std::io::Read;
+    for path in ["data/Thumbs.db", "data/sample.ppt", "data/attachment.msg"] {
+      let read = Reader::from_path(path).unwrap();
+      let mapped = Reader::from_mmap(path).unwrap();
+      assert_eq!(mapped.sat, read.sat);
+      assert_eq!(mapped.msat, read.msat);
+      assert_eq!(mapped.body_allocated_len(), 0);
+      for (entry, mapped_entry) in read.iterate().zip(mapped.iterate()) {
+        assert_eq!(entry.name(), mapped_entry.name());
+        let (mut buf, mut mapped_buf) = (vec![], vec![]);
+        if let Ok(mut slice) = read.get_entry_slice(entry) {
+          slice.read_to_end(&mut buf).unwrap();
+          mapped.get_entry_slice(mapped_entry).unwrap()
+            .read_to_end(&mut mapped_buf).unwrap();
+        }
+        assert_eq!(buf, mapped_buf);
+      }
+    }
+    assert!(Reader::from_mmap("data/missing.db").is_err());
+    assert!(Reader::from_mmap("data/bad_outlook.msg").is_err());
+  }
+
+  #[test]
+  fn from_reader() {
+    use std::io::{Read, Seek};
+    for path in ["data/Thumbs.db", "data/sample.ppt", "data/attachment.msg"] {
+      let bytes = std::fs::read(path).unwrap();
+      let read = Reader::new(&bytes[..]).unwrap();
+      // The file follows other data in the source.
+      let mut source = std::io::Cursor::new([&[0u8; 100][..], &bytes].concat());
+      source.seek(std::io::SeekFrom::Start(100)).unwrap();
+      let seeked = Reader::from_reader(source).unwrap();
+      assert_eq!(seeked.sat, read.sat);
+      assert_eq!(seeked.body_allocated_len(), 0);
+      for (entry, seeked_entry) in read.iterate().zip(seeked.iterate()) {
+        assert_eq!(entry.name(), seeked_entry.name());
+        let (mut buf, mut seeked_buf) = (vec![], vec![]);
+        if let Ok(mut slice) = read.get_entry_slice(entry) {
+          slice.read_to_end(&mut buf).unwrap();
+          seeked.get_entry_slice(seeked_entry).unwrap()
+            .read_to_end(&mut seeked_buf).unwrap();
+        }
+        assert_eq!(buf, seeked_buf);
+      }
+    }
+    let bytes = std::fs::read("data/bad_outlook.msg").unwrap();
+    assert!(Reader::from_reader(std::io::Cursor::new(bytes)).is_err());
+  }
+
+  #[test]
+  fn limits() {
+    let bytes = std::fs::read("data/test_email.msg").unwrap();
+    match Reader::with_limits(&bytes[..], Some(8), None) {
+      Err(Error::EntryLimitExceeded(8)) => {},
+      _ => panic!("expected the entry limit to be exceeded")
+    }
+    let ole = Reader::with_limits(&bytes[..], None, Some(64)).unwrap();
+    let large = ole.iterate().find(|entry| entry.len() > 64).unwrap();
+    assert!(matches!(ole.get_entry_slice(large),
+      Err(Error::StreamLimitExceeded(64))));
+  }
 }
@@ -0,0 +1,349 @@
+use crate::ole::util::FromSlice;
+
+/// Names of the storages and streams of a message, by which the sectors of
+/// its directory are recognized. The root storage is left aside, as any
+/// compound file attached to the message has one.
+const MESSAGE_NAMES: [&str; 5] = ["__substg1.0_", "__properties_version1.0",
+  "__recip_version1.0_", "__attach_version1.0_", "__nameid_version1.0"];
+
+/// Storages only the root storage of a message holds, which are given back
+/// to it when the directory lost the link to them.
+const ROOT_STORAGES: [&str; 3] = ["__recip_version1.0_",
+  "__attach_version1.0_", "__nameid_version1.0"];
+
+impl<'ole> super::ole::Reader<'ole> {
+
+  /// Constructs a `Reader` from a damaged file, recovering what can be read
+  /// rather than failing, e.g. a message whose FAT lost sectors, or whose
+  /// directory chain is broken.
+  ///
+  /// Tables are read up to their first sector out of the file. Sectors of
+  /// the directory the chain of the header misses are found by scanning the
+  /// file for directory entries named as in a message, `__substg1.0_`
+  /// streams and such. Entries which cannot be read are left empty, and
+  /// storages of recipients and attachments the tree lost go back to the
+  /// root storage. A chain which is broken carries on with the sectors
+  /// following its last one, as files are most often written in
+  /// consecutive sectors, so streams are read whole as far as possible.
+  ///
+  /// Only the streams reachable from the root storage are recovered.
+  /// `validate` tells what is wrong with the file.
+  ///
+  /// # Examples
+  ///
+  /// ```ignore
+  /// use msg_parser::ole::Reader;
+  /// let bytes = std::fs::read("data/attachment.msg").unwrap();
+  /// let parser = Reader::from_slice(&bytes)
+  ///   .or_else(|_| Reader::salvage(&bytes)).unwrap();
+  /// ```
+  pub fn salvage(slice: &'ole [u8])
+        -> Result<super::ole::Reader<'ole>, super::error::Error> {
+    super::ole::Reader::salvage_with_limits(slice, None, None)
+  }
+
+  /// Same as `salvage`, with the limits of `with_limits`.
+  pub fn salvage_with_limits(slice: &'ole [u8], max_entries: Option<usize>,
+      max_stream_size: Option<usize>)
+        -> Result<super::ole::Reader<'ole>, super::error::Error> {
+    let header = slice.get(.. super::constants::HEADER_SIZE)
+      .filter(|header| header[0 .. 8] == super::constants::IDENTIFIER)
+      .ok_or(super::error::Error::InvalidOLEFile)?;
+    let mut t = super::ole::Reader::blank(std::io::empty(), max_entries,
+      max_stream_size);
+    t.body = Some(super::ole::Body::Borrowed(slice));
+    t.uid = header[8 .. 24].to_vec();
+    t.revision_number = Some(usize::from_slice(&header[24 .. 26]) as u16);
+    t.version_number = Some(usize::from_slice(&header[26 .. 28]) as u16);
+    // Sizes the header may have lost are the ones of a version 3 file.
+    t.sec_size = Some(match usize::from_slice(&header[30 .. 32]) {
+      12 => 4096,
+      _ => 512
+    });
+    t.short_sec_size = Some(64);
+    t.minimum_standard_stream_size = Some(4096);
+    t.msat = Some(std::vec::Vec::new());
+    t.sat = Some(std::vec::Vec::new());
+    t.ssat = Some(vec![u32::from_slice(&header[60 .. 64])]);
+
+    // The MSAT is kept as far as it was read.
+    let _ = t.build_master_sector_allocation_table(header);
+    let sec_size = t.sec_size.unwrap();
+    let count = t.body.as_ref().map_or(0, |body| body.len())
+      .saturating_sub(t.sector_offset(0)) / sec_size;
+    let mut sec_ids = vec![super::constants::FREE_SECID_U32; sec_size / 4];
+    for sector_index in t.msat.clone().unwrap() {
+      // SecIDs of a sector out of the file end their chains, which carry on
+      // with the sectors following them, rather than free their sectors.
+      if t.read_sat_sector(sector_index as usize, &mut sec_ids).is_err() {
+        sec_ids.fill(super::constants::END_OF_CHAIN_SECID_U32);
+      }
+      t.sat.as_mut().unwrap().extend_from_slice(&sec_ids);
+    }
+    let short_chain = super::sat::build_chain(t.sat.as_ref().unwrap(),
+      u32::from_slice(&header[60 .. 64]), None);
+    let _ = t.build_ssat();
+
+    // Sectors no stream is stored in, which a broken chain must not run
+    // into.
+    let mut reserved = std::collections::HashSet::new();
+    reserved.extend(t.msat.iter().flatten().copied());
+    reserved.extend(short_chain);
+
+    let dsat = t.salvage_directory_sectors(u32::from_slice(&header[48 .. 52]),
+      count);
+    reserved.extend(dsat.iter().copied());
+    let n_entry_by_sector = sec_size / super::constants::DIRECTORY_ENTRY_SIZE;
+    if let Some(max) = max_entries
+        .filter(|max| dsat.len() * n_entry_by_sector > *max) {
+      return Err(super::error::Error::EntryLimitExceeded(max));
+    }
+    let mut entries = std::vec::Vec::new();
+    for sector_index in &dsat {
+      let sector = t.read_sector(*sector_index as usize)?;
+      for slot in sector.chunks_exact(super::constants::DIRECTORY_ENTRY_SIZE) {
+        let id = entries.len() as u32;
+        entries.push(super::entry::Entry::from_slice(slot, id)
+          .unwrap_or_else(|_| super::entry::Entry::empty(id)));
+      }
+    }
+    t.dsat = Some(dsat);
+    match entries.first() {
+      Some(root) if root._type() == super::entry::EntryType::RootStorage => {},
+      _ => return Err(super::error::Error::InvalidOLEFile)
+    }
+    t.root_entry = Some(0);
+
+    // Chains are read up to the sectors the size of the entry needs.
+    let stream_size = t.minimum_standard_stream_size.unwrap();
+    let short_count = entries[0].len().div_ceil(64);
+    for entry in entries.iter_mut() {
+      let size = entry.len();
+      let chain = match entry._type() {
+        super::entry::EntryType::UserStream if size < stream_size =>
+          salvage_chain(t.ssat.as_ref().unwrap(), entry.start_sec_id(),
+            size.div_ceil(64), short_count, &std::collections::HashSet::new()),
+        super::entry::EntryType::UserStream
+          | super::entry::EntryType::RootStorage =>
+          salvage_chain(t.sat.as_ref().unwrap(), entry.start_sec_id(),
+            size.div_ceil(sec_size), count, &reserved),
+        _ => continue
+      };
+      entry.set_sec_id_chain(chain);
+    }
+    t.entries = Some(entries);
+    let mut visited = vec![false; t.entries.as_ref().unwrap().len()];
+    t.build_entry_tree(0, None, &mut visited);
+    // Storages the tree lost go back to the root storage, along with their
+    // siblings and their children.
+    for id in 1 .. visited.len() {
+      let entry = &t.entries.as_ref().unwrap()[id];
+      if !visited[id] && entry._type() == super::entry::EntryType::UserStorage
+          && ROOT_STORAGES.iter().any(|name| entry.name().starts_with(name)) {
+        t.build_entry_tree(id as u32, Some(0), &mut visited);
+      }
+    }
+    #[cfg(feature = "tracing")]
+    tracing::debug!(entries = visited.iter().filter(|v| **v).count(),
+      "directory entries salvaged");
+    Ok(t)
+  }
+
+  /// Returns the sectors of the directory, from `start` as long as its chain
+  /// holds directory entries. A chain which is broken is followed by the
+  /// sectors of the file holding entries named as in a message, the one
+  /// holding the root storage first. Sectors the SAT frees are left aside,
+  /// as they may hold a former copy of the directory.
+  fn salvage_directory_sectors(&self, start: u32, count: usize)
+    -> std::vec::Vec<u32> {
+    let sat = self.sat.as_ref().unwrap();
+    let chain = super::sat::build_chain(sat, start, None);
+    let mut sectors: std::vec::Vec<u32> = chain.iter().copied()
+      .take_while(|sector| self.directory_sector(*sector).is_some())
+      .collect();
+    let complete = sectors.len() == chain.len() && chain.last()
+      .is_some_and(|last| sat[*last as usize]
+        == super::constants::END_OF_CHAIN_SECID_U32)
+      && self.links_within(&sectors);
+    if !complete {
+      for sector in 0 .. count as u32 {
+        if !sectors.contains(&sector)
+            && sat.get(sector as usize)
+              != Some(&super::constants::FREE_SECID_U32)
+            && self.directory_sector(sector) == Some(true) {
+          sectors.push(sector);
+        }
+      }
+      if let Some(root) = sectors.iter().position(|sector| {
+        self.read_sector(*sector as usize).is_ok_and(|bytes| bytes[66] == 5)
+      }) {
+        let sector = sectors.remove(root);
+        sectors.insert(0, sector);
+      }
+    }
+    sectors
+  }
+
+  /// Tells whether the entries of the directory `sectors` only link to
+  /// entries of its own, the chain of a directory which links further
+  /// having lost sectors.
+  fn links_within(&self, sectors: &[u32]) -> bool {
+    let n_entries = (sectors.len() * self.sec_size.unwrap()
+      / super::constants::DIRECTORY_ENTRY_SIZE) as u32;
+    sectors.iter().filter_map(|sector| self.read_sector(*sector as usize).ok())
+      .all(|sector| sector.chunks_exact(super::constants::DIRECTORY_ENTRY_SIZE)
+        .filter(|slot| slot[66] != 0)
+        .flat_map(|slot| slot[68 .. 80].chunks_exact(4).map(u32::from_slice)
+          .collect::<std::vec::Vec<u32>>())
+        .all(|dir_id| dir_id < n_entries
+          || dir_id == super::constants::FREE_SECID_U32))
+  }
+
+  /// Tells whether a sector holds directory entries, some of them being
+  /// possibly damaged: `Some(true)` when one at least is named as in a
+  /// message, `Some(false)` otherwise, and `None` when none is an entry.
+  fn directory_sector(&self, sector_index: u32) -> Option<bool> {
+    let sector = self.read_sector(sector_index as usize).ok()?;
+    let slots: std::vec::Vec<Option<bool>> = sector
+      .chunks_exact(super::constants::DIRECTORY_ENTRY_SIZE)
+      .map(recognize).collect();
+    match slots.iter().flatten().count() {
+      0 => None,
+      _ => Some(slots.contains(&Some(true)))
+    }
+  }
+}
+
+/// Tells whether `slot` is a directory entry: `Some(true)` for a storage or
+/// a stream named as in a message, `Some(false)` for another one or an
+/// empty one, and `None` for bytes which are no entry.
+fn recognize(slot: &[u8]) -> Option<bool> {
+  let name_len = usize::from_slice(&slot[64 .. 66]);
+  match slot[66] {
+    0 => return Some(false),
+    1 | 2 | 5 if slot[67] <= 1 => {},
+    _ => return None
+  }
+  if !(2 ..= 64).contains(&name_len) || name_len % 2 != 0
+      || slot[name_len - 2 .. name_len] != [0, 0]
+      || slot[.. name_len - 2].chunks_exact(2).any(|c| c == [0, 0]) {
+    return None;
+  }
+  let name: std::string::String = slot[.. name_len - 2].chunks_exact(2)
+    .map(|c| c[0] as char).collect();
+  Some(MESSAGE_NAMES.iter().any(|prefix| name.starts_with(prefix)))
+}
+
+/// Follows a chain of `table` from `start` for `len` SecIDs, as
+/// `sat::build_chain` does. Where the chain is broken, by a SecID out of
+/// the `count` sectors, met before or `reserved`, it carries on with the
+/// sector following its last one.
+pub(crate) fn salvage_chain(table: &[u32], start: u32, len: usize,
+    count: usize, reserved: &std::collections::HashSet<u32>)
+    -> std::vec::Vec<u32> {
+  let mut chain = std::vec::Vec::new();
+  let mut visited = std::collections::HashSet::new();
+  let usable = |sec_id: u32, visited: &std::collections::HashSet<u32>| {
+    (sec_id as usize) < count && !reserved.contains(&sec_id)
+      && !visited.contains(&sec_id)
+  };
+  let mut next = Some(start);
+  while chain.len() < len {
+    let sec_id = match next.filter(|sec_id| usable(*sec_id, &visited)) {
+      Some(sec_id) => sec_id,
+      None => match chain.last().map(|last| last + 1) {
+        Some(sec_id) if usable(sec_id, &visited) => sec_id,
+        _ => break
+      }
+    };
+    visited.insert(sec_id);
+    chain.push(sec_id);
+    next = table.get(sec_id as usize).copied();
+  }
+  chain
+}
+
+#[cfg(test)]
+mod tests {
+  use super::super::ole::Reader;
+
+  fn stream(ole: &Reader, path: &str) -> std::vec::Vec<u8> {
+    let entry = ole.entry_by_path(path).unwrap();
+    ole.get_entry_bytes(entry).unwrap().into_owned()
+  }
+
+  #[test]
+  fn intact() {
+    let bytes = std::fs::read("data/attachment.msg").unwrap();
+    let ole = Reader::from_slice(&bytes).unwrap();
+    let salvaged = Reader::salvage(&bytes).unwrap();
+    assert_eq!(salvaged.iterate().count(), ole.iterate().count());
+    for entry in ole.iterate() {
+      let other = salvaged.iterate().nth(entry.id() as usize).unwrap();
+      assert_eq!(other.name(), entry.name());
+      assert_eq!(other.parent_node(), entry.parent_node());
+      if entry._type() == super::super::entry::EntryType::UserStream
+          && !entry.is_empty() {
+        assert_eq!(salvaged.get_entry_bytes(other).unwrap(),
+          ole.get_entry_bytes(entry).unwrap());
+      }
+    }
+    assert!(Reader::salvage(&bytes[.. 100]).is_err());
+  }
+
+  #[test]
+  fn broken_fat() {
+    use super::super::writer::{Writer, ROOT};
+
+    let large: std::vec::Vec<u8> = (0 .. 10_000u32).map(|i| i as u8).collect();
+    let mut writer = Writer::new();
+    writer.add_stream(ROOT, "__substg1.0_0037001F", b"S\0u\0b\0".to_vec());
+    let attachment = writer.add_storage(ROOT, "__attach_version1.0_#00000000");
+    writer.add_stream(attachment, "__substg1.0_37010102", large.clone());
+    writer.add_stream(attachment, "__substg1.0_3707001F", vec![b'a'; 300]);
+    let bytes = writer.to_bytes();
+    let ole = Reader::from_slice(&bytes).unwrap();
+
+    // The SAT sector is out of the file, and so every chain it holds.
+    let mut damaged = bytes.clone();
+    damaged[76 .. 80].copy_from_slice(&0xFFFFu32.to_le_bytes());
+    assert!(Reader::from_slice(&damaged).is_err());
+    let salvaged = Reader::salvage(&damaged).unwrap();
+    for path in ["__substg1.0_0037001F",
+        "__attach_version1.0_#00000000/__substg1.0_37010102",
+        "__attach_version1.0_#00000000/__substg1.0_3707001F"] {
+      assert_eq!(stream(&salvaged, path), stream(&ole, path));
+    }
+  }
+
+  #[test]
+  fn broken_directory() {
+    let bytes = std::fs::read("data/attachment.msg").unwrap();
+    let ole = Reader::from_slice(&bytes).unwrap();
+    let path = "__attach_version1.0_#00000001/__substg1.0_3707001F";
+
+    // The directory chain ends after its first sector, and the entry
+    // linking to an attachment in the tree is overwritten.
+    let mut damaged = bytes.clone();
+    let dsat = ole.dsat.clone().unwrap();
+    let sat = ole.msat.as_ref().unwrap()[0] as usize;
+    let offset = 512 + ole.sector_offset(sat) + dsat[0] as usize * 4;
+    damaged[offset .. offset + 4].copy_from_slice(&[0xFE, 0xFF, 0xFF, 0xFF]);
+    let attachment = ole.entry_by_path("__attach_version1.0_#00000001")
+      .unwrap();
+    let sibling = ole.iterate().find(|entry| {
+      entry.left_child_node() == attachment.id()
+        || entry.right_child_node() == attachment.id()
+    }).unwrap();
+    let offset = 512 + ole.sector_offset(dsat[sibling.id() as usize / 4]
+      as usize) + sibling.id() as usize % 4 * 128;
+    damaged[offset + 66] = 0x7F;
+
+    let salvaged = Reader::salvage(&damaged).unwrap();
+    assert_eq!(salvaged.iterate().count(), ole.iterate().count());
+    assert!(salvaged.iterate().nth(sibling.id() as usize).unwrap()
+      ._type() == super::super::entry::EntryType::Empty);
+    assert_eq!(stream(&salvaged, path), stream(&ole, path));
+    assert!(!salvaged.validate().is_valid());
+  }
+}
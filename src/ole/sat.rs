@@ -1,16 +1,15 @@
-use std;
 use crate::ole::util::FromSlice;
 
 impl<'ole> super::ole::Reader<'ole> {
 
+  #[cfg_attr(feature = "tracing", tracing::instrument(level = "debug", skip_all))]
   pub(crate) fn build_sat(&mut self)
     -> Result<(), super::error::Error> {
     let sector_size = self.sec_size.unwrap();
-    let result: Result<(), super::error::Error>;
     let mut sec_ids = vec![super::constants::FREE_SECID_U32;
         sector_size / 4];
-    if self.msat.as_ref().unwrap().len() == 0 {
-      result = Err(super::error::Error::EmptyMasterSectorAllocationTable);
+    if self.msat.as_ref().unwrap().is_empty() {
+      Err(super::error::Error::EmptyMasterSectorAllocationTable)
     } else {
       for i in 0 .. self.msat.as_ref().unwrap().len() {
         let sector_index = self.msat.as_ref().unwrap()[i];
@@ -19,9 +18,11 @@ impl<'ole> super::ole::Reader<'ole> {
       }
       self.build_ssat()?;
       self.build_dsat()?;
-      result = Ok(());
+      #[cfg(feature = "tracing")]
+      tracing::debug!(sectors = self.sat.as_ref().unwrap().len(),
+        "sector allocation table read");
+      Ok(())
     }
-    result
   }
 
   pub(crate) fn read_sat_sector(&mut self, sector_index: usize,
@@ -34,32 +35,18 @@ impl<'ole> super::ole::Reader<'ole> {
     Ok(())
   }
 
-  pub(crate) fn build_chain_from_sat(&mut self, start: u32)
-        -> std::vec::Vec<u32> {
-    let mut chain = std::vec::Vec::new();
-    let mut sector_index = start;
-    let sat = self.sat.as_mut().unwrap();
-    while sector_index != super::constants::END_OF_CHAIN_SECID_U32 {
-      chain.push(sector_index);
-      sector_index = sat[sector_index as usize];
-    }
-
-    chain
+  /// Follows the chain of sectors from `start` in the SAT, for at most
+  /// `max_len` sectors.
+  pub(crate) fn build_chain_from_sat(&mut self, start: u32,
+      max_len: Option<usize>) -> std::vec::Vec<u32> {
+    build_chain(self.sat.as_ref().unwrap(), start, max_len)
   }
 
-  pub(crate) fn build_chain_from_ssat(&mut self, start: u32)
-        -> std::vec::Vec<u32> {
-    let mut chain = std::vec::Vec::new();
-    let mut sector_index = start;
-    let sat = self.ssat.as_mut().unwrap();
-    while sector_index != super::constants::END_OF_CHAIN_SECID_U32
-        && sector_index != super::constants::FREE_SECID_U32 {
-      chain.push(sector_index);
-
-      sector_index = sat[sector_index as usize];
-    }
-
-    chain
+  /// Follows the chain of short sectors from `start` in the SSAT, for at
+  /// most `max_len` short sectors.
+  pub(crate) fn build_chain_from_ssat(&mut self, start: u32,
+      max_len: Option<usize>) -> std::vec::Vec<u32> {
+    build_chain(self.ssat.as_ref().unwrap(), start, max_len)
   }
 
   pub(crate) fn build_ssat(&mut self) -> Result<(), super::error::Error> {
@@ -67,7 +54,7 @@ impl<'ole> super::ole::Reader<'ole> {
         self.sec_size.as_ref().unwrap() / 4];
 
     let sector_index = self.ssat.as_mut().unwrap().remove(0);
-    let chain = self.build_chain_from_sat(sector_index);
+    let chain = self.build_chain_from_sat(sector_index, None);
 
     for sector_index in chain {
       self.read_sat_sector(sector_index as usize, &mut sec_ids)?;
@@ -79,7 +66,7 @@ impl<'ole> super::ole::Reader<'ole> {
   pub(crate) fn build_dsat(&mut self) -> Result<(), super::error::Error> {
 
     let sector_index = self.dsat.as_mut().unwrap().remove(0);
-    let chain = self.build_chain_from_sat(sector_index);
+    let chain = self.build_chain_from_sat(sector_index, None);
 
     for sector_index in chain {
       self.dsat.as_mut().unwrap().push(sector_index);
@@ -88,3 +75,28 @@ impl<'ole> super::ole::Reader<'ole> {
     Ok(())
   }
 }
+
+/// Follows a chain of `table` from `start` up to its end, a SecID out of
+/// the table, or `max_len` SecIDs. Without `max_len`, the chain ends on a
+/// SecID met before instead, as crafted files may link them in a cycle.
+pub(crate) fn build_chain(table: &[u32], start: u32, max_len: Option<usize>)
+    -> std::vec::Vec<u32> {
+  let mut chain = std::vec::Vec::new();
+  let mut visited = match max_len {
+    Some(_) => std::vec::Vec::new(),
+    None => vec![false; table.len()]
+  };
+  let max_len = max_len.unwrap_or(table.len());
+  let mut sec_id = start;
+  while (sec_id as usize) < table.len() && chain.len() < max_len {
+    if let Some(seen) = visited.get_mut(sec_id as usize) {
+      if *seen {
+        break;
+      }
+      *seen = true;
+    }
+    chain.push(sec_id);
+    sec_id = table[sec_id as usize];
+  }
+  chain
+}
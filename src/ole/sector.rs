@@ -1,28 +1,34 @@
 impl<'ole> super::ole::Reader<'ole> {
+  #[cfg_attr(feature = "tracing", tracing::instrument(level = "trace", skip(self)))]
   pub(crate) fn read_sector(&self, sector_index: usize)
-    -> Result<&[u8], super::error::Error> {
-    let result: Result<&[u8], super::error::Error>;
+    -> Result<std::borrow::Cow<'_, [u8]>, super::error::Error> {
+    self.read_sector_part(sector_index, 0, self.sec_size.unwrap())
+  }
+
+  /// Reads the bytes from `start` to `end` of a sector, e.g. a short sector
+  /// of the short stream.
+  pub(crate) fn read_sector_part(&self, sector_index: usize, start: usize,
+      end: usize) -> Result<std::borrow::Cow<'_, [u8]>, super::error::Error> {
     let sector_size = self.sec_size.unwrap();
-    let offset = sector_size * sector_index;
+    let offset = self.sector_offset(sector_index);
     let max_size = offset + sector_size;
 
-    let body_size: usize;
-    if self.body.is_some() {
-      body_size = self.body.as_ref().unwrap().len();
-    } else {
-      body_size = 0;
-    }
+    let body_size = self.body.as_ref().map_or(0, |body| body.len());
 
     // Check if the sector has already been read
-    let sector : &[u8];
     if body_size >= max_size {
-      let body = self.body.as_ref().unwrap();
-      sector = &body[offset .. offset + sector_size];
-      result = Ok(sector);
+      self.body.as_ref().unwrap().read(offset + start, end - start)
     } else {
-      result = Err(super::error::Error::BadSizeValue("File is too short"));
+      Err(super::error::Error::BadSizeValue("File is too short"))
     }
+  }
 
-    result
+  /// Returns the offset of a sector in the body. The header takes a whole
+  /// sector of a version 4 file, whose sectors are 4096 bytes long, the
+  /// first sector of the body then starting after its padding.
+  pub(crate) fn sector_offset(&self, sector_index: usize) -> usize {
+    let sector_size = self.sec_size.unwrap();
+    sector_size.saturating_sub(super::constants::HEADER_SIZE)
+      + sector_size * sector_index
   }
 }
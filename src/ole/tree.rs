@@ -0,0 +1,158 @@
+/// A storage of a compound file, with the storages and the streams it holds.
+///
+/// # Basic example
+///
+/// ```ignore
+/// use msg_parser::ole::Reader;
+/// let parser = Reader::from_path("data/attachment.msg").unwrap();
+/// let tree = parser.tree().unwrap();
+/// for attachment in tree.children.iter()
+///   .filter(|storage| storage.name.starts_with("__attach")) {
+///   println!("{} holds {} streams", attachment.name, attachment.streams.len());
+/// }
+/// // Prints the whole tree, one entry by line.
+/// println!("{}", tree);
+/// ```
+#[derive(Debug, Clone)]
+pub struct Storage<'a> {
+
+  /// Name of the storage.
+  pub name: &'a str,
+
+  /// CLSID of the storage, all zeroes when not set.
+  pub clsid: [u8; 16],
+
+  /// Storages held, in the order of their names.
+  pub children: std::vec::Vec<Storage<'a>>,
+
+  /// Streams held, in the order of their names.
+  pub streams: std::vec::Vec<&'a super::entry::Entry>
+}
+
+impl<'a> Storage<'a> {
+
+  fn new(ole: &'a super::ole::Reader, entry: &'a super::entry::Entry)
+    -> Storage<'a> {
+    let mut storage = Storage {
+      name: entry.name(),
+      clsid: entry.clsid(),
+      children: std::vec::Vec::new(),
+      streams: std::vec::Vec::new()
+    };
+    for child in ole.children_of(entry) {
+      match child._type() {
+        super::entry::EntryType::UserStorage =>
+          storage.children.push(Storage::new(ole, child)),
+        super::entry::EntryType::UserStream => storage.streams.push(child),
+        _ => {}
+      }
+    }
+    storage
+  }
+
+  /// Returns the storage at `path`, the names of the storages separated by
+  /// `/`, from this storage.
+  pub fn storage(&self, path: &str) -> Option<&Storage<'a>> {
+    let mut storage = self;
+    for name in path.split('/').filter(|name| !name.is_empty()) {
+      storage = storage.children.iter()
+        .find(|child| super::ole::Reader::same_name(child.name, name))?;
+    }
+    Some(storage)
+  }
+
+  /// Returns the stream named `name` held by this storage.
+  pub fn stream(&self, name: &str) -> Option<&'a super::entry::Entry> {
+    self.streams.iter()
+      .find(|stream| super::ole::Reader::same_name(stream.name(), name))
+      .copied()
+  }
+
+  fn write_tree(&self, f: &mut std::fmt::Formatter, depth: usize)
+    -> std::fmt::Result {
+    write!(f, "{:indent$}{}", "", self.name, indent = depth * 2)?;
+    if self.clsid != [0u8; 16] {
+      write!(f, " {{{}}}", format_clsid(&self.clsid))?;
+    }
+    writeln!(f)?;
+    for stream in &self.streams {
+      writeln!(f, "{:indent$}{} ({} bytes)", "", stream.name(), stream.len(),
+        indent = (depth + 1) * 2)?;
+    }
+    for child in &self.children {
+      child.write_tree(f, depth + 1)?;
+    }
+    Ok(())
+  }
+}
+
+/// Prints the storage, then the streams and the storages it holds, indented
+/// by two spaces by level.
+impl<'a> std::fmt::Display for Storage<'a> {
+  fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+    self.write_tree(f, 0)
+  }
+}
+
+/// Formats a CLSID the way the registry lists them, e.g.
+/// `00020D0B-0000-0000-C000-000000000046`.
+pub(crate) fn format_clsid(clsid: &[u8; 16]) -> std::string::String {
+  let mut text = format!("{:08X}-{:04X}-{:04X}-",
+    u32::from_le_bytes([clsid[0], clsid[1], clsid[2], clsid[3]]),
+    u16::from_le_bytes([clsid[4], clsid[5]]),
+    u16::from_le_bytes([clsid[6], clsid[7]]));
+  for (i, byte) in clsid[8 ..].iter().enumerate() {
+    if i == 2 {
+      text.push('-');
+    }
+    text.push_str(&format!("{:02X}", byte));
+  }
+  text
+}
+
+impl<'ole> super::ole::Reader<'ole> {
+
+  /// Returns the tree of the storages and the streams of the file, from the
+  /// root storage.
+  pub fn tree(&self) -> Option<Storage<'_>> {
+    let root = self.entries.as_ref()?.first()?;
+    Some(Storage::new(self, root))
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::super::ole::Reader;
+
+  #[test]
+  fn tree() {
+    let ole = Reader::from_path("data/attachment.msg").unwrap();
+    let tree = ole.tree().unwrap();
+    assert_eq!(tree.name, "Root Entry");
+    let attachments: std::vec::Vec<&str> = tree.children.iter()
+      .map(|storage| storage.name)
+      .filter(|name| name.starts_with("__attach")).collect();
+    assert_eq!(attachments, ["__attach_version1.0_#00000000",
+      "__attach_version1.0_#00000001", "__attach_version1.0_#00000002"]);
+    let attachment = tree.storage("__attach_version1.0_#00000001").unwrap();
+    let stream = attachment.stream("__substg1.0_3707001F").unwrap();
+    assert_eq!(stream.id(), ole
+      .entry_by_path("__attach_version1.0_#00000001/__substg1.0_3707001F")
+      .unwrap().id());
+    assert!(tree.storage("__attach_version1.0_#00000009").is_none());
+
+    // Every stream and storage is in the tree once.
+    fn count(storage: &super::Storage) -> usize {
+      1 + storage.streams.len() + storage.children.iter().map(count)
+        .sum::<usize>()
+    }
+    let entries = ole.iterate().filter(|entry| entry.parent_node().is_some()
+      || entry.id() == 0).count();
+    assert_eq!(count(&tree), entries);
+
+    let text = tree.to_string();
+    assert!(text.starts_with("Root Entry {00020D0B-0000-0000-C000-000000000046}\n"));
+    assert!(text.contains("\n  __attach_version1.0_#00000001\n"));
+    assert!(text.contains("\n    __substg1.0_3707001F ("));
+  }
+}
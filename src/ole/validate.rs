@@ -0,0 +1,376 @@
+/// Chain of sectors a problem was found in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Chain {
+
+  /// Sectors of the directory.
+  Directory,
+
+  /// Sectors of the short stream, held by the root storage.
+  ShortStream,
+
+  /// Sectors, or short sectors, of the stream of the entry with this DirID.
+  Stream(u32)
+}
+
+impl std::fmt::Display for Chain {
+  fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+    match *self {
+      Chain::Directory => write!(f, "directory"),
+      Chain::ShortStream => write!(f, "short stream"),
+      Chain::Stream(id) => write!(f, "stream of entry #{}", id)
+    }
+  }
+}
+
+/// A problem in the structure of a compound file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Problem {
+
+  /// A chain links to a SecID out of its allocation table.
+  SectorOutOfRange {
+    /// Chain of the link.
+    chain: Chain,
+    /// SecID linked to.
+    sec_id: u32
+  },
+
+  /// A chain links to a free sector rather than ending.
+  FreeSectorInChain {
+    /// Chain of the link.
+    chain: Chain,
+    /// SecID linked to.
+    sec_id: u32
+  },
+
+  /// A chain links back to one of its own sectors.
+  ChainCycle {
+    /// Chain of the link.
+    chain: Chain,
+    /// SecID linked to.
+    sec_id: u32
+  },
+
+  /// A sector belongs to two chains.
+  SharedSector {
+    /// Chain met second.
+    chain: Chain,
+    /// Chain met first.
+    other: Chain,
+    /// SecID of the sector.
+    sec_id: u32
+  },
+
+  /// A stream has fewer sectors than its size needs.
+  ChainTooShort {
+    /// Chain of the stream.
+    chain: Chain,
+    /// Sectors of the chain.
+    sectors: usize,
+    /// Sectors the size of the stream needs.
+    needed: usize
+  },
+
+  /// A stream has more sectors than its size needs.
+  ChainTooLong {
+    /// Chain of the stream.
+    chain: Chain,
+    /// Sectors of the chain.
+    sectors: usize,
+    /// Sectors the size of the stream needs.
+    needed: usize
+  },
+
+  /// A directory entry links to a DirID out of the directory.
+  EntryOutOfRange {
+    /// DirID of the entry.
+    entry: u32,
+    /// DirID linked to.
+    link: u32
+  },
+
+  /// A directory entry is linked twice, from two storages or in a cycle.
+  EntryLinkedTwice {
+    /// DirID of the entry.
+    entry: u32
+  },
+
+  /// A stream or a storage no storage holds.
+  UnreachableEntry {
+    /// DirID of the entry.
+    entry: u32
+  }
+}
+
+impl std::fmt::Display for Problem {
+  fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+    match self {
+      Problem::SectorOutOfRange { chain, sec_id } =>
+        write!(f, "{} links to sector {} out of the table", chain, sec_id),
+      Problem::FreeSectorInChain { chain, sec_id } =>
+        write!(f, "{} links to free sector {}", chain, sec_id),
+      Problem::ChainCycle { chain, sec_id } =>
+        write!(f, "{} links back to sector {}", chain, sec_id),
+      Problem::SharedSector { chain, other, sec_id } =>
+        write!(f, "{} shares sector {} with {}", chain, sec_id, other),
+      Problem::ChainTooShort { chain, sectors, needed } =>
+        write!(f, "{} has {} sectors, {} needed", chain, sectors, needed),
+      Problem::ChainTooLong { chain, sectors, needed } =>
+        write!(f, "{} has {} sectors, {} needed", chain, sectors, needed),
+      Problem::EntryOutOfRange { entry, link } =>
+        write!(f, "entry #{} links to entry #{} out of the directory", entry,
+          link),
+      Problem::EntryLinkedTwice { entry } =>
+        write!(f, "entry #{} is linked twice", entry),
+      Problem::UnreachableEntry { entry } =>
+        write!(f, "entry #{} is held by no storage", entry)
+    }
+  }
+}
+
+/// Problems found by `Reader::validate`, none for a well-formed file.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ValidationReport {
+
+  /// Problems found, in the order they were met.
+  pub problems: std::vec::Vec<Problem>
+}
+
+impl ValidationReport {
+
+  /// Returns true if no problem was found.
+  pub fn is_valid(&self) -> bool {
+    self.problems.is_empty()
+  }
+}
+
+/// Sectors of an allocation table, and the chain each one belongs to.
+struct Table<'t> {
+  sec_ids: &'t [u32],
+  len: usize, // SecIDs from this one are out of the table
+  owners: std::collections::HashMap<u32, Chain>
+}
+
+impl<'t> Table<'t> {
+
+  fn new(sec_ids: &'t [u32], len: usize) -> Table<'t> {
+    Table {
+      sec_ids,
+      len: std::cmp::min(len, sec_ids.len()),
+      owners: std::collections::HashMap::new()
+    }
+  }
+
+  /// Follows `chain` from `start`, and returns its number of sectors if it
+  /// ends properly.
+  fn walk(&mut self, chain: Chain, start: u32,
+      problems: &mut std::vec::Vec<Problem>) -> Option<usize> {
+    let mut sectors = 0usize;
+    let mut sec_id = start;
+    loop {
+      let problem = if sec_id == super::constants::END_OF_CHAIN_SECID_U32 {
+        return Some(sectors);
+      } else if sec_id == super::constants::FREE_SECID_U32 {
+        Problem::FreeSectorInChain { chain, sec_id }
+      } else if sec_id as usize >= self.len {
+        Problem::SectorOutOfRange { chain, sec_id }
+      } else {
+        match self.owners.insert(sec_id, chain) {
+          None => {
+            sectors += 1;
+            sec_id = self.sec_ids[sec_id as usize];
+            continue;
+          },
+          Some(other) if other == chain => {
+            Problem::ChainCycle { chain, sec_id }
+          },
+          Some(other) => {
+            self.owners.insert(sec_id, other);
+            Problem::SharedSector { chain, other, sec_id }
+          }
+        }
+      };
+      problems.push(problem);
+      return None;
+    }
+  }
+}
+
+impl<'ole> super::ole::Reader<'ole> {
+
+  /// Checks the structure of the file: the chains of the allocation tables,
+  /// which must end properly, without cycles and without sharing sectors,
+  /// the number of sectors of each stream against its size, and the tree
+  /// of the directory, which must hold every entry once.
+  ///
+  /// A `Reader` reads corrupt files as far as it can, chains being cut
+  /// where they go wrong, which this tells apart from a well-formed file.
+  ///
+  /// # Examples
+  ///
+  /// ```ignore
+  /// use msg_parser::ole::Reader;
+  /// let parser = Reader::from_path("data/attachment.msg").unwrap();
+  /// for problem in parser.validate().problems {
+  ///   println!("{}", problem);
+  /// }
+  /// ```
+  pub fn validate(&self) -> ValidationReport {
+    let mut problems = std::vec::Vec::new();
+    let entries = self.entries.as_deref().unwrap_or(&[]);
+    let sec_size = self.sec_size.unwrap();
+    let short_sec_size = self.short_sec_size.unwrap();
+    let sat = self.sat.as_deref().unwrap_or(&[]);
+    let ssat = self.ssat.as_deref().unwrap_or(&[]);
+    let body_sectors = self.body.as_ref().map_or(0, |body| body.len())
+      .saturating_sub(self.sector_offset(0)) / sec_size;
+    let mut table = Table::new(sat, body_sectors);
+
+    if let Some(start) = self.dsat.as_ref().and_then(|dsat| dsat.first()) {
+      table.walk(Chain::Directory, *start, &mut problems);
+    }
+    let mut short_stream_len = 0;
+    if let Some(root) = entries.first() {
+      let chain = Chain::ShortStream;
+      let needed = root.len().div_ceil(sec_size);
+      if let Some(sectors) = table.walk(chain, root.start_sec_id(),
+          &mut problems) {
+        Self::check_len(chain, sectors, needed, &mut problems);
+        short_stream_len = std::cmp::min(sectors, needed);
+      }
+    }
+    let mut short_table = Table::new(ssat,
+      short_stream_len * (sec_size / short_sec_size));
+    let stream_size = self.minimum_standard_stream_size.unwrap();
+    for entry in entries.iter().filter(|entry| {
+      entry._type() == super::entry::EntryType::UserStream
+        && !entry.is_empty()
+    }) {
+      let chain = Chain::Stream(entry.id());
+      let (table, size) = match entry.len() < stream_size {
+        true => (&mut short_table, short_sec_size),
+        false => (&mut table, sec_size)
+      };
+      if let Some(sectors) = table.walk(chain, entry.start_sec_id(),
+          &mut problems) {
+        Self::check_len(chain, sectors, entry.len().div_ceil(size),
+          &mut problems);
+      }
+    }
+    Self::check_tree(entries, &mut problems);
+    ValidationReport { problems }
+  }
+
+  fn check_len(chain: Chain, sectors: usize, needed: usize,
+      problems: &mut std::vec::Vec<Problem>) {
+    if sectors < needed {
+      problems.push(Problem::ChainTooShort { chain, sectors, needed });
+    } else if sectors > needed {
+      problems.push(Problem::ChainTooLong { chain, sectors, needed });
+    }
+  }
+
+  /// Walks the tree of the directory from the root storage.
+  fn check_tree(entries: &[super::entry::Entry],
+      problems: &mut std::vec::Vec<Problem>) {
+    let mut seen = vec![false; entries.len()];
+    let mut stack = vec![0u32];
+    while let Some(id) = stack.pop() {
+      let Some(entry) = entries.get(id as usize) else {
+        continue;
+      };
+      if std::mem::replace(&mut seen[id as usize], true) {
+        problems.push(Problem::EntryLinkedTwice { entry: id });
+        continue;
+      }
+      let mut links = vec![entry.left_child_node(), entry.right_child_node()];
+      if entry._type() == super::entry::EntryType::RootStorage
+          || entry._type() == super::entry::EntryType::UserStorage {
+        links.push(entry.child_node());
+      }
+      for link in links.into_iter()
+          .filter(|link| *link != super::constants::FREE_SECID_U32) {
+        if link as usize >= entries.len() {
+          problems.push(Problem::EntryOutOfRange { entry: id, link });
+        } else {
+          stack.push(link);
+        }
+      }
+    }
+    for entry in entries.iter().filter(|entry| !seen[entry.id() as usize]
+        && entry._type() != super::entry::EntryType::Empty) {
+      problems.push(Problem::UnreachableEntry { entry: entry.id() });
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::{Chain, Problem};
+  use super::super::ole::Reader;
+  use super::super::writer::{Writer, ROOT};
+
+  /// Offset of the directory entry named `name` in a file.
+  fn entry_offset(bytes: &[u8], name: &str) -> usize {
+    let name: std::vec::Vec<u8> = name.encode_utf16()
+      .flat_map(u16::to_le_bytes).collect();
+    bytes.windows(name.len()).position(|window| window == name).unwrap()
+  }
+
+  fn set_u32(bytes: &mut [u8], offset: usize, value: u32) {
+    bytes[offset .. offset + 4].copy_from_slice(&value.to_le_bytes());
+  }
+
+  #[test]
+  fn valid() {
+    for path in ["data/Thumbs.db", "data/sample.doc", "data/sample.ppt",
+        "data/attachment.msg", "data/unicode.msg", "data/test_email.msg"] {
+      let report = Reader::from_path(path).unwrap().validate();
+      assert!(report.is_valid(), "{}: {:?}", path, report.problems);
+    }
+  }
+
+  #[test]
+  fn corrupt() {
+    let mut writer = Writer::new();
+    writer.add_stream(ROOT, "Large", vec![1u8; 5000]);
+    writer.add_stream(ROOT, "Other", vec![2u8; 5000]);
+    writer.add_stream(ROOT, "Small", vec![3u8; 100]);
+    let bytes = writer.to_bytes();
+    let large = entry_offset(&bytes, "Large");
+    let other = entry_offset(&bytes, "Other");
+    let start = |offset: usize| u32::from_le_bytes([bytes[offset + 116],
+      bytes[offset + 117], bytes[offset + 118], bytes[offset + 119]]);
+
+    // "Large" linked to its own first sector, and "Other" starting in it.
+    let mut corrupt = bytes.clone();
+    let sat = 512 + 512 * u32::from_le_bytes([bytes[76], bytes[77],
+      bytes[78], bytes[79]]) as usize;
+    set_u32(&mut corrupt, sat + 4 * (start(large) as usize + 9),
+      start(large));
+    set_u32(&mut corrupt, other + 116, start(large) + 1);
+    let reader = Reader::new(&corrupt[..]).unwrap();
+    let report = reader.validate();
+    assert!(!report.is_valid());
+    let large_id = reader.entry_by_path("Large").unwrap().id();
+    let other_id = reader.entry_by_path("Other").unwrap().id();
+    assert!(report.problems.contains(&Problem::ChainCycle {
+      chain: Chain::Stream(large_id), sec_id: start(large) }));
+    assert!(report.problems.contains(&Problem::SharedSector {
+      chain: Chain::Stream(other_id), other: Chain::Stream(large_id),
+      sec_id: start(large) + 1 }));
+
+    // A size larger than the chain, and an entry out of the directory.
+    let mut corrupt = bytes.clone();
+    set_u32(&mut corrupt, large + 120, 6000);
+    set_u32(&mut corrupt, entry_offset(&bytes, "Small") + 68, 1000);
+    let reader = Reader::new(&corrupt[..]).unwrap();
+    let report = reader.validate();
+    let small_id = reader.entry_by_path("Small").unwrap().id();
+    assert!(report.problems.contains(&Problem::ChainTooShort {
+      chain: Chain::Stream(large_id), sectors: 10, needed: 12 }));
+    assert!(report.problems.contains(&Problem::EntryOutOfRange {
+      entry: small_id, link: 1000 }));
+    assert_eq!(report.problems[0].to_string(),
+      format!("stream of entry #{} has 10 sectors, 12 needed", large_id));
+  }
+}
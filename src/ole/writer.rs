@@ -0,0 +1,608 @@
+use super::constants::{DIRECTORY_ENTRY_SIZE, END_OF_CHAIN_SECID_U32, FREE_SECID_U32,
+  HEADER_SIZE, IDENTIFIER, LITTLE_ENDIAN_IDENTIFIER};
+use super::entry::EntryType;
+
+/// DirID of the root storage, the parent of the first storages and
+/// streams added to a `Writer`.
+pub const ROOT: usize = 0;
+
+/// Version 3 files have sectors of 512 bytes (2 ^ 9), version 4 files
+/// sectors of 4096 bytes (2 ^ 12). Both have short sectors of 64 bytes
+/// (2 ^ 6).
+const SECTOR_SHIFT: u16 = 9;
+const SECTOR_SHIFT_V4: u16 = 12;
+const SHORT_SECTOR_SHIFT: u16 = 6;
+const SHORT_SECTOR_SIZE: usize = 1 << SHORT_SECTOR_SHIFT;
+
+/// Streams shorter than this live in the short stream.
+const MINIMUM_STANDARD_STREAM_SIZE: usize = 4096;
+
+/// Special SecIDs marking the sectors of the SAT and the MSAT.
+const SAT_SECID_U32: u32 = 0xFFFFFFFDu32;
+const MSAT_SECID_U32: u32 = 0xFFFFFFFCu32;
+
+/// Number of SecIDs of the MSAT held by the header.
+const HEADER_MSAT_LEN: usize = 109;
+
+/// Names are at most 31 UTF-16 code units long, plus a terminating null.
+const MAX_NAME_LEN: usize = 31;
+
+/// A stream or a storage to be written.
+struct Node {
+  name: std::string::String,
+  entry_type: EntryType,
+  clsid: [u8; 16],
+  data: std::vec::Vec<u8>,
+  children: std::vec::Vec<usize>
+}
+
+/// Links of a directory entry.
+#[derive(Clone, Copy)]
+struct Links {
+  left: u32,
+  right: u32,
+  child: u32,
+  red: bool
+}
+
+/// An OLE file writer.
+///
+/// Storages and streams are added under their parent storage, then the
+/// whole file is laid out at once: streams, short stream, short-sector
+/// allocation table, directory, and sector allocation tables. The files
+/// written are read back by `Reader`.
+///
+/// # Basic Example
+///
+/// ```ignore
+/// use msg_parser::ole::{Writer, ROOT};
+///
+/// let mut writer = Writer::new();
+/// let storage = writer.add_storage(ROOT, "Folder");
+/// writer.add_stream(storage, "File", b"content".to_vec());
+/// let bytes = writer.to_bytes();
+/// ```
+pub struct Writer {
+  nodes: std::vec::Vec<Node>,
+  major_version: u16
+}
+
+impl Writer {
+
+  /// Constructs a new `Writer` holding an empty root storage.
+  pub fn new() -> Writer {
+    Writer {
+      nodes: vec![Node {
+        name: "Root Entry".to_string(),
+        entry_type: EntryType::RootStorage,
+        clsid: [0u8; 16],
+        data: std::vec::Vec::new(),
+        children: std::vec::Vec::new()
+      }],
+      major_version: 3
+    }
+  }
+
+  /// Sets the major version of the file, 3 by default, or 4 for sectors
+  /// of 4096 bytes.
+  ///
+  /// # Panics
+  ///
+  /// Panics if `major_version` is neither 3 nor 4.
+  pub fn set_major_version(&mut self, major_version: u16) {
+    assert!(major_version == 3 || major_version == 4,
+      "major version {} is neither 3 nor 4", major_version);
+    self.major_version = major_version;
+  }
+
+  /// Writes the file to `writer`.
+  pub fn write_to<W: std::io::Write>(&self, mut writer: W)
+      -> Result<(), super::error::Error> {
+    writer.write_all(&self.to_bytes()).map_err(super::error::Error::IOError)
+  }
+
+  fn sector_shift(&self) -> u16 {
+    match self.major_version {
+      4 => SECTOR_SHIFT_V4,
+      _ => SECTOR_SHIFT
+    }
+  }
+
+  /// Constructs a `Writer` holding the storages and the streams of a file,
+  /// e.g. to write them again with another major version.
+  pub fn from_reader(reader: &super::ole::Reader)
+      -> Result<Writer, super::error::Error> {
+    let mut writer = Writer::new();
+    if let Some(tree) = reader.tree() {
+      writer.set_clsid(ROOT, tree.clsid);
+      writer.copy_storage(reader, &tree, ROOT)?;
+    }
+    Ok(writer)
+  }
+
+  fn copy_storage(&mut self, reader: &super::ole::Reader,
+      storage: &super::tree::Storage, id: usize)
+      -> Result<(), super::error::Error> {
+    for stream in &storage.streams {
+      let data = match stream.is_empty() {
+        true => std::vec::Vec::new(),
+        false => reader.get_entry_bytes(stream)?.into_owned()
+      };
+      self.add_stream(id, stream.name(), data);
+    }
+    for child in &storage.children {
+      let child_id = self.add_storage(id, child.name);
+      self.set_clsid(child_id, child.clsid);
+      self.copy_storage(reader, child, child_id)?;
+    }
+    Ok(())
+  }
+
+  /// Sets the class identifier of a storage.
+  pub fn set_clsid(&mut self, storage: usize, clsid: [u8; 16]) {
+    self.nodes[storage].clsid = clsid;
+  }
+
+  /// Adds a storage under `parent` and returns its DirID. Names longer than
+  /// 31 UTF-16 code units are cut.
+  ///
+  /// # Panics
+  ///
+  /// Panics if `parent` is not the DirID of a storage of the writer.
+  pub fn add_storage(&mut self, parent: usize, name: &str) -> usize {
+    self.add_node(parent, name, EntryType::UserStorage, std::vec::Vec::new())
+  }
+
+  /// Adds a stream under `parent` and returns its DirID. Names longer than
+  /// 31 UTF-16 code units are cut.
+  ///
+  /// # Panics
+  ///
+  /// Panics if `parent` is not the DirID of a storage of the writer.
+  pub fn add_stream(&mut self, parent: usize, name: &str,
+      data: std::vec::Vec<u8>) -> usize {
+    self.add_node(parent, name, EntryType::UserStream, data)
+  }
+
+  fn add_node(&mut self, parent: usize, name: &str, entry_type: EntryType,
+      data: std::vec::Vec<u8>) -> usize {
+    assert!(self.nodes.get(parent).is_some_and(|node| node.entry_type
+      != EntryType::UserStream), "{} is not the DirID of a storage", parent);
+    let id = self.nodes.len();
+    let name: std::vec::Vec<u16> = name.encode_utf16().take(MAX_NAME_LEN)
+      .collect();
+    self.nodes.push(Node {
+      name: std::string::String::from_utf16_lossy(&name),
+      entry_type,
+      clsid: [0u8; 16],
+      data,
+      children: std::vec::Vec::new()
+    });
+    self.nodes[parent].children.push(id);
+    id
+  }
+
+  /// Lays out the file.
+  pub fn to_bytes(&self) -> std::vec::Vec<u8> {
+    let sector_size = 1usize << self.sector_shift();
+    let mut body = std::vec::Vec::<u8>::new();
+    let mut sat = std::vec::Vec::<u32>::new();
+
+    // Start SecIDs of the entries; short streams refer to short sectors.
+    let mut starts = vec![END_OF_CHAIN_SECID_U32; self.nodes.len()];
+    let mut short_stream = std::vec::Vec::<u8>::new();
+    let mut ssat = std::vec::Vec::<u32>::new();
+    for (id, node) in self.nodes.iter().enumerate() {
+      if node.entry_type != EntryType::UserStream || node.data.is_empty() {
+        continue;
+      }
+      if node.data.len() < MINIMUM_STANDARD_STREAM_SIZE {
+        starts[id] = allocate(&mut short_stream, &mut ssat, &node.data,
+          SHORT_SECTOR_SIZE);
+      } else {
+        starts[id] = allocate(&mut body, &mut sat, &node.data, sector_size);
+      }
+    }
+    if !short_stream.is_empty() {
+      starts[ROOT] = allocate(&mut body, &mut sat, &short_stream, sector_size);
+    }
+
+    let mut ssat_start = END_OF_CHAIN_SECID_U32;
+    let mut ssat_len = 0;
+    if !ssat.is_empty() {
+      let table = sec_ids_to_bytes(&ssat, sector_size);
+      ssat_len = table.len() / sector_size;
+      ssat_start = allocate(&mut body, &mut sat, &table, sector_size);
+    }
+
+    let directory = self.directory(&starts, short_stream.len(), sector_size);
+    let directory_start = allocate(&mut body, &mut sat, &directory, sector_size);
+
+    // The SAT describes its own sectors and the ones of the MSAT,
+    // so their numbers depend on each other.
+    let data_len = sat.len();
+    let (mut sat_len, mut msat_len) = (0usize, 0usize);
+    loop {
+      let total = data_len + sat_len + msat_len;
+      let needed_sat_len = total.div_ceil(sector_size / 4);
+      let needed_msat_len = needed_sat_len.saturating_sub(HEADER_MSAT_LEN)
+        .div_ceil(sector_size / 4 - 1);
+      if needed_sat_len == sat_len && needed_msat_len == msat_len {
+        break;
+      }
+      sat_len = needed_sat_len;
+      msat_len = needed_msat_len;
+    }
+    let sat_sec_ids: std::vec::Vec<u32> =
+      (data_len .. data_len + sat_len).map(|id| id as u32).collect();
+    sat.extend(std::iter::repeat_n(SAT_SECID_U32, sat_len));
+    sat.extend(std::iter::repeat_n(MSAT_SECID_U32, msat_len));
+
+    // The header takes a whole sector of a version 4 file, the rest of it
+    // being zeroes.
+    let header_len = std::cmp::max(HEADER_SIZE, sector_size);
+    let mut file = std::vec::Vec::with_capacity(
+      header_len + (data_len + sat_len + msat_len) * sector_size);
+    file.extend(self.header(&sat_sec_ids, directory_start,
+      directory.len() / sector_size, ssat_start, ssat_len,
+      data_len + sat_len, msat_len));
+    file.resize(header_len, 0u8);
+    file.extend(body);
+    file.extend(sec_ids_to_bytes(&sat, sector_size));
+
+    // SecIDs beyond the header go in MSAT sectors, the last SecID of
+    // each one pointing to the next.
+    let per_sector = sector_size / 4 - 1;
+    let rest: std::vec::Vec<u32> = sat_sec_ids.iter().skip(HEADER_MSAT_LEN)
+      .copied().collect();
+    for (i, chunk) in rest.chunks(per_sector).enumerate() {
+      let mut sector = chunk.to_vec();
+      sector.resize(per_sector, FREE_SECID_U32);
+      sector.push(if i + 1 < msat_len {
+        (data_len + sat_len + i + 1) as u32
+      } else {
+        END_OF_CHAIN_SECID_U32
+      });
+      file.extend(sec_ids_to_bytes(&sector, sector_size));
+    }
+    file
+  }
+
+  #[allow(clippy::too_many_arguments)]
+  fn header(&self, sat_sec_ids: &[u32], directory_start: u32,
+      directory_len: usize, ssat_start: u32, ssat_len: usize,
+      msat_start: usize, msat_len: usize)
+      -> std::vec::Vec<u8> {
+    let mut header = std::vec::Vec::with_capacity(HEADER_SIZE);
+    header.extend(IDENTIFIER);
+    // UID
+    header.extend([0u8; 16]);
+    // Revision number & version number
+    header.extend(0x003Eu16.to_le_bytes());
+    header.extend(self.major_version.to_le_bytes());
+    header.extend(LITTLE_ENDIAN_IDENTIFIER);
+    header.extend(self.sector_shift().to_le_bytes());
+    header.extend(SHORT_SECTOR_SHIFT.to_le_bytes());
+    header.extend([0u8; 6]);
+    // Number of directory sectors, which version 3 files leave to 0
+    let directory_len = match self.major_version {
+      4 => directory_len as u32,
+      _ => 0
+    };
+    header.extend(directory_len.to_le_bytes());
+    header.extend((sat_sec_ids.len() as u32).to_le_bytes());
+    header.extend(directory_start.to_le_bytes());
+    header.extend([0u8; 4]);
+    header.extend((MINIMUM_STANDARD_STREAM_SIZE as u32).to_le_bytes());
+    header.extend(ssat_start.to_le_bytes());
+    header.extend((ssat_len as u32).to_le_bytes());
+    let msat_start = if msat_len > 0 {
+      msat_start as u32
+    } else {
+      END_OF_CHAIN_SECID_U32
+    };
+    header.extend(msat_start.to_le_bytes());
+    header.extend((msat_len as u32).to_le_bytes());
+    let mut msat: std::vec::Vec<u32> = sat_sec_ids.iter()
+      .take(HEADER_MSAT_LEN).copied().collect();
+    msat.resize(HEADER_MSAT_LEN, FREE_SECID_U32);
+    header.extend(sec_ids_to_bytes(&msat, HEADER_SIZE).iter().take(HEADER_MSAT_LEN * 4));
+    header
+  }
+
+  /// Directory entries, each storage holding its children in a
+  /// red-black tree.
+  fn directory(&self, starts: &[u32], short_stream_len: usize,
+      sector_size: usize) -> std::vec::Vec<u8> {
+    let mut links = vec![Links {
+      left: FREE_SECID_U32,
+      right: FREE_SECID_U32,
+      child: FREE_SECID_U32,
+      red: false
+    }; self.nodes.len()];
+    for (id, node) in self.nodes.iter().enumerate() {
+      let mut children = node.children.clone();
+      children.sort_by(|a, b| compare_names(&self.nodes[*a].name,
+        &self.nodes[*b].name));
+      if !children.is_empty() {
+        let max_depth = (usize::BITS - 1 - children.len().leading_zeros())
+          as usize;
+        links[id].child = build_tree(&children, 0, max_depth, &mut links);
+      }
+    }
+
+    let mut directory = std::vec::Vec::with_capacity(
+      self.nodes.len().next_multiple_of(sector_size / DIRECTORY_ENTRY_SIZE)
+      * DIRECTORY_ENTRY_SIZE);
+    for (id, node) in self.nodes.iter().enumerate() {
+      let size = match node.entry_type {
+        EntryType::RootStorage => short_stream_len,
+        _ => node.data.len()
+      };
+      directory.extend(entry_to_bytes(node, &links[id], starts[id], size));
+    }
+    // Unused entries fill the last sector.
+    while !directory.len().is_multiple_of(sector_size) {
+      let mut empty = [0u8; DIRECTORY_ENTRY_SIZE];
+      empty[68 .. 80].fill(0xFF);
+      directory.extend(empty);
+    }
+    directory
+  }
+}
+
+impl Default for Writer {
+  fn default() -> Writer {
+    Writer::new()
+  }
+}
+
+/// Appends data to a stream of sectors, chaining them in the allocation
+/// table, and returns the SecID of the first one.
+fn allocate(body: &mut std::vec::Vec<u8>, table: &mut std::vec::Vec<u32>,
+    data: &[u8], sector_size: usize) -> u32 {
+  let start = table.len();
+  let count = data.len().div_ceil(sector_size);
+  for i in 0 .. count {
+    table.push(if i + 1 < count {
+      (start + i + 1) as u32
+    } else {
+      END_OF_CHAIN_SECID_U32
+    });
+  }
+  body.extend(data);
+  body.resize((start + count) * sector_size, 0u8);
+  start as u32
+}
+
+/// Allocation table sectors, padded with free SecIDs.
+fn sec_ids_to_bytes(sec_ids: &[u32], sector_size: usize) -> std::vec::Vec<u8> {
+  let mut bytes: std::vec::Vec<u8> = sec_ids.iter()
+    .flat_map(|sec_id| sec_id.to_le_bytes()).collect();
+  while !bytes.len().is_multiple_of(sector_size) {
+    bytes.extend(FREE_SECID_U32.to_le_bytes());
+  }
+  bytes
+}
+
+/// Siblings are ordered by the length of their names first, then by
+/// their upper case names.
+fn compare_names(a: &str, b: &str) -> std::cmp::Ordering {
+  a.encode_utf16().count().cmp(&b.encode_utf16().count())
+    .then_with(|| a.to_uppercase().encode_utf16()
+      .cmp(b.to_uppercase().encode_utf16()))
+}
+
+/// Builds a balanced tree of sorted siblings and returns its root.
+///
+/// Every path from the root holds the same number of black nodes once
+/// the nodes of the deepest level are red, which makes it a valid
+/// red-black tree.
+fn build_tree(sorted: &[usize], depth: usize, max_depth: usize,
+    links: &mut [Links]) -> u32 {
+  if sorted.is_empty() {
+    return FREE_SECID_U32;
+  }
+  let middle = sorted.len() / 2;
+  let id = sorted[middle];
+  links[id].left = build_tree(&sorted[.. middle], depth + 1, max_depth, links);
+  links[id].right = build_tree(&sorted[middle + 1 ..], depth + 1, max_depth,
+    links);
+  links[id].red = depth > 0 && depth == max_depth;
+  id as u32
+}
+
+fn entry_to_bytes(node: &Node, links: &Links, start: u32, size: usize)
+    -> [u8; DIRECTORY_ENTRY_SIZE] {
+  let mut entry = [0u8; DIRECTORY_ENTRY_SIZE];
+  let name: std::vec::Vec<u16> = node.name.encode_utf16().collect();
+  for (i, unit) in name.iter().enumerate() {
+    entry[i * 2 .. i * 2 + 2].copy_from_slice(&unit.to_le_bytes());
+  }
+  entry[64 .. 66].copy_from_slice(&((name.len() as u16 + 1) * 2).to_le_bytes());
+  entry[66] = match node.entry_type {
+    EntryType::UserStorage => 1,
+    EntryType::UserStream => 2,
+    _ => 5
+  };
+  entry[67] = if links.red { 0 } else { 1 };
+  entry[68 .. 72].copy_from_slice(&links.left.to_le_bytes());
+  entry[72 .. 76].copy_from_slice(&links.right.to_le_bytes());
+  entry[76 .. 80].copy_from_slice(&links.child.to_le_bytes());
+  entry[80 .. 96].copy_from_slice(&node.clsid);
+  entry[116 .. 120].copy_from_slice(&start.to_le_bytes());
+  entry[120 .. 128].copy_from_slice(&(size as u64).to_le_bytes());
+  entry
+}
+
+
+#[cfg(test)]
+mod tests {
+  use super::{Writer, ROOT, compare_names};
+  use super::super::{EntryType, Reader, Storage};
+  use std::io::Read;
+
+  fn read(reader: &Reader, name: &str) -> std::vec::Vec<u8> {
+    let entry = reader.iterate().find(|entry| entry.name() == name).unwrap();
+    let mut slice = reader.get_entry_slice(entry).unwrap();
+    let mut buf = vec![0u8; slice.len()];
+    slice.read_exact(&mut buf).unwrap();
+    buf
+  }
+
+  #[test]
+  fn compare() {
+    use std::cmp::Ordering;
+    assert_eq!(compare_names("B", "AA"), Ordering::Less);
+    assert_eq!(compare_names("ab", "AC"), Ordering::Less);
+    assert_eq!(compare_names("ab", "AB"), Ordering::Equal);
+  }
+
+  #[test]
+  fn round_trip() {
+    let mut writer = Writer::new();
+    let storage = writer.add_storage(ROOT, "Storage");
+    let sizes = [1usize, 63, 64, 4095, 4096, 10000];
+    for (i, size) in sizes.iter().enumerate() {
+      let data = (0 .. *size).map(|b| (b * 7 + i) as u8).collect();
+      writer.add_stream(storage, &format!("Stream{}", i), data);
+    }
+    for i in 0 .. 40 {
+      writer.add_stream(ROOT, &format!("Child{}", i), vec![i as u8; 100]);
+    }
+    writer.add_stream(ROOT, "Empty", vec![]);
+    let bytes = writer.to_bytes();
+
+    let reader = Reader::new(&bytes[..]).unwrap();
+    assert_eq!(reader.iterate().filter(|entry| entry._type()
+      != EntryType::Empty).count(), 49);
+    for (i, size) in sizes.iter().enumerate() {
+      let data: std::vec::Vec<u8> =
+        (0 .. *size).map(|b| (b * 7 + i) as u8).collect();
+      assert_eq!(read(&reader, &format!("Stream{}", i)), data);
+    }
+    for i in 0 .. 40 {
+      let entry = reader.iterate()
+        .find(|entry| entry.name() == format!("Child{}", i)).unwrap();
+      assert_eq!(entry.parent_node(), Some(ROOT as u32));
+      assert_eq!(read(&reader, entry.name()), vec![i as u8; 100]);
+    }
+    let storage = reader.iterate().find(|entry| entry.name() == "Storage")
+      .unwrap();
+    assert_eq!(storage.children_nodes().len(), sizes.len());
+    let empty = reader.iterate().find(|entry| entry.name() == "Empty").unwrap();
+    assert_eq!(empty.len(), 0);
+    assert!(reader.validate().is_valid());
+  }
+
+  #[test]
+  fn version_4() {
+    let mut writer = Writer::new();
+    writer.set_major_version(4);
+    let sizes = [1usize, 4095, 4096, 10000, 70000];
+    for (i, size) in sizes.iter().enumerate() {
+      let data = (0 .. *size).map(|b| (b * 7 + i) as u8).collect();
+      writer.add_stream(ROOT, &format!("Stream{}", i), data);
+    }
+    let bytes = writer.to_bytes();
+    assert_eq!(&bytes[26 .. 28], &[4, 0]);
+    assert_eq!(&bytes[30 .. 32], &[12, 0]);
+    assert!(bytes.len().is_multiple_of(4096));
+    assert!(bytes[512 .. 4096].iter().all(|byte| *byte == 0));
+
+    let reader = Reader::new(&bytes[..]).unwrap();
+    assert_eq!(reader.sec_size, Some(4096));
+    for (i, size) in sizes.iter().enumerate() {
+      let data: std::vec::Vec<u8> =
+        (0 .. *size).map(|b| (b * 7 + i) as u8).collect();
+      assert_eq!(read(&reader, &format!("Stream{}", i)), data);
+    }
+    let borrowed = Reader::from_slice(&bytes).unwrap();
+    let entry = borrowed.entry_by_path("Stream4").unwrap();
+    let data = borrowed.get_entry_bytes(entry).unwrap();
+    assert!(matches!(data, std::borrow::Cow::Borrowed(_)));
+    assert_eq!(data.into_owned(), read(&reader, "Stream4"));
+  }
+
+  #[test]
+  fn from_reader() {
+    let file = Reader::from_path("data/attachment.msg").unwrap();
+    let mut writer = Writer::from_reader(&file).unwrap();
+    writer.set_major_version(4);
+    let bytes = writer.to_bytes();
+    let copy = Reader::new(&bytes[..]).unwrap();
+    assert_eq!(copy.tree().unwrap().to_string(),
+      file.tree().unwrap().to_string());
+    fn compare(file: &Reader, storage: &Storage, copy: &Reader,
+        copy_storage: &Storage) {
+      assert_eq!(storage.clsid, copy_storage.clsid);
+      for (stream, copy_stream) in storage.streams.iter()
+          .zip(&copy_storage.streams) {
+        assert_eq!(file.get_entry_bytes(stream).ok(),
+          copy.get_entry_bytes(copy_stream).ok());
+      }
+      for (child, copy_child) in storage.children.iter()
+          .zip(&copy_storage.children) {
+        compare(file, child, copy, copy_child);
+      }
+    }
+    compare(&file, &file.tree().unwrap(), &copy, &copy.tree().unwrap());
+  }
+
+  #[test]
+  fn names_and_clsids() {
+    let mut writer = Writer::default();
+    let clsid = [7u8; 16];
+    let storage = writer.add_storage(ROOT, &"Long".repeat(10));
+    writer.set_clsid(storage, clsid);
+    writer.add_stream(storage, "Stream", b"content".to_vec());
+    let mut bytes = vec![];
+    writer.write_to(&mut bytes).unwrap();
+    assert_eq!(bytes, writer.to_bytes());
+
+    let reader = Reader::from_slice(&bytes).unwrap();
+    let name = "Long".repeat(10)[.. 31].to_string();
+    let storage = reader.entry_by_path(&name).unwrap();
+    assert_eq!(storage.clsid(), clsid);
+    assert_eq!(reader.validate().problems, vec![]);
+    assert_eq!(read(&reader, "Stream"), b"content");
+  }
+
+  #[test]
+  #[should_panic(expected = "is not the DirID of a storage")]
+  fn stream_parent() {
+    let mut writer = Writer::new();
+    let stream = writer.add_stream(ROOT, "Stream", vec![]);
+    writer.add_stream(stream, "Inner", vec![]);
+  }
+
+  #[test]
+  fn large_file() {
+    // More than 109 SAT sectors call for an MSAT sector.
+    let mut writer = Writer::new();
+    writer.add_stream(ROOT, "Large", vec![0x42u8; 110 * 128 * 512]);
+    let bytes = writer.to_bytes();
+    let msat_len = u32::from_le_bytes([bytes[72], bytes[73], bytes[74],
+      bytes[75]]);
+    assert_eq!(msat_len, 1);
+    let sat_len = u32::from_le_bytes([bytes[44], bytes[45], bytes[46],
+      bytes[47]]) as usize;
+    assert_eq!(bytes.len(), 512 + (110 * 128 + 1 + sat_len + 1) * 512);
+
+    let reader = Reader::new(&bytes[..]).unwrap();
+    assert_eq!(reader.msat.as_ref().unwrap().len(), sat_len);
+    assert_eq!(read(&reader, "Large"), vec![0x42u8; 110 * 128 * 512]);
+
+    // A DIFAT sector linked to itself, and a count of sectors
+    // larger than the file.
+    let mut looped = bytes.clone();
+    looped[72 .. 76].copy_from_slice(&u32::MAX.to_le_bytes());
+    let difat = u32::from_le_bytes([bytes[68], bytes[69], bytes[70],
+      bytes[71]]);
+    let len = looped.len();
+    looped[len - 4 ..].copy_from_slice(&difat.to_le_bytes());
+    let reader = Reader::new(&looped[..]).unwrap();
+    assert_eq!(reader.msat.as_ref().unwrap().len(), sat_len);
+    assert_eq!(read(&reader, "Large").len(), 110 * 128 * 512);
+  }
+}
@@ -0,0 +1,233 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use super::{
+    decode::DataType,
+    meeting::Meeting,
+    outlook::Person,
+    recurrence::RecurrencePattern,
+    timezone::TimeZone,
+    storage::{Properties, Recipients},
+};
+
+// Appointment holds the calendar specific properties
+// of an IPM.Appointment or IPM.Schedule.Meeting.* item.
+// Refer to MS-OXOCAL 2.2.1
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct Appointment {
+    pub uid: String,                           // "GlobalObjectId"
+    pub subject: String,                       // "Subject"
+    pub start: Option<DateTime<Utc>>,          // "AppointmentStartWhole"
+    pub end: Option<DateTime<Utc>>,            // "AppointmentEndWhole"
+    pub location: String,                      // "Location"
+    pub all_day: bool,                         // "AppointmentSubType"
+    pub organizer: Person,                     // "SentRepresentingName", "SentRepresentingSmtpAddress"/"SentRepresentingEmailAddress"
+    pub required_attendees: Vec<Person>,       // "ToAttendeesString"
+    pub optional_attendees: Vec<Person>,       // "CcAttendeesString"
+    pub recurrence: Option<RecurrencePattern>, // "AppointmentRecur"
+    pub meeting: Option<Meeting>,              // See Meeting struct
+    pub time_zone: Option<TimeZone>,           // "AppointmentTimeZoneDefinitionStartDisplay"/"TimeZoneStruct"
+}
+
+impl Appointment {
+    pub fn is_calendar_class(message_class: &str) -> bool {
+        let message_class = message_class.to_ascii_lowercase();
+        message_class == "ipm.appointment"
+            || message_class.starts_with("ipm.appointment.")
+            || message_class.starts_with("ipm.schedule.meeting.")
+    }
+
+    // Attendee strings only carry display names separated
+    // by semicolons, emails are looked up in the recipients.
+    fn attendees_from_props(props: &Properties, key: &str, recipients: &Recipients) -> Vec<Person> {
+        let names = props
+            .get(key)
+            .and_then(DataType::as_str)
+            .unwrap_or_default();
+        names
+            .split(';')
+            .map(|name| name.trim())
+            .filter(|name| !name.is_empty())
+            .map(|name| {
+                recipients
+                    .iter()
+                    .find(|recip| recip.get("DisplayName").and_then(DataType::as_str) == Some(name))
+                    .map(|recip| {
+                        Person::create_from_props(
+                            recip,
+                            "DisplayName",
+                            vec!["SmtpAddress", "EmailAddress"],
+                        )
+                    })
+                    .unwrap_or_else(|| Person::new(name.to_string(), String::new()))
+            })
+            .collect()
+    }
+
+    pub fn create_from_props(props: &Properties, recipients: &Recipients) -> Option<Self> {
+        let message_class = props.get("MessageClass").and_then(DataType::as_str)?;
+        if !Self::is_calendar_class(message_class) {
+            return None;
+        }
+        let mut organizer = Person::create_from_props(
+            props,
+            "SentRepresentingName",
+            vec![
+                "SentRepresentingSmtpAddress",
+                "SentRepresentingEmailAddress",
+            ],
+        );
+        if organizer.name.is_empty() && organizer.email.is_empty() {
+            organizer = Person::create_from_props(
+                props,
+                "SenderName",
+                vec!["SenderSmtpAddress", "SenderEmailAddress"],
+            );
+        }
+        Some(Self {
+            uid: props
+                .get("GlobalObjectId")
+                .and_then(DataType::as_bytes)
+                .map(hex::encode_upper)
+                .unwrap_or_default(),
+            subject: props
+                .get("Subject")
+                .and_then(DataType::as_str)
+                .unwrap_or_default()
+                .to_string(),
+            start: props
+                .get("AppointmentStartWhole")
+                .and_then(DataType::as_time),
+            end: props.get("AppointmentEndWhole").and_then(DataType::as_time),
+            location: props
+                .get("Location")
+                .and_then(DataType::as_str)
+                .unwrap_or_default()
+                .to_string(),
+            all_day: props
+                .get("AppointmentSubType")
+                .and_then(DataType::as_bool)
+                .unwrap_or(false),
+            organizer,
+            required_attendees: Self::attendees_from_props(props, "ToAttendeesString", recipients),
+            optional_attendees: Self::attendees_from_props(props, "CcAttendeesString", recipients),
+            recurrence: props
+                .get("AppointmentRecur")
+                .and_then(DataType::as_bytes)
+                .and_then(|buff| RecurrencePattern::from_appointment_recur(buff).ok()),
+            meeting: Meeting::create_from_props(props),
+            time_zone: TimeZone::create_from_props(props),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{TimeZone, Utc};
+
+    use super::{
+        super::{
+            decode::DataType,
+            meeting::MeetingKind,
+            storage::{props, string, Properties},
+        },
+        Appointment, Person,
+    };
+
+    fn meeting_props() -> Properties {
+        props(&[
+            ("MessageClass", string("IPM.Schedule.Meeting.Request")),
+            (
+                "AppointmentStartWhole",
+                DataType::PtypTime(Utc.with_ymd_and_hms(2021, 6, 1, 14, 0, 0).unwrap()),
+            ),
+            (
+                "AppointmentEndWhole",
+                DataType::PtypTime(Utc.with_ymd_and_hms(2021, 6, 1, 15, 0, 0).unwrap()),
+            ),
+            ("Location", string("Room 101")),
+            ("AppointmentSubType", DataType::PtypBoolean(false)),
+            ("SentRepresentingName", string("Alice")),
+            ("SentRepresentingSmtpAddress", string("alice@example.com")),
+            ("ToAttendeesString", string("Bob; Carol")),
+            ("CcAttendeesString", string("Dave")),
+        ])
+    }
+
+    #[test]
+    fn test_is_calendar_class() {
+        assert!(Appointment::is_calendar_class("IPM.Appointment"));
+        assert!(Appointment::is_calendar_class(
+            "ipm.schedule.meeting.request"
+        ));
+        assert!(Appointment::is_calendar_class(
+            "IPM.Schedule.Meeting.Resp.Pos"
+        ));
+        assert!(!Appointment::is_calendar_class("IPM.Note"));
+        assert!(!Appointment::is_calendar_class("IPM.AppointmentX"));
+    }
+
+    #[test]
+    fn test_create_appointment() {
+        let mut bob = Properties::new();
+        bob.insert("DisplayName".to_string(), string("Bob"));
+        bob.insert("SmtpAddress".to_string(), string("bob@example.com"));
+
+        let appointment = Appointment::create_from_props(&meeting_props(), &vec![bob]).unwrap();
+        assert_eq!(
+            appointment.start,
+            Some(Utc.with_ymd_and_hms(2021, 6, 1, 14, 0, 0).unwrap())
+        );
+        assert_eq!(
+            appointment.end,
+            Some(Utc.with_ymd_and_hms(2021, 6, 1, 15, 0, 0).unwrap())
+        );
+        assert_eq!(appointment.location, "Room 101".to_string());
+        assert!(!appointment.all_day);
+        assert_eq!(
+            appointment.organizer,
+            Person::new("Alice".to_string(), "alice@example.com".to_string())
+        );
+        assert_eq!(
+            appointment.required_attendees,
+            vec![
+                Person::new("Bob".to_string(), "bob@example.com".to_string()),
+                Person::new("Carol".to_string(), String::new()),
+            ]
+        );
+        assert_eq!(
+            appointment.optional_attendees,
+            vec![Person::new("Dave".to_string(), String::new())]
+        );
+        assert_eq!(appointment.recurrence, None);
+        assert_eq!(
+            appointment.meeting.map(|meeting| meeting.kind),
+            Some(MeetingKind::Request)
+        );
+    }
+
+    #[test]
+    fn test_organizer_falls_back_to_sender() {
+        let mut props = meeting_props();
+        props.remove("SentRepresentingName");
+        props.remove("SentRepresentingSmtpAddress");
+        props.insert("SenderName".to_string(), string("Eve"));
+        let appointment = Appointment::create_from_props(&props, &vec![]).unwrap();
+        assert_eq!(
+            appointment.organizer,
+            Person::new("Eve".to_string(), String::new())
+        );
+    }
+
+    #[test]
+    fn test_not_an_appointment() {
+        let mut props = meeting_props();
+        props.insert("MessageClass".to_string(), string("IPM.Note"));
+        assert_eq!(Appointment::create_from_props(&props, &vec![]), None);
+        assert_eq!(
+            Appointment::create_from_props(&Properties::new(), &vec![]),
+            None
+        );
+    }
+}
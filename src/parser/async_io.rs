@@ -0,0 +1,93 @@
+use std::io::{self, SeekFrom};
+
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncSeek, AsyncSeekExt, AsyncWrite, AsyncWriteExt};
+
+use super::{
+    error::Error,
+    extract::CHUNK_SIZE,
+    options::ParserOptions,
+    outlook::{Attachment, Outlook},
+};
+
+impl Outlook {
+    // Same as from_reader for an asynchronous source, e.g.
+    // a blob fetched from object storage. The message, from
+    // the current position of the source to its end, is
+    // read without blocking the thread, then parsed from
+    // memory.
+    pub async fn from_async_reader<R: AsyncRead + AsyncSeek + Unpin>(reader: R) -> Result<Self, Error> {
+        Self::from_async_reader_with(reader, &ParserOptions::default()).await
+    }
+
+    pub async fn from_async_reader_with<R: AsyncRead + AsyncSeek + Unpin>(
+        mut reader: R,
+        options: &ParserOptions,
+    ) -> Result<Self, Error> {
+        let start = reader.stream_position().await?;
+        let end = reader.seek(SeekFrom::End(0)).await?;
+        reader.seek(SeekFrom::Start(start)).await?;
+        let mut bytes = Vec::with_capacity(end.saturating_sub(start) as usize);
+        reader.read_to_end(&mut bytes).await?;
+        Self::from_slice_with(&bytes, options)
+    }
+}
+
+impl Attachment {
+    // Writes the data of the attachment to an asynchronous
+    // writer, e.g. an upload to object storage, decoding it
+    // chunk by chunk. Returns the number of bytes written.
+    pub async fn write_to_async<W: AsyncWrite + Unpin>(&self, writer: &mut W) -> Result<u64, Error> {
+        let mut written = 0;
+        for chunk in self.payload.as_bytes().chunks(CHUNK_SIZE) {
+            let bytes = hex::decode(chunk).map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))?;
+            writer.write_all(&bytes).await?;
+            written += bytes.len() as u64;
+        }
+        writer.flush().await?;
+        Ok(written)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::{Cursor, SeekFrom};
+
+    use tokio::{io::AsyncSeekExt, runtime::Builder};
+
+    use super::super::{options::ParserOptions, outlook::Outlook};
+
+    #[test]
+    fn test_from_async_reader() {
+        let runtime = Builder::new_current_thread().build().unwrap();
+        runtime.block_on(async {
+            let path = "data/attachment.msg";
+            let file = tokio::fs::File::open(path).await.unwrap();
+            let outlook = Outlook::from_async_reader(file).await.unwrap();
+            assert_eq!(outlook.to_json().unwrap(), Outlook::from_path(path).unwrap().to_json().unwrap());
+
+            // The message follows other data in the source.
+            let bytes = std::fs::read(path).unwrap();
+            let mut source = Cursor::new([&[0u8; 100][..], &bytes].concat());
+            source.seek(SeekFrom::Start(100)).await.unwrap();
+            let options = ParserOptions::new().attachments(false);
+            let outlook = Outlook::from_async_reader_with(source, &options).await.unwrap();
+            assert!(outlook.attachments.iter().all(|attachment| attachment.payload.is_empty()));
+
+            let bytes = std::fs::read("data/bad_outlook.msg").unwrap();
+            assert!(Outlook::from_async_reader(Cursor::new(bytes)).await.is_err());
+        });
+    }
+
+    #[test]
+    fn test_write_to_async() {
+        let runtime = Builder::new_current_thread().build().unwrap();
+        runtime.block_on(async {
+            let outlook = Outlook::from_path("data/attachment.msg").unwrap();
+            let attachment = &outlook.attachments[0];
+            let mut written = vec![];
+            let len = attachment.write_to_async(&mut written).await.unwrap();
+            assert_eq!(len, written.len() as u64);
+            assert_eq!(written, hex::decode(&attachment.payload).unwrap());
+        });
+    }
+}
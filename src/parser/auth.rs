@@ -0,0 +1,408 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use super::headers::{strip_comments, Headers};
+
+// Verdict is the result of an authentication method.
+// Refer to RFC 8601 2.7 and RFC 7208 2.6
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub enum Verdict {
+    Pass,
+    Fail,
+    SoftFail,
+    Neutral,
+    None,
+    TempError,
+    PermError,
+    Policy,
+    Unknown,
+}
+
+impl Verdict {
+    pub fn from_value(value: &str) -> Self {
+        match value.to_ascii_lowercase().as_str() {
+            "pass" => Verdict::Pass,
+            // "hardfail" is still sent by some receivers.
+            "fail" | "hardfail" => Verdict::Fail,
+            "softfail" => Verdict::SoftFail,
+            "neutral" => Verdict::Neutral,
+            "none" => Verdict::None,
+            "temperror" => Verdict::TempError,
+            "permerror" => Verdict::PermError,
+            "policy" => Verdict::Policy,
+            _ => Verdict::Unknown,
+        }
+    }
+}
+
+// AuthResult is the result of one method of an
+// Authentication-Results field, e.g. "dkim=pass header.d=example.com".
+// Refer to RFC 8601 2.2
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct AuthResult {
+    pub method: String,                    // e.g. "spf", "dkim", "dmarc"
+    pub verdict: Verdict,                  // See Verdict enum
+    pub properties: Vec<(String, String)>, // e.g. ("smtp.mailfrom", "example.com")
+}
+
+impl AuthResult {
+    // Properties naming the domain a method authenticated.
+    const DOMAIN_PROPERTIES: [&'static str; 5] = [
+        "smtp.mailfrom",
+        "smtp.helo",
+        "header.d",
+        "header.i",
+        "header.from",
+    ];
+
+    pub fn property(&self, name: &str) -> Option<&str> {
+        self.properties
+            .iter()
+            .find(|(key, _)| key.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.as_str())
+    }
+
+    // Domains named by the properties, addresses being
+    // reduced to their domain.
+    pub fn domains(&self) -> Vec<String> {
+        Self::DOMAIN_PROPERTIES
+            .iter()
+            .filter_map(|name| self.property(name))
+            .map(|value| {
+                let domain = value.rsplit('@').next().unwrap_or(value);
+                domain.to_ascii_lowercase()
+            })
+            .filter(|domain| !domain.is_empty())
+            .collect()
+    }
+
+    // Parses the results of an Authentication-Results field.
+    // The authserv-id and any segment without a result are skipped,
+    // which also covers receivers that repeat their id in between.
+    pub fn parse_all(value: &str) -> Vec<Self> {
+        strip_comments(value)
+            .split(';')
+            .filter_map(|segment| {
+                let mut words = segment.split_whitespace();
+                let (method, result) = words.next()?.split_once('=')?;
+                // The method may carry a version, e.g. "dkim/1".
+                let method = method.split('/').next().unwrap_or_default();
+                let properties = words
+                    .filter_map(|word| word.split_once('='))
+                    .map(|(key, value)| (key.to_string(), value.trim_matches('"').to_string()))
+                    .collect();
+                Some(Self {
+                    method: method.to_ascii_lowercase(),
+                    verdict: Verdict::from_value(result),
+                    properties,
+                })
+            })
+            .collect()
+    }
+}
+
+// ReceivedSpf is the SPF check a receiver recorded.
+// Refer to RFC 7208 9.1
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct ReceivedSpf {
+    pub verdict: Verdict,              // See Verdict enum
+    pub client_ip: Option<String>,     // "client-ip"
+    pub envelope_from: Option<String>, // "envelope-from"
+    pub helo: Option<String>,          // "helo"
+    pub receiver: Option<String>,      // "receiver"
+}
+
+impl ReceivedSpf {
+    pub fn parse(value: &str) -> Self {
+        let value = strip_comments(value);
+        let mut words = value.split(|c: char| c == ';' || c.is_whitespace());
+        let verdict = Verdict::from_value(words.next().unwrap_or_default());
+        let pairs: Vec<(&str, &str)> = words.filter_map(|word| word.split_once('=')).collect();
+        let get = |name: &str| {
+            pairs
+                .iter()
+                .find(|(key, _)| key.eq_ignore_ascii_case(name))
+                .map(|(_, value)| value.trim_matches('"').to_string())
+        };
+        Self {
+            verdict,
+            client_ip: get("client-ip"),
+            envelope_from: get("envelope-from"),
+            helo: get("helo"),
+            receiver: get("receiver"),
+        }
+    }
+}
+
+// DkimSignature is the decomposition of a DKIM-Signature field.
+// Refer to RFC 6376 3.5
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct DkimSignature {
+    pub version: Option<String>,           // "v"
+    pub algorithm: Option<String>,         // "a", e.g. "rsa-sha256"
+    pub canonicalization: Option<String>,  // "c", e.g. "relaxed/relaxed"
+    pub domain: Option<String>,            // "d", signing domain
+    pub selector: Option<String>,          // "s"
+    pub identity: Option<String>,          // "i"
+    pub body_hash: Option<String>,         // "bh"
+    pub signature: Option<String>,         // "b"
+    pub signed_headers: Vec<String>,       // "h"
+    pub timestamp: Option<DateTime<Utc>>,  // "t"
+    pub expiration: Option<DateTime<Utc>>, // "x"
+}
+
+impl DkimSignature {
+    pub fn parse(value: &str) -> Self {
+        // Whitespace within a tag is folding and is ignored.
+        let tags: Vec<(String, String)> = value
+            .split(';')
+            .filter_map(|tag| tag.split_once('='))
+            .map(|(name, value)| {
+                (
+                    name.trim().to_ascii_lowercase(),
+                    value.split_whitespace().collect::<String>(),
+                )
+            })
+            .collect();
+        let get = |name: &str| {
+            tags.iter()
+                .find(|(key, _)| key == name)
+                .map(|(_, value)| value.to_string())
+        };
+        let time = |name: &str| {
+            get(name)
+                .and_then(|value| value.parse::<i64>().ok())
+                .and_then(|secs| DateTime::from_timestamp(secs, 0))
+        };
+        Self {
+            version: get("v"),
+            algorithm: get("a"),
+            canonicalization: get("c"),
+            domain: get("d").map(|domain| domain.to_ascii_lowercase()),
+            selector: get("s"),
+            identity: get("i"),
+            body_hash: get("bh"),
+            signature: get("b"),
+            signed_headers: get("h")
+                .map(|value| {
+                    value
+                        .split(':')
+                        .filter(|name| !name.is_empty())
+                        .map(String::from)
+                        .collect()
+                })
+                .unwrap_or_default(),
+            timestamp: time("t"),
+            expiration: time("x"),
+        }
+    }
+}
+
+// Authentication gathers what receivers recorded
+// about the authentication of the message.
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct Authentication {
+    pub results: Vec<AuthResult>,            // "Authentication-Results"
+    pub received_spf: Vec<ReceivedSpf>,      // "Received-SPF"
+    pub dkim_signatures: Vec<DkimSignature>, // "DKIM-Signature"
+}
+
+impl Authentication {
+    pub fn create_from_headers(headers: &Headers) -> Self {
+        Self {
+            results: headers
+                .get_all("Authentication-Results")
+                .into_iter()
+                .flat_map(AuthResult::parse_all)
+                .collect(),
+            received_spf: headers
+                .get_all("Received-SPF")
+                .into_iter()
+                .map(ReceivedSpf::parse)
+                .collect(),
+            dkim_signatures: headers
+                .get_all("DKIM-Signature")
+                .into_iter()
+                .map(DkimSignature::parse)
+                .collect(),
+        }
+    }
+
+    // Verdict of the first result of the method, the
+    // topmost field being the one of the last receiver.
+    pub fn verdict(&self, method: &str) -> Option<Verdict> {
+        self.results
+            .iter()
+            .find(|result| result.method.eq_ignore_ascii_case(method))
+            .map(|result| result.verdict)
+    }
+
+    // Domains that signed the message, whether or
+    // not their signature was verified.
+    pub fn signing_domains(&self) -> Vec<&str> {
+        self.dkim_signatures
+            .iter()
+            .filter_map(|signature| signature.domain.as_deref())
+            .collect()
+    }
+
+    // Domains of the methods that passed, without duplicates.
+    pub fn authenticated_domains(&self) -> Vec<String> {
+        let mut domains: Vec<String> = vec![];
+        for domain in self
+            .results
+            .iter()
+            .filter(|result| result.verdict == Verdict::Pass)
+            .flat_map(AuthResult::domains)
+        {
+            if !domains.contains(&domain) {
+                domains.push(domain);
+            }
+        }
+        domains
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        super::{headers::Headers, outlook::Outlook},
+        AuthResult, Authentication, DkimSignature, ReceivedSpf, Verdict,
+    };
+    use chrono::{TimeZone as _, Utc};
+
+    #[test]
+    fn test_parse_auth_results() {
+        let results = AuthResult::parse_all(
+            "mx.example.org 1; spf=softfail (not permitted) smtp.mailfrom=jane@Example.com; \
+             dkim/1=fail header.d=example.com header.s=\"sel\"; arc=none",
+        );
+        assert_eq!(
+            results,
+            vec![
+                AuthResult {
+                    method: "spf".to_string(),
+                    verdict: Verdict::SoftFail,
+                    properties: vec![("smtp.mailfrom".to_string(), "jane@Example.com".to_string())],
+                },
+                AuthResult {
+                    method: "dkim".to_string(),
+                    verdict: Verdict::Fail,
+                    properties: vec![
+                        ("header.d".to_string(), "example.com".to_string()),
+                        ("header.s".to_string(), "sel".to_string()),
+                    ],
+                },
+                AuthResult {
+                    method: "arc".to_string(),
+                    verdict: Verdict::None,
+                    properties: vec![],
+                },
+            ]
+        );
+        assert_eq!(results[0].domains(), vec!["example.com".to_string()]);
+        assert!(AuthResult::parse_all("mx.example.org; none").is_empty());
+    }
+
+    #[test]
+    fn test_parse_received_spf() {
+        assert_eq!(
+            ReceivedSpf::parse(
+                "fail (example.org: domain of a@example.com does not designate 192.0.2.1) \
+                 client-ip=192.0.2.1; envelope-from=\"a@example.com\"; helo=mail.example.com;"
+            ),
+            ReceivedSpf {
+                verdict: Verdict::Fail,
+                client_ip: Some("192.0.2.1".to_string()),
+                envelope_from: Some("a@example.com".to_string()),
+                helo: Some("mail.example.com".to_string()),
+                receiver: None,
+            }
+        );
+        assert_eq!(ReceivedSpf::parse("").verdict, Verdict::Unknown);
+    }
+
+    #[test]
+    fn test_parse_dkim_signature() {
+        let signature = DkimSignature::parse(
+            "v=1; a=rsa-sha256; d=Example.com; s=sel;\r\n\t\
+             c=relaxed/simple; q=dns/txt; i=jane@example.com;\r\n\t\
+             t=1117574938; x=1118006938; h=from:to:\r\n\t subject:date;\r\n\t\
+             bh=MTIzNDU2Nzg5MDEy\r\n\t MzQ1Njc4OTAxMjM0NTY3ODkwMTI=;\r\n\t\
+             b=dzdVyOfAKCdLXdJOc9G2q8LoXSlEniSbav+yuU4zGeeruD00lszZVoG4ZHRNiYzR",
+        );
+        assert_eq!(
+            signature,
+            DkimSignature {
+                version: Some("1".to_string()),
+                algorithm: Some("rsa-sha256".to_string()),
+                canonicalization: Some("relaxed/simple".to_string()),
+                domain: Some("example.com".to_string()),
+                selector: Some("sel".to_string()),
+                identity: Some("jane@example.com".to_string()),
+                body_hash: Some("MTIzNDU2Nzg5MDEyMzQ1Njc4OTAxMjM0NTY3ODkwMTI=".to_string()),
+                signature: Some(
+                    "dzdVyOfAKCdLXdJOc9G2q8LoXSlEniSbav+yuU4zGeeruD00lszZVoG4ZHRNiYzR".to_string()
+                ),
+                signed_headers: vec![
+                    "from".to_string(),
+                    "to".to_string(),
+                    "subject".to_string(),
+                    "date".to_string(),
+                ],
+                timestamp: Some(Utc.with_ymd_and_hms(2005, 5, 31, 21, 28, 58).unwrap()),
+                expiration: Some(Utc.with_ymd_and_hms(2005, 6, 5, 21, 28, 58).unwrap()),
+            }
+        );
+        assert_eq!(DkimSignature::parse("garbage").domain, None);
+    }
+
+    #[test]
+    fn test_authentication() {
+        let outlook = Outlook::from_path("data/test_email_4.msg").unwrap();
+        let authentication = &outlook.authentication;
+        assert_eq!(authentication.verdict("spf"), Some(Verdict::Pass));
+        assert_eq!(authentication.verdict("DKIM"), Some(Verdict::Pass));
+        assert_eq!(authentication.verdict("dmarc"), Some(Verdict::Pass));
+        assert_eq!(authentication.verdict("compauth"), Some(Verdict::Pass));
+        assert_eq!(authentication.verdict("arc"), None);
+        assert_eq!(
+            authentication.authenticated_domains(),
+            vec!["protonmail.com".to_string()]
+        );
+        assert_eq!(
+            authentication.received_spf,
+            vec![ReceivedSpf {
+                verdict: Verdict::Pass,
+                client_ip: Some("185.70.40.140".to_string()),
+                envelope_from: None,
+                helo: Some("mail-40140.protonmail.ch".to_string()),
+                receiver: Some("protection.outlook.com".to_string()),
+            }]
+        );
+
+        assert_eq!(authentication.signing_domains(), vec!["protonmail.com"]);
+        let signature = &authentication.dkim_signatures[0];
+        assert_eq!(signature.selector, Some("protonmail".to_string()));
+        assert_eq!(
+            signature.body_hash,
+            Some("ntVymWQvytQ2DzhImCi3ZgizPLKxPYEJempMqUH8bKo=".to_string())
+        );
+        assert_eq!(
+            signature.signed_headers,
+            vec!["Date", "To", "From", "Reply-To", "Subject", "From"]
+        );
+
+        let outlook = Outlook::from_path("data/test_email.msg").unwrap();
+        assert_eq!(outlook.authentication, Authentication::default());
+        assert_eq!(
+            Authentication::create_from_headers(&Headers::parse("")),
+            Authentication::default()
+        );
+    }
+}
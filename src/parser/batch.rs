@@ -0,0 +1,162 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    time::{Duration, Instant},
+};
+
+use rayon::prelude::*;
+
+use super::{error::Error, options::ParserOptions, outlook::Outlook};
+
+// Extension of the files parse_dir parses, compared
+// ignoring case.
+const MSG_EXTENSION: &str = "msg";
+
+// BatchStats sums up the files of a batch.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct BatchStats {
+    pub files: usize,      // Files given
+    pub parsed: usize,     // Files parsed
+    pub failed: usize,     // Files failing to open or parse
+    pub bytes: u64,        // Size of the files parsed
+    pub elapsed: Duration, // Time the whole batch took
+}
+
+// BatchResult holds the result of each file, in the
+// order the files were given, and the stats of the batch.
+#[derive(Debug)]
+pub struct BatchResult {
+    pub results: Vec<(PathBuf, Result<Outlook, Error>)>,
+    pub stats: BatchStats,
+}
+
+impl BatchResult {
+    pub fn parsed(&self) -> impl Iterator<Item = (&PathBuf, &Outlook)> {
+        self.results
+            .iter()
+            .filter_map(|(path, result)| result.as_ref().ok().map(|outlook| (path, outlook)))
+    }
+
+    pub fn failed(&self) -> impl Iterator<Item = (&PathBuf, &Error)> {
+        self.results
+            .iter()
+            .filter_map(|(path, result)| result.as_ref().err().map(|err| (path, err)))
+    }
+}
+
+// Parses the files on the threads of the rayon pool, one
+// failing leaving the others parsed.
+pub fn parse_paths<P: AsRef<Path>, I: IntoIterator<Item = P>>(paths: I) -> BatchResult {
+    parse_paths_with(paths, &ParserOptions::default())
+}
+
+pub fn parse_paths_with<P: AsRef<Path>, I: IntoIterator<Item = P>>(
+    paths: I,
+    options: &ParserOptions,
+) -> BatchResult {
+    let start = Instant::now();
+    let paths: Vec<PathBuf> = paths.into_iter().map(|path| path.as_ref().to_path_buf()).collect();
+    let parsed: Vec<(PathBuf, Result<Outlook, Error>, u64)> = paths
+        .into_par_iter()
+        .map(|path| {
+            let result = Outlook::from_path_with(&path, options);
+            let bytes = match &result {
+                Ok(_) => fs::metadata(&path).map_or(0, |metadata| metadata.len()),
+                Err(_) => 0,
+            };
+            (path, result, bytes)
+        })
+        .collect();
+
+    let mut stats = BatchStats {
+        files: parsed.len(),
+        ..BatchStats::default()
+    };
+    let results = parsed
+        .into_iter()
+        .map(|(path, result, bytes)| {
+            if result.is_ok() {
+                stats.parsed += 1;
+                stats.bytes += bytes;
+            } else {
+                stats.failed += 1;
+            }
+            (path, result)
+        })
+        .collect();
+    stats.elapsed = start.elapsed();
+    BatchResult { results, stats }
+}
+
+// Parses the .msg files of the directory and of its
+// subdirectories, sorted by path. Fails only when a
+// directory cannot be read.
+pub fn parse_dir<P: AsRef<Path>>(path: P) -> Result<BatchResult, Error> {
+    parse_dir_with(path, &ParserOptions::default())
+}
+
+pub fn parse_dir_with<P: AsRef<Path>>(path: P, options: &ParserOptions) -> Result<BatchResult, Error> {
+    let mut paths = vec![];
+    msg_files(path.as_ref(), &mut paths)?;
+    paths.sort();
+    Ok(parse_paths_with(paths, options))
+}
+
+fn msg_files(dir: &Path, paths: &mut Vec<PathBuf>) -> Result<(), Error> {
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.is_dir() {
+            msg_files(&path, paths)?;
+        } else if path
+            .extension()
+            .is_some_and(|extension| extension.eq_ignore_ascii_case(MSG_EXTENSION))
+        {
+            paths.push(path);
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{fs, path::PathBuf};
+
+    use super::{super::error::Error, parse_dir, parse_paths};
+
+    #[test]
+    fn test_parse_paths() {
+        let paths = ["data/attachment.msg", "data/bad_outlook.msg", "data/missing.msg", "data/unicode.msg"];
+        let batch = parse_paths(paths);
+        assert_eq!(batch.stats.files, 4);
+        assert_eq!(batch.stats.parsed, 2);
+        assert_eq!(batch.stats.failed, 2);
+        let sizes = fs::metadata(paths[0]).unwrap().len() + fs::metadata(paths[3]).unwrap().len();
+        assert_eq!(batch.stats.bytes, sizes);
+
+        // In the order given.
+        let order: Vec<PathBuf> = batch.results.iter().map(|(path, _)| path.clone()).collect();
+        assert_eq!(order, paths.iter().map(PathBuf::from).collect::<Vec<PathBuf>>());
+        assert_eq!(batch.parsed().count(), 2);
+        let (path, err) = batch.failed().last().unwrap();
+        assert_eq!(path, &PathBuf::from("data/missing.msg"));
+        assert!(matches!(err.root(), Error::Io { .. }));
+    }
+
+    #[test]
+    fn test_parse_dir() {
+        let dir = std::env::temp_dir().join(format!("msg-batch-{}", std::process::id()));
+        fs::create_dir_all(dir.join("nested")).unwrap();
+        fs::copy("data/attachment.msg", dir.join("a.msg")).unwrap();
+        fs::copy("data/test_email.msg", dir.join("nested").join("B.MSG")).unwrap();
+        fs::write(dir.join("notes.txt"), "not a message").unwrap();
+        let batch = parse_dir(&dir).unwrap();
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(batch.stats.files, 2);
+        assert_eq!(batch.stats.parsed, 2);
+        assert_eq!(batch.results[0].0, dir.join("a.msg"));
+        assert_eq!(batch.results[1].0, dir.join("nested").join("B.MSG"));
+
+        assert!(parse_dir(&dir).is_err());
+    }
+}
@@ -0,0 +1,165 @@
+use super::error::DataTypeError;
+
+// BlobReader walks over a binary property value
+// and reads little-endian primitives out of it.
+// Every read fails with MalformedBlob when the
+// remaining buffer is too short.
+pub struct BlobReader<'a> {
+    buff: &'a [u8],
+    offset: usize,
+}
+
+impl<'a> BlobReader<'a> {
+    pub fn new(buff: &'a [u8]) -> Self {
+        Self { buff, offset: 0 }
+    }
+
+    pub fn offset(&self) -> usize {
+        self.offset
+    }
+
+    pub fn remaining(&self) -> usize {
+        self.buff.len() - self.offset
+    }
+
+    pub fn read_bytes(&mut self, len: usize) -> Result<&'a [u8], DataTypeError> {
+        if self.remaining() < len {
+            return Err(DataTypeError::MalformedBlob(format!(
+                "expected {} bytes at offset {}, {} left",
+                len,
+                self.offset,
+                self.remaining()
+            )));
+        }
+        let bytes = &self.buff[self.offset..self.offset + len];
+        self.offset += len;
+        Ok(bytes)
+    }
+
+    pub fn skip(&mut self, len: usize) -> Result<(), DataTypeError> {
+        self.read_bytes(len).map(|_| ())
+    }
+
+    pub fn read_u8(&mut self) -> Result<u8, DataTypeError> {
+        Ok(self.read_bytes(1)?[0])
+    }
+
+    pub fn read_u16(&mut self) -> Result<u16, DataTypeError> {
+        let bytes = self.read_bytes(2)?;
+        Ok(u16::from_le_bytes([bytes[0], bytes[1]]))
+    }
+
+    pub fn read_u32(&mut self) -> Result<u32, DataTypeError> {
+        let bytes = self.read_bytes(4)?;
+        Ok(u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+    }
+
+    pub fn read_i32(&mut self) -> Result<i32, DataTypeError> {
+        Ok(self.read_u32()? as i32)
+    }
+
+    // Reads a GUID stored as little-endian Data1, Data2, Data3
+    // followed by the 8 bytes of Data4, and formats it the
+    // way property sets are listed in MS-OXPROPS.
+    pub fn read_guid(&mut self) -> Result<String, DataTypeError> {
+        let bytes = self.read_bytes(16)?;
+        Ok(format!(
+            "{:08X}-{:04X}-{:04X}-{}-{}",
+            u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]),
+            u16::from_le_bytes([bytes[4], bytes[5]]),
+            u16::from_le_bytes([bytes[6], bytes[7]]),
+            hex::encode_upper(&bytes[8..10]),
+            hex::encode_upper(&bytes[10..16])
+        ))
+    }
+
+    // Reads `len` UTF-16LE code units.
+    pub fn read_utf16(&mut self, len: usize) -> Result<String, DataTypeError> {
+        let bytes = self.read_bytes(len * 2)?;
+        let units = bytes
+            .chunks(2)
+            .map(|duo| u16::from_le_bytes([duo[0], duo[1]]))
+            .collect::<Vec<u16>>();
+        Ok(String::from_utf16(&units)?)
+    }
+
+    // Reads UTF-16LE code units up to and including
+    // a null terminator.
+    pub fn read_utf16_nul(&mut self) -> Result<String, DataTypeError> {
+        let mut units = vec![];
+        loop {
+            match self.read_u16()? {
+                0 => break,
+                unit => units.push(unit),
+            }
+        }
+        Ok(String::from_utf16(&units)?)
+    }
+
+    // Reads 8-bit characters up to and including
+    // a null terminator.
+    pub fn read_ascii_nul(&mut self) -> Result<String, DataTypeError> {
+        let mut chars = String::new();
+        loop {
+            match self.read_u8()? {
+                0 => break,
+                c => chars.push(c as char),
+            }
+        }
+        Ok(chars)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::BlobReader;
+
+    #[test]
+    fn test_read_primitives() {
+        let buff = vec![
+            0x01, 0x02, 0x00, 0x04, 0x03, 0x02, 0x01, 0xFF, 0xFF, 0xFF, 0xFF, 0x48, 0x00, 0x69, 0x00,
+        ];
+        let mut reader = BlobReader::new(&buff);
+        assert_eq!(reader.read_u8().unwrap(), 0x01);
+        assert_eq!(reader.read_u16().unwrap(), 0x0002);
+        assert_eq!(reader.read_u32().unwrap(), 0x01020304);
+        assert_eq!(reader.read_i32().unwrap(), -1);
+        assert_eq!(reader.read_utf16(2).unwrap(), "Hi".to_string());
+        assert_eq!(reader.remaining(), 0);
+    }
+
+    #[test]
+    fn test_read_nul_terminated() {
+        let buff = vec![0x48, 0x00, 0x69, 0x00, 0x00, 0x00, 0x48, 0x69, 0x00, 0x48];
+        let mut reader = BlobReader::new(&buff);
+        assert_eq!(reader.read_utf16_nul().unwrap(), "Hi".to_string());
+        assert_eq!(reader.read_ascii_nul().unwrap(), "Hi".to_string());
+        assert!(reader.read_ascii_nul().is_err());
+    }
+
+    #[test]
+    fn test_read_guid() {
+        let buff = vec![
+            0x02, 0x20, 0x06, 0x00, 0x00, 0x00, 0x00, 0x00, 0xC0, 0x00, 0x00, 0x00, 0x00, 0x00,
+            0x00, 0x46,
+        ];
+        let mut reader = BlobReader::new(&buff);
+        assert_eq!(
+            reader.read_guid().unwrap(),
+            "00062002-0000-0000-C000-000000000046".to_string()
+        );
+    }
+
+    #[test]
+    fn test_read_past_end() {
+        let buff = vec![0x01, 0x02];
+        let mut reader = BlobReader::new(&buff);
+        let err = reader.read_u32().unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "DataTypeError: Malformed blob: expected 4 bytes at offset 0, 2 left"
+        );
+        // A failed read does not move the cursor.
+        assert_eq!(reader.offset(), 0);
+    }
+}
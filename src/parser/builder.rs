@@ -0,0 +1,428 @@
+use std::path::Path;
+
+use chrono::{DateTime, Utc};
+
+use super::{
+    decode::DataType,
+    error::Error,
+    flags::MessageFlags,
+    importance::{Importance, Sensitivity},
+    outlook::Outlook,
+    recipient::RecipientType,
+    storage::Properties,
+    verbose::MessageProperties,
+};
+
+// Attachment flag of attachments shown in the HTML body.
+// Refer to MS-OXCMSG 2.2.2.18
+pub(super) const ATT_MHTML_REF: i32 = 0x4;
+
+// Attach method of attachments kept at their path name.
+// Refer to MS-OXCMSG 2.2.2.9
+const ATTACH_BY_REFERENCE: i32 = 2;
+
+// Code page of the HTML body, which is kept as UTF-8.
+const CP_UTF8: i32 = 65001;
+
+pub(super) fn string(value: &str) -> DataType {
+    DataType::PtypString(value.to_string())
+}
+
+// Sets the fields of a mailbox, e.g. "SenderName" for
+// the "Sender" prefix.
+pub(super) fn mailbox_props(props: &mut Properties, prefix: &str, name: &str, address: &str) {
+    let name = if name.is_empty() { address } else { name };
+    props.insert(format!("{}Name", prefix), string(name));
+    props.insert(format!("{}EmailAddress", prefix), string(address));
+    props.insert(format!("{}SmtpAddress", prefix), string(address));
+    props.insert(format!("{}AddressType", prefix), string("SMTP"));
+}
+
+// Properties of a recipient with an SMTP address.
+// Refer to MS-OXCMSG 2.2.1.3
+pub(super) fn recipient_props(
+    row: usize,
+    recipient_type: RecipientType,
+    name: &str,
+    address: &str,
+) -> Properties {
+    let mut props = Properties::new();
+    let name = if name.is_empty() { address } else { name };
+    props.insert("Rowid".to_string(), DataType::PtypInteger32(row as i32));
+    props.insert(
+        "RecipientType".to_string(),
+        DataType::PtypInteger32(recipient_type.to_value() as i32),
+    );
+    props.insert("ObjectType".to_string(), DataType::PtypInteger32(6));
+    props.insert("DisplayType".to_string(), DataType::PtypInteger32(0));
+    props.insert("DisplayName".to_string(), string(name));
+    props.insert("EmailAddress".to_string(), string(address));
+    props.insert("SmtpAddress".to_string(), string(address));
+    props.insert("AddressType".to_string(), string("SMTP"));
+    props
+}
+
+// Properties of an attachment by value.
+// Refer to MS-OXCMSG 2.2.2
+pub(super) fn attachment_props(index: usize, name: &str, mime_tag: &str, data: Vec<u8>) -> Properties {
+    let mut props = Properties::new();
+    let extension = name.rfind('.').map_or("", |dot| &name[dot..]);
+    props.insert("AttachNumber".to_string(), DataType::PtypInteger32(index as i32));
+    props.insert("AttachMethod".to_string(), DataType::PtypInteger32(1));
+    props.insert("RenderingPosition".to_string(), DataType::PtypInteger32(-1));
+    props.insert("AttachSize".to_string(), DataType::PtypInteger32(data.len() as i32));
+    props.insert("AttachLongFilename".to_string(), string(name));
+    props.insert("AttachFilename".to_string(), string(name));
+    props.insert("DisplayName".to_string(), string(name));
+    props.insert("AttachExtension".to_string(), string(extension));
+    props.insert("AttachMimeTag".to_string(), string(mime_tag));
+    props.insert("AttachDataObject".to_string(), DataType::PtypBinary(data));
+    props
+}
+
+// Marks an attachment as shown in the HTML body,
+// where "cid:<content_id>" refers to it.
+pub(super) fn set_inline(props: &mut Properties, content_id: &str) {
+    props.insert("AttachContentId".to_string(), string(content_id));
+    props.insert("AttachFlags".to_string(), DataType::PtypInteger32(ATT_MHTML_REF));
+    props.insert("AttachmentHidden".to_string(), DataType::PtypBoolean(true));
+}
+
+// Sets DisplayTo, DisplayCc and DisplayBcc to the
+// names of the recipients of each type.
+pub(super) fn set_display_fields(props: &mut MessageProperties) {
+    for (field, recipient_type) in [
+        ("DisplayTo", RecipientType::To),
+        ("DisplayCc", RecipientType::Cc),
+        ("DisplayBcc", RecipientType::Bcc),
+    ] {
+        let names: Vec<&str> = props
+            .recipients
+            .iter()
+            .filter(|recipient| {
+                recipient
+                    .get("RecipientType")
+                    .and_then(DataType::as_i64)
+                    .map(RecipientType::from_value)
+                    == Some(recipient_type)
+            })
+            .filter_map(|recipient| recipient.get("DisplayName").and_then(DataType::as_str))
+            .collect();
+        let names = names.join("; ");
+        props.root.insert(field.to_string(), string(&names));
+    }
+}
+
+// OutlookBuilder puts together a new message from scratch,
+// e.g.
+//
+//   OutlookBuilder::new()
+//       .subject("Hello")
+//       .sender("Alice", "alice@example.com")
+//       .to("Bob", "bob@example.com")
+//       .body("Hi Bob")
+//       .write_to_path("hello.msg")?;
+//
+// The message is marked as read, or as a draft with
+// draft(true). Setting a field twice keeps the last value.
+#[derive(Debug, Clone)]
+pub struct OutlookBuilder {
+    props: MessageProperties,
+    flags: MessageFlags,
+}
+
+impl Default for OutlookBuilder {
+    fn default() -> Self {
+        Self {
+            props: MessageProperties::default(),
+            flags: MessageFlags {
+                read: true,
+                ..MessageFlags::default()
+            },
+        }
+    }
+}
+
+impl OutlookBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn set(mut self, key: &str, value: DataType) -> Self {
+        self.props.root.insert(key.to_string(), value);
+        self
+    }
+
+    // Defaults to "IPM.Note".
+    pub fn message_class(self, message_class: &str) -> Self {
+        self.set("MessageClass", string(message_class))
+    }
+
+    pub fn subject(self, subject: &str) -> Self {
+        self.set("Subject", string(subject))
+            .set("ConversationTopic", string(subject))
+    }
+
+    pub fn body(self, body: &str) -> Self {
+        self.set("Body", string(body))
+    }
+
+    pub fn html(self, html: &str) -> Self {
+        self.set("Html", DataType::PtypBinary(html.as_bytes().to_vec()))
+            .set("InternetCodepage", DataType::PtypInteger32(CP_UTF8))
+    }
+
+    // The sender also stands for the author unless
+    // on_behalf_of() names another.
+    pub fn sender(mut self, name: &str, email: &str) -> Self {
+        mailbox_props(&mut self.props.root, "Sender", name, email);
+        self
+    }
+
+    pub fn on_behalf_of(mut self, name: &str, email: &str) -> Self {
+        mailbox_props(&mut self.props.root, "SentRepresenting", name, email);
+        self
+    }
+
+    pub fn recipient(mut self, recipient_type: RecipientType, name: &str, email: &str) -> Self {
+        let row = self.props.recipients.len();
+        self.props
+            .recipients
+            .push(recipient_props(row, recipient_type, name, email));
+        self
+    }
+
+    pub fn to(self, name: &str, email: &str) -> Self {
+        self.recipient(RecipientType::To, name, email)
+    }
+
+    pub fn cc(self, name: &str, email: &str) -> Self {
+        self.recipient(RecipientType::Cc, name, email)
+    }
+
+    pub fn bcc(self, name: &str, email: &str) -> Self {
+        self.recipient(RecipientType::Bcc, name, email)
+    }
+
+    // Date the message was sent and delivered.
+    pub fn date(self, date: DateTime<Utc>) -> Self {
+        self.set("ClientSubmitTime", DataType::PtypTime(date))
+            .set("MessageDeliveryTime", DataType::PtypTime(date))
+    }
+
+    // Ids are kept without their angle brackets, as
+    // Outlook.message_id gives them back.
+    pub fn message_id(self, message_id: &str) -> Self {
+        self.set("InternetMessageId", string(bare_id(message_id)))
+    }
+
+    pub fn in_reply_to(self, message_id: &str) -> Self {
+        self.set("InReplyToId", string(bare_id(message_id)))
+    }
+
+    pub fn importance(self, importance: Importance) -> Self {
+        self.set("Importance", DataType::PtypInteger32(importance.to_value() as i32))
+    }
+
+    pub fn sensitivity(self, sensitivity: Sensitivity) -> Self {
+        self.set("Sensitivity", DataType::PtypInteger32(sensitivity.to_value() as i32))
+    }
+
+    // Header of the message as received, with lines
+    // ending in CRLF.
+    pub fn transport_headers(self, headers: &str) -> Self {
+        self.set("TransportMessageHeaders", string(headers))
+    }
+
+    pub fn draft(mut self, draft: bool) -> Self {
+        self.flags.unsent = draft;
+        self
+    }
+
+    pub fn attachment(mut self, name: &str, mime_tag: &str, data: Vec<u8>) -> Self {
+        let index = self.props.attachments.len();
+        self.props
+            .attachments
+            .push(attachment_props(index, name, mime_tag, data));
+        self
+    }
+
+    // An attachment by reference, whose data is kept at
+    // pathname, e.g. on a file share.
+    pub fn attachment_by_reference(mut self, name: &str, mime_tag: &str, pathname: &str) -> Self {
+        let index = self.props.attachments.len();
+        let mut props = attachment_props(index, name, mime_tag, vec![]);
+        props.remove("AttachDataObject");
+        props.insert("AttachMethod".to_string(), DataType::PtypInteger32(ATTACH_BY_REFERENCE));
+        props.insert("AttachLongPathname".to_string(), string(pathname));
+        props.insert("AttachPathname".to_string(), string(pathname));
+        self.props.attachments.push(props);
+        self
+    }
+
+    // An attachment the HTML body shows through a
+    // "cid:" URL rather than listing it.
+    pub fn inline_attachment(mut self, name: &str, mime_tag: &str, content_id: &str, data: Vec<u8>) -> Self {
+        let index = self.props.attachments.len();
+        let mut props = attachment_props(index, name, mime_tag, data);
+        set_inline(&mut props, bare_id(content_id));
+        self.props.attachments.push(props);
+        self
+    }
+
+    // Properties of the message, with the fields derived
+    // from the others filled in.
+    pub fn properties(&self) -> MessageProperties {
+        let mut props = self.props.clone();
+        let root = &mut props.root;
+        root.entry("MessageClass".to_string())
+            .or_insert_with(|| string("IPM.Note"));
+        for field in ["Name", "EmailAddress", "SmtpAddress", "AddressType"] {
+            if let Some(value) = root.get(&format!("Sender{}", field)).cloned() {
+                root.entry(format!("SentRepresenting{}", field)).or_insert(value);
+            }
+        }
+        let flags = MessageFlags {
+            has_attachments: !props.attachments.is_empty(),
+            ..self.flags
+        };
+        root.insert("MessageFlags".to_string(), DataType::PtypInteger32(flags.to_value() as i32));
+        set_display_fields(&mut props);
+        props
+    }
+
+    // Bytes of the MSG file.
+    pub fn to_msg(&self) -> Vec<u8> {
+        self.properties().to_msg()
+    }
+
+    // The message as read back from its MSG file.
+    pub fn build(&self) -> Result<Outlook, Error> {
+        Outlook::from_slice(&self.to_msg())
+    }
+
+    pub fn write_to_path<P: AsRef<Path>>(&self, path: P) -> Result<(), Error> {
+        std::fs::write(path, self.to_msg())?;
+        Ok(())
+    }
+}
+
+fn bare_id(id: &str) -> &str {
+    id.trim().trim_start_matches('<').trim_end_matches('>')
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{TimeZone as _, Utc};
+
+    use super::{
+        super::{
+            importance::{Importance, Sensitivity},
+            outlook::Outlook,
+            recipient::RecipientType,
+        },
+        OutlookBuilder,
+    };
+
+    fn builder() -> OutlookBuilder {
+        OutlookBuilder::new()
+            .subject("Quarterly report")
+            .sender("Alice", "alice@example.com")
+            .to("Bob", "bob@example.com")
+            .to("", "carol@example.com")
+            .cc("Dave", "dave@example.com")
+            .bcc("Eve", "eve@example.com")
+            .date(Utc.with_ymd_and_hms(2024, 5, 1, 9, 30, 0).unwrap())
+            .message_id("<report@example.com>")
+            .importance(Importance::High)
+            .sensitivity(Sensitivity::Confidential)
+            .body("See attached.")
+            .html("<p>See attached.</p><img src=\"cid:logo\">")
+            .attachment("report.csv", "text/csv", b"a,b\r\n1,2\r\n".to_vec())
+            .inline_attachment("logo.png", "image/png", "<logo>", vec![0x89, b'P', b'N', b'G'])
+    }
+
+    #[test]
+    fn test_build() {
+        let outlook = builder().build().unwrap();
+        assert_eq!(outlook.subject, "Quarterly report".to_string());
+        assert_eq!(outlook.message_class, "IPM.Note".to_string());
+        assert_eq!(outlook.body, "See attached.".to_string());
+        assert_eq!(outlook.sender.name, "Alice".to_string());
+        assert_eq!(outlook.sender.email, "alice@example.com".to_string());
+        assert_eq!(outlook.sent_representing.email, "alice@example.com".to_string());
+        let to: Vec<&str> = outlook.to.iter().map(|person| person.email.as_str()).collect();
+        assert_eq!(to, ["bob@example.com", "carol@example.com"]);
+        assert_eq!(outlook.to[1].name, "carol@example.com".to_string());
+        assert_eq!(outlook.cc[0].name, "Dave".to_string());
+        assert_eq!(outlook.bcc[0].name, "Eve".to_string());
+        assert_eq!(outlook.recipients[3].recipient_type, RecipientType::Bcc);
+        assert_eq!(outlook.message_id, "report@example.com".to_string());
+        assert_eq!(outlook.importance, Importance::High);
+        assert_eq!(outlook.sensitivity, Sensitivity::Confidential);
+        assert!(outlook.flags.read);
+        assert!(outlook.flags.has_attachments);
+        assert!(!outlook.flags.is_draft());
+
+        assert_eq!(outlook.attachments.len(), 2);
+        assert_eq!(outlook.attachments[0].file_name, "report.csv".to_string());
+        assert_eq!(outlook.attachments[0].extension, ".csv".to_string());
+        assert_eq!(outlook.attachments[1].mime_tag, "image/png".to_string());
+
+        let props = builder().properties();
+        assert_eq!(
+            props.root.get("DisplayTo").and_then(|value| value.as_str()),
+            Some("Bob; carol@example.com")
+        );
+        assert_eq!(
+            props.attachments[1].get("AttachContentId").and_then(|value| value.as_str()),
+            Some("logo")
+        );
+    }
+
+    #[test]
+    fn test_draft() {
+        let outlook = OutlookBuilder::new()
+            .message_class("IPM.Note.Custom")
+            .sender("Alice", "alice@example.com")
+            .on_behalf_of("Team", "team@example.com")
+            .draft(true)
+            .build()
+            .unwrap();
+        assert_eq!(outlook.message_class, "IPM.Note.Custom".to_string());
+        assert_eq!(outlook.sender.email, "alice@example.com".to_string());
+        assert_eq!(outlook.sent_representing.email, "team@example.com".to_string());
+        assert!(outlook.flags.is_draft());
+        assert!(!outlook.flags.has_attachments);
+        assert!(outlook.to.is_empty());
+    }
+
+    #[test]
+    fn test_write_to_path() {
+        let path = std::env::temp_dir().join(format!("builder-{}.msg", std::process::id()));
+        builder().write_to_path(&path).unwrap();
+        let outlook = Outlook::from_path(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(outlook.subject, "Quarterly report".to_string());
+        assert_eq!(outlook.attachments.len(), 2);
+        assert_eq!(outlook.to_eml().matches("Content-ID: <logo>").count(), 1);
+    }
+    #[test]
+    fn test_attachment_by_reference() {
+        let path = std::env::temp_dir().join(format!("builder-reference-{}.msg", std::process::id()));
+        builder()
+            .attachment_by_reference("plan.xlsx", "", "\\\\files\\shared\\plan.xlsx")
+            .write_to_path(&path)
+            .unwrap();
+        let outlook = Outlook::from_path(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(outlook.attachments.len(), 3);
+        let reference = &outlook.attachments[2];
+        assert!(reference.by_reference);
+        assert!(reference.payload.is_empty());
+        assert_eq!(reference.file_name, "plan.xlsx");
+        assert_eq!(reference.pathname, "\\\\files\\shared\\plan.xlsx");
+        assert!(!outlook.attachments[0].by_reference);
+        assert!(outlook.attachments[0].pathname.is_empty());
+    }
+}
@@ -0,0 +1,75 @@
+use ciborium::value::{Integer, Value as CborValue};
+use serde_json::Value;
+
+use super::{error::Error, json::is_binary_field, outlook::Outlook};
+
+// Value of the JSON of the model, binary fields being
+// byte strings. Refer to RFC 8949
+fn to_cbor_value(value: Value) -> CborValue {
+    match value {
+        Value::Null => CborValue::Null,
+        Value::Bool(value) => CborValue::Bool(value),
+        Value::Number(number) => match (number.as_i64(), number.as_u64()) {
+            (Some(value), _) => CborValue::Integer(Integer::from(value)),
+            (_, Some(value)) => CborValue::Integer(Integer::from(value)),
+            _ => CborValue::Float(number.as_f64().unwrap_or_default()),
+        },
+        Value::String(string) => CborValue::Text(string),
+        Value::Array(values) => CborValue::Array(values.into_iter().map(to_cbor_value).collect()),
+        Value::Object(map) => CborValue::Map(
+            map.into_iter()
+                .map(|(key, value)| {
+                    let value = match value {
+                        Value::String(hex) if is_binary_field(&key) => match hex::decode(&hex) {
+                            Ok(bytes) => CborValue::Bytes(bytes),
+                            Err(_) => CborValue::Text(hex),
+                        },
+                        value => to_cbor_value(value),
+                    };
+                    (CborValue::Text(key), value)
+                })
+                .collect(),
+        ),
+    }
+}
+
+impl Outlook {
+    // Same fields as to_json, in CBOR.
+    pub fn to_cbor(&self) -> Result<Vec<u8>, Error> {
+        let value = to_cbor_value(serde_json::to_value(self)?);
+        let mut bytes = vec![];
+        ciborium::into_writer(&value, &mut bytes).map_err(|err| Error::EncodeError(err.to_string()))?;
+        Ok(bytes)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ciborium::value::Value as CborValue;
+
+    use super::super::outlook::Outlook;
+
+    #[test]
+    fn test_to_cbor() {
+        let outlook = Outlook::from_path("data/attachment.msg").unwrap();
+        let bytes = outlook.to_cbor().unwrap();
+        assert!(bytes.len() < outlook.to_json().unwrap().len());
+
+        let value: CborValue = ciborium::from_reader(bytes.as_slice()).unwrap();
+        let field = |value: &CborValue, key: &str| {
+            value
+                .as_map()
+                .unwrap()
+                .iter()
+                .find(|(name, _)| name.as_text() == Some(key))
+                .map(|(_, value)| value.clone())
+                .unwrap()
+        };
+        assert_eq!(field(&value, "subject"), CborValue::Text(outlook.subject.clone()));
+        let attachment = field(&value, "attachments").as_array().unwrap()[0].clone();
+        assert_eq!(
+            field(&attachment, "payload"),
+            CborValue::Bytes(hex::decode(&outlook.attachments[0].payload).unwrap())
+        );
+    }
+}
@@ -0,0 +1,98 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use chrono::{DateTime, Utc};
+
+// Clock is the source of the current time. Times are always
+// decoded and formatted in UTC or in the time zone stored in
+// the message, never in the host's local zone, so the current
+// time is the only input coming from the environment.
+pub trait Clock {
+    fn now(&self) -> DateTime<Utc>;
+}
+
+// SystemClock reads the system time.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> DateTime<Utc> {
+        let since_epoch = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default();
+        DateTime::from_timestamp(since_epoch.as_secs() as i64, since_epoch.subsec_nanos())
+            .unwrap_or_default()
+    }
+}
+
+// FixedClock always returns the same instant,
+// for reproducible output.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FixedClock(pub DateTime<Utc>);
+
+impl Clock for FixedClock {
+    fn now(&self) -> DateTime<Utc> {
+        self.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{TimeZone as _, Utc};
+
+    use super::{
+        super::{
+            decode::DataType,
+            options::ParserOptions,
+            storage::{Storages, ROOT_ID},
+            timezone::{tests::eastern_tz_struct, TimeZone},
+        },
+        Clock, FixedClock, SystemClock,
+    };
+    use crate::ole::Reader;
+
+    #[test]
+    fn test_fixed_clock() {
+        let instant = Utc.with_ymd_and_hms(2021, 6, 1, 12, 0, 0).unwrap();
+        assert_eq!(FixedClock(instant).now(), instant);
+    }
+
+    #[test]
+    fn test_system_clock() {
+        // 2020-01-01
+        assert!(SystemClock.now().timestamp() > 1_577_836_800);
+    }
+
+    // Everything time related that gets decoded or formatted,
+    // rendered to strings, the current time coming from a
+    // FixedClock.
+    fn render_times() -> Vec<String> {
+        let parser = Reader::from_path("data/unicode.msg").unwrap();
+        let mut storages = Storages::create(&parser, ROOT_ID, &ParserOptions::default());
+        storages.process_streams(&parser).unwrap();
+        let submit_time = storages
+            .root
+            .get("ClientSubmitTime")
+            .map(String::from)
+            .unwrap_or_default();
+
+        let instant = Utc.with_ymd_and_hms(2021, 7, 1, 12, 0, 0).unwrap();
+        let tz = TimeZone::from_tz_struct(&eastern_tz_struct()).unwrap();
+        vec![
+            submit_time,
+            String::from(&DataType::PtypTime(instant)),
+            tz.to_local(&instant).to_rfc3339(),
+            tz.offset_now(&FixedClock(instant)).to_string(),
+        ]
+    }
+
+    #[test]
+    fn test_render_times() {
+        let expected = vec![
+            "2013-11-18T08:26:24Z".to_string(),
+            "2021-07-01T12:00:00Z".to_string(),
+            "2021-07-01T08:00:00-04:00".to_string(),
+            "-04:00".to_string(),
+        ];
+        assert_eq!(render_times(), expected);
+    }
+}
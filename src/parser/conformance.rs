@@ -0,0 +1,142 @@
+use serde_json::{json, Value};
+use sha2::{Digest, Sha256};
+
+use super::{
+    error::Error,
+    outlook::{Outlook, Person},
+};
+
+// Vector is a bundled message along with the summary
+// its parse result is expected to have.
+#[derive(Debug, Clone, Copy)]
+pub struct Vector {
+    pub name: &'static str,
+    pub message: &'static [u8],
+    pub expected: &'static str, // JSON, see summary
+}
+
+impl Vector {
+    pub fn expected_value(&self) -> Value {
+        serde_json::from_str(self.expected).unwrap_or(Value::Null)
+    }
+
+    // Parses the message with the native API and tells
+    // whether it gives the expected summary.
+    pub fn check(&self) -> bool {
+        summary(Outlook::from_slice(self.message).as_ref()) == self.expected_value()
+    }
+}
+
+// The messages of the plain and attachments vectors are
+// small ones written by OutlookBuilder, see the recipes of
+// the tests, so that the vectors stay light to embed.
+pub fn vectors() -> Vec<Vector> {
+    vec![
+        Vector {
+            name: "plain",
+            message: include_bytes!("../../data/conformance/plain.msg"),
+            expected: include_str!("../../data/conformance/plain.json"),
+        },
+        Vector {
+            name: "attachments",
+            message: include_bytes!("../../data/conformance/attachments.msg"),
+            expected: include_str!("../../data/conformance/attachments.json"),
+        },
+        Vector {
+            name: "malformed",
+            message: include_bytes!("../../data/bad_outlook.msg"),
+            expected: include_str!("../../data/conformance/malformed.json"),
+        },
+    ]
+}
+
+fn sha256(bytes: &[u8]) -> String {
+    hex::encode(Sha256::digest(bytes))
+}
+
+fn person(person: &Person) -> Value {
+    json!({"name": person.name, "email": person.email})
+}
+
+// Summary of a parse result that bindings compare against
+// the expected one. Bodies and attachment data are
+// reduced to their SHA-256 digest, errors to their code,
+// see Error::code.
+pub fn summary(result: Result<&Outlook, &Error>) -> Value {
+    let outlook = match result {
+        Ok(outlook) => outlook,
+        Err(err) => return json!({"error_code": err.code()}),
+    };
+    let attachments = outlook
+        .attachments
+        .iter()
+        .map(|attachment| {
+            let payload = hex::decode(&attachment.payload).unwrap_or_default();
+            json!({
+                "file_name": attachment.file_name,
+                "mime_tag": attachment.mime_tag,
+                "size": payload.len(),
+                "sha256": sha256(&payload),
+            })
+        })
+        .collect::<Vec<Value>>();
+    json!({
+        "subject": outlook.subject,
+        "sender": person(&outlook.sender),
+        "to": outlook.to.iter().map(person).collect::<Vec<Value>>(),
+        "cc": outlook.cc.iter().map(person).collect::<Vec<Value>>(),
+        "body_sha256": sha256(outlook.body.as_bytes()),
+        "attachments": attachments,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{super::builder::OutlookBuilder, summary, vectors, Outlook};
+
+    // Recipes of the bundled messages, written once with
+    // OutlookBuilder::write_to_path.
+    fn recipe(name: &str) -> OutlookBuilder {
+        let builder = OutlookBuilder::new().to("Bob Stone", "bob@example.com");
+        match name {
+            "plain" => builder
+                .subject("Quarterly report")
+                .sender("Alice Martin", "alice@example.com")
+                .body("Hello Bob,\r\nthe figures are in.\r\n"),
+            _ => builder
+                .subject("R\u{e9}sum\u{e9} et pi\u{e8}ces jointes")
+                .sender("\u{c9}lise Durand", "elise@example.fr")
+                .cc("Carol", "carol@example.com")
+                .body("Voir les pi\u{e8}ces jointes.\r\n")
+                .attachment("notes.txt", "text/plain", b"first line\nsecond line\n".to_vec())
+                .attachment("pixel.png", "image/png", vec![0x89, 0x50, 0x4E, 0x47, 0x0D, 0x0A, 0x1A, 0x0A]),
+        }
+    }
+
+    #[test]
+    fn test_vectors() {
+        let vectors = vectors();
+        assert_eq!(
+            vectors.iter().map(|v| v.name).collect::<Vec<&str>>(),
+            vec!["plain", "attachments", "malformed"]
+        );
+        for vector in vectors {
+            assert_eq!(
+                summary(Outlook::from_slice(vector.message).as_ref()),
+                vector.expected_value(),
+                "{}",
+                vector.name
+            );
+            assert!(vector.check());
+        }
+    }
+
+    #[test]
+    fn test_recipes() {
+        for vector in vectors().iter().filter(|v| v.name != "malformed") {
+            assert!(vector.message.len() < 16 * 1024, "{}", vector.name);
+            let outlook = recipe(vector.name).build().unwrap();
+            assert_eq!(summary(Ok(&outlook)), vector.expected_value(), "{}", vector.name);
+        }
+    }
+}
@@ -1,4 +1,6 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
+
+use super::named::NamedPropMap;
 
 // PropIdNameMap refers to mapping between property ID and
 // Full list is available in [MS-OXPROPS].
@@ -102,6 +104,7 @@ impl PropIdNameMap {
             ("0x0C1D", "SenderSearchKey"),
             ("0x0C1E", "SenderAddressType"),
             ("0x0C1F", "SenderEmailAddress"),
+            ("0x0C20", "NonDeliveryReportStatusCode"),
             ("0x0C21", "RemoteMessageTransferAgent"),
             ("0x0E01", "DeleteAfterSubmit"),
             ("0x0E02", "DisplayBcc"),
@@ -570,4 +573,261 @@ impl PropIdNameMap {
     pub fn get_canonical_name(&self, id: &str) -> Option<String> {
         self.map.get(id).map(|v| v.to_string())
     }
+
+    pub fn names(&self) -> impl Iterator<Item = &String> {
+        self.map.values()
+    }
+
+    // Property id of a canonical name, the reverse of
+    // get_canonical_name.
+    pub fn get_id(&self, name: &str) -> Option<u16> {
+        self.map
+            .iter()
+            .filter(|(_, v)| v.as_str() == name)
+            .filter_map(|(id, _)| u16::from_str_radix(id.trim_start_matches("0x"), 16).ok())
+            .min()
+    }
+
+    // Ids from 0x8000 onwards refer to named properties
+    // whose mapping is specific to each message file,
+    // so the static entries of that range are replaced.
+    pub fn set_named(&mut self, named: &NamedPropMap) {
+        self.map.retain(|id, _| {
+            u32::from_str_radix(id.trim_start_matches("0x"), 16).map_or(true, |id| id < 0x8000)
+        });
+        self.map
+            .extend(named.iter().map(|(id, name)| (id.to_string(), name.to_string())));
+    }
+}
+
+// Property sets of named properties.
+// Full list is available in [MS-OXPROPS] 1.3.2.
+pub const PS_MAPI: &str = "00020328-0000-0000-C000-000000000046";
+pub const PS_PUBLIC_STRINGS: &str = "00020329-0000-0000-C000-000000000046";
+pub const PSETID_APPOINTMENT: &str = "00062002-0000-0000-C000-000000000046";
+pub const PSETID_ADDRESS: &str = "00062004-0000-0000-C000-000000000046";
+pub const PSETID_COMMON: &str = "00062008-0000-0000-C000-000000000046";
+pub const PSETID_TASK: &str = "00062003-0000-0000-C000-000000000046";
+pub const PSETID_NOTE: &str = "0006200E-0000-0000-C000-000000000046";
+pub const PSETID_MEETING: &str = "6ED8DA90-450B-101B-98DA-00AA003F1305";
+pub const PS_INTERNET_HEADERS: &str = "00020386-0000-0000-C000-000000000046";
+
+// LidNameMap refers to mapping between a named property,
+// identified by its property set and long ID (LID), and
+// its canonical name.
+// Full list is available in [MS-OXPROPS].
+#[derive(Debug)]
+pub struct LidNameMap {
+    map: HashMap<(String, u32), String>,
+}
+
+impl LidNameMap {
+    pub fn init() -> Self {
+        let map: HashMap<(String, u32), String> = vec![
+            (PSETID_APPOINTMENT, 0x8233, "TimeZoneStruct"),
+            (PSETID_APPOINTMENT, 0x8234, "TimeZoneDescription"),
+            (
+                PSETID_APPOINTMENT,
+                0x825E,
+                "AppointmentTimeZoneDefinitionStartDisplay",
+            ),
+            (
+                PSETID_APPOINTMENT,
+                0x825F,
+                "AppointmentTimeZoneDefinitionEndDisplay",
+            ),
+            (
+                PSETID_APPOINTMENT,
+                0x8260,
+                "AppointmentTimeZoneDefinitionRecur",
+            ),
+            (PSETID_APPOINTMENT, 0x8201, "AppointmentSequence"),
+            (PSETID_APPOINTMENT, 0x8205, "BusyStatus"),
+            (PSETID_APPOINTMENT, 0x8208, "Location"),
+            (PSETID_APPOINTMENT, 0x820D, "AppointmentStartWhole"),
+            (PSETID_APPOINTMENT, 0x820E, "AppointmentEndWhole"),
+            (PSETID_APPOINTMENT, 0x8213, "AppointmentDuration"),
+            (PSETID_APPOINTMENT, 0x8215, "AppointmentSubType"),
+            (PSETID_APPOINTMENT, 0x8216, "AppointmentRecur"),
+            (PSETID_APPOINTMENT, 0x8218, "ResponseStatus"),
+            (PSETID_APPOINTMENT, 0x8250, "AppointmentProposedStartWhole"),
+            (PSETID_APPOINTMENT, 0x8251, "AppointmentProposedEndWhole"),
+            (PSETID_APPOINTMENT, 0x8257, "AppointmentCounterProposal"),
+            (PSETID_APPOINTMENT, 0x823B, "ToAttendeesString"),
+            (PSETID_APPOINTMENT, 0x823C, "CcAttendeesString"),
+            (PSETID_MEETING, 0x0003, "GlobalObjectId"),
+            (PSETID_ADDRESS, 0x8005, "FileUnder"),
+            (PSETID_ADDRESS, 0x8053, "DistributionListName"),
+            (PSETID_ADDRESS, 0x8054, "DistributionListOneOffMembers"),
+            (PSETID_ADDRESS, 0x8055, "DistributionListMembers"),
+            (PSETID_ADDRESS, 0x8080, "Email1DisplayName"),
+            (PSETID_ADDRESS, 0x8082, "Email1AddressType"),
+            (PSETID_ADDRESS, 0x8083, "Email1EmailAddress"),
+            (PSETID_ADDRESS, 0x8084, "Email1OriginalDisplayName"),
+            (PSETID_ADDRESS, 0x8090, "Email2DisplayName"),
+            (PSETID_ADDRESS, 0x8092, "Email2AddressType"),
+            (PSETID_ADDRESS, 0x8093, "Email2EmailAddress"),
+            (PSETID_ADDRESS, 0x8094, "Email2OriginalDisplayName"),
+            (PSETID_ADDRESS, 0x80A0, "Email3DisplayName"),
+            (PSETID_ADDRESS, 0x80A2, "Email3AddressType"),
+            (PSETID_ADDRESS, 0x80A3, "Email3EmailAddress"),
+            (PSETID_ADDRESS, 0x80A4, "Email3OriginalDisplayName"),
+            (PSETID_TASK, 0x8101, "TaskStatus"),
+            (PSETID_TASK, 0x8102, "PercentComplete"),
+            (PSETID_TASK, 0x8104, "TaskStartDate"),
+            (PSETID_TASK, 0x8105, "TaskDueDate"),
+            (PSETID_TASK, 0x810F, "TaskDateCompleted"),
+            (PSETID_TASK, 0x811C, "TaskComplete"),
+            (PSETID_TASK, 0x811F, "TaskOwner"),
+            (PSETID_TASK, 0x8121, "TaskAssigner"),
+            (PSETID_NOTE, 0x8B00, "NoteColor"),
+            (PSETID_NOTE, 0x8B02, "NoteWidth"),
+            (PSETID_NOTE, 0x8B03, "NoteHeight"),
+            (PSETID_NOTE, 0x8B04, "NoteX"),
+            (PSETID_NOTE, 0x8B05, "NoteY"),
+            (PSETID_COMMON, 0x8501, "ReminderDelta"),
+            (PSETID_COMMON, 0x8502, "ReminderTime"),
+            (PSETID_COMMON, 0x8503, "ReminderSet"),
+            (PSETID_COMMON, 0x851C, "ReminderOverride"),
+            (PSETID_COMMON, 0x851E, "ReminderPlaySound"),
+            (PSETID_COMMON, 0x851F, "ReminderFileParameter"),
+            (PSETID_COMMON, 0x8520, "VerbStream"),
+            (PSETID_COMMON, 0x8524, "VerbResponse"),
+            (PSETID_COMMON, 0x8530, "FlagRequest"),
+            (PSETID_COMMON, 0x8560, "ReminderSignalTime"),
+        ]
+        .into_iter()
+        .map(|(guid, lid, v)| ((guid.to_string(), lid), v.to_string()))
+        .collect();
+
+        Self { map }
+    }
+
+    pub fn get_canonical_name(&self, guid: &str, lid: u32) -> Option<String> {
+        self.map.get(&(guid.to_string(), lid)).map(|v| v.to_string())
+    }
+
+    pub fn names(&self) -> impl Iterator<Item = &String> {
+        self.map.values()
+    }
+
+    // Property set and LID of a canonical name.
+    pub fn get_lid(&self, name: &str) -> Option<(String, u32)> {
+        self.map
+            .iter()
+            .find(|(_, v)| v.as_str() == name)
+            .map(|(key, _)| key.clone())
+    }
+}
+
+// PropEnumMap refers to mapping between the documented
+// values of enumerated properties and their symbolic names.
+// Values of flags properties are named bit by bit.
+// Full list is available in [MS-OXPROPS].
+#[derive(Debug)]
+pub struct PropEnumMap {
+    map: HashMap<(String, i64), String>,
+    flags: HashSet<String>,
+}
+
+impl PropEnumMap {
+    pub fn init() -> Self {
+        let map: HashMap<(String, i64), String> = vec![
+            ("AttachMethod", 0, "None"),
+            ("AttachMethod", 1, "ByValue"),
+            ("AttachMethod", 2, "ByReference"),
+            ("AttachMethod", 4, "ByReferenceOnly"),
+            ("AttachMethod", 5, "EmbeddedMessage"),
+            ("AttachMethod", 6, "Storage"),
+            ("AttachMethod", 7, "ByWebReference"),
+            ("FlagStatus", 1, "Complete"),
+            ("FlagStatus", 2, "Flagged"),
+            ("Importance", 0, "Low"),
+            ("Importance", 1, "Normal"),
+            ("Importance", 2, "High"),
+            ("MessageFlags", 0x0000_0001, "Read"),
+            ("MessageFlags", 0x0000_0002, "Unmodified"),
+            ("MessageFlags", 0x0000_0004, "Submitted"),
+            ("MessageFlags", 0x0000_0008, "Unsent"),
+            ("MessageFlags", 0x0000_0010, "HasAttach"),
+            ("MessageFlags", 0x0000_0020, "FromMe"),
+            ("MessageFlags", 0x0000_0040, "Associated"),
+            ("MessageFlags", 0x0000_0080, "Resend"),
+            ("MessageFlags", 0x0000_0100, "NotifyRead"),
+            ("MessageFlags", 0x0000_0200, "NotifyUnread"),
+            ("MessageFlags", 0x0000_0400, "EverRead"),
+            ("MessageFlags", 0x0000_2000, "Internet"),
+            ("MessageFlags", 0x0000_8000, "Untrusted"),
+            ("MessageStatus", 0x0000_0800, "InConflict"),
+            ("MessageStatus", 0x0000_1000, "RemoteDownload"),
+            ("MessageStatus", 0x0000_2000, "RemoteDelete"),
+            ("NoteColor", 0, "Blue"),
+            ("NoteColor", 1, "Green"),
+            ("NoteColor", 2, "Pink"),
+            ("NoteColor", 3, "Yellow"),
+            ("NoteColor", 4, "White"),
+            ("NonDeliveryReportReasonCode", 0, "TransferFailed"),
+            ("NonDeliveryReportReasonCode", 1, "UnableToTransfer"),
+            ("NonDeliveryReportReasonCode", 2, "ConversionNotPerformed"),
+            ("NonDeliveryReportReasonCode", 3, "PhysicalRenditionNotPerformed"),
+            ("NonDeliveryReportReasonCode", 4, "PhysicalDeliveryNotPerformed"),
+            ("NonDeliveryReportReasonCode", 5, "RestrictedDelivery"),
+            ("NonDeliveryReportReasonCode", 6, "DirectoryOperationUnsuccessful"),
+            ("OriginalSensitivity", 0, "Normal"),
+            ("OriginalSensitivity", 1, "Personal"),
+            ("OriginalSensitivity", 2, "Private"),
+            ("OriginalSensitivity", 3, "Confidential"),
+            ("Priority", -1, "NonUrgent"),
+            ("Priority", 0, "Normal"),
+            ("Priority", 1, "Urgent"),
+            ("RecipientType", 0, "Originator"),
+            ("RecipientType", 1, "To"),
+            ("RecipientType", 2, "Cc"),
+            ("RecipientType", 3, "Bcc"),
+            ("Sensitivity", 0, "Normal"),
+            ("Sensitivity", 1, "Personal"),
+            ("Sensitivity", 2, "Private"),
+            ("Sensitivity", 3, "Confidential"),
+            ("TaskStatus", 0, "NotStarted"),
+            ("TaskStatus", 1, "InProgress"),
+            ("TaskStatus", 2, "Complete"),
+            ("TaskStatus", 3, "WaitingOnOthers"),
+            ("TaskStatus", 4, "Deferred"),
+        ]
+        .into_iter()
+        .map(|(name, value, v)| ((name.to_string(), value), v.to_string()))
+        .collect();
+        let flags: HashSet<String> = vec!["MessageFlags", "MessageStatus"]
+            .into_iter()
+            .map(|name| name.to_string())
+            .collect();
+
+        Self { map, flags }
+    }
+
+    pub fn is_enum(&self, name: &str) -> bool {
+        self.flags.contains(name) || self.map.keys().any(|(key, _)| key == name)
+    }
+
+    // Symbolic name of a value. Flags are joined with " | ",
+    // bits without a name being kept as hex.
+    pub fn get_symbolic_name(&self, name: &str, value: i64) -> Option<String> {
+        if !self.flags.contains(name) {
+            return self.map.get(&(name.to_string(), value)).map(|v| v.to_string());
+        }
+        let value = value as u32;
+        if value == 0 {
+            return None;
+        }
+        let names = (0..32)
+            .map(|bit| 1u32 << bit)
+            .filter(|flag| value & flag != 0)
+            .map(|flag| {
+                self.map
+                    .get(&(name.to_string(), flag as i64))
+                    .map_or_else(|| format!("0x{:08X}", flag), |v| v.to_string())
+            })
+            .collect::<Vec<String>>();
+        Some(names.join(" | "))
+    }
 }
@@ -0,0 +1,244 @@
+use serde::{Deserialize, Serialize};
+
+use super::{
+    decode::DataType,
+    outlook::Person,
+    storage::{Attachments, Properties},
+};
+
+// Telephone number properties of a contact along with
+// the label of the number.
+const PHONE_NUMBERS: [(&str, &str); 14] = [
+    ("PrimaryTelephoneNumber", "Primary"),
+    ("BusinessTelephoneNumber", "Business"),
+    ("Business2TelephoneNumbers", "Business2"),
+    ("CompanyMainTelephoneNumber", "CompanyMain"),
+    ("HomeTelephoneNumber", "Home"),
+    ("Home2TelephoneNumbers", "Home2"),
+    ("MobileTelephoneNumber", "Mobile"),
+    ("CarTelephoneNumber", "Car"),
+    ("OtherTelephoneNumber", "Other"),
+    ("PagerTelephoneNumber", "Pager"),
+    ("AssistantTelephoneNumber", "Assistant"),
+    ("BusinessFaxNumber", "BusinessFax"),
+    ("HomeFaxNumber", "HomeFax"),
+    ("PrimaryFaxNumber", "PrimaryFax"),
+];
+
+// PhoneNumber is one of the telephone numbers of
+// a contact, e.g. kind "Mobile".
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct PhoneNumber {
+    pub kind: String,
+    pub number: String,
+}
+
+// ContactPhoto is the picture attached to a contact.
+// Refer to MS-OXOCNTC 2.2.1.8
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct ContactPhoto {
+    pub mime_tag: String,  // "AttachMimeTag"
+    pub extension: String, // "AttachExtension"
+    pub payload: String,   // "AttachDataObject"
+}
+
+impl ContactPhoto {
+    fn create_from_attachments(attachments: &Attachments) -> Option<Self> {
+        let props = attachments.iter().find(|props| {
+            props
+                .get("AttachmentContactPhoto")
+                .and_then(DataType::as_bool)
+                .unwrap_or(false)
+        })?;
+        let string = |key: &str| props.get(key).map(String::from).unwrap_or_default();
+        Some(Self {
+            mime_tag: string("AttachMimeTag"),
+            extension: string("AttachExtension"),
+            payload: string("AttachDataObject"),
+        })
+    }
+}
+
+// Contact holds the properties of an IPM.Contact item.
+// Refer to MS-OXOCNTC 2.2.1
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct Contact {
+    pub display_name: String,            // "DisplayName"
+    pub prefix: String,                  // "DisplayNamePrefix"
+    pub given_name: String,              // "GivenName"
+    pub middle_name: String,             // "MiddleName"
+    pub surname: String,                 // "Surname"
+    pub suffix: String,                  // "Generation"
+    pub nickname: String,                // "Nickname"
+    pub company: String,                 // "CompanyName"
+    pub job_title: String,               // "Title"
+    pub department: String,              // "DepartmentName"
+    pub phone_numbers: Vec<PhoneNumber>, // See PHONE_NUMBERS
+    pub emails: Vec<Person>,             // "Email1DisplayName", "Email1EmailAddress" to Email3
+    pub photo: Option<ContactPhoto>,     // See ContactPhoto struct
+}
+
+impl Contact {
+    pub fn is_contact_class(message_class: &str) -> bool {
+        let message_class = message_class.to_ascii_lowercase();
+        message_class == "ipm.contact" || message_class.starts_with("ipm.contact.")
+    }
+
+    pub fn create_from_props(props: &Properties, attachments: &Attachments) -> Option<Self> {
+        let message_class = props.get("MessageClass").and_then(DataType::as_str)?;
+        if !Self::is_contact_class(message_class) {
+            return None;
+        }
+        let string = |key: &str| {
+            props
+                .get(key)
+                .and_then(DataType::as_str)
+                .unwrap_or_default()
+                .trim()
+                .to_string()
+        };
+        let phone_numbers = PHONE_NUMBERS
+            .iter()
+            .map(|(key, kind)| PhoneNumber {
+                kind: kind.to_string(),
+                number: string(key),
+            })
+            .filter(|phone| !phone.number.is_empty())
+            .collect();
+        let emails = (1..=3)
+            .map(|i| {
+                Person::create_from_props(
+                    props,
+                    &format!("Email{}DisplayName", i),
+                    vec![&format!("Email{}EmailAddress", i)],
+                )
+            })
+            .filter(|person| !person.email.is_empty())
+            .collect();
+        Some(Self {
+            display_name: string("DisplayName"),
+            prefix: string("DisplayNamePrefix"),
+            given_name: string("GivenName"),
+            middle_name: string("MiddleName"),
+            surname: string("Surname"),
+            suffix: string("Generation"),
+            nickname: string("Nickname"),
+            company: string("CompanyName"),
+            job_title: string("Title"),
+            department: string("DepartmentName"),
+            phone_numbers,
+            emails,
+            photo: ContactPhoto::create_from_attachments(attachments),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        super::{
+            decode::DataType,
+            storage::{props, string, Properties},
+        },
+        Contact, ContactPhoto, Person, PhoneNumber,
+    };
+
+    fn contact_props() -> Properties {
+        props(&[
+            ("MessageClass", string("IPM.Contact")),
+            ("DisplayName", string("Dr. Jane Q. Doe Jr.")),
+            ("DisplayNamePrefix", string("Dr.")),
+            ("GivenName", string("Jane")),
+            ("MiddleName", string("Q.")),
+            ("Surname", string("Doe")),
+            ("Generation", string("Jr.")),
+            ("CompanyName", string("Contoso")),
+            ("Title", string("Engineer")),
+            ("DepartmentName", string("R&D")),
+            ("MobileTelephoneNumber", string("+1 555 0100")),
+            ("BusinessTelephoneNumber", string("+1 555 0199")),
+            ("HomeTelephoneNumber", string(" ")),
+            ("Email1DisplayName", string("Jane Doe (jane@contoso.com)")),
+            ("Email1EmailAddress", string("jane@contoso.com")),
+            ("Email3EmailAddress", string("jane@example.org")),
+        ])
+    }
+
+    #[test]
+    fn test_create_contact() {
+        let contact = Contact::create_from_props(&contact_props(), &vec![]).unwrap();
+        assert_eq!(contact.display_name, "Dr. Jane Q. Doe Jr.".to_string());
+        assert_eq!(contact.prefix, "Dr.".to_string());
+        assert_eq!(contact.given_name, "Jane".to_string());
+        assert_eq!(contact.middle_name, "Q.".to_string());
+        assert_eq!(contact.surname, "Doe".to_string());
+        assert_eq!(contact.suffix, "Jr.".to_string());
+        assert_eq!(contact.nickname, String::new());
+        assert_eq!(contact.company, "Contoso".to_string());
+        assert_eq!(contact.job_title, "Engineer".to_string());
+        assert_eq!(contact.department, "R&D".to_string());
+        assert_eq!(
+            contact.phone_numbers,
+            vec![
+                PhoneNumber {
+                    kind: "Business".to_string(),
+                    number: "+1 555 0199".to_string()
+                },
+                PhoneNumber {
+                    kind: "Mobile".to_string(),
+                    number: "+1 555 0100".to_string()
+                },
+            ]
+        );
+        assert_eq!(
+            contact.emails,
+            vec![
+                Person::new(
+                    "Jane Doe (jane@contoso.com)".to_string(),
+                    "jane@contoso.com".to_string()
+                ),
+                Person::new(String::new(), "jane@example.org".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_contact_photo() {
+        let attachment = |photo: bool| {
+            let mut props = Properties::new();
+            props.insert("AttachmentContactPhoto".to_string(), DataType::PtypBoolean(photo));
+            props.insert("AttachExtension".to_string(), DataType::PtypString(".jpg".to_string()));
+            props.insert("AttachDataObject".to_string(), DataType::PtypBinary(vec![0xFF, 0xD8]));
+            props
+        };
+        let contact = Contact::create_from_props(&contact_props(), &vec![attachment(false)]).unwrap();
+        assert_eq!(contact.photo, None);
+
+        let contact =
+            Contact::create_from_props(&contact_props(), &vec![attachment(false), attachment(true)])
+                .unwrap();
+        assert_eq!(
+            contact.photo,
+            Some(ContactPhoto {
+                mime_tag: String::new(),
+                extension: ".jpg".to_string(),
+                payload: "ffd8".to_string(),
+            })
+        );
+    }
+
+    #[test]
+    fn test_not_a_contact() {
+        let mut props = contact_props();
+        props.insert(
+            "MessageClass".to_string(),
+            DataType::PtypString("IPM.Note".to_string()),
+        );
+        assert_eq!(Contact::create_from_props(&props, &vec![]), None);
+        assert!(Contact::is_contact_class("ipm.contact.custom"));
+        assert!(!Contact::is_contact_class("IPM.ContactX"));
+    }
+}
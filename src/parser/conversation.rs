@@ -0,0 +1,134 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use super::{
+    blob::BlobReader,
+    decode::{filetime_to_datetime, DataType},
+    error::DataTypeError,
+    storage::Properties,
+};
+
+// Length of the header block and of each response level.
+const HEADER_LEN: usize = 22;
+const RESPONSE_LEVEL_LEN: usize = 5;
+
+// ConversationIndex tells where a message stands in its
+// conversation: the time and GUID of the message that
+// started it, followed by one response level per reply.
+// Refer to MS-OXOMSG 2.2.1.3
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct ConversationIndex {
+    pub time: Option<DateTime<Utc>>,         // time the conversation started
+    pub guid: String,                        // conversation GUID
+    pub replies: Vec<Option<DateTime<Utc>>>, // time of each reply, in order
+}
+
+impl ConversationIndex {
+    pub fn from_bytes(buff: &[u8]) -> Result<Self, DataTypeError> {
+        if buff.len() < HEADER_LEN || !(buff.len() - HEADER_LEN).is_multiple_of(RESPONSE_LEVEL_LEN) {
+            return Err(DataTypeError::MalformedBlob(format!(
+                "conversation index of {} bytes",
+                buff.len()
+            )));
+        }
+        let mut reader = BlobReader::new(buff);
+        let header = reader.read_bytes(6)?;
+        // The header holds the high order bytes of a FILETIME.
+        // Recent clients keep the reserved 0x01 byte apart and
+        // store five bytes, dropping three instead of two.
+        let mut filetime = if header[0] == 0x01 && header[1] == 0x01 {
+            header[1..]
+                .iter()
+                .fold(0u64, |acc, &byte| (acc << 8) | byte as u64)
+                << 24
+        } else {
+            header
+                .iter()
+                .fold(0u64, |acc, &byte| (acc << 8) | byte as u64)
+                << 16
+        };
+        let time = filetime_to_datetime(filetime);
+        let guid = reader.read_guid()?;
+
+        let mut replies = vec![];
+        while reader.remaining() > 0 {
+            let level = reader.read_bytes(RESPONSE_LEVEL_LEN)?;
+            let level = u32::from_be_bytes([level[0], level[1], level[2], level[3]]);
+            // The delta code tells which bits of the time
+            // elapsed since the previous level are kept.
+            let delta = (level & 0x7FFF_FFFF) as u64;
+            let delta = if level & 0x8000_0000 == 0 {
+                delta << 18
+            } else {
+                delta << 23
+            };
+            filetime = filetime.saturating_add(delta);
+            replies.push(filetime_to_datetime(filetime));
+        }
+        Ok(Self {
+            time,
+            guid,
+            replies,
+        })
+    }
+
+    pub fn create_from_props(props: &Properties) -> Option<Self> {
+        let buff = props.get("ConversationIndex").and_then(DataType::as_bytes)?;
+        Self::from_bytes(buff).ok()
+    }
+
+    // Number of replies between the message and the one
+    // that started the conversation.
+    pub fn depth(&self) -> usize {
+        self.replies.len()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{TimeZone as _, Utc};
+
+    use super::{super::outlook::Outlook, ConversationIndex, HEADER_LEN};
+
+    #[test]
+    fn test_from_bytes() {
+        let mut buff = hex::decode("01d6e30ea221d1898e06dfce4b9da3b90f8456cc8342").unwrap();
+        let index = ConversationIndex::from_bytes(&buff).unwrap();
+        assert_eq!(index.guid, "068E89D1-CEDF-9D4B-A3B9-0F8456CC8342".to_string());
+        assert_eq!(index.depth(), 0);
+        let time = index.time.unwrap();
+        assert_eq!(
+            time.date_naive(),
+            Utc.with_ymd_and_hms(2021, 1, 5, 0, 0, 0).unwrap().date_naive()
+        );
+
+        // A reply 2^18 * 1000 intervals later, then one with
+        // the other delta code.
+        buff.extend([0x00, 0x00, 0x03, 0xE8, 0x5A]);
+        buff.extend([0x80, 0x00, 0x00, 0x01, 0x00]);
+        let index = ConversationIndex::from_bytes(&buff).unwrap();
+        assert_eq!(index.depth(), 2);
+        let first = index.replies[0].unwrap();
+        let second = index.replies[1].unwrap();
+        assert_eq!((first - time).num_microseconds(), Some((1000 << 18) / 10));
+        assert_eq!((second - first).num_microseconds(), Some((1 << 23) / 10));
+
+        assert!(ConversationIndex::from_bytes(&buff[..HEADER_LEN + 1]).is_err());
+        assert!(ConversationIndex::from_bytes(&[]).is_err());
+    }
+
+    #[test]
+    fn test_conversation_index() {
+        let outlook = Outlook::from_path("data/test_email_4.msg").unwrap();
+        let index = outlook.conversation_index.unwrap();
+        assert_eq!(index.depth(), 0);
+        assert_eq!(
+            index.time.unwrap().date_naive(),
+            Utc.with_ymd_and_hms(2021, 3, 14, 0, 0, 0).unwrap().date_naive()
+        );
+
+        let outlook = Outlook::from_path("data/attachment.msg").unwrap();
+        assert_eq!(outlook.conversation_index.unwrap().depth(), 1);
+    }
+}
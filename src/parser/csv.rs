@@ -0,0 +1,229 @@
+use std::{
+    fs::File,
+    io::{BufWriter, Write},
+    path::Path,
+};
+
+use chrono::{DateTime, SecondsFormat, Utc};
+
+use sha2::{Digest, Sha256};
+
+use super::{
+    error::Error,
+    outlook::{Outlook, Person},
+};
+
+// Columns of each row, in order.
+const COLUMNS: [&str; 12] = [
+    "source",
+    "sender_name",
+    "sender_email",
+    "to",
+    "cc",
+    "bcc",
+    "subject",
+    "submit_time",
+    "delivery_time",
+    "attachment_count",
+    "attachment_names",
+    "attachment_sha256",
+];
+
+// Separator of the values listed in one field, e.g. the
+// recipients.
+const LIST_SEPARATOR: &str = "; ";
+
+// CsvWriter writes one row per message, for an inventory
+// of many messages opened as a spreadsheet. The first row
+// names the columns. Fields holding the delimiter, quotes
+// or line breaks are quoted, and line breaks end rows as
+// CRLF.
+// Refer to RFC 4180
+pub struct CsvWriter<W: Write> {
+    writer: W,
+    delimiter: char,
+    count: usize, // Messages written so far
+}
+
+impl CsvWriter<BufWriter<File>> {
+    pub fn create<P: AsRef<Path>>(path: P) -> Result<Self, Error> {
+        Ok(Self::new(BufWriter::new(File::create(path)?)))
+    }
+}
+
+impl<W: Write> CsvWriter<W> {
+    pub fn new(writer: W) -> Self {
+        Self {
+            writer,
+            delimiter: ',',
+            count: 0,
+        }
+    }
+
+    // Separates fields with tabs, for TSV.
+    pub fn tsv(mut self) -> Self {
+        self.delimiter = '\t';
+        self
+    }
+
+    pub fn count(&self) -> usize {
+        self.count
+    }
+
+    fn escape(&self, field: &str) -> String {
+        if field.contains([self.delimiter, '"', '\r', '\n']) {
+            format!("\"{}\"", field.replace('"', "\"\""))
+        } else {
+            field.to_string()
+        }
+    }
+
+    fn write_row(&mut self, fields: &[String]) -> Result<(), Error> {
+        let row: Vec<String> = fields.iter().map(|field| self.escape(field)).collect();
+        write!(self.writer, "{}\r\n", row.join(&self.delimiter.to_string()))?;
+        Ok(())
+    }
+
+    // Writes the row of a message, source naming where it
+    // comes from, e.g. its path.
+    pub fn write(&mut self, source: &str, outlook: &Outlook) -> Result<(), Error> {
+        if self.count == 0 {
+            let columns: Vec<String> = COLUMNS.iter().map(|column| column.to_string()).collect();
+            self.write_row(&columns)?;
+        }
+        let persons = |persons: &[Person]| {
+            persons
+                .iter()
+                .map(|person| {
+                    if person.email.is_empty() {
+                        person.name.to_string()
+                    } else if person.name.is_empty() || person.name == person.email {
+                        person.email.to_string()
+                    } else {
+                        format!("{} <{}>", person.name, person.email)
+                    }
+                })
+                .collect::<Vec<String>>()
+                .join(LIST_SEPARATOR)
+        };
+        let time = |time: Option<DateTime<Utc>>| {
+            time.map(|time| time.to_rfc3339_opts(SecondsFormat::Secs, true))
+                .unwrap_or_default()
+        };
+        let names: Vec<String> = outlook
+            .attachments
+            .iter()
+            .map(|attachment| {
+                [&attachment.display_name, &attachment.file_name]
+                    .iter()
+                    .find(|name| !name.is_empty())
+                    .map(|name| name.to_string())
+                    .unwrap_or_default()
+            })
+            .collect();
+        // Attachments without data, e.g. embedded messages,
+        // have no hash.
+        let hashes: Vec<String> = outlook
+            .attachments
+            .iter()
+            .map(|attachment| match hex::decode(&attachment.payload) {
+                Ok(bytes) if !bytes.is_empty() => hex::encode(Sha256::digest(bytes)),
+                _ => String::new(),
+            })
+            .collect();
+        self.write_row(&[
+            source.to_string(),
+            outlook.sender.name.to_string(),
+            outlook.sender.email.to_string(),
+            persons(&outlook.to),
+            persons(&outlook.cc),
+            persons(&outlook.bcc),
+            outlook.subject.to_string(),
+            time(outlook.submit_time),
+            time(outlook.delivery_time),
+            outlook.attachments.len().to_string(),
+            names.join(LIST_SEPARATOR),
+            hashes.join(LIST_SEPARATOR),
+        ])?;
+        self.count += 1;
+        Ok(())
+    }
+
+    // Parses each file and writes its row, the path being
+    // its source. Stops at the first file failing to parse.
+    pub fn write_paths<P: AsRef<Path>, I: IntoIterator<Item = P>>(&mut self, paths: I) -> Result<usize, Error> {
+        let start = self.count;
+        for path in paths {
+            let outlook = Outlook::from_path(&path)?;
+            self.write(&path.as_ref().display().to_string(), &outlook)?;
+        }
+        Ok(self.count - start)
+    }
+
+    // Flushes the rows written and gives back the
+    // underlying writer.
+    pub fn finish(mut self) -> Result<W, Error> {
+        self.writer.flush()?;
+        Ok(self.writer)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        super::{builder::OutlookBuilder, outlook::Outlook},
+        CsvWriter,
+    };
+
+    #[test]
+    fn test_write() {
+        let outlook = OutlookBuilder::new()
+            .subject("Q3 \"final\", really")
+            .sender("Alice", "alice@example.com")
+            .to("Bob", "bob@example.com")
+            .to("", "carol@example.com")
+            .body("Hello")
+            .build()
+            .unwrap();
+        let mut csv = CsvWriter::new(vec![]);
+        csv.write("built", &outlook).unwrap();
+        assert_eq!(csv.count(), 1);
+        let text = String::from_utf8(csv.finish().unwrap()).unwrap();
+        let rows: Vec<&str> = text.split("\r\n").collect();
+        assert_eq!(
+            rows[0],
+            "source,sender_name,sender_email,to,cc,bcc,subject,submit_time,delivery_time,\
+             attachment_count,attachment_names,attachment_sha256"
+        );
+        assert_eq!(
+            rows[1],
+            "built,Alice,alice@example.com,Bob <bob@example.com>; carol@example.com,,,\
+             \"Q3 \"\"final\"\", really\",,,0,,"
+        );
+        assert_eq!(rows[2], "");
+    }
+
+    #[test]
+    fn test_write_paths() {
+        let path = std::env::temp_dir().join(format!("inventory-{}.tsv", std::process::id()));
+        let mut tsv = CsvWriter::create(&path).unwrap().tsv();
+        let paths = ["data/attachment.msg", "data/test_email.msg"];
+        assert_eq!(tsv.write_paths(paths).unwrap(), 2);
+        tsv.finish().unwrap();
+        let text = std::fs::read_to_string(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        let rows: Vec<Vec<&str>> = text.lines().map(|row| row.split('\t').collect()).collect();
+        assert_eq!(rows.len(), 3);
+        assert!(rows.iter().all(|row| row.len() == 12));
+        let outlook = Outlook::from_path(paths[0]).unwrap();
+        assert_eq!(rows[1][0], "data/attachment.msg");
+        assert_eq!(rows[1][6], outlook.subject);
+        assert_eq!(rows[1][9], "3");
+        assert!(rows[1][10].starts_with("loan_proposal.doc; "));
+        // A SHA-256 for each of the 3 attachments.
+        assert_eq!(rows[1][11].split("; ").filter(|hash| hash.len() == 64).count(), 3);
+
+        assert!(CsvWriter::new(vec![]).write_paths(["data/bad_outlook.msg"]).is_err());
+    }
+}
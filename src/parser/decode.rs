@@ -1,18 +1,38 @@
-use std::io::Read;
+use std::{convert::TryFrom, io::Read};
 
-use hex;
+use chrono::{DateTime, SecondsFormat, Utc};
+
+use serde::{Deserialize, Serialize};
 
 use crate::ole::EntrySlice;
 
 use super::error::{DataTypeError, Error};
 
+// Seconds between 1601-01-01 (FILETIME epoch) and 1970-01-01.
+const FILETIME_UNIX_EPOCH_DIFF: i64 = 11_644_473_600;
+
+// Code page of 8-bit strings when the message names none.
+pub const DEFAULT_CODEPAGE: u32 = 1252;
+
 // DataType corresponds to decoded property values
 // as specified in this document.
 // https://docs.microsoft.com/en-us/openspecs/exchange_server_protocols/ms-oxcdata/0c77892e-288e-435a-9c49-be1c20c7afdb
-#[derive(Clone, Debug, PartialEq)]
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub enum DataType {
     PtypString(String),
     PtypBinary(Vec<u8>),
+    PtypInteger16(i16),
+    PtypInteger32(i32),
+    PtypFloating32(f32),
+    PtypFloating64(f64),
+    PtypCurrency(i64),
+    PtypFloatingTime(f64),
+    PtypErrorCode(u32),
+    PtypBoolean(bool),
+    PtypInteger64(i64),
+    PtypTime(DateTime<Utc>),
+    PtypMultipleString(Vec<String>),
+    PtypMultipleBinary(Vec<Vec<u8>>),
 }
 
 impl From<&DataType> for String {
@@ -20,8 +40,91 @@ impl From<&DataType> for String {
         match *data {
             DataType::PtypBinary(ref bytes) => hex::encode(bytes),
             DataType::PtypString(ref string) => string.to_string(),
+            DataType::PtypInteger16(value) => value.to_string(),
+            DataType::PtypInteger32(value) => value.to_string(),
+            DataType::PtypFloating32(value) => value.to_string(),
+            DataType::PtypFloating64(value) => value.to_string(),
+            DataType::PtypCurrency(value) => value.to_string(),
+            DataType::PtypFloatingTime(value) => value.to_string(),
+            DataType::PtypErrorCode(value) => format!("0x{:08X}", value),
+            DataType::PtypBoolean(value) => value.to_string(),
+            DataType::PtypInteger64(value) => value.to_string(),
+            DataType::PtypTime(ref time) => time.to_rfc3339_opts(SecondsFormat::Secs, true),
+            DataType::PtypMultipleString(ref strings) => strings.join(", "),
+            DataType::PtypMultipleBinary(ref values) => values
+                .iter()
+                .map(hex::encode)
+                .collect::<Vec<String>>()
+                .join(", "),
+        }
+    }
+}
+
+impl DataType {
+    pub fn as_str(&self) -> Option<&str> {
+        match *self {
+            DataType::PtypString(ref string) => Some(string),
+            _ => None,
+        }
+    }
+
+    pub fn as_bytes(&self) -> Option<&[u8]> {
+        match *self {
+            DataType::PtypBinary(ref bytes) => Some(bytes),
+            _ => None,
+        }
+    }
+
+    // Integer value of any of the integer types.
+    pub fn as_i64(&self) -> Option<i64> {
+        match *self {
+            DataType::PtypInteger16(value) => Some(value as i64),
+            DataType::PtypInteger32(value) => Some(value as i64),
+            DataType::PtypInteger64(value) => Some(value),
+            _ => None,
+        }
+    }
+
+    pub fn as_f64(&self) -> Option<f64> {
+        match *self {
+            DataType::PtypFloating32(value) => Some(value as f64),
+            DataType::PtypFloating64(value) => Some(value),
+            _ => None,
+        }
+    }
+
+    pub fn as_bool(&self) -> Option<bool> {
+        match *self {
+            DataType::PtypBoolean(value) => Some(value),
+            _ => None,
         }
     }
+
+    pub fn as_time(&self) -> Option<DateTime<Utc>> {
+        match *self {
+            DataType::PtypTime(time) => Some(time),
+            _ => None,
+        }
+    }
+}
+
+// Converts a FILETIME, the number of 100-nanosecond
+// intervals since 1601-01-01 UTC, into a DateTime.
+pub fn filetime_to_datetime(filetime: u64) -> Option<DateTime<Utc>> {
+    let secs = (filetime / 10_000_000) as i64 - FILETIME_UNIX_EPOCH_DIFF;
+    let nanos = (filetime % 10_000_000) as u32 * 100;
+    DateTime::from_timestamp(secs, nanos)
+}
+
+// Converts a time into a FILETIME, the reverse
+// of filetime_to_datetime. Times before 1601 are
+// clamped to its start.
+pub fn datetime_to_filetime(time: &DateTime<Utc>) -> u64 {
+    let secs = time.timestamp() + FILETIME_UNIX_EPOCH_DIFF;
+    if secs < 0 {
+        return 0;
+    }
+    secs as u64 * 10_000_000 + time.timestamp_subsec_nanos() as u64 / 100
 }
 
 // PytpDecoder converts a byte sequence
@@ -31,32 +134,95 @@ pub struct PtypDecoder {}
 impl PtypDecoder {
     pub fn decode(entry_slice: &mut EntrySlice, code: &str) -> Result<DataType, Error> {
         let mut buff = vec![0u8; entry_slice.len()];
-        entry_slice.read(&mut buff)?;
+        entry_slice.read_exact(&mut buff)?;
         match code {
             "0x001F" => decode_ptypstring(&buff),
             "0x0102" => decode_ptypbinary(&buff),
             _ => Err(DataTypeError::UnknownCode(code.to_string()).into()),
         }
     }
+
+    // Decodes the values of a multiple valued property,
+    // each of them being read from a stream of its own.
+    // Refer to MS-OXMSG 2.1.4.2.2
+    pub fn decode_multiple(
+        entry_slices: &mut [EntrySlice],
+        code: &str,
+    ) -> Result<DataType, Error> {
+        let mut values = vec![];
+        for entry_slice in entry_slices.iter_mut() {
+            let mut buff = vec![0u8; entry_slice.len()];
+            entry_slice.read_exact(&mut buff)?;
+            values.push(buff);
+        }
+        match code {
+            "0x101F" => {
+                let mut strings = vec![];
+                for buff in values {
+                    if let DataType::PtypString(string) = decode_ptypstring(&buff)? {
+                        // Values may keep their terminating null character.
+                        strings.push(string.trim_end_matches('\0').to_string());
+                    }
+                }
+                Ok(DataType::PtypMultipleString(strings))
+            }
+            "0x1102" => Ok(DataType::PtypMultipleBinary(values)),
+            _ => Err(DataTypeError::UnknownCode(code.to_string()).into()),
+        }
+    }
+
+    // Decodes an 8-bit string in the given code page.
+    // Code pages that are not known are read as Windows-1252.
+    // Refer to MS-OXCDATA 2.11.1
+    pub fn decode_string8(entry_slice: &mut EntrySlice, codepage: u32) -> Result<DataType, Error> {
+        let mut buff = vec![0u8; entry_slice.len()];
+        entry_slice.read_exact(&mut buff)?;
+        let encoding = u16::try_from(codepage)
+            .ok()
+            .and_then(codepage::to_encoding)
+            .unwrap_or(encoding_rs::WINDOWS_1252);
+        let (string, _, _) = encoding.decode(&buff);
+        Ok(DataType::PtypString(string.trim_end_matches('\0').to_string()))
+    }
+
+    // Decodes the 8 bytes value of a fixed length property
+    // held in a property stream entry.
+    // Refer to MS-OXMSG 2.4.2.1
+    pub fn decode_fixed(value: &[u8; 8], code: &str) -> Result<DataType, Error> {
+        let int16 = i16::from_le_bytes([value[0], value[1]]);
+        let int32 = i32::from_le_bytes([value[0], value[1], value[2], value[3]]);
+        let int64 = i64::from_le_bytes(*value);
+        match code {
+            "0x0002" => Ok(DataType::PtypInteger16(int16)),
+            "0x0003" => Ok(DataType::PtypInteger32(int32)),
+            "0x0004" => Ok(DataType::PtypFloating32(f32::from_bits(int32 as u32))),
+            "0x0005" => Ok(DataType::PtypFloating64(f64::from_bits(int64 as u64))),
+            "0x0006" => Ok(DataType::PtypCurrency(int64)),
+            "0x0007" => Ok(DataType::PtypFloatingTime(f64::from_bits(int64 as u64))),
+            "0x000A" => Ok(DataType::PtypErrorCode(int32 as u32)),
+            "0x000B" => Ok(DataType::PtypBoolean(int16 != 0)),
+            "0x0014" => Ok(DataType::PtypInteger64(int64)),
+            "0x0040" => filetime_to_datetime(int64 as u64)
+                .map(DataType::PtypTime)
+                .ok_or_else(|| {
+                    DataTypeError::MalformedBlob(format!("invalid FILETIME {}", int64)).into()
+                }),
+            _ => Err(DataTypeError::UnknownCode(code.to_string()).into()),
+        }
+    }
 }
 
-fn decode_ptypbinary(buff: &Vec<u8>) -> Result<DataType, Error> {
+fn decode_ptypbinary(buff: &[u8]) -> Result<DataType, Error> {
     Ok(DataType::PtypBinary(buff.to_vec()))
 }
 
-fn decode_ptypstring(buff: &Vec<u8>) -> Result<DataType, Error> {
+fn decode_ptypstring(buff: &[u8]) -> Result<DataType, Error> {
     // PtypString
     // Byte sequence is in little-endian format
     // Use UTF-16 String decode
     let mut buff_iter = buff.iter();
     let mut buffu16 = Vec::new();
-    loop {
-        let c1 = match buff_iter.next() {
-            Some(c) => c,
-            None => {
-                break;
-            },
-        };
+    while let Some(c1) = buff_iter.next() {
         let duo = match buff_iter.next() {
             Some(c2) => [*c1, *c2],
             None => [*c1, 0_u8],
@@ -71,9 +237,11 @@ fn decode_ptypstring(buff: &Vec<u8>) -> Result<DataType, Error> {
 }
 
 #[cfg(test)]
+#[allow(clippy::bool_assert_comparison)]
 mod tests {
-    use super::{DataType, PtypDecoder, decode_ptypstring};
+    use super::{DataType, PtypDecoder, datetime_to_filetime, decode_ptypstring, filetime_to_datetime};
     use crate::ole::Reader;
+    use chrono::{TimeZone, Utc};
 
     #[test]
     fn test_unknown_code() {
@@ -92,6 +260,65 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_decode_fixed() {
+        let value = [0xFE, 0xFF, 0xFF, 0xFF, 0x00, 0x00, 0x00, 0x00];
+        assert_eq!(
+            PtypDecoder::decode_fixed(&value, "0x0002").unwrap(),
+            DataType::PtypInteger16(-2)
+        );
+        assert_eq!(
+            PtypDecoder::decode_fixed(&value, "0x0003").unwrap(),
+            DataType::PtypInteger32(-2)
+        );
+        assert_eq!(
+            PtypDecoder::decode_fixed(&value, "0x0014").unwrap(),
+            DataType::PtypInteger64(0xFFFFFFFE)
+        );
+        assert_eq!(
+            PtypDecoder::decode_fixed(&value, "0x000B").unwrap(),
+            DataType::PtypBoolean(true)
+        );
+        assert_eq!(
+            PtypDecoder::decode_fixed(&[0u8; 8], "0x000B").unwrap(),
+            DataType::PtypBoolean(false)
+        );
+        let value = 1.5f64.to_le_bytes();
+        assert_eq!(
+            PtypDecoder::decode_fixed(&value, "0x0005").unwrap(),
+            DataType::PtypFloating64(1.5)
+        );
+        assert!(PtypDecoder::decode_fixed(&value, "0x001F").is_err());
+    }
+
+    #[test]
+    fn test_decode_ptyptime() {
+        // 2013-11-18 08:26:24 UTC
+        let filetime: u64 = 130_292_367_840_000_000;
+        let decoded = PtypDecoder::decode_fixed(&filetime.to_le_bytes(), "0x0040").unwrap();
+        let expected = Utc.with_ymd_and_hms(2013, 11, 18, 8, 26, 24).unwrap();
+        assert_eq!(decoded, DataType::PtypTime(expected));
+        assert_eq!(String::from(&decoded), "2013-11-18T08:26:24Z".to_string());
+
+        assert_eq!(
+            filetime_to_datetime(0),
+            Some(Utc.with_ymd_and_hms(1601, 1, 1, 0, 0, 0).unwrap())
+        );
+        assert_eq!(datetime_to_filetime(&expected), filetime);
+        assert_eq!(
+            datetime_to_filetime(&Utc.with_ymd_and_hms(1500, 1, 1, 0, 0, 0).unwrap()),
+            0
+        );
+    }
+
+    #[test]
+    fn test_multiple_to_string() {
+        let strings = DataType::PtypMultipleString(vec!["a".to_string(), "b".to_string()]);
+        assert_eq!(String::from(&strings), "a, b".to_string());
+        let values = DataType::PtypMultipleBinary(vec![vec![0x01, 0xAB], vec![0xFF]]);
+        assert_eq!(String::from(&values), "01ab, ff".to_string());
+    }
+
     #[test]
     fn test_ptypstring() {
         let path = "data/test_email.msg";
@@ -134,4 +361,18 @@ mod tests {
         assert_eq!(s, DataType::PtypString("Réponse".to_string()));
         assert_ne!(s, DataType::PtypString("Réponse".to_string()));
     }
+
+    #[test]
+    fn test_serde() {
+        let values = vec![
+            DataType::PtypString("Réponse".to_string()),
+            DataType::PtypBinary(vec![0, 1, 2]),
+            DataType::PtypFloating64(0.5),
+            DataType::PtypTime(Utc.with_ymd_and_hms(2021, 3, 4, 5, 6, 7).unwrap()),
+            DataType::PtypMultipleString(vec!["a".to_string(), "b".to_string()]),
+        ];
+        let json = serde_json::to_string(&values).unwrap();
+        assert!(json.starts_with(r#"[{"PtypString":"Réponse"},{"PtypBinary":[0,1,2]}"#));
+        assert_eq!(serde_json::from_str::<Vec<DataType>>(&json).unwrap(), values);
+    }
 }
@@ -0,0 +1,303 @@
+use chrono::{DateTime, NaiveDateTime, Utc};
+
+use super::error::DataTypeError;
+
+// Tags of the ASN.1 types read by the parser.
+pub const TAG_BOOLEAN: u8 = 0x01;
+pub const TAG_INTEGER: u8 = 0x02;
+pub const TAG_OCTET_STRING: u8 = 0x04;
+pub const TAG_OID: u8 = 0x06;
+pub const TAG_UTF8_STRING: u8 = 0x0C;
+pub const TAG_UTC_TIME: u8 = 0x17;
+pub const TAG_GENERALIZED_TIME: u8 = 0x18;
+pub const TAG_BMP_STRING: u8 = 0x1E;
+pub const TAG_SEQUENCE: u8 = 0x30;
+pub const TAG_SET: u8 = 0x31;
+
+// Bit telling the value is made of other values.
+const CONSTRUCTED: u8 = 0x20;
+
+// Indefinite lengths are found by reading the values they
+// hold, which may themselves have indefinite lengths, and
+// constructed octet strings by reading the strings they hold.
+const MAX_NESTING: usize = 32;
+
+// Tlv is an encoded value: its tag and its contents.
+// Refer to X.690 8.1
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Tlv<'a> {
+    pub tag: u8,
+    pub content: &'a [u8],
+}
+
+impl<'a> Tlv<'a> {
+    pub fn is_constructed(&self) -> bool {
+        self.tag & CONSTRUCTED != 0
+    }
+
+    // Reader over the values this one is made of.
+    pub fn children(&self) -> DerReader<'a> {
+        DerReader::new(self.content)
+    }
+
+    // Bytes of an octet string. BER may split them
+    // into a constructed string of smaller ones.
+    pub fn octets(&self) -> Result<Vec<u8>, DataTypeError> {
+        self.octets_at(0)
+    }
+
+    fn octets_at(&self, depth: usize) -> Result<Vec<u8>, DataTypeError> {
+        if !self.is_constructed() {
+            return Ok(self.content.to_vec());
+        }
+        if depth >= MAX_NESTING {
+            return Err(DataTypeError::MalformedBlob("octet strings nested too deep".to_string()));
+        }
+        let mut octets = vec![];
+        let mut children = self.children();
+        while !children.is_empty() {
+            octets.extend(children.read()?.octets_at(depth + 1)?);
+        }
+        Ok(octets)
+    }
+
+    // Dotted form of an object identifier, e.g. "2.5.4.3".
+    pub fn oid(&self) -> Result<String, DataTypeError> {
+        let (first, rest) = self
+            .content
+            .split_first()
+            .ok_or_else(|| DataTypeError::MalformedBlob("empty object identifier".to_string()))?;
+        // The first byte holds the first two arcs.
+        let mut arcs = match first {
+            0..=39 => vec![0, *first as u64],
+            40..=79 => vec![1, (first - 40) as u64],
+            _ => vec![2, (first - 80) as u64],
+        };
+        let mut arc: u64 = 0;
+        for &byte in rest {
+            arc = (arc << 7) | (byte & 0x7F) as u64;
+            if byte & 0x80 == 0 {
+                arcs.push(arc);
+                arc = 0;
+            }
+        }
+        Ok(arcs
+            .iter()
+            .map(u64::to_string)
+            .collect::<Vec<String>>()
+            .join("."))
+    }
+
+    // Text of a string type, BMP strings being UTF-16.
+    pub fn string(&self) -> String {
+        match self.tag {
+            TAG_BMP_STRING => {
+                let units: Vec<u16> = self
+                    .content
+                    .chunks_exact(2)
+                    .map(|unit| u16::from_be_bytes([unit[0], unit[1]]))
+                    .collect();
+                String::from_utf16_lossy(&units)
+            }
+            TAG_UTF8_STRING => String::from_utf8_lossy(self.content).into_owned(),
+            // Printable, IA5 and teletex strings hold no more than Latin-1.
+            _ => self.content.iter().map(|&b| b as char).collect(),
+        }
+    }
+
+    pub fn time(&self) -> Option<DateTime<Utc>> {
+        let text = std::str::from_utf8(self.content).ok()?;
+        let text = text.strip_suffix('Z')?;
+        let text = match self.tag {
+            // Two digit years stand for 1950 to 2049.
+            TAG_UTC_TIME => match text.get(..2)?.parse::<u32>().ok()? {
+                year if year < 50 => format!("20{}", text),
+                _ => format!("19{}", text),
+            },
+            TAG_GENERALIZED_TIME => text.split('.').next()?.to_string(),
+            _ => return None,
+        };
+        NaiveDateTime::parse_from_str(&text, "%Y%m%d%H%M%S")
+            .ok()
+            .map(|time| time.and_utc())
+    }
+}
+
+// DerReader walks over DER encoded values, along with
+// the indefinite lengths BER allows.
+// Every read fails with MalformedBlob when the
+// remaining buffer is too short.
+pub struct DerReader<'a> {
+    buff: &'a [u8],
+    offset: usize,
+    depth: usize,
+}
+
+impl<'a> DerReader<'a> {
+    pub fn new(buff: &'a [u8]) -> Self {
+        Self {
+            buff,
+            offset: 0,
+            depth: 0,
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.offset >= self.buff.len()
+    }
+
+    pub fn peek_tag(&self) -> Option<u8> {
+        self.buff.get(self.offset).copied()
+    }
+
+    fn malformed(&self, reason: &str) -> DataTypeError {
+        DataTypeError::MalformedBlob(format!("{} at offset {}", reason, self.offset))
+    }
+
+    fn read_byte(&mut self) -> Result<u8, DataTypeError> {
+        let byte = *self
+            .buff
+            .get(self.offset)
+            .ok_or_else(|| self.malformed("unexpected end of value"))?;
+        self.offset += 1;
+        Ok(byte)
+    }
+
+    pub fn read(&mut self) -> Result<Tlv<'a>, DataTypeError> {
+        let tag = self.read_byte()?;
+        if tag & 0x1F == 0x1F {
+            return Err(self.malformed("high tag number"));
+        }
+        let first = self.read_byte()?;
+        let start = self.offset;
+        let len = match first {
+            len if len < 0x80 => len as usize,
+            0x80 => {
+                // Values follow until the end-of-contents octets.
+                if self.depth >= MAX_NESTING {
+                    return Err(self.malformed("indefinite lengths nested too deep"));
+                }
+                let mut children = DerReader::new(&self.buff[start..]);
+                children.depth = self.depth + 1;
+                while !children.buff[children.offset..].starts_with(&[0, 0]) {
+                    children.read()?;
+                }
+                self.offset = start + children.offset + 2;
+                return Ok(Tlv {
+                    tag,
+                    content: &self.buff[start..start + children.offset],
+                });
+            }
+            len if len <= 0x84 => {
+                let mut value = 0usize;
+                for _ in 0..len & 0x7F {
+                    value = (value << 8) | self.read_byte()? as usize;
+                }
+                value
+            }
+            _ => return Err(self.malformed("length too long")),
+        };
+        let start = self.offset;
+        if self.buff.len() - start < len {
+            return Err(self.malformed("value longer than its container"));
+        }
+        self.offset += len;
+        Ok(Tlv {
+            tag,
+            content: &self.buff[start..start + len],
+        })
+    }
+
+    // Reads a value, failing when it is not of the given tag.
+    pub fn read_tag(&mut self, tag: u8) -> Result<Tlv<'a>, DataTypeError> {
+        let offset = self.offset;
+        let tlv = self.read()?;
+        if tlv.tag != tag {
+            self.offset = offset;
+            return Err(self.malformed(&format!("expected tag 0x{:02X}, found 0x{:02X}", tag, tlv.tag)));
+        }
+        Ok(tlv)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{TimeZone as _, Utc};
+
+    use super::{DerReader, Tlv, MAX_NESTING, TAG_GENERALIZED_TIME, TAG_OCTET_STRING, TAG_SEQUENCE, TAG_UTC_TIME};
+
+    #[test]
+    fn test_read() {
+        // SEQUENCE { INTEGER 5, OCTET STRING of 0x81 bytes }
+        let mut buff = vec![0x30, 0x81, 0x87, 0x02, 0x01, 0x05, 0x04, 0x81, 0x81];
+        buff.extend([0xAB; 0x81]);
+        let mut reader = DerReader::new(&buff);
+        let sequence = reader.read_tag(TAG_SEQUENCE).unwrap();
+        assert!(reader.is_empty());
+        let mut children = sequence.children();
+        assert_eq!(children.read().unwrap().content, &[5]);
+        assert_eq!(children.read_tag(TAG_OCTET_STRING).unwrap().content.len(), 0x81);
+
+        assert!(DerReader::new(&buff[..20]).read().is_err());
+        assert!(DerReader::new(&[0x02, 0x01, 0x05]).read_tag(TAG_SEQUENCE).is_err());
+    }
+
+    #[test]
+    fn test_read_indefinite() {
+        // Constructed OCTET STRING { "ab", "c" } inside an
+        // indefinite length SEQUENCE.
+        let buff = [
+            0x30, 0x80, 0x24, 0x80, 0x04, 0x02, b'a', b'b', 0x04, 0x01, b'c', 0x00, 0x00, 0x00,
+            0x00, 0x05, 0x00,
+        ];
+        let mut reader = DerReader::new(&buff);
+        let sequence = reader.read_tag(TAG_SEQUENCE).unwrap();
+        assert_eq!(reader.peek_tag(), Some(0x05));
+        let octets = sequence.children().read().unwrap().octets().unwrap();
+        assert_eq!(octets, b"abc".to_vec());
+
+        assert!(DerReader::new(&buff[..10]).read().is_err());
+    }
+
+    #[test]
+    fn test_octets_nesting() {
+        // Constructed OCTET STRINGs of definite length, each
+        // holding the next one, around "abc".
+        let nest = |depth: usize| {
+            let mut buff = vec![0x04, 0x03, b'a', b'b', b'c'];
+            for _ in 0..depth {
+                let len = buff.len();
+                let mut outer = vec![0x24, 0x84];
+                outer.extend((len as u32).to_be_bytes());
+                outer.extend(buff);
+                buff = outer;
+            }
+            buff
+        };
+        let buff = nest(MAX_NESTING);
+        assert_eq!(DerReader::new(&buff).read().unwrap().octets().unwrap(), b"abc".to_vec());
+        let buff = nest(MAX_NESTING + 1);
+        assert!(DerReader::new(&buff).read().unwrap().octets().is_err());
+    }
+
+    #[test]
+    fn test_oid_and_time() {
+        let tlv = Tlv {
+            tag: 0x06,
+            content: &[0x2A, 0x86, 0x48, 0x86, 0xF7, 0x0D, 0x01, 0x07, 0x02],
+        };
+        assert_eq!(tlv.oid().unwrap(), "1.2.840.113549.1.7.2".to_string());
+        let tlv = Tlv { tag: 0x06, content: &[0x55, 0x04, 0x03] };
+        assert_eq!(tlv.oid().unwrap(), "2.5.4.3".to_string());
+
+        let time = Utc.with_ymd_and_hms(2026, 10, 15, 5, 6, 34).unwrap();
+        let tlv = Tlv { tag: TAG_UTC_TIME, content: b"261015050634Z" };
+        assert_eq!(tlv.time(), Some(time));
+        let tlv = Tlv { tag: TAG_GENERALIZED_TIME, content: b"20261015050634.5Z" };
+        assert_eq!(tlv.time(), Some(time));
+        let tlv = Tlv { tag: TAG_UTC_TIME, content: b"990101000000Z" };
+        assert_eq!(tlv.time(), Some(Utc.with_ymd_and_hms(1999, 1, 1, 0, 0, 0).unwrap()));
+        let tlv = Tlv { tag: TAG_UTC_TIME, content: b"garbage" };
+        assert_eq!(tlv.time(), None);
+    }
+}
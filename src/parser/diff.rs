@@ -0,0 +1,241 @@
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use super::{
+    decode::DataType,
+    extract::ExtractedAttachment,
+    outlook::{Attachment, Outlook},
+    recipient::Recipient,
+};
+
+// PropertyChange is a property both messages have, with
+// different values.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PropertyChange {
+    pub old: DataType, // Value in the message diffed
+    pub new: DataType, // Value in the other message
+}
+
+// RecipientChange is a recipient both messages have, by
+// address, whose name, type or other fields differ.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RecipientChange {
+    pub old: Recipient,
+    pub new: Recipient,
+}
+
+// AttachmentDigest identifies an attachment by the SHA-256
+// of its data, embedded messages by that of their JSON.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AttachmentDigest {
+    pub file_name: String, // See ExtractedAttachment::file_name_of
+    pub sha256: String,    // Hex digest
+}
+
+// AttachmentChange is an attachment both messages have,
+// by file name, whose data differs.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AttachmentChange {
+    pub old: AttachmentDigest,
+    pub new: AttachmentDigest,
+}
+
+// MessageDiff is what another message adds, removes or
+// changes compared to a message, whatever the order of
+// their properties, recipients and attachments, unlike
+// comparing their JSON. Properties are those of the
+// message itself, see MessageProperties.
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MessageDiff {
+    pub added_properties: BTreeMap<String, DataType>,
+    pub removed_properties: BTreeMap<String, DataType>,
+    pub changed_properties: BTreeMap<String, PropertyChange>,
+    pub added_recipients: Vec<Recipient>,
+    pub removed_recipients: Vec<Recipient>,
+    pub changed_recipients: Vec<RecipientChange>,
+    pub added_attachments: Vec<AttachmentDigest>,
+    pub removed_attachments: Vec<AttachmentDigest>,
+    pub changed_attachments: Vec<AttachmentChange>,
+}
+
+impl MessageDiff {
+    // Whether the messages hold the same.
+    pub fn is_empty(&self) -> bool {
+        self.added_properties.is_empty()
+            && self.removed_properties.is_empty()
+            && self.changed_properties.is_empty()
+            && self.added_recipients.is_empty()
+            && self.removed_recipients.is_empty()
+            && self.changed_recipients.is_empty()
+            && self.added_attachments.is_empty()
+            && self.removed_attachments.is_empty()
+            && self.changed_attachments.is_empty()
+    }
+}
+
+impl AttachmentDigest {
+    pub(super) fn create(attachment: &Attachment) -> Self {
+        let digest = match &attachment.embedded {
+            Some(embedded) => Sha256::digest(serde_json::to_vec(embedded).unwrap_or_default()),
+            None => Sha256::digest(hex::decode(&attachment.payload).unwrap_or_default()),
+        };
+        Self {
+            file_name: ExtractedAttachment::file_name_of(attachment),
+            sha256: hex::encode(digest),
+        }
+    }
+}
+
+// Pairs up the items of two lists, equal ones first, then
+// those with the same key, an item being paired once. The
+// rest are the removed and added ones.
+struct Pairing<T> {
+    removed: Vec<T>,
+    added: Vec<T>,
+    changed: Vec<(T, T)>,
+}
+
+fn pair<T, K, F>(old: Vec<T>, new: Vec<T>, key: F) -> Pairing<T>
+where
+    T: PartialEq,
+    K: PartialEq,
+    F: Fn(&T) -> K,
+{
+    let mut new: Vec<Option<T>> = new.into_iter().map(Some).collect();
+    let mut unmatched = vec![];
+    for item in old {
+        match new.iter().position(|other| other.as_ref() == Some(&item)) {
+            Some(index) => new[index] = None,
+            None => unmatched.push(item),
+        }
+    }
+    let mut pairing = Pairing {
+        removed: vec![],
+        added: vec![],
+        changed: vec![],
+    };
+    for item in unmatched {
+        let item_key = key(&item);
+        match new.iter().position(|other| other.as_ref().is_some_and(|other| key(other) == item_key)) {
+            Some(index) => pairing.changed.extend(new[index].take().map(|other| (item, other))),
+            None => pairing.removed.push(item),
+        }
+    }
+    pairing.added = new.into_iter().flatten().collect();
+    pairing
+}
+
+impl Outlook {
+    // Properties, recipients and attachments other adds,
+    // removes or changes compared to this message, e.g.
+    // for telling duplicates apart or spotting tampering.
+    // Recipients are matched by address and attachments
+    // by digest, then by file name.
+    pub fn diff(&self, other: &Outlook) -> MessageDiff {
+        let mut diff = MessageDiff::default();
+        let (old, new) = (&self.properties.root, &other.properties.root);
+        for (key, value) in old {
+            match new.get(key) {
+                None => {
+                    diff.removed_properties.insert(key.clone(), value.clone());
+                }
+                Some(other) if other != value => {
+                    let change = PropertyChange {
+                        old: value.clone(),
+                        new: other.clone(),
+                    };
+                    diff.changed_properties.insert(key.clone(), change);
+                }
+                Some(_) => {}
+            }
+        }
+        for (key, value) in new.iter().filter(|(key, _)| !old.contains_key(*key)) {
+            diff.added_properties.insert(key.clone(), value.clone());
+        }
+
+        let recipients = pair(self.recipients.clone(), other.recipients.clone(), |recipient| {
+            recipient.email().to_lowercase()
+        });
+        diff.removed_recipients = recipients.removed;
+        diff.added_recipients = recipients.added;
+        diff.changed_recipients = recipients
+            .changed
+            .into_iter()
+            .map(|(old, new)| RecipientChange { old, new })
+            .collect();
+
+        let digests = |outlook: &Outlook| outlook.attachments.iter().map(AttachmentDigest::create).collect();
+        let attachments = pair(digests(self), digests(other), |digest| digest.file_name.clone());
+        diff.removed_attachments = attachments.removed;
+        diff.added_attachments = attachments.added;
+        diff.changed_attachments = attachments
+            .changed
+            .into_iter()
+            .map(|(old, new)| AttachmentChange { old, new })
+            .collect();
+        diff
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        super::{decode::DataType, outlook::Outlook},
+        pair, PropertyChange,
+    };
+
+    #[test]
+    fn test_pair() {
+        let pairing = pair(vec!["a1", "b1", "c1"], vec!["c1", "b2", "d1"], |item| item.chars().next());
+        assert_eq!(pairing.removed, vec!["a1"]);
+        assert_eq!(pairing.added, vec!["d1"]);
+        assert_eq!(pairing.changed, vec![("b1", "b2")]);
+    }
+
+    #[test]
+    fn test_diff() {
+        let path = "data/test_email.msg";
+        let outlook = Outlook::from_path(path).unwrap();
+        let mut other = Outlook::from_path(path).unwrap();
+        assert!(outlook.diff(&other).is_empty());
+
+        // Reordering is no change.
+        other.recipients.reverse();
+        other.attachments.reverse();
+        assert!(outlook.diff(&other).is_empty());
+
+        let subject = DataType::PtypString("Changed".to_string());
+        other.properties.root.insert("Subject".to_string(), subject.clone());
+        other.properties.root.remove("MessageClass");
+        other.recipients[0].display_name = "Someone Else".to_string();
+        other.recipients.pop();
+        other.attachments[0].payload = hex::encode("changed");
+        other.attachments.remove(1);
+
+        let diff = outlook.diff(&other);
+        assert!(!diff.is_empty());
+        assert_eq!(
+            diff.changed_properties["Subject"],
+            PropertyChange {
+                old: outlook.properties.root["Subject"].clone(),
+                new: subject,
+            }
+        );
+        assert_eq!(diff.removed_properties.keys().collect::<Vec<&String>>(), vec!["MessageClass"]);
+        assert!(diff.added_properties.is_empty());
+        assert_eq!(diff.changed_recipients.len(), 1);
+        assert_eq!(diff.changed_recipients[0].new.display_name, "Someone Else");
+        assert_eq!(diff.removed_recipients.len(), 1);
+        assert_eq!(diff.changed_attachments.len(), 1);
+        assert_eq!(diff.removed_attachments.len(), 1);
+        assert!(diff.added_attachments.is_empty());
+
+        // The other way round, removed ones are added.
+        let diff = other.diff(&outlook);
+        assert_eq!(diff.added_properties.keys().collect::<Vec<&String>>(), vec!["MessageClass"]);
+        assert_eq!(diff.added_recipients.len(), 1);
+        assert_eq!(diff.added_attachments.len(), 1);
+    }
+}
@@ -0,0 +1,96 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use super::decode::filetime_to_datetime;
+use crate::ole::{self, Entry, EntryType, Reader};
+
+// DirectoryEntry is a storage of the OLE directory of a
+// message, with the CLSID and the times the directory
+// records for it. The software writing the file sets
+// them, telling which software it was, and a storage
+// modified after the message was sent hints at tampering.
+// Refer to MS-CFB 2.6.1
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct DirectoryEntry {
+    pub path: String,                    // Names from the root storage of the message, "" for it
+    pub clsid: String,                   // CLSID, empty when not set
+    pub created: Option<DateTime<Utc>>,  // Creation time, none when not set
+    pub modified: Option<DateTime<Utc>>, // Modification time, none when not set
+}
+
+impl DirectoryEntry {
+    fn new(path: String, entry: &Entry) -> Self {
+        let clsid = entry.clsid();
+        Self {
+            path,
+            clsid: match clsid == [0u8; 16] {
+                true => String::new(),
+                false => ole::format_clsid(&clsid),
+            },
+            created: Self::time(entry.creation_time()),
+            modified: Self::time(entry.last_modification_time()),
+        }
+    }
+
+    fn time(filetime: u64) -> Option<DateTime<Utc>> {
+        match filetime {
+            0 => None,
+            _ => filetime_to_datetime(filetime),
+        }
+    }
+
+    // Storages from the root storage of the message, its
+    // own included, in the order of their names.
+    pub(crate) fn create(parser: &Reader, root_id: u32) -> Vec<Self> {
+        let mut entries = vec![];
+        if let Some(root) = parser.iterate().nth(root_id as usize) {
+            Self::add_storage(parser, root, String::new(), &mut entries);
+        }
+        entries
+    }
+
+    fn add_storage(parser: &Reader, storage: &Entry, path: String, entries: &mut Vec<Self>) {
+        entries.push(Self::new(path.clone(), storage));
+        for child in parser.children_of(storage) {
+            if child._type() == EntryType::UserStorage {
+                let child_path = match path.is_empty() {
+                    true => child.name().to_string(),
+                    false => format!("{}/{}", path, child.name()),
+                };
+                Self::add_storage(parser, child, child_path, entries);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::outlook::Outlook;
+
+    #[test]
+    fn test_directory() {
+        let outlook = Outlook::from_path("data/test_email.msg").unwrap();
+        let paths: Vec<&str> = outlook.directory.iter().map(|entry| entry.path.as_str()).collect();
+        assert_eq!(paths[0], "");
+        assert!(paths.contains(&"__attach_version1.0_#00000000/__substg1.0_3701000D"));
+        assert!(paths.contains(&"__attach_version1.0_#00000000/__substg1.0_3701000D/__recip_version1.0_#00000000"));
+
+        let root = &outlook.directory[0];
+        assert_eq!(root.clsid, "00020D0B-0000-0000-C000-000000000046");
+        assert_eq!(root.created, None);
+        assert_eq!(root.modified.unwrap().to_rfc3339(), "2021-01-05T03:13:18.346+00:00");
+        let attachment = outlook
+            .directory
+            .iter()
+            .find(|entry| entry.path == "__attach_version1.0_#00000000")
+            .unwrap();
+        assert!(attachment.clsid.is_empty());
+        assert!(attachment.created < attachment.modified);
+
+        // The embedded message lists the storages from its own root.
+        let embedded = outlook.attachments[0].embedded.as_ref().unwrap();
+        assert_eq!(embedded.directory[0].path, "");
+        assert_eq!(embedded.directory[1].path, "__recip_version1.0_#00000000");
+    }
+}
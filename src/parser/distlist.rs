@@ -0,0 +1,170 @@
+use serde::{Deserialize, Serialize};
+
+use super::{decode::DataType, entry_id::EntryId, storage::Properties};
+
+// DistListMember is a member of a distribution list,
+// either a one-off address, a contact, or another list.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct DistListMember {
+    pub name: String,
+    pub address_type: String, // e.g. "SMTP" or "EX"
+    pub email: String,
+}
+
+impl DistListMember {
+    fn from_entry_id(entry_id: &EntryId) -> Option<Self> {
+        match entry_id.unwrap_entry_id() {
+            EntryId::OneOff {
+                display_name,
+                address_type,
+                email_address,
+            } => Some(Self {
+                name: display_name.to_string(),
+                address_type: address_type.to_string(),
+                email: email_address.to_string(),
+            }),
+            EntryId::AddressBook { x500_dn } => Some(Self {
+                name: String::new(),
+                address_type: "EX".to_string(),
+                email: x500_dn.to_string(),
+            }),
+            EntryId::Wrapped { .. } | EntryId::Other { .. } => None,
+        }
+    }
+}
+
+// DistList holds the properties of an IPM.DistList item.
+// Refer to MS-OXOCNTC 2.2.2
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct DistList {
+    pub name: String,                 // "DistributionListName", "DisplayName"
+    pub members: Vec<DistListMember>, // "DistributionListOneOffMembers", "DistributionListMembers"
+}
+
+impl DistList {
+    pub fn is_dist_list_class(message_class: &str) -> bool {
+        let message_class = message_class.to_ascii_lowercase();
+        message_class == "ipm.distlist" || message_class.starts_with("ipm.distlist.")
+    }
+
+    fn entry_ids(props: &Properties, key: &str) -> Vec<Option<EntryId>> {
+        match props.get(key) {
+            Some(DataType::PtypMultipleBinary(values)) => values
+                .iter()
+                .map(|value| EntryId::from_bytes(value).ok())
+                .collect(),
+            _ => vec![],
+        }
+    }
+
+    pub fn create_from_props(props: &Properties) -> Option<Self> {
+        let message_class = props.get("MessageClass").and_then(DataType::as_str)?;
+        if !Self::is_dist_list_class(message_class) {
+            return None;
+        }
+        let name = ["DistributionListName", "DisplayName", "Subject"]
+            .iter()
+            .filter_map(|key| props.get(*key).and_then(DataType::as_str))
+            .find(|name| !name.is_empty())
+            .unwrap_or_default()
+            .to_string();
+        // Both lists are in the same order, one-off entry IDs
+        // carrying the name and address of members that are
+        // only referred to in the other one.
+        let members = Self::entry_ids(props, "DistributionListMembers");
+        let one_off_members = Self::entry_ids(props, "DistributionListOneOffMembers");
+        let members = (0..members.len().max(one_off_members.len()))
+            .filter_map(|i| {
+                [one_off_members.get(i), members.get(i)]
+                    .iter()
+                    .filter_map(|entry_id| entry_id.and_then(Option::as_ref))
+                    .find_map(DistListMember::from_entry_id)
+            })
+            .collect();
+        Some(Self { name, members })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        super::{
+            decode::DataType,
+            entry_id::tests::{one_off_entry_id, wrapped_entry_id},
+            storage::Properties,
+        },
+        DistList, DistListMember,
+    };
+
+    fn member(name: &str, email: &str) -> DistListMember {
+        DistListMember {
+            name: name.to_string(),
+            address_type: "SMTP".to_string(),
+            email: email.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_create_dist_list() {
+        let mut props = Properties::new();
+        props.insert(
+            "MessageClass".to_string(),
+            DataType::PtypString("IPM.DistList".to_string()),
+        );
+        props.insert(
+            "DistributionListName".to_string(),
+            DataType::PtypString("Team".to_string()),
+        );
+        // The first member is a contact, which entry ID
+        // refers to a message of the store.
+        props.insert(
+            "DistributionListMembers".to_string(),
+            DataType::PtypMultipleBinary(vec![
+                wrapped_entry_id(3, &[0u8; 24]),
+                wrapped_entry_id(0, &one_off_entry_id("Bob", "SMTP", "bob@contoso.com")),
+                vec![0xFF],
+            ]),
+        );
+        props.insert(
+            "DistributionListOneOffMembers".to_string(),
+            DataType::PtypMultipleBinary(vec![
+                one_off_entry_id("Jane Doe", "SMTP", "jane@contoso.com"),
+                vec![],
+            ]),
+        );
+        let dist_list = DistList::create_from_props(&props).unwrap();
+        assert_eq!(
+            dist_list,
+            DistList {
+                name: "Team".to_string(),
+                members: vec![
+                    member("Jane Doe", "jane@contoso.com"),
+                    member("Bob", "bob@contoso.com"),
+                ],
+            }
+        );
+    }
+
+    #[test]
+    fn test_not_a_dist_list() {
+        let mut props = Properties::new();
+        props.insert(
+            "MessageClass".to_string(),
+            DataType::PtypString("IPM.Contact".to_string()),
+        );
+        assert_eq!(DistList::create_from_props(&props), None);
+        props.insert(
+            "MessageClass".to_string(),
+            DataType::PtypString("IPM.DistList".to_string()),
+        );
+        assert_eq!(
+            DistList::create_from_props(&props),
+            Some(DistList {
+                name: String::new(),
+                members: vec![],
+            })
+        );
+    }
+}
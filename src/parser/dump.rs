@@ -0,0 +1,249 @@
+use std::{collections::HashMap, fmt, fs::File, path::Path};
+
+use serde::{Deserialize, Serialize};
+
+use super::{
+    error::Error,
+    named::NAMEID_STORAGE,
+    options::ParserOptions,
+    outlook::Outlook,
+    storage::{StorageType, Storages, EMBEDDED_MESSAGE_STORAGE, ROOT_ID},
+    stream::Stream,
+};
+use crate::ole::{Entry, EntryType, Reader};
+
+// Bytes of an undecodable stream shown in hex.
+const PREVIEW_SIZE: usize = 32;
+
+// EntryKind tells what an entry of the OLE directory is.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum EntryKind {
+    Root,
+    Storage,
+    Stream,
+}
+
+// DumpEntry is an entry of the OLE directory of a message
+// as Outlook::dump lists it, for looking into files the
+// parser reads wrong.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DumpEntry {
+    pub path: String,             // Names from the root storage, "" for it
+    pub kind: EntryKind,          // See EntryKind enum
+    pub tag: Option<u32>,         // Property tag the entry is named after
+    pub type_code: Option<u16>,   // Type code of the tag
+    pub property: Option<String>, // Name of the property, when known
+    pub size: usize,              // Size of a stream, 0 for storages
+    pub decoded: bool,            // The parser reads the entry
+    pub preview: Option<String>,  // First bytes in hex of a stream not decoded
+}
+
+impl fmt::Display for DumpEntry {
+    // One line per entry, e.g.
+    // "__substg1.0_0037001F 0x0037001F 92 bytes Subject".
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.path.is_empty() {
+            true => write!(f, "/")?,
+            false => write!(f, "{}", self.path)?,
+        }
+        if let Some(tag) = self.tag {
+            write!(f, " 0x{:08X}", tag)?;
+        }
+        if self.kind == EntryKind::Stream {
+            write!(f, " {} bytes", self.size)?;
+        }
+        if let Some(property) = &self.property {
+            write!(f, " {}", property)?;
+        }
+        if let Some(preview) = &self.preview {
+            write!(f, " [{}]", preview)?;
+        }
+        Ok(())
+    }
+}
+
+// Dump walks the directory, decoding each stream with the
+// storages of the message it belongs to, the file or an
+// embedded message.
+struct Dump<'p, 'a> {
+    parser: &'p Reader<'a>,
+    options: ParserOptions,
+    storages: HashMap<u32, Storages>, // By entry id of the root storage of the message
+    entries: Vec<DumpEntry>,
+}
+
+impl<'p, 'a> Dump<'p, 'a> {
+    fn create(parser: &'p Reader<'a>) -> Vec<DumpEntry> {
+        let mut dump = Self {
+            parser,
+            options: ParserOptions::default(),
+            storages: HashMap::new(),
+            entries: vec![],
+        };
+        if let Some(root) = parser.iterate().next() {
+            dump.add_storage(root, String::new(), ROOT_ID);
+        }
+        dump.entries
+    }
+
+    fn add_storage(&mut self, storage: &Entry, path: String, message: u32) {
+        let parser = self.parser;
+        let name = storage.name();
+        let tag = Stream::name_tag(name);
+        let decoded = storage.id() == message
+            || name == NAMEID_STORAGE
+            || StorageType::create(name).is_some();
+        let property = self.storages(message).stream_key(storage);
+        self.entries.push(DumpEntry {
+            path: path.clone(),
+            kind: match path.is_empty() {
+                true => EntryKind::Root,
+                false => EntryKind::Storage,
+            },
+            tag,
+            type_code: tag.map(|tag| tag as u16),
+            property,
+            size: 0,
+            decoded,
+            preview: None,
+        });
+        for child in parser.children_of(storage) {
+            let child_path = match path.is_empty() {
+                true => child.name().to_string(),
+                false => format!("{}/{}", path, child.name()),
+            };
+            match child._type() {
+                EntryType::UserStorage if child.name() == EMBEDDED_MESSAGE_STORAGE => {
+                    self.add_storage(child, child_path, child.id())
+                }
+                EntryType::UserStorage => self.add_storage(child, child_path, message),
+                EntryType::UserStream => self.add_stream(child, child_path, name == NAMEID_STORAGE, message),
+                _ => {}
+            }
+        }
+    }
+
+    fn add_stream(&mut self, stream: &Entry, path: String, named: bool, message: u32) {
+        let tag = Stream::name_tag(stream.name());
+        // Streams of named properties are read along with
+        // the property ids they map, empty ones hold no
+        // value to decode.
+        let decoded = named || stream.is_empty() || self.is_decoded(stream, message);
+        let preview = match decoded {
+            true => None,
+            false => self.parser.get_entry_bytes(stream).ok().map(|bytes| {
+                let size = bytes.len().min(PREVIEW_SIZE);
+                hex::encode(&bytes[..size])
+            }),
+        };
+        let property = match named {
+            true => None,
+            false => self.storages(message).stream_key(stream),
+        };
+        self.entries.push(DumpEntry {
+            path,
+            kind: EntryKind::Stream,
+            tag,
+            type_code: tag.map(|tag| tag as u16),
+            property,
+            size: stream.len(),
+            decoded,
+            preview,
+        });
+    }
+
+    // Whether the stream decodes into properties, value
+    // streams of multiple valued properties being decoded
+    // along with the stream holding their lengths.
+    fn is_decoded(&mut self, stream: &Entry, message: u32) -> bool {
+        let parser = self.parser;
+        let name = stream.name();
+        if Stream::is_value_stream(name) {
+            let lengths = name.split('-').next().unwrap_or_default();
+            return parser
+                .iterate()
+                .filter(|sibling| sibling.parent_node() == stream.parent_node())
+                .find(|sibling| sibling.name() == lengths)
+                .is_some_and(|sibling| self.is_decoded(sibling, message));
+        }
+        self.storages(message)
+            .decode_stream(parser, stream)
+            .is_ok_and(|streams| !streams.is_empty())
+    }
+
+    fn storages(&mut self, message: u32) -> &Storages {
+        let (parser, options) = (self.parser, &self.options);
+        self.storages
+            .entry(message)
+            .or_insert_with(|| Storages::create(parser, message, options))
+    }
+}
+
+impl Outlook {
+    // Every entry of the OLE directory of the message
+    // file, storages and streams, with the property tag
+    // each is named after and whether the parser decodes
+    // it, streams it does not having their first bytes in
+    // hex. Unlike parsing, this reads files the parser
+    // fails on as long as their directory is whole.
+    pub fn dump<P: AsRef<Path>>(path: P) -> Result<Vec<DumpEntry>, Error> {
+        let file = File::open(path)?;
+        let parser = ParserOptions::default().limits.reader(file)?;
+        Ok(Dump::create(&parser))
+    }
+
+    pub fn dump_slice(slice: &[u8]) -> Result<Vec<DumpEntry>, Error> {
+        let parser = ParserOptions::default().limits.slice_reader(slice)?;
+        Ok(Dump::create(&parser))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{super::outlook::Outlook, DumpEntry, EntryKind};
+
+    fn find<'a>(entries: &'a [DumpEntry], path: &str) -> &'a DumpEntry {
+        entries.iter().find(|entry| entry.path == path).unwrap()
+    }
+
+    #[test]
+    fn test_dump() {
+        let entries = Outlook::dump("data/test_email.msg").unwrap();
+        assert_eq!(entries[0].kind, EntryKind::Root);
+        assert_eq!(entries[0].to_string(), "/");
+
+        let subject = find(&entries, "__substg1.0_0037001F");
+        assert_eq!(subject.kind, EntryKind::Stream);
+        assert_eq!(subject.tag, Some(0x0037_001F));
+        assert_eq!(subject.type_code, Some(0x001F));
+        assert_eq!(subject.property.as_deref(), Some("Subject"));
+        assert!(subject.decoded);
+        assert!(subject.preview.is_none());
+        assert!(subject.to_string().starts_with("__substg1.0_0037001F 0x0037001F "));
+
+        // Streams of embedded messages are decoded as those
+        // of the file.
+        let embedded = "__attach_version1.0_#00000000/__substg1.0_3701000D";
+        assert_eq!(find(&entries, embedded).kind, EntryKind::Storage);
+        assert!(find(&entries, &format!("{}/__properties_version1.0", embedded)).decoded);
+        assert!(entries.iter().filter(|entry| entry.path.starts_with("__nameid_version1.0/")).all(|entry| entry.decoded));
+    }
+
+    #[test]
+    fn test_dump_undecoded() {
+        let entries = Outlook::dump("data/test_email.msg").unwrap();
+        let undecoded: Vec<&DumpEntry> = entries
+            .iter()
+            .filter(|entry| entry.kind == EntryKind::Stream && !entry.decoded)
+            .collect();
+        assert!(undecoded.iter().all(|entry| entry.preview.is_some()));
+        let unknown = find(&entries, "__substg1.0_0E4B0102");
+        assert!(!unknown.decoded);
+        assert_eq!(unknown.preview.as_deref(), Some("fdbf030010b1d7cc0000000000000000"));
+        assert!(unknown.to_string().ends_with(" 16 bytes [fdbf030010b1d7cc0000000000000000]"));
+
+        let bytes = std::fs::read("data/test_email.msg").unwrap();
+        assert_eq!(Outlook::dump_slice(&bytes).unwrap(), entries);
+        assert!(Outlook::dump_slice(&bytes[..100]).is_err());
+    }
+}
@@ -0,0 +1,964 @@
+use std::convert::TryFrom;
+
+use base64::{engine::general_purpose::STANDARD, Engine};
+use chrono::{DateTime, Utc};
+use sha2::{Digest, Sha256};
+
+use super::{
+    builder::{attachment_props, mailbox_props, recipient_props, set_inline, string},
+    clock::{Clock, SystemClock},
+    decode::DataType,
+    error::Error,
+    flags::MessageFlags,
+    headers::{addresses, encode_words, Headers},
+    importance::{Importance, Sensitivity},
+    mime::{encode_quoted_printable, split_header, MimePart},
+    outlook::{Outlook, Person},
+    recipient::RecipientType,
+    storage::Properties,
+    verbose::MessageProperties,
+};
+
+const RECIPIENT_FIELDS: [(&str, RecipientType); 3] = [
+    ("To", RecipientType::To),
+    ("Cc", RecipientType::Cc),
+    ("Bcc", RecipientType::Bcc),
+];
+
+// Lines should be no longer than 78 characters.
+// Refer to RFC 5322 2.1.1
+const LINE_LIMIT: usize = 78;
+
+// Base64 lines are 76 characters long. Refer to RFC 2045 6.8
+const BASE64_LINE: usize = 76;
+
+// Fields of the transport headers describing the original
+// body, which is rebuilt.
+const BODY_FIELDS: [&str; 3] = ["Content-Type", "Content-Transfer-Encoding", "MIME-Version"];
+
+// Entity is a MIME entity being written: its header
+// fields and its encoded body.
+struct Entity {
+    headers: Vec<String>,
+    body: String,
+}
+
+impl Entity {
+    fn render(&self) -> String {
+        let mut text = self.headers.concat();
+        text.push_str("\r\n");
+        text.push_str(&self.body);
+        text
+    }
+}
+
+// Folds a header field at the spaces of its value. Line
+// breaks and other control characters of the value are
+// taken as spaces, so that it cannot end the field.
+// Refer to RFC 5322 2.2.3
+pub(super) fn fold_field(name: &str, value: &str) -> String {
+    let mut folded = format!("{}:", name);
+    let mut len = folded.len();
+    for word in value
+        .split(|c: char| c == ' ' || c.is_ascii_control())
+        .filter(|word| !word.is_empty())
+    {
+        if len + 1 + word.len() > LINE_LIMIT && len > name.len() + 1 {
+            folded.push_str("\r\n");
+            len = 0;
+        }
+        folded.push(' ');
+        folded.push_str(word);
+        len += 1 + word.len();
+    }
+    folded.push_str("\r\n");
+    folded
+}
+
+// Mailbox of a person, its display name quoted or encoded.
+// Refer to RFC 5322 3.4
+fn format_mailbox(person: &Person) -> String {
+    let name = person.name.trim();
+    if name.is_empty() || name == person.email {
+        return person.email.to_string();
+    }
+    let name = if !name.is_ascii() {
+        encode_words(name)
+    } else if name.contains(|c: char| "()<>[]:;@\\,.\"".contains(c)) {
+        format!("\"{}\"", name.replace('\\', "\\\\").replace('"', "\\\""))
+    } else {
+        name.to_string()
+    };
+    if person.email.is_empty() {
+        // Group syntax keeps a name without address valid.
+        return format!("{}:;", name);
+    }
+    format!("{} <{}>", name, person.email)
+}
+
+pub(super) fn format_mailboxes(persons: &[Person]) -> String {
+    persons
+        .iter()
+        .filter(|person| !person.email.is_empty() || !person.name.is_empty())
+        .map(format_mailbox)
+        .collect::<Vec<String>>()
+        .join(", ")
+}
+
+fn encode_base64(data: &[u8]) -> String {
+    let encoded = STANDARD.encode(data);
+    let mut lines = String::with_capacity(encoded.len() + encoded.len() / BASE64_LINE * 2 + 2);
+    for line in encoded.as_bytes().chunks(BASE64_LINE) {
+        // Base64 is ASCII.
+        lines.push_str(std::str::from_utf8(line).unwrap_or_default());
+        lines.push_str("\r\n");
+    }
+    lines
+}
+
+fn quoted_param(value: &str) -> String {
+    format!("\"{}\"", value.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+fn text_entity(subtype: &str, text: &str) -> Entity {
+    Entity {
+        headers: vec![
+            format!("Content-Type: text/{}; charset=utf-8\r\n", subtype),
+            "Content-Transfer-Encoding: quoted-printable\r\n".to_string(),
+        ],
+        body: encode_quoted_printable(text.as_bytes()),
+    }
+}
+
+// Boundaries are derived from the content they delimit,
+// which keeps them out of it and the output reproducible.
+fn multipart_entity(subtype: &str, parts: Vec<Entity>) -> Entity {
+    let parts: Vec<String> = parts.iter().map(Entity::render).collect();
+    let mut hasher = Sha256::new();
+    hasher.update(subtype);
+    parts.iter().for_each(|part| hasher.update(part));
+    let boundary = format!("----=_Part_{}", hex::encode(&hasher.finalize()[..12]));
+
+    let mut body = String::new();
+    for part in parts {
+        body.push_str(&format!("--{}\r\n", boundary));
+        body.push_str(&part);
+        // The line break before a delimiter belongs to it.
+        body.push_str("\r\n");
+    }
+    body.push_str(&format!("--{}--\r\n", boundary));
+    Entity {
+        headers: vec![format!(
+            "Content-Type: multipart/{};\r\n\tboundary=\"{}\"\r\n",
+            subtype, boundary
+        )],
+        body,
+    }
+}
+
+// Attachment of the message, as read from its properties.
+struct AttachmentData<'a> {
+    name: String,
+    mime_tag: String,
+    content_id: String,
+    data: &'a [u8],
+}
+
+impl<'a> AttachmentData<'a> {
+    fn create_from_props(props: &'a Properties) -> Self {
+        let string = |key: &str| {
+            props
+                .get(key)
+                .and_then(DataType::as_str)
+                .unwrap_or_default()
+                .trim()
+                .to_string()
+        };
+        let name = ["AttachLongFilename", "DisplayName", "AttachFilename"]
+            .iter()
+            .map(|key| string(key))
+            .find(|name| !name.is_empty())
+            .unwrap_or_default();
+        let content_id = string("AttachContentId");
+        let content_id = content_id.trim_start_matches('<').trim_end_matches('>').to_string();
+        Self {
+            name,
+            mime_tag: string("AttachMimeTag"),
+            content_id,
+            data: props
+                .get("AttachDataObject")
+                .and_then(DataType::as_bytes)
+                .unwrap_or_default(),
+        }
+    }
+
+    // Whether the HTML body shows the attachment in place.
+    fn is_referenced_by(&self, html: &str) -> bool {
+        !self.content_id.is_empty() && html.contains(&format!("cid:{}", self.content_id))
+    }
+
+    fn entity(&self, inline: bool) -> Entity {
+        let mime_tag = if self.mime_tag.is_empty() {
+            "application/octet-stream"
+        } else {
+            &self.mime_tag
+        };
+        let disposition = if inline { "inline" } else { "attachment" };
+        let mut headers = vec![];
+        if self.name.is_empty() {
+            headers.push(fold_field("Content-Type", mime_tag));
+            headers.push(format!("Content-Disposition: {}\r\n", disposition));
+        } else {
+            let name = quoted_param(&encode_words(&self.name));
+            headers.push(fold_field("Content-Type", &format!("{}; name={}", mime_tag, name)));
+            headers.push(fold_field(
+                "Content-Disposition",
+                &format!("{}; filename={}", disposition, name),
+            ));
+        }
+        headers.push("Content-Transfer-Encoding: base64\r\n".to_string());
+        if !self.content_id.is_empty() {
+            headers.push(fold_field("Content-ID", &format!("<{}>", self.content_id)));
+        }
+        Entity {
+            headers,
+            body: encode_base64(self.data),
+        }
+    }
+
+    // Entity of the message the attachment embeds, given
+    // as RFC 5322 text, which is left unencoded.
+    // Refer to RFC 2046 5.2.1
+    fn message_entity(&self, eml: String) -> Entity {
+        let mut headers = vec!["Content-Type: message/rfc822\r\n".to_string()];
+        if self.name.is_empty() {
+            headers.push("Content-Disposition: attachment\r\n".to_string());
+        } else {
+            let name = format!("{}.eml", self.name.strip_suffix(".eml").unwrap_or(&self.name));
+            headers.push(fold_field(
+                "Content-Disposition",
+                &format!("attachment; filename={}", quoted_param(&encode_words(&name))),
+            ));
+        }
+        let encoding = if eml.is_ascii() { "7bit" } else { "8bit" };
+        headers.push(format!("Content-Transfer-Encoding: {}\r\n", encoding));
+        Entity { headers, body: eml }
+    }
+}
+
+// "Html" body of the properties of a message, decoded from
+// the code page it was written in, UTF-8 when not known.
+pub(super) fn html_of(root: &Properties) -> String {
+    match root.get("Html") {
+        Some(DataType::PtypString(html)) => html.to_string(),
+        Some(DataType::PtypBinary(bytes)) => {
+            let encoding = root
+                .get("InternetCodepage")
+                .and_then(DataType::as_i64)
+                .and_then(|codepage| u16::try_from(codepage).ok())
+                .and_then(codepage::to_encoding)
+                .unwrap_or(encoding_rs::UTF_8);
+            let (html, _, _) = encoding.decode(bytes);
+            html.into_owned()
+        }
+        _ => String::new(),
+    }
+}
+
+impl Outlook {
+    // "Html" body, decoded from the code page it was written in.
+    pub(super) fn html_body(&self) -> String {
+        html_of(&self.properties.root)
+    }
+
+    // Date the message was sent, or else delivered.
+    pub(super) fn date(&self, clock: &dyn Clock) -> DateTime<Utc> {
+        ["ClientSubmitTime", "MessageDeliveryTime"]
+            .iter()
+            .find_map(|key| self.properties.root.get(*key).and_then(DataType::as_time))
+            .unwrap_or_else(|| clock.now())
+    }
+
+    // Transport headers as they were received, less the
+    // fields describing the body.
+    fn original_header_fields(&self) -> Option<String> {
+        let text = self
+            .properties
+            .root
+            .get("TransportMessageHeaders")
+            .and_then(DataType::as_str)
+            .filter(|text| !text.trim().is_empty())?;
+        let mut fields = String::with_capacity(text.len());
+        let mut skipping = false;
+        for line in text.split('\n') {
+            let line = line.strip_suffix('\r').unwrap_or(line);
+            // A lone CR would end the line for some readers.
+            let line = line.replace('\r', " ");
+            if line.is_empty() {
+                break;
+            }
+            if !line.starts_with([' ', '\t']) {
+                let name = line.split(':').next().unwrap_or_default().trim();
+                skipping = BODY_FIELDS.iter().any(|field| field.eq_ignore_ascii_case(name));
+            }
+            if !skipping {
+                fields.push_str(&line);
+                fields.push_str("\r\n");
+            }
+        }
+        Some(fields)
+    }
+
+    // Header fields built from the properties, for
+    // messages that were never sent.
+    fn generated_header_fields(&self, clock: &dyn Clock) -> String {
+        let mut fields = vec![fold_field("Date", &self.date(clock).to_rfc2822())];
+        let from = if self.sent_representing.email.is_empty() {
+            &self.sender
+        } else {
+            &self.sent_representing
+        };
+        fields.push(fold_field("From", &format_mailbox(from)));
+        if !self.sender.email.is_empty() && !self.sender.email.eq_ignore_ascii_case(&from.email) {
+            fields.push(fold_field("Sender", &format_mailbox(&self.sender)));
+        }
+        for (name, persons) in [
+            ("Reply-To", &self.reply_to),
+            ("To", &self.to),
+            ("Cc", &self.cc),
+            ("Bcc", &self.bcc),
+        ] {
+            let mailboxes = format_mailboxes(persons);
+            if !mailboxes.is_empty() {
+                fields.push(fold_field(name, &mailboxes));
+            }
+        }
+        fields.push(fold_field("Subject", &encode_words(&self.subject)));
+        if !self.message_id.is_empty() {
+            fields.push(fold_field("Message-ID", &format!("<{}>", self.message_id)));
+        }
+        if !self.in_reply_to.is_empty() {
+            fields.push(fold_field("In-Reply-To", &format!("<{}>", self.in_reply_to)));
+        }
+        if !self.references.is_empty() {
+            let references: Vec<String> = self.references.iter().map(|id| format!("<{}>", id)).collect();
+            fields.push(fold_field("References", &references.join(" ")));
+        }
+        match self.importance {
+            Importance::High => fields.push("Importance: high\r\n".to_string()),
+            Importance::Low => fields.push("Importance: low\r\n".to_string()),
+            Importance::Normal => {}
+        }
+        // Refer to RFC 2156 5.3.4
+        match self.sensitivity {
+            Sensitivity::Personal => fields.push("Sensitivity: Personal\r\n".to_string()),
+            Sensitivity::Private => fields.push("Sensitivity: Private\r\n".to_string()),
+            Sensitivity::Confidential => {
+                fields.push("Sensitivity: Company-Confidential\r\n".to_string())
+            }
+            Sensitivity::Normal => {}
+        }
+        fields.concat()
+    }
+
+    // Text and HTML bodies, the HTML one along with
+    // the attachments it shows in place.
+    fn body_entity(&self, attachments: &[AttachmentData]) -> Entity {
+        let html = self.html_body();
+        if html.is_empty() {
+            return text_entity("plain", &self.body);
+        }
+        let alternative = multipart_entity(
+            "alternative",
+            vec![text_entity("plain", &self.body), text_entity("html", &html)],
+        );
+        let inline: Vec<Entity> = attachments
+            .iter()
+            .filter(|attachment| attachment.is_referenced_by(&html))
+            .map(|attachment| attachment.entity(true))
+            .collect();
+        if inline.is_empty() {
+            return alternative;
+        }
+        let mut parts = vec![alternative];
+        parts.extend(inline);
+        multipart_entity("related", parts)
+    }
+
+    // Renders the message as RFC 5322 text. The transport
+    // headers are kept as they were when the message was
+    // received, and built from the properties otherwise.
+    // Embedded messages are rendered in turn as message/rfc822
+    // parts. Other attachments without data, e.g. links, are
+    // left out.
+    pub fn to_eml(&self) -> String {
+        self.to_eml_with_clock(&SystemClock)
+    }
+
+    // Same as to_eml, the date of messages without any
+    // being taken from clock.
+    pub fn to_eml_with_clock(&self, clock: &dyn Clock) -> String {
+        let properties = self.message_properties();
+        let attachments: Vec<AttachmentData> = properties
+            .attachments
+            .iter()
+            .map(AttachmentData::create_from_props)
+            .filter(|attachment| !attachment.data.is_empty())
+            .collect();
+        let html = self.html_body();
+        let mut entity = self.body_entity(&attachments);
+        let attached: Vec<Entity> = properties
+            .attachments
+            .iter()
+            .zip(self.attachments.iter())
+            .filter_map(|(props, attachment)| {
+                let data = AttachmentData::create_from_props(props);
+                match &attachment.embedded {
+                    Some(message) => Some(data.message_entity(message.to_eml_with_clock(clock))),
+                    None if !data.data.is_empty() && !data.is_referenced_by(&html) => Some(data.entity(false)),
+                    None => None,
+                }
+            })
+            .collect();
+        if !attached.is_empty() {
+            let mut parts = vec![entity];
+            parts.extend(attached);
+            entity = multipart_entity("mixed", parts);
+        }
+
+        let mut eml = self
+            .original_header_fields()
+            .unwrap_or_else(|| self.generated_header_fields(clock));
+        eml.push_str("MIME-Version: 1.0\r\n");
+        eml.push_str(&entity.render());
+        eml
+    }
+}
+
+// Date of a Date or Received field, the latter
+// ending with it after a semicolon.
+fn parse_date(value: &str) -> Option<DataType> {
+    let value = value.rsplit(';').next()?.trim();
+    // Comments, e.g. "(UTC)", are not understood.
+    let value = value.split(" (").next().unwrap_or(value);
+    DateTime::parse_from_rfc2822(value)
+        .ok()
+        .map(|date| DataType::PtypTime(date.with_timezone(&Utc)))
+}
+
+// Importance of the Importance field, or else of the
+// X-Priority one, where 1 is the highest.
+fn parse_importance(headers: &Headers) -> Option<i32> {
+    if let Some(importance) = headers.get("Importance") {
+        return match importance.trim().to_ascii_lowercase().as_str() {
+            "high" => Some(2),
+            "low" => Some(0),
+            _ => Some(1),
+        };
+    }
+    let priority = headers.get("X-Priority")?.trim().chars().next()?;
+    match priority {
+        '1' | '2' => Some(2),
+        '4' | '5' => Some(0),
+        _ => Some(1),
+    }
+}
+
+// Refer to RFC 2156 5.3.4
+fn parse_sensitivity(headers: &Headers) -> Option<i32> {
+    match headers.get("Sensitivity")?.trim().to_ascii_lowercase().as_str() {
+        "personal" => Some(1),
+        "private" => Some(2),
+        "company-confidential" => Some(3),
+        _ => None,
+    }
+}
+
+// Parts holding content, in order. Only the signed
+// content of a clear-signed message is looked into.
+fn leaf_parts(part: &MimePart) -> Vec<&MimePart> {
+    if !part.is_multipart() {
+        return vec![part];
+    }
+    let parts = if part.content_type() == "multipart/signed" {
+        &part.parts[..1]
+    } else {
+        &part.parts[..]
+    };
+    parts.iter().flat_map(leaf_parts).collect()
+}
+
+fn is_attached(part: &MimePart) -> bool {
+    let disposition = part.headers.get("Content-Disposition").unwrap_or_default();
+    disposition.trim().to_ascii_lowercase().starts_with("attachment") || part.file_name().is_some()
+}
+
+impl MessageProperties {
+    // Reads an RFC 5322 message into the properties of
+    // the MSG file Outlook would have stored it as. The
+    // header is kept whole as TransportMessageHeaders.
+    // Fails on MIME parts nested too deep, see MimePart::parse.
+    pub fn from_eml(data: &[u8]) -> Result<Self, Error> {
+        let message = MimePart::parse(data)?;
+        let headers = &message.headers;
+        let mut root = Properties::new();
+        let (header, _) = split_header(data);
+        root.insert(
+            "TransportMessageHeaders".to_string(),
+            string(&String::from_utf8_lossy(header)),
+        );
+        let subject = headers.get("Subject").unwrap_or_default().trim();
+        root.insert("Subject".to_string(), string(subject));
+        root.insert("ConversationTopic".to_string(), string(subject));
+
+        if let Some(date) = headers.get("Date").and_then(parse_date) {
+            root.insert("ClientSubmitTime".to_string(), date);
+        }
+        let delivered = headers.get("Received").and_then(parse_date);
+        if let Some(date) = delivered.or_else(|| root.get("ClientSubmitTime").cloned()) {
+            root.insert("MessageDeliveryTime".to_string(), date);
+        }
+        for (field, key) in [
+            ("Message-ID", "InternetMessageId"),
+            ("In-Reply-To", "InReplyToId"),
+            ("References", "InternetReferences"),
+        ] {
+            if let Some(value) = headers.get(field) {
+                root.insert(key.to_string(), string(value.trim()));
+            }
+        }
+        if let Some(importance) = parse_importance(headers) {
+            root.insert("Importance".to_string(), DataType::PtypInteger32(importance));
+        }
+        if let Some(sensitivity) = parse_sensitivity(headers) {
+            root.insert("Sensitivity".to_string(), DataType::PtypInteger32(sensitivity));
+        }
+
+        // The sender is whoever the Sender field names,
+        // sending on behalf of the author otherwise.
+        let from = addresses(headers.get("From").unwrap_or_default());
+        let sender = addresses(headers.get("Sender").unwrap_or_default());
+        for (prefix, mailbox) in [
+            ("SentRepresenting", from.first()),
+            ("Sender", sender.first().or(from.first())),
+        ] {
+            if let Some((name, address)) = mailbox {
+                mailbox_props(&mut root, prefix, name, address);
+            }
+        }
+
+        let mut recipients = vec![];
+        for (field, recipient_type) in RECIPIENT_FIELDS {
+            let mailboxes = addresses(&headers.get_all(field).join(", "));
+            let names: Vec<&str> = mailboxes
+                .iter()
+                .map(|(name, address)| if name.is_empty() { address } else { name })
+                .map(String::as_str)
+                .collect();
+            root.insert(format!("Display{}", field), string(&names.join("; ")));
+            for ((_, address), name) in mailboxes.iter().zip(names) {
+                recipients.push(recipient_props(recipients.len(), recipient_type, name, address));
+            }
+        }
+
+        let mut message_class = "IPM.Note";
+        let mut attachments = vec![];
+        match message.content_type().as_str() {
+            // S/MIME content is kept as the single attachment.
+            // Refer to MS-OXOSMIME 2.1
+            "multipart/signed" => {
+                message_class = "IPM.Note.SMIME.MultipartSigned";
+                attachments.push(attachment_props(0, "smime.p7m", "multipart/signed", data.to_vec()));
+            }
+            "application/pkcs7-mime" | "application/x-pkcs7-mime" => {
+                message_class = "IPM.Note.SMIME";
+                attachments.push(attachment_props(
+                    0,
+                    "smime.p7m",
+                    "application/pkcs7-mime",
+                    message.body.clone(),
+                ));
+            }
+            _ => {}
+        }
+        let signed = !attachments.is_empty();
+        let leaves = leaf_parts(&message);
+        let body_part = |content_type: &str| {
+            leaves
+                .iter()
+                .position(|part| !is_attached(part) && part.content_type() == content_type)
+        };
+        let body = body_part("text/plain");
+        let html = body_part("text/html");
+        let html_text = html.map(|html| leaves[html].text()).unwrap_or_default();
+        for (index, part) in leaves.iter().enumerate() {
+            if signed || Some(index) == body || Some(index) == html {
+                continue;
+            }
+            let content_type = part.content_type();
+            let name = match part.file_name() {
+                Some(name) => name,
+                None if content_type == "message/rfc822" => {
+                    // The name is all it is parsed for.
+                    let embedded = MimePart::parse(&part.body).unwrap_or_default();
+                    format!("{}.eml", embedded.headers.get("Subject").unwrap_or("message").trim())
+                }
+                None => String::new(),
+            };
+            let mut props = attachment_props(attachments.len(), &name, &content_type, part.body.clone());
+            if let Some(content_id) = part.headers.get("Content-ID") {
+                let content_id = content_id.trim().trim_start_matches('<').trim_end_matches('>');
+                if html_text.contains(&format!("cid:{}", content_id)) {
+                    set_inline(&mut props, content_id);
+                } else {
+                    props.insert("AttachContentId".to_string(), string(content_id));
+                }
+            }
+            attachments.push(props);
+        }
+
+        root.insert("MessageClass".to_string(), string(message_class));
+        if let Some(body) = body {
+            root.insert("Body".to_string(), string(&leaves[body].text()));
+        }
+        if html.is_some() {
+            root.insert("Html".to_string(), DataType::PtypBinary(html_text.into_bytes()));
+            root.insert("InternetCodepage".to_string(), DataType::PtypInteger32(65001));
+        }
+        let flags = MessageFlags {
+            read: true,
+            has_attachments: !attachments.is_empty(),
+            ..MessageFlags::default()
+        };
+        root.insert("MessageFlags".to_string(), DataType::PtypInteger32(flags.to_value() as i32));
+        Ok(Self {
+            root,
+            recipients,
+            attachments,
+            embedded: vec![],
+        })
+    }
+}
+
+impl Outlook {
+    // Reads an RFC 5322 message as if it had been stored
+    // by Outlook, going through its MSG file. Use
+    // MessageProperties::from_eml(data)?.to_msg() for the
+    // file itself.
+    pub fn from_eml(data: &[u8]) -> Result<Self, Error> {
+        Self::from_slice(&MessageProperties::from_eml(data)?.to_msg())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{TimeZone as _, Utc};
+
+    use super::{
+        super::{
+            builder::OutlookBuilder, clock::FixedClock, decode::DataType, importance::Importance, mime::MimePart,
+            outlook::Outlook, storage::props,
+        },
+        fold_field, format_mailbox, html_of, Person,
+    };
+
+    fn clock() -> FixedClock {
+        FixedClock(Utc.with_ymd_and_hms(2024, 5, 6, 7, 8, 9).unwrap())
+    }
+
+    #[test]
+    fn test_format_mailbox() {
+        let person = |name: &str, email: &str| Person::new(name.to_string(), email.to_string());
+        assert_eq!(
+            format_mailbox(&person("Jane Doe", "jane@contoso.com")),
+            "Jane Doe <jane@contoso.com>".to_string()
+        );
+        assert_eq!(
+            format_mailbox(&person("Doe, Jane", "jane@contoso.com")),
+            "\"Doe, Jane\" <jane@contoso.com>".to_string()
+        );
+        assert_eq!(
+            format_mailbox(&person("Zoë", "zoe@contoso.com")),
+            "=?UTF-8?B?Wm/Dqw==?= <zoe@contoso.com>".to_string()
+        );
+        assert_eq!(
+            format_mailbox(&person("", "jane@contoso.com")),
+            "jane@contoso.com".to_string()
+        );
+    }
+
+    #[test]
+    fn test_fold_field() {
+        let value = ["someone@example.com,"; 10].join(" ");
+        let folded = fold_field("To", &value);
+        assert!(folded.split("\r\n").all(|line| line.len() <= 78));
+        assert!(folded.starts_with("To: someone@example.com,"));
+        assert!(folded.contains("\r\n someone@example.com,"));
+    }
+
+    #[test]
+    fn test_transport_headers_kept() {
+        let outlook = Outlook::from_path("data/test_email_4.msg").unwrap();
+        let eml = outlook.to_eml_with_clock(&clock());
+        let message = MimePart::parse(eml.as_bytes()).unwrap();
+        assert_eq!(message.headers.get_all("Received").len(), 3);
+        assert_eq!(message.headers.get("Subject"), Some("test"));
+        assert_eq!(message.headers.get_all("MIME-Version"), vec!["1.0"]);
+        assert_eq!(message.content_type(), "text/plain".to_string());
+        assert_eq!(message.text(), outlook.body.replace("\r\n", "\n").replace('\n', "\r\n"));
+    }
+
+    #[test]
+    fn test_generated_headers() {
+        let outlook = Outlook::from_path("data/test_email.msg").unwrap();
+        let eml = outlook.to_eml_with_clock(&clock());
+        let message = MimePart::parse(eml.as_bytes()).unwrap();
+        assert_eq!(message.headers.get("Date"), Some("Tue, 5 Jan 2021 03:00:32 +0000"));
+        assert!(message.headers.get("From").unwrap().contains(&outlook.sender.email));
+        assert!(message.headers.get("To").unwrap().contains(&outlook.to[0].email));
+        assert_eq!(message.headers.get("Subject"), Some(outlook.subject.as_str()));
+        assert_eq!(message.content_type(), "multipart/mixed".to_string());
+
+        // Attachments are kept byte for byte, bar the empty
+        // ones, embedded messages being rendered in turn.
+        let (embedded, attachments): (Vec<&MimePart>, Vec<&MimePart>) = message
+            .parts
+            .iter()
+            .skip(1)
+            .partition(|part| part.content_type() == "message/rfc822");
+        let count = outlook.attachments.iter().filter(|attachment| attachment.embedded.is_some()).count();
+        assert_eq!(embedded.len(), count);
+        let payloads: Vec<Vec<u8>> = outlook
+            .attachments
+            .iter()
+            .map(|attachment| hex::decode(&attachment.payload).unwrap())
+            .filter(|payload| !payload.is_empty())
+            .collect();
+        assert_eq!(attachments.len(), payloads.len());
+        for (part, payload) in attachments.iter().zip(payloads) {
+            assert_eq!(part.body, payload);
+            assert!(part.headers.get("Content-Disposition").unwrap().starts_with("attachment"));
+        }
+        assert_eq!(
+            attachments[0].file_name(),
+            Some("milky-way-2695569_960_720.jpg".to_string())
+        );
+    }
+
+    #[test]
+    fn test_header_injection() {
+        let injected = "\r\nX-Injected: yes";
+        let outlook = OutlookBuilder::new()
+            .subject(&format!("Hello{}", injected))
+            .sender(&format!("Jane{}", injected), "jane@contoso.com")
+            .to("Bob", &format!("bob@contoso.com{}", injected))
+            .message_id(&format!("abc@contoso.com{}", injected))
+            .in_reply_to("def@contoso.com\nX-Injected: yes")
+            .inline_attachment(
+                "logo.png",
+                &format!("image/png{}", injected),
+                &format!("logo@contoso.com{}", injected),
+                vec![1, 2, 3],
+            )
+            .build()
+            .unwrap();
+        let eml = outlook.to_eml_with_clock(&clock());
+        assert!(eml.split('\n').all(|line| !line.starts_with("X-Injected")));
+        let message = MimePart::parse(eml.as_bytes()).unwrap();
+        assert!(message.walk().iter().all(|part| part.headers.get("X-Injected").is_none()));
+        assert_eq!(message.headers.get("Subject"), Some("Hello\r\nX-Injected: yes"));
+
+        let outlook = OutlookBuilder::new()
+            .transport_headers("Subject: Hello\rX-Injected: yes\r\n")
+            .build()
+            .unwrap();
+        let eml = outlook.to_eml_with_clock(&clock());
+        assert!(eml.starts_with("Subject: Hello X-Injected: yes\r\n"));
+    }
+
+    #[test]
+    fn test_html_codepage() {
+        let html = |codepage: i32, bytes: &[u8]| {
+            html_of(&props(&[
+                ("Html", DataType::PtypBinary(bytes.to_vec())),
+                ("InternetCodepage", DataType::PtypInteger32(codepage)),
+            ]))
+        };
+        assert_eq!(html(1251, b"<p>\xCF\xF0\xE8\xE2\xE5\xF2</p>"), "<p>Привет</p>");
+        assert_eq!(html(932, b"<p>\x83\x65\x83\x58\x83\x67</p>"), "<p>テスト</p>");
+        assert_eq!(html(1252, b"<p>\x80</p>"), "<p>\u{20AC}</p>");
+        assert_eq!(html(0x10000, "<p>Olá</p>".as_bytes()), "<p>Olá</p>");
+    }
+
+    #[test]
+    fn test_related_bodies() {
+        let mut outlook = Outlook::from_path("data/attachment.msg").unwrap();
+        let content_id = outlook.properties.attachments[0]
+            .get("AttachContentId")
+            .and_then(DataType::as_str)
+            .unwrap()
+            .to_string();
+        let html = format!("<p>Olá</p><img src=\"cid:{}\">", content_id);
+        outlook
+            .properties
+            .root
+            .insert("Html".to_string(), DataType::PtypBinary(html.clone().into_bytes()));
+        outlook
+            .properties
+            .root
+            .insert("InternetCodepage".to_string(), DataType::PtypInteger32(65001));
+        outlook.subject = "Café ".repeat(20);
+        outlook.properties.root.remove("TransportMessageHeaders");
+
+        let eml = outlook.to_eml_with_clock(&clock());
+        assert!(eml.split("\r\n").all(|line| line.len() <= 998));
+        let message = MimePart::parse(eml.as_bytes()).unwrap();
+        assert_eq!(message.headers.get("Subject"), Some(outlook.subject.as_str()));
+
+        let related = message
+            .walk()
+            .into_iter()
+            .find(|part| part.content_type() == "multipart/related")
+            .unwrap();
+        let alternative = &related.parts[0];
+        assert_eq!(alternative.content_type(), "multipart/alternative".to_string());
+        assert_eq!(alternative.parts[1].text(), html);
+        let image = &related.parts[1];
+        assert_eq!(
+            image.headers.get("Content-ID"),
+            Some(format!("<{}>", content_id).as_str())
+        );
+        assert!(image.headers.get("Content-Disposition").unwrap().starts_with("inline"));
+        assert_eq!(image.body, hex::decode(&outlook.attachments[0].payload).unwrap());
+
+        // Output is reproducible.
+        assert_eq!(outlook.to_eml_with_clock(&clock()), eml);
+    }
+
+    const EML: &str = "From: Jane Doe <jane@contoso.com>\r\n\
+        To: Bob <bob@contoso.com>, al@contoso.com\r\n\
+        Cc: \"Doe, John\" <john@contoso.com>\r\n\
+        Subject: =?UTF-8?B?Q2Fmw6k=?=\r\n\
+        Date: Sun, 14 Mar 2021 04:03:57 +0000\r\n\
+        Message-ID: <abc@contoso.com>\r\n\
+        Importance: high\r\n\
+        MIME-Version: 1.0\r\n\
+        Content-Type: multipart/mixed; boundary=\"mixed\"\r\n\
+        \r\n\
+        --mixed\r\n\
+        Content-Type: multipart/related; boundary=\"related\"\r\n\
+        \r\n\
+        --related\r\n\
+        Content-Type: multipart/alternative; boundary=\"alternative\"\r\n\
+        \r\n\
+        --alternative\r\n\
+        Content-Type: text/plain; charset=utf-8\r\n\
+        \r\n\
+        Hello\r\n\
+        --alternative\r\n\
+        Content-Type: text/html; charset=utf-8\r\n\
+        \r\n\
+        <p>Hello</p><img src=\"cid:logo@contoso.com\">\r\n\
+        --alternative--\r\n\
+        --related\r\n\
+        Content-Type: image/png\r\n\
+        Content-Transfer-Encoding: base64\r\n\
+        Content-ID: <logo@contoso.com>\r\n\
+        \r\n\
+        iVBORw0KGgo=\r\n\
+        --related--\r\n\
+        --mixed\r\n\
+        Content-Type: application/pdf; name=\"report.pdf\"\r\n\
+        Content-Disposition: attachment; filename=\"report.pdf\"\r\n\
+        Content-Transfer-Encoding: base64\r\n\
+        \r\n\
+        JVBERi0=\r\n\
+        --mixed--\r\n";
+
+    #[test]
+    fn test_from_eml() {
+        let outlook = Outlook::from_eml(EML.as_bytes()).unwrap();
+        assert_eq!(outlook.subject, "Café".to_string());
+        assert_eq!(outlook.message_class, "IPM.Note".to_string());
+        assert_eq!(outlook.sender.email, "jane@contoso.com".to_string());
+        assert_eq!(outlook.sent_representing.name, "Jane Doe".to_string());
+        assert_eq!(outlook.to.len(), 2);
+        assert_eq!(outlook.to[1].email, "al@contoso.com".to_string());
+        assert_eq!(outlook.cc[0].name, "Doe, John".to_string());
+        assert_eq!(outlook.body, "Hello".to_string());
+        assert_eq!(outlook.message_id, "abc@contoso.com".to_string());
+        assert_eq!(outlook.importance, Importance::High);
+        assert!(outlook.flags.has_attachments);
+        assert_eq!(
+            outlook.properties.root.get("ClientSubmitTime"),
+            Some(&DataType::PtypTime(Utc.with_ymd_and_hms(2021, 3, 14, 4, 3, 57).unwrap()))
+        );
+        assert_eq!(outlook.transport_headers().get("Message-ID"), Some("<abc@contoso.com>"));
+
+        let attachments = &outlook.properties.attachments;
+        assert_eq!(attachments.len(), 2);
+        assert_eq!(
+            attachments[0].get("AttachContentId"),
+            Some(&DataType::PtypString("logo@contoso.com".to_string()))
+        );
+        assert_eq!(attachments[0].get("AttachmentHidden"), Some(&DataType::PtypBoolean(true)));
+        assert_eq!(outlook.attachments[1].file_name, "report.pdf".to_string());
+        assert_eq!(outlook.attachments[1].mime_tag, "application/pdf".to_string());
+        assert_eq!(outlook.attachments[1].payload, hex::encode(b"%PDF-"));
+
+        // Exporting gives the same structure back.
+        let message = MimePart::parse(outlook.to_eml().as_bytes()).unwrap();
+        assert!(message.find("image/png").is_some());
+        assert_eq!(
+            message.find("text/html").unwrap().text(),
+            "<p>Hello</p><img src=\"cid:logo@contoso.com\">".to_string()
+        );
+    }
+
+    #[test]
+    fn test_from_eml_signed() {
+        let data = std::fs::read("data/smime_signed.eml").unwrap();
+        let outlook = Outlook::from_eml(&data).unwrap();
+        assert_eq!(outlook.message_class, "IPM.Note.SMIME.MultipartSigned".to_string());
+        assert_eq!(outlook.attachments.len(), 1);
+        assert!(outlook.smime.unwrap().is_verified());
+        assert_eq!(outlook.body, "Signed hello\r\n".to_string());
+    }
+
+    #[test]
+    fn test_eml_round_trip() {
+        let original = Outlook::from_path("data/attachment.msg").unwrap();
+        let outlook = Outlook::from_eml(original.to_eml().as_bytes()).unwrap();
+        assert_eq!(outlook.subject, original.subject);
+        let data = |outlook: &Outlook| -> Vec<String> {
+            outlook
+                .attachments
+                .iter()
+                .map(|attachment| attachment.payload.clone())
+                .filter(|payload| !payload.is_empty())
+                .collect()
+        };
+        assert!(!data(&original).is_empty());
+        assert_eq!(data(&outlook), data(&original));
+    }
+
+    #[test]
+    fn test_embedded_messages() {
+        let outlook = Outlook::from_path("data/test_email_1.msg").unwrap();
+        let subjects: Vec<String> = outlook
+            .attachments
+            .iter()
+            .filter_map(|attachment| Some(attachment.embedded.as_ref()?.subject.clone()))
+            .collect();
+        assert_eq!(subjects.len(), 3);
+        let eml = outlook.to_eml_with_clock(&clock());
+        let message = MimePart::parse(eml.as_bytes()).unwrap();
+        let embedded: Vec<String> = message
+            .walk()
+            .into_iter()
+            .filter(|part| part.content_type() == "message/rfc822")
+            .map(|part| Outlook::from_eml(&part.body).unwrap().subject)
+            .collect();
+        assert_eq!(embedded, subjects);
+    }
+}
@@ -0,0 +1,243 @@
+use serde::{Deserialize, Serialize};
+
+use super::{blob::BlobReader, error::DataTypeError};
+
+// Provider UIDs telling what an entry ID refers to.
+// Refer to MS-OXCDATA 2.2.5 and MS-OXOCNTC 2.2.2.2.1
+const ONE_OFF_PROVIDER: &str = "A41F2B81-A3BE-1910-9D6E-00DD010F5402";
+const ADDRESS_BOOK_PROVIDER: &str = "C840A7DC-42C0-1A10-B4B9-08002B2FE182";
+const WRAPPED_PROVIDER: &str = "D3AD91C0-9D51-11CF-A4A9-00AA0047FAA4";
+
+// Strings of a one-off entry ID are UTF-16 when set.
+const ONE_OFF_UNICODE: u16 = 0x8000;
+
+// Wrapped entry IDs may wrap wrapped ones, which never
+// go more than a couple deep in practice.
+const MAX_WRAPPING: usize = 8;
+
+// EntryId identifies a recipient, a contact or
+// any other object in a message store.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum EntryId {
+    // Recipient that is in no address book.
+    // Refer to MS-OXCDATA 2.2.5.1
+    OneOff {
+        display_name: String,
+        address_type: String,
+        email_address: String,
+    },
+    // Object of the Exchange address book, e.g. a GAL user.
+    // Refer to MS-OXCDATA 2.2.5.2
+    AddressBook { x500_dn: String },
+    // Entry ID of a contact or distribution list member
+    // wrapping the entry ID it refers to.
+    // Refer to MS-OXOCNTC 2.2.2.2.1
+    Wrapped { kind: u8, entry_id: Box<EntryId> },
+    // Any other entry ID, e.g. of a message in a store.
+    Other { provider: String },
+}
+
+impl EntryId {
+    pub fn from_bytes(buff: &[u8]) -> Result<Self, DataTypeError> {
+        Self::from_bytes_at(buff, 0)
+    }
+
+    fn from_bytes_at(buff: &[u8], depth: usize) -> Result<Self, DataTypeError> {
+        let mut reader = BlobReader::new(buff);
+        // Flags
+        reader.skip(4)?;
+        let provider = reader.read_guid()?;
+        match provider.as_str() {
+            ONE_OFF_PROVIDER => {
+                // Version
+                reader.skip(2)?;
+                let flags = reader.read_u16()?;
+                let mut read_string = || {
+                    if flags & ONE_OFF_UNICODE != 0 {
+                        reader.read_utf16_nul()
+                    } else {
+                        reader.read_ascii_nul()
+                    }
+                };
+                Ok(EntryId::OneOff {
+                    display_name: read_string()?,
+                    address_type: read_string()?,
+                    email_address: read_string()?,
+                })
+            }
+            ADDRESS_BOOK_PROVIDER => {
+                // Version and type
+                reader.skip(8)?;
+                Ok(EntryId::AddressBook {
+                    x500_dn: reader.read_ascii_nul()?,
+                })
+            }
+            WRAPPED_PROVIDER => {
+                if depth >= MAX_WRAPPING {
+                    return Err(DataTypeError::MalformedBlob("entry IDs wrapped too deep".to_string()));
+                }
+                // The lower four bits tell the kind of object.
+                let kind = reader.read_u8()? & 0x0F;
+                let entry_id = Self::from_bytes_at(reader.read_bytes(reader.remaining())?, depth + 1)?;
+                Ok(EntryId::Wrapped {
+                    kind,
+                    entry_id: Box::new(entry_id),
+                })
+            }
+            _ => Ok(EntryId::Other { provider }),
+        }
+    }
+
+    // Entry IDs of a FlatEntryList, e.g. "ReplyRecipientEntries".
+    // Entries that can't be read are None so that they still
+    // line up with any list of names given alongside.
+    // Refer to MS-OXCDATA 2.3.3
+    pub fn from_flat_entry_list(buff: &[u8]) -> Result<Vec<Option<Self>>, DataTypeError> {
+        let mut reader = BlobReader::new(buff);
+        let count = reader.read_u32()?;
+        // Size of the entries
+        reader.skip(4)?;
+        let mut entry_ids = vec![];
+        for _ in 0..count {
+            let size = reader.read_u32()? as usize;
+            entry_ids.push(Self::from_bytes(reader.read_bytes(size)?).ok());
+            // Each entry is padded to a multiple of 4 bytes,
+            // except sometimes the last one.
+            let padding = (4 - size % 4) % 4;
+            reader.skip(padding.min(reader.remaining()))?;
+        }
+        Ok(entry_ids)
+    }
+
+    // Entry ID left once wrappers are removed.
+    pub fn unwrap_entry_id(&self) -> &Self {
+        match self {
+            EntryId::Wrapped { entry_id, .. } => entry_id.unwrap_entry_id(),
+            _ => self,
+        }
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::{EntryId, MAX_WRAPPING};
+
+    pub(crate) fn one_off_entry_id(name: &str, address_type: &str, email: &str) -> Vec<u8> {
+        let mut buff = vec![0u8; 4];
+        buff.extend([
+            0x81, 0x2B, 0x1F, 0xA4, 0xBE, 0xA3, 0x10, 0x19, 0x9D, 0x6E, 0x00, 0xDD, 0x01, 0x0F,
+            0x54, 0x02,
+        ]);
+        buff.extend([0x00, 0x00, 0x00, 0x80]);
+        for value in [name, address_type, email] {
+            buff.extend(value.encode_utf16().chain([0]).flat_map(u16::to_le_bytes));
+        }
+        buff
+    }
+
+    pub(crate) fn wrapped_entry_id(kind: u8, entry_id: &[u8]) -> Vec<u8> {
+        let mut buff = vec![0u8; 4];
+        buff.extend([
+            0xC0, 0x91, 0xAD, 0xD3, 0x51, 0x9D, 0xCF, 0x11, 0xA4, 0xA9, 0x00, 0xAA, 0x00, 0x47,
+            0xFA, 0xA4,
+        ]);
+        buff.push(0x80 | kind);
+        buff.extend(entry_id);
+        buff
+    }
+
+    #[test]
+    fn test_one_off() {
+        let entry_id = one_off_entry_id("Jane Doe", "SMTP", "jane@contoso.com");
+        assert_eq!(
+            EntryId::from_bytes(&entry_id).unwrap(),
+            EntryId::OneOff {
+                display_name: "Jane Doe".to_string(),
+                address_type: "SMTP".to_string(),
+                email_address: "jane@contoso.com".to_string(),
+            }
+        );
+        assert!(EntryId::from_bytes(&entry_id[..entry_id.len() - 2]).is_err());
+
+        // 8-bit strings
+        let mut entry_id = entry_id[..22].to_vec();
+        entry_id.extend([0x00, 0x00]);
+        entry_id.extend(b"Jane\0SMTP\0jane@contoso.com\0");
+        assert_eq!(
+            EntryId::from_bytes(&entry_id).unwrap(),
+            EntryId::OneOff {
+                display_name: "Jane".to_string(),
+                address_type: "SMTP".to_string(),
+                email_address: "jane@contoso.com".to_string(),
+            }
+        );
+    }
+
+    pub(crate) fn flat_entry_list(entry_ids: &[Vec<u8>]) -> Vec<u8> {
+        let entries: Vec<u8> = entry_ids
+            .iter()
+            .flat_map(|entry_id| {
+                let mut entry = (entry_id.len() as u32).to_le_bytes().to_vec();
+                entry.extend(entry_id);
+                entry.resize(entry.len() + (4 - entry_id.len() % 4) % 4, 0);
+                entry
+            })
+            .collect();
+        let mut buff = (entry_ids.len() as u32).to_le_bytes().to_vec();
+        buff.extend((entries.len() as u32).to_le_bytes());
+        buff.extend(entries);
+        buff
+    }
+
+    #[test]
+    fn test_flat_entry_list() {
+        let jane = one_off_entry_id("Jane", "SMTP", "jane@contoso.com");
+        let buff = flat_entry_list(&[jane.clone(), vec![0xFF], jane]);
+        let entry_ids = EntryId::from_flat_entry_list(&buff).unwrap();
+        assert_eq!(entry_ids.len(), 3);
+        assert_eq!(entry_ids[0], entry_ids[2]);
+        assert_eq!(entry_ids[1], None);
+        assert!(EntryId::from_flat_entry_list(&buff[..buff.len() - 8]).is_err());
+    }
+
+    #[test]
+    fn test_address_book_and_wrapped() {
+        let mut entry_id = vec![0u8; 4];
+        entry_id.extend([
+            0xDC, 0xA7, 0x40, 0xC8, 0xC0, 0x42, 0x10, 0x1A, 0xB4, 0xB9, 0x08, 0x00, 0x2B, 0x2F,
+            0xE1, 0x82,
+        ]);
+        entry_id.extend([0x01, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00]);
+        entry_id.extend(b"/O=CONTOSO/CN=RECIPIENTS/CN=JANE\0");
+        let address_book = EntryId::AddressBook {
+            x500_dn: "/O=CONTOSO/CN=RECIPIENTS/CN=JANE".to_string(),
+        };
+        assert_eq!(EntryId::from_bytes(&entry_id).unwrap(), address_book);
+
+        let wrapped = EntryId::from_bytes(&wrapped_entry_id(5, &entry_id)).unwrap();
+        assert_eq!(
+            wrapped,
+            EntryId::Wrapped {
+                kind: 5,
+                entry_id: Box::new(address_book.clone()),
+            }
+        );
+        assert_eq!(wrapped.unwrap_entry_id(), &address_book);
+
+        let mut nested = entry_id;
+        for _ in 0..MAX_WRAPPING {
+            nested = wrapped_entry_id(5, &nested);
+        }
+        assert_eq!(EntryId::from_bytes(&nested).unwrap().unwrap_entry_id(), &address_book);
+        let nested = wrapped_entry_id(5, &nested);
+        assert!(EntryId::from_bytes(&nested).is_err());
+
+        let other = EntryId::from_bytes(&[0u8; 24]).unwrap();
+        assert_eq!(
+            other,
+            EntryId::Other {
+                provider: "00000000-0000-0000-0000-000000000000".to_string()
+            }
+        );
+    }
+}
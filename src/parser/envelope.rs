@@ -0,0 +1,119 @@
+use chrono::{DateTime, Utc};
+
+use serde::{Deserialize, Serialize};
+
+use super::{
+    decode::DataType,
+    headers::Headers,
+    outlook::{Outlook, Person, RECIPIENT_BCC, RECIPIENT_CC, RECIPIENT_TO},
+    storage::{Properties, Recipients},
+};
+
+// Root properties of variable length an envelope is made
+// of. Fixed length ones, as the dates, are all in the
+// property stream of the root.
+pub(super) const ENVELOPE_KEYS: [&str; 10] = [
+    "Subject",
+    "MessageClass",
+    "SenderName",
+    "SenderSmtpAddress",
+    "SenderEmailAddress",
+    "SentRepresentingName",
+    "SentRepresentingSmtpAddress",
+    "SentRepresentingEmailAddress",
+    "InternetMessageId",
+    "TransportMessageHeaders",
+];
+
+// Envelope holds what identifies a message, without its
+// bodies and attachments, for indexers that only need
+// metadata. Fields are the same as the ones of Outlook.
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+pub struct Envelope {
+    pub sender: Person,                       // "SenderName" , "SenderSmtpAddress"/"SenderEmailAddress"
+    pub sent_representing: Person,            // "SentRepresentingName", "SentRepresentingSmtpAddress"/"SentRepresentingEmailAddress"
+    pub to: Vec<Person>,                      // "DisplayName", "SmtpAddress"/"EmailAddress"
+    pub cc: Vec<Person>,                      // "DisplayName", "SmtpAddress"/"EmailAddress"
+    pub bcc: Vec<Person>,                     // "DisplayName", "SmtpAddress"/"EmailAddress"
+    pub subject: String,                      // "Subject"
+    pub message_class: String,                // "MessageClass"
+    pub message_id: String,                   // "InternetMessageId"
+    pub submit_time: Option<DateTime<Utc>>,   // "ClientSubmitTime"
+    pub delivery_time: Option<DateTime<Utc>>, // "MessageDeliveryTime"
+}
+
+impl Envelope {
+    pub fn create_from_props(props: &Properties, recipients: &Recipients) -> Self {
+        let value = |key: &str| props.get(key).map(String::from).unwrap_or_default();
+        let headers_text = value("TransportMessageHeaders");
+        let headers = Headers::parse(&headers_text);
+        Self {
+            sender: Outlook::resolve_smtp(
+                recipients,
+                &headers,
+                Person::create_from_props(props, "SenderName", vec!["SenderSmtpAddress", "SenderEmailAddress"]),
+                &["Sender", "From"],
+            ),
+            sent_representing: Outlook::resolve_smtp(
+                recipients,
+                &headers,
+                Person::create_from_props(
+                    props,
+                    "SentRepresentingName",
+                    vec!["SentRepresentingSmtpAddress", "SentRepresentingEmailAddress"],
+                ),
+                &["From"],
+            ),
+            to: Outlook::extract_recipients(recipients, &headers, RECIPIENT_TO),
+            cc: if Outlook::has_recipient_types(recipients) {
+                Outlook::extract_recipients(recipients, &headers, RECIPIENT_CC)
+            } else {
+                Outlook::extract_cc_from_headers(&headers_text)
+            },
+            bcc: Outlook::extract_recipients(recipients, &headers, RECIPIENT_BCC),
+            subject: value("Subject"),
+            message_class: value("MessageClass"),
+            message_id: Outlook::thread_ids(props, &headers, "InternetMessageId", "Message-ID")
+                .into_iter()
+                .next()
+                .unwrap_or_default(),
+            submit_time: props.get("ClientSubmitTime").and_then(DataType::as_time),
+            delivery_time: props.get("MessageDeliveryTime").and_then(DataType::as_time),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{super::outlook::Outlook, Envelope};
+
+    #[test]
+    fn test_envelope() {
+        for path in [
+            "data/attachment.msg",
+            "data/test_email.msg",
+            "data/test_email_1.msg",
+            "data/test_email_2.msg",
+            "data/test_email_3.msg",
+            "data/test_email_4.msg",
+            "data/unicode.msg",
+        ] {
+            let outlook = Outlook::from_path(path).unwrap();
+            let envelope = Outlook::envelope(path).unwrap();
+            let expected = Envelope {
+                sender: outlook.sender,
+                sent_representing: outlook.sent_representing,
+                to: outlook.to,
+                cc: outlook.cc,
+                bcc: outlook.bcc,
+                subject: outlook.subject,
+                message_class: outlook.message_class,
+                message_id: outlook.message_id,
+                submit_time: outlook.submit_time,
+                delivery_time: outlook.delivery_time,
+            };
+            assert_eq!(envelope, expected, "{}", path);
+        }
+        assert!(Outlook::envelope("data/bad_outlook.msg").is_err());
+    }
+}
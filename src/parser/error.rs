@@ -1,39 +1,59 @@
 use std::{
+    fmt,
     io,
 };
 
+use serde::{Deserialize, Serialize};
 use serde_json::Error as SerdeError;
 
 use thiserror::Error as ThisError;
 
 use crate::ole::Error as OleError;
 
+use super::{limits::Limit, mode::MissingPart};
+
 // DataTypeError is used when decode fails in datatype.rs
 #[derive(ThisError, Debug)]
 pub enum DataTypeError {
     UnknownCode(String),
+    MalformedBlob(String),
     Utf8Err(#[from] std::string::FromUtf8Error),
     Utf16Err(#[from] std::string::FromUtf16Error),
 }
 
+impl DataTypeError {
+    // Stable code of the error, see Error::code.
+    pub fn code(&self) -> u32 {
+        match self {
+            DataTypeError::UnknownCode(_) => 100,
+            DataTypeError::MalformedBlob(_) => 101,
+            DataTypeError::Utf8Err(_) => 102,
+            DataTypeError::Utf16Err(_) => 103,
+        }
+    }
+}
+
 impl std::fmt::Display for DataTypeError {
     fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
         match *self {
             DataTypeError::UnknownCode(ref value) => {
                 write!(f, "DataTypeError: Unknown value encoding: 0x{}", value)
             }
+            DataTypeError::MalformedBlob(ref reason) => {
+                write!(f, "DataTypeError: Malformed blob: {}", reason)
+            }
             DataTypeError::Utf8Err(ref err) => {
                 write!(
                     f,
                     "DataTypeError: Unable to decode bytes into UTF-8 string {}",
-                    err.to_string()
+                    err
                 )
             }
             DataTypeError::Utf16Err(ref err) => {
                 write!(
                     f,
                     "DataTypeError: Unable to decode bytes into UTF-16 string {}",
-                    err.to_string()
+                    err
                 )
             }
         }
@@ -52,11 +72,219 @@ pub enum Error {
     },
 
     #[error("Error parsing file with ole: {}", .source)]
-    OleError {
-        #[from]
-        source: OleError,
-    },
+    OleError { source: OleError },
 
     #[error(transparent)]
     SerdeJsonError(#[from] SerdeError),
+
+    // See Outlook::to_cbor and Outlook::to_msgpack.
+    #[error("Unable to encode: {}", .0)]
+    EncodeError(String),
+
+    // Returned in strict mode only.
+    #[error("Message is incomplete, missing {}", join_parts(.missing))]
+    Incomplete { missing: Vec<MissingPart> },
+
+    // See Limits.
+    #[error("Limit exceeded: {} over {}", .limit, .max)]
+    LimitExceeded { limit: Limit, max: usize },
+
+    // Any of the above along with where it happened.
+    #[error("{} ({})", .source, .context)]
+    Context {
+        source: Box<Error>,
+        context: ErrorContext,
+    },
+}
+
+// ErrorContext locates an error in the file, as far as
+// the code failing knows it.
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ErrorContext {
+    pub path: Option<String>,  // Entry path from the root storage, e.g. "__attach_version1.0_#00000000/__substg1.0_37010102"
+    pub tag: Option<u32>,      // Property tag, the property id followed by its type code
+    pub offset: Option<usize>, // Byte offset in the entry
+}
+
+impl fmt::Display for ErrorContext {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mut parts = vec![];
+        if let Some(path) = &self.path {
+            parts.push(format!("in {}", path));
+        }
+        if let Some(tag) = self.tag {
+            parts.push(format!("property 0x{:08X}", tag));
+        }
+        if let Some(offset) = self.offset {
+            parts.push(format!("at byte {}", offset));
+        }
+        write!(f, "{}", parts.join(", "))
+    }
+}
+
+impl Error {
+    // Stable code of the error, for callers branching on the
+    // kind of failure without matching its message, e.g.
+    // across FFI. Codes never change once released, new
+    // variants take new ones. Context has the code of the
+    // error it wraps.
+    pub fn code(&self) -> u32 {
+        match self {
+            Error::DataTypeError(err) => err.code(),
+            Error::Io { .. } => 1,
+            Error::OleError { .. } => 2,
+            Error::SerdeJsonError(_) => 3,
+            Error::Incomplete { .. } => 4,
+            Error::LimitExceeded { .. } => 5,
+            Error::EncodeError(_) => 6,
+            Error::Context { source, .. } => source.code(),
+        }
+    }
+
+    // Where the error happened, if known.
+    pub fn context(&self) -> Option<&ErrorContext> {
+        match self {
+            Error::Context { context, .. } => Some(context),
+            _ => None,
+        }
+    }
+
+    // The error itself, without its context.
+    pub fn root(&self) -> &Error {
+        match self {
+            Error::Context { source, .. } => source,
+            err => err,
+        }
+    }
+
+    // Adds to the context of the error, keeping what the
+    // code closer to the failure already set.
+    fn with_context(self, add: impl FnOnce(&mut ErrorContext)) -> Self {
+        match self {
+            Error::Context { source, mut context } => {
+                add(&mut context);
+                Error::Context { source, context }
+            }
+            err => {
+                let mut context = ErrorContext::default();
+                add(&mut context);
+                Error::Context {
+                    source: Box::new(err),
+                    context,
+                }
+            }
+        }
+    }
+
+    pub(crate) fn in_entry(self, path: &str) -> Self {
+        self.with_context(|context| {
+            context.path.get_or_insert_with(|| path.to_string());
+        })
+    }
+
+    pub(crate) fn at_tag(self, tag: u32) -> Self {
+        self.with_context(|context| {
+            context.tag.get_or_insert(tag);
+        })
+    }
+
+    pub(crate) fn at_offset(self, offset: usize) -> Self {
+        self.with_context(|context| {
+            context.offset.get_or_insert(offset);
+        })
+    }
+}
+
+// Limits the OLE reader enforces are reported as the
+// ones enforced on decoding.
+impl From<OleError> for Error {
+    fn from(source: OleError) -> Self {
+        match source {
+            OleError::EntryLimitExceeded(max) => Error::LimitExceeded {
+                limit: Limit::Entries,
+                max,
+            },
+            OleError::StreamLimitExceeded(max) => Error::LimitExceeded {
+                limit: Limit::StreamSize,
+                max,
+            },
+            source => Error::OleError { source },
+        }
+    }
+}
+
+fn join_parts(parts: &[MissingPart]) -> String {
+    parts
+        .iter()
+        .map(MissingPart::to_string)
+        .collect::<Vec<String>>()
+        .join(", ")
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io;
+
+    use super::{
+        super::{limits::Limit, mode::MissingPart},
+        DataTypeError, Error, ErrorContext, OleError,
+    };
+
+    #[test]
+    fn test_context() {
+        let err = Error::LimitExceeded {
+            limit: Limit::StreamSize,
+            max: 16,
+        };
+        assert!(err.context().is_none());
+        // The context closest to the failure wins, and is
+        // merged rather than nested.
+        let err = err
+            .at_tag(0x3701_0102)
+            .at_offset(8)
+            .in_entry("__attach_version1.0_#00000000/__substg1.0_37010102")
+            .at_tag(0);
+        assert_eq!(
+            err.context(),
+            Some(&ErrorContext {
+                path: Some("__attach_version1.0_#00000000/__substg1.0_37010102".to_string()),
+                tag: Some(0x3701_0102),
+                offset: Some(8),
+            })
+        );
+        assert!(matches!(err.root(), Error::LimitExceeded { max: 16, .. }));
+        assert_eq!(
+            err.to_string(),
+            "Limit exceeded: stream size over 16 (in __attach_version1.0_#00000000/__substg1.0_37010102, property 0x37010102, at byte 8)"
+                .to_string()
+        );
+    }
+
+    #[test]
+    fn test_code() {
+        let errors = [
+            Error::from(io::Error::other("closed")),
+            Error::OleError {
+                source: OleError::EmptyEntry,
+            },
+            Error::from(serde_json::from_str::<u8>("").unwrap_err()),
+            Error::Incomplete {
+                missing: vec![MissingPart::Sender],
+            },
+            Error::LimitExceeded {
+                limit: Limit::Depth,
+                max: 1,
+            },
+            Error::EncodeError("closed".to_string()),
+            Error::from(DataTypeError::UnknownCode("0048".to_string())),
+            Error::from(DataTypeError::MalformedBlob("short".to_string())),
+            Error::from(DataTypeError::Utf8Err(String::from_utf8(vec![0xFF]).unwrap_err())),
+            Error::from(DataTypeError::Utf16Err(String::from_utf16(&[0xD800]).unwrap_err())),
+        ];
+        let codes: Vec<u32> = errors.iter().map(Error::code).collect();
+        assert_eq!(codes, vec![1, 2, 3, 4, 5, 6, 100, 101, 102, 103]);
+
+        let err = Error::from(DataTypeError::UnknownCode("0048".to_string())).at_tag(0x8009_0048);
+        assert_eq!(err.code(), 100);
+    }
 }
@@ -0,0 +1,393 @@
+use std::{
+    collections::HashSet,
+    fs::OpenOptions,
+    io::{self, BufWriter, Write},
+    path::{Path, PathBuf},
+};
+
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use super::{
+    error::Error,
+    outlook::{Attachment, Outlook},
+};
+
+// Number of hex characters decoded and written at once.
+pub(super) const CHUNK_SIZE: usize = 64 * 1024;
+
+// HashAlgorithm is the digest computed over each
+// extracted attachment while it is being written.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum HashAlgorithm {
+    Sha256,
+}
+
+impl HashAlgorithm {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_ascii_lowercase().as_str() {
+            "sha256" | "sha-256" => Some(HashAlgorithm::Sha256),
+            _ => None,
+        }
+    }
+}
+
+// Parses a human readable size such as "512", "1k", "50M"
+// or "2G" into bytes. Suffixes are powers of 1024.
+pub fn parse_size(size: &str) -> Option<u64> {
+    let size = size.trim();
+    let split = size
+        .find(|c: char| !c.is_ascii_digit())
+        .unwrap_or(size.len());
+    let (digits, suffix) = size.split_at(split);
+    let multiplier: u64 = match suffix.to_ascii_lowercase().as_str() {
+        "" | "b" => 1,
+        "k" | "kb" => 1 << 10,
+        "m" | "mb" => 1 << 20,
+        "g" | "gb" => 1 << 30,
+        _ => return None,
+    };
+    digits.parse::<u64>().ok()?.checked_mul(multiplier)
+}
+
+// Case-insensitive glob match supporting `*` and `?`.
+fn glob_match(pattern: &str, name: &str) -> bool {
+    let pattern: Vec<char> = pattern.to_lowercase().chars().collect();
+    let name: Vec<char> = name.to_lowercase().chars().collect();
+    let (mut p, mut n) = (0, 0);
+    // Position of the last `*` seen and where it started matching.
+    let mut star: Option<(usize, usize)> = None;
+    while n < name.len() {
+        if p < pattern.len() && (pattern[p] == '?' || pattern[p] == name[n]) {
+            p += 1;
+            n += 1;
+        } else if p < pattern.len() && pattern[p] == '*' {
+            star = Some((p, n));
+            p += 1;
+        } else if let Some((star_p, star_n)) = star {
+            p = star_p + 1;
+            n = star_n + 1;
+            star = Some((star_p, star_n + 1));
+        } else {
+            return false;
+        }
+    }
+    pattern[p..].iter().all(|&c| c == '*')
+}
+
+// AttachmentFilter selects which attachments get extracted,
+// by file name glob and payload size bounds (inclusive).
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AttachmentFilter {
+    pub pattern: Option<String>,
+    pub min_size: Option<u64>,
+    pub max_size: Option<u64>,
+}
+
+impl AttachmentFilter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn pattern(mut self, pattern: &str) -> Self {
+        self.pattern = Some(pattern.to_string());
+        self
+    }
+
+    pub fn min_size(mut self, size: u64) -> Self {
+        self.min_size = Some(size);
+        self
+    }
+
+    pub fn max_size(mut self, size: u64) -> Self {
+        self.max_size = Some(size);
+        self
+    }
+
+    pub fn matches(&self, attachment: &Attachment) -> bool {
+        let size = (attachment.payload.len() / 2) as u64;
+        let name = ExtractedAttachment::file_name_of(attachment);
+        self.pattern
+            .as_ref()
+            .is_none_or(|pattern| glob_match(pattern, &name))
+            && self.min_size.is_none_or(|min| size >= min)
+            && self.max_size.is_none_or(|max| size <= max)
+    }
+}
+
+// Last component of a name, without the characters
+// separating directories or drives on any platform, nor
+// control ones. None when nothing usable is left, e.g.
+// for "..".
+fn sanitize_name(name: &str) -> Option<String> {
+    let name = name.rsplit(['/', '\\', ':']).next().unwrap_or_default();
+    let name: String = name.chars().filter(|c| !c.is_control()).collect();
+    let name = name.trim();
+    (!name.is_empty() && name != "." && name != "..").then(|| name.to_string())
+}
+
+// ExtractedAttachment is one entry of the manifest
+// written for an extraction run.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ExtractedAttachment {
+    pub index: usize,
+    pub file_name: String,
+    pub path: PathBuf,
+    pub size: u64,
+    pub mime_tag: String,
+    pub hash: Option<String>,
+}
+
+impl ExtractedAttachment {
+    // Name an attachment is saved under, taken from its
+    // display name or short file name, without any path.
+    pub(crate) fn file_name_of(attachment: &Attachment) -> String {
+        let name = [&attachment.display_name, &attachment.file_name]
+            .iter()
+            .find(|name| !name.is_empty())
+            .map(|name| name.to_string())
+            .unwrap_or_default();
+        sanitize_name(&name).unwrap_or_default()
+    }
+
+    // Decodes the hex payload chunk by chunk into the
+    // writer, feeding the digest along the way.
+    fn write_payload<W: Write>(
+        payload: &str,
+        writer: &mut W,
+        hash: Option<HashAlgorithm>,
+    ) -> Result<Option<String>, Error> {
+        let mut hasher = hash.map(|_| Sha256::new());
+        for chunk in payload.as_bytes().chunks(CHUNK_SIZE) {
+            let bytes =
+                hex::decode(chunk).map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))?;
+            if let Some(hasher) = hasher.as_mut() {
+                hasher.update(&bytes);
+            }
+            writer.write_all(&bytes)?;
+        }
+        writer.flush()?;
+        Ok(hasher.map(|hasher| hex::encode(hasher.finalize())))
+    }
+}
+
+// ExtractManifest lists every attachment written
+// by Outlook::extract_attachments.
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ExtractManifest {
+    pub attachments: Vec<ExtractedAttachment>,
+}
+
+impl ExtractManifest {
+    pub fn to_json(&self) -> Result<String, Error> {
+        Ok(serde_json::to_string(self)?)
+    }
+}
+
+impl Outlook {
+    // Writes the attachments matching filter into dir, which
+    // must exist. Attachments without data (e.g. embedded
+    // messages) are skipped. Files already in dir are never
+    // overwritten: clashing file names are prefixed with the
+    // attachment index, and a counter if need be.
+    pub fn extract_attachments<P: AsRef<Path>>(
+        &self,
+        dir: P,
+        filter: &AttachmentFilter,
+        hash: Option<HashAlgorithm>,
+    ) -> Result<ExtractManifest, Error> {
+        let mut manifest = ExtractManifest::default();
+        let mut used_names: HashSet<String> = HashSet::new();
+        for (index, attachment) in self.attachments.iter().enumerate() {
+            if attachment.payload.is_empty() || !filter.matches(attachment) {
+                continue;
+            }
+            let name = match ExtractedAttachment::file_name_of(attachment) {
+                name if name.is_empty() => {
+                    let extension = sanitize_name(&attachment.extension)
+                        .filter(|extension| extension.starts_with('.'))
+                        .unwrap_or_default();
+                    format!("attachment_{}{}", index, extension)
+                }
+                name => name,
+            };
+            let mut attempt = 0;
+            let (file_name, path, file) = loop {
+                let file_name = match attempt {
+                    0 => name.clone(),
+                    1 => format!("{}_{}", index, name),
+                    _ => format!("{}_{}_{}", index, attempt, name),
+                };
+                attempt += 1;
+                if !used_names.insert(file_name.to_lowercase()) {
+                    continue;
+                }
+                let path = dir.as_ref().join(&file_name);
+                match OpenOptions::new().write(true).create_new(true).open(&path) {
+                    Ok(file) => break (file_name, path, file),
+                    Err(err) if err.kind() == io::ErrorKind::AlreadyExists => continue,
+                    Err(err) => return Err(err.into()),
+                }
+            };
+            let mut writer = BufWriter::new(file);
+            let hash = ExtractedAttachment::write_payload(&attachment.payload, &mut writer, hash)?;
+            manifest.attachments.push(ExtractedAttachment {
+                index,
+                file_name,
+                path,
+                size: (attachment.payload.len() / 2) as u64,
+                mime_tag: attachment.mime_tag.clone(),
+                hash,
+            });
+        }
+        Ok(manifest)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use super::{
+        super::builder::OutlookBuilder, glob_match, parse_size, sanitize_name, AttachmentFilter,
+        ExtractedAttachment, HashAlgorithm, Outlook,
+    };
+
+    #[test]
+    fn test_parse_size() {
+        assert_eq!(parse_size("512"), Some(512));
+        assert_eq!(parse_size("1k"), Some(1024));
+        assert_eq!(parse_size("50M"), Some(50 * 1024 * 1024));
+        assert_eq!(parse_size("2GB"), Some(2 * 1024 * 1024 * 1024));
+        assert_eq!(parse_size("12x"), None);
+        assert_eq!(parse_size("k"), None);
+    }
+
+    #[test]
+    fn test_glob_match() {
+        assert!(glob_match("*.pdf", "report.pdf"));
+        assert!(glob_match("*.pdf", "REPORT.PDF"));
+        assert!(!glob_match("*.pdf", "report.pdf.exe"));
+        assert!(glob_match("image00?.*", "image001.png"));
+        assert!(glob_match("*", ""));
+        assert!(glob_match("a*b*c", "aXXbYYbc"));
+        assert!(!glob_match("a*b*c", "aXXbYY"));
+    }
+
+    #[test]
+    fn test_write_payload() {
+        let mut buff: Vec<u8> = vec![];
+        let hash =
+            ExtractedAttachment::write_payload("616263", &mut buff, Some(HashAlgorithm::Sha256))
+                .unwrap();
+        assert_eq!(buff, b"abc".to_vec());
+        assert_eq!(
+            hash,
+            Some("ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad".to_string())
+        );
+    }
+
+    #[test]
+    fn test_filter() {
+        let outlook = Outlook::from_path("data/attachment.msg").unwrap();
+        let names = |filter: &AttachmentFilter| {
+            outlook
+                .attachments
+                .iter()
+                .filter(|attachment| filter.matches(attachment))
+                .map(|attachment| attachment.display_name.clone())
+                .collect::<Vec<String>>()
+        };
+        assert_eq!(names(&AttachmentFilter::new()).len(), 3);
+        assert_eq!(
+            names(&AttachmentFilter::new().pattern("*.doc")),
+            vec!["loan_proposal.doc".to_string()]
+        );
+        assert_eq!(names(&AttachmentFilter::new().min_size(u64::MAX)).len(), 0);
+        assert_eq!(names(&AttachmentFilter::new().max_size(0)).len(), 0);
+    }
+
+    #[test]
+    fn test_extract_attachments() {
+        let dir = std::env::temp_dir().join("msg_parser_test_extract_attachments");
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+
+        let outlook = Outlook::from_path("data/attachment.msg").unwrap();
+        let filter = AttachmentFilter::new().pattern("image*");
+        let manifest = outlook
+            .extract_attachments(&dir, &filter, Some(HashAlgorithm::Sha256))
+            .unwrap();
+        assert_eq!(manifest.attachments.len(), 2);
+        assert_eq!(manifest.attachments[0].index, 1);
+        assert_eq!(manifest.attachments[0].file_name, "image001.png".to_string());
+        assert_eq!(manifest.attachments[0].mime_tag, "image/png".to_string());
+
+        let written = fs::read(&manifest.attachments[0].path).unwrap();
+        assert_eq!(written.len() as u64, manifest.attachments[0].size);
+        // PNG signature
+        assert_eq!(&written[..4], &[0x89, 0x50, 0x4E, 0x47]);
+        assert_eq!(manifest.attachments[0].hash.as_ref().unwrap().len(), 64);
+        assert!(manifest.to_json().unwrap().contains("image002.jpg"));
+
+        // Files of an earlier run are left as they are.
+        fs::write(dir.join("1_image001.png"), b"kept").unwrap();
+        let manifest = outlook.extract_attachments(&dir, &filter, None).unwrap();
+        let names: Vec<&str> = manifest
+            .attachments
+            .iter()
+            .map(|attachment| attachment.file_name.as_str())
+            .collect();
+        assert_eq!(names, ["1_2_image001.png", "2_image002.jpg"]);
+        assert_eq!(fs::read(dir.join("image001.png")).unwrap(), written);
+        assert_eq!(fs::read(dir.join("1_image001.png")).unwrap(), b"kept".to_vec());
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_sanitize_name() {
+        assert_eq!(sanitize_name("report.pdf"), Some("report.pdf".to_string()));
+        assert_eq!(sanitize_name("../../etc/passwd"), Some("passwd".to_string()));
+        assert_eq!(sanitize_name("..\\..\\x.exe"), Some("x.exe".to_string()));
+        assert_eq!(sanitize_name("C:evil.bat"), Some("evil.bat".to_string()));
+        assert_eq!(sanitize_name("a\u{0}b\r\n"), Some("ab".to_string()));
+        assert_eq!(sanitize_name("dir/.."), None);
+        assert_eq!(sanitize_name(" "), None);
+    }
+
+    #[test]
+    fn test_extract_unsafe_names() {
+        let dir = std::env::temp_dir().join("msg_parser_test_extract_unsafe_names");
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+
+        let mut outlook = OutlookBuilder::new()
+            .attachment("a.txt", "text/plain", b"1".to_vec())
+            .attachment("a.txt", "text/plain", b"2".to_vec())
+            .attachment("1_a.txt", "text/plain", b"3".to_vec())
+            .attachment("", "", b"4".to_vec())
+            .attachment("", "", b"5".to_vec())
+            .build()
+            .unwrap();
+        // The second "a.txt" is renamed "1_a.txt", which the
+        // third one then clashes with.
+        outlook.attachments[3].extension = "/../x".to_string();
+        outlook.attachments[4].extension = "\\..\\..\\x".to_string();
+        let manifest = outlook
+            .extract_attachments(&dir, &AttachmentFilter::new(), None)
+            .unwrap();
+        let names: Vec<&str> = manifest
+            .attachments
+            .iter()
+            .map(|attachment| attachment.file_name.as_str())
+            .collect();
+        assert_eq!(names, vec!["a.txt", "1_a.txt", "2_1_a.txt", "attachment_3", "attachment_4"]);
+        for attachment in &manifest.attachments {
+            assert_eq!(attachment.path.parent(), Some(dir.as_path()));
+        }
+        assert_eq!(fs::read(dir.join("1_a.txt")).unwrap(), b"2".to_vec());
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
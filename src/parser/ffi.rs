@@ -0,0 +1,288 @@
+use std::{
+    ffi::{c_char, CString},
+    panic::{self, AssertUnwindSafe},
+    ptr, slice,
+};
+
+use super::{
+    extract::ExtractedAttachment,
+    outlook::{Attachment, Outlook},
+};
+
+// Codes the functions return, besides those of Error::code.
+// include/msg_parser.h declares them too.
+pub const MSG_OK: u32 = 0;
+pub const MSG_INVALID_ARGUMENT: u32 = 200; // Null pointer, or a string holding a NUL byte
+pub const MSG_NO_ATTACHMENT: u32 = 201;    // Attachment index out of range
+pub const MSG_PANIC: u32 = 202;            // Parsing panicked, which is a bug to report
+
+// MsgMessage is the parsed message C callers hold through
+// a pointer, from msg_open until msg_free.
+pub struct MsgMessage {
+    outlook: Outlook,
+}
+
+// Runs the body of a function, returning failed if it
+// panics, as unwinding into the caller is undefined.
+fn catch_panic<T, F: FnOnce() -> T>(failed: T, body: F) -> T {
+    panic::catch_unwind(AssertUnwindSafe(body)).unwrap_or(failed)
+}
+
+// Bytes of the file a caller passed, an empty file being
+// allowed to be null.
+unsafe fn input<'a>(data: *const u8, len: usize) -> Option<&'a [u8]> {
+    match (data.is_null(), len) {
+        (true, 0) => Some(&[]),
+        (true, _) => None,
+        (false, _) => Some(slice::from_raw_parts(data, len)),
+    }
+}
+
+// Hands a string over to the caller, who frees it with
+// msg_free_string.
+unsafe fn set_string(out: *mut *mut c_char, value: String) -> u32 {
+    match CString::new(value) {
+        Ok(value) => {
+            *out = value.into_raw();
+            MSG_OK
+        }
+        Err(_) => MSG_INVALID_ARGUMENT,
+    }
+}
+
+// Parses the len bytes at data into the JSON of
+// Outlook::to_json, written to out. On failure out
+// holds the error message instead and the code of the
+// error is returned. Either string is freed with
+// msg_free_string.
+// Safety: data must point to len readable bytes and out to
+// a writable pointer.
+#[no_mangle]
+pub unsafe extern "C" fn msg_parse_json(data: *const u8, len: usize, out: *mut *mut c_char) -> u32 {
+    catch_panic(MSG_PANIC, || {
+        if out.is_null() {
+            return MSG_INVALID_ARGUMENT;
+        }
+        *out = ptr::null_mut();
+        let slice = match input(data, len) {
+            Some(slice) => slice,
+            None => return MSG_INVALID_ARGUMENT,
+        };
+        match Outlook::from_slice(slice).and_then(|outlook| outlook.to_json()) {
+            Ok(json) => set_string(out, json),
+            Err(err) => match set_string(out, err.to_string()) {
+                MSG_OK => err.code(),
+                failed => failed,
+            },
+        }
+    })
+}
+
+// Parses the len bytes at data into a message written
+// to out, for its attachments to be enumerated. The
+// message is freed with msg_free.
+// Safety: data must point to len readable bytes and out to
+// a writable pointer.
+#[no_mangle]
+pub unsafe extern "C" fn msg_open(data: *const u8, len: usize, out: *mut *mut MsgMessage) -> u32 {
+    catch_panic(MSG_PANIC, || {
+        if out.is_null() {
+            return MSG_INVALID_ARGUMENT;
+        }
+        *out = ptr::null_mut();
+        let slice = match input(data, len) {
+            Some(slice) => slice,
+            None => return MSG_INVALID_ARGUMENT,
+        };
+        match Outlook::from_slice(slice) {
+            Ok(outlook) => {
+                *out = Box::into_raw(Box::new(MsgMessage { outlook }));
+                MSG_OK
+            }
+            Err(err) => err.code(),
+        }
+    })
+}
+
+// Number of attachments of message, 0 if it is null.
+// Safety: message must be null or returned by msg_open.
+#[no_mangle]
+pub unsafe extern "C" fn msg_attachment_count(message: *const MsgMessage) -> usize {
+    catch_panic(0, || {
+        message.as_ref().map_or(0, |message| message.outlook.attachments.len())
+    })
+}
+
+// Writes the file name of the attachment at index to
+// out, freed with msg_free_string.
+// Safety: message must be null or returned by msg_open, and
+// out must point to a writable pointer.
+#[no_mangle]
+pub unsafe extern "C" fn msg_attachment_name(message: *const MsgMessage, index: usize, out: *mut *mut c_char) -> u32 {
+    catch_panic(MSG_PANIC, || {
+        attachment_string(message, index, out, ExtractedAttachment::file_name_of)
+    })
+}
+
+// Writes the MIME type of the attachment at index to
+// out, freed with msg_free_string.
+// Safety: Same as msg_attachment_name.
+#[no_mangle]
+pub unsafe extern "C" fn msg_attachment_mime_type(message: *const MsgMessage, index: usize, out: *mut *mut c_char) -> u32 {
+    catch_panic(MSG_PANIC, || {
+        attachment_string(message, index, out, |attachment| attachment.mime_tag.clone())
+    })
+}
+
+unsafe fn attachment_string<F>(message: *const MsgMessage, index: usize, out: *mut *mut c_char, value: F) -> u32
+where
+    F: Fn(&Attachment) -> String,
+{
+    if out.is_null() {
+        return MSG_INVALID_ARGUMENT;
+    }
+    *out = ptr::null_mut();
+    let message = match message.as_ref() {
+        Some(message) => message,
+        None => return MSG_INVALID_ARGUMENT,
+    };
+    match message.outlook.attachments.get(index) {
+        Some(attachment) => set_string(out, value(attachment)),
+        None => MSG_NO_ATTACHMENT,
+    }
+}
+
+// Writes the data of the attachment at index to out
+// and its size to len, freed with msg_free_bytes.
+// Embedded messages have no data, out being null.
+// Safety: message must be null or returned by msg_open, out
+// and len must be writable.
+#[no_mangle]
+pub unsafe extern "C" fn msg_attachment_data(
+    message: *const MsgMessage,
+    index: usize,
+    out: *mut *mut u8,
+    len: *mut usize,
+) -> u32 {
+    catch_panic(MSG_PANIC, || {
+        if out.is_null() || len.is_null() {
+            return MSG_INVALID_ARGUMENT;
+        }
+        *out = ptr::null_mut();
+        *len = 0;
+        let message = match message.as_ref() {
+            Some(message) => message,
+            None => return MSG_INVALID_ARGUMENT,
+        };
+        let attachment = match message.outlook.attachments.get(index) {
+            Some(attachment) => attachment,
+            None => return MSG_NO_ATTACHMENT,
+        };
+        let bytes = hex::decode(&attachment.payload).unwrap_or_default();
+        if !bytes.is_empty() {
+            let bytes = bytes.into_boxed_slice();
+            *len = bytes.len();
+            *out = Box::into_raw(bytes) as *mut u8;
+        }
+        MSG_OK
+    })
+}
+
+// Frees a message returned by msg_open.
+// Safety: message must be null or returned by msg_open, and
+// not freed before.
+#[no_mangle]
+pub unsafe extern "C" fn msg_free(message: *mut MsgMessage) {
+    if !message.is_null() {
+        drop(Box::from_raw(message));
+    }
+}
+
+// Frees a string returned by any of the functions.
+// Safety: value must be null or returned by one of them, and
+// not freed before.
+#[no_mangle]
+pub unsafe extern "C" fn msg_free_string(value: *mut c_char) {
+    if !value.is_null() {
+        drop(CString::from_raw(value));
+    }
+}
+
+// Frees the data returned by msg_attachment_data.
+// Safety: data and len must be those it returned, not freed
+// before.
+#[no_mangle]
+pub unsafe extern "C" fn msg_free_bytes(data: *mut u8, len: usize) {
+    if !data.is_null() {
+        drop(Box::from_raw(ptr::slice_from_raw_parts_mut(data, len)));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        ffi::{c_char, CStr},
+        ptr,
+    };
+
+    use super::{
+        msg_attachment_count, msg_attachment_data, msg_attachment_mime_type, msg_attachment_name,
+        msg_free, msg_free_bytes, msg_free_string, msg_open, msg_parse_json, MsgMessage,
+        catch_panic, MSG_INVALID_ARGUMENT, MSG_NO_ATTACHMENT, MSG_OK, MSG_PANIC,
+    };
+
+    fn take_string(value: *mut c_char) -> String {
+        let string = unsafe { CStr::from_ptr(value) }.to_str().unwrap().to_string();
+        unsafe { msg_free_string(value) };
+        string
+    }
+
+    #[test]
+    fn test_parse_json() {
+        let bytes = std::fs::read("data/attachment.msg").unwrap();
+        let mut out: *mut c_char = ptr::null_mut();
+        assert_eq!(unsafe { msg_parse_json(bytes.as_ptr(), bytes.len(), &mut out) }, MSG_OK);
+        let json: serde_json::Value = serde_json::from_str(&take_string(out)).unwrap();
+        assert_eq!(json["attachments"].as_array().unwrap().len(), 3);
+
+        // Errors have the code of Error::code, and the message.
+        assert_eq!(unsafe { msg_parse_json(bytes.as_ptr(), 3000, &mut out) }, 2);
+        assert_eq!(take_string(out), "Error parsing file with ole: File is too short");
+        assert_eq!(unsafe { msg_parse_json(ptr::null(), 10, &mut out) }, MSG_INVALID_ARGUMENT);
+        assert!(out.is_null());
+        assert_eq!(unsafe { msg_parse_json(bytes.as_ptr(), bytes.len(), ptr::null_mut()) }, MSG_INVALID_ARGUMENT);
+    }
+
+    #[test]
+    fn test_attachments() {
+        let bytes = std::fs::read("data/attachment.msg").unwrap();
+        let mut message: *mut MsgMessage = ptr::null_mut();
+        assert_eq!(unsafe { msg_open(bytes.as_ptr(), bytes.len(), &mut message) }, MSG_OK);
+        assert_eq!(unsafe { msg_attachment_count(message) }, 3);
+
+        let mut out: *mut c_char = ptr::null_mut();
+        assert_eq!(unsafe { msg_attachment_name(message, 0, &mut out) }, MSG_OK);
+        assert_eq!(take_string(out), "loan_proposal.doc");
+        assert_eq!(unsafe { msg_attachment_mime_type(message, 0, &mut out) }, MSG_OK);
+        assert_eq!(take_string(out), "application/msword");
+        assert_eq!(unsafe { msg_attachment_name(message, 3, &mut out) }, MSG_NO_ATTACHMENT);
+
+        let mut data: *mut u8 = ptr::null_mut();
+        let mut len = 0;
+        assert_eq!(unsafe { msg_attachment_data(message, 0, &mut data, &mut len) }, MSG_OK);
+        assert_eq!(len, 16896);
+        assert_eq!(unsafe { std::slice::from_raw_parts(data, 8) }, [0xD0, 0xCF, 0x11, 0xE0, 0xA1, 0xB1, 0x1A, 0xE1]);
+        unsafe { msg_free_bytes(data, len) };
+        unsafe { msg_free(message) };
+
+        assert_eq!(unsafe { msg_open(bytes.as_ptr(), 100, &mut message) }, 2);
+        assert!(message.is_null());
+        assert_eq!(unsafe { msg_attachment_count(message) }, 0);
+    }
+
+    #[test]
+    fn test_catch_panic() {
+        assert_eq!(catch_panic(MSG_PANIC, || MSG_OK), MSG_OK);
+        assert_eq!(catch_panic(MSG_PANIC, || -> u32 { panic!("bug") }), MSG_PANIC);
+    }
+}
@@ -0,0 +1,129 @@
+use sha2::{Digest, Sha256};
+
+use super::{
+    diff::AttachmentDigest,
+    outlook::{Outlook, Person},
+};
+
+// Times are compared to the minute, exports of the same
+// message differing in the seconds they record.
+const TIME_PRECISION: i64 = 60;
+
+// Collapses runs of whitespace, line endings included, into
+// one space.
+fn normalize(text: &str) -> String {
+    text.split_whitespace().collect::<Vec<&str>>().join(" ")
+}
+
+// Addresses of persons, lowercased and sorted, for the order
+// of the recipients not to matter.
+fn addresses(persons: &[Person]) -> Vec<String> {
+    let mut addresses: Vec<String> = persons
+        .iter()
+        .map(|person| match person.email.is_empty() {
+            true => normalize(&person.name),
+            false => person.email.trim().to_lowercase(),
+        })
+        .collect();
+    addresses.sort_unstable();
+    addresses
+}
+
+// Fingerprint feeds fields to the digest, each preceded by
+// its length so that no two lists of fields run together.
+struct Fingerprint {
+    hasher: Sha256,
+}
+
+impl Fingerprint {
+    fn add(&mut self, field: &str) {
+        self.hasher.update((field.len() as u64).to_le_bytes());
+        self.hasher.update(field.as_bytes());
+    }
+
+    fn add_all(&mut self, fields: &[String]) {
+        self.add(&fields.len().to_string());
+        for field in fields {
+            self.add(field);
+        }
+    }
+}
+
+impl Outlook {
+    // Hex SHA-256 identifying the message across exports,
+    // for deduplicating overlapping PST and MSG ones. It
+    // covers the sender, recipients, subject, message id,
+    // time sent, body text and attachment digests, whitespace
+    // and the case of addresses being normalized. The time
+    // sent is taken to the minute, and times that exports
+    // set, e.g. of delivery or modification, are left out.
+    pub fn fingerprint(&self) -> String {
+        let mut fingerprint = Fingerprint { hasher: Sha256::new() };
+        fingerprint.add_all(&addresses(std::slice::from_ref(&self.sender)));
+        fingerprint.add_all(&addresses(&self.to));
+        fingerprint.add_all(&addresses(&self.cc));
+        fingerprint.add_all(&addresses(&self.bcc));
+        fingerprint.add(&normalize(&self.subject));
+        fingerprint.add(self.message_id.trim());
+        let sent = self.submit_time.or(self.delivery_time);
+        let minute = sent.map(|time| time.timestamp().div_euclid(TIME_PRECISION));
+        fingerprint.add(&minute.map(|minute| minute.to_string()).unwrap_or_default());
+        fingerprint.add(&normalize(&self.body_text()));
+        let mut digests: Vec<String> = self
+            .attachments
+            .iter()
+            .map(|attachment| AttachmentDigest::create(attachment).sha256)
+            .collect();
+        digests.sort_unstable();
+        fingerprint.add_all(&digests);
+        hex::encode(fingerprint.hasher.finalize())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{TimeZone, Utc};
+
+    use super::super::{builder::OutlookBuilder, outlook::Outlook};
+
+    fn builder() -> OutlookBuilder {
+        OutlookBuilder::new()
+            .subject("Quarterly report")
+            .sender("Alice", "alice@example.com")
+            .to("Bob", "bob@example.com")
+            .to("Carol", "carol@example.com")
+            .message_id("<1234@example.com>")
+            .body("Figures attached.\r\nRegards")
+            .attachment("report.csv", "text/csv", b"a,b\n1,2\n".to_vec())
+    }
+
+    #[test]
+    fn test_fingerprint() {
+        let date = Utc.with_ymd_and_hms(2024, 3, 1, 9, 30, 12).unwrap();
+        let fingerprint = builder().date(date).build().unwrap().fingerprint();
+        assert_eq!(fingerprint.len(), 64);
+
+        // Seconds of the time, whitespace, the case of
+        // addresses and the order of recipients do not
+        // change it.
+        let mut outlook = builder()
+            .date(Utc.with_ymd_and_hms(2024, 3, 1, 9, 30, 47).unwrap())
+            .build()
+            .unwrap();
+        outlook.body = "Figures  attached.\nRegards\n".to_string();
+        outlook.to.reverse();
+        outlook.to[0].email = "CAROL@example.com".to_string();
+        outlook.delivery_time = None;
+        assert_eq!(outlook.fingerprint(), fingerprint);
+
+        outlook.attachments[0].payload = hex::encode("a,b\n1,3\n");
+        assert_ne!(outlook.fingerprint(), fingerprint);
+        let other = builder().date(date).subject("Quarterly report (2)").build().unwrap();
+        assert_ne!(other.fingerprint(), fingerprint);
+        let other = builder().date(Utc.with_ymd_and_hms(2024, 3, 1, 9, 31, 12).unwrap()).build().unwrap();
+        assert_ne!(other.fingerprint(), fingerprint);
+
+        let outlook = Outlook::from_path("data/test_email.msg").unwrap();
+        assert_eq!(outlook.fingerprint(), Outlook::from_path("data/test_email.msg").unwrap().fingerprint());
+    }
+}
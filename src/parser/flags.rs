@@ -0,0 +1,107 @@
+use serde::{Deserialize, Serialize};
+
+use super::{decode::DataType, storage::Properties};
+
+// Bits of "MessageFlags".
+// Refer to MS-OXCMSG 2.2.1.6
+const MSGFLAG_READ: i64 = 0x0000_0001;
+const MSGFLAG_UNMODIFIED: i64 = 0x0000_0002;
+const MSGFLAG_SUBMITTED: i64 = 0x0000_0004;
+const MSGFLAG_UNSENT: i64 = 0x0000_0008;
+const MSGFLAG_HASATTACH: i64 = 0x0000_0010;
+const MSGFLAG_FROMME: i64 = 0x0000_0020;
+const MSGFLAG_ASSOCIATED: i64 = 0x0000_0040;
+const MSGFLAG_RESEND: i64 = 0x0000_0080;
+
+// MessageFlags tells the state of the message,
+// e.g. whether it was read or is still a draft.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct MessageFlags {
+    pub read: bool,            // mfRead
+    pub unmodified: bool,      // mfUnmodified
+    pub submitted: bool,       // mfSubmitted
+    pub unsent: bool,          // mfUnsent, the message is a draft
+    pub has_attachments: bool, // mfHasAttach
+    pub from_me: bool,         // mfFromMe
+    pub associated: bool,      // mfFAI
+    pub resend: bool,          // mfResend
+}
+
+impl MessageFlags {
+    pub fn from_value(value: i64) -> Self {
+        Self {
+            read: value & MSGFLAG_READ != 0,
+            unmodified: value & MSGFLAG_UNMODIFIED != 0,
+            submitted: value & MSGFLAG_SUBMITTED != 0,
+            unsent: value & MSGFLAG_UNSENT != 0,
+            has_attachments: value & MSGFLAG_HASATTACH != 0,
+            from_me: value & MSGFLAG_FROMME != 0,
+            associated: value & MSGFLAG_ASSOCIATED != 0,
+            resend: value & MSGFLAG_RESEND != 0,
+        }
+    }
+
+    pub fn to_value(&self) -> i64 {
+        [
+            (self.read, MSGFLAG_READ),
+            (self.unmodified, MSGFLAG_UNMODIFIED),
+            (self.submitted, MSGFLAG_SUBMITTED),
+            (self.unsent, MSGFLAG_UNSENT),
+            (self.has_attachments, MSGFLAG_HASATTACH),
+            (self.from_me, MSGFLAG_FROMME),
+            (self.associated, MSGFLAG_ASSOCIATED),
+            (self.resend, MSGFLAG_RESEND),
+        ]
+        .iter()
+        .filter(|(set, _)| *set)
+        .fold(0, |value, (_, flag)| value | flag)
+    }
+
+    pub fn create_from_props(props: &Properties) -> Self {
+        props
+            .get("MessageFlags")
+            .and_then(DataType::as_i64)
+            .map_or_else(Self::default, Self::from_value)
+    }
+
+    pub fn is_draft(&self) -> bool {
+        self.unsent
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        super::{decode::DataType, outlook::Outlook, storage::Properties},
+        MessageFlags,
+    };
+
+    #[test]
+    fn test_from_value() {
+        assert_eq!(MessageFlags::from_value(0), MessageFlags::default());
+        let flags = MessageFlags::from_value(0x0000_00B9);
+        assert!(flags.read);
+        assert!(flags.unsent);
+        assert!(flags.is_draft());
+        assert!(flags.has_attachments);
+        assert!(flags.from_me);
+        assert!(flags.resend);
+        assert!(!flags.unmodified);
+        assert!(!flags.submitted);
+        assert!(!flags.associated);
+        assert_eq!(flags.to_value(), 0x0000_00B9);
+    }
+
+    #[test]
+    fn test_create_from_props() {
+        let mut props = Properties::new();
+        assert_eq!(MessageFlags::create_from_props(&props), MessageFlags::default());
+        props.insert("MessageFlags".to_string(), DataType::PtypInteger32(0x11));
+        let flags = MessageFlags::create_from_props(&props);
+        assert!(flags.read && flags.has_attachments && !flags.unsent);
+
+        let outlook = Outlook::from_path("data/attachment.msg").unwrap();
+        assert!(outlook.flags.has_attachments);
+    }
+}
@@ -0,0 +1,116 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use super::{clock::Clock, decode::DataType, storage::Properties};
+
+// FlagStatus is the state of a follow-up flag.
+// Refer to MS-OXOFLAG 2.2.1.1
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub enum FlagStatus {
+    Complete,
+    Flagged,
+}
+
+impl FlagStatus {
+    pub fn from_value(value: i64) -> Option<Self> {
+        match value {
+            1 => Some(FlagStatus::Complete),
+            2 => Some(FlagStatus::Flagged),
+            _ => None,
+        }
+    }
+}
+
+// FollowUpFlag holds the follow-up flag of a message.
+// The reminder that comes with it is the one of the message.
+// Refer to MS-OXOFLAG 2.2.1
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct FollowUpFlag {
+    pub status: FlagStatus,                   // "FlagStatus"
+    pub request: String,                      // "FlagRequest", e.g. "Follow up"
+    pub start_date: Option<DateTime<Utc>>,    // "TaskStartDate"
+    pub due_date: Option<DateTime<Utc>>,      // "TaskDueDate"
+    pub reply_time: Option<DateTime<Utc>>,    // "ReplyTime", reply requested by
+    pub complete_time: Option<DateTime<Utc>>, // "FlagCompleteTime"
+}
+
+impl FollowUpFlag {
+    // Returns None when the message was never flagged.
+    pub fn create_from_props(props: &Properties) -> Option<Self> {
+        let status = props
+            .get("FlagStatus")
+            .and_then(DataType::as_i64)
+            .and_then(FlagStatus::from_value)?;
+        let time = |key: &str| props.get(key).and_then(DataType::as_time);
+        Some(Self {
+            status,
+            request: props
+                .get("FlagRequest")
+                .and_then(DataType::as_str)
+                .unwrap_or_default()
+                .to_string(),
+            start_date: time("TaskStartDate"),
+            due_date: time("TaskDueDate"),
+            reply_time: time("ReplyTime"),
+            complete_time: time("FlagCompleteTime"),
+        })
+    }
+
+    pub fn is_overdue(&self, clock: &dyn Clock) -> bool {
+        self.status == FlagStatus::Flagged && self.due_date.is_some_and(|due_date| due_date < clock.now())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{TimeZone, Utc};
+
+    use super::{
+        super::{clock::FixedClock, decode::DataType, outlook::Outlook, storage::Properties},
+        FlagStatus, FollowUpFlag,
+    };
+
+    #[test]
+    fn test_create_follow_up_flag() {
+        let due_date = Utc.with_ymd_and_hms(2021, 6, 4, 0, 0, 0).unwrap();
+        let on = |day: u32| FixedClock(Utc.with_ymd_and_hms(2021, 6, day, 0, 0, 0).unwrap());
+        let mut props = Properties::new();
+        assert_eq!(FollowUpFlag::create_from_props(&props), None);
+        props.insert("FlagStatus".to_string(), DataType::PtypInteger32(0));
+        assert_eq!(FollowUpFlag::create_from_props(&props), None);
+
+        props.insert("FlagStatus".to_string(), DataType::PtypInteger32(2));
+        props.insert(
+            "FlagRequest".to_string(),
+            DataType::PtypString("Review".to_string()),
+        );
+        props.insert("TaskDueDate".to_string(), DataType::PtypTime(due_date));
+        let flag = FollowUpFlag::create_from_props(&props).unwrap();
+        assert_eq!(
+            flag,
+            FollowUpFlag {
+                status: FlagStatus::Flagged,
+                request: "Review".to_string(),
+                start_date: None,
+                due_date: Some(due_date),
+                reply_time: None,
+                complete_time: None,
+            }
+        );
+        assert!(flag.is_overdue(&on(5)));
+        assert!(!flag.is_overdue(&on(3)));
+
+        props.insert("FlagStatus".to_string(), DataType::PtypInteger32(1));
+        let flag = FollowUpFlag::create_from_props(&props).unwrap();
+        assert_eq!(flag.status, FlagStatus::Complete);
+        assert!(!flag.is_overdue(&on(5)));
+    }
+
+    #[test]
+    fn test_not_flagged() {
+        let outlook = Outlook::from_path("data/test_email.msg").unwrap();
+        assert_eq!(outlook.follow_up, None);
+    }
+}
@@ -0,0 +1,381 @@
+use base64::{engine::general_purpose::STANDARD, Engine};
+use encoding_rs::Encoding;
+use regex::{Captures, Regex};
+use serde::{Deserialize, Serialize};
+
+use super::outlook::Outlook;
+
+// Headers holds the fields of the transport message
+// headers in the order they appear, duplicates included.
+// Refer to RFC 5322 2.2
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct Headers {
+    fields: Vec<(String, String)>,
+}
+
+impl Headers {
+    pub fn parse(text: &str) -> Self {
+        let mut fields: Vec<(String, String)> = vec![];
+        for line in text.split('\n') {
+            let line = line.strip_suffix('\r').unwrap_or(line);
+            // Headers end with the first empty line.
+            if line.is_empty() {
+                break;
+            }
+            // Unfolding removes the line break before whitespace.
+            if line.starts_with([' ', '\t']) {
+                if let Some((_, value)) = fields.last_mut() {
+                    value.push_str(line);
+                }
+                continue;
+            }
+            if let Some((name, value)) = line.split_once(':') {
+                fields.push((name.trim().to_string(), value.to_string()));
+            }
+        }
+        let fields = fields
+            .into_iter()
+            .map(|(name, value)| (name, decode_encoded_words(value.trim())))
+            .collect();
+        Self { fields }
+    }
+
+    // First value of the field, the name being case insensitive.
+    pub fn get(&self, name: &str) -> Option<&str> {
+        self.get_all(name).into_iter().next()
+    }
+
+    // Every value of the field, in order.
+    pub fn get_all(&self, name: &str) -> Vec<&str> {
+        self.iter()
+            .filter(|(key, _)| key.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value)
+            .collect()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.fields
+            .iter()
+            .map(|(name, value)| (name.as_str(), value.as_str()))
+    }
+
+    // Non-standard fields, e.g. "X-Mailer", in order.
+    pub fn x_headers(&self) -> Vec<(&str, &str)> {
+        self.iter()
+            .filter(|(name, _)| {
+                name.get(..2)
+                    .is_some_and(|prefix| prefix.eq_ignore_ascii_case("x-"))
+            })
+            .collect()
+    }
+
+    pub fn len(&self) -> usize {
+        self.fields.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.fields.is_empty()
+    }
+}
+
+// Removes the comments of a field, e.g. "(10.0.0.1)".
+pub(crate) fn strip_comments(value: &str) -> String {
+    let mut depth = 0usize;
+    let mut stripped = String::with_capacity(value.len());
+    for c in value.chars() {
+        match c {
+            '(' => depth += 1,
+            ')' if depth > 0 => depth -= 1,
+            _ if depth == 0 => stripped.push(c),
+            _ => {}
+        }
+    }
+    stripped
+}
+
+// Message IDs of a Message-ID, In-Reply-To or References value,
+// e.g. "<a@example.com> <b@example.com>", brackets removed.
+// Refer to RFC 5322 3.6.4
+pub(crate) fn message_ids(value: &str) -> Vec<String> {
+    let ids: Vec<String> = strip_comments(value)
+        .split('<')
+        .skip(1)
+        .filter_map(|id| id.split_once('>'))
+        .map(|(id, _)| id.trim().to_string())
+        .filter(|id| !id.is_empty())
+        .collect();
+    // Some senders leave the brackets out.
+    if ids.is_empty() && !value.trim().is_empty() && !value.contains('<') {
+        return value.split_whitespace().map(String::from).collect();
+    }
+    ids
+}
+
+// Name and address of each mailbox of an address list,
+// e.g. "\"Doe, Jane\" <jane@example.com>, bob@example.com".
+// Refer to RFC 5322 3.4
+pub(crate) fn addresses(value: &str) -> Vec<(String, String)> {
+    let mut mailboxes = vec![];
+    let mut mailbox = String::new();
+    let (mut quoted, mut bracketed) = (false, false);
+    for c in value.chars().chain(std::iter::once(',')) {
+        match c {
+            '"' => quoted = !quoted,
+            '<' if !quoted => bracketed = true,
+            '>' if !quoted => bracketed = false,
+            ',' | ';' if !quoted && !bracketed => {
+                mailboxes.push(std::mem::take(&mut mailbox));
+                continue;
+            }
+            _ => {}
+        }
+        mailbox.push(c);
+    }
+    mailboxes
+        .iter()
+        .map(|mailbox| strip_comments(mailbox))
+        .filter_map(|mailbox| {
+            let mailbox = mailbox.trim();
+            let (name, address) = match (mailbox.rfind('<'), mailbox.rfind('>')) {
+                (Some(start), Some(end)) if start < end => {
+                    (&mailbox[..start], &mailbox[start + 1..end])
+                }
+                _ => ("", mailbox),
+            };
+            let name = name.trim().trim_matches('"').trim();
+            let address = address.trim();
+            // Group names, e.g. "undisclosed-recipients:", are left out.
+            let address = address.rsplit(':').next().unwrap_or_default().trim();
+            (!address.is_empty()).then(|| (name.to_string(), address.to_string()))
+        })
+        .collect()
+}
+
+// Decodes the text of an encoded word given its charset.
+// Charsets that are not known are read as UTF-8.
+// Refer to the Encoding Standard, 4.2 Names and labels
+pub(crate) fn decode_charset(charset: &str, bytes: &[u8]) -> String {
+    // The charset may carry a language, e.g. "utf-8*en".
+    let charset = charset.split('*').next().unwrap_or_default();
+    let encoding = Encoding::for_label(charset.trim().as_bytes()).unwrap_or(encoding_rs::UTF_8);
+    let (text, _, _) = encoding.decode(bytes);
+    text.into_owned()
+}
+
+// Decodes "Q" encoded text, where underscores stand for spaces.
+fn decode_q(text: &str) -> Vec<u8> {
+    let bytes = text.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'_' => decoded.push(b' '),
+            b'=' if i + 2 < bytes.len() => {
+                let byte = std::str::from_utf8(&bytes[i + 1..i + 3])
+                    .ok()
+                    .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+                match byte {
+                    Some(byte) => {
+                        decoded.push(byte);
+                        i += 2;
+                    }
+                    None => decoded.push(b'='),
+                }
+            }
+            byte => decoded.push(byte),
+        }
+        i += 1;
+    }
+    decoded
+}
+
+// Decodes the RFC 2047 encoded words of a header value,
+// dropping the whitespace between adjacent ones.
+// Words that can't be decoded are left as they are.
+pub(crate) fn decode_encoded_words(value: &str) -> String {
+    let word = Regex::new(r"=\?([^?\s]+)\?([BbQq])\?([^?\s]*)\?=").unwrap();
+    let adjacent = Regex::new(r"(\?=)\s+(=\?)").unwrap();
+    let value = adjacent.replace_all(value, "$1$2");
+    word.replace_all(&value, |caps: &Captures| {
+        let bytes = match &caps[2] {
+            "B" | "b" => STANDARD.decode(&caps[3]).ok(),
+            _ => Some(decode_q(&caps[3])),
+        };
+        match bytes {
+            Some(bytes) => decode_charset(&caps[1], &bytes),
+            None => caps[0].to_string(),
+        }
+    })
+    .into_owned()
+}
+
+// Encodes a header value holding other than ASCII, or
+// control characters such as line breaks, as RFC 2047 "B"
+// encoded words, separated by spaces where the field may
+// be folded, each short enough to fit on a line.
+pub(crate) fn encode_words(value: &str) -> String {
+    if value.is_ascii() && !value.contains(|c: char| c.is_ascii_control() && c != '\t') {
+        return value.to_string();
+    }
+    let mut words = vec![];
+    let mut chunk = String::new();
+    for c in value.chars() {
+        // 45 bytes are 60 characters once encoded.
+        if chunk.len() + c.len_utf8() > 45 {
+            words.push(std::mem::take(&mut chunk));
+        }
+        chunk.push(c);
+    }
+    words.push(chunk);
+    words
+        .iter()
+        .map(|word| format!("=?UTF-8?B?{}?=", STANDARD.encode(word)))
+        .collect::<Vec<String>>()
+        .join(" ")
+}
+
+impl Outlook {
+    // Parsed "TransportMessageHeaders".
+    pub fn transport_headers(&self) -> Headers {
+        let text = self
+            .properties
+            .root
+            .get("TransportMessageHeaders")
+            .map(String::from)
+            .unwrap_or_default();
+        Headers::parse(&text)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{addresses, decode_encoded_words, encode_words, message_ids, Headers, Outlook};
+
+    #[test]
+    fn test_parse() {
+        let headers = Headers::parse(
+            "Received: from a\r\n by b; Sun, 14 Mar 2021 04:03:57 +0000\r\n\
+             Subject: =?utf-8?B?SGVsbG8g8J+Riw==?=\r\n\
+             Received: from c\r\n\tby a\r\n\
+             X-Empty:\r\n\
+             not a header\r\n\
+             \r\n\
+             Body: not a header either\r\n",
+        );
+        assert_eq!(headers.len(), 4);
+        assert_eq!(headers.get("subject"), Some("Hello 👋"));
+        assert_eq!(
+            headers.get_all("Received"),
+            vec!["from a by b; Sun, 14 Mar 2021 04:03:57 +0000", "from c\tby a"]
+        );
+        assert_eq!(headers.get("X-Empty"), Some(""));
+        assert_eq!(headers.get("Body"), None);
+        assert_eq!(
+            headers.iter().map(|(name, _)| name).collect::<Vec<&str>>(),
+            vec!["Received", "Subject", "Received", "X-Empty"]
+        );
+        assert!(Headers::parse("").is_empty());
+    }
+
+    #[test]
+    fn test_x_headers() {
+        let headers = Headers::parse(
+            "X-Mailer: Thunderbird\r\n\
+             Subject: test\r\n\
+             x-originating-ip: [192.0.2.1]\r\n\
+             X: not an x-header\r\n\
+             X-Mailer: again\r\n",
+        );
+        assert_eq!(
+            headers.x_headers(),
+            vec![
+                ("X-Mailer", "Thunderbird"),
+                ("x-originating-ip", "[192.0.2.1]"),
+                ("X-Mailer", "again"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_message_ids() {
+        assert_eq!(
+            message_ids("<a@example.com>\r\n <b@example.com> (comment <c@example.com>)"),
+            vec!["a@example.com".to_string(), "b@example.com".to_string()]
+        );
+        assert_eq!(message_ids("a@example.com"), vec!["a@example.com".to_string()]);
+        assert_eq!(message_ids("<>"), Vec::<String>::new());
+        assert_eq!(message_ids(""), Vec::<String>::new());
+    }
+
+    #[test]
+    fn test_addresses() {
+        let address = |name: &str, email: &str| (name.to_string(), email.to_string());
+        assert_eq!(
+            addresses("\"Doe, Jane\" <jane@example.com>, bob@example.com (Bob);Al <al@example.com>"),
+            vec![
+                address("Doe, Jane", "jane@example.com"),
+                address("", "bob@example.com"),
+                address("Al", "al@example.com"),
+            ]
+        );
+        assert_eq!(addresses("undisclosed-recipients:;"), vec![]);
+        assert_eq!(addresses(""), vec![]);
+    }
+
+    #[test]
+    fn test_decode_encoded_words() {
+        assert_eq!(
+            decode_encoded_words("=?ISO-8859-1?Q?Andr=E9?= Pirard <PIRARD@vm1.ulg.ac.be>"),
+            "André Pirard <PIRARD@vm1.ulg.ac.be>".to_string()
+        );
+        assert_eq!(
+            decode_encoded_words("=?UTF-8?Q?a_b?= =?UTF-8?Q?_c?= d"),
+            "a b c d".to_string()
+        );
+        assert_eq!(decode_encoded_words("=?UTF-8?Q?=4?="), "=4".to_string());
+        assert_eq!(
+            decode_encoded_words("=?UTF-8?B?not base64?="),
+            "=?UTF-8?B?not base64?=".to_string()
+        );
+        assert_eq!(decode_encoded_words("plain"), "plain".to_string());
+        assert_eq!(
+            decode_encoded_words("=?windows-1252?Q?=93Hi=94_=80?="),
+            "\u{201C}Hi\u{201D} \u{20AC}".to_string()
+        );
+        assert_eq!(decode_encoded_words("=?koi8-r?B?8NLJ18XU?="), "Привет".to_string());
+        assert_eq!(decode_encoded_words("=?windows-1251?Q?=CF=F0=E8=E2=E5=F2?="), "Привет".to_string());
+        assert_eq!(decode_encoded_words("=?x-unknown?Q?caf=C3=A9?="), "café".to_string());
+    }
+
+    #[test]
+    fn test_encode_words() {
+        assert_eq!(encode_words("plain"), "plain".to_string());
+        assert_eq!(encode_words("Café"), "=?UTF-8?B?Q2Fmw6k=?=".to_string());
+        let long = "Ünïcödé ".repeat(10);
+        let encoded = encode_words(&long);
+        assert!(encoded.split(' ').all(|word| word.len() <= 75));
+        assert_eq!(decode_encoded_words(&encoded), long);
+    }
+
+    #[test]
+    fn test_transport_headers() {
+        let outlook = Outlook::from_path("data/test_email_4.msg").unwrap();
+        let headers = outlook.transport_headers();
+        assert_eq!(headers.get("Subject"), Some("test"));
+        assert_eq!(headers.get_all("received").len(), 3);
+        assert_eq!(
+            headers.get("Content-Type"),
+            Some("multipart/alternative; boundary=\"b1_HY5e9qYHWVXuzkbT85tCRGVSdZ50eSlUGWb4Ryq4T0\"")
+        );
+        assert_eq!(outlook.x_headers.len(), headers.x_headers().len());
+        assert_eq!(
+            outlook
+                .x_headers
+                .iter()
+                .find(|(name, _)| name == "X-Sender-IP")
+                .map(|(_, value)| value.as_str()),
+            Some("185.70.40.140")
+        );
+    }
+}
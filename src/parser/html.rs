@@ -0,0 +1,414 @@
+use super::outlook::Outlook;
+
+// Elements ending the line before them and starting a
+// new one after them.
+const BLOCK_ELEMENTS: [&str; 21] = [
+    "address", "article", "aside", "dd", "div", "dl", "dt", "fieldset", "figcaption", "figure", "footer", "form",
+    "header", "hr", "li", "main", "nav", "section", "tbody", "thead", "tr",
+];
+
+// Block elements set apart by a blank line.
+const PARAGRAPH_ELEMENTS: [&str; 12] = [
+    "blockquote", "h1", "h2", "h3", "h4", "h5", "h6", "ol", "p", "pre", "table", "ul",
+];
+
+// Elements whose content is not displayed.
+const HIDDEN_ELEMENTS: [&str; 6] = ["head", "noscript", "script", "style", "template", "title"];
+
+// Named character references most mail clients write.
+// Refer to https://html.spec.whatwg.org/multipage/named-characters.html
+const ENTITIES: [(&str, char); 24] = [
+    ("amp", '&'),
+    ("lt", '<'),
+    ("gt", '>'),
+    ("quot", '"'),
+    ("apos", '\''),
+    ("nbsp", '\u{a0}'),
+    ("copy", '©'),
+    ("reg", '®'),
+    ("trade", '™'),
+    ("euro", '€'),
+    ("pound", '£'),
+    ("yen", '¥'),
+    ("cent", '¢'),
+    ("sect", '§'),
+    ("deg", '°'),
+    ("middot", '·'),
+    ("bull", '•'),
+    ("hellip", '…'),
+    ("ndash", '–'),
+    ("mdash", '—'),
+    ("lsquo", '‘'),
+    ("rsquo", '’'),
+    ("ldquo", '“'),
+    ("rdquo", '”'),
+];
+
+// TextWriter collapses the white space of the text it is
+// given, as a browser does, and keeps the line breaks
+// the elements ask for.
+#[derive(Debug, Default)]
+struct TextWriter {
+    text: String,
+    space: bool,     // White space seen since the last character
+    newlines: usize, // Line breaks asked for since the last character
+    pre: usize,      // Depth of the "pre" elements the text is in
+}
+
+impl TextWriter {
+    fn flush(&mut self) {
+        if !self.text.is_empty() {
+            if self.newlines > 0 {
+                self.text.truncate(self.text.trim_end_matches(' ').len());
+                let newlines = self.newlines.min(2) - usize::from(self.text.ends_with('\n'));
+                self.text.extend(std::iter::repeat_n('\n', newlines));
+            } else if self.space && !self.text.ends_with(['\n', '\t', ' ']) {
+                self.text.push(' ');
+            }
+        }
+        self.space = false;
+        self.newlines = 0;
+    }
+
+    fn push_text(&mut self, text: &str) {
+        for c in text.chars() {
+            if self.pre > 0 {
+                self.flush();
+                self.text.push(c);
+            } else if c.is_whitespace() && c != '\u{a0}' {
+                self.space = true;
+            } else {
+                self.flush();
+                self.text.push(if c == '\u{a0}' { ' ' } else { c });
+            }
+        }
+    }
+
+    // Writes text as it is, e.g. the marker of a list item.
+    fn push_raw(&mut self, text: &str) {
+        self.flush();
+        self.text.push_str(text);
+    }
+
+    fn break_line(&mut self, newlines: usize) {
+        self.newlines = self.newlines.max(newlines);
+    }
+
+    fn finish(self) -> String {
+        self.text
+            .lines()
+            .map(|line| line.trim_end())
+            .collect::<Vec<&str>>()
+            .join("\n")
+            .trim()
+            .to_string()
+    }
+}
+
+// Replaces the character references of text.
+fn decode_entities(text: &str) -> String {
+    let mut decoded = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find('&') {
+        decoded.push_str(&rest[..start]);
+        rest = &rest[start..];
+        let entity = rest[1..]
+            .find(';')
+            .filter(|end| *end <= 10)
+            .map(|end| &rest[1..end + 1]);
+        let c = entity.and_then(|entity| match entity.strip_prefix('#') {
+            Some(code) => match code.strip_prefix(['x', 'X']) {
+                Some(hex) => u32::from_str_radix(hex, 16).ok(),
+                None => code.parse::<u32>().ok(),
+            }
+            .and_then(char::from_u32),
+            None => ENTITIES
+                .iter()
+                .find(|(name, _)| *name == entity)
+                .map(|(_, c)| *c),
+        });
+        match (c, entity) {
+            (Some(c), Some(entity)) => {
+                decoded.push(c);
+                rest = &rest[entity.len() + 2..];
+            }
+            _ => {
+                decoded.push('&');
+                rest = &rest[1..];
+            }
+        }
+    }
+    decoded.push_str(rest);
+    decoded
+}
+
+// Value of the attribute of a start tag, e.g. "href".
+fn attribute(attributes: &str, name: &str) -> Option<String> {
+    let lower = attributes.to_ascii_lowercase();
+    let mut from = 0;
+    while let Some(found) = lower[from..].find(name) {
+        let start = from + found;
+        from = start + name.len();
+        let preceded = lower[..start].ends_with(|c: char| c.is_ascii_whitespace());
+        let value = lower[from..].trim_start();
+        if !preceded || !value.starts_with('=') {
+            continue;
+        }
+        let offset = attributes.len() - value.len() + 1;
+        let value = attributes[offset..].trim_start();
+        let value = match value.chars().next() {
+            Some(quote @ ('"' | '\'')) => value[1..].split(quote).next().unwrap_or_default(),
+            _ => value
+                .split(|c: char| c.is_ascii_whitespace() || c == '>')
+                .next()
+                .unwrap_or_default(),
+        };
+        return Some(decode_entities(value));
+    }
+    None
+}
+
+// Converts an HTML body to plain text: tags are dropped,
+// block elements start new lines, list items are marked
+// with "- ", table cells are separated by tabs and links
+// are followed by their target in angle brackets.
+pub(crate) fn html_to_text(html: &str) -> String {
+    let mut writer = TextWriter::default();
+    // Target of the link being written, and where its
+    // text starts.
+    let mut link: Option<(String, usize)> = None;
+    // Closing tags are looked for in the lowercased body,
+    // lowercasing keeping offsets.
+    let lower = html.to_ascii_lowercase();
+    let mut rest = html;
+    while !rest.is_empty() {
+        if let Some(comment) = rest.strip_prefix("<!--") {
+            rest = comment.find("-->").map_or("", |end| &comment[end + 3..]);
+            continue;
+        }
+        let end = match rest.strip_prefix('<').and_then(|tag| tag.find('>')) {
+            Some(end) if rest[1..].starts_with(|c: char| c.is_ascii_alphabetic() || c == '/' || c == '!') => end,
+            _ => {
+                let text_end = match rest.strip_prefix('<') {
+                    Some(text) => text.find('<').map_or(rest.len(), |end| end + 1),
+                    None => rest.find('<').unwrap_or(rest.len()),
+                };
+                writer.push_text(&decode_entities(&rest[..text_end]));
+                rest = &rest[text_end..];
+                continue;
+            }
+        };
+        let tag = &rest[1..end + 1];
+        rest = &rest[end + 2..];
+        let closing = tag.starts_with('/');
+        let tag = tag.trim_start_matches('/');
+        let name_len = tag.find(|c: char| !c.is_ascii_alphanumeric()).unwrap_or(tag.len());
+        let name = tag[..name_len].to_ascii_lowercase();
+        let attributes = &tag[name_len..];
+
+        if HIDDEN_ELEMENTS.contains(&name.as_str()) {
+            if !closing && !attributes.trim_end().ends_with('/') {
+                let lower = &lower[html.len() - rest.len()..];
+                rest = match lower.find(&format!("</{}", name)) {
+                    Some(start) => rest[start..].find('>').map_or("", |end| &rest[start + end + 1..]),
+                    None => "",
+                };
+            }
+            continue;
+        }
+        match name.as_str() {
+            // Line breaks are kept even when they leave the
+            // line empty.
+            "br" => writer.push_raw("\n"),
+            "li" if !closing => {
+                writer.break_line(1);
+                writer.push_raw("- ");
+            }
+            "td" | "th" if !closing => {
+                if writer.newlines == 0 && !writer.text.is_empty() && !writer.text.ends_with('\n') {
+                    writer.space = false;
+                    writer.push_raw("\t");
+                }
+            }
+            "a" if !closing => {
+                writer.flush();
+                link = attribute(attributes, "href").map(|href| (href, writer.text.len()));
+            }
+            "a" => {
+                if let Some((href, start)) = link.take() {
+                    let text = writer.text.get(start..).unwrap_or_default().trim().to_string();
+                    let target = href.strip_prefix("mailto:").unwrap_or(&href).trim();
+                    if !target.is_empty() && !target.starts_with('#') && text != target {
+                        writer.space = !text.is_empty();
+                        writer.push_raw(&format!("<{}>", target));
+                    }
+                }
+            }
+            "img" => {
+                if let Some(alt) = attribute(attributes, "alt").filter(|alt| !alt.trim().is_empty()) {
+                    writer.space = true;
+                    writer.push_text(&alt);
+                    writer.space = true;
+                }
+            }
+            name => {
+                if name == "pre" {
+                    writer.pre = if closing { writer.pre.saturating_sub(1) } else { writer.pre + 1 };
+                }
+                if PARAGRAPH_ELEMENTS.contains(&name) {
+                    writer.break_line(2);
+                } else if BLOCK_ELEMENTS.contains(&name) {
+                    writer.break_line(1);
+                }
+            }
+        }
+    }
+    writer.finish()
+}
+
+// Anchors of an HTML body, the target of each with the
+// text it shows, in order.
+pub(crate) fn anchors(html: &str) -> Vec<(String, String)> {
+    let lower = html.to_ascii_lowercase();
+    // Where the next anchor starts, from an offset.
+    let next_anchor = |mut from: usize| {
+        while let Some(found) = lower[from..].find("<a") {
+            let start = from + found;
+            from = start + 2;
+            if lower[from..].starts_with(|c: char| c.is_ascii_whitespace()) {
+                return Some(start);
+            }
+        }
+        None
+    };
+    let mut anchors = vec![];
+    let mut from = 0;
+    while let Some(found) = next_anchor(from) {
+        let start = found + 2;
+        let end = match lower[start..].find('>') {
+            Some(end) => start + end,
+            None => break,
+        };
+        from = end + 1;
+        let href = match attribute(&html[start..end], "href") {
+            Some(href) => href.trim().to_string(),
+            None => continue,
+        };
+        // An anchor left open ends where the next one starts.
+        let next = next_anchor(from).unwrap_or(html.len());
+        let close = lower[from..next].find("</a").map_or(next, |close| from + close);
+        anchors.push((href, html_to_text(&html[from..close])));
+        from = close;
+    }
+    anchors
+}
+
+impl Outlook {
+    // Plain text body, converted from the HTML one when the
+    // message has no other.
+    pub fn body_text(&self) -> String {
+        if !self.body.trim().is_empty() {
+            return self.body.to_string();
+        }
+        html_to_text(&self.html_body())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{super::builder::OutlookBuilder, anchors, attribute, decode_entities, html_to_text};
+
+    #[test]
+    fn test_decode_entities() {
+        assert_eq!(decode_entities("Tom &amp; Jerry&nbsp;&#233;&#x2014;&rsquo;"), "Tom & Jerry\u{a0}é—’".to_string());
+        assert_eq!(decode_entities("AT&T &unknown; & &;"), "AT&T &unknown; & &;".to_string());
+    }
+
+    #[test]
+    fn test_attribute() {
+        let attributes = r#" class="x" HREF='https://example.com/?a=1&amp;b=2' title=plain"#;
+        assert_eq!(attribute(attributes, "href"), Some("https://example.com/?a=1&b=2".to_string()));
+        assert_eq!(attribute(attributes, "title"), Some("plain".to_string()));
+        assert_eq!(attribute(attributes, "ref"), None);
+        assert_eq!(attribute(attributes, "alt"), None);
+    }
+
+    #[test]
+    fn test_anchors() {
+        let html = "<p><A HREF=\"https://example.com/a\">See <b>this</b></a> or <abbr>x</abbr>\
+                    <a name=top>top</a><a href='http://evil.test'>https://bank.com</A>";
+        assert_eq!(
+            anchors(html),
+            vec![
+                ("https://example.com/a".to_string(), "See this".to_string()),
+                ("http://evil.test".to_string(), "https://bank.com".to_string()),
+            ]
+        );
+        assert!(anchors("<a href=\"x\"").is_empty());
+
+        let html = "<a href=\"https://bank.com\">Bank <p>Log in at \
+                    <a href=\"http://evil.test\">https://bank.com</a>";
+        assert_eq!(
+            anchors(html),
+            vec![
+                ("https://bank.com".to_string(), "Bank\n\nLog in at".to_string()),
+                ("http://evil.test".to_string(), "https://bank.com".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_html_to_text() {
+        let html = "<html><head><title>Ignored</title><style>p { color: red; }</style></head>\r\n\
+                    <body><!-- hidden --><h1>Report</h1>\r\n\
+                    <p>Hello   <b>team</b>,<br>see the\r\n<a href=\"https://example.com/q3\">Q3 numbers</a>.</p>\
+                    <ul><li>One</li><li>Two &amp; three</li></ul>\
+                    <table><tr><th>Name</th><th>Total</th></tr><tr><td>Bob</td><td>42</td></tr></table>\
+                    <pre>  keep\n   this</pre>\
+                    <div>Mail <a href=\"mailto:bob@example.com\">bob@example.com</a> or 1 &lt; 2\
+                    <img src=\"cid:logo\" alt=\"Logo\"></div><script>alert(1)</script></body></html>";
+        assert_eq!(
+            html_to_text(html),
+            "Report\n\
+             \n\
+             Hello team,\n\
+             see the Q3 numbers <https://example.com/q3>.\n\
+             \n\
+             - One\n\
+             - Two & three\n\
+             \n\
+             Name\tTotal\n\
+             Bob\t42\n\
+             \n\
+             \x20 keep\n\
+             \x20  this\n\
+             \n\
+             Mail bob@example.com or 1 < 2 Logo"
+                .to_string()
+        );
+        assert_eq!(html_to_text("a<br><br>b"), "a\n\nb".to_string());
+        // Non-breaking spaces are not collapsed.
+        assert_eq!(html_to_text("a&nbsp;&nbsp;b"), "a  b".to_string());
+        assert_eq!(html_to_text("x < y and <3"), "x < y and <3".to_string());
+        assert_eq!(html_to_text("<p>Grüße,</p>Ümit"), "Grüße,\n\nÜmit".to_string());
+    }
+
+    #[test]
+    fn test_body_text() {
+        let outlook = OutlookBuilder::new()
+            .subject("Html only")
+            .html("<p>Hello <i>world</i></p>")
+            .build()
+            .unwrap();
+        assert!(outlook.body.is_empty());
+        assert_eq!(outlook.body_text(), "Hello world".to_string());
+
+        let outlook = OutlookBuilder::new()
+            .subject("Both")
+            .body("Plain")
+            .html("<p>Rich</p>")
+            .build()
+            .unwrap();
+        assert_eq!(outlook.body_text(), "Plain".to_string());
+    }
+}
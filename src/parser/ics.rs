@@ -0,0 +1,460 @@
+use chrono::{DateTime, Datelike, Duration, NaiveDate, NaiveDateTime, Utc, Weekday};
+use sha2::{Digest, Sha256};
+
+use super::{
+    appointment::Appointment,
+    clock::{Clock, SystemClock},
+    outlook::Person,
+    recurrence::{RecurrenceEnd, RecurrenceFrequency, RecurrencePattern},
+};
+
+// Content lines are folded at 75 octets.
+// Refer to RFC 5545 3.1
+const LINE_LIMIT: usize = 75;
+
+// Escapes TEXT values. Refer to RFC 5545 3.3.11
+pub(super) fn escape_text(text: &str) -> String {
+    text.replace('\\', "\\\\")
+        .replace(';', "\\;")
+        .replace(',', "\\,")
+        .replace("\r\n", "\\n")
+        .replace('\n', "\\n")
+}
+
+// Parameter values holding separators are quoted.
+fn quote_param(value: &str) -> String {
+    let value = value.replace('"', "");
+    if value.contains([':', ';', ',']) {
+        format!("\"{}\"", value)
+    } else {
+        value
+    }
+}
+
+pub(super) fn fold_line(line: &str) -> String {
+    let mut folded = String::with_capacity(line.len() + 8);
+    let mut len = 0;
+    for c in line.chars() {
+        // Continuation lines start with a space.
+        if len + c.len_utf8() > LINE_LIMIT {
+            folded.push_str("\r\n ");
+            len = 1;
+        }
+        folded.push(c);
+        len += c.len_utf8();
+    }
+    folded.push_str("\r\n");
+    folded
+}
+
+fn format_utc(time: &DateTime<Utc>) -> String {
+    time.format("%Y%m%dT%H%M%SZ").to_string()
+}
+
+fn format_date(date: &NaiveDate) -> String {
+    date.format("%Y%m%d").to_string()
+}
+
+fn format_weekday(day: &Weekday) -> &'static str {
+    match day {
+        Weekday::Mon => "MO",
+        Weekday::Tue => "TU",
+        Weekday::Wed => "WE",
+        Weekday::Thu => "TH",
+        Weekday::Fri => "FR",
+        Weekday::Sat => "SA",
+        Weekday::Sun => "SU",
+    }
+}
+
+fn cal_address(person: &Person) -> String {
+    if person.email.is_empty() {
+        // What Outlook writes for attendees without address.
+        "invalid:nomail".to_string()
+    } else {
+        format!("mailto:{}", person.email)
+    }
+}
+
+// Builds the RRULE value of a recurrence pattern.
+// Refer to RFC 5545 3.3.10
+fn recurrence_rule(pattern: &RecurrencePattern, all_day: bool) -> String {
+    let frequency = match pattern.frequency {
+        RecurrenceFrequency::Daily => "DAILY",
+        RecurrenceFrequency::Weekly => "WEEKLY",
+        RecurrenceFrequency::Monthly => "MONTHLY",
+        RecurrenceFrequency::Yearly => "YEARLY",
+    };
+    let mut parts = vec![format!("FREQ={}", frequency)];
+    if pattern.interval > 1 {
+        parts.push(format!("INTERVAL={}", pattern.interval));
+    }
+    if pattern.frequency == RecurrenceFrequency::Yearly {
+        parts.push(format!("BYMONTH={}", pattern.start_date.month()));
+    }
+    if !pattern.days.is_empty() {
+        let days = pattern.days.iter().map(format_weekday).collect::<Vec<&str>>();
+        parts.push(format!("BYDAY={}", days.join(",")));
+    }
+    if let Some(nth) = pattern.nth {
+        // 5 stands for the last occurrence.
+        let position = if nth >= 5 { -1 } else { nth as i32 };
+        parts.push(format!("BYSETPOS={}", position));
+    }
+    if let Some(day) = pattern.day_of_month {
+        let day = if day >= 31 { -1 } else { day as i32 };
+        parts.push(format!("BYMONTHDAY={}", day));
+    }
+    match pattern.end {
+        RecurrenceEnd::Count(count) => parts.push(format!("COUNT={}", count)),
+        // UNTIL must have the same value type as DTSTART.
+        RecurrenceEnd::Date(date) if all_day => parts.push(format!("UNTIL={}", format_date(&date))),
+        RecurrenceEnd::Date(date) => parts.push(format!("UNTIL={}T235959Z", format_date(&date))),
+        RecurrenceEnd::Never => {}
+    }
+    parts.push(format!("WKST={}", format_weekday(&pattern.first_day_of_week)));
+    parts.join(";")
+}
+
+impl Appointment {
+    // Date of an all day event, AppointmentStartWhole being
+    // the midnight of the appointment's time zone in UTC.
+    fn local_date(&self, time: &DateTime<Utc>) -> NaiveDate {
+        match self.time_zone {
+            Some(ref tz) => tz.to_local(time).date_naive(),
+            None => time.date_naive(),
+        }
+    }
+
+    // Recurrence dates are local to the appointment's time zone,
+    // they are left as floating times when it is unknown.
+    fn format_local(&self, time: &NaiveDateTime) -> String {
+        match self.time_zone {
+            Some(ref tz) => format_utc(&tz.to_utc(time)),
+            None => time.format("%Y%m%dT%H%M%S").to_string(),
+        }
+    }
+
+    fn format_start_end(&self, name: &str, time: &DateTime<Utc>) -> String {
+        if self.all_day {
+            format!("{};VALUE=DATE:{}", name, format_date(&self.local_date(time)))
+        } else {
+            format!("{}:{}", name, format_utc(time))
+        }
+    }
+
+    fn uid_or_digest(&self) -> String {
+        if !self.uid.is_empty() {
+            return self.uid.clone();
+        }
+        let mut hasher = Sha256::new();
+        hasher.update(self.subject.as_bytes());
+        hasher.update(self.start.map(|start| start.timestamp()).unwrap_or(0).to_le_bytes());
+        hasher.update(self.organizer.email.as_bytes());
+        hex::encode_upper(hasher.finalize())
+    }
+
+    fn event_lines(&self, dtstamp: &str) -> Vec<String> {
+        let mut lines = vec![
+            "BEGIN:VEVENT".to_string(),
+            format!("UID:{}", self.uid_or_digest()),
+            format!("DTSTAMP:{}", dtstamp),
+        ];
+        if let Some(ref start) = self.start {
+            lines.push(self.format_start_end("DTSTART", start));
+        }
+        if let Some(ref end) = self.end {
+            lines.push(self.format_start_end("DTEND", end));
+        }
+        lines.push(format!("SUMMARY:{}", escape_text(&self.subject)));
+        if !self.location.is_empty() {
+            lines.push(format!("LOCATION:{}", escape_text(&self.location)));
+        }
+        if let Some(ref meeting) = self.meeting {
+            if let Some(sequence) = meeting.sequence {
+                lines.push(format!("SEQUENCE:{}", sequence));
+            }
+        }
+        if !self.organizer.name.is_empty() || !self.organizer.email.is_empty() {
+            lines.push(format!(
+                "ORGANIZER;CN={}:{}",
+                quote_param(&self.organizer.name),
+                cal_address(&self.organizer)
+            ));
+        }
+        let attendees = self
+            .required_attendees
+            .iter()
+            .map(|person| (person, "REQ-PARTICIPANT"))
+            .chain(self.optional_attendees.iter().map(|person| (person, "OPT-PARTICIPANT")));
+        for (person, role) in attendees {
+            lines.push(format!(
+                "ATTENDEE;CN={};ROLE={}:{}",
+                quote_param(&person.name),
+                role,
+                cal_address(person)
+            ));
+        }
+        if let Some(ref pattern) = self.recurrence {
+            lines.push(format!("RRULE:{}", recurrence_rule(pattern, self.all_day)));
+            // Modified occurrences are listed as deleted too,
+            // they come back as events of their own.
+            let exdates = pattern
+                .deleted_dates
+                .iter()
+                .filter(|date| {
+                    !pattern
+                        .exceptions
+                        .iter()
+                        .any(|exception| exception.original_start.date() == date.date())
+                })
+                .map(|date| self.exdate(pattern, date))
+                .collect::<Vec<String>>();
+            if !exdates.is_empty() {
+                lines.push(exdates.join("\r\n"));
+            }
+        }
+        lines.push("END:VEVENT".to_string());
+        lines
+    }
+
+    fn exdate(&self, pattern: &RecurrencePattern, date: &NaiveDateTime) -> String {
+        if self.all_day {
+            return format!("EXDATE;VALUE=DATE:{}", format_date(&date.date()));
+        }
+        let start = *date + Duration::minutes(pattern.start_time_offset as i64);
+        format!("EXDATE:{}", self.format_local(&start))
+    }
+
+    // Modified occurrences, as events overriding the
+    // series through RECURRENCE-ID.
+    fn exception_lines(&self, dtstamp: &str) -> Vec<String> {
+        let pattern = match self.recurrence {
+            Some(ref pattern) => pattern,
+            None => return vec![],
+        };
+        let mut lines = vec![];
+        for exception in pattern.exceptions.iter() {
+            lines.push("BEGIN:VEVENT".to_string());
+            lines.push(format!("UID:{}", self.uid_or_digest()));
+            lines.push(format!("DTSTAMP:{}", dtstamp));
+            if self.all_day {
+                lines.push(format!(
+                    "RECURRENCE-ID;VALUE=DATE:{}",
+                    format_date(&exception.original_start.date())
+                ));
+                lines.push(format!("DTSTART;VALUE=DATE:{}", format_date(&exception.start.date())));
+                lines.push(format!("DTEND;VALUE=DATE:{}", format_date(&exception.end.date())));
+            } else {
+                lines.push(format!(
+                    "RECURRENCE-ID:{}",
+                    self.format_local(&exception.original_start)
+                ));
+                lines.push(format!("DTSTART:{}", self.format_local(&exception.start)));
+                lines.push(format!("DTEND:{}", self.format_local(&exception.end)));
+            }
+            let subject = exception.subject.as_ref().unwrap_or(&self.subject);
+            lines.push(format!("SUMMARY:{}", escape_text(subject)));
+            let location = exception.location.as_ref().unwrap_or(&self.location);
+            if !location.is_empty() {
+                lines.push(format!("LOCATION:{}", escape_text(location)));
+            }
+            lines.push("END:VEVENT".to_string());
+        }
+        lines
+    }
+
+    // Renders the appointment as an RFC 5545 calendar
+    // holding a single VEVENT, plus one per modified
+    // occurrence of a recurring series.
+    pub fn to_ics(&self) -> String {
+        self.to_ics_with_clock(&SystemClock)
+    }
+
+    // Same as to_ics, DTSTAMP being taken from clock.
+    pub fn to_ics_with_clock(&self, clock: &dyn Clock) -> String {
+        let dtstamp = format_utc(&clock.now());
+        let mut lines = vec![
+            "BEGIN:VCALENDAR".to_string(),
+            "VERSION:2.0".to_string(),
+            "PRODID:-//msg_parser//EN".to_string(),
+        ];
+        lines.extend(self.event_lines(&dtstamp));
+        lines.extend(self.exception_lines(&dtstamp));
+        lines.push("END:VCALENDAR".to_string());
+        lines
+            .iter()
+            .flat_map(|line| line.split("\r\n"))
+            .map(fold_line)
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{NaiveDate, TimeZone, Utc, Weekday};
+
+    use super::{
+        super::{
+            clock::FixedClock,
+            recurrence::{RecurrenceEnd, RecurrenceException, RecurrenceFrequency, RecurrencePattern},
+        },
+        escape_text, fold_line, recurrence_rule, Appointment, Person,
+    };
+
+    fn appointment() -> Appointment {
+        Appointment {
+            uid: "040000008200E00074C5B7101A82E008".to_string(),
+            subject: "Budget review; Q3".to_string(),
+            start: Some(Utc.with_ymd_and_hms(2021, 6, 7, 13, 0, 0).unwrap()),
+            end: Some(Utc.with_ymd_and_hms(2021, 6, 7, 14, 0, 0).unwrap()),
+            location: "Room 101".to_string(),
+            all_day: false,
+            organizer: Person::new("Alice".to_string(), "alice@example.com".to_string()),
+            required_attendees: vec![Person::new(
+                "Bob".to_string(),
+                "bob@example.com".to_string(),
+            )],
+            optional_attendees: vec![Person::new("Carol, External".to_string(), String::new())],
+            recurrence: None,
+            meeting: None,
+            time_zone: None,
+        }
+    }
+
+    fn weekly_pattern() -> RecurrencePattern {
+        let date = |day| {
+            NaiveDate::from_ymd_opt(2021, 6, day)
+                .unwrap()
+                .and_hms_opt(0, 0, 0)
+                .unwrap()
+        };
+        let time = |day, hour| {
+            NaiveDate::from_ymd_opt(2021, 6, day)
+                .unwrap()
+                .and_hms_opt(hour, 0, 0)
+                .unwrap()
+        };
+        RecurrencePattern {
+            frequency: RecurrenceFrequency::Weekly,
+            interval: 1,
+            days: vec![Weekday::Mon],
+            day_of_month: None,
+            nth: None,
+            first_day_of_week: Weekday::Sun,
+            start_date: NaiveDate::from_ymd_opt(2021, 6, 7).unwrap(),
+            end: RecurrenceEnd::Count(4),
+            start_time_offset: 9 * 60,
+            end_time_offset: 10 * 60,
+            deleted_dates: vec![date(14), date(21)],
+            exceptions: vec![RecurrenceException {
+                original_start: time(21, 9),
+                start: time(21, 11),
+                end: time(21, 12),
+                subject: None,
+                location: Some("Room 202".to_string()),
+            }],
+        }
+    }
+
+    #[test]
+    fn test_escape_and_fold() {
+        assert_eq!(escape_text("a;b,c\\d\r\ne"), "a\\;b\\,c\\\\d\\ne");
+        assert_eq!(fold_line("short"), "short\r\n");
+        let folded = fold_line(&"x".repeat(100));
+        assert_eq!(folded, format!("{}\r\n {}\r\n", "x".repeat(75), "x".repeat(25)));
+        // Multi-byte characters are never split.
+        let folded = fold_line(&"é".repeat(40));
+        assert!(folded.split("\r\n").all(|line| line.len() <= 75));
+    }
+
+    #[test]
+    fn test_recurrence_rule() {
+        let mut pattern = weekly_pattern();
+        assert_eq!(recurrence_rule(&pattern, false), "FREQ=WEEKLY;BYDAY=MO;COUNT=4;WKST=SU");
+
+        pattern.frequency = RecurrenceFrequency::Monthly;
+        pattern.interval = 2;
+        pattern.days = vec![Weekday::Fri];
+        pattern.nth = Some(5);
+        pattern.end = RecurrenceEnd::Date(NaiveDate::from_ymd_opt(2021, 12, 31).unwrap());
+        assert_eq!(
+            recurrence_rule(&pattern, false),
+            "FREQ=MONTHLY;INTERVAL=2;BYDAY=FR;BYSETPOS=-1;UNTIL=20211231T235959Z;WKST=SU"
+        );
+
+        pattern.frequency = RecurrenceFrequency::Yearly;
+        pattern.interval = 1;
+        pattern.days = vec![];
+        pattern.nth = None;
+        pattern.day_of_month = Some(7);
+        pattern.end = RecurrenceEnd::Never;
+        assert_eq!(
+            recurrence_rule(&pattern, true),
+            "FREQ=YEARLY;BYMONTH=6;BYMONTHDAY=7;WKST=SU"
+        );
+    }
+
+    #[test]
+    fn test_to_ics() {
+        let clock = FixedClock(Utc.with_ymd_and_hms(2021, 6, 1, 0, 0, 0).unwrap());
+        let ics = appointment().to_ics_with_clock(&clock);
+        assert_eq!(
+            ics,
+            [
+                "BEGIN:VCALENDAR",
+                "VERSION:2.0",
+                "PRODID:-//msg_parser//EN",
+                "BEGIN:VEVENT",
+                "UID:040000008200E00074C5B7101A82E008",
+                "DTSTAMP:20210601T000000Z",
+                "DTSTART:20210607T130000Z",
+                "DTEND:20210607T140000Z",
+                "SUMMARY:Budget review\\; Q3",
+                "LOCATION:Room 101",
+                "ORGANIZER;CN=Alice:mailto:alice@example.com",
+                "ATTENDEE;CN=Bob;ROLE=REQ-PARTICIPANT:mailto:bob@example.com",
+                "ATTENDEE;CN=\"Carol, External\";ROLE=OPT-PARTICIPANT:invalid:nomail",
+                "END:VEVENT",
+                "END:VCALENDAR",
+                "",
+            ]
+            .join("\r\n")
+        );
+    }
+
+    #[test]
+    fn test_to_ics_recurring() {
+        let clock = FixedClock(Utc.with_ymd_and_hms(2021, 6, 1, 0, 0, 0).unwrap());
+        let mut appointment = appointment();
+        appointment.recurrence = Some(weekly_pattern());
+        let ics = appointment.to_ics_with_clock(&clock);
+        let lines = ics.split("\r\n").collect::<Vec<&str>>();
+        assert!(lines.contains(&"RRULE:FREQ=WEEKLY;BYDAY=MO;COUNT=4;WKST=SU"));
+        // Only the deleted occurrence, the modified one has its own event.
+        assert!(lines.contains(&"EXDATE:20210614T090000"));
+        assert!(!lines.contains(&"EXDATE:20210621T090000"));
+        assert!(lines.contains(&"RECURRENCE-ID:20210621T090000"));
+        assert!(lines.contains(&"DTSTART:20210621T110000"));
+        assert!(lines.contains(&"LOCATION:Room 202"));
+        assert_eq!(lines.iter().filter(|line| **line == "BEGIN:VEVENT").count(), 2);
+    }
+
+    #[test]
+    fn test_to_ics_all_day() {
+        let clock = FixedClock(Utc.with_ymd_and_hms(2021, 6, 1, 0, 0, 0).unwrap());
+        let mut appointment = appointment();
+        appointment.uid = String::new();
+        appointment.all_day = true;
+        appointment.start = Some(Utc.with_ymd_and_hms(2021, 6, 7, 0, 0, 0).unwrap());
+        appointment.end = Some(Utc.with_ymd_and_hms(2021, 6, 8, 0, 0, 0).unwrap());
+        let ics = appointment.to_ics_with_clock(&clock);
+        let lines = ics.split("\r\n").collect::<Vec<&str>>();
+        assert!(lines.contains(&"DTSTART;VALUE=DATE:20210607"));
+        assert!(lines.contains(&"DTEND;VALUE=DATE:20210608"));
+        // Without GlobalObjectId the UID is derived from the content.
+        let uid = lines.iter().find(|line| line.starts_with("UID:")).unwrap();
+        assert_eq!(uid.len(), 4 + 64);
+        assert_eq!(ics, appointment.to_ics_with_clock(&clock));
+    }
+}
@@ -0,0 +1,146 @@
+use serde::{Deserialize, Serialize};
+
+use super::{decode::DataType, storage::Properties};
+
+// Importance is the importance the sender gave the message.
+// Refer to MS-OXCMSG 2.2.1.11
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub enum Importance {
+    Low,
+    Normal,
+    High,
+}
+
+impl Importance {
+    pub fn from_value(value: i64) -> Self {
+        match value {
+            0 => Importance::Low,
+            2 => Importance::High,
+            _ => Importance::Normal,
+        }
+    }
+
+    pub fn to_value(&self) -> i64 {
+        match self {
+            Importance::Low => 0,
+            Importance::Normal => 1,
+            Importance::High => 2,
+        }
+    }
+
+    pub fn create_from_props(props: &Properties) -> Self {
+        props
+            .get("Importance")
+            .and_then(DataType::as_i64)
+            .map_or(Importance::Normal, Self::from_value)
+    }
+}
+
+// Priority is the urgency of the message for transport.
+// Refer to MS-OXCMSG 2.2.1.12
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub enum Priority {
+    NonUrgent,
+    Normal,
+    Urgent,
+}
+
+impl Priority {
+    pub fn from_value(value: i64) -> Self {
+        match value {
+            -1 => Priority::NonUrgent,
+            1 => Priority::Urgent,
+            _ => Priority::Normal,
+        }
+    }
+
+    pub fn create_from_props(props: &Properties) -> Self {
+        props
+            .get("Priority")
+            .and_then(DataType::as_i64)
+            .map_or(Priority::Normal, Self::from_value)
+    }
+}
+
+// Sensitivity tells who the message is meant for.
+// Refer to MS-OXCMSG 2.2.1.13
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub enum Sensitivity {
+    Normal,
+    Personal,
+    Private,
+    Confidential,
+}
+
+impl Sensitivity {
+    pub fn from_value(value: i64) -> Self {
+        match value {
+            1 => Sensitivity::Personal,
+            2 => Sensitivity::Private,
+            3 => Sensitivity::Confidential,
+            _ => Sensitivity::Normal,
+        }
+    }
+
+    pub fn to_value(&self) -> i64 {
+        match self {
+            Sensitivity::Normal => 0,
+            Sensitivity::Personal => 1,
+            Sensitivity::Private => 2,
+            Sensitivity::Confidential => 3,
+        }
+    }
+
+    pub fn create_from_props(props: &Properties) -> Self {
+        props
+            .get("Sensitivity")
+            .and_then(DataType::as_i64)
+            .map_or(Sensitivity::Normal, Self::from_value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        super::{decode::DataType, outlook::Outlook, storage::Properties},
+        Importance, Priority, Sensitivity,
+    };
+
+    #[test]
+    fn test_from_props() {
+        let mut props = Properties::new();
+        assert_eq!(Importance::create_from_props(&props), Importance::Normal);
+        assert_eq!(Priority::create_from_props(&props), Priority::Normal);
+        assert_eq!(Sensitivity::create_from_props(&props), Sensitivity::Normal);
+
+        props.insert("Importance".to_string(), DataType::PtypInteger32(2));
+        props.insert("Priority".to_string(), DataType::PtypInteger32(-1));
+        props.insert("Sensitivity".to_string(), DataType::PtypInteger32(3));
+        assert_eq!(Importance::create_from_props(&props), Importance::High);
+        assert_eq!(Priority::create_from_props(&props), Priority::NonUrgent);
+        assert_eq!(Sensitivity::create_from_props(&props), Sensitivity::Confidential);
+
+        assert_eq!(Importance::from_value(0), Importance::Low);
+        assert_eq!(Priority::from_value(1), Priority::Urgent);
+        assert_eq!(Sensitivity::from_value(1), Sensitivity::Personal);
+        assert_eq!(Sensitivity::from_value(2), Sensitivity::Private);
+        assert_eq!(Importance::from_value(Importance::High.to_value()), Importance::High);
+        assert_eq!(
+            Sensitivity::from_value(Sensitivity::Confidential.to_value()),
+            Sensitivity::Confidential
+        );
+    }
+
+    #[test]
+    fn test_outlook_importance() {
+        let outlook = Outlook::from_path("data/test_email.msg").unwrap();
+        assert_eq!(outlook.importance, Importance::Normal);
+        assert_eq!(outlook.priority, Priority::Normal);
+        assert_eq!(outlook.sensitivity, Sensitivity::Normal);
+        let json = outlook.to_json().unwrap();
+        assert!(json.contains("\"sensitivity\":\"Normal\""));
+    }
+}
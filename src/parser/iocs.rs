@@ -0,0 +1,312 @@
+use std::{
+    collections::HashMap,
+    net::{IpAddr, Ipv4Addr, Ipv6Addr},
+};
+
+use serde::{Deserialize, Serialize};
+
+use super::{
+    decode::DataType,
+    eml::html_of,
+    headers::Headers,
+    html::{anchors, html_to_text},
+    link::{find_urls, host_of, MARKUP_HOSTS, TRAILING},
+    outlook::Outlook,
+    rtf::decompress,
+    storage::Properties,
+};
+
+// Characters ending a word that may be an indicator,
+// besides white space.
+const DELIMITERS: &str = "<>\"'`{}|\\^()[],;";
+
+// Headers naming messages rather than mailboxes, whose ids
+// look like addresses.
+const ID_HEADERS: [&str; 3] = ["Message-ID", "In-Reply-To", "References"];
+
+// Extensions of files, which names written in text end with
+// like domains do. The few that are top-level domains too,
+// e.g. "zip", are given up on outside URLs and addresses.
+const FILE_EXTENSIONS: [&str; 24] = [
+    "txt", "pdf", "doc", "docx", "docm", "xls", "xlsx", "xlsm", "ppt", "pptx", "csv", "xml", "htm", "html", "png",
+    "jpg", "jpeg", "gif", "zip", "rar", "msg", "eml", "exe", "dll",
+];
+
+// IocSource tells where an indicator was found.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum IocSource {
+    Body,           // "Body"
+    Html,           // "Html"
+    Rtf,            // "RtfCompressed"
+    Header(String), // Name of the transport header, e.g. "Received"
+}
+
+// Ioc is an indicator of compromise, found once whatever
+// the number of times and places it appears.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Ioc {
+    pub value: String,           // Lowercased, IPv6 addresses in their canonical form
+    pub sources: Vec<IocSource>, // See IocSource enum
+}
+
+// Iocs holds the IP addresses, domains and email addresses
+// of the bodies and transport headers of a message, each
+// in the order it is first found. Domains include those of
+// the URLs and email addresses found.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Iocs {
+    pub ips: Vec<Ioc>,
+    pub domains: Vec<Ioc>,
+    pub emails: Vec<Ioc>,
+}
+
+// Whether a word is a domain name: at least two labels of
+// letters, digits and hyphens, the last of letters only.
+// Refer to RFC 1035 2.3.1
+fn is_domain(word: &str) -> bool {
+    let labels: Vec<&str> = word.split('.').collect();
+    let tld = labels.last().copied().unwrap_or_default();
+    labels.len() >= 2
+        && tld.len() >= 2
+        && tld.chars().all(|c| c.is_ascii_alphabetic())
+        && labels.iter().all(|label| {
+            !label.is_empty()
+                && label.len() <= 63
+                && !label.starts_with('-')
+                && !label.ends_with('-')
+                && label.chars().all(|c| c.is_ascii_alphanumeric() || c == '-')
+        })
+}
+
+// Splits an email address into its local part and domain,
+// None when it is not one.
+fn split_email(word: &str) -> Option<(&str, &str)> {
+    let (local, domain) = word.rsplit_once('@')?;
+    let valid = !local.is_empty()
+        && !local.starts_with('.')
+        && !local.ends_with('.')
+        && local.chars().all(|c| c.is_ascii_alphanumeric() || "._%+-".contains(c));
+    (valid && is_domain(domain)).then_some((local, domain))
+}
+
+fn parse_ip(word: &str) -> Option<IpAddr> {
+    match word.contains(':') {
+        true => word.parse::<Ipv6Addr>().ok().map(IpAddr::V6),
+        false => word.parse::<Ipv4Addr>().ok().map(IpAddr::V4),
+    }
+}
+
+// IocList gathers indicators of one kind, in the order they
+// are first found.
+#[derive(Default)]
+struct IocList {
+    iocs: Vec<Ioc>,
+    indexes: HashMap<String, usize>, // Value to index of its indicator
+}
+
+impl IocList {
+    fn add(&mut self, value: String, source: &IocSource) {
+        let index = match self.indexes.get(&value) {
+            Some(index) => *index,
+            None => {
+                let index = self.iocs.len();
+                self.indexes.insert(value.clone(), index);
+                self.iocs.push(Ioc { value, sources: vec![] });
+                index
+            }
+        };
+        let ioc = &mut self.iocs[index];
+        if !ioc.sources.contains(source) {
+            ioc.sources.push(source.clone());
+        }
+    }
+
+    fn retain(&mut self, keep: impl Fn(&Ioc) -> bool) {
+        self.iocs.retain(keep);
+        self.indexes = self.iocs.iter().enumerate().map(|(index, ioc)| (ioc.value.clone(), index)).collect();
+    }
+}
+
+// IocLists gathers the indicators of a message, see Iocs.
+#[derive(Default)]
+struct IocLists {
+    ips: IocList,
+    domains: IocList,
+    emails: IocList,
+}
+
+impl Iocs {
+    pub fn is_empty(&self) -> bool {
+        self.ips.is_empty() && self.domains.is_empty() && self.emails.is_empty()
+    }
+
+    // Indicators of the bodies and transport headers of the
+    // message.
+    pub fn create_from_props(props: &Properties) -> Self {
+        let mut iocs = IocLists::default();
+        let string = |key: &str| props.get(key).map_or(String::new(), String::from);
+
+        iocs.add_text(&string("Body"), &IocSource::Body);
+
+        let html = html_of(props);
+        for (href, _) in anchors(&html) {
+            iocs.add_text(&href, &IocSource::Html);
+        }
+        iocs.add_text(&html_to_text(&html), &IocSource::Html);
+
+        if let Some(DataType::PtypBinary(bytes)) = props.get("RtfCompressed") {
+            if let Some(rtf) = decompress(bytes) {
+                let rtf: String = rtf.iter().map(|byte| char::from(*byte)).collect();
+                iocs.add_words(&rtf, &IocSource::Rtf, false);
+                iocs.domains.retain(|ioc| {
+                    ioc.sources != [IocSource::Rtf] || !MARKUP_HOSTS.contains(&ioc.value.as_str())
+                });
+            }
+        }
+
+        let headers = Headers::parse(&string("TransportMessageHeaders"));
+        for (name, value) in headers.iter() {
+            let source = IocSource::Header(name.to_string());
+            match ID_HEADERS.iter().any(|id| id.eq_ignore_ascii_case(name)) {
+                // Only the domains of message ids are kept.
+                true => {
+                    for id in value.split(|c: char| c.is_whitespace() || DELIMITERS.contains(c)) {
+                        if let Some((_, domain)) = split_email(id) {
+                            iocs.domains.add(domain.to_ascii_lowercase(), &source);
+                        }
+                    }
+                }
+                false => iocs.add_text(value, &source),
+            }
+        }
+        Self {
+            ips: iocs.ips.iocs,
+            domains: iocs.domains.iocs,
+            emails: iocs.emails.iocs,
+        }
+    }
+}
+
+impl IocLists {
+    fn add_host(&mut self, host: &str, source: &IocSource) {
+        let host = host.trim_start_matches('[').trim_end_matches(']');
+        match parse_ip(host) {
+            Some(ip) => self.ips.add(ip.to_string(), source),
+            None if is_domain(host) => self.domains.add(host.to_ascii_lowercase(), source),
+            None => {}
+        }
+    }
+
+    fn add_text(&mut self, text: &str, source: &IocSource) {
+        self.add_words(text, source, true);
+    }
+
+    // Bare domains are not looked for in markup, the class
+    // names of the HTML encapsulated in RTF, e.g. "p.msonormal",
+    // reading as domains.
+    fn add_words(&mut self, text: &str, source: &IocSource, bare_domains: bool) {
+        for url in find_urls(text) {
+            self.add_host(host_of(&url), source);
+        }
+        for word in text.split(|c: char| c.is_whitespace() || DELIMITERS.contains(c)) {
+            // Of "key=value" pairs, e.g. "smtp.mailfrom=example.com"
+            // in Authentication-Results, only the value is kept.
+            let word = word.rsplit('=').next().unwrap_or_default();
+            let word = word.trim_start_matches('.').trim_end_matches(TRAILING);
+            let lower = word.to_ascii_lowercase();
+            let word = ["mailto:", "ipv6:"]
+                .iter()
+                .find_map(|prefix| lower.strip_prefix(prefix))
+                .unwrap_or(&lower);
+            // URLs are found above, as a whole.
+            if word.contains('/') {
+                continue;
+            }
+            if let Some((_, domain)) = split_email(word) {
+                self.emails.add(word.to_string(), source);
+                self.domains.add(domain.to_string(), source);
+            } else if let Some(ip) = parse_ip(word) {
+                self.ips.add(ip.to_string(), source);
+            } else if bare_domains && is_domain(word) {
+                let extension = word.rsplit('.').next().unwrap_or_default();
+                if !FILE_EXTENSIONS.contains(&extension) {
+                    self.domains.add(word.to_string(), source);
+                }
+            }
+        }
+    }
+}
+
+impl Outlook {
+    // IP addresses, domains and email addresses the message
+    // holds, for triage. See Iocs.
+    pub fn iocs(&self) -> Iocs {
+        Iocs::create_from_props(&self.properties.root)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        super::{builder::OutlookBuilder, outlook::Outlook},
+        is_domain, split_email, IocSource,
+    };
+
+    #[test]
+    fn test_is_domain() {
+        assert!(is_domain("mail.example.com"));
+        assert!(is_domain("xn--bcher-kva.example"));
+        assert!(!is_domain("localhost"));
+        assert!(!is_domain("1.2.3.4"));
+        assert!(!is_domain("e.g"));
+        assert!(!is_domain("-bad.example.com"));
+        assert_eq!(split_email("first.last+tag@example.com"), Some(("first.last+tag", "example.com")));
+        assert_eq!(split_email("@example.com"), None);
+        assert_eq!(split_email("a@b"), None);
+    }
+
+    #[test]
+    fn test_iocs() {
+        let outlook = OutlookBuilder::new()
+            .body("Wire to payments@evil.test by Friday, see report.pdf or https://203.0.113.7/pay.")
+            .html("<p>Contact <a href=\"mailto:Help@Support.example.org\">us</a> at login.example.net.</p>")
+            .transport_headers(
+                "Received: from mx.sender.test (mx.sender.test [198.51.100.23]) by mx.example.com\r\n\
+                 Received: from client (IPv6:2001:DB8::1) by mx.sender.test\r\n\
+                 Authentication-Results: mx.example.com; spf=pass smtp.mailfrom=sender.test\r\n\
+                 Message-ID: <1234@mx.sender.test>\r\n",
+            )
+            .build()
+            .unwrap();
+        let iocs = outlook.iocs();
+        let values = |iocs: &[super::Ioc]| iocs.iter().map(|ioc| ioc.value.clone()).collect::<Vec<String>>();
+        assert_eq!(values(&iocs.ips), vec!["203.0.113.7", "198.51.100.23", "2001:db8::1"]);
+        assert_eq!(values(&iocs.emails), vec!["payments@evil.test", "help@support.example.org"]);
+        assert_eq!(
+            values(&iocs.domains),
+            vec![
+                "evil.test",
+                "support.example.org",
+                "login.example.net",
+                "mx.sender.test",
+                "mx.example.com",
+                "sender.test",
+            ]
+        );
+        assert_eq!(iocs.ips[0].sources, vec![IocSource::Body]);
+        assert_eq!(iocs.ips[1].sources, vec![IocSource::Header("Received".to_string())]);
+        assert_eq!(
+            iocs.domains[3].sources,
+            vec![
+                IocSource::Header("Received".to_string()),
+                IocSource::Header("Message-ID".to_string()),
+            ]
+        );
+
+        let outlook = Outlook::from_path("data/attachment.msg").unwrap();
+        let iocs = outlook.iocs();
+        assert!(iocs.domains.iter().any(|ioc| ioc.value == "www.docsign-online.com"));
+        assert!(iocs.domains.iter().all(|ioc| ioc.value != "www.w3.org" && ioc.value != "p.msonormal"));
+        assert!(iocs.emails.iter().any(|ioc| ioc.value == "duncan@docsign-online.com"));
+    }
+}
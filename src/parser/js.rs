@@ -0,0 +1,217 @@
+use serde::{
+    ser::{SerializeMap, SerializeSeq},
+    Serialize, Serializer,
+};
+use serde_json::Value;
+
+use crate::ole::{constants::IDENTIFIER, Error as OleError};
+
+use super::{error::Error, extract::ExtractedAttachment, json::is_binary_field, outlook::Outlook};
+
+// ErrorInfo holds the properties added to the Error the
+// JavaScript bindings throw, for apps telling e.g. a file
+// that is not a message from one cut short.
+#[derive(Serialize, Debug, PartialEq)]
+pub(super) struct ErrorInfo {
+    pub(super) code: u32,          // Error::code
+    pub(super) kind: &'static str, // See ParseErrorKind
+    pub(super) message: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(super) path: Option<String>, // Stream or storage the error happened in
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(super) tag: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(super) offset: Option<usize>,
+}
+
+impl ErrorInfo {
+    pub(super) fn new(err: &Error, slice: &[u8]) -> Self {
+        let context = err.context().cloned().unwrap_or_default();
+        Self {
+            code: err.code(),
+            kind: Self::kind(err.root(), slice),
+            message: err.to_string(),
+            path: context.path,
+            tag: context.tag,
+            offset: context.offset,
+        }
+    }
+
+    fn kind(err: &Error, slice: &[u8]) -> &'static str {
+        if !slice.starts_with(&IDENTIFIER) {
+            return "not_ole";
+        }
+        match err {
+            Error::OleError {
+                source: OleError::IOError(err),
+            }
+            | Error::Io { source: err }
+                if err.kind() == std::io::ErrorKind::UnexpectedEof =>
+            {
+                "truncated"
+            }
+            Error::OleError {
+                source: OleError::BadSizeValue("File is too short"),
+            } => "truncated",
+            Error::OleError {
+                source: OleError::InvalidOLEFile,
+            } => "not_ole",
+            Error::OleError { .. } | Error::DataTypeError(_) | Error::Context { .. } => "corrupt",
+            Error::Incomplete { .. } => "incomplete",
+            Error::LimitExceeded { .. } => "limit_exceeded",
+            Error::Io { .. } => "io",
+            Error::SerdeJsonError(_) | Error::EncodeError(_) => "encode",
+        }
+    }
+}
+
+// Bytes of a binary field, which the bindings write as a
+// Uint8Array or Buffer.
+struct Bytes(Vec<u8>);
+
+impl Serialize for Bytes {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_bytes(&self.0)
+    }
+}
+
+// Value of the JSON of the model, binary fields being
+// bytes rather than hex strings.
+pub(super) struct JsModel<'a>(pub(super) &'a Value);
+
+impl Serialize for JsModel<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self.0 {
+            Value::Array(values) => {
+                let mut array = serializer.serialize_seq(Some(values.len()))?;
+                for value in values {
+                    array.serialize_element(&JsModel(value))?;
+                }
+                array.end()
+            }
+            Value::Object(map) => {
+                let mut object = serializer.serialize_map(Some(map.len()))?;
+                for (key, value) in map {
+                    match value {
+                        Value::String(hex) if is_binary_field(key) => match hex::decode(hex) {
+                            Ok(bytes) => object.serialize_entry(key, &Bytes(bytes))?,
+                            Err(_) => object.serialize_entry(key, hex)?,
+                        },
+                        value => object.serialize_entry(key, &JsModel(value))?,
+                    }
+                }
+                object.end()
+            }
+            value => value.serialize(serializer),
+        }
+    }
+}
+
+// AttachmentInfo describes an attachment of a Message,
+// without its data.
+#[derive(Serialize, Debug, PartialEq)]
+pub(super) struct AttachmentInfo {
+    pub(super) index: usize,
+    pub(super) file_name: String,
+    pub(super) mime_tag: String,
+    pub(super) size: usize,
+    pub(super) embedded: bool,
+}
+
+impl Outlook {
+    pub(super) fn attachment_infos(&self) -> Vec<AttachmentInfo> {
+        self.attachments
+            .iter()
+            .enumerate()
+            .map(|(index, attachment)| AttachmentInfo {
+                index,
+                file_name: ExtractedAttachment::file_name_of(attachment),
+                mime_tag: attachment.mime_tag.clone(),
+                size: attachment.payload.len() / 2,
+                embedded: attachment.embedded.is_some(),
+            })
+            .collect()
+    }
+
+    pub(super) fn attachment_bytes(&self, index: usize) -> Result<Vec<u8>, String> {
+        let attachment = self
+            .attachments
+            .get(index)
+            .ok_or_else(|| format!("no attachment {} of {}", index, self.attachments.len()))?;
+        if attachment.payload.is_empty() && attachment.embedded.is_some() {
+            return Err(format!("attachment {} is an embedded message", index));
+        }
+        hex::decode(&attachment.payload).map_err(|err| err.to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::Value;
+
+    use super::{
+        super::{limits::Limit, outlook::Outlook},
+        Error, ErrorInfo, JsModel,
+    };
+
+    #[test]
+    fn test_js_model() {
+        let outlook = Outlook::from_path("data/attachment.msg").unwrap();
+        let value = serde_json::to_value(&outlook).unwrap();
+        let model = serde_json::to_value(JsModel(&value)).unwrap();
+        assert_eq!(model["subject"], value["subject"]);
+        assert_eq!(model["attachments"][0]["display_name"], value["attachments"][0]["display_name"]);
+
+        // Bytes are written as arrays of numbers by serde_json.
+        let payload: Vec<u8> = serde_json::from_value(model["attachments"][0]["payload"].clone()).unwrap();
+        assert_eq!(payload, hex::decode(&outlook.attachments[0].payload).unwrap());
+        assert!(matches!(model["rtf_compressed"], Value::Array(_)));
+    }
+
+    #[test]
+    fn test_attachment_infos() {
+        let outlook = Outlook::from_path("data/attachment.msg").unwrap();
+        let infos = outlook.attachment_infos();
+        assert_eq!(infos.len(), outlook.attachments.len());
+        assert_eq!(infos[0].index, 0);
+        assert_eq!(infos[0].file_name, "loan_proposal.doc");
+        assert!(!infos[0].embedded);
+
+        let bytes = outlook.attachment_bytes(0).unwrap();
+        assert_eq!(bytes.len(), infos[0].size);
+        assert_eq!(bytes, hex::decode(&outlook.attachments[0].payload).unwrap());
+        assert_eq!(
+            outlook.attachment_bytes(infos.len()),
+            Err(format!("no attachment {} of {}", infos.len(), infos.len()))
+        );
+    }
+
+    #[test]
+    fn test_error_info() {
+        let kind = |slice: &[u8]| ErrorInfo::new(&Outlook::from_slice(slice).unwrap_err(), slice).kind;
+        let bytes = std::fs::read("data/attachment.msg").unwrap();
+        assert_eq!(kind(b"hello world"), "not_ole");
+        assert_eq!(kind(&bytes[..300]), "truncated");
+        assert_eq!(kind(&bytes[..3000]), "truncated");
+
+        let err = Error::LimitExceeded {
+            limit: Limit::StreamSize,
+            max: 16,
+        }
+        .in_entry("__substg1.0_1000001F");
+        let info = ErrorInfo::new(&err, &bytes);
+        assert_eq!(
+            info,
+            ErrorInfo {
+                code: 5,
+                kind: "limit_exceeded",
+                message: "Limit exceeded: stream size over 16 (in __substg1.0_1000001F)".to_string(),
+                path: Some("__substg1.0_1000001F".to_string()),
+                tag: None,
+                offset: None,
+            }
+        );
+        let value = serde_json::to_value(&info).unwrap();
+        assert!(value.get("tag").is_none());
+    }
+}
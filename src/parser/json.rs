@@ -0,0 +1,282 @@
+use base64::{engine::general_purpose::STANDARD, Engine};
+
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+use super::{error::Error, outlook::Outlook};
+
+// Version of the JSON to_json writes, raised whenever a
+// field is added, renamed or removed, as is the one of
+// schema/outlook.schema.json.
+pub const JSON_SCHEMA_VERSION: &str = "1.3.0";
+
+// Fields holding binary values, as hex strings.
+const BINARY_FIELDS: [&str; 3] = ["payload", "rtf_compressed", "entry_id"];
+
+// Fields holding the bodies of a message.
+const BODY_FIELDS: [&str; 2] = ["body", "rtf_compressed"];
+
+// Field holding the data of an attachment, or of a
+// contact photo.
+const ATTACHMENT_DATA_FIELD: &str = "payload";
+
+// KeyCase is the case of the keys of the JSON objects.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum KeyCase {
+    #[default]
+    Snake, // "message_class", as the fields are named
+    Camel, // "messageClass"
+}
+
+// BinaryEncoding is how binary values are written.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum BinaryEncoding {
+    #[default]
+    Hex,
+    Base64, // Standard alphabet, padded
+}
+
+// JsonOptions tells how Outlook::to_json_with writes a
+// message, the default writing what to_json does, keys
+// sorted.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct JsonOptions {
+    pub pretty: bool,           // Indented over several lines
+    pub key_case: KeyCase,      // See KeyCase enum
+    pub binary: BinaryEncoding, // See BinaryEncoding enum
+    pub omit_empty: bool,       // Leave out nulls, empty strings, arrays and objects
+    pub bodies: bool,           // Write "body" and "rtf_compressed"
+    pub attachment_data: bool,  // Write the "payload" of attachments
+}
+
+impl Default for JsonOptions {
+    fn default() -> Self {
+        Self {
+            pretty: false,
+            key_case: KeyCase::Snake,
+            binary: BinaryEncoding::Hex,
+            omit_empty: false,
+            bodies: true,
+            attachment_data: true,
+        }
+    }
+}
+
+impl JsonOptions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn pretty(mut self, pretty: bool) -> Self {
+        self.pretty = pretty;
+        self
+    }
+
+    pub fn key_case(mut self, key_case: KeyCase) -> Self {
+        self.key_case = key_case;
+        self
+    }
+
+    pub fn binary(mut self, binary: BinaryEncoding) -> Self {
+        self.binary = binary;
+        self
+    }
+
+    pub fn omit_empty(mut self, omit_empty: bool) -> Self {
+        self.omit_empty = omit_empty;
+        self
+    }
+
+    pub fn bodies(mut self, bodies: bool) -> Self {
+        self.bodies = bodies;
+        self
+    }
+
+    pub fn attachment_data(mut self, attachment_data: bool) -> Self {
+        self.attachment_data = attachment_data;
+        self
+    }
+
+    // Whether the field is left out of the output.
+    fn is_excluded(&self, key: &str) -> bool {
+        (!self.bodies && BODY_FIELDS.contains(&key))
+            || (!self.attachment_data && key == ATTACHMENT_DATA_FIELD)
+    }
+
+    // Rewrites the JSON of the model, embedded messages
+    // included, as the options tell.
+    pub(crate) fn apply(&self, value: Value) -> Value {
+        match value {
+            Value::Object(map) => Value::Object(
+                map.into_iter()
+                    .filter(|(key, _)| !self.is_excluded(key))
+                    .map(|(key, value)| {
+                        let value = match value {
+                            Value::String(hex) if is_binary_field(&key) => {
+                                Value::String(self.encode_binary(hex))
+                            }
+                            value => self.apply(value),
+                        };
+                        (self.rename(&key), value)
+                    })
+                    .filter(|(_, value)| !self.omit_empty || !is_empty(value))
+                    .collect::<Map<String, Value>>(),
+            ),
+            Value::Array(values) => Value::Array(
+                values
+                    .into_iter()
+                    .map(|value| self.apply(value))
+                    .filter(|value| !self.omit_empty || !is_empty(value))
+                    .collect(),
+            ),
+            value => value,
+        }
+    }
+
+    fn encode_binary(&self, hex: String) -> String {
+        match self.binary {
+            BinaryEncoding::Hex => hex,
+            BinaryEncoding::Base64 => match hex::decode(&hex) {
+                Ok(bytes) => STANDARD.encode(bytes),
+                Err(_) => hex,
+            },
+        }
+    }
+
+    fn rename(&self, key: &str) -> String {
+        match self.key_case {
+            KeyCase::Snake => key.to_string(),
+            KeyCase::Camel => to_camel_case(key),
+        }
+    }
+
+    pub(crate) fn write(&self, value: &Value) -> Result<String, Error> {
+        if self.pretty {
+            Ok(serde_json::to_string_pretty(value)?)
+        } else {
+            Ok(serde_json::to_string(value)?)
+        }
+    }
+}
+
+// Whether the field holds a binary value, as a hex string.
+pub(super) fn is_binary_field(key: &str) -> bool {
+    BINARY_FIELDS.contains(&key)
+}
+
+fn is_empty(value: &Value) -> bool {
+    match value {
+        Value::Null => true,
+        Value::String(string) => string.is_empty(),
+        Value::Array(values) => values.is_empty(),
+        Value::Object(map) => map.is_empty(),
+        _ => false,
+    }
+}
+
+fn to_camel_case(key: &str) -> String {
+    let mut camel = String::with_capacity(key.len());
+    let mut upper = false;
+    for c in key.chars() {
+        if c == '_' {
+            upper = !camel.is_empty();
+        } else if upper {
+            camel.extend(c.to_uppercase());
+            upper = false;
+        } else {
+            camel.push(c);
+        }
+    }
+    camel
+}
+
+impl Outlook {
+    // Same as to_json, written as the options tell.
+    pub fn to_json_with(&self, options: &JsonOptions) -> Result<String, Error> {
+        let value = options.apply(serde_json::to_value(self)?);
+        options.write(&value)
+    }
+}
+
+#[cfg(feature = "schemars")]
+impl Outlook {
+    // JSON Schema of what to_json writes, identified by
+    // JSON_SCHEMA_VERSION. Options of to_json_with other
+    // than the default ones are not reflected in it.
+    pub fn json_schema() -> Value {
+        let mut schema = schemars::schema_for!(Outlook);
+        schema.insert(
+            "$id".to_string(),
+            Value::from(format!("urn:msg-parser:outlook:{}", JSON_SCHEMA_VERSION)),
+        );
+        schema.insert("version".to_string(), Value::from(JSON_SCHEMA_VERSION));
+        schema.to_value()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::Value;
+
+    use super::{super::outlook::Outlook, to_camel_case, BinaryEncoding, JsonOptions, KeyCase};
+
+    #[test]
+    fn test_to_camel_case() {
+        assert_eq!(to_camel_case("message_class"), "messageClass".to_string());
+        assert_eq!(to_camel_case("sent_representing"), "sentRepresenting".to_string());
+        assert_eq!(to_camel_case("subject"), "subject".to_string());
+        assert_eq!(to_camel_case("PtypString"), "PtypString".to_string());
+    }
+
+    #[test]
+    fn test_to_json_with() {
+        let outlook = Outlook::from_path("data/attachment.msg").unwrap();
+        let parse = |json: String| serde_json::from_str::<Value>(&json).unwrap();
+        assert_eq!(
+            parse(outlook.to_json_with(&JsonOptions::new()).unwrap()),
+            parse(outlook.to_json().unwrap())
+        );
+
+        let pretty = outlook.to_json_with(&JsonOptions::new().pretty(true)).unwrap();
+        assert!(pretty.contains("\n  \"headers\": {"));
+
+        let options = JsonOptions::new().key_case(KeyCase::Camel).binary(BinaryEncoding::Base64);
+        let json = parse(outlook.to_json_with(&options).unwrap());
+        assert_eq!(json["messageClass"], Value::from(outlook.message_class.as_str()));
+        assert!(json.get("message_class").is_none());
+        // "\xD0\xCF\x11\xE0", the signature of the OLE file
+        // the .doc attachment is.
+        assert!(json["attachments"][0]["payload"].as_str().unwrap().starts_with("0M8R4"));
+        assert!(json["attachments"][0]["displayName"].is_string());
+
+        let options = JsonOptions::new().omit_empty(true).bodies(false).attachment_data(false);
+        let json = parse(outlook.to_json_with(&options).unwrap());
+        assert!(json.get("body").is_none());
+        assert!(json.get("rtf_compressed").is_none());
+        assert!(json.get("bcc").is_none());
+        assert!(json.get("appointment").is_none());
+        assert!(json["attachments"][0].get("payload").is_none());
+        assert_eq!(json["subject"], Value::from(outlook.subject.as_str()));
+    }
+
+    #[cfg(feature = "schemars")]
+    #[test]
+    fn test_json_schema() {
+        use super::JSON_SCHEMA_VERSION;
+
+        let schema = Outlook::json_schema();
+        assert_eq!(schema["version"], Value::from(JSON_SCHEMA_VERSION));
+        let outlook = Outlook::from_path("data/test_email.msg").unwrap();
+        let json: Value = serde_json::from_str(&outlook.to_json().unwrap()).unwrap();
+        let properties = schema["properties"].as_object().unwrap();
+        let keys = json.as_object().unwrap().keys();
+        assert!(keys.clone().all(|key| properties.contains_key(key)));
+        assert_eq!(properties.len(), keys.len());
+
+        // A schema changing along with the model needs its
+        // version raised, and the file written again.
+        let path = "schema/outlook.schema.json";
+        let written: Value = serde_json::from_str(&std::fs::read_to_string(path).unwrap()).unwrap();
+        assert_eq!(schema, written, "{} is out of date", path);
+    }
+}
@@ -0,0 +1,193 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use super::{decode::DataType, headers::Headers, storage::Properties};
+
+// Prefix of the fields of each label.
+const LABEL_PREFIX: &str = "MSIP_Label_";
+
+// Bits of "ContentBits", the content marking and
+// protection the label applied.
+const CONTENT_HEADER: i64 = 0x1;
+const CONTENT_FOOTER: i64 = 0x2;
+const CONTENT_WATERMARK: i64 = 0x4;
+const CONTENT_ENCRYPTION: i64 = 0x8;
+
+// SensitivityLabel is a Microsoft Information Protection
+// label applied to the message, as kept in "msip_labels".
+// Its fields read "MSIP_Label_<GUID>_<Field>=<Value>".
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct SensitivityLabel {
+    pub id: String,                      // GUID of the label
+    pub name: String,                    // "Name"
+    pub enabled: bool,                   // "Enabled"
+    pub set_date: Option<DateTime<Utc>>, // "SetDate"
+    pub method: String,                  // "Method", "Standard" or "Privileged" when chosen by hand
+    pub site_id: String,                 // "SiteId", GUID of the tenant
+    pub action_id: String,               // "ActionId"
+    pub parent_id: String,               // "Parent", GUID of the parent of a sublabel
+    pub owner: String,                   // "Owner"
+    pub content_bits: i64,               // "ContentBits"
+}
+
+impl SensitivityLabel {
+    fn new(id: &str) -> Self {
+        Self {
+            id: id.to_string(),
+            name: String::new(),
+            enabled: false,
+            set_date: None,
+            method: String::new(),
+            site_id: String::new(),
+            action_id: String::new(),
+            parent_id: String::new(),
+            owner: String::new(),
+            content_bits: 0,
+        }
+    }
+
+    // Labels of a "msip_labels" value, in the order
+    // they first appear.
+    pub fn parse_all(value: &str) -> Vec<Self> {
+        let mut labels: Vec<Self> = vec![];
+        for field in value.split(';') {
+            let (key, value) = match field.split_once('=') {
+                Some((key, value)) => (key.trim(), value.trim()),
+                None => continue,
+            };
+            let rest = match key.get(..LABEL_PREFIX.len()) {
+                Some(prefix) if prefix.eq_ignore_ascii_case(LABEL_PREFIX) => &key[LABEL_PREFIX.len()..],
+                _ => continue,
+            };
+            // GUIDs hold no underscores, unlike some field names.
+            let (id, name) = match rest.split_once('_') {
+                Some(split) => split,
+                None => continue,
+            };
+            let position = labels.iter().position(|label| label.id.eq_ignore_ascii_case(id));
+            let label = match position {
+                Some(position) => &mut labels[position],
+                None => {
+                    labels.push(Self::new(id));
+                    labels.last_mut().unwrap()
+                }
+            };
+            match name.to_ascii_lowercase().as_str() {
+                "name" => label.name = value.to_string(),
+                "enabled" => label.enabled = value.eq_ignore_ascii_case("true"),
+                "setdate" => {
+                    label.set_date = DateTime::parse_from_rfc3339(value)
+                        .ok()
+                        .map(|date| date.with_timezone(&Utc))
+                }
+                "method" => label.method = value.to_string(),
+                "siteid" => label.site_id = value.to_string(),
+                "actionid" => label.action_id = value.to_string(),
+                "parent" => label.parent_id = value.to_string(),
+                "owner" => label.owner = value.to_string(),
+                "contentbits" => label.content_bits = value.parse().unwrap_or(0),
+                _ => {}
+            }
+        }
+        labels
+    }
+
+    // Labels of the named property, or else of the
+    // transport header field of the same name.
+    pub fn create_from_props(props: &Properties, headers: &Headers) -> Vec<Self> {
+        props
+            .get("msip_labels")
+            .and_then(DataType::as_str)
+            .filter(|value| !value.is_empty())
+            .or_else(|| headers.get("msip_labels"))
+            .map(Self::parse_all)
+            .unwrap_or_default()
+    }
+
+    pub fn has_header(&self) -> bool {
+        self.content_bits & CONTENT_HEADER != 0
+    }
+
+    pub fn has_footer(&self) -> bool {
+        self.content_bits & CONTENT_FOOTER != 0
+    }
+
+    pub fn has_watermark(&self) -> bool {
+        self.content_bits & CONTENT_WATERMARK != 0
+    }
+
+    // Whether the label encrypts the content.
+    pub fn is_encrypted(&self) -> bool {
+        self.content_bits & CONTENT_ENCRYPTION != 0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{TimeZone as _, Utc};
+
+    use super::{
+        super::{decode::DataType, headers::Headers, outlook::Outlook, storage::Properties},
+        SensitivityLabel,
+    };
+
+    const LABELS: &str = "MSIP_Label_f42aa342-8706-4288-bd11-ebb85995028c_Enabled=true;\
+        MSIP_Label_f42aa342-8706-4288-bd11-ebb85995028c_SetDate=2021-03-14T04:03:57.1234567Z;\
+        MSIP_Label_f42aa342-8706-4288-bd11-ebb85995028c_Method=Privileged;\
+        MSIP_Label_f42aa342-8706-4288-bd11-ebb85995028c_Name=Confidential;\
+        MSIP_Label_f42aa342-8706-4288-bd11-ebb85995028c_SiteId=72f988bf-86f1-41af-91ab-2d7cd011db47;\
+        MSIP_Label_f42aa342-8706-4288-bd11-ebb85995028c_ActionId=0b3fd5a7-7b7a-4b0d-8a0b-1c2d3e4f5a6b;\
+        MSIP_Label_f42aa342-8706-4288-bd11-ebb85995028c_ContentBits=9;\
+        MSIP_Label_f42aa342-8706-4288-bd11-ebb85995028c_Extended_MSFT_Method=Manual;\
+        MSIP_Label_87867195-f2b8-4ac2-b0b6-6bb73cb33afc_Enabled=false;\
+        MSIP_Label_87867195-f2b8-4ac2-b0b6-6bb73cb33afc_Name=Public;\
+        Sensitivity=Confidential";
+
+    #[test]
+    fn test_parse_all() {
+        let labels = SensitivityLabel::parse_all(LABELS);
+        assert_eq!(labels.len(), 2);
+        let label = &labels[0];
+        assert_eq!(label.id, "f42aa342-8706-4288-bd11-ebb85995028c".to_string());
+        assert_eq!(label.name, "Confidential".to_string());
+        assert!(label.enabled);
+        assert_eq!(label.method, "Privileged".to_string());
+        assert_eq!(label.site_id, "72f988bf-86f1-41af-91ab-2d7cd011db47".to_string());
+        assert_eq!(
+            label.set_date.unwrap().date_naive(),
+            Utc.with_ymd_and_hms(2021, 3, 14, 0, 0, 0).unwrap().date_naive()
+        );
+        assert!(label.has_header());
+        assert!(!label.has_footer());
+        assert!(!label.has_watermark());
+        assert!(label.is_encrypted());
+
+        assert_eq!(labels[1].name, "Public".to_string());
+        assert!(!labels[1].enabled);
+        assert_eq!(labels[1].set_date, None);
+
+        assert!(SensitivityLabel::parse_all("Sensitivity=Public; MSIP_Label_x").is_empty());
+    }
+
+    #[test]
+    fn test_create_from_props() {
+        let headers = Headers::parse(&format!("msip_labels: {}\r\n", LABELS));
+        assert_eq!(
+            SensitivityLabel::create_from_props(&Properties::new(), &headers).len(),
+            2
+        );
+
+        let mut props = Properties::new();
+        props.insert(
+            "msip_labels".to_string(),
+            DataType::PtypString("MSIP_Label_87867195-f2b8-4ac2-b0b6-6bb73cb33afc_Name=Public".to_string()),
+        );
+        let labels = SensitivityLabel::create_from_props(&props, &headers);
+        assert_eq!(labels.len(), 1);
+        assert_eq!(labels[0].name, "Public".to_string());
+
+        let outlook = Outlook::from_path("data/test_email.msg").unwrap();
+        assert!(outlook.sensitivity_labels.is_empty());
+    }
+}
@@ -0,0 +1,313 @@
+use std::{borrow::Cow, fs::File, path::Path};
+
+use crate::ole::{self, EntryType};
+
+use super::{
+    decode::DataType,
+    envelope::{Envelope, ENVELOPE_KEYS},
+    error::Error,
+    options::ParserOptions,
+    outlook::{Attachment, Outlook, Person},
+    recipient::Recipient,
+    storage::{Properties, Recipients, StorageType, Storages, ROOT_ID},
+    stream::Stream,
+};
+
+// Property holding the data of an attachment.
+const ATTACHMENT_DATA: &str = "AttachDataObject";
+
+// Stream of the message, located without decoding it.
+#[derive(Debug)]
+struct StreamIndex {
+    storage: StorageType, // Storage the stream belongs to
+    key: Option<String>,  // Property it holds, None for property streams
+    id: u32,              // Entry id of the stream
+}
+
+// LazyOutlook indexes the storages and streams of a message
+// up front but only decodes a stream when an accessor asks
+// for it, e.g. reading the subject of a large message does
+// not copy any of its attachments. Each call decodes its
+// streams again, see load to parse the whole message once.
+// Streams going over Limits.max_stream_size are left out,
+// as if the message lacked them.
+pub struct LazyOutlook<'a> {
+    parser: ole::Reader<'a>,
+    storages: Storages,      // Fixed length properties of the root only
+    index: Vec<StreamIndex>, // Streams of the message, embedded messages left out
+    options: ParserOptions,
+}
+
+impl<'a> LazyOutlook<'a> {
+    fn create(parser: ole::Reader<'a>, options: &ParserOptions) -> Self {
+        let mut storages = Storages::create(&parser, ROOT_ID, options);
+        let index: Vec<StreamIndex> = parser
+            .iterate()
+            .filter(|entry| matches!(entry._type(), EntryType::UserStream))
+            .filter_map(|entry| {
+                let storage = storages.storage_type(entry)?.clone();
+                let key = storages.stream_key(entry);
+                if key.is_none() && !Stream::is_property_stream(entry.name()) {
+                    return None;
+                }
+                Some(StreamIndex {
+                    storage,
+                    key,
+                    id: entry.id(),
+                })
+            })
+            .collect();
+        // Fixed length properties of the root are few and
+        // tell the code page of 8-bit strings.
+        for item in index.iter().filter(|item| item.storage == StorageType::RootEntry && item.key.is_none()) {
+            let streams = Self::decode(&parser, &storages, item.id);
+            storages.root.extend(streams.into_iter().map(|stream| (stream.key, stream.value)));
+        }
+        Self {
+            parser,
+            storages,
+            index,
+            options: options.clone(),
+        }
+    }
+
+    fn decode(parser: &ole::Reader, storages: &Storages, id: u32) -> Vec<Stream> {
+        parser
+            .iterate()
+            .find(|entry| entry.id() == id)
+            .and_then(|entry| storages.decode_stream(parser, entry).ok())
+            .unwrap_or_default()
+    }
+
+    // Decodes the streams of a storage, or only the one of
+    // key when given.
+    fn decode_storage(&self, storage: &StorageType, key: Option<&str>) -> Properties {
+        self.index
+            .iter()
+            .filter(|item| &item.storage == storage)
+            .filter(|item| key.is_none() || item.key.as_deref() == key)
+            .flat_map(|item| Self::decode(&self.parser, &self.storages, item.id))
+            .map(|stream| (stream.key, stream.value))
+            .collect()
+    }
+
+    // Indexes of the storages of a kind, in order.
+    fn storage_ids(&self, kind: fn(&StorageType) -> Option<u32>) -> Vec<u32> {
+        let mut ids: Vec<u32> = self.index.iter().filter_map(|item| kind(&item.storage)).collect();
+        ids.sort_unstable();
+        ids.dedup();
+        ids
+    }
+
+    fn recipient_ids(&self) -> Vec<u32> {
+        self.storage_ids(|storage| match storage {
+            StorageType::Recipient(id) => Some(*id),
+            _ => None,
+        })
+    }
+
+    fn attachment_ids(&self) -> Vec<u32> {
+        self.storage_ids(|storage| match storage {
+            StorageType::Attachment(id) => Some(*id),
+            _ => None,
+        })
+    }
+
+    fn attachment_storage(&self, index: usize) -> Option<StorageType> {
+        self.attachment_ids().get(index).map(|id| StorageType::Attachment(*id))
+    }
+
+    pub fn from_path<P: AsRef<Path>>(path: P) -> Result<Self, Error> {
+        Self::from_path_with(path, &ParserOptions::default())
+    }
+
+    pub fn from_path_with<P: AsRef<Path>>(path: P, options: &ParserOptions) -> Result<Self, Error> {
+        let file = File::open(path)?;
+        Ok(Self::create(options.limits.reader(file)?, options))
+    }
+
+    pub fn from_slice(slice: &'a [u8]) -> Result<Self, Error> {
+        Self::from_slice_with(slice, &ParserOptions::default())
+    }
+
+    pub fn from_slice_with(slice: &'a [u8], options: &ParserOptions) -> Result<Self, Error> {
+        Ok(Self::create(options.limits.slice_reader(slice)?, options))
+    }
+
+    // Property of the message, decoding its stream if it
+    // is of variable length.
+    pub fn property(&self, key: &str) -> Option<DataType> {
+        if let Some(value) = self.storages.root.get(key) {
+            return Some(value.clone());
+        }
+        self.decode_storage(&StorageType::RootEntry, Some(key)).remove(key)
+    }
+
+    // Every property of the message, bodies included.
+    pub fn properties(&self) -> Properties {
+        self.decode_storage(&StorageType::RootEntry, None)
+    }
+
+    fn string(&self, key: &str) -> String {
+        self.property(key).as_ref().map(String::from).unwrap_or_default()
+    }
+
+    pub fn subject(&self) -> String {
+        self.string("Subject")
+    }
+
+    pub fn message_class(&self) -> String {
+        self.string("MessageClass")
+    }
+
+    pub fn body(&self) -> String {
+        self.string("Body")
+    }
+
+    // Sender as the message names it, X.500 addresses being
+    // left as they are, unlike Outlook.sender.
+    pub fn sender(&self) -> Person {
+        let keys = ["SenderName", "SenderSmtpAddress", "SenderEmailAddress"];
+        let props: Properties = keys
+            .iter()
+            .filter_map(|key| Some((key.to_string(), self.property(key)?)))
+            .collect();
+        Person::create_from_props(&props, keys[0], keys[1..].to_vec())
+    }
+
+    fn recipient_props(&self) -> Recipients {
+        self.recipient_ids()
+            .into_iter()
+            .map(|id| self.decode_storage(&StorageType::Recipient(id), None))
+            .collect()
+    }
+
+    pub fn recipients(&self) -> Vec<Recipient> {
+        Recipient::create_all(&self.recipient_props())
+    }
+
+    // Sender, recipients, subject and dates, decoding the
+    // few root streams they need and the recipient ones.
+    pub fn envelope(&self) -> Envelope {
+        let mut root = self.storages.root.clone();
+        root.extend(
+            ENVELOPE_KEYS
+                .iter()
+                .filter_map(|key| Some((key.to_string(), self.property(key)?))),
+        );
+        Envelope::create_from_props(&root, &self.recipient_props())
+    }
+
+    pub fn attachment_count(&self) -> usize {
+        self.attachment_ids().len()
+    }
+
+    // Property of the attachment at index, e.g. its
+    // "DisplayName" without loading its data.
+    pub fn attachment_property(&self, index: usize, key: &str) -> Option<DataType> {
+        let storage = self.attachment_storage(index)?;
+        self.decode_storage(&storage, Some(key)).remove(key)
+    }
+
+    // Attachment at index with its data, embedded messages
+    // being left unparsed.
+    pub fn attachment(&self, index: usize) -> Option<Attachment> {
+        let storage = self.attachment_storage(index)?;
+        Some(Attachment::create_from_props(&self.decode_storage(&storage, None), &self.options.limits))
+    }
+
+    // Data of the attachment at index, borrowed from the
+    // message rather than decoded when parsed from a slice
+    // and stored in one run of sectors, as most large ones
+    // are. None when the attachment has no data, e.g. an
+    // embedded message, or when the options leave it out.
+    pub fn attachment_data(&self, index: usize) -> Option<Cow<'_, [u8]>> {
+        if !self.options.attachments {
+            return None;
+        }
+        let storage = self.attachment_storage(index)?;
+        let item = self
+            .index
+            .iter()
+            .find(|item| item.storage == storage && item.key.as_deref() == Some(ATTACHMENT_DATA))?;
+        let entry = self.parser.iterate().find(|entry| entry.id() == item.id)?;
+        self.parser.get_entry_bytes(entry).ok()
+    }
+
+    // Parses the whole message, as Outlook::from_path_with
+    // does with the same options.
+    pub fn load(&self) -> Result<Outlook, Error> {
+        Outlook::create(&self.parser, ROOT_ID, &self.options)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::borrow::Cow;
+
+    use super::{
+        super::{decode::DataType, options::ParserOptions, outlook::Outlook},
+        LazyOutlook,
+    };
+
+    #[test]
+    fn test_lazy_outlook() {
+        let path = "data/attachment.msg";
+        let outlook = Outlook::from_path(path).unwrap();
+        let lazy = LazyOutlook::from_path(path).unwrap();
+        assert_eq!(lazy.subject(), outlook.subject);
+        assert_eq!(lazy.message_class(), outlook.message_class);
+        assert_eq!(lazy.body(), outlook.body);
+        assert_eq!(lazy.recipients(), outlook.recipients);
+        assert_eq!(lazy.property("ClientSubmitTime").and_then(|value| value.as_time()), outlook.submit_time);
+        assert_eq!(lazy.property("Unknown"), None);
+        assert_eq!(lazy.properties().get("Subject"), outlook.properties.root.get("Subject"));
+
+        assert_eq!(lazy.attachment_count(), outlook.attachments.len());
+        assert_eq!(
+            lazy.attachment_property(0, "DisplayName"),
+            Some(DataType::PtypString("loan_proposal.doc".to_string()))
+        );
+        assert_eq!(lazy.attachment(2).as_ref(), outlook.attachments.get(2));
+        assert_eq!(lazy.attachment(3), None);
+        assert_eq!(lazy.load().unwrap(), outlook);
+    }
+
+    #[test]
+    fn test_attachment_data() {
+        let bytes = std::fs::read("data/attachment.msg").unwrap();
+        let outlook = Outlook::from_slice(&bytes).unwrap();
+        let lazy = LazyOutlook::from_slice(&bytes).unwrap();
+        for (index, attachment) in outlook.attachments.iter().enumerate() {
+            let data = lazy.attachment_data(index).unwrap();
+            assert_eq!(hex::encode(&data), attachment.payload);
+        }
+        // The .doc attachment is stored in one run of
+        // sectors, and borrowed from the message.
+        let data = lazy.attachment_data(0).unwrap();
+        assert!(matches!(data, Cow::Borrowed(_)));
+        let range = bytes.as_ptr_range();
+        assert!(range.contains(&data.as_ptr()));
+        assert_eq!(lazy.attachment_data(3), None);
+
+        let lazy = LazyOutlook::from_path("data/attachment.msg").unwrap();
+        assert_eq!(lazy.attachment_data(0), Some(data));
+        let options = ParserOptions::new().attachments(false);
+        let lazy = LazyOutlook::from_slice_with(&bytes, &options).unwrap();
+        assert_eq!(lazy.attachment_data(0), None);
+    }
+
+    #[test]
+    fn test_lazy_outlook_options() {
+        let bytes = std::fs::read("data/unicode.msg").unwrap();
+        let outlook = Outlook::from_slice(&bytes).unwrap();
+        let lazy = LazyOutlook::from_slice(&bytes).unwrap();
+        assert_eq!(lazy.subject(), outlook.subject);
+        assert_eq!(lazy.sender().name, outlook.sender.name);
+
+        let options = ParserOptions::new().bodies(false);
+        let lazy = LazyOutlook::from_slice_with(&bytes, &options).unwrap();
+        assert!(lazy.body().is_empty());
+        assert!(lazy.load().unwrap().body.is_empty());
+    }
+}
@@ -0,0 +1,269 @@
+use std::{
+    fmt,
+    io::{Read, Seek},
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::ole;
+
+use super::{decode::DataType, error::Error};
+
+// Nesting depth of embedded messages parsed by default,
+// well below what would exhaust the stack.
+pub const DEFAULT_MAX_DEPTH: usize = 32;
+
+// Limits bound what parsing a message may read and allocate,
+// guarding services parsing untrusted files against ones
+// crafted to exhaust their memory. Parsing fails with
+// Error::LimitExceeded on the first limit exceeded.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Limits {
+    pub max_stream_size: Option<usize>,   // Bytes of any stream read
+    pub max_decoded_bytes: Option<usize>, // Bytes of all property values decoded, embedded messages included
+    pub max_entries: Option<usize>,       // Directory entries of the file, unused ones included
+    pub max_depth: Option<usize>,         // Nesting depth of embedded messages, the message itself being 0
+}
+
+impl Default for Limits {
+    fn default() -> Self {
+        Self {
+            max_stream_size: None,
+            max_decoded_bytes: None,
+            max_entries: None,
+            max_depth: Some(DEFAULT_MAX_DEPTH),
+        }
+    }
+}
+
+impl Limits {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn max_stream_size(mut self, size: usize) -> Self {
+        self.max_stream_size = Some(size);
+        self
+    }
+
+    pub fn max_decoded_bytes(mut self, size: usize) -> Self {
+        self.max_decoded_bytes = Some(size);
+        self
+    }
+
+    pub fn max_entries(mut self, count: usize) -> Self {
+        self.max_entries = Some(count);
+        self
+    }
+
+    pub fn max_depth(mut self, depth: usize) -> Self {
+        self.max_depth = Some(depth);
+        self
+    }
+
+    // Opens an OLE reader enforcing the limits of the file
+    // structure, the other ones being enforced on decoding.
+    pub(crate) fn reader<'a, R: Read + 'a>(&self, readable: R) -> Result<ole::Reader<'a>, Error> {
+        Ok(ole::Reader::with_limits(readable, self.max_entries, self.max_stream_size)?)
+    }
+
+    // Same as reader for a slice, borrowed rather than
+    // copied.
+    pub(crate) fn slice_reader<'a>(&self, slice: &'a [u8]) -> Result<ole::Reader<'a>, Error> {
+        Ok(ole::Reader::from_slice_with_limits(slice, self.max_entries, self.max_stream_size)?)
+    }
+
+    // Same as reader for a seekable source, read as its
+    // sectors are needed.
+    pub(crate) fn seek_reader<'a, R: Read + Seek + 'a>(&self, source: R) -> Result<ole::Reader<'a>, Error> {
+        Ok(ole::Reader::from_reader_with_limits(source, self.max_entries, self.max_stream_size)?)
+    }
+
+    // Same as slice_reader for a damaged file, see
+    // ole::Reader::salvage.
+    pub(crate) fn salvage_reader<'a>(&self, slice: &'a [u8]) -> Result<ole::Reader<'a>, Error> {
+        Ok(ole::Reader::salvage_with_limits(slice, self.max_entries, self.max_stream_size)?)
+    }
+
+    // Same as reader for a file mapped in memory.
+    #[cfg(feature = "mmap")]
+    pub(crate) fn mmap_reader<P: AsRef<std::path::Path>>(&self, path: P) -> Result<ole::Reader<'static>, Error> {
+        Ok(ole::Reader::from_mmap_with_limits(path, self.max_entries, self.max_stream_size)?)
+    }
+
+    // Fails once decoded, the bytes decoded so far, goes
+    // over max_decoded_bytes.
+    pub(crate) fn check_decoded(&self, decoded: usize) -> Result<(), Error> {
+        match self.max_decoded_bytes {
+            Some(max) if decoded > max => Err(Error::LimitExceeded {
+                limit: Limit::DecodedBytes,
+                max,
+            }),
+            _ => Ok(()),
+        }
+    }
+
+    // Fails when an embedded message is nested deeper
+    // than max_depth.
+    pub(crate) fn check_depth(&self, depth: usize) -> Result<(), Error> {
+        match self.max_depth {
+            Some(max) if depth > max => Err(Error::LimitExceeded { limit: Limit::Depth, max }),
+            _ => Ok(()),
+        }
+    }
+}
+
+// Bytes of a decoded property value, as counted against
+// max_decoded_bytes.
+pub(crate) fn decoded_len(value: &DataType) -> usize {
+    match value {
+        DataType::PtypString(string) => string.len(),
+        DataType::PtypBinary(bytes) => bytes.len(),
+        DataType::PtypMultipleString(strings) => strings.iter().map(String::len).sum(),
+        DataType::PtypMultipleBinary(values) => values.iter().map(Vec::len).sum(),
+        DataType::PtypInteger16(_) => 2,
+        DataType::PtypInteger32(_) | DataType::PtypFloating32(_) | DataType::PtypErrorCode(_) => 4,
+        DataType::PtypBoolean(_) => 1,
+        _ => 8,
+    }
+}
+
+// Limit is the one of Limits a message went over.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum Limit {
+    StreamSize,   // Limits.max_stream_size
+    DecodedBytes, // Limits.max_decoded_bytes
+    Entries,      // Limits.max_entries
+    Depth,        // Limits.max_depth
+    MimeDepth,    // Nesting depth of MIME multiparts, see MimePart::parse
+}
+
+impl fmt::Display for Limit {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            Limit::StreamSize => "stream size",
+            Limit::DecodedBytes => "decoded size",
+            Limit::Entries => "directory entry count",
+            Limit::Depth => "embedded message depth",
+            Limit::MimeDepth => "MIME part depth",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        super::{decode::DataType, error::Error, options::ParserOptions, outlook::Outlook},
+        decoded_len, Limit, Limits, DEFAULT_MAX_DEPTH,
+    };
+    use crate::ole::{writer::ROOT, Writer};
+
+    fn limit_exceeded(limits: Limits) -> Option<(Limit, usize)> {
+        let options = ParserOptions::new().limits(limits);
+        match Outlook::from_path_with("data/attachment.msg", &options) {
+            Err(err) => match err.root() {
+                Error::LimitExceeded { limit, max } => Some((*limit, *max)),
+                _ => panic!("unexpected error {}", err),
+            },
+            Ok(_) => None,
+        }
+    }
+
+    // A message with an attachment embedding a message, and
+    // so on depth times.
+    fn nested_msg(depth: usize) -> Vec<u8> {
+        let utf16 = |text: &str| text.encode_utf16().flat_map(u16::to_le_bytes).collect();
+        let mut writer = Writer::new();
+        let mut storage = ROOT;
+        for _ in 0..depth {
+            let attachment = writer.add_storage(storage, "__attach_version1.0_#00000000");
+            writer.add_stream(attachment, "__substg1.0_3001001F", utf16("Nested"));
+            storage = writer.add_storage(attachment, "__substg1.0_3701000D");
+        }
+        writer.add_stream(storage, "__substg1.0_0037001F", utf16("Innermost"));
+        writer.to_bytes()
+    }
+
+    #[test]
+    fn test_depth() {
+        let msg = nested_msg(5);
+        let mut outlook = Outlook::from_slice(&msg).unwrap();
+        for _ in 0..5 {
+            outlook = *outlook.attachments.remove(0).embedded.unwrap();
+        }
+        assert_eq!(outlook.subject, "Innermost".to_string());
+
+        let options = ParserOptions::new().limits(Limits::new().max_depth(5));
+        assert!(Outlook::from_slice_with(&msg, &options).is_ok());
+        let options = ParserOptions::new().limits(Limits::new().max_depth(3));
+        let err = Outlook::from_slice_with(&msg, &options).unwrap_err();
+        assert!(matches!(
+            err.root(),
+            Error::LimitExceeded {
+                limit: Limit::Depth,
+                max: 3
+            }
+        ));
+        // Located by the storage of the embedded message
+        // nested too deep.
+        let path = err.context().and_then(|context| context.path.clone()).unwrap();
+        assert_eq!(path.matches("__substg1.0_3701000D").count(), 4);
+
+        // Deeper than the default, only the depth limit
+        // stops the recursion.
+        let msg = nested_msg(DEFAULT_MAX_DEPTH + 1);
+        assert!(Outlook::from_slice(&msg).is_err());
+        let options = ParserOptions::new().embedded_messages(false);
+        assert!(Outlook::from_slice_with(&msg, &options).is_ok());
+    }
+
+    #[test]
+    fn test_decoded_len() {
+        assert_eq!(decoded_len(&DataType::PtypString("abc".to_string())), 3);
+        assert_eq!(decoded_len(&DataType::PtypMultipleBinary(vec![vec![0; 2], vec![0; 3]])), 5);
+        assert_eq!(decoded_len(&DataType::PtypInteger32(1)), 4);
+    }
+
+    #[test]
+    fn test_limits() {
+        assert_eq!(limit_exceeded(Limits::new()), None);
+        assert_eq!(
+            limit_exceeded(Limits::new().max_stream_size(10_000)),
+            Some((Limit::StreamSize, 10_000))
+        );
+        assert_eq!(
+            limit_exceeded(Limits::new().max_decoded_bytes(10_000)),
+            Some((Limit::DecodedBytes, 10_000))
+        );
+        assert_eq!(limit_exceeded(Limits::new().max_entries(8)), Some((Limit::Entries, 8)));
+        assert_eq!(
+            limit_exceeded(
+                Limits::new()
+                    .max_stream_size(1 << 20)
+                    .max_decoded_bytes(1 << 20)
+                    .max_entries(1024)
+            ),
+            None
+        );
+
+        // Streams the options skip are never read.
+        let options = ParserOptions::new()
+            .attachments(false)
+            .limits(Limits::new().max_stream_size(10_000));
+        assert!(Outlook::from_path_with("data/attachment.msg", &options).is_ok());
+
+        // The stream going over the limit is named.
+        let options = ParserOptions::new().limits(Limits::new().max_stream_size(10_000));
+        let err = Outlook::from_path_with("data/attachment.msg", &options).unwrap_err();
+        assert!(err
+            .to_string()
+            .starts_with("Limit exceeded: stream size over 10000 (in __attach_version1.0_#"));
+
+        let err = Error::LimitExceeded {
+            limit: Limit::Entries,
+            max: 8,
+        };
+        assert_eq!(err.to_string(), "Limit exceeded: directory entry count over 8".to_string());
+    }
+}
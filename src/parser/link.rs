@@ -0,0 +1,255 @@
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+
+use super::{
+    decode::DataType,
+    eml::html_of,
+    html::{anchors, html_to_text},
+    rtf::decompress,
+    storage::Properties,
+};
+
+// Schemes of the URLs looked for, hosts starting with
+// "www." being taken for http ones.
+const SCHEMES: [&str; 3] = ["https://", "http://", "ftp://"];
+
+// Characters ending a URL written in text, besides white
+// space, as RFC 3986 Appendix C suggests delimiting them.
+const DELIMITERS: &str = "<>\"'`{}|\\^";
+
+// Punctuation ending a sentence rather than the URL it
+// follows.
+pub(crate) const TRAILING: &[char] = &['.', ',', ';', ':', '!', '?', ')', ']'];
+
+// Hosts of the XML namespaces and document types the HTML
+// encapsulated in RTF declares, which no one follows.
+pub(crate) const MARKUP_HOSTS: [&str; 3] = ["schemas.microsoft.com", "schemas.openxmlformats.org", "www.w3.org"];
+
+// LinkSource tells where a link was found.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub enum LinkSource {
+    Body,    // "Body"
+    Html,    // "Html"
+    Rtf,     // "RtfCompressed"
+    Headers, // "TransportMessageHeaders", e.g. "List-Unsubscribe"
+}
+
+// Link is a URL a message holds, found once whatever the
+// number of times and places it appears.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct Link {
+    pub url: String,              // Scheme and host lowercased, see normalize_url
+    pub sources: Vec<LinkSource>, // See LinkSource enum
+    pub texts: Vec<String>,       // Text of the HTML anchors linking to it
+    pub mismatched: bool,         // An anchor shows a URL of another host than it links to
+}
+
+// Normalizes a URL: the scheme and host are lowercased,
+// default ports dropped, an empty path written "/" and
+// "www." hosts given the http scheme. Punctuation around
+// a URL written in text is dropped. None when it is not an
+// http, https or ftp one.
+pub(crate) fn normalize_url(url: &str) -> Option<String> {
+    let url = url.trim_start_matches(['(', '[']).trim_end_matches(TRAILING);
+    let lower = url.to_ascii_lowercase();
+    let (scheme, rest) = match SCHEMES.iter().find(|scheme| lower.starts_with(*scheme)) {
+        Some(scheme) => (&scheme[..scheme.len() - 3], &url[scheme.len()..]),
+        None if lower.starts_with("www.") => ("http", url),
+        None => return None,
+    };
+    let end = rest.find(['/', '?', '#']).unwrap_or(rest.len());
+    let authority = rest[..end].to_ascii_lowercase();
+    let (user, host) = match authority.rsplit_once('@') {
+        Some((user, host)) => (format!("{}@", user), host),
+        None => (String::new(), authority.as_str()),
+    };
+    let default_port = match scheme {
+        "https" => ":443",
+        "http" => ":80",
+        _ => ":21",
+    };
+    let host = host.strip_suffix(default_port).unwrap_or(host).trim_end_matches('.');
+    if host.is_empty() || host.starts_with(':') {
+        return None;
+    }
+    let path = match &rest[end..] {
+        "" => "/",
+        path => path,
+    };
+    Some(format!("{}://{}{}{}", scheme, user, host, path))
+}
+
+// Host of a normalized URL.
+pub(crate) fn host_of(url: &str) -> &str {
+    let rest = url.split_once("://").map_or(url, |(_, rest)| rest);
+    let authority = rest.split(['/', '?', '#']).next().unwrap_or_default();
+    let host = authority.rsplit('@').next().unwrap_or_default();
+    host.split(':').next().unwrap_or_default()
+}
+
+// URLs written in text, normalized, in order.
+pub(crate) fn find_urls(text: &str) -> Vec<String> {
+    text.split(|c: char| c.is_whitespace() || DELIMITERS.contains(c))
+        .filter_map(|word| {
+            let lower = word.to_ascii_lowercase();
+            let start = SCHEMES
+                .iter()
+                .filter_map(|scheme| lower.find(scheme))
+                .min()
+                .or_else(|| lower.find("www.").filter(|start| *start == 0 || !lower[..*start].ends_with('.')))?;
+            normalize_url(&word[start..])
+        })
+        .collect()
+}
+
+// Links gathers the links of a message, in the order they
+// are first found.
+#[derive(Default)]
+struct Links {
+    links: Vec<Link>,
+    indexes: HashMap<String, usize>, // URL to index of its link
+}
+
+impl Links {
+    fn add(&mut self, url: String, source: LinkSource) -> &mut Link {
+        let index = match self.indexes.get(&url) {
+            Some(index) => *index,
+            None => {
+                let index = self.links.len();
+                self.indexes.insert(url.clone(), index);
+                self.links.push(Link {
+                    url,
+                    sources: vec![],
+                    texts: vec![],
+                    mismatched: false,
+                });
+                index
+            }
+        };
+        let link = &mut self.links[index];
+        if !link.sources.contains(&source) {
+            link.sources.push(source);
+        }
+        link
+    }
+
+    fn add_text(&mut self, text: &str, source: LinkSource) {
+        for url in find_urls(text) {
+            self.add(url, source);
+        }
+    }
+}
+
+impl Link {
+    // Links of the bodies and transport headers of the
+    // message.
+    pub fn create_from_props(props: &Properties) -> Vec<Self> {
+        let mut links = Links::default();
+        let string = |key: &str| props.get(key).map_or(String::new(), String::from);
+
+        links.add_text(&string("Body"), LinkSource::Body);
+
+        let html = html_of(props);
+        for (href, text) in anchors(&html) {
+            let url = match normalize_url(&href) {
+                Some(url) => url,
+                None => continue,
+            };
+            let shown = find_urls(&text).into_iter().next();
+            let link = links.add(url, LinkSource::Html);
+            if let Some(shown) = shown {
+                link.mismatched |= host_of(&shown) != host_of(&link.url);
+            }
+            let text = text.trim();
+            if !text.is_empty() && !link.texts.iter().any(|known| known == text) {
+                link.texts.push(text.to_string());
+            }
+        }
+        links.add_text(&html_to_text(&html), LinkSource::Html);
+
+        if let Some(DataType::PtypBinary(bytes)) = props.get("RtfCompressed") {
+            if let Some(rtf) = decompress(bytes) {
+                let rtf: String = rtf.iter().map(|byte| char::from(*byte)).collect();
+                for url in find_urls(&rtf).into_iter().filter(|url| !MARKUP_HOSTS.contains(&host_of(url))) {
+                    links.add(url, LinkSource::Rtf);
+                }
+            }
+        }
+
+        links.add_text(&string("TransportMessageHeaders"), LinkSource::Headers);
+        links.links
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        super::{builder::OutlookBuilder, outlook::Outlook},
+        find_urls, normalize_url, Link, LinkSource,
+    };
+
+    #[test]
+    fn test_normalize_url() {
+        assert_eq!(normalize_url("HTTPS://Example.COM"), Some("https://example.com/".to_string()));
+        assert_eq!(
+            normalize_url("http://Example.com:80/Path?q=A#top"),
+            Some("http://example.com/Path?q=A#top".to_string())
+        );
+        assert_eq!(normalize_url("(www.example.com/a)."), Some("http://www.example.com/a".to_string()));
+        assert_eq!(normalize_url("https://user@example.com:8443"), Some("https://user@example.com:8443/".to_string()));
+        assert_eq!(normalize_url("mailto:someone@example.com"), None);
+        assert_eq!(normalize_url("https:///path"), None);
+    }
+
+    #[test]
+    fn test_find_urls() {
+        assert_eq!(
+            find_urls("See https://example.com/a, or <http://other.test/b>.\r\nAlso www.third.test!"),
+            vec!["https://example.com/a", "http://other.test/b", "http://www.third.test/"]
+        );
+        assert_eq!(find_urls("{HYPERLINK \"https://example.com/\"}\\par"), vec!["https://example.com/"]);
+        assert!(find_urls("no links, just a.www.b and words").is_empty());
+    }
+
+    #[test]
+    fn test_links() {
+        let outlook = OutlookBuilder::new()
+            .body("Report at https://example.com/q3 and https://example.com/q3.")
+            .html(
+                "<p><a href=\"https://evil.test/login\">https://bank.com/login</a> \
+                 <a href=\"HTTPS://Example.com/q3\">Q3</a> <a href=\"mailto:a@example.com\">mail</a></p>",
+            )
+            .transport_headers("List-Unsubscribe: <https://lists.example.com/unsubscribe?id=1>\r\n")
+            .build()
+            .unwrap();
+        let links = &outlook.links;
+        let urls: Vec<&str> = links.iter().map(|link| link.url.as_str()).collect();
+        assert_eq!(
+            urls,
+            vec![
+                "https://example.com/q3",
+                "https://evil.test/login",
+                "https://bank.com/login",
+                "https://lists.example.com/unsubscribe?id=1",
+            ]
+        );
+        assert_eq!(links[0].sources, vec![LinkSource::Body, LinkSource::Html]);
+        assert_eq!(links[0].texts, vec!["Q3"]);
+        assert!(!links[0].mismatched);
+        assert!(links[1].mismatched);
+        assert_eq!(links[3].sources, vec![LinkSource::Headers]);
+    }
+
+    #[test]
+    fn test_rtf_links() {
+        let outlook = Outlook::from_path("data/attachment.msg").unwrap();
+        assert_eq!(outlook.links.len(), 1);
+        assert_eq!(outlook.links[0].url, "https://www.docsign-online.com/pixel_b75cd8c513.gif");
+        assert_eq!(outlook.links[0].sources, vec![LinkSource::Body, LinkSource::Rtf]);
+        assert!(outlook.rtf_body().contains("http://www.w3.org/TR/REC-html40"));
+        assert_eq!(Link::create_from_props(&outlook.properties.root), outlook.links);
+    }
+}
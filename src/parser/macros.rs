@@ -0,0 +1,178 @@
+use std::convert::TryInto;
+
+use crate::ole::EntryType;
+
+use super::limits::Limits;
+
+// Signatures of compound files and of zip archives, which
+// OOXML documents are.
+// Refer to MS-CFB 2.2 and APPNOTE.TXT 4.3.16
+const OLE_SIGNATURE: [u8; 8] = [0xD0, 0xCF, 0x11, 0xE0, 0xA1, 0xB1, 0x1A, 0xE1];
+const ZIP_SIGNATURE: [u8; 4] = [0x50, 0x4B, 0x03, 0x04];
+
+// Storages holding a VBA project, "Macros" in Word
+// documents and "VBA" in Excel ones, under "_VBA_PROJECT_CUR".
+// Refer to MS-OVBA 2.2.1
+const VBA_STORAGES: [&str; 2] = ["VBA", "Macros"];
+
+// Part holding the VBA project of an OOXML document, e.g.
+// "word/vbaProject.bin".
+const VBA_PART: &str = "vbaproject.bin";
+
+// End of central directory record and central directory
+// file header of a zip archive.
+// Refer to APPNOTE.TXT 4.3.12 and 4.3.16
+const END_SIGNATURE: u32 = 0x0605_4B50;
+const END_SIZE: usize = 22;
+const MAX_COMMENT_SIZE: usize = 0xFFFF;
+const HEADER_SIGNATURE: u32 = 0x0201_4B50;
+const HEADER_SIZE: usize = 46;
+
+// Offsets are read from the file, and may be anything,
+// so they are added with checks.
+fn u16_at(bytes: &[u8], offset: usize) -> Option<usize> {
+    Some(u16::from_le_bytes(bytes.get(offset..offset.checked_add(2)?)?.try_into().ok()?) as usize)
+}
+
+fn u32_at(bytes: &[u8], offset: usize) -> Option<u32> {
+    Some(u32::from_le_bytes(bytes.get(offset..offset.checked_add(4)?)?.try_into().ok()?))
+}
+
+// Names of the entries of a zip archive, from its central
+// directory. None when it cannot be found.
+fn zip_names(bytes: &[u8]) -> Option<Vec<&[u8]>> {
+    let first = bytes.len().checked_sub(END_SIZE)?;
+    let last = first.saturating_sub(MAX_COMMENT_SIZE);
+    let end = (last..=first).rev().find(|offset| u32_at(bytes, *offset) == Some(END_SIGNATURE))?;
+    let count = u16_at(bytes, end + 10)?;
+    let mut offset = u32_at(bytes, end + 16)? as usize;
+    let mut names = vec![];
+    for _ in 0..count {
+        if u32_at(bytes, offset)? != HEADER_SIGNATURE {
+            break;
+        }
+        let name_size = u16_at(bytes, offset.checked_add(28)?)?;
+        let extra_size = u16_at(bytes, offset.checked_add(30)?)?;
+        let comment_size = u16_at(bytes, offset.checked_add(32)?)?;
+        let name = offset.checked_add(HEADER_SIZE)?;
+        names.push(bytes.get(name..name.checked_add(name_size)?)?);
+        offset = name.checked_add(name_size)?.checked_add(extra_size)?.checked_add(comment_size)?;
+    }
+    Some(names)
+}
+
+// Whether a file holds VBA macros: a compound file, e.g. a
+// .doc or .xls, with a VBA storage, or an OOXML document,
+// e.g. a .docm or .xlsm, with a vbaProject.bin part.
+// Compound files are read within the limits of the message
+// attaching them.
+pub(crate) fn has_macros(bytes: &[u8], limits: &Limits) -> bool {
+    if bytes.starts_with(&OLE_SIGNATURE) {
+        return match limits.slice_reader(bytes) {
+            Ok(reader) => reader.iterate().any(|entry| {
+                entry._type() == EntryType::UserStorage
+                    && VBA_STORAGES.iter().any(|name| entry.name().eq_ignore_ascii_case(name))
+            }),
+            Err(_) => false,
+        };
+    }
+    if bytes.starts_with(&ZIP_SIGNATURE) {
+        return zip_names(bytes).unwrap_or_default().iter().any(|name| {
+            let name = String::from_utf8_lossy(name).to_ascii_lowercase();
+            name.rsplit('/').next() == Some(VBA_PART)
+        });
+    }
+    false
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        super::{builder::OutlookBuilder, limits::Limits, outlook::Outlook},
+        has_macros, u16_at, u32_at, zip_names,
+    };
+    use crate::ole::{Writer, ROOT};
+
+    // Zip archive of empty stored entries.
+    fn zip(names: &[&str]) -> Vec<u8> {
+        let mut bytes = vec![];
+        let mut directory = vec![];
+        for name in names {
+            let offset = bytes.len() as u32;
+            bytes.extend_from_slice(&[0x50, 0x4B, 0x03, 0x04]);
+            bytes.extend_from_slice(&[0; 22]);
+            bytes.extend_from_slice(&(name.len() as u16).to_le_bytes());
+            bytes.extend_from_slice(&[0; 2]);
+            bytes.extend_from_slice(name.as_bytes());
+
+            directory.extend_from_slice(&[0x50, 0x4B, 0x01, 0x02]);
+            directory.extend_from_slice(&[0; 24]);
+            directory.extend_from_slice(&(name.len() as u16).to_le_bytes());
+            directory.extend_from_slice(&[0; 12]);
+            directory.extend_from_slice(&offset.to_le_bytes());
+            directory.extend_from_slice(name.as_bytes());
+        }
+        let offset = bytes.len() as u32;
+        bytes.extend_from_slice(&directory);
+        bytes.extend_from_slice(&[0x50, 0x4B, 0x05, 0x06, 0, 0, 0, 0]);
+        bytes.extend_from_slice(&(names.len() as u16).to_le_bytes());
+        bytes.extend_from_slice(&(names.len() as u16).to_le_bytes());
+        bytes.extend_from_slice(&(directory.len() as u32).to_le_bytes());
+        bytes.extend_from_slice(&offset.to_le_bytes());
+        bytes.extend_from_slice(&[0; 2]);
+        bytes
+    }
+
+    #[test]
+    fn test_has_macros() {
+        let docx = zip(&["[Content_Types].xml", "word/document.xml"]);
+        let docm = zip(&["[Content_Types].xml", "word/document.xml", "word/vbaProject.bin"]);
+        assert_eq!(zip_names(&docx).unwrap(), vec![&b"[Content_Types].xml"[..], &b"word/document.xml"[..]]);
+        assert!(!has_macros(&docx, &Limits::default()));
+        assert!(has_macros(&docm, &Limits::default()));
+
+        let mut writer = Writer::new();
+        writer.add_stream(ROOT, "WordDocument", vec![0; 16]);
+        assert!(!has_macros(&writer.to_bytes(), &Limits::default()));
+        let macros = writer.add_storage(ROOT, "Macros");
+        let vba = writer.add_storage(macros, "VBA");
+        writer.add_stream(vba, "dir", vec![0; 16]);
+        assert!(has_macros(&writer.to_bytes(), &Limits::default()));
+        assert!(!has_macros(&writer.to_bytes(), &Limits::new().max_entries(2)));
+
+        assert!(!has_macros(b"%PDF-1.7", &Limits::default()));
+        assert!(!has_macros(&docm[..8], &Limits::default()));
+
+        let outlook = OutlookBuilder::new()
+            .attachment("report.docx", "application/vnd.openxmlformats-officedocument.wordprocessingml.document", docx)
+            .attachment("report.docm", "application/vnd.ms-word.document.macroEnabled.12", docm)
+            .build()
+            .unwrap();
+        let flags: Vec<bool> = outlook.attachments.iter().map(|attachment| attachment.has_macros).collect();
+        assert_eq!(flags, vec![false, true]);
+
+        let outlook = Outlook::from_path("data/attachment.msg").unwrap();
+        assert!(outlook.attachments.iter().all(|attachment| !attachment.has_macros));
+    }
+
+    #[test]
+    fn test_zip_overflow() {
+        let docm = zip(&["word/vbaProject.bin"]);
+        assert_eq!(u16_at(&docm, usize::MAX), None);
+        assert_eq!(u32_at(&docm, usize::MAX - 1), None);
+
+        // Central directory past the end of the archive.
+        let mut bytes = docm.clone();
+        let offset = bytes.len() - 6;
+        bytes[offset..offset + 4].copy_from_slice(&u32::MAX.to_le_bytes());
+        assert_eq!(zip_names(&bytes), None);
+        assert!(!has_macros(&bytes, &Limits::default()));
+
+        // Name running past the end of the archive.
+        let mut bytes = docm;
+        let directory = bytes.len() - 22 - 46 - "word/vbaProject.bin".len();
+        bytes[directory + 28..directory + 30].copy_from_slice(&u16::MAX.to_le_bytes());
+        assert_eq!(zip_names(&bytes), None);
+        assert!(!has_macros(&bytes, &Limits::default()));
+    }
+}
@@ -0,0 +1,191 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use super::{
+    clock::{Clock, SystemClock},
+    decode::DataType,
+    error::Error,
+    followup::FlagStatus,
+    outlook::Outlook,
+};
+
+// Last action taken on the message.
+// Refer to MS-OXOMSG 2.2.1.56
+const NOTEIVERB_REPLYTOSENDER: i64 = 102;
+const NOTEIVERB_REPLYTOALL: i64 = 103;
+const NOTEIVERB_FORWARD: i64 = 104;
+
+// Host name used when the system one is unknown.
+const DEFAULT_HOSTNAME: &str = "localhost";
+
+// Name of the host the messages are written on, with
+// the characters file names cannot hold escaped.
+fn system_hostname() -> String {
+    let hostname = fs::read_to_string("/proc/sys/kernel/hostname")
+        .ok()
+        .or_else(|| std::env::var("HOSTNAME").ok())
+        .or_else(|| std::env::var("COMPUTERNAME").ok())
+        .map(|hostname| hostname.trim().to_string())
+        .filter(|hostname| !hostname.is_empty())
+        .unwrap_or_else(|| DEFAULT_HOSTNAME.to_string());
+    escape_hostname(&hostname)
+}
+
+fn escape_hostname(hostname: &str) -> String {
+    hostname.replace('/', "\\057").replace(':', "\\072")
+}
+
+// Maildir flags of the message, in ASCII order: "D" for
+// drafts, "F" for flagged, "P" for forwarded, "R" for
+// replied and "S" for read ones.
+fn maildir_flags(outlook: &Outlook) -> String {
+    let last_verb = outlook
+        .properties
+        .root
+        .get("LastVerbExecuted")
+        .and_then(DataType::as_i64);
+    let flagged = outlook
+        .follow_up
+        .as_ref()
+        .is_some_and(|flag| flag.status == FlagStatus::Flagged);
+    [
+        ('D', outlook.flags.unsent),
+        ('F', flagged),
+        ('P', last_verb == Some(NOTEIVERB_FORWARD)),
+        (
+            'R',
+            last_verb == Some(NOTEIVERB_REPLYTOSENDER) || last_verb == Some(NOTEIVERB_REPLYTOALL),
+        ),
+        ('S', outlook.flags.read),
+    ]
+    .iter()
+    .filter(|(_, set)| *set)
+    .map(|(flag, _)| *flag)
+    .collect()
+}
+
+// MaildirWriter writes each message, as RFC 5322 text, to
+// a file of its own in a Maildir. Files are written to
+// "tmp" first and then moved to "new" for messages without
+// any flag, or to "cur" with a ":2,<flags>" suffix.
+// Refer to https://cr.yp.to/proto/maildir.html
+#[derive(Debug, Clone)]
+pub struct MaildirWriter {
+    path: PathBuf,    // Root of the Maildir
+    hostname: String, // Last part of the file names
+    count: usize,     // Messages written so far, making names unique
+}
+
+impl MaildirWriter {
+    // Opens the Maildir at path, creating it and its
+    // "tmp", "new" and "cur" folders if need be.
+    pub fn create<P: AsRef<Path>>(path: P) -> Result<Self, Error> {
+        let path = path.as_ref().to_path_buf();
+        for folder in ["tmp", "new", "cur"] {
+            fs::create_dir_all(path.join(folder))?;
+        }
+        Ok(Self {
+            path,
+            hostname: system_hostname(),
+            count: 0,
+        })
+    }
+
+    pub fn hostname(mut self, hostname: &str) -> Self {
+        self.hostname = escape_hostname(hostname);
+        self
+    }
+
+    pub fn count(&self) -> usize {
+        self.count
+    }
+
+    // Writes the message and returns the path of its file.
+    pub fn write(&mut self, outlook: &Outlook) -> Result<PathBuf, Error> {
+        self.write_with_clock(outlook, &SystemClock)
+    }
+
+    // Same as write, the current time, used in the file
+    // name and as the date of messages without any, being
+    // taken from clock.
+    pub fn write_with_clock(&mut self, outlook: &Outlook, clock: &dyn Clock) -> Result<PathBuf, Error> {
+        let flags = maildir_flags(outlook);
+        self.write_eml(&outlook.to_eml_with_clock(clock), &flags, clock)
+    }
+
+    // Writes a message already in RFC 5322 form with the
+    // given Maildir flags.
+    pub fn write_eml(&mut self, eml: &str, flags: &str, clock: &dyn Clock) -> Result<PathBuf, Error> {
+        let now = clock.now();
+        let name = format!(
+            "{}.M{}P{}Q{}.{}",
+            now.timestamp(),
+            now.timestamp_subsec_micros(),
+            std::process::id(),
+            self.count + 1,
+            self.hostname
+        );
+        let tmp = self.path.join("tmp").join(&name);
+        fs::write(&tmp, eml)?;
+        let path = if flags.is_empty() {
+            self.path.join("new").join(&name)
+        } else {
+            self.path.join("cur").join(format!("{}:2,{}", name, flags))
+        };
+        fs::rename(&tmp, &path)?;
+        self.count += 1;
+        Ok(path)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{TimeZone as _, Utc};
+
+    use super::{
+        super::{clock::FixedClock, decode::DataType, outlook::Outlook},
+        escape_hostname, maildir_flags, MaildirWriter,
+    };
+
+    #[test]
+    fn test_maildir_flags() {
+        let outlook = Outlook::from_path("data/test_email.msg").unwrap();
+        assert_eq!(maildir_flags(&outlook), "DS".to_string());
+        let outlook = Outlook::from_path("data/test_email_4.msg").unwrap();
+        assert_eq!(maildir_flags(&outlook), "".to_string());
+        let mut outlook = Outlook::from_path("data/attachment.msg").unwrap();
+        assert_eq!(maildir_flags(&outlook), "S".to_string());
+        outlook
+            .properties
+            .root
+            .insert("LastVerbExecuted".to_string(), DataType::PtypInteger32(103));
+        assert_eq!(maildir_flags(&outlook), "RS".to_string());
+        assert_eq!(escape_hostname("a/b:c"), "a\\057b\\072c".to_string());
+    }
+
+    #[test]
+    fn test_write() {
+        let dir = std::env::temp_dir().join(format!("maildir-{}", std::process::id()));
+        let clock = FixedClock(Utc.with_ymd_and_hms(2024, 1, 2, 3, 4, 5).unwrap());
+        let mut maildir = MaildirWriter::create(&dir).unwrap().hostname("mail.example.com");
+        let outlook = Outlook::from_path("data/attachment.msg").unwrap();
+        let read = maildir.write_with_clock(&outlook, &clock).unwrap();
+        let unread = Outlook::from_path("data/test_email_4.msg").unwrap();
+        let unread = maildir.write_with_clock(&unread, &clock).unwrap();
+        assert_eq!(maildir.count(), 2);
+
+        let name = |path: &std::path::Path| path.file_name().unwrap().to_string_lossy().to_string();
+        assert_eq!(
+            name(&read),
+            format!("1704164645.M0P{}Q1.mail.example.com:2,S", std::process::id())
+        );
+        assert!(read.starts_with(dir.join("cur")));
+        assert!(unread.starts_with(dir.join("new")));
+        assert_ne!(name(&read), name(&unread));
+        assert_eq!(std::fs::read_dir(dir.join("tmp")).unwrap().count(), 0);
+        assert_eq!(std::fs::read_to_string(&read).unwrap(), outlook.to_eml_with_clock(&clock));
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
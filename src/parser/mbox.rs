@@ -0,0 +1,154 @@
+use std::{
+    fs::{File, OpenOptions},
+    io::{BufWriter, Write},
+    path::Path,
+};
+
+use chrono::{DateTime, Utc};
+
+use super::{
+    clock::{Clock, SystemClock},
+    error::Error,
+    outlook::Outlook,
+};
+
+// Envelope sender of messages without a usable address.
+const UNKNOWN_SENDER: &str = "MAILER-DAEMON";
+
+// MboxWriter appends messages, as RFC 5322 text, to an
+// mbox file in the "mboxrd" flavour Thunderbird and most
+// importers read. Each message follows a "From " line, and
+// lines starting with "From ", after any number of ">",
+// are quoted with one more ">".
+// Refer to RFC 4155
+pub struct MboxWriter<W: Write> {
+    writer: W,
+    count: usize, // Messages appended so far
+}
+
+impl MboxWriter<BufWriter<File>> {
+    // Opens path for appending, creating the file if it
+    // does not exist yet.
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Self, Error> {
+        let file = OpenOptions::new().create(true).append(true).open(path)?;
+        Ok(Self::new(BufWriter::new(file)))
+    }
+}
+
+impl<W: Write> MboxWriter<W> {
+    pub fn new(writer: W) -> Self {
+        Self { writer, count: 0 }
+    }
+
+    pub fn count(&self) -> usize {
+        self.count
+    }
+
+    pub fn append(&mut self, outlook: &Outlook) -> Result<(), Error> {
+        self.append_with_clock(outlook, &SystemClock)
+    }
+
+    // Same as append, the date of messages without any
+    // being taken from clock.
+    pub fn append_with_clock(&mut self, outlook: &Outlook, clock: &dyn Clock) -> Result<(), Error> {
+        let sender = [&outlook.sender.email, &outlook.sent_representing.email]
+            .iter()
+            .find(|email| email.contains('@') && !email.contains(char::is_whitespace))
+            .map_or(UNKNOWN_SENDER, |email| email.as_str());
+        self.append_eml(sender, outlook.date(clock), &outlook.to_eml_with_clock(clock))
+    }
+
+    // Appends a message already in RFC 5322 form. Line
+    // endings are written as LF.
+    pub fn append_eml(&mut self, sender: &str, date: DateTime<Utc>, eml: &str) -> Result<(), Error> {
+        let sender = if sender.is_empty() { UNKNOWN_SENDER } else { sender };
+        writeln!(self.writer, "From {} {}", sender, date.format("%a %b %e %H:%M:%S %Y"))?;
+        let eml = eml.strip_suffix('\n').unwrap_or(eml);
+        for line in eml.split('\n') {
+            let line = line.strip_suffix('\r').unwrap_or(line);
+            if line.trim_start_matches('>').starts_with("From ") {
+                self.writer.write_all(b">")?;
+            }
+            writeln!(self.writer, "{}", line)?;
+        }
+        // A blank line ends each message.
+        writeln!(self.writer)?;
+        self.count += 1;
+        Ok(())
+    }
+
+    // Flushes the messages written and gives back the
+    // underlying writer.
+    pub fn finish(mut self) -> Result<W, Error> {
+        self.writer.flush()?;
+        Ok(self.writer)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{TimeZone as _, Utc};
+
+    use super::{
+        super::{builder::OutlookBuilder, clock::FixedClock, outlook::Outlook},
+        MboxWriter,
+    };
+
+    #[test]
+    fn test_append_eml() {
+        let mut mbox = MboxWriter::new(vec![]);
+        let date = Utc.with_ymd_and_hms(2024, 5, 1, 9, 3, 0).unwrap();
+        mbox.append_eml(
+            "alice@example.com",
+            date,
+            "Subject: Hi\r\n\r\nFrom now on,\r\n>From the start\r\nFrom: me\r\n",
+        )
+        .unwrap();
+        mbox.append_eml("", date, "Subject: Again\r\n\r\nBye").unwrap();
+        assert_eq!(mbox.count(), 2);
+        let text = String::from_utf8(mbox.finish().unwrap()).unwrap();
+        assert_eq!(
+            text,
+            "From alice@example.com Wed May  1 09:03:00 2024\n\
+             Subject: Hi\n\
+             \n\
+             >From now on,\n\
+             >>From the start\n\
+             From: me\n\
+             \n\
+             From MAILER-DAEMON Wed May  1 09:03:00 2024\n\
+             Subject: Again\n\
+             \n\
+             Bye\n\
+             \n"
+        );
+    }
+
+    #[test]
+    fn test_append() {
+        let clock = FixedClock(Utc.with_ymd_and_hms(2024, 1, 2, 3, 4, 5).unwrap());
+        let outlook = OutlookBuilder::new()
+            .subject("Batch")
+            .sender("Alice", "alice@example.com")
+            .to("Bob", "bob@example.com")
+            .body("Hello")
+            .build()
+            .unwrap();
+        let other = Outlook::from_path("data/test_email.msg").unwrap();
+        let path = std::env::temp_dir().join(format!("mbox-{}.mbox", std::process::id()));
+        for _ in 0..2 {
+            // Each run appends to what is already there.
+            let mut mbox = MboxWriter::open(&path).unwrap();
+            mbox.append_with_clock(&outlook, &clock).unwrap();
+            mbox.append_with_clock(&other, &clock).unwrap();
+            mbox.finish().unwrap();
+        }
+        let text = std::fs::read_to_string(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        let separators: Vec<&str> = text.lines().filter(|line| line.starts_with("From ")).collect();
+        assert_eq!(separators.len(), 4);
+        assert_eq!(separators[0], "From alice@example.com Tue Jan  2 03:04:05 2024");
+        assert!(!text.contains('\r'));
+        assert_eq!(text.matches("Subject: Batch\n").count(), 2);
+    }
+}
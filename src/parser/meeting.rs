@@ -0,0 +1,172 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use super::{decode::DataType, storage::Properties};
+
+// MeetingKind is the kind of meeting object,
+// as told by its message class.
+// Refer to MS-OXOCAL 2.2.6.1 and 2.2.7.1
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub enum MeetingKind {
+    Request,
+    Accepted,
+    Declined,
+    Tentative,
+    Canceled,
+}
+
+impl MeetingKind {
+    pub fn from_message_class(message_class: &str) -> Option<Self> {
+        let message_class = message_class.to_ascii_lowercase();
+        let kind = message_class.strip_prefix("ipm.schedule.meeting.")?;
+        // Custom forms may append their own suffix.
+        match kind.split('.').take(2).collect::<Vec<&str>>().as_slice() {
+            ["request", ..] => Some(MeetingKind::Request),
+            ["resp", "pos"] => Some(MeetingKind::Accepted),
+            ["resp", "neg"] => Some(MeetingKind::Declined),
+            ["resp", "tent"] => Some(MeetingKind::Tentative),
+            ["canceled", ..] => Some(MeetingKind::Canceled),
+            _ => None,
+        }
+    }
+}
+
+// ResponseStatus is the response of the owner of the
+// calendar object to the meeting.
+// Refer to MS-OXOCAL 2.2.1.11
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub enum ResponseStatus {
+    None,
+    Organized,
+    Tentative,
+    Accepted,
+    Declined,
+    NotResponded,
+}
+
+impl ResponseStatus {
+    pub fn from_value(value: i64) -> Self {
+        match value {
+            1 => ResponseStatus::Organized,
+            2 => ResponseStatus::Tentative,
+            3 => ResponseStatus::Accepted,
+            4 => ResponseStatus::Declined,
+            5 => ResponseStatus::NotResponded,
+            _ => ResponseStatus::None,
+        }
+    }
+}
+
+// Meeting holds what meeting requests, responses and
+// cancellations add on top of an appointment.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct Meeting {
+    pub kind: MeetingKind,                     // "MessageClass"
+    pub response_status: ResponseStatus,       // "ResponseStatus"
+    pub sequence: Option<i64>,                 // "AppointmentSequence"
+    pub proposed_start: Option<DateTime<Utc>>, // "AppointmentProposedStartWhole"
+    pub proposed_end: Option<DateTime<Utc>>,   // "AppointmentProposedEndWhole"
+}
+
+impl Meeting {
+    pub fn create_from_props(props: &Properties) -> Option<Self> {
+        let kind = props
+            .get("MessageClass")
+            .and_then(DataType::as_str)
+            .and_then(MeetingKind::from_message_class)?;
+        // Proposed times are only meaningful on a counter proposal.
+        let counter_proposal = props
+            .get("AppointmentCounterProposal")
+            .and_then(DataType::as_bool)
+            .unwrap_or(false);
+        let proposed = |key: &str| {
+            props
+                .get(key)
+                .and_then(DataType::as_time)
+                .filter(|_| counter_proposal)
+        };
+        Some(Self {
+            kind,
+            response_status: props
+                .get("ResponseStatus")
+                .and_then(DataType::as_i64)
+                .map_or(ResponseStatus::None, ResponseStatus::from_value),
+            sequence: props.get("AppointmentSequence").and_then(DataType::as_i64),
+            proposed_start: proposed("AppointmentProposedStartWhole"),
+            proposed_end: proposed("AppointmentProposedEndWhole"),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{TimeZone, Utc};
+
+    use super::{
+        super::{decode::DataType, storage::Properties},
+        Meeting, MeetingKind, ResponseStatus,
+    };
+
+    #[test]
+    fn test_meeting_kind() {
+        let kind = MeetingKind::from_message_class;
+        assert_eq!(kind("IPM.Schedule.Meeting.Request"), Some(MeetingKind::Request));
+        assert_eq!(kind("IPM.Schedule.Meeting.Resp.Pos"), Some(MeetingKind::Accepted));
+        assert_eq!(kind("ipm.schedule.meeting.resp.neg"), Some(MeetingKind::Declined));
+        assert_eq!(kind("IPM.Schedule.Meeting.Resp.Tent"), Some(MeetingKind::Tentative));
+        assert_eq!(kind("IPM.Schedule.Meeting.Canceled"), Some(MeetingKind::Canceled));
+        assert_eq!(
+            kind("IPM.Schedule.Meeting.Request.Custom"),
+            Some(MeetingKind::Request)
+        );
+        assert_eq!(kind("IPM.Schedule.Meeting.Notification.Forward"), None);
+        assert_eq!(kind("IPM.Appointment"), None);
+        assert_eq!(kind("IPM.Note"), None);
+    }
+
+    #[test]
+    fn test_counter_proposal() {
+        let start = Utc.with_ymd_and_hms(2021, 6, 2, 14, 0, 0).unwrap();
+        let end = Utc.with_ymd_and_hms(2021, 6, 2, 15, 0, 0).unwrap();
+        let mut props = Properties::new();
+        props.insert(
+            "MessageClass".to_string(),
+            DataType::PtypString("IPM.Schedule.Meeting.Resp.Tent".to_string()),
+        );
+        props.insert("ResponseStatus".to_string(), DataType::PtypInteger32(2));
+        props.insert("AppointmentSequence".to_string(), DataType::PtypInteger32(3));
+        props.insert("AppointmentProposedStartWhole".to_string(), DataType::PtypTime(start));
+        props.insert("AppointmentProposedEndWhole".to_string(), DataType::PtypTime(end));
+
+        // Without the counter proposal flag the times are ignored.
+        let meeting = Meeting::create_from_props(&props).unwrap();
+        assert_eq!(meeting.proposed_start, None);
+
+        props.insert("AppointmentCounterProposal".to_string(), DataType::PtypBoolean(true));
+        let meeting = Meeting::create_from_props(&props).unwrap();
+        assert_eq!(
+            meeting,
+            Meeting {
+                kind: MeetingKind::Tentative,
+                response_status: ResponseStatus::Tentative,
+                sequence: Some(3),
+                proposed_start: Some(start),
+                proposed_end: Some(end),
+            }
+        );
+    }
+
+    #[test]
+    fn test_not_a_meeting() {
+        let mut props = Properties::new();
+        props.insert(
+            "MessageClass".to_string(),
+            DataType::PtypString("IPM.Appointment".to_string()),
+        );
+        assert_eq!(Meeting::create_from_props(&props), None);
+        assert_eq!(Meeting::create_from_props(&Properties::new()), None);
+    }
+}
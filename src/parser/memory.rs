@@ -0,0 +1,182 @@
+use std::mem::size_of;
+
+use serde::{Deserialize, Serialize};
+
+use super::{
+    decode::DataType,
+    outlook::Outlook,
+    storage::{Properties, Storages},
+    verbose::MessageProperties,
+};
+use crate::ole;
+
+// Properties accounted as bodies rather than properties.
+const BODIES: [&str; 3] = ["Body", "Html", "RtfCompressed"];
+
+// Property accounted as attachment data.
+const ATTACHMENT_DATA: &str = "AttachDataObject";
+
+// MemoryReport tells how many bytes each phase of parsing
+// a message allocated, measured once parsing completes.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct MemoryReport {
+    pub file: usize,        // File buffered by the OLE reader, 0 unless read from a Read
+    pub directory: usize,   // Allocation tables and directory entries
+    pub properties: usize,  // Decoded properties but bodies and attachment data
+    pub bodies: usize,      // "Body", "Html", "RtfCompressed"
+    pub attachments: usize, // "AttachDataObject"
+    pub peak: usize,        // All of the above plus the Outlook built from them
+}
+
+impl MemoryReport {
+    pub(crate) fn create(parser: &ole::Reader, storages: &Storages, outlook: &Outlook) -> Self {
+        let mut report = Self {
+            file: parser.body_allocated_len(),
+            directory: parser.directory_allocated_len(),
+            ..Self::default()
+        };
+        report.add_storages(storages);
+        // The reader and the storages are only dropped
+        // once the Outlook is built.
+        report.peak = report.total() + Self::outlook_len(outlook);
+        report
+    }
+
+    // Bytes held by the reader and the storages.
+    pub fn total(&self) -> usize {
+        self.file + self.directory + self.properties + self.bodies + self.attachments
+    }
+
+    fn value_len(value: &DataType) -> usize {
+        let heap = match value {
+            DataType::PtypString(string) => string.capacity(),
+            DataType::PtypBinary(bytes) => bytes.capacity(),
+            DataType::PtypMultipleString(strings) => strings
+                .iter()
+                .map(|string| size_of::<String>() + string.capacity())
+                .sum(),
+            DataType::PtypMultipleBinary(values) => values
+                .iter()
+                .map(|bytes| size_of::<Vec<u8>>() + bytes.capacity())
+                .sum(),
+            _ => 0,
+        };
+        size_of::<DataType>() + heap
+    }
+
+    fn add_props(&mut self, props: &Properties) {
+        for (key, value) in props {
+            let entry = size_of::<String>() + key.capacity();
+            let value = Self::value_len(value);
+            if BODIES.contains(&key.as_str()) {
+                self.properties += entry;
+                self.bodies += value;
+            } else if key == ATTACHMENT_DATA {
+                self.properties += entry;
+                self.attachments += value;
+            } else {
+                self.properties += entry + value;
+            }
+        }
+    }
+
+    fn add_storages(&mut self, storages: &Storages) {
+        self.add_props(&storages.root);
+        for props in storages.recipients.iter().chain(storages.attachments.iter()) {
+            self.add_props(props);
+        }
+    }
+
+    // Bytes held by the Outlook on top of its own size:
+    // the copy of the properties, attachment data aside,
+    // the bodies and the hex encoded attachments.
+    fn outlook_len(outlook: &Outlook) -> usize {
+        let MessageProperties {
+            root,
+            recipients,
+            attachments,
+            ..
+        } = &outlook.properties;
+        let mut copy = Self::default();
+        copy.add_props(root);
+        for props in recipients.iter().chain(attachments.iter()) {
+            copy.add_props(props);
+        }
+        let attachments: usize = outlook
+            .attachments
+            .iter()
+            .map(|attachment| attachment.payload.capacity())
+            .sum();
+        size_of::<Outlook>()
+            + copy.total()
+            + outlook.subject.capacity()
+            + outlook.body.capacity()
+            + outlook.rtf_compressed.capacity()
+            + attachments
+    }
+}
+
+impl Outlook {
+    pub fn memory_report(&self) -> &MemoryReport {
+        &self.memory
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::mem::size_of;
+
+    use super::{
+        super::{decode::DataType, storage::Properties},
+        MemoryReport, Outlook,
+    };
+
+    #[test]
+    fn test_add_props() {
+        let mut props = Properties::new();
+        props.insert("Body".to_string(), DataType::PtypString("Hello".to_string()));
+        props.insert("AttachDataObject".to_string(), DataType::PtypBinary(vec![0; 16]));
+        props.insert("Importance".to_string(), DataType::PtypInteger32(1));
+
+        let mut report = MemoryReport::default();
+        report.add_props(&props);
+        assert_eq!(report.bodies, size_of::<DataType>() + 5);
+        assert_eq!(report.attachments, size_of::<DataType>() + 16);
+        assert_eq!(
+            report.properties,
+            3 * size_of::<String>() + "BodyAttachDataObjectImportance".len() + size_of::<DataType>()
+        );
+        assert_eq!(report.total(), report.properties + report.bodies + report.attachments);
+    }
+
+    #[test]
+    fn test_memory_report() {
+        let outlook = Outlook::from_path("data/attachment.msg").unwrap();
+        let report = outlook.memory_report();
+        // The reader buffers everything past the 512 bytes header.
+        let len = std::fs::metadata("data/attachment.msg").unwrap().len() as usize;
+        assert!(report.file >= len - 512);
+        assert!(report.directory > 0);
+        assert!(report.properties > 0);
+        assert!(report.bodies >= outlook.body.len());
+        // Attachment data is hex encoded in the Outlook.
+        let payload: usize = outlook.attachments.iter().map(|a| a.payload.len()).sum();
+        assert!(report.attachments >= payload / 2);
+        assert!(report.peak > report.total());
+
+        // A slice is borrowed by the reader, not buffered.
+        let outlook = Outlook::from_slice(&std::fs::read("data/attachment.msg").unwrap()).unwrap();
+        assert_eq!(outlook.memory_report().file, 0);
+        assert_eq!(outlook.memory_report().peak, report.peak - report.file);
+    }
+    #[cfg(feature = "mmap")]
+    #[test]
+    fn test_memory_report_mmap() {
+        let outlook = Outlook::from_mmap("data/attachment.msg").unwrap();
+        let report = outlook.memory_report();
+        let buffered = *Outlook::from_path("data/attachment.msg").unwrap().memory_report();
+        assert_eq!(report.file, 0);
+        assert_eq!(report.directory, buffered.directory);
+        assert_eq!(report.peak, buffered.peak - buffered.file);
+    }
+}
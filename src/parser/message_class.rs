@@ -0,0 +1,130 @@
+use serde::{Deserialize, Serialize};
+
+use super::{
+    appointment::Appointment,
+    contact::Contact,
+    distlist::DistList,
+    meeting::MeetingKind,
+    note::StickyNote,
+    outlook::Outlook,
+    report::{NonDeliveryReport, ReceiptKind},
+    task::{Task, TaskRequestKind},
+};
+
+// MessageClass is the kind of item a message is,
+// as told by its message class.
+// Refer to MS-OXCMSG 2.2.1.3
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum MessageClass {
+    Note,
+    Appointment,
+    MeetingRequest,
+    MeetingResponse,
+    MeetingCancellation,
+    Contact,
+    DistList,
+    Task,
+    TaskRequest,
+    StickyNote,
+    NonDeliveryReport,
+    ReadReceipt,
+    NotReadReceipt,
+    DeliveryReceipt,
+    Unknown,
+}
+
+impl MessageClass {
+    // Custom forms derive their class from a standard one,
+    // e.g. IPM.Note.Custom is a note.
+    pub fn from_message_class(message_class: &str) -> Self {
+        if NonDeliveryReport::is_ndr_class(message_class) {
+            return MessageClass::NonDeliveryReport;
+        }
+        if let Some(kind) = ReceiptKind::from_message_class(message_class) {
+            return match kind {
+                ReceiptKind::Read => MessageClass::ReadReceipt,
+                ReceiptKind::NotRead => MessageClass::NotReadReceipt,
+                ReceiptKind::Delivery => MessageClass::DeliveryReceipt,
+            };
+        }
+        if let Some(kind) = MeetingKind::from_message_class(message_class) {
+            return match kind {
+                MeetingKind::Request => MessageClass::MeetingRequest,
+                MeetingKind::Accepted | MeetingKind::Declined | MeetingKind::Tentative => {
+                    MessageClass::MeetingResponse
+                }
+                MeetingKind::Canceled => MessageClass::MeetingCancellation,
+            };
+        }
+        if Appointment::is_calendar_class(message_class) {
+            return MessageClass::Appointment;
+        }
+        if Contact::is_contact_class(message_class) {
+            return MessageClass::Contact;
+        }
+        if DistList::is_dist_list_class(message_class) {
+            return MessageClass::DistList;
+        }
+        if TaskRequestKind::from_message_class(message_class).is_some() {
+            return MessageClass::TaskRequest;
+        }
+        if Task::is_task_class(message_class) {
+            return MessageClass::Task;
+        }
+        if StickyNote::is_note_class(message_class) {
+            return MessageClass::StickyNote;
+        }
+        let message_class = message_class.to_ascii_lowercase();
+        if message_class == "ipm"
+            || message_class == "ipm.note"
+            || message_class.starts_with("ipm.note.")
+        {
+            return MessageClass::Note;
+        }
+        MessageClass::Unknown
+    }
+}
+
+impl Outlook {
+    // Raw "MessageClass" along with the kind of item it tells.
+    pub fn message_class(&self) -> (&str, MessageClass) {
+        (
+            &self.message_class,
+            MessageClass::from_message_class(&self.message_class),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{MessageClass, Outlook};
+
+    #[test]
+    fn test_from_message_class() {
+        let class = MessageClass::from_message_class;
+        assert_eq!(class("IPM.Note"), MessageClass::Note);
+        assert_eq!(class("IPM.Note.SMIME.MultipartSigned"), MessageClass::Note);
+        assert_eq!(class("IPM"), MessageClass::Note);
+        assert_eq!(class("IPM.Appointment"), MessageClass::Appointment);
+        assert_eq!(class("IPM.Schedule.Meeting.Request"), MessageClass::MeetingRequest);
+        assert_eq!(class("IPM.Schedule.Meeting.Resp.Tent"), MessageClass::MeetingResponse);
+        assert_eq!(class("IPM.Schedule.Meeting.Canceled"), MessageClass::MeetingCancellation);
+        assert_eq!(class("IPM.Contact"), MessageClass::Contact);
+        assert_eq!(class("IPM.DistList"), MessageClass::DistList);
+        assert_eq!(class("IPM.Task"), MessageClass::Task);
+        assert_eq!(class("IPM.TaskRequest.Accept"), MessageClass::TaskRequest);
+        assert_eq!(class("IPM.StickyNote"), MessageClass::StickyNote);
+        assert_eq!(class("REPORT.IPM.Note.NDR"), MessageClass::NonDeliveryReport);
+        assert_eq!(class("REPORT.IPM.Note.IPNRN"), MessageClass::ReadReceipt);
+        assert_eq!(class("REPORT.IPM.Note.IPNNRN"), MessageClass::NotReadReceipt);
+        assert_eq!(class("REPORT.IPM.Note.DR"), MessageClass::DeliveryReceipt);
+        assert_eq!(class("IPM.Notes"), MessageClass::Unknown);
+        assert_eq!(class(""), MessageClass::Unknown);
+    }
+
+    #[test]
+    fn test_message_class() {
+        let outlook = Outlook::from_path("data/test_email.msg").unwrap();
+        assert_eq!(outlook.message_class(), ("IPM.Note", MessageClass::Note));
+    }
+}
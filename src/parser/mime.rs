@@ -0,0 +1,439 @@
+use base64::{
+    engine::{general_purpose::GeneralPurpose, DecodePaddingMode, GeneralPurposeConfig},
+    alphabet, Engine,
+};
+use std::ops::Range;
+
+use serde::{Deserialize, Serialize};
+
+use super::{
+    error::Error,
+    headers::{decode_charset, Headers},
+    limits::Limit,
+};
+
+// Nesting depth of the parts of a multipart parsed, the
+// entity itself being 0, well below what would exhaust
+// the stack.
+const MAX_DEPTH: usize = 32;
+
+// Base64 bodies are often written without padding.
+const BASE64: GeneralPurpose = GeneralPurpose::new(
+    &alphabet::STANDARD,
+    GeneralPurposeConfig::new().with_decode_padding_mode(DecodePaddingMode::Indifferent),
+);
+
+// MimePart is an entity of a MIME message: its header
+// fields, and either its body or the parts of a multipart.
+// Refer to RFC 2045 and RFC 2046
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct MimePart {
+    pub headers: Headers,     // fields of the entity
+    #[serde(with = "hex")]
+    #[cfg_attr(feature = "schemars", schemars(with = "String"))]
+    pub body: Vec<u8>,        // decoded from its "Content-Transfer-Encoding"
+    pub parts: Vec<MimePart>, // parts of a multipart, in order
+    #[serde(skip)]
+    pub raw: Range<usize>,    // entity as it was read, header fields included, within the data parsed
+}
+
+// Splits an entity at the empty line ending its header fields.
+pub(crate) fn split_header(data: &[u8]) -> (&[u8], &[u8]) {
+    if data.starts_with(b"\r\n") {
+        return (&[], &data[2..]);
+    }
+    if data.starts_with(b"\n") {
+        return (&[], &data[1..]);
+    }
+    let mut offset = 0;
+    for line in data.split_inclusive(|&b| b == b'\n') {
+        offset += line.len();
+        if line == b"\r\n" || line == b"\n" {
+            return (&data[..offset - line.len()], &data[offset..]);
+        }
+    }
+    (data, &[])
+}
+
+// Splits the body of a multipart at the delimiter lines,
+// returning the ranges of the parts within it. The preamble
+// and the epilogue are left out, as is the line break
+// before each delimiter, which belongs to it.
+fn split_parts(body: &[u8], boundary: &str) -> Vec<Range<usize>> {
+    let delimiter = format!("--{}", boundary);
+    let mut parts = vec![];
+    let mut start: Option<usize> = None;
+    let mut offset = 0;
+    for line in body.split_inclusive(|&b| b == b'\n') {
+        let line_start = offset;
+        offset += line.len();
+        let trimmed = line.trim_ascii_end();
+        if !trimmed.starts_with(delimiter.as_bytes()) {
+            continue;
+        }
+        let rest = &trimmed[delimiter.len()..];
+        if !rest.is_empty() && rest != b"--" {
+            continue;
+        }
+        if let Some(start) = start {
+            let end = if body[..line_start].ends_with(b"\r\n") {
+                line_start - 2
+            } else if body[..line_start].ends_with(b"\n") {
+                line_start - 1
+            } else {
+                line_start
+            };
+            parts.push(start..end.max(start));
+        }
+        if rest == b"--" {
+            return parts;
+        }
+        start = Some(offset);
+    }
+    // The closing delimiter is missing.
+    if let Some(start) = start {
+        parts.push(start..body.len());
+    }
+    parts
+}
+
+// Decodes "quoted-printable" text, dropping soft line breaks.
+// Refer to RFC 2045 6.7
+pub(crate) fn decode_quoted_printable(data: &[u8]) -> Vec<u8> {
+    let mut decoded = Vec::with_capacity(data.len());
+    let mut i = 0;
+    while i < data.len() {
+        if data[i] != b'=' {
+            decoded.push(data[i]);
+            i += 1;
+            continue;
+        }
+        let rest = &data[i + 1..];
+        if rest.starts_with(b"\r\n") {
+            i += 3;
+        } else if rest.starts_with(b"\n") {
+            i += 2;
+        } else {
+            let byte = rest
+                .get(..2)
+                .and_then(|hex| std::str::from_utf8(hex).ok())
+                .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+            match byte {
+                Some(byte) => {
+                    decoded.push(byte);
+                    i += 3;
+                }
+                None => {
+                    decoded.push(b'=');
+                    i += 1;
+                }
+            }
+        }
+    }
+    decoded
+}
+
+// Encodes text as "quoted-printable", lines ending with CRLF
+// and none of them longer than 76 characters.
+// Refer to RFC 2045 6.7
+pub(crate) fn encode_quoted_printable(data: &[u8]) -> String {
+    let mut encoded = String::with_capacity(data.len() + data.len() / 8);
+    let text = String::from_utf8_lossy(data).replace("\r\n", "\n");
+    for (i, line) in text.split('\n').enumerate() {
+        if i > 0 {
+            encoded.push_str("\r\n");
+        }
+        let mut len = 0;
+        let bytes = line.as_bytes();
+        for (j, &byte) in bytes.iter().enumerate() {
+            // Whitespace is kept as it is, but at the end of a line.
+            let literal = match byte {
+                b'=' => false,
+                b' ' | b'\t' => j + 1 < bytes.len(),
+                33..=126 => true,
+                _ => false,
+            };
+            let chunk = if literal {
+                (byte as char).to_string()
+            } else {
+                format!("={:02X}", byte)
+            };
+            // Room is kept for the "=" of the soft line break.
+            if len + chunk.len() > 75 {
+                encoded.push_str("=\r\n");
+                len = 0;
+            }
+            encoded.push_str(&chunk);
+            len += chunk.len();
+        }
+    }
+    encoded
+}
+
+// Value of a parameter of a header field value,
+// e.g. the boundary of "multipart/mixed; boundary=x".
+pub(crate) fn parameter(value: &str, name: &str) -> Option<String> {
+    let mut params = vec![];
+    let mut param = String::new();
+    let mut quoted = false;
+    for c in value.chars().chain(std::iter::once(';')) {
+        match c {
+            '"' => quoted = !quoted,
+            ';' if !quoted => {
+                params.push(std::mem::take(&mut param));
+                continue;
+            }
+            _ => {}
+        }
+        param.push(c);
+    }
+    params.iter().skip(1).find_map(|param| {
+        let (key, value) = param.split_once('=')?;
+        if !key.trim().eq_ignore_ascii_case(name) {
+            return None;
+        }
+        let value = value.trim();
+        let value = value
+            .strip_prefix('"')
+            .and_then(|value| value.strip_suffix('"'))
+            .unwrap_or(value);
+        Some(value.to_string())
+    })
+}
+
+impl MimePart {
+    // Fails with Error::LimitExceeded when multiparts are
+    // nested deeper than MAX_DEPTH.
+    pub fn parse(data: &[u8]) -> Result<Self, Error> {
+        Self::parse_range(data, 0..data.len(), 0)
+    }
+
+    fn parse_range(data: &[u8], raw: Range<usize>, depth: usize) -> Result<Self, Error> {
+        if depth > MAX_DEPTH {
+            return Err(Error::LimitExceeded {
+                limit: Limit::MimeDepth,
+                max: MAX_DEPTH,
+            });
+        }
+        let (header, body) = split_header(&data[raw.clone()]);
+        // The body ends the entity.
+        let body_start = raw.end - body.len();
+        let headers = Headers::parse(&String::from_utf8_lossy(header));
+        let mut part = Self {
+            headers,
+            raw,
+            ..Self::default()
+        };
+        let boundary = part.parameter("Content-Type", "boundary");
+        match boundary {
+            Some(boundary) if part.content_type().starts_with("multipart/") => {
+                part.parts = split_parts(body, &boundary)
+                    .into_iter()
+                    .map(|range| {
+                        let range = body_start + range.start..body_start + range.end;
+                        Self::parse_range(data, range, depth + 1)
+                    })
+                    .collect::<Result<Vec<Self>, Error>>()?;
+            }
+            _ => part.body = part.decode_body(body),
+        }
+        Ok(part)
+    }
+
+    fn decode_body(&self, body: &[u8]) -> Vec<u8> {
+        let encoding = self
+            .headers
+            .get("Content-Transfer-Encoding")
+            .unwrap_or_default()
+            .trim()
+            .to_ascii_lowercase();
+        match encoding.as_str() {
+            "base64" => {
+                let text: Vec<u8> = body
+                    .iter()
+                    .copied()
+                    .filter(|b| !b.is_ascii_whitespace())
+                    .collect();
+                BASE64.decode(text).unwrap_or_else(|_| body.to_vec())
+            }
+            "quoted-printable" => decode_quoted_printable(body),
+            _ => body.to_vec(),
+        }
+    }
+
+    // Media type in lower case, "text/plain" when not given.
+    pub fn content_type(&self) -> String {
+        self.headers
+            .get("Content-Type")
+            .and_then(|value| value.split(';').next())
+            .map(|value| value.trim().to_ascii_lowercase())
+            .filter(|value| !value.is_empty())
+            .unwrap_or_else(|| "text/plain".to_string())
+    }
+
+    pub fn parameter(&self, field: &str, name: &str) -> Option<String> {
+        parameter(self.headers.get(field)?, name)
+    }
+
+    pub fn file_name(&self) -> Option<String> {
+        self.parameter("Content-Disposition", "filename")
+            .or_else(|| self.parameter("Content-Type", "name"))
+    }
+
+    pub fn is_multipart(&self) -> bool {
+        !self.parts.is_empty()
+    }
+
+    // Body read in its charset.
+    pub fn text(&self) -> String {
+        let charset = self
+            .parameter("Content-Type", "charset")
+            .unwrap_or_else(|| "utf-8".to_string());
+        decode_charset(&charset, &self.body)
+    }
+
+    // This part followed by every part it holds, depth first.
+    pub fn walk(&self) -> Vec<&MimePart> {
+        let mut parts = vec![self];
+        for part in self.parts.iter() {
+            parts.extend(part.walk());
+        }
+        parts
+    }
+
+    // First part that is no multipart and has the given media type.
+    pub fn find(&self, content_type: &str) -> Option<&MimePart> {
+        self.walk()
+            .into_iter()
+            .find(|part| !part.is_multipart() && part.content_type() == content_type)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        decode_quoted_printable, encode_quoted_printable, parameter, Error, Limit, MimePart, MAX_DEPTH,
+    };
+
+    const MESSAGE: &[u8] = b"Content-Type: multipart/mixed; boundary=\"outer\"\r\n\
+        Subject: Hello\r\n\
+        \r\n\
+        This is the preamble.\r\n\
+        --outer\r\n\
+        Content-Type: multipart/alternative; boundary=inner\r\n\
+        \r\n\
+        --inner\r\n\
+        Content-Type: text/plain; charset=iso-8859-1\r\n\
+        Content-Transfer-Encoding: quoted-printable\r\n\
+        \r\n\
+        Caf=E9 au =\r\n\
+        lait\r\n\
+        --inner\r\n\
+        Content-Type: text/html\r\n\
+        \r\n\
+        <p>Caf\xC3\xA9</p>\r\n\
+        --inner--\r\n\
+        --outer\r\n\
+        Content-Type: application/octet-stream; name=\"a.bin\"\r\n\
+        Content-Disposition: attachment; filename=\"data.bin\"\r\n\
+        Content-Transfer-Encoding: base64\r\n\
+        \r\n\
+        AAEC\r\n\
+        Aw\r\n\
+        --outer--\r\n\
+        This is the epilogue.\r\n";
+
+    #[test]
+    fn test_parse() {
+        let message = MimePart::parse(MESSAGE).unwrap();
+        assert_eq!(message.content_type(), "multipart/mixed".to_string());
+        assert_eq!(message.headers.get("Subject"), Some("Hello"));
+        assert_eq!(message.parts.len(), 2);
+        assert_eq!(message.walk().len(), 5);
+
+        let plain = message.find("text/plain").unwrap();
+        assert_eq!(plain.text(), "Café au lait".to_string());
+        let html = message.find("text/html").unwrap();
+        assert_eq!(html.text(), "<p>Café</p>".to_string());
+        assert!(MESSAGE[html.raw.clone()].starts_with(b"Content-Type: text/html\r\n"));
+        assert!(MESSAGE[html.raw.clone()].ends_with(b"</p>"));
+
+        let attachment = &message.parts[1];
+        assert_eq!(attachment.file_name(), Some("data.bin".to_string()));
+        assert_eq!(attachment.body, vec![0, 1, 2, 3]);
+        assert_eq!(message.find("image/png"), None);
+    }
+
+    #[test]
+    fn test_parse_without_headers() {
+        let part = MimePart::parse(b"\r\nbody\r\n").unwrap();
+        assert_eq!(part.content_type(), "text/plain".to_string());
+        assert_eq!(part.body, b"body\r\n".to_vec());
+
+        // The closing delimiter is missing.
+        let part =
+            MimePart::parse(b"Content-Type: multipart/mixed; boundary=b\n\n--b\n\nfirst\n--b\n\nsecond\n").unwrap();
+        let bodies: Vec<String> = part.parts.iter().map(MimePart::text).collect();
+        assert_eq!(bodies, vec!["first".to_string(), "second\n".to_string()]);
+    }
+
+    // A multipart holding a multipart, and so on depth times.
+    fn nested(depth: usize) -> Vec<u8> {
+        let mut entity = b"Content-Type: text/plain\r\n\r\ninnermost".to_vec();
+        for level in 0..depth {
+            let mut multipart = format!(
+                "Content-Type: multipart/mixed; boundary=\"b{}\"\r\n\r\n--b{}\r\n",
+                level, level
+            )
+            .into_bytes();
+            multipart.extend(entity);
+            multipart.extend(format!("\r\n--b{}--\r\n", level).into_bytes());
+            entity = multipart;
+        }
+        entity
+    }
+
+    #[test]
+    fn test_max_depth() {
+        let data = nested(MAX_DEPTH);
+        let mut part = &MimePart::parse(&data).unwrap();
+        while part.is_multipart() {
+            part = &part.parts[0];
+        }
+        assert_eq!(part.text(), "innermost".to_string());
+        assert_eq!(&data[part.raw.clone()], b"Content-Type: text/plain\r\n\r\ninnermost");
+
+        let err = MimePart::parse(&nested(MAX_DEPTH + 1)).unwrap_err();
+        assert!(matches!(
+            err,
+            Error::LimitExceeded {
+                limit: Limit::MimeDepth,
+                max: MAX_DEPTH
+            }
+        ));
+    }
+
+    #[test]
+    fn test_decode_quoted_printable() {
+        assert_eq!(decode_quoted_printable(b"a=3Db=\nc=ZZ="), b"a=bc=ZZ=".to_vec());
+    }
+
+    #[test]
+    fn test_encode_quoted_printable() {
+        let encoded = encode_quoted_printable("Café = 1 \nnext\t\r\n".as_bytes());
+        assert_eq!(encoded, "Caf=C3=A9 =3D 1=20\r\nnext=09\r\n".to_string());
+        let long = "a".repeat(100);
+        let encoded = encode_quoted_printable(long.as_bytes());
+        assert!(encoded.split("\r\n").all(|line| line.len() <= 76));
+        assert_eq!(decode_quoted_printable(encoded.as_bytes()), long.into_bytes());
+    }
+
+    #[test]
+    fn test_parameter() {
+        let value = "attachment; filename=\"a; b.txt\"; size=10";
+        assert_eq!(parameter(value, "filename"), Some("a; b.txt".to_string()));
+        assert_eq!(parameter(value, "SIZE"), Some("10".to_string()));
+        assert_eq!(parameter(value, "attachment"), None);
+    }
+}
@@ -0,0 +1,135 @@
+use std::time::SystemTime;
+
+use super::{
+    error::Error,
+    extract::ExtractedAttachment,
+    outlook::{Attachment as OutlookAttachment, Outlook, Person as OutlookPerson},
+};
+
+#[derive(uniffi::Record, Debug, Clone, PartialEq)]
+pub struct Person {
+    pub name: String,
+    pub email: String,
+}
+
+#[derive(uniffi::Record, Debug, Clone, PartialEq)]
+pub struct Attachment {
+    pub file_name: String, // See ExtractedAttachment::file_name_of
+    pub mime_type: String, // "AttachMimeTag"
+    pub data: Vec<u8>,     // Empty for embedded messages
+    pub embedded: bool,    // The attachment is a message
+}
+
+// Message is the message of the Kotlin and Swift bindings,
+// which uniffi-bindgen generates from the built library.
+// It holds what a mail client shows of a message rather
+// than the whole of Outlook, which to_json has.
+#[derive(uniffi::Record, Debug, Clone, PartialEq)]
+pub struct Message {
+    pub subject: String,
+    pub sender: Person,
+    pub to: Vec<Person>,
+    pub cc: Vec<Person>,
+    pub bcc: Vec<Person>,
+    pub body: String, // See Outlook::body_text
+    pub html: String, // HTML body, converted from RTF if need be
+    pub message_id: String,
+    pub submit_time: Option<SystemTime>,
+    pub delivery_time: Option<SystemTime>,
+    pub attachments: Vec<Attachment>,
+}
+
+// ParseError is thrown as ParseException in Kotlin and
+// Swift, code being that of Error::code.
+#[derive(uniffi::Error, thiserror::Error, Debug, Clone, PartialEq)]
+pub enum ParseError {
+    #[error("{}", .message)]
+    Failed { code: u32, message: String },
+}
+
+impl From<Error> for ParseError {
+    fn from(err: Error) -> Self {
+        ParseError::Failed {
+            code: err.code(),
+            message: err.to_string(),
+        }
+    }
+}
+
+impl From<&OutlookPerson> for Person {
+    fn from(person: &OutlookPerson) -> Self {
+        Self {
+            name: person.name.clone(),
+            email: person.email.clone(),
+        }
+    }
+}
+
+impl From<&OutlookAttachment> for Attachment {
+    fn from(attachment: &OutlookAttachment) -> Self {
+        Self {
+            file_name: ExtractedAttachment::file_name_of(attachment),
+            mime_type: attachment.mime_tag.clone(),
+            data: hex::decode(&attachment.payload).unwrap_or_default(),
+            embedded: attachment.embedded.is_some(),
+        }
+    }
+}
+
+impl From<&Outlook> for Message {
+    fn from(outlook: &Outlook) -> Self {
+        let persons = |persons: &Vec<OutlookPerson>| persons.iter().map(Person::from).collect();
+        Self {
+            subject: outlook.subject.clone(),
+            sender: Person::from(&outlook.sender),
+            to: persons(&outlook.to),
+            cc: persons(&outlook.cc),
+            bcc: persons(&outlook.bcc),
+            body: outlook.body_text(),
+            html: outlook.html_body(),
+            message_id: outlook.message_id.clone(),
+            submit_time: outlook.submit_time.map(SystemTime::from),
+            delivery_time: outlook.delivery_time.map(SystemTime::from),
+            attachments: outlook.attachments.iter().map(Attachment::from).collect(),
+        }
+    }
+}
+
+// Parses a message from the bytes of a .msg file.
+#[uniffi::export]
+pub fn parse_message(data: Vec<u8>) -> Result<Message, ParseError> {
+    Ok(Message::from(&Outlook::from_slice(&data)?))
+}
+
+// Same as parse_message, as the JSON of Outlook::to_json.
+#[uniffi::export]
+pub fn parse_message_json(data: Vec<u8>) -> Result<String, ParseError> {
+    Ok(Outlook::from_slice(&data)?.to_json()?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{parse_message, parse_message_json, ParseError};
+
+    #[test]
+    fn test_parse_message() {
+        let data = std::fs::read("data/attachment.msg").unwrap();
+        let message = parse_message(data.clone()).unwrap();
+        assert_eq!(
+            message.subject,
+            "FW: [WARNING: ATTACHMENT(S) MAY CONTAIN MALWARE]Please check all aspects in documents"
+        );
+        assert_eq!(message.attachments.len(), 3);
+        assert_eq!(message.attachments[0].file_name, "loan_proposal.doc");
+        assert_eq!(message.attachments[0].data.len(), 16896);
+        assert!(parse_message_json(data.clone()).unwrap().starts_with('{'));
+
+        assert_eq!(
+            parse_message(data[..100].to_vec()),
+            Err(ParseError::Failed {
+                code: 2,
+                message: "Error parsing file with ole: failed to fill whole buffer".to_string(),
+            })
+        );
+    }
+}
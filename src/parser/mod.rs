@@ -1,10 +1,203 @@
+mod appointment;
+#[cfg(feature = "tokio")]
+mod async_io;
+mod auth;
+#[cfg(feature = "rayon")]
+mod batch;
+mod blob;
+mod builder;
+#[cfg(feature = "cbor")]
+mod cbor;
+mod clock;
+#[cfg(feature = "conformance")]
+pub mod conformance;
 mod constants;
+mod contact;
+mod conversation;
+mod csv;
 mod decode;
+mod der;
+mod diff;
+mod directory;
+mod distlist;
+mod dump;
+mod eml;
+mod entry_id;
+mod envelope;
+#[cfg(feature = "ffi")]
+mod ffi;
+mod fingerprint;
+mod flags;
+mod followup;
+mod headers;
+mod html;
+mod ics;
+mod importance;
+mod iocs;
+#[cfg(any(feature = "wasm", feature = "node"))]
+mod js;
+mod json;
+mod label;
+mod lazy;
+mod limits;
+mod link;
+mod macros;
+mod maildir;
+mod mbox;
+mod meeting;
+mod message_class;
+mod memory;
+mod mime;
+#[cfg(feature = "uniffi")]
+mod mobile;
+mod mode;
+#[cfg(feature = "msgpack")]
+mod msgpack;
+mod named;
+#[cfg(feature = "node")]
+mod node;
+mod note;
+mod options;
+mod phishing;
+mod preview;
+mod protection;
+mod provenance;
+mod received;
+mod recipient;
+mod recurrence;
+mod redact;
+mod reminder;
+mod report;
+mod rtf;
+mod shared;
+mod smime;
 mod storage;
 mod stream;
+mod task;
+mod thread;
+mod timezone;
+#[cfg(feature = "schemars")]
+mod typescript;
+mod vcard;
+mod verbose;
+mod voting;
+mod warning;
+#[cfg(feature = "wasm")]
+mod wasm;
+mod writer;
 
 mod error;
-pub use error::{DataTypeError, Error};
+pub use error::{DataTypeError, Error, ErrorContext};
+
+mod extract;
+pub use extract::{
+    parse_size, AttachmentFilter, ExtractManifest, ExtractedAttachment, HashAlgorithm,
+};
 
 mod outlook;
 pub use outlook::{Attachment, Outlook, Person, TransportHeaders};
+
+pub use appointment::Appointment;
+
+pub use auth::{AuthResult, Authentication, DkimSignature, ReceivedSpf, Verdict};
+
+#[cfg(feature = "rayon")]
+pub use batch::{parse_dir, parse_dir_with, parse_paths, parse_paths_with, BatchResult, BatchStats};
+
+pub use builder::OutlookBuilder;
+
+pub use contact::{Contact, ContactPhoto, PhoneNumber};
+
+pub use conversation::ConversationIndex;
+
+pub use csv::CsvWriter;
+
+pub use clock::{Clock, FixedClock, SystemClock};
+
+pub use diff::{AttachmentChange, AttachmentDigest, MessageDiff, PropertyChange, RecipientChange};
+
+pub use directory::DirectoryEntry;
+
+pub use distlist::{DistList, DistListMember};
+
+pub use dump::{DumpEntry, EntryKind};
+
+pub use entry_id::EntryId;
+
+pub use envelope::Envelope;
+
+pub use flags::MessageFlags;
+
+pub use followup::{FlagStatus, FollowUpFlag};
+
+pub use headers::Headers;
+
+pub use importance::{Importance, Priority, Sensitivity};
+
+pub use iocs::{Ioc, IocSource, Iocs};
+
+pub use json::{BinaryEncoding, JsonOptions, KeyCase, JSON_SCHEMA_VERSION};
+
+pub use label::SensitivityLabel;
+
+pub use lazy::LazyOutlook;
+
+pub use limits::{Limit, Limits};
+
+pub use link::{Link, LinkSource};
+
+pub use maildir::MaildirWriter;
+
+pub use mbox::MboxWriter;
+
+pub use meeting::{Meeting, MeetingKind, ResponseStatus};
+
+pub use memory::MemoryReport;
+
+pub use message_class::MessageClass;
+
+pub use mime::MimePart;
+
+pub use mode::{MissingPart, ParseMode};
+
+pub use note::{NoteColor, StickyNote};
+
+pub use options::ParserOptions;
+
+pub use phishing::{Indicator, PhishingReport};
+
+pub use protection::{KeyRecipient, ProtectionKind, ProtectionStatus};
+
+pub use provenance::{PropertySource, Provenance, SourceKind, Sources};
+
+pub use received::ReceivedHop;
+
+pub use recipient::{AttendeeRole, Recipient, RecipientType};
+
+pub use redact::Redaction;
+
+pub use reminder::Reminder;
+
+pub use report::{NdrRecipient, NonDeliveryReport, OriginalEnvelope, Receipt, ReceiptKind};
+
+pub use shared::SharedAttachment;
+
+pub use smime::{Certificate, Smime, SmimeKind, SmimeSigner};
+
+pub use task::{Task, TaskRequestKind, TaskStatus};
+
+pub use thread::{Thread, ThreadNode, Threader};
+
+pub use recurrence::{
+    RecurrenceEnd, RecurrenceException, RecurrenceFrequency, RecurrencePattern,
+};
+
+pub use timezone::{TimeZone, TimeZoneRule, TransitionDate};
+
+pub use vcard::VCardVersion;
+
+pub use verbose::MessageProperties;
+
+pub use voting::Voting;
+
+pub use warning::{ParseReport, SkipReason, Warning};
@@ -0,0 +1,179 @@
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+use super::{
+    decode::DataType,
+    storage::{Properties, Recipients},
+};
+
+// ParseMode tells what to do with a message lacking parts
+// most messages have, as drafts and incomplete messages do.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum ParseMode {
+    #[default]
+    Lenient, // Missing fields are left empty or None, and listed in Outlook.missing
+    Strict,  // Parsing fails with Error::Incomplete
+    Salvage, // As Lenient, the streams of a damaged file being recovered rather than failing
+}
+
+// MissingPart is a part of the message its MSG file
+// does not hold.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub enum MissingPart {
+    MessageClass, // "MessageClass"
+    Sender,       // "Sender*" and "SentRepresenting*"
+    Recipients,   // Recipient storages
+    SubmitTime,   // "ClientSubmitTime"
+    DeliveryTime, // "MessageDeliveryTime"
+}
+
+impl MissingPart {
+    pub fn create_from_props(props: &Properties, recipients: &Recipients) -> Vec<Self> {
+        let has = |key: &str| match props.get(key) {
+            Some(DataType::PtypString(value)) => !value.is_empty(),
+            Some(_) => true,
+            None => false,
+        };
+        let has_sender = ["Name", "EmailAddress", "SmtpAddress"]
+            .iter()
+            .any(|field| has(&format!("Sender{}", field)) || has(&format!("SentRepresenting{}", field)));
+        [
+            (MissingPart::MessageClass, has("MessageClass")),
+            (MissingPart::Sender, has_sender),
+            (MissingPart::Recipients, !recipients.is_empty()),
+            (MissingPart::SubmitTime, has("ClientSubmitTime")),
+            (MissingPart::DeliveryTime, has("MessageDeliveryTime")),
+        ]
+        .iter()
+        .filter(|(_, present)| !present)
+        .map(|(part, _)| *part)
+        .collect()
+    }
+}
+
+impl fmt::Display for MissingPart {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            MissingPart::MessageClass => "message class",
+            MissingPart::Sender => "sender",
+            MissingPart::Recipients => "recipients",
+            MissingPart::SubmitTime => "submit time",
+            MissingPart::DeliveryTime => "delivery time",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{TimeZone as _, Utc};
+
+    use super::{
+        super::{
+            decode::DataType,
+            error::Error,
+            outlook::Outlook,
+            storage::Properties,
+            warning::{SkipReason, Warning},
+        },
+        MissingPart, ParseMode,
+    };
+    use crate::ole::{writer::ROOT, Writer};
+
+    #[test]
+    fn test_create_from_props() {
+        let missing = MissingPart::create_from_props(&Properties::new(), &vec![]);
+        assert_eq!(missing.len(), 5);
+
+        let mut props = Properties::new();
+        props.insert("MessageClass".to_string(), DataType::PtypString("IPM.Note".to_string()));
+        props.insert("SenderName".to_string(), DataType::PtypString(String::new()));
+        props.insert("SentRepresentingSmtpAddress".to_string(), DataType::PtypString("a@x.com".to_string()));
+        props.insert(
+            "ClientSubmitTime".to_string(),
+            DataType::PtypTime(Utc.with_ymd_and_hms(2024, 1, 1, 0, 0, 0).unwrap()),
+        );
+        assert_eq!(
+            MissingPart::create_from_props(&props, &vec![Properties::new()]),
+            vec![MissingPart::DeliveryTime]
+        );
+        assert_eq!(MissingPart::DeliveryTime.to_string(), "delivery time".to_string());
+    }
+
+    #[test]
+    fn test_parse_mode() {
+        // A draft, never sent, though naming its author.
+        let path = "data/test_email.msg";
+        let outlook = Outlook::from_path(path).unwrap();
+        assert_eq!(outlook.missing, vec![MissingPart::SubmitTime]);
+        assert_eq!(outlook.submit_time, None);
+        assert!(outlook.delivery_time.is_some());
+        let err = Outlook::from_path_with_mode(path, ParseMode::Strict).unwrap_err();
+        assert!(matches!(err, Error::Incomplete { .. }));
+        assert_eq!(
+            err.to_string(),
+            "Message is incomplete, missing submit time".to_string()
+        );
+
+        let outlook = Outlook::from_path_with_mode("data/attachment.msg", ParseMode::Strict).unwrap();
+        assert!(outlook.missing.is_empty());
+        assert!(outlook.submit_time.is_some());
+    }
+
+    #[test]
+    fn test_salvage() {
+        let path = "data/attachment.msg";
+        let bytes = std::fs::read(path).unwrap();
+        let outlook = Outlook::from_slice(&bytes).unwrap();
+        let salvaged = Outlook::from_path_with_mode(path, ParseMode::Salvage).unwrap();
+        assert_eq!(salvaged.to_json().unwrap(), outlook.to_json().unwrap());
+        assert!(!salvaged.report.salvaged);
+
+        // The directory chain ends after its first sector, as
+        // the SAT tells.
+        let mut damaged = bytes.clone();
+        let sec_id = |at: usize| u32::from_le_bytes([bytes[at], bytes[at + 1], bytes[at + 2], bytes[at + 3]]) as usize;
+        let (sat, directory) = (sec_id(76), sec_id(48));
+        let offset = 512 + sat * 512 + directory * 4;
+        damaged[offset..offset + 4].copy_from_slice(&0xFFFF_FFFEu32.to_le_bytes());
+        let lost = Outlook::from_slice(&damaged).unwrap();
+        assert!(lost.subject.is_empty());
+        assert!(lost.attachments.is_empty());
+
+        let salvaged = Outlook::from_slice_with_mode(&damaged, ParseMode::Salvage).unwrap();
+        assert!(salvaged.report.salvaged);
+        assert_eq!(salvaged.subject, outlook.subject);
+        assert_eq!(salvaged.attachments.len(), 3);
+        assert_eq!(salvaged.attachments[2].payload, outlook.attachments[2].payload);
+    }
+
+    #[test]
+    fn test_salvage_malformed_names() {
+        let utf16 = |text: &str| text.encode_utf16().flat_map(u16::to_le_bytes).collect();
+        let mut writer = Writer::new();
+        writer.add_stream(ROOT, "__substg1.0_0037001F", utf16("Hello"));
+        for name in ["__substg1.0_", "__substg1.0_AB", "__substg1.0_001E"] {
+            writer.add_stream(ROOT, name, utf16("Damaged"));
+        }
+        for name in ["__recip_version1.0_", "__attach_version1.0_"] {
+            let storage = writer.add_storage(ROOT, name);
+            writer.add_stream(storage, "__substg1.0_3001001F", utf16("Damaged"));
+        }
+        let msg = writer.to_bytes();
+        for mode in [ParseMode::Lenient, ParseMode::Salvage] {
+            let outlook = Outlook::from_slice_with_mode(&msg, mode).unwrap();
+            assert_eq!(outlook.subject, "Hello".to_string());
+            let skipped: Vec<&Warning> = outlook
+                .report
+                .warnings
+                .iter()
+                .filter(|warning| {
+                    matches!(warning, Warning::SkippedStream { reason: SkipReason::UnknownName, .. })
+                })
+                .collect();
+            assert_eq!(skipped.len(), 3);
+        }
+    }
+}
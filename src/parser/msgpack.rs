@@ -0,0 +1,76 @@
+use rmpv::Value as MsgPackValue;
+use serde_json::Value;
+
+use super::{error::Error, json::is_binary_field, outlook::Outlook};
+
+// Value of the JSON of the model, binary fields being
+// of the bin format family.
+// Refer to https://github.com/msgpack/msgpack/blob/master/spec.md
+fn to_msgpack_value(value: Value) -> MsgPackValue {
+    match value {
+        Value::Null => MsgPackValue::Nil,
+        Value::Bool(value) => MsgPackValue::Boolean(value),
+        Value::Number(number) => match (number.as_i64(), number.as_u64()) {
+            (Some(value), _) => MsgPackValue::from(value),
+            (_, Some(value)) => MsgPackValue::from(value),
+            _ => MsgPackValue::F64(number.as_f64().unwrap_or_default()),
+        },
+        Value::String(string) => MsgPackValue::from(string),
+        Value::Array(values) => MsgPackValue::Array(values.into_iter().map(to_msgpack_value).collect()),
+        Value::Object(map) => MsgPackValue::Map(
+            map.into_iter()
+                .map(|(key, value)| {
+                    let value = match value {
+                        Value::String(hex) if is_binary_field(&key) => match hex::decode(&hex) {
+                            Ok(bytes) => MsgPackValue::Binary(bytes),
+                            Err(_) => MsgPackValue::from(hex),
+                        },
+                        value => to_msgpack_value(value),
+                    };
+                    (MsgPackValue::from(key), value)
+                })
+                .collect(),
+        ),
+    }
+}
+
+impl Outlook {
+    // Same fields as to_json, in MessagePack.
+    pub fn to_msgpack(&self) -> Result<Vec<u8>, Error> {
+        let value = to_msgpack_value(serde_json::to_value(self)?);
+        let mut bytes = vec![];
+        rmpv::encode::write_value(&mut bytes, &value).map_err(|err| Error::EncodeError(err.to_string()))?;
+        Ok(bytes)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rmpv::Value as MsgPackValue;
+
+    use super::super::outlook::Outlook;
+
+    #[test]
+    fn test_to_msgpack() {
+        let outlook = Outlook::from_path("data/attachment.msg").unwrap();
+        let bytes = outlook.to_msgpack().unwrap();
+        assert!(bytes.len() < outlook.to_json().unwrap().len());
+
+        let value = rmpv::decode::read_value(&mut bytes.as_slice()).unwrap();
+        let field = |value: &MsgPackValue, key: &str| {
+            value
+                .as_map()
+                .unwrap()
+                .iter()
+                .find(|(name, _)| name.as_str() == Some(key))
+                .map(|(_, value)| value.clone())
+                .unwrap()
+        };
+        assert_eq!(field(&value, "subject"), MsgPackValue::from(outlook.subject.as_str()));
+        let attachment = field(&value, "attachments").as_array().unwrap()[0].clone();
+        assert_eq!(
+            field(&attachment, "payload"),
+            MsgPackValue::Binary(hex::decode(&outlook.attachments[0].payload).unwrap())
+        );
+    }
+}
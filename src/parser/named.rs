@@ -0,0 +1,308 @@
+use std::{
+    collections::{HashMap, HashSet},
+    io::Read,
+};
+
+use crate::ole::Reader;
+
+use super::{
+    blob::BlobReader,
+    constants::{LidNameMap, PropIdNameMap, PS_MAPI, PS_PUBLIC_STRINGS},
+};
+
+pub(super) const NAMEID_STORAGE: &str = "__nameid_version1.0";
+pub(super) const GUID_STREAM: &str = "__substg1.0_00020102";
+pub(super) const ENTRY_STREAM: &str = "__substg1.0_00030102";
+pub(super) const STRING_STREAM: &str = "__substg1.0_00040102";
+
+// NamedPropMap maps the property ids (0x8000 onwards)
+// a message file assigned to its named properties onto
+// their canonical names.
+// Refer to MS-OXMSG 2.2.3
+#[derive(Debug, Default)]
+pub struct NamedPropMap {
+    map: HashMap<String, String>,
+}
+
+impl NamedPropMap {
+    fn read_stream(parser: &Reader, name: &str) -> Vec<u8> {
+        let entry = parser.entry_by_path(&format!("{}/{}", NAMEID_STORAGE, name));
+        let mut buff = vec![];
+        if let Some(mut slice) = entry.and_then(|entry| parser.get_entry_slice(entry).ok()) {
+            buff = vec![0u8; slice.len()];
+            if slice.read_exact(&mut buff).is_err() {
+                buff.clear();
+            }
+        }
+        buff
+    }
+
+    // Guid index 1 and 2 are reserved for PS_MAPI and
+    // PS_PUBLIC_STRINGS, others point into the GUID stream.
+    fn resolve_guid(guids: &[String], guid_index: u32) -> Option<String> {
+        match guid_index {
+            1 => Some(PS_MAPI.to_string()),
+            2 => Some(PS_PUBLIC_STRINGS.to_string()),
+            idx if idx >= 3 => guids.get(idx as usize - 3).cloned(),
+            _ => None,
+        }
+    }
+
+    // The string stream is a sequence of UTF-16LE names, each
+    // preceded by its length in bytes and padded to a 4 bytes
+    // boundary. Names are keyed by their offset in the stream,
+    // which is what string named property entries refer to.
+    // Refer to MS-OXMSG 2.2.3.1.4
+    fn read_names(string_stream: &[u8]) -> HashMap<u32, String> {
+        let mut names = HashMap::new();
+        let mut reader = BlobReader::new(string_stream);
+        while reader.remaining() >= 4 {
+            let offset = reader.offset() as u32;
+            let len = reader.read_u32().unwrap_or_default() as usize;
+            let bytes = match reader.read_bytes(len) {
+                Ok(bytes) => bytes,
+                // Length runs past the end of the stream.
+                Err(_) => break,
+            };
+            // Names that do not decode are left out so that
+            // their properties fall back to a hex identifier.
+            if len.is_multiple_of(2) {
+                if let Ok(name) = BlobReader::new(bytes).read_utf16(len / 2) {
+                    names.insert(offset, name);
+                }
+            }
+            // The last name may not be padded.
+            if reader.skip((4 - len % 4) % 4).is_err() {
+                break;
+            }
+        }
+        names
+    }
+
+    fn create(
+        guid_stream: &[u8],
+        entry_stream: &[u8],
+        string_stream: &[u8],
+        prop_map: &PropIdNameMap,
+        lid_map: &LidNameMap,
+    ) -> Self {
+        let mut guid_reader = BlobReader::new(guid_stream);
+        let mut guids: Vec<String> = vec![];
+        while let Ok(guid) = guid_reader.read_guid() {
+            guids.push(guid);
+        }
+
+        let names = Self::read_names(string_stream);
+        // Names the file picks itself may not be those of
+        // other properties, which they would shadow.
+        let canonical: HashSet<&String> = prop_map.names().chain(lid_map.names()).collect();
+        let mut map = HashMap::new();
+        let mut entry_reader = BlobReader::new(entry_stream);
+        while entry_reader.remaining() >= 8 {
+            let name_id = entry_reader.read_u32().unwrap_or_default();
+            let index_and_kind = entry_reader.read_u32().unwrap_or_default();
+            let guid_index = (index_and_kind >> 1) & 0x7FFF;
+            let prop_id = format!("0x{:04X}", 0x8000 + (index_and_kind >> 16));
+            // Lowest bit is the property kind, numerical (0) or string (1).
+            // For string properties name_id is an offset into the string
+            // stream; a malformed name, or one taken by another property,
+            // keeps the property under its id.
+            if index_and_kind & 1 == 1 {
+                let name = names
+                    .get(&name_id)
+                    .filter(|name| !canonical.contains(name) && !name.starts_with("0x"))
+                    .cloned()
+                    .unwrap_or_else(|| prop_id.clone());
+                map.insert(prop_id, name);
+                continue;
+            }
+            let name = Self::resolve_guid(&guids, guid_index)
+                .and_then(|guid| lid_map.get_canonical_name(&guid, name_id));
+            if let Some(name) = name {
+                map.insert(prop_id, name);
+            }
+        }
+        Self { map }
+    }
+
+    pub fn new(parser: &Reader, prop_map: &PropIdNameMap) -> Self {
+        match parser.entry_by_path(NAMEID_STORAGE) {
+            Some(_) => Self::create(
+                &Self::read_stream(parser, GUID_STREAM),
+                &Self::read_stream(parser, ENTRY_STREAM),
+                &Self::read_stream(parser, STRING_STREAM),
+                prop_map,
+                &LidNameMap::init(),
+            ),
+            None => Self::default(),
+        }
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&String, &String)> {
+        self.map.iter()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        super::{
+            constants::{LidNameMap, PropIdNameMap},
+            outlook::Outlook,
+        },
+        NamedPropMap, ENTRY_STREAM, GUID_STREAM, NAMEID_STORAGE, STRING_STREAM,
+    };
+    use crate::ole::{Reader, Writer, ROOT};
+
+    #[test]
+    fn test_create_named_prop_map() {
+        // PSETID_Appointment as the first GUID of the GUID stream.
+        let guid_stream = vec![
+            0x02, 0x20, 0x06, 0x00, 0x00, 0x00, 0x00, 0x00, 0xC0, 0x00, 0x00, 0x00, 0x00, 0x00,
+            0x00, 0x46,
+        ];
+        let entry_stream = vec![
+            // 0x8233 (TimeZoneStruct), GUID index 3, property index 0.
+            0x33, 0x82, 0x00, 0x00, 0x06, 0x00, 0x00, 0x00,
+            // String named property at offset 0, property index 1.
+            0x00, 0x00, 0x00, 0x00, 0x05, 0x00, 0x01, 0x00,
+            // 0x825E (AppointmentTimeZoneDefinitionStartDisplay), property index 2.
+            0x5E, 0x82, 0x00, 0x00, 0x06, 0x00, 0x02, 0x00,
+            // Unknown GUID index, property index 3.
+            0x33, 0x82, 0x00, 0x00, 0x0E, 0x00, 0x03, 0x00,
+        ];
+        let named = NamedPropMap::create(&guid_stream, &entry_stream, &[], &PropIdNameMap::init(), &LidNameMap::init());
+        assert_eq!(named.map.len(), 3);
+        assert_eq!(named.map.get("0x8000"), Some(&"TimeZoneStruct".to_string()));
+        assert_eq!(
+            named.map.get("0x8002"),
+            Some(&"AppointmentTimeZoneDefinitionStartDisplay".to_string())
+        );
+        // Without a string stream the string property keeps its id.
+        assert_eq!(named.map.get("0x8001"), Some(&"0x8001".to_string()));
+    }
+
+    #[test]
+    fn test_read_names() {
+        let string_stream = vec![
+            // "Hi", 4 bytes, no padding needed.
+            0x04, 0x00, 0x00, 0x00, 0x48, 0x00, 0x69, 0x00,
+            // "Tag", 6 bytes, padded with 2 bytes.
+            0x06, 0x00, 0x00, 0x00, 0x54, 0x00, 0x61, 0x00, 0x67, 0x00, 0x00, 0x00,
+            // Odd length, not UTF-16, padded with 1 byte.
+            0x03, 0x00, 0x00, 0x00, 0x41, 0x42, 0x43, 0x00,
+            // Unpaired surrogate.
+            0x02, 0x00, 0x00, 0x00, 0x00, 0xD8, 0x00, 0x00,
+            // "X", last name without padding.
+            0x02, 0x00, 0x00, 0x00, 0x58, 0x00,
+        ];
+        let names = NamedPropMap::read_names(&string_stream);
+        assert_eq!(names.len(), 3);
+        assert_eq!(names.get(&0), Some(&"Hi".to_string()));
+        assert_eq!(names.get(&8), Some(&"Tag".to_string()));
+        assert_eq!(names.get(&36), Some(&"X".to_string()));
+
+        // Length running past the end of the stream.
+        let string_stream = vec![
+            0x04, 0x00, 0x00, 0x00, 0x48, 0x00, 0x69, 0x00, 0xFF, 0x00, 0x00, 0x00, 0x41, 0x00,
+        ];
+        let names = NamedPropMap::read_names(&string_stream);
+        assert_eq!(names.len(), 1);
+    }
+
+    #[test]
+    fn test_create_string_named_props() {
+        let string_stream = vec![0x08, 0x00, 0x00, 0x00, 0x4B, 0x00, 0x65, 0x00, 0x79, 0x00, 0x73, 0x00];
+        let entry_stream = vec![
+            // String at offset 0 in PS_PUBLIC_STRINGS, property index 0.
+            0x00, 0x00, 0x00, 0x00, 0x05, 0x00, 0x00, 0x00,
+            // Offset 6 is not the start of a name, property index 1.
+            0x06, 0x00, 0x00, 0x00, 0x05, 0x00, 0x01, 0x00,
+        ];
+        let named = NamedPropMap::create(
+            &[],
+            &entry_stream,
+            &string_stream,
+            &PropIdNameMap::init(),
+            &LidNameMap::init(),
+        );
+        assert_eq!(named.map.get("0x8000"), Some(&"Keys".to_string()));
+        assert_eq!(named.map.get("0x8001"), Some(&"0x8001".to_string()));
+    }
+
+    #[test]
+    fn test_string_names_shadowing() {
+        let name = |text: &str| {
+            let bytes: Vec<u8> = text.encode_utf16().flat_map(u16::to_le_bytes).collect();
+            let mut name = (bytes.len() as u32).to_le_bytes().to_vec();
+            name.extend(bytes);
+            name.resize(name.len().next_multiple_of(4), 0);
+            name
+        };
+        let mut string_stream = name("Subject");
+        string_stream.extend(name("ReminderSet"));
+        string_stream.extend(name("0x0037"));
+        string_stream.extend(name("x-custom"));
+        let entry_stream = vec![
+            // "Subject", property index 0.
+            0x00, 0x00, 0x00, 0x00, 0x05, 0x00, 0x00, 0x00,
+            // "ReminderSet", property index 1.
+            0x14, 0x00, 0x00, 0x00, 0x05, 0x00, 0x01, 0x00,
+            // "0x0037", property index 2.
+            0x30, 0x00, 0x00, 0x00, 0x05, 0x00, 0x02, 0x00,
+            // "x-custom", property index 3.
+            0x40, 0x00, 0x00, 0x00, 0x05, 0x00, 0x03, 0x00,
+        ];
+        let named = NamedPropMap::create(
+            &[],
+            &entry_stream,
+            &string_stream,
+            &PropIdNameMap::init(),
+            &LidNameMap::init(),
+        );
+        assert_eq!(named.map.get("0x8000"), Some(&"0x8000".to_string()));
+        assert_eq!(named.map.get("0x8001"), Some(&"0x8001".to_string()));
+        assert_eq!(named.map.get("0x8002"), Some(&"0x8002".to_string()));
+        assert_eq!(named.map.get("0x8003"), Some(&"x-custom".to_string()));
+
+        // A file naming a property "Subject" leaves the
+        // subject as it is, whatever the order of the streams.
+        let utf16 = |text: &str| text.encode_utf16().flat_map(u16::to_le_bytes).collect::<Vec<u8>>();
+        let streams = [("__substg1.0_0037001F", "Real"), ("__substg1.0_8000001F", "Spoofed")];
+        for order in [[0, 1], [1, 0]] {
+            let mut writer = Writer::new();
+            for index in order {
+                let (stream, value) = streams[index];
+                writer.add_stream(ROOT, stream, utf16(value));
+            }
+            let storage = writer.add_storage(ROOT, NAMEID_STORAGE);
+            writer.add_stream(storage, GUID_STREAM, vec![]);
+            writer.add_stream(storage, ENTRY_STREAM, entry_stream[..8].to_vec());
+            writer.add_stream(storage, STRING_STREAM, name("Subject"));
+            let outlook = Outlook::from_slice(&writer.to_bytes()).unwrap();
+            assert_eq!(outlook.subject, "Real".to_string());
+        }
+    }
+
+    #[test]
+    fn test_named_prop_map_test_email() {
+        let parser = Reader::from_path("data/test_email.msg").unwrap();
+        let named = NamedPropMap::new(&parser, &PropIdNameMap::init());
+        // No calendar properties in a plain note.
+        assert!(named.iter().all(|(_, name)| name != "TimeZoneStruct"));
+        assert!(named.iter().any(|(_, name)| name == "ReminderSet"));
+
+        let parser = Reader::from_path("data/attachment.msg").unwrap();
+        let named = NamedPropMap::new(&parser, &PropIdNameMap::init());
+        assert_eq!(named.map.get("0x8003"), None);
+        assert_eq!(named.map.get("0x8010"), Some(&"TeeVersion".to_string()));
+        assert_eq!(
+            named.map.get("0x8013"),
+            Some(&"x-ms-exchange-organization-originalclientipaddress".to_string())
+        );
+
+        let parser = Reader::from_path("data/Thumbs.db").unwrap();
+        let named = NamedPropMap::new(&parser, &PropIdNameMap::init());
+        assert_eq!(named.iter().count(), 0);
+    }
+}
@@ -0,0 +1,96 @@
+// napi registers the functions outside of tests only.
+#![cfg_attr(test, allow(dead_code))]
+
+use napi::{
+    bindgen_prelude::{Buffer, Object},
+    Env, Error as NapiError, JsValue, Result, Unknown,
+};
+use napi_derive::napi;
+
+use super::{
+    js::{ErrorInfo, JsModel},
+    outlook::Outlook,
+};
+
+// Error thrown for a message that failed to parse, with
+// the properties of ParseError as the WASM build has them.
+fn to_node_error(env: &Env, info: ErrorInfo) -> NapiError {
+    let error = || -> Result<NapiError> {
+        let mut error: Object = env.create_error(NapiError::from_reason(info.message.clone()))?;
+        error.set("code", info.code)?;
+        error.set("kind", info.kind)?;
+        if let Some(path) = &info.path {
+            error.set("path", path.as_str())?;
+        }
+        if let Some(tag) = info.tag {
+            error.set("tag", tag)?;
+        }
+        if let Some(offset) = info.offset {
+            error.set("offset", offset as u32)?;
+        }
+        Ok(NapiError::from(error.to_unknown()))
+    };
+    error().unwrap_or_else(|err| err)
+}
+
+fn parse(env: &Env, data: &[u8]) -> Result<Outlook> {
+    Outlook::from_slice(data).map_err(|err| to_node_error(env, ErrorInfo::new(&err, data)))
+}
+
+fn to_object<'env>(env: &'env Env, outlook: &Outlook) -> Result<Unknown<'env>> {
+    let value = serde_json::to_value(outlook)?;
+    env.to_js_value(&JsModel(&value))
+}
+
+// Parses a message into an object holding the fields of
+// Outlook::to_json, binary ones being Buffers.
+#[napi(js_name = "parseToObject", ts_return_type = "Outlook")]
+pub fn parse_to_object<'env>(env: &'env Env, data: Buffer) -> Result<Unknown<'env>> {
+    to_object(env, &parse(env, &data)?)
+}
+
+// Same as parseToObject, as the JSON of Outlook::to_json.
+#[napi(js_name = "jsonFromBuffer")]
+pub fn json_from_buffer(env: &Env, data: Buffer) -> Result<String> {
+    parse(env, &data)?.to_json().map_err(|err| NapiError::from_reason(err.to_string()))
+}
+
+// Message keeps a parsed message on the Rust side, its
+// attachments being fetched one at a time.
+#[napi]
+pub struct Message {
+    outlook: Outlook,
+}
+
+#[napi]
+impl Message {
+    #[napi(constructor)]
+    pub fn new(env: &Env, data: Buffer) -> Result<Self> {
+        Ok(Self {
+            outlook: parse(env, &data)?,
+        })
+    }
+
+    // Same object as parseToObject returns.
+    #[napi(js_name = "toObject", ts_return_type = "Outlook")]
+    pub fn to_object<'env>(&self, env: &'env Env) -> Result<Unknown<'env>> {
+        to_object(env, &self.outlook)
+    }
+
+    // Index, name, MIME type, size and whether it is an
+    // embedded message of each attachment.
+    #[napi(ts_return_type = "AttachmentInfo[]")]
+    pub fn attachments<'env>(&self, env: &'env Env) -> Result<Unknown<'env>> {
+        env.to_js_value(&self.outlook.attachment_infos())
+    }
+
+    // Data of the attachment at index, the Buffer taking
+    // the decoded bytes over rather than a copy.
+    #[napi]
+    pub fn attachment(&self, index: u32) -> Result<Buffer> {
+        self.outlook
+            .attachment_bytes(index as usize)
+            .map(Buffer::from)
+            .map_err(NapiError::from_reason)
+    }
+}
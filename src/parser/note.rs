@@ -0,0 +1,127 @@
+use serde::{Deserialize, Serialize};
+
+use super::{decode::DataType, storage::Properties};
+
+// NoteColor is the background color of a sticky note.
+// Refer to MS-OXONOTE 2.2.1.1
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub enum NoteColor {
+    Blue,
+    Green,
+    Pink,
+    Yellow,
+    White,
+}
+
+impl NoteColor {
+    pub fn from_value(value: i64) -> Self {
+        match value {
+            0 => NoteColor::Blue,
+            1 => NoteColor::Green,
+            2 => NoteColor::Pink,
+            4 => NoteColor::White,
+            // Outlook creates yellow notes by default.
+            _ => NoteColor::Yellow,
+        }
+    }
+}
+
+// StickyNote holds the properties of an IPM.StickyNote item.
+// Sizes and positions are in pixels.
+// Refer to MS-OXONOTE 2.2.1
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct StickyNote {
+    pub color: NoteColor, // "NoteColor"
+    pub width: i64,       // "NoteWidth"
+    pub height: i64,      // "NoteHeight"
+    pub x: i64,           // "NoteX"
+    pub y: i64,           // "NoteY"
+    pub body: String,     // "Body"
+}
+
+impl StickyNote {
+    pub fn is_note_class(message_class: &str) -> bool {
+        let message_class = message_class.to_ascii_lowercase();
+        message_class == "ipm.stickynote" || message_class.starts_with("ipm.stickynote.")
+    }
+
+    pub fn create_from_props(props: &Properties) -> Option<Self> {
+        let message_class = props.get("MessageClass").and_then(DataType::as_str)?;
+        if !Self::is_note_class(message_class) {
+            return None;
+        }
+        let integer = |key: &str| props.get(key).and_then(DataType::as_i64).unwrap_or(0);
+        Some(Self {
+            color: props
+                .get("NoteColor")
+                .and_then(DataType::as_i64)
+                .map_or(NoteColor::Yellow, NoteColor::from_value),
+            width: integer("NoteWidth"),
+            height: integer("NoteHeight"),
+            x: integer("NoteX"),
+            y: integer("NoteY"),
+            body: props
+                .get("Body")
+                .and_then(DataType::as_str)
+                .unwrap_or_default()
+                .to_string(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        super::{
+            decode::DataType,
+            storage::{props, string, Properties},
+        },
+        NoteColor, StickyNote,
+    };
+
+    fn note_props(message_class: &str) -> Properties {
+        props(&[
+            ("MessageClass", string(message_class)),
+            ("NoteColor", DataType::PtypInteger32(1)),
+            ("NoteWidth", DataType::PtypInteger32(200)),
+            ("NoteHeight", DataType::PtypInteger32(166)),
+            ("NoteX", DataType::PtypInteger32(40)),
+            ("NoteY", DataType::PtypInteger32(60)),
+            ("Body", string("Buy milk")),
+        ])
+    }
+
+    #[test]
+    fn test_create_sticky_note() {
+        let note = StickyNote::create_from_props(&note_props("IPM.StickyNote")).unwrap();
+        assert_eq!(
+            note,
+            StickyNote {
+                color: NoteColor::Green,
+                width: 200,
+                height: 166,
+                x: 40,
+                y: 60,
+                body: "Buy milk".to_string(),
+            }
+        );
+    }
+
+    #[test]
+    fn test_default_color() {
+        let mut props = note_props("ipm.stickynote");
+        props.remove("NoteColor");
+        let note = StickyNote::create_from_props(&props).unwrap();
+        assert_eq!(note.color, NoteColor::Yellow);
+        assert_eq!(NoteColor::from_value(4), NoteColor::White);
+    }
+
+    #[test]
+    fn test_not_a_sticky_note() {
+        assert_eq!(StickyNote::create_from_props(&note_props("IPM.Note")), None);
+        assert!(StickyNote::is_note_class("IPM.StickyNote.Custom"));
+        assert!(!StickyNote::is_note_class("IPM.StickyNotes"));
+    }
+}
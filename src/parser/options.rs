@@ -0,0 +1,154 @@
+use serde::{Deserialize, Serialize};
+
+use super::{limits::Limits, mode::ParseMode};
+
+// ParserOptions tells what parsing a message loads and
+// decodes, e.g. a previewer may leave attachment data out
+// while a forensic pipeline wants embedded messages too.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ParserOptions {
+    pub mode: ParseMode,                // See ParseMode enum
+    pub attachments: bool,              // Load attachment data, "AttachDataObject"
+    pub embedded_messages: bool,        // Parse embedded messages into Attachment.embedded
+    pub bodies: bool,                   // Load "Body", "Html" and "RtfCompressed"
+    pub codepage: Option<u32>,          // Code page of 8-bit strings, in place of the message one
+    pub max_stream_size: Option<usize>, // Streams larger than this are skipped
+    pub limits: Limits,                 // See Limits struct, failing rather than skipping
+    pub provenance: bool,               // Record where each property is read from, see Provenance
+}
+
+impl Default for ParserOptions {
+    fn default() -> Self {
+        Self {
+            mode: ParseMode::Lenient,
+            attachments: true,
+            embedded_messages: true,
+            bodies: true,
+            codepage: None,
+            max_stream_size: None,
+            limits: Limits::default(),
+            provenance: false,
+        }
+    }
+}
+
+impl ParserOptions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn mode(mut self, mode: ParseMode) -> Self {
+        self.mode = mode;
+        self
+    }
+
+    pub fn attachments(mut self, attachments: bool) -> Self {
+        self.attachments = attachments;
+        self
+    }
+
+    pub fn embedded_messages(mut self, embedded_messages: bool) -> Self {
+        self.embedded_messages = embedded_messages;
+        self
+    }
+
+    pub fn bodies(mut self, bodies: bool) -> Self {
+        self.bodies = bodies;
+        self
+    }
+
+    pub fn codepage(mut self, codepage: u32) -> Self {
+        self.codepage = Some(codepage);
+        self
+    }
+
+    pub fn max_stream_size(mut self, size: usize) -> Self {
+        self.max_stream_size = Some(size);
+        self
+    }
+
+    pub fn limits(mut self, limits: Limits) -> Self {
+        self.limits = limits;
+        self
+    }
+
+    pub fn provenance(mut self, provenance: bool) -> Self {
+        self.provenance = provenance;
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        super::{mode::ParseMode, outlook::Outlook},
+        ParserOptions,
+    };
+
+    #[test]
+    fn test_attachments_and_bodies() {
+        let path = "data/attachment.msg";
+        let outlook = Outlook::from_path(path).unwrap();
+        assert!(!outlook.body.is_empty());
+        assert!(!outlook.attachments[0].payload.is_empty());
+
+        let options = ParserOptions::new().attachments(false).bodies(false);
+        let light = Outlook::from_path_with(path, &options).unwrap();
+        assert_eq!(light.subject, outlook.subject);
+        assert!(light.body.is_empty());
+        assert!(light.rtf_compressed.is_empty());
+        assert_eq!(light.attachments.len(), 3);
+        assert_eq!(light.attachments[0].display_name, "loan_proposal.doc".to_string());
+        assert!(light.attachments[0].payload.is_empty());
+        assert!(light.memory.total() < outlook.memory.total());
+
+        let options = ParserOptions::new().max_stream_size(10_000);
+        let small = Outlook::from_path_with(path, &options).unwrap();
+        assert!(small.attachments[0].payload.is_empty());
+        assert!(!small.attachments[2].payload.is_empty());
+    }
+
+    #[test]
+    fn test_embedded_messages() {
+        let path = "data/test_email.msg";
+        let outlook = Outlook::from_path(path).unwrap();
+        let embedded = outlook.attachments[0].embedded.as_ref().unwrap();
+        assert_eq!(embedded.subject, outlook.attachments[0].display_name);
+        assert!(!embedded.recipients.is_empty());
+        assert!(outlook.attachments[1].embedded.is_none());
+
+        let options = ParserOptions::new().embedded_messages(false);
+        let outlook = Outlook::from_path_with(path, &options).unwrap();
+        assert!(outlook.attachments[0].embedded.is_none());
+
+        let options = ParserOptions::new().mode(ParseMode::Strict);
+        assert!(Outlook::from_path_with(path, &options).is_err());
+    }
+
+    #[test]
+    fn test_codepage() {
+        use crate::ole::{writer::ROOT, Writer};
+
+        // A message holding its subject as an 8-bit string,
+        // "Привет" in Windows-1251.
+        let mut props = vec![0u8; 32];
+        props.extend_from_slice(&0x3FFD_0003u32.to_le_bytes());
+        props.extend_from_slice(&0u32.to_le_bytes());
+        props.extend_from_slice(&1251u64.to_le_bytes());
+        let mut writer = Writer::new();
+        writer.add_stream(ROOT, "__properties_version1.0", props);
+        writer.add_stream(ROOT, "__substg1.0_0037001E", b"\xcf\xf0\xe8\xe2\xe5\xf2\0".to_vec());
+        let msg = writer.to_bytes();
+
+        let outlook = Outlook::from_slice(&msg).unwrap();
+        assert_eq!(outlook.subject, "Привет".to_string());
+        let options = ParserOptions::new().codepage(1252);
+        let outlook = Outlook::from_slice_with(&msg, &options).unwrap();
+        assert_eq!(outlook.subject, "Ïðèâåò".to_string());
+
+        // Unicode strings do not depend on the code page.
+        let path = "data/unicode.msg";
+        let other = Outlook::from_path_with(path, &ParserOptions::new().codepage(1251)).unwrap();
+        assert_eq!(other.subject, Outlook::from_path(path).unwrap().subject);
+    }
+}
@@ -1,29 +1,80 @@
 use std::{
-    fs::File,
+    fs::{self, File},
+    io::{Read, Seek},
     path::Path
 };
 
+use chrono::{DateTime, Utc};
+
 use regex::Regex;
 
 use serde::{Deserialize, Serialize};
-use serde_json;
 
 use crate::ole;
 
 use super::{
+    appointment::Appointment,
+    auth::Authentication,
+    contact::Contact,
+    conversation::ConversationIndex,
+    decode::DataType,
+    directory::DirectoryEntry,
+    distlist::DistList,
+    envelope::Envelope,
+    entry_id::EntryId,
     error::Error,
+    flags::MessageFlags,
+    followup::FollowUpFlag,
+    headers::{addresses, message_ids, Headers},
+    importance::{Importance, Priority, Sensitivity},
+    label::SensitivityLabel,
+    lazy::LazyOutlook,
+    limits::Limits,
+    link::Link,
+    macros::has_macros,
+    memory::MemoryReport,
+    mode::{MissingPart, ParseMode},
+    options::ParserOptions,
+    note::StickyNote,
+    protection::ProtectionStatus,
+    provenance::Provenance,
+    recipient::Recipient,
+    reminder::Reminder,
+    report::{NonDeliveryReport, Receipt},
+    smime::Smime,
+    task::Task,
     storage::{
+        entry_path,
         Properties,
-        Storages
-    }
+        Recipients,
+        Storages,
+        ROOT_ID
+    },
+    timezone::TimeZone,
+    verbose::MessageProperties,
+    voting::Voting,
+    warning::ParseReport,
 };
 
 type Name = String;
 type Email = String;
 
+// Whether the address is an Exchange X.500 distinguished
+// name rather than an SMTP address.
+fn is_x500(address: &str) -> bool {
+    address.starts_with('/') && !address.contains('@')
+}
+
+// Values of "RecipientType".
+// Refer to MS-OXOMSG 2.2.3.1
+pub(super) const RECIPIENT_TO: i64 = 1;
+pub(super) const RECIPIENT_CC: i64 = 2;
+pub(super) const RECIPIENT_BCC: i64 = 3;
+
 // TransportHeaders contains transport specific message
 // envelope information for the email.
 #[derive(Debug, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 pub struct TransportHeaders {
     pub content_type: String,
     pub date: String,
@@ -33,7 +84,7 @@ pub struct TransportHeaders {
 
 impl TransportHeaders {
     fn extract_field(text: &str, re: Regex) -> String {
-        if text.len() == 0 {
+        if text.is_empty() {
             return String::from("");
         }
         let caps = re.captures(text);
@@ -51,7 +102,7 @@ impl TransportHeaders {
                 text,
                 Regex::new(r"(?i)Content-Type: (.*(\n\s.*)*)\r\n").unwrap(),
             ),
-            date: Self::extract_field(&text, Regex::new(r"(?i)Date: (.*(\n\s.*)*)\r\n").unwrap()),
+            date: Self::extract_field(text, Regex::new(r"(?i)Date: (.*(\n\s.*)*)\r\n").unwrap()),
             message_id: Self::extract_field(
                 text,
                 Regex::new(r"(?i)Message-ID: (.*(\n\s.*)*)\r\n").unwrap(),
@@ -66,45 +117,83 @@ impl TransportHeaders {
 
 // Person represents either Sender or Receiver.
 #[derive(Debug, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 pub struct Person {
     pub name: Name,
     pub email: Email,
 }
 
 impl Person {
-    fn new(name: Name, email: Email) -> Self {
+    pub(crate) fn new(name: Name, email: Email) -> Self {
         Self { name, email }
     }
-    fn create_from_props(props: &Properties, name_key: &str, email_keys: Vec<&str>) -> Self {
+    pub(crate) fn from_entry_id(entry_id: &EntryId) -> Option<Self> {
+        match entry_id.unwrap_entry_id() {
+            EntryId::OneOff {
+                display_name,
+                email_address,
+                ..
+            } => Some(Self::new(display_name.to_string(), email_address.to_string())),
+            EntryId::AddressBook { x500_dn } => Some(Self::new(String::new(), x500_dn.to_string())),
+            EntryId::Wrapped { .. } | EntryId::Other { .. } => None,
+        }
+    }
+    pub(crate) fn create_from_props(props: &Properties, name_key: &str, email_keys: Vec<&str>) -> Self {
         let name: String = props.get(name_key).map_or(String::new(), |x| x.into());
         // Get the fist email that can be found in props given email_keys.
         let email = email_keys
             .iter()
             .map(|&key| props.get(key).map_or(String::new(), |x| x.into()))
-            .find(|x| x.len() > 0)
+            .find(|x| !x.is_empty())
             .unwrap_or(String::from(""));
         Self { name, email }
     }
 }
 
+// Values of "AttachMethod" of attachments whose data is
+// kept at their path name rather than in the message: by
+// reference, by reference resolve and by reference only.
+// Refer to MS-OXCMSG 2.2.2.9
+const ATTACH_BY_REFERENCE: [i64; 3] = [2, 3, 4];
+
 // Attachment represents attachment object in the mail.
 #[derive(Debug, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 pub struct Attachment {
     pub display_name: String, // "DisplayName"
     pub payload: String,      // "AttachDataObject"
     pub extension: String,    // "AttachExtension"
     pub mime_tag: String,     // "AttachMimeTag"
     pub file_name: String,    // "AttachFilename"
+    pub embedded: Option<Box<Outlook>>, // "AttachDataObject" storage of an embedded message
+    pub has_macros: bool,     // "AttachDataObject" holding a VBA project, see has_macros
+    pub pathname: String,     // "AttachLongPathname"/"AttachPathname"
+    pub by_reference: bool,   // "AttachMethod" of an attachment without data, see pathname
 }
 
 impl Attachment {
-    fn create(storages: &Storages, idx: usize) -> Self {
+    pub(crate) fn create_from_props(props: &Properties, limits: &Limits) -> Self {
+        let value = |key: &str| props.get(key).map(String::from).unwrap_or_default();
         Self {
-            display_name: storages.get_val_from_attachment_or_default(idx, "DisplayName"),
-            payload: storages.get_val_from_attachment_or_default(idx, "AttachDataObject"),
-            extension: storages.get_val_from_attachment_or_default(idx, "AttachExtension"),
-            mime_tag: storages.get_val_from_attachment_or_default(idx, "AttachMimeTag"),
-            file_name: storages.get_val_from_attachment_or_default(idx, "AttachFilename"),
+            display_name: value("DisplayName"),
+            payload: value("AttachDataObject"),
+            extension: value("AttachExtension"),
+            mime_tag: value("AttachMimeTag"),
+            file_name: value("AttachFilename"),
+            embedded: None,
+            has_macros: match props.get("AttachDataObject") {
+                Some(DataType::PtypBinary(bytes)) => has_macros(bytes, limits),
+                _ => false,
+            },
+            pathname: ["AttachLongPathname", "AttachPathname"]
+                .iter()
+                .map(|key| value(key))
+                .find(|pathname| !pathname.is_empty())
+                .unwrap_or_default(),
+            by_reference: props
+                .get("AttachMethod")
+                .and_then(DataType::as_i64)
+                .is_some_and(|method| ATTACH_BY_REFERENCE.contains(&method)),
         }
     }
 }
@@ -114,21 +203,64 @@ impl Attachment {
 // MS-OXPROPS.
 // https://docs.microsoft.com/en-us/openspecs/exchange_server_protocols/ms-oxprops/f6ab1613-aefe-447d-a49c-18217230b148
 // Note: Prefixes are omitted for brevity.
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 pub struct Outlook {
     pub headers: TransportHeaders,    // "TransportMessageHeader"
     pub sender: Person,               // "SenderName" , "SenderSmtpAddress"/"SenderEmailAddress"
+    pub sent_representing: Person,    // "SentRepresentingName", "SentRepresentingSmtpAddress"/"SentRepresentingEmailAddress"
     pub to: Vec<Person>,              // "DisplayName", "SmtpAddress"/"EmailAddress"
-    pub cc: Vec<Person>,              // "DisplayCc"
-    pub bcc: Name,                    // "DisplayBcc"
+    pub cc: Vec<Person>,              // "DisplayName", "SmtpAddress"/"EmailAddress"
+    pub bcc: Vec<Person>,             // "DisplayName", "SmtpAddress"/"EmailAddress"
+    pub reply_to: Vec<Person>,        // "ReplyRecipientEntries", "ReplyRecipientNames"
+    pub recipients: Vec<Recipient>,   // See Recipient struct
     pub subject: String,              // "Subject"
+    pub message_class: String,        // "MessageClass"
     pub body: String,                 // "Body"
     pub rtf_compressed: String,       // "RtfCompressed"
     pub attachments: Vec<Attachment>, // See Attachment struct
+    pub time_zone: Option<TimeZone>,  // "AppointmentTimeZoneDefinitionStartDisplay"/"TimeZoneStruct"
+    pub appointment: Option<Appointment>, // See Appointment struct
+    pub reminder: Option<Reminder>,   // See Reminder struct
+    pub contact: Option<Contact>,     // See Contact struct
+    pub task: Option<Task>,           // See Task struct
+    pub sticky_note: Option<StickyNote>, // See StickyNote struct
+    pub dist_list: Option<DistList>,  // See DistList struct
+    pub non_delivery_report: Option<NonDeliveryReport>, // See NonDeliveryReport struct
+    pub receipt: Option<Receipt>,     // See Receipt struct
+    pub authentication: Authentication, // "Authentication-Results", "Received-SPF"
+    pub x_headers: Vec<(String, String)>, // "X-" fields of "TransportMessageHeaders"
+    pub message_id: String,           // "InternetMessageId"
+    pub in_reply_to: String,          // "InReplyToId"
+    pub references: Vec<String>,      // "InternetReferences"
+    pub conversation_index: Option<ConversationIndex>, // See ConversationIndex struct
+    pub importance: Importance,       // "Importance"
+    pub priority: Priority,           // "Priority"
+    pub sensitivity: Sensitivity,     // "Sensitivity"
+    pub flags: MessageFlags,          // "MessageFlags"
+    pub submit_time: Option<DateTime<Utc>>, // "ClientSubmitTime"
+    pub delivery_time: Option<DateTime<Utc>>, // "MessageDeliveryTime"
+    pub follow_up: Option<FollowUpFlag>, // See FollowUpFlag struct
+    pub voting: Option<Voting>,       // See Voting struct
+    pub smime: Option<Smime>,         // See Smime struct
+    pub protection: ProtectionStatus, // See ProtectionStatus struct
+    pub sensitivity_labels: Vec<SensitivityLabel>, // "msip_labels"
+    pub links: Vec<Link>,             // See Link struct
+    pub missing: Vec<MissingPart>,    // See MissingPart enum
+    #[serde(skip)]
+    pub properties: MessageProperties, // Every decoded property
+    #[serde(skip)]
+    pub directory: Vec<DirectoryEntry>, // See DirectoryEntry struct
+    #[serde(skip)]
+    pub memory: MemoryReport,         // See MemoryReport struct
+    #[serde(skip)]
+    pub report: ParseReport,          // See ParseReport struct
+    #[serde(skip)]
+    pub provenance: Provenance,       // See Provenance struct
 }
 
 impl Outlook {
-    fn extract_cc_from_headers(header_text: &str) -> Vec<Person> {
+    pub(super) fn extract_cc_from_headers(header_text: &str) -> Vec<Person> {
         // Format in header is:
         // CC: NAME <EMAIL>, NAME <EMAIL> \r\n
         let re = Regex::new(r"(?i)CC: .*(\r\n\t)?.*\r\n").unwrap();
@@ -162,70 +294,365 @@ impl Outlook {
         cc_persons
     }
 
-    fn populate(storages: &Storages) -> Self {
+    // Reply recipients are kept as entry IDs along with their
+    // names, or else in the Reply-To transport header field.
+    fn extract_reply_to(storages: &Storages, headers: &Headers) -> Vec<Person> {
+        let names = storages.get_val_from_root_or_default("ReplyRecipientNames");
+        let names: Vec<&str> = names.split(';').map(str::trim).collect();
+        let entry_ids = storages
+            .root
+            .get("ReplyRecipientEntries")
+            .and_then(DataType::as_bytes)
+            .and_then(|buff| EntryId::from_flat_entry_list(buff).ok())
+            .unwrap_or_default();
+        let reply_to: Vec<Person> = entry_ids
+            .iter()
+            .enumerate()
+            .filter_map(|(i, entry_id)| {
+                let mut person = entry_id.as_ref().and_then(Person::from_entry_id)?;
+                if person.name.is_empty() {
+                    person.name = names.get(i).unwrap_or(&"").to_string();
+                }
+                Some(Outlook::resolve_smtp(&storages.recipients, headers, person, &["Reply-To"]))
+            })
+            .collect();
+        if !reply_to.is_empty() {
+            return reply_to;
+        }
+        headers
+            .get("Reply-To")
+            .map(addresses)
+            .unwrap_or_default()
+            .into_iter()
+            .map(|(name, email)| Person::new(name, email))
+            .collect()
+    }
+
+    // Message IDs of the property, or else of the transport
+    // header field when the property was not kept.
+    pub(super) fn thread_ids(root: &Properties, headers: &Headers, key: &str, field: &str) -> Vec<String> {
+        let ids = message_ids(&root.get(key).map(String::from).unwrap_or_default());
+        if !ids.is_empty() {
+            return ids;
+        }
+        headers.get(field).map(message_ids).unwrap_or_default()
+    }
+
+    // Exchange users are known by their X.500 address, e.g.
+    // "/O=ORG/OU=.../CN=RECIPIENTS/CN=USER". Their SMTP address is
+    // looked up in the recipient table, where the same user may be
+    // listed with it, or else in the given transport header fields.
+    pub(super) fn resolve_smtp(
+        recipients: &Recipients,
+        headers: &Headers,
+        mut person: Person,
+        fields: &[&str],
+    ) -> Person {
+        if !is_x500(&person.email) {
+            return person;
+        }
+        let listed = recipients.iter().find_map(|recip_map| {
+            let email: String = recip_map.get("EmailAddress")?.into();
+            let smtp: String = recip_map.get("SmtpAddress")?.into();
+            (email.eq_ignore_ascii_case(&person.email) && !smtp.is_empty()).then_some(smtp)
+        });
+        if let Some(smtp) = listed {
+            person.email = smtp;
+            return person;
+        }
+        let mailboxes: Vec<(String, String)> = fields
+            .iter()
+            .flat_map(|field| headers.get_all(field))
+            .flat_map(addresses)
+            .collect();
+        let mailbox = mailboxes
+            .iter()
+            .find(|(name, _)| !name.is_empty() && name.eq_ignore_ascii_case(&person.name))
+            .or(match mailboxes.as_slice() {
+                [mailbox] => Some(mailbox),
+                _ => None,
+            });
+        if let Some((_, email)) = mailbox {
+            person.email = email.to_string();
+        }
+        person
+    }
+
+    pub(super) fn has_recipient_types(recipients: &Recipients) -> bool {
+        recipients
+            .iter()
+            .any(|recip_map| recip_map.contains_key("RecipientType"))
+    }
+
+    // Recipients of the given type. Recipients without a
+    // type are taken for primary ones.
+    pub(super) fn extract_recipients(recipients: &Recipients, headers: &Headers, recipient_type: i64) -> Vec<Person> {
+        let field = match recipient_type {
+            RECIPIENT_CC => "Cc",
+            RECIPIENT_BCC => "Bcc",
+            _ => "To",
+        };
+        recipients
+            .iter()
+            .filter(|recip_map| {
+                // The upper bits are flags, e.g. a recipient to resend to.
+                recip_map
+                    .get("RecipientType")
+                    .and_then(DataType::as_i64)
+                    .map_or(RECIPIENT_TO, |value| value & 0x0F)
+                    == recipient_type
+            })
+            .map(|recip_map| {
+                let person = Person::create_from_props(
+                    recip_map,
+                    "DisplayName",
+                    vec!["SmtpAddress", "EmailAddress"],
+                );
+                Outlook::resolve_smtp(recipients, headers, person, &[field])
+            })
+            .collect()
+    }
+
+    fn populate(storages: &Storages, limits: &Limits) -> Self {
         let headers_text = storages.get_val_from_root_or_default("TransportMessageHeaders");
         let headers = TransportHeaders::create_from_headers_text(&headers_text);
+        let transport_headers = Headers::parse(&headers_text);
 
-        // Outlook::extract_cc_from_headers(&headers_text);
+        let envelope = Envelope::create_from_props(&storages.root, &storages.recipients);
         Self {
             headers,
-            sender: Person::create_from_props(
-                &storages.root,
-                "SenderName",
-                vec!["SenderSmtpAddress", "SenderEmailAddress"],
-            ),
-            to: storages
-                .recipients
-                .iter()
-                .map(|recip_map| {
-                    Person::create_from_props(
-                        recip_map,
-                        "DisplayName",
-                        vec!["SmtpAddress", "EmailAddress"],
-                    )
-                })
-                .collect(),
-            cc: Outlook::extract_cc_from_headers(&headers_text),
-            bcc: storages.get_val_from_root_or_default("DisplayBcc"),
-            subject: storages.get_val_from_root_or_default("Subject"),
+            sender: envelope.sender,
+            sent_representing: envelope.sent_representing,
+            to: envelope.to,
+            cc: envelope.cc,
+            bcc: envelope.bcc,
+            reply_to: Outlook::extract_reply_to(storages, &transport_headers),
+            recipients: Recipient::create_all(&storages.recipients),
+            subject: envelope.subject,
+            message_class: envelope.message_class,
             body: storages.get_val_from_root_or_default("Body"),
             rtf_compressed: storages.get_val_from_root_or_default("RtfCompressed"),
             attachments: storages
                 .attachments
                 .iter()
-                .enumerate()
-                .map(|(i, _)| Attachment::create(storages, i))
+                .map(|props| Attachment::create_from_props(props, limits))
                 .collect(),
+            time_zone: TimeZone::create_from_props(&storages.root),
+            appointment: Appointment::create_from_props(&storages.root, &storages.recipients),
+            reminder: Reminder::create_from_props(&storages.root),
+            contact: Contact::create_from_props(&storages.root, &storages.attachments),
+            task: Task::create_from_props(&storages.root),
+            sticky_note: StickyNote::create_from_props(&storages.root),
+            dist_list: DistList::create_from_props(&storages.root),
+            non_delivery_report: NonDeliveryReport::create_from_props(
+                &storages.root,
+                &storages.recipients,
+            ),
+            receipt: Receipt::create_from_props(&storages.root),
+            authentication: Authentication::create_from_headers(&transport_headers),
+            x_headers: transport_headers
+                .x_headers()
+                .into_iter()
+                .map(|(name, value)| (name.to_string(), value.to_string()))
+                .collect(),
+            message_id: envelope.message_id,
+            in_reply_to: Self::thread_ids(&storages.root, &transport_headers, "InReplyToId", "In-Reply-To")
+                .into_iter()
+                .next()
+                .unwrap_or_default(),
+            references: Self::thread_ids(&storages.root, &transport_headers, "InternetReferences", "References"),
+            conversation_index: ConversationIndex::create_from_props(&storages.root),
+            importance: Importance::create_from_props(&storages.root),
+            priority: Priority::create_from_props(&storages.root),
+            sensitivity: Sensitivity::create_from_props(&storages.root),
+            flags: MessageFlags::create_from_props(&storages.root),
+            submit_time: envelope.submit_time,
+            delivery_time: envelope.delivery_time,
+            follow_up: FollowUpFlag::create_from_props(&storages.root),
+            voting: Voting::create_from_props(&storages.root),
+            smime: Smime::create_from_props(&storages.root, &storages.attachments),
+            protection: ProtectionStatus::create_from_props(
+                &storages.root,
+                &storages.attachments,
+                &transport_headers,
+            ),
+            sensitivity_labels: SensitivityLabel::create_from_props(&storages.root, &transport_headers),
+            links: Link::create_from_props(&storages.root),
+            missing: MissingPart::create_from_props(&storages.root, &storages.recipients),
+            properties: MessageProperties::create(storages),
+            directory: vec![],
+            memory: MemoryReport::default(),
+            report: ParseReport {
+                warnings: storages.warnings.clone(),
+                salvaged: false,
+            },
+            provenance: storages.provenance.clone(),
         }
     }
 
+    // Parses the message whose root storage has the given
+    // entry id, and the messages embedded in it.
+    pub(crate) fn create(parser: &ole::Reader, root_id: u32, options: &ParserOptions) -> Result<Self, Error> {
+        Self::create_counted(parser, root_id, options, &mut 0, 0)
+    }
+
+    // Same as create, decoded being the bytes decoded by
+    // the messages parsed before, which share the limit,
+    // and depth the nesting depth of the message.
+    #[cfg_attr(feature = "tracing", tracing::instrument(level = "debug", skip(parser, options, decoded)))]
+    fn create_counted(
+        parser: &ole::Reader,
+        root_id: u32,
+        options: &ParserOptions,
+        decoded: &mut usize,
+        depth: usize,
+    ) -> Result<Self, Error> {
+        options
+            .limits
+            .check_depth(depth)
+            .map_err(|err| err.in_entry(&entry_path(parser, root_id)))?;
+        let mut storages = Storages::create(parser, root_id, options);
+        storages.decoded = *decoded;
+        storages.process_streams(parser)?;
+        *decoded = storages.decoded;
+
+        let mut outlook = Self::populate(&storages, &options.limits);
+        if options.mode == ParseMode::Strict && !outlook.missing.is_empty() {
+            return Err(Error::Incomplete {
+                missing: outlook.missing,
+            });
+        }
+        if options.embedded_messages {
+            for (index, attachment) in outlook.attachments.iter_mut().enumerate() {
+                if let Some(storage) = storages.embedded_message(index) {
+                    attachment.embedded = Some(Box::new(Self::create_counted(parser, storage, options, decoded, depth + 1)?));
+                }
+            }
+        }
+        outlook.directory = DirectoryEntry::create(parser, root_id);
+        outlook.memory = MemoryReport::create(parser, &storages, &outlook);
+        Ok(outlook)
+    }
+
+    // Parses with the default options, see ParserOptions.
     pub fn from_path<P: AsRef<Path>>(path: P) -> Result<Self, Error> {
+        Self::from_path_with(path, &ParserOptions::default())
+    }
+
+    pub fn from_path_with<P: AsRef<Path>>(path: P, options: &ParserOptions) -> Result<Self, Error> {
+        if options.mode == ParseMode::Salvage {
+            return Self::salvage(&fs::read(path)?, options);
+        }
         let file = File::open(path)?;
-        let parser = ole::Reader::new(file)?;
-        let mut storages = Storages::new(&parser);
-        storages.process_streams(&parser);
+        let parser = options.limits.reader(file)?;
+        Self::create(&parser, ROOT_ID, options)
+    }
 
-        let outlook = Self::populate(&storages);
-        Ok(outlook)
+    // Parses a message read from a seekable source, e.g.
+    // a file in an archive, as its parts are needed rather
+    // than whole. Parts the options leave out, e.g.
+    // attachment data, are never read. The message starts
+    // at the current position of the source.
+    pub fn from_reader<R: Read + Seek>(reader: R) -> Result<Self, Error> {
+        Self::from_reader_with(reader, &ParserOptions::default())
+    }
+
+    pub fn from_reader_with<R: Read + Seek>(mut reader: R, options: &ParserOptions) -> Result<Self, Error> {
+        if options.mode == ParseMode::Salvage {
+            let mut bytes = Vec::new();
+            reader.read_to_end(&mut bytes)?;
+            return Self::salvage(&bytes, options);
+        }
+        let parser = options.limits.seek_reader(reader)?;
+        Self::create(&parser, ROOT_ID, options)
+    }
+
+    // Same as from_path, the file being mapped in memory
+    // rather than read, which halves the memory a large
+    // one takes. The file must not change while parsed.
+    #[cfg(feature = "mmap")]
+    pub fn from_mmap<P: AsRef<Path>>(path: P) -> Result<Self, Error> {
+        Self::from_mmap_with(path, &ParserOptions::default())
+    }
+
+    #[cfg(feature = "mmap")]
+    pub fn from_mmap_with<P: AsRef<Path>>(path: P, options: &ParserOptions) -> Result<Self, Error> {
+        if options.mode == ParseMode::Salvage {
+            return Self::from_path_with(path, options);
+        }
+        let parser = options.limits.mmap_reader(path)?;
+        Self::create(&parser, ROOT_ID, options)
+    }
+
+    // In lenient mode, parts the message lacks, e.g. the
+    // sender of a draft, are left empty and listed in
+    // missing. Strict mode fails on them instead.
+    pub fn from_path_with_mode<P: AsRef<Path>>(path: P, mode: ParseMode) -> Result<Self, Error> {
+        Self::from_path_with(path, &ParserOptions::new().mode(mode))
     }
 
     pub fn from_slice(slice: &[u8]) -> Result<Self, Error> {
-        let parser = ole::Reader::new(slice)?;
-        let mut storages = Storages::new(&parser);
-        storages.process_streams(&parser);
+        Self::from_slice_with(slice, &ParserOptions::default())
+    }
+
+    pub fn from_slice_with(slice: &[u8], options: &ParserOptions) -> Result<Self, Error> {
+        if options.mode == ParseMode::Salvage {
+            return Self::salvage(slice, options);
+        }
+        let parser = options.limits.slice_reader(slice)?;
+        Self::create(&parser, ROOT_ID, options)
+    }
 
-        let outlook = Self::populate(&storages);
+    // In salvage mode, a file the reader fails on, or whose
+    // chains or directory entries are broken, is read again
+    // by ole::Reader::salvage, recovering the streams it
+    // can reach. report.salvaged tells it was.
+    fn salvage(slice: &[u8], options: &ParserOptions) -> Result<Self, Error> {
+        if let Ok(parser) = options.limits.slice_reader(slice) {
+            if parser.validate().is_valid() {
+                return Self::create(&parser, ROOT_ID, options);
+            }
+        }
+        let parser = options.limits.salvage_reader(slice)?;
+        let mut outlook = Self::create(&parser, ROOT_ID, options)?;
+        outlook.report.salvaged = true;
         Ok(outlook)
     }
 
+    pub fn from_slice_with_mode(slice: &[u8], mode: ParseMode) -> Result<Self, Error> {
+        Self::from_slice_with(slice, &ParserOptions::new().mode(mode))
+    }
+
+    // Reads only what identifies the message, its sender,
+    // recipients, subject and dates, leaving out bodies and
+    // attachments, see Envelope.
+    pub fn envelope<P: AsRef<Path>>(path: P) -> Result<Envelope, Error> {
+        Ok(LazyOutlook::from_path(path)?.envelope())
+    }
+
+    // Whether the message was sent by a delegate on behalf
+    // of someone else, the sender being the delegate.
+    pub fn is_delegated(&self) -> bool {
+        let represented = &self.sent_representing;
+        let is_empty = |person: &Person| person.email.is_empty() && person.name.is_empty();
+        if is_empty(represented) || is_empty(&self.sender) {
+            return false;
+        }
+        if !represented.email.is_empty() && !self.sender.email.is_empty() {
+            return !represented.email.eq_ignore_ascii_case(&self.sender.email);
+        }
+        represented.name != self.sender.name
+    }
+
     pub fn to_json(&self) -> Result<String, Error> {
         Ok(serde_json::to_string(self)?)
     }
 }
 
 #[cfg(test)]
+#[allow(clippy::bool_assert_comparison, clippy::len_zero)]
 mod tests {
-    use super::{Outlook, Person, TransportHeaders};
+    use super::{Headers, Outlook, Person, TransportHeaders};
 
     #[test]
     fn test_invalid_file() {
@@ -237,14 +664,72 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_from_reader() {
+        use super::super::options::ParserOptions;
+        use std::{
+            cell::Cell,
+            io::{Cursor, Read, Result, Seek, SeekFrom},
+        };
+
+        // Counts the bytes read from the source.
+        struct Counted<'a>(Cursor<Vec<u8>>, &'a Cell<usize>);
+
+        impl Read for Counted<'_> {
+            fn read(&mut self, buf: &mut [u8]) -> Result<usize> {
+                let len = self.0.read(buf)?;
+                self.1.set(self.1.get() + len);
+                Ok(len)
+            }
+        }
+
+        impl Seek for Counted<'_> {
+            fn seek(&mut self, pos: SeekFrom) -> Result<u64> {
+                self.0.seek(pos)
+            }
+        }
+
+        let path = "data/attachment.msg";
+        let bytes = std::fs::read(path).unwrap();
+        let read = Cell::new(0);
+        let outlook = Outlook::from_reader(Counted(Cursor::new(bytes.clone()), &read)).unwrap();
+        assert_eq!(outlook.to_json().unwrap(), Outlook::from_path(path).unwrap().to_json().unwrap());
+
+        // Attachment data left out is never read.
+        let read = Cell::new(0);
+        let options = ParserOptions::new().attachments(false);
+        let outlook = Outlook::from_reader_with(Counted(Cursor::new(bytes.clone()), &read), &options).unwrap();
+        assert_eq!(outlook.subject, Outlook::from_path(path).unwrap().subject);
+        assert!(read.get() < bytes.len() / 2, "{} of {} bytes read", read.get(), bytes.len());
+    }
+
+    #[test]
+    fn test_version_4() {
+        use crate::ole::{Reader, Writer};
+
+        // The same message in a file with sectors of 4096 bytes.
+        for path in ["data/attachment.msg", "data/test_email.msg"] {
+            let mut writer = Writer::from_reader(&Reader::from_path(path).unwrap()).unwrap();
+            writer.set_major_version(4);
+            let bytes = writer.to_bytes();
+            let outlook = Outlook::from_slice(&bytes).unwrap();
+            assert_eq!(outlook.to_json().unwrap(), Outlook::from_path(path).unwrap().to_json().unwrap());
+            let outlook = Outlook::from_reader(std::io::Cursor::new(&bytes)).unwrap();
+            assert_eq!(outlook.to_json().unwrap(), Outlook::from_path(path).unwrap().to_json().unwrap());
+        }
+    }
+
     #[test]
     fn test_transport_header_test_email_1() {
-        use super::super::storage::Storages;
+        use super::super::{
+            options::ParserOptions,
+            storage::{Storages, ROOT_ID},
+        };
         use crate::ole::Reader;
 
         let parser = Reader::from_path("data/test_email.msg").unwrap();
-        let mut storages = Storages::new(&parser);
-        storages.process_streams(&parser);
+        let mut storages = Storages::create(&parser, ROOT_ID, &ParserOptions::default());
+        storages.process_streams(&parser).unwrap();
 
         let transport_text = storages.get_val_from_root_or_default("TransportMessageHeaders");
 
@@ -276,9 +761,14 @@ mod tests {
             outlook.to,
             vec![
                 Person {
-                    name: "marirs@outlook.com".to_string(),
-                    email: "marirs@outlook.com".to_string()
+                    name: "Sriram Govindan".to_string(),
+                    email: "marirs@gmail.com".to_string()
                 },
+            ]
+        );
+        assert_eq!(
+            outlook.cc,
+            vec![
                 Person {
                     name: "Sriram Govindan".to_string(),
                     email: "marirs@aol.in".to_string()
@@ -287,6 +777,11 @@ mod tests {
                     name: "marirs@outlook.in".to_string(),
                     email: "marirs@outlook.in".to_string()
                 },
+            ]
+        );
+        assert_eq!(
+            outlook.bcc,
+            vec![
                 Person {
                     name: "Sriram Govindan".to_string(),
                     email: "marirs@aol.in".to_string()
@@ -343,29 +838,9 @@ mod tests {
         assert_eq!(
             outlook.to,
             vec![
-                Person {
-                    name: "marirs@outlook.com".to_string(),
-                    email: "marirs@outlook.com".to_string()
-                },
-                Person {
-                    name: "Sriram Govindan".to_string(),
-                    email: "marirs@aol.in".to_string()
-                },
-                Person {
-                    name: "marirs@outlook.in".to_string(),
-                    email: "marirs@outlook.in".to_string()
-                },
-                Person {
-                    name: "Sriram Govindan".to_string(),
-                    email: "marirs@aol.in".to_string()
-                },
                 Person {
                     name: "Sriram Govindan".to_string(),
-                    email: "marirs@outlook.com".to_string()
-                },
-                Person {
-                    name: "marirs@outlook.in".to_string(),
-                    email: "marirs@outlook.in".to_string()
+                    email: "marirs@gmail.com".to_string()
                 },
             ]
         );
@@ -514,10 +989,6 @@ mod tests {
                     name: "brianzhou@me.com".to_string(),
                     email: "brianzhou@me.com".to_string()
                 },
-                Person {
-                    name: "Brian Zhou".to_string(),
-                    email: "brizhou@gmail.com".to_string(),
-                }
             ]
         );
 
@@ -549,7 +1020,10 @@ mod tests {
 
         assert_eq!(
             outlook.cc,
-            vec![]
+            vec![
+                Person::new("Sriram Govindan".to_string(), "marirs@aol.in".to_string()),
+                Person::new("marirs@outlook.in".to_string(), "marirs@outlook.in".to_string()),
+            ]
         );
     }
 
@@ -559,5 +1033,162 @@ mod tests {
         let outlook = Outlook::from_path(path).unwrap();
         let json = outlook.to_json().unwrap();
         assert_eq!(json.len() > 0, true);
+
+        // Fields skipped by to_json are left to their default.
+        let parsed: Outlook = serde_json::from_str(&json).unwrap();
+        assert_eq!(parsed.subject, outlook.subject);
+        assert_eq!(parsed.recipients, outlook.recipients);
+        assert!(parsed.properties.root.is_empty());
+    }
+
+    #[test]
+    fn test_time_zone_absent_from_note() {
+        let path = "data/test_email.msg";
+        let outlook = Outlook::from_path(path).unwrap();
+        assert_eq!(outlook.time_zone, None);
+        assert_eq!(outlook.appointment, None);
+        assert_eq!(outlook.contact, None);
+        assert_eq!(outlook.task, None);
+        assert_eq!(outlook.sticky_note, None);
+        assert_eq!(outlook.dist_list, None);
+        assert_eq!(outlook.non_delivery_report, None);
+        assert_eq!(outlook.receipt, None);
+    }
+
+    #[test]
+    fn test_sent_on_behalf() {
+        let outlook = Outlook::from_path("data/test_email.msg").unwrap();
+        // The sender was not kept on this one.
+        assert_eq!(
+            outlook.sent_representing,
+            Person::new(
+                "marirs@outlook.com".to_string(),
+                "marirs@outlook.com".to_string()
+            )
+        );
+        assert!(!outlook.is_delegated());
+
+        let mut outlook = Outlook::from_path("data/test_email_4.msg").unwrap();
+        assert_eq!(outlook.sent_representing, outlook.sender);
+        assert!(!outlook.is_delegated());
+
+        outlook.sent_representing = Person::new(
+            "Jane Doe".to_string(),
+            outlook.sender.email.to_uppercase(),
+        );
+        assert!(!outlook.is_delegated());
+        outlook.sent_representing = Person::new(
+            "Jane Doe".to_string(),
+            "jane@contoso.com".to_string(),
+        );
+        assert!(outlook.is_delegated());
+        outlook.sent_representing = Person::new(String::new(), String::new());
+        assert!(!outlook.is_delegated());
+    }
+
+    #[test]
+    fn test_resolve_smtp() {
+        use super::super::{decode::DataType, storage::Properties};
+
+        let x500 = "/O=ORG/OU=EXCHANGE ADMINISTRATIVE GROUP/CN=RECIPIENTS/CN=JDOE";
+        let person = || Person::new("Jane Doe".to_string(), x500.to_string());
+        let headers = Headers::parse(
+            "From: \"Jane Doe\" <jane@contoso.com>\r\n\
+             To: John <john@contoso.com>, Jack <jack@contoso.com>\r\n",
+        );
+        let resolved = Outlook::resolve_smtp(&vec![], &headers, person(), &["From"]);
+        assert_eq!(resolved.email, "jane@contoso.com".to_string());
+        // Several addresses, none of them with the same name.
+        let resolved = Outlook::resolve_smtp(&vec![], &headers, person(), &["To"]);
+        assert_eq!(resolved.email, x500.to_string());
+
+        let mut recip_map = Properties::new();
+        recip_map.insert("EmailAddress".to_string(), DataType::PtypString(x500.to_lowercase()));
+        recip_map.insert("SmtpAddress".to_string(), DataType::PtypString("jdoe@org.com".to_string()));
+        let resolved = Outlook::resolve_smtp(&vec![recip_map], &headers, person(), &["From"]);
+        assert_eq!(resolved.email, "jdoe@org.com".to_string());
+
+        let smtp = Person::new("Jane Doe".to_string(), "jane@x.com".to_string());
+        assert_eq!(Outlook::resolve_smtp(&vec![], &headers, smtp, &["From"]).email, "jane@x.com");
+
+        // The sender of this one is only known by its X.500
+        // address, which neither its recipients nor headers
+        // resolve.
+        let outlook = Outlook::from_path("data/test_email_2.msg").unwrap();
+        assert!(outlook.sender.email.starts_with("/O=FIRST ORGANIZATION/"));
+        assert_eq!(outlook.sent_representing.email, outlook.sender.email);
+    }
+
+    #[test]
+    fn test_reply_to() {
+        use super::super::{
+            decode::DataType,
+            entry_id::tests::{flat_entry_list, one_off_entry_id},
+            options::ParserOptions,
+            storage::{Storages, ROOT_ID},
+        };
+        use crate::ole::Reader;
+
+        let parser = Reader::from_path("data/test_email.msg").unwrap();
+        let mut storages = Storages::create(&parser, ROOT_ID, &ParserOptions::default());
+        storages.process_streams(&parser).unwrap();
+        storages.root.insert(
+            "ReplyRecipientEntries".to_string(),
+            DataType::PtypBinary(flat_entry_list(&[
+                one_off_entry_id("", "SMTP", "jane@contoso.com"),
+                one_off_entry_id("Bob", "SMTP", "bob@contoso.com"),
+            ])),
+        );
+        storages.root.insert(
+            "ReplyRecipientNames".to_string(),
+            DataType::PtypString("Jane Doe; Bob".to_string()),
+        );
+        let headers = Headers::parse("Reply-To: Al <al@contoso.com>\r\n");
+        assert_eq!(
+            Outlook::extract_reply_to(&storages, &headers),
+            vec![
+                Person::new("Jane Doe".to_string(), "jane@contoso.com".to_string()),
+                Person::new("Bob".to_string(), "bob@contoso.com".to_string()),
+            ]
+        );
+        storages.root.remove("ReplyRecipientEntries");
+        assert_eq!(
+            Outlook::extract_reply_to(&storages, &headers),
+            vec![Person::new("Al".to_string(), "al@contoso.com".to_string())]
+        );
+
+        let outlook = Outlook::from_path("data/test_email_4.msg").unwrap();
+        assert_eq!(
+            outlook.reply_to,
+            vec![Person::new(
+                "Account Testing".to_string(),
+                "account-testing7777@protonmail.com".to_string()
+            )]
+        );
+    }
+
+    #[test]
+    fn test_threading_ids() {
+        let outlook = Outlook::from_path("data/test_email_1.msg").unwrap();
+        assert_eq!(
+            outlook.message_id,
+            "PSAPR02MB4837B16144ECEDB641FCDE04FD6D9@PSAPR02MB4837.apcprd02.prod.outlook.com"
+                .to_string()
+        );
+        assert_eq!(outlook.in_reply_to, String::new());
+        assert!(outlook.references.is_empty());
+
+        let outlook = Outlook::from_path("data/test_email_4.msg").unwrap();
+        assert!(outlook.message_id.ends_with("@protonmail.com"));
+        assert!(!outlook.message_id.starts_with('<'));
+    }
+
+    #[test]
+    fn test_reminder_not_set() {
+        let path = "data/test_email.msg";
+        let outlook = Outlook::from_path(path).unwrap();
+        let reminder = outlook.reminder.unwrap();
+        assert!(!reminder.set);
+        assert_eq!(reminder.signal_time, None);
     }
 }
@@ -0,0 +1,309 @@
+use serde::{Deserialize, Serialize};
+
+use super::{
+    auth::Verdict,
+    link::LinkSource,
+    outlook::{Attachment, Outlook},
+};
+
+// Extensions of files Windows runs when opened, programs
+// and scripts alike.
+const EXECUTABLE_EXTENSIONS: [&str; 20] = [
+    "exe", "scr", "com", "pif", "bat", "cmd", "msi", "cpl", "dll", "lnk", "hta", "js", "jse", "vbs", "vbe", "wsf",
+    "wsh", "ps1", "jar", "reg",
+];
+
+// Characters, and pairs of them, standing in for others
+// in lookalike domains, e.g. "paypa1.com" or "rnicrosoft.com".
+const HOMOGLYPHS: [(&str, &str); 8] = [
+    ("rn", "m"),
+    ("vv", "w"),
+    ("0", "o"),
+    ("1", "l"),
+    ("i", "l"),
+    ("3", "e"),
+    ("5", "s"),
+    ("@", "a"),
+];
+
+// Second-level labels registries of country code domains
+// hand names out under, e.g. "co" of "bank.co.uk".
+const SECOND_LEVEL_LABELS: [&str; 16] = [
+    "ac", "co", "com", "edu", "go", "gob", "gov", "ltd", "mil", "ne", "net", "nic", "or", "org", "plc", "sch",
+];
+
+// Indicator is a sign of phishing found by Outlook::analyze.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum Indicator {
+    // Display name of the sender holding an address other
+    // than the one the message is from.
+    DisplayNameMismatch { name: String, address: String },
+    // Reply-To address in another domain than the sender.
+    ReplyToMismatch { reply_to: String, from: String },
+    // Authentication method a receiver recorded as failed.
+    AuthenticationFailed { method: String, verdict: Verdict },
+    // Link to a domain resembling that of the sender
+    // without being it.
+    LookalikeLink { url: String, domain: String, sender_domain: String },
+    // Attachment that runs when opened.
+    ExecutableAttachment { file_name: String },
+}
+
+// PhishingReport holds the indicators of a message, none of
+// them proving phishing on its own.
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PhishingReport {
+    pub indicators: Vec<Indicator>,
+}
+
+impl PhishingReport {
+    pub fn is_suspicious(&self) -> bool {
+        !self.indicators.is_empty()
+    }
+}
+
+// Domain of an address, lowercased.
+fn address_domain(address: &str) -> Option<String> {
+    let (_, domain) = address.trim().rsplit_once('@')?;
+    let domain = domain.trim_end_matches('>').trim().to_ascii_lowercase();
+    (!domain.is_empty()).then_some(domain)
+}
+
+// Host of an http or https URL, lowercased.
+fn url_host(url: &str) -> Option<String> {
+    let lower = url.trim().to_ascii_lowercase();
+    let rest = lower.strip_prefix("https://").or_else(|| lower.strip_prefix("http://"))?;
+    let authority = rest.split(['/', '?', '#']).next().unwrap_or_default();
+    let host = authority.rsplit('@').next().unwrap_or_default();
+    let host = host.split(':').next().unwrap_or_default().trim_end_matches('.');
+    (!host.is_empty()).then(|| host.to_string())
+}
+
+// Last two labels of a domain, e.g. "example.com" for
+// "mail.example.com", or three under the second-level
+// domains of country code ones, e.g. "bank.co.uk".
+fn base_domain(domain: &str) -> &str {
+    let labels: Vec<&str> = domain.rsplitn(4, '.').collect();
+    let count = match labels.as_slice() {
+        [tld, second, _, ..] if tld.len() == 2 && SECOND_LEVEL_LABELS.contains(second) => 3,
+        _ => 2,
+    };
+    match domain.rmatch_indices('.').nth(count - 1) {
+        Some((index, _)) => &domain[index + 1..],
+        None => domain,
+    }
+}
+
+fn skeleton(domain: &str) -> String {
+    HOMOGLYPHS
+        .iter()
+        .fold(domain.to_string(), |domain, (glyph, letter)| domain.replace(glyph, letter))
+}
+
+// Number of characters to insert, delete or replace to
+// turn a into b.
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut row: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.chars().enumerate() {
+        let mut previous = row[0];
+        row[0] = i + 1;
+        for (j, cb) in b.iter().enumerate() {
+            let current = row[j + 1];
+            row[j + 1] = match ca == *cb {
+                true => previous,
+                false => 1 + previous.min(row[j]).min(current),
+            };
+            previous = current;
+        }
+    }
+    row[b.len()]
+}
+
+// Whether a domain imitates another, by one character, by
+// characters looking alike, or by adding to its name,
+// e.g. "example-login.com" for "example.com".
+fn is_lookalike(domain: &str, sender: &str) -> bool {
+    let (domain, sender) = (base_domain(domain), base_domain(sender));
+    if domain == sender {
+        return false;
+    }
+    let name = sender.split('.').next().unwrap_or_default();
+    let label = domain.split('.').next().unwrap_or_default();
+    edit_distance(domain, sender) == 1
+        || skeleton(domain) == skeleton(sender)
+        || (name.len() >= 4 && label.contains(name))
+}
+
+fn is_executable(attachment: &Attachment) -> bool {
+    [&attachment.file_name, &attachment.display_name, &attachment.extension]
+        .iter()
+        .filter_map(|name| name.trim().rsplit('.').next())
+        .any(|extension| EXECUTABLE_EXTENSIONS.contains(&extension.to_ascii_lowercase().as_str()))
+}
+
+impl Outlook {
+    // Heuristics pointing at phishing, from the sender,
+    // the authentication receivers recorded, the links of
+    // the bodies and the attachments. See Indicator.
+    pub fn analyze(&self) -> PhishingReport {
+        let mut indicators = vec![];
+        let from = self.sender.email.trim().to_ascii_lowercase();
+        let sender_domain = address_domain(&from);
+
+        for word in self.sender.name.split(|c: char| c.is_whitespace() || "<>\"'()[],;".contains(c)) {
+            if address_domain(word).is_some() && !word.eq_ignore_ascii_case(&from) {
+                indicators.push(Indicator::DisplayNameMismatch {
+                    name: self.sender.name.clone(),
+                    address: self.sender.email.clone(),
+                });
+                break;
+            }
+        }
+
+        if let Some(sender_domain) = &sender_domain {
+            for person in &self.reply_to {
+                if address_domain(&person.email).is_some_and(|domain| base_domain(&domain) != base_domain(sender_domain)) {
+                    indicators.push(Indicator::ReplyToMismatch {
+                        reply_to: person.email.clone(),
+                        from: self.sender.email.clone(),
+                    });
+                }
+            }
+        }
+
+        let failed = |verdict: Verdict| matches!(verdict, Verdict::Fail | Verdict::SoftFail | Verdict::PermError);
+        let authentication = &self.authentication;
+        let results = authentication.results.iter().map(|result| (result.method.to_ascii_lowercase(), result.verdict));
+        let received_spf = authentication.received_spf.iter().map(|spf| ("spf".to_string(), spf.verdict));
+        for (method, verdict) in results.chain(received_spf).filter(|(_, verdict)| failed(*verdict)) {
+            let indicator = Indicator::AuthenticationFailed { method, verdict };
+            if !indicators.contains(&indicator) {
+                indicators.push(indicator);
+            }
+        }
+
+        if let Some(sender_domain) = &sender_domain {
+            let body_links = self.links.iter().filter(|link| link.sources != [LinkSource::Headers]);
+            for link in body_links {
+                if let Some(domain) = url_host(&link.url).filter(|domain| is_lookalike(domain, sender_domain)) {
+                    let indicator = Indicator::LookalikeLink {
+                        url: link.url.clone(),
+                        domain,
+                        sender_domain: sender_domain.clone(),
+                    };
+                    if !indicators.contains(&indicator) {
+                        indicators.push(indicator);
+                    }
+                }
+            }
+        }
+
+        for attachment in self.attachments.iter().filter(|attachment| is_executable(attachment)) {
+            let file_name = match attachment.file_name.is_empty() {
+                true => attachment.display_name.clone(),
+                false => attachment.file_name.clone(),
+            };
+            indicators.push(Indicator::ExecutableAttachment { file_name });
+        }
+        PhishingReport { indicators }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        super::{auth::Verdict, builder::OutlookBuilder},
+        base_domain, edit_distance, is_lookalike, url_host, Indicator,
+    };
+
+    #[test]
+    fn test_url_host() {
+        assert_eq!(url_host("HTTPS://User@Mail.Example.com:8080/login?x=1"), Some("mail.example.com".to_string()));
+        assert_eq!(url_host("http://example.com."), Some("example.com".to_string()));
+        assert_eq!(url_host("mailto:someone@example.com"), None);
+        assert_eq!(base_domain("mail.example.com"), "example.com");
+        assert_eq!(base_domain("localhost"), "localhost");
+        assert_eq!(base_domain("secure.bank.co.uk"), "bank.co.uk");
+        assert_eq!(base_domain("bank.co.uk"), "bank.co.uk");
+        assert_eq!(base_domain("www.shop.com.au"), "shop.com.au");
+        assert_eq!(base_domain("co.uk"), "co.uk");
+        assert_eq!(base_domain("mail.example.co"), "example.co");
+    }
+
+    #[test]
+    fn test_is_lookalike() {
+        assert_eq!(edit_distance("example", "exarnple"), 2);
+        assert!(is_lookalike("paypa1.com", "paypal.com"));
+        assert!(is_lookalike("login.rnicrosoft.com", "microsoft.com"));
+        assert!(is_lookalike("example-secure.com", "example.com"));
+        assert!(is_lookalike("exampe.com", "example.com"));
+        assert!(!is_lookalike("mail.example.com", "example.com"));
+        assert!(!is_lookalike("wikipedia.org", "example.com"));
+    }
+
+    #[test]
+    fn test_analyze() {
+        let outlook = OutlookBuilder::new()
+            .sender("support@bank.com", "alerts@example.com")
+            .to("Bob", "bob@example.com")
+            .html("<p>Log in <a href=\"https://examp1e.com/login\">here</a> or \
+                   <a href=\"https://secure.example.com/\">there</a></p>")
+            .transport_headers(
+                "Authentication-Results: mx.example.com; spf=fail smtp.mailfrom=example.com; dkim=pass header.d=example.com\r\n\
+                 Reply-To: collect@elsewhere.test\r\n",
+            )
+            .attachment("invoice.pdf.exe", "application/octet-stream", vec![0x4D, 0x5A])
+            .attachment("invoice.pdf", "application/pdf", b"%PDF".to_vec())
+            .build()
+            .unwrap();
+        let report = outlook.analyze();
+        assert!(report.is_suspicious());
+        assert_eq!(
+            report.indicators,
+            vec![
+                Indicator::DisplayNameMismatch {
+                    name: "support@bank.com".to_string(),
+                    address: "alerts@example.com".to_string(),
+                },
+                Indicator::ReplyToMismatch {
+                    reply_to: "collect@elsewhere.test".to_string(),
+                    from: "alerts@example.com".to_string(),
+                },
+                Indicator::AuthenticationFailed {
+                    method: "spf".to_string(),
+                    verdict: Verdict::Fail,
+                },
+                Indicator::LookalikeLink {
+                    url: "https://examp1e.com/login".to_string(),
+                    domain: "examp1e.com".to_string(),
+                    sender_domain: "example.com".to_string(),
+                },
+                Indicator::ExecutableAttachment {
+                    file_name: "invoice.pdf.exe".to_string(),
+                },
+            ]
+        );
+
+        // Both are under co.uk, in different domains.
+        let outlook = OutlookBuilder::new()
+            .sender("Bank", "alerts@bank.co.uk")
+            .transport_headers("Reply-To: x@attacker.co.uk, help@mail.bank.co.uk\r\n")
+            .build()
+            .unwrap();
+        assert_eq!(
+            outlook.analyze().indicators,
+            vec![Indicator::ReplyToMismatch {
+                reply_to: "x@attacker.co.uk".to_string(),
+                from: "alerts@bank.co.uk".to_string(),
+            }]
+        );
+
+        let outlook = OutlookBuilder::new()
+            .sender("Alice", "alice@example.com")
+            .body("See https://www.example.com/report")
+            .build()
+            .unwrap();
+        assert!(!outlook.analyze().is_suspicious());
+    }
+}
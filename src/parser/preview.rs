@@ -0,0 +1,124 @@
+use super::outlook::Outlook;
+
+// Lines starting the message replied to or forwarded,
+// after which everything is quoted.
+const QUOTE_SEPARATORS: [&str; 3] = [
+    "-----Original Message-----",
+    "-----Forwarded Message-----",
+    "---------- Forwarded message ---------",
+];
+
+// Whether the line starts the quoted message, as the
+// separator, attribution or header block mail clients
+// write above it.
+fn starts_quote(lines: &[&str], index: usize) -> bool {
+    let line = lines[index].trim();
+    if QUOTE_SEPARATORS.iter().any(|separator| line.eq_ignore_ascii_case(separator)) {
+        return true;
+    }
+    // "On Mon, 1 Jan 2024, Bob <bob@example.com> wrote:",
+    // as Gmail and Thunderbird write it.
+    if line.starts_with("On ") && line.ends_with("wrote:") {
+        return true;
+    }
+    // The line Outlook draws above the header block.
+    if line.len() >= 10 && line.chars().all(|c| c == '_') {
+        return true;
+    }
+    // The header block itself, "From:" followed by "Sent:"
+    // or "Date:".
+    line.starts_with("From:")
+        && lines[index + 1..]
+            .iter()
+            .take(3)
+            .any(|next| next.trim_start().starts_with("Sent:") || next.trim_start().starts_with("Date:"))
+}
+
+// Text of a body without the message it quotes, and the
+// lines quoted with ">".
+fn strip_quotes(text: &str) -> String {
+    let lines: Vec<&str> = text.lines().collect();
+    let end = (0..lines.len())
+        .find(|index| starts_quote(&lines, *index))
+        .unwrap_or(lines.len());
+    lines[..end]
+        .iter()
+        .filter(|line| !line.trim_start().starts_with('>'))
+        .copied()
+        .collect::<Vec<&str>>()
+        .join("\n")
+}
+
+impl Outlook {
+    // First length characters of the body, as mail clients
+    // show it in their message list: the text the sender
+    // wrote, without the message quoted below it, white
+    // space collapsed to single spaces.
+    pub fn preview(&self, length: usize) -> String {
+        let text = strip_quotes(&self.body_text());
+        text.split_whitespace()
+            .collect::<Vec<&str>>()
+            .join(" ")
+            .chars()
+            .take(length)
+            .collect::<String>()
+            .trim_end()
+            .to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        super::{builder::OutlookBuilder, outlook::Outlook},
+        strip_quotes,
+    };
+
+    #[test]
+    fn test_strip_quotes() {
+        assert_eq!(
+            strip_quotes("Sounds good.\r\n\r\nOn Mon, 1 Jan 2024, Bob wrote:\r\n> Lunch?\r\n"),
+            "Sounds good.\n".to_string()
+        );
+        assert_eq!(
+            strip_quotes("Inline:\n> asked\nanswered\n"),
+            "Inline:\nanswered".to_string()
+        );
+        assert_eq!(
+            strip_quotes("Done.\n\n________________________________\nFrom: Bob\nSent: Monday\n"),
+            "Done.\n".to_string()
+        );
+        assert_eq!(
+            strip_quotes("See below\n-----Original Message-----\nFrom: Bob"),
+            "See below".to_string()
+        );
+        assert_eq!(
+            strip_quotes("From: the team\nThanks all"),
+            "From: the team\nThanks all".to_string()
+        );
+    }
+
+    #[test]
+    fn test_preview() {
+        let outlook = OutlookBuilder::new()
+            .subject("Re: Lunch")
+            .body("Sounds   good,\r\nsee you at noon.\r\n\r\nOn Mon, Bob wrote:\r\n> Lunch?")
+            .build()
+            .unwrap();
+        assert_eq!(outlook.preview(100), "Sounds good, see you at noon.".to_string());
+        assert_eq!(outlook.preview(13), "Sounds good,".to_string());
+        assert_eq!(outlook.preview(0), String::new());
+
+        let outlook = OutlookBuilder::new()
+            .subject("Html")
+            .html("<p>Grüße</p><p>aus Köln</p>")
+            .build()
+            .unwrap();
+        assert_eq!(outlook.preview(9), "Grüße aus".to_string());
+
+        let outlook = Outlook::from_path("data/test_email.msg").unwrap();
+        let preview = outlook.preview(80);
+        assert!(preview.chars().count() <= 80);
+        assert!(!preview.contains(['\r', '\n']));
+    }
+}
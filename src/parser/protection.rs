@@ -0,0 +1,310 @@
+use serde::{Deserialize, Serialize};
+
+use super::{
+    decode::DataType,
+    der::{Tlv, TAG_INTEGER, TAG_OID, TAG_SEQUENCE, TAG_SET},
+    error::DataTypeError,
+    headers::Headers,
+    smime::{content_info, format_name, name_attributes, pkcs7_der, serial_number, OID_SIGNED_DATA},
+    storage::{Attachments, Properties},
+};
+
+// Object identifiers of the encrypted CMS content types.
+// Refer to RFC 5652 6.1 and RFC 5083
+const OID_ENVELOPED_DATA: &str = "1.2.840.113549.1.7.3";
+const OID_AUTH_ENVELOPED_DATA: &str = "1.2.840.113549.1.9.16.1.23";
+
+const ENCRYPTION_ALGORITHMS: [(&str, &str); 8] = [
+    ("1.2.840.113549.3.2", "rc2-cbc"),
+    ("1.2.840.113549.3.7", "des-ede3-cbc"),
+    ("2.16.840.1.101.3.4.1.2", "aes128-cbc"),
+    ("2.16.840.1.101.3.4.1.6", "aes128-gcm"),
+    ("2.16.840.1.101.3.4.1.22", "aes192-cbc"),
+    ("2.16.840.1.101.3.4.1.26", "aes192-gcm"),
+    ("2.16.840.1.101.3.4.1.42", "aes256-cbc"),
+    ("2.16.840.1.101.3.4.1.46", "aes256-gcm"),
+];
+
+// Content class and attachment of rights-managed messages.
+// Refer to MS-OXORMMS 2.2.1
+const RPMSG_CONTENT_CLASS: &str = "rpmsg.message";
+const RPMSG_ATTACHMENT: &str = "message.rpmsg";
+const RPMSG_MIME_TAG: &str = "application/x-microsoft-rpmsg-message";
+
+// ProtectionKind tells what keeps the content of a
+// message from being read as it is.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub enum ProtectionKind {
+    Unprotected,
+    // S/MIME signed, the content being readable.
+    Signed,
+    // S/MIME encrypted for the holders of some certificates.
+    Encrypted,
+    // Protected by Information Rights Management.
+    RightsManaged,
+}
+
+// KeyRecipient is a certificate the content encryption
+// key of an S/MIME encrypted message was encrypted with.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct KeyRecipient {
+    pub issuer: String,        // issuer of the certificate
+    pub serial_number: String, // serial number of the certificate, in hex
+}
+
+// ProtectionStatus tells why a message has no readable
+// body, its content being kept in an attachment.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct ProtectionStatus {
+    pub kind: ProtectionKind,              // See ProtectionKind enum
+    pub container: String,                 // attachment with the content, e.g. "smime.p7m"
+    pub encryption_algorithm: String,      // e.g. "aes256-cbc", for S/MIME encrypted messages
+    pub key_recipients: Vec<KeyRecipient>, // See KeyRecipient struct
+}
+
+// File name of an attachment as it was attached.
+fn attachment_name(attachment: &Properties) -> String {
+    ["AttachLongFilename", "AttachFilename", "DisplayName"]
+        .iter()
+        .filter_map(|key| attachment.get(*key).and_then(DataType::as_str))
+        .find(|name| !name.is_empty())
+        .unwrap_or_default()
+        .to_string()
+}
+
+fn attachment_mime_tag(attachment: &Properties) -> String {
+    attachment
+        .get("AttachMimeTag")
+        .and_then(DataType::as_str)
+        .unwrap_or_default()
+        .to_ascii_lowercase()
+}
+
+// Refer to RFC 5652 6.1 and 6.2.1
+fn parse_enveloped_data(content: Tlv) -> Result<(String, Vec<KeyRecipient>), DataTypeError> {
+    let mut enveloped_data = content.children();
+    enveloped_data.read_tag(TAG_INTEGER)?;
+    if enveloped_data.peek_tag() == Some(0xA0) {
+        // Originator info
+        enveloped_data.read()?;
+    }
+    let mut key_recipients = vec![];
+    let mut recipient_infos = enveloped_data.read_tag(TAG_SET)?.children();
+    while !recipient_infos.is_empty() {
+        // Key agreement and other tagged kinds are left out.
+        let info = recipient_infos.read()?;
+        if info.tag != TAG_SEQUENCE {
+            continue;
+        }
+        let mut info = info.children();
+        info.read_tag(TAG_INTEGER)?;
+        let recipient_id = info.read()?;
+        if recipient_id.tag != TAG_SEQUENCE {
+            continue;
+        }
+        let mut recipient_id = recipient_id.children();
+        key_recipients.push(KeyRecipient {
+            issuer: format_name(&name_attributes(recipient_id.read_tag(TAG_SEQUENCE)?)?),
+            serial_number: serial_number(recipient_id.read_tag(TAG_INTEGER)?),
+        });
+    }
+    let mut encrypted_content = enveloped_data.read_tag(TAG_SEQUENCE)?.children();
+    encrypted_content.read_tag(TAG_OID)?;
+    let oid = encrypted_content
+        .read_tag(TAG_SEQUENCE)?
+        .children()
+        .read_tag(TAG_OID)?
+        .oid()?;
+    let algorithm = ENCRYPTION_ALGORITHMS
+        .iter()
+        .find(|(algorithm_oid, _)| *algorithm_oid == oid)
+        .map_or(oid.clone(), |(_, name)| name.to_string());
+    Ok((algorithm, key_recipients))
+}
+
+impl ProtectionStatus {
+    fn new(kind: ProtectionKind, container: String) -> Self {
+        Self {
+            kind,
+            container,
+            encryption_algorithm: String::new(),
+            key_recipients: vec![],
+        }
+    }
+
+    pub fn unprotected() -> Self {
+        Self::new(ProtectionKind::Unprotected, String::new())
+    }
+
+    // Reads the "application/pkcs7-mime" content of an S/MIME
+    // message, which is either signed or encrypted.
+    pub fn from_pkcs7(data: &[u8], container: String) -> Self {
+        let der = pkcs7_der(data);
+        let mut status = Self::new(ProtectionKind::Encrypted, container);
+        let (content_type, content) = match content_info(&der) {
+            Ok(info) => info,
+            Err(_) => return status,
+        };
+        match content_type.as_str() {
+            OID_SIGNED_DATA => status.kind = ProtectionKind::Signed,
+            OID_ENVELOPED_DATA | OID_AUTH_ENVELOPED_DATA => {
+                if let Ok((algorithm, key_recipients)) = parse_enveloped_data(content) {
+                    status.encryption_algorithm = algorithm;
+                    status.key_recipients = key_recipients;
+                }
+            }
+            _ => {}
+        }
+        status
+    }
+
+    pub fn create_from_props(props: &Properties, attachments: &Attachments, headers: &Headers) -> Self {
+        let message_class = props
+            .get("MessageClass")
+            .and_then(DataType::as_str)
+            .unwrap_or_default()
+            .to_ascii_lowercase();
+        let content_class = props
+            .get("content-class")
+            .and_then(DataType::as_str)
+            .or_else(|| headers.get("Content-Class"))
+            .unwrap_or_default();
+
+        let rpmsg = attachments.iter().find(|attachment| {
+            attachment_name(attachment).eq_ignore_ascii_case(RPMSG_ATTACHMENT)
+                || attachment_mime_tag(attachment) == RPMSG_MIME_TAG
+        });
+        if rpmsg.is_some()
+            || content_class.trim().eq_ignore_ascii_case(RPMSG_CONTENT_CLASS)
+            || message_class.contains(".rpmsg")
+        {
+            let container = rpmsg.map_or(RPMSG_ATTACHMENT.to_string(), attachment_name);
+            return Self::new(ProtectionKind::RightsManaged, container);
+        }
+
+        if !message_class.starts_with("ipm.note.smime") {
+            return Self::unprotected();
+        }
+        let attachment = match attachments.first() {
+            Some(attachment) => attachment,
+            None => return Self::new(ProtectionKind::Encrypted, String::new()),
+        };
+        let container = attachment_name(attachment);
+        if message_class.ends_with(".multipartsigned")
+            || attachment_mime_tag(attachment) == "multipart/signed"
+        {
+            return Self::new(ProtectionKind::Signed, container);
+        }
+        let data = attachment
+            .get("AttachDataObject")
+            .and_then(DataType::as_bytes)
+            .unwrap_or_default();
+        Self::from_pkcs7(data, container)
+    }
+
+    // Whether the content of the message can be read
+    // without a key or a license.
+    pub fn is_readable(&self) -> bool {
+        matches!(self.kind, ProtectionKind::Unprotected | ProtectionKind::Signed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        super::{decode::DataType, headers::Headers, outlook::Outlook, storage::Properties},
+        KeyRecipient, ProtectionKind, ProtectionStatus,
+    };
+
+    fn attachment(name: &str, mime_tag: &str, data: Vec<u8>) -> Properties {
+        let mut attachment = Properties::new();
+        attachment.insert("AttachLongFilename".to_string(), DataType::PtypString(name.to_string()));
+        attachment.insert("AttachMimeTag".to_string(), DataType::PtypString(mime_tag.to_string()));
+        attachment.insert("AttachDataObject".to_string(), DataType::PtypBinary(data));
+        attachment
+    }
+
+    fn message_class(message_class: &str) -> Properties {
+        let mut props = Properties::new();
+        props.insert(
+            "MessageClass".to_string(),
+            DataType::PtypString(message_class.to_string()),
+        );
+        props
+    }
+
+    #[test]
+    fn test_smime_encrypted() {
+        let data = std::fs::read("data/smime_encrypted.p7m").unwrap();
+        let attachments = vec![attachment("smime.p7m", "application/pkcs7-mime", data)];
+        let status = ProtectionStatus::create_from_props(
+            &message_class("IPM.Note.SMIME"),
+            &attachments,
+            &Headers::default(),
+        );
+        assert_eq!(status.kind, ProtectionKind::Encrypted);
+        assert!(!status.is_readable());
+        assert_eq!(status.container, "smime.p7m".to_string());
+        assert_eq!(status.encryption_algorithm, "aes256-cbc".to_string());
+        assert_eq!(
+            status.key_recipients,
+            vec![KeyRecipient {
+                issuer: "C=US, O=Contoso, CN=Jane Doe, emailAddress=jane@contoso.com".to_string(),
+                serial_number: "04866DE3F703615A2217BF3516B3463A4685C405".to_string(),
+            }]
+        );
+
+        // Opaque signed content is readable.
+        let data = std::fs::read("data/smime_opaque.p7m").unwrap();
+        let status = ProtectionStatus::from_pkcs7(&data, "smime.p7m".to_string());
+        assert_eq!(status.kind, ProtectionKind::Signed);
+        assert!(status.is_readable());
+
+        // Unreadable content is still taken for encrypted.
+        let status = ProtectionStatus::from_pkcs7(b"garbage", String::new());
+        assert_eq!(status.kind, ProtectionKind::Encrypted);
+        assert_eq!(status.encryption_algorithm, String::new());
+    }
+
+    #[test]
+    fn test_smime_signed() {
+        let attachments = vec![attachment("smime.p7m", "multipart/signed", vec![])];
+        let status = ProtectionStatus::create_from_props(
+            &message_class("IPM.Note.SMIME.MultipartSigned"),
+            &attachments,
+            &Headers::default(),
+        );
+        assert_eq!(status.kind, ProtectionKind::Signed);
+    }
+
+    #[test]
+    fn test_rights_managed() {
+        let attachments = vec![attachment(
+            "message.rpmsg",
+            "application/x-microsoft-rpmsg-message",
+            vec![],
+        )];
+        let status = ProtectionStatus::create_from_props(
+            &message_class("IPM.Note"),
+            &attachments,
+            &Headers::default(),
+        );
+        assert_eq!(status.kind, ProtectionKind::RightsManaged);
+        assert_eq!(status.container, "message.rpmsg".to_string());
+
+        let headers = Headers::parse("Content-Class: rpmsg.message\r\n");
+        let status = ProtectionStatus::create_from_props(&message_class("IPM.Note"), &vec![], &headers);
+        assert_eq!(status.kind, ProtectionKind::RightsManaged);
+        assert!(!status.is_readable());
+    }
+
+    #[test]
+    fn test_unprotected() {
+        let outlook = Outlook::from_path("data/test_email.msg").unwrap();
+        assert_eq!(outlook.protection, ProtectionStatus::unprotected());
+        assert!(outlook.protection.is_readable());
+    }
+}
@@ -0,0 +1,388 @@
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+
+use super::{outlook::Outlook, recipient::RecipientType};
+
+// Properties the fields of Outlook are read from, those
+// of recipients and attachments being named after the
+// index of the one they belong to, e.g.
+// "attachments[0].file_name". Fields read from recipients
+// or attachments as well only have their root properties.
+const FIELD_PROPERTIES: [(&str, &[&str]); 36] = [
+    ("headers", &["TransportMessageHeaders"]),
+    ("sender", &["SenderName", "SenderSmtpAddress", "SenderEmailAddress"]),
+    (
+        "sent_representing",
+        &["SentRepresentingName", "SentRepresentingSmtpAddress", "SentRepresentingEmailAddress"],
+    ),
+    ("subject", &["Subject"]),
+    ("message_class", &["MessageClass"]),
+    ("body", &["Body"]),
+    ("rtf_compressed", &["RtfCompressed"]),
+    ("authentication", &["TransportMessageHeaders"]),
+    ("x_headers", &["TransportMessageHeaders"]),
+    ("message_id", &["InternetMessageId"]),
+    ("in_reply_to", &["InReplyToId", "TransportMessageHeaders"]),
+    ("references", &["InternetReferences", "TransportMessageHeaders"]),
+    ("conversation_index", &["ConversationIndex"]),
+    ("importance", &["Importance"]),
+    ("priority", &["Priority"]),
+    ("sensitivity", &["Sensitivity"]),
+    ("flags", &["MessageFlags"]),
+    ("submit_time", &["ClientSubmitTime"]),
+    ("delivery_time", &["MessageDeliveryTime"]),
+    ("reply_to", &["ReplyRecipientEntries", "ReplyRecipientNames", "TransportMessageHeaders"]),
+    ("time_zone", &["AppointmentTimeZoneDefinitionStartDisplay", "TimeZoneStruct"]),
+    (
+        "appointment",
+        &[
+            "MessageClass",
+            "AppointmentStartWhole",
+            "AppointmentEndWhole",
+            "Location",
+            "AppointmentSubType",
+            "AppointmentRecur",
+            "GlobalObjectId",
+            "ToAttendeesString",
+            "CcAttendeesString",
+            "SentRepresentingName",
+            "SentRepresentingSmtpAddress",
+            "SentRepresentingEmailAddress",
+        ],
+    ),
+    (
+        "reminder",
+        &[
+            "ReminderSet",
+            "ReminderTime",
+            "ReminderSignalTime",
+            "ReminderDelta",
+            "ReminderPlaySound",
+            "ReminderFileParameter",
+            "ReminderOverride",
+        ],
+    ),
+    (
+        "contact",
+        &[
+            "MessageClass",
+            "DisplayName",
+            "DisplayNamePrefix",
+            "GivenName",
+            "MiddleName",
+            "Surname",
+            "Generation",
+            "Nickname",
+            "CompanyName",
+            "Title",
+            "DepartmentName",
+            "Email1DisplayName",
+            "Email1EmailAddress",
+            "Email2DisplayName",
+            "Email2EmailAddress",
+            "Email3DisplayName",
+            "Email3EmailAddress",
+            "PrimaryTelephoneNumber",
+            "BusinessTelephoneNumber",
+            "Business2TelephoneNumbers",
+            "HomeTelephoneNumber",
+            "Home2TelephoneNumbers",
+            "MobileTelephoneNumber",
+            "CarTelephoneNumber",
+            "PagerTelephoneNumber",
+            "AssistantTelephoneNumber",
+            "CompanyMainTelephoneNumber",
+            "OtherTelephoneNumber",
+            "PrimaryFaxNumber",
+            "BusinessFaxNumber",
+            "HomeFaxNumber",
+        ],
+    ),
+    (
+        "task",
+        &[
+            "MessageClass",
+            "TaskStatus",
+            "PercentComplete",
+            "TaskStartDate",
+            "TaskDueDate",
+            "TaskDateCompleted",
+            "TaskComplete",
+            "TaskOwner",
+            "TaskAssigner",
+        ],
+    ),
+    ("sticky_note", &["MessageClass", "NoteColor", "NoteWidth", "NoteHeight", "NoteX", "NoteY", "Body"]),
+    (
+        "dist_list",
+        &["MessageClass", "DistributionListName", "DistributionListMembers", "DistributionListOneOffMembers"],
+    ),
+    (
+        "non_delivery_report",
+        &[
+            "MessageClass",
+            "ReportText",
+            "OriginalSubject",
+            "OriginalSenderName",
+            "OriginalSenderEmailAddress",
+            "OriginalDisplayTo",
+            "OriginalDisplayCc",
+            "OriginalSubmitTime",
+        ],
+    ),
+    (
+        "receipt",
+        &[
+            "MessageClass",
+            "InReplyToId",
+            "InternetReferences",
+            "ReportTime",
+            "ReportName",
+            "ReportEntryId",
+            "OriginalSubject",
+            "OriginalSenderName",
+            "OriginalSenderEmailAddress",
+            "OriginalDisplayTo",
+            "OriginalDisplayCc",
+            "OriginalSubmitTime",
+        ],
+    ),
+    ("follow_up", &["FlagStatus", "FlagRequest", "TaskStartDate", "TaskDueDate", "ReplyTime", "FlagCompleteTime"]),
+    ("voting", &["VerbStream", "VerbResponse"]),
+    ("smime", &["MessageClass"]),
+    ("protection", &["MessageClass"]),
+    ("sensitivity_labels", &["msip_labels", "TransportMessageHeaders"]),
+    ("links", &["Body", "Html", "RtfCompressed", "TransportMessageHeaders"]),
+    (
+        "missing",
+        &[
+            "MessageClass",
+            "SenderName",
+            "SenderEmailAddress",
+            "SenderSmtpAddress",
+            "SentRepresentingName",
+            "SentRepresentingEmailAddress",
+            "SentRepresentingSmtpAddress",
+            "ClientSubmitTime",
+            "MessageDeliveryTime",
+        ],
+    ),
+];
+
+// Properties of each recipient "to", "cc" and "bcc" are
+// read from.
+const PERSON_PROPERTIES: [&str; 4] = ["DisplayName", "SmtpAddress", "EmailAddress", "RecipientType"];
+
+const RECIPIENT_PROPERTIES: [(&str, &[&str]); 9] = [
+    ("display_name", &["DisplayName"]),
+    ("email_address", &["EmailAddress"]),
+    ("smtp_address", &["SmtpAddress"]),
+    ("address_type", &["AddressType"]),
+    ("entry_id", &["EntryId"]),
+    ("recipient_type", &["RecipientType"]),
+    ("responsible", &["Responsibility"]),
+    ("flags", &["RecipientFlags"]),
+    ("track_status", &["RecipientTrackStatus"]),
+];
+
+const ATTACHMENT_PROPERTIES: [(&str, &[&str]); 5] = [
+    ("display_name", &["DisplayName"]),
+    ("payload", &["AttachDataObject"]),
+    ("extension", &["AttachExtension"]),
+    ("mime_tag", &["AttachMimeTag"]),
+    ("file_name", &["AttachFilename"]),
+];
+
+// SourceKind tells whether a property had a stream of its
+// own or an entry of the property stream of its storage.
+// Refer to MS-OXMSG 2.4.2
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum SourceKind {
+    Substream,      // "__substg1.0_AAAABBBB"
+    PropertyStream, // "__properties_version1.0"
+}
+
+// PropertySource is where the value of a property was
+// read from.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PropertySource {
+    pub stream: String,   // Entry path, e.g. "__recip_version1.0_#00000000/__substg1.0_3001001F"
+    pub tag: u32,         // Property id followed by its type code
+    pub kind: SourceKind, // See SourceKind enum
+}
+
+pub type Sources = BTreeMap<String, PropertySource>;
+
+// Provenance holds the source of each decoded property,
+// by name, as MessageProperties holds their values. It is
+// recorded with ParserOptions.provenance only.
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Provenance {
+    pub root: Sources,
+    pub recipients: Vec<Sources>,
+    pub attachments: Vec<Sources>,
+}
+
+impl Provenance {
+    pub fn is_empty(&self) -> bool {
+        self.root.is_empty() && self.recipients.is_empty() && self.attachments.is_empty()
+    }
+}
+
+// Splits "attachments[2].file_name" into the index and
+// the field.
+fn indexed<'a>(field: &'a str, list: &str) -> Option<(usize, &'a str)> {
+    let rest = field.strip_prefix(list)?.strip_prefix('[')?;
+    let (index, field) = rest.split_once("].")?;
+    Some((index.parse().ok()?, field))
+}
+
+fn sources<'a>(sources: Option<&'a Sources>, keys: &[&str]) -> Vec<&'a PropertySource> {
+    match sources {
+        Some(sources) => keys.iter().filter_map(|key| sources.get(*key)).collect(),
+        None => vec![],
+    }
+}
+
+fn keys<'a>(table: &[(&str, &'a [&'a str])], field: &str) -> Option<&'a [&'a str]> {
+    table.iter().find(|(name, _)| *name == field).map(|(_, keys)| *keys)
+}
+
+fn all_keys<'a>(table: &[(&str, &'a [&'a str])]) -> Vec<&'a str> {
+    table.iter().flat_map(|(_, keys)| keys.iter().copied()).collect()
+}
+
+impl Outlook {
+    // Sources of the properties a field was read from, e.g.
+    // "subject", "sender" or "attachments[0].payload", for
+    // reports citing them. "to", "cc" and "bcc" have those
+    // of the recipients of their type, "recipients" and
+    // "attachments" those of every one. Empty unless parsed
+    // with ParserOptions.provenance, None for fields Outlook
+    // does not have.
+    pub fn sources_of(&self, field: &str) -> Option<Vec<&PropertySource>> {
+        let provenance = &self.provenance;
+        if let Some((index, field)) = indexed(field, "recipients") {
+            return Some(sources(provenance.recipients.get(index), keys(&RECIPIENT_PROPERTIES, field)?));
+        }
+        if let Some((index, field)) = indexed(field, "attachments") {
+            return Some(sources(provenance.attachments.get(index), keys(&ATTACHMENT_PROPERTIES, field)?));
+        }
+        let recipient_type = match field {
+            "to" => RecipientType::To,
+            "cc" => RecipientType::Cc,
+            "bcc" => RecipientType::Bcc,
+            "recipients" => {
+                let keys = all_keys(&RECIPIENT_PROPERTIES);
+                return Some(
+                    provenance
+                        .recipients
+                        .iter()
+                        .flat_map(|recipient| sources(Some(recipient), &keys))
+                        .collect(),
+                );
+            }
+            "attachments" => {
+                let keys = all_keys(&ATTACHMENT_PROPERTIES);
+                return Some(
+                    provenance
+                        .attachments
+                        .iter()
+                        .flat_map(|attachment| sources(Some(attachment), &keys))
+                        .collect(),
+                );
+            }
+            field => return Some(sources(Some(&provenance.root), keys(&FIELD_PROPERTIES, field)?)),
+        };
+        Some(
+            provenance
+                .recipients
+                .iter()
+                .zip(&self.recipients)
+                .filter(|(_, recipient)| recipient.recipient_type == recipient_type)
+                .flat_map(|(recipient, _)| sources(Some(recipient), &PERSON_PROPERTIES))
+                .collect(),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        super::{options::ParserOptions, outlook::Outlook, recipient::RecipientType},
+        indexed, PropertySource, SourceKind,
+    };
+
+    #[test]
+    fn test_indexed() {
+        assert_eq!(indexed("attachments[2].file_name", "attachments"), Some((2, "file_name")));
+        assert_eq!(indexed("attachments[x].file_name", "attachments"), None);
+        assert_eq!(indexed("subject", "attachments"), None);
+    }
+
+    #[test]
+    fn test_sources_of() {
+        let path = "data/attachment.msg";
+        assert!(Outlook::from_path(path).unwrap().provenance.is_empty());
+
+        let options = ParserOptions::new().provenance(true);
+        let outlook = Outlook::from_path_with(path, &options).unwrap();
+        assert_eq!(
+            outlook.sources_of("subject"),
+            Some(vec![&PropertySource {
+                stream: "__substg1.0_0037001F".to_string(),
+                tag: 0x0037_001F,
+                kind: SourceKind::Substream,
+            }])
+        );
+        assert_eq!(
+            outlook.sources_of("importance"),
+            Some(vec![&PropertySource {
+                stream: "__properties_version1.0".to_string(),
+                tag: 0x0017_0003,
+                kind: SourceKind::PropertyStream,
+            }])
+        );
+        assert_eq!(
+            outlook.sources_of("attachments[1].payload"),
+            Some(vec![&PropertySource {
+                stream: "__attach_version1.0_#00000001/__substg1.0_37010102".to_string(),
+                tag: 0x3701_0102,
+                kind: SourceKind::Substream,
+            }])
+        );
+        assert_eq!(outlook.provenance.attachments.len(), outlook.attachments.len());
+        assert!(!outlook.sources_of("to").unwrap().is_empty());
+        assert_eq!(outlook.sources_of("attachments[9].payload"), Some(vec![]));
+        assert_eq!(outlook.sources_of("attachments").unwrap().len(), 15);
+        assert_eq!(outlook.sources_of("follow_up"), Some(vec![]));
+        assert_eq!(outlook.sources_of("no_such_field"), None);
+        assert_eq!(outlook.sources_of("attachments[0].no_such_field"), None);
+    }
+
+    #[test]
+    fn test_sources_of_recipient_types() {
+        let options = ParserOptions::new().provenance(true);
+        let outlook = Outlook::from_path_with("data/test_email.msg", &options).unwrap();
+        // Each recipient has one "RecipientType" source.
+        let count = |field: &str| {
+            let sources = outlook.sources_of(field).unwrap();
+            sources.iter().filter(|source| source.tag >> 16 == 0x0C15).count()
+        };
+        let count_of = |recipient_type: RecipientType| {
+            outlook
+                .recipients
+                .iter()
+                .filter(|recipient| recipient.recipient_type == recipient_type)
+                .count()
+        };
+        assert!(count("to") > 0 && count("cc") > 0);
+        assert_eq!(count("to"), count_of(RecipientType::To));
+        assert_eq!(count("cc"), count_of(RecipientType::Cc));
+        assert_eq!(count("bcc"), count_of(RecipientType::Bcc));
+        assert_eq!(count("recipients"), outlook.recipients.len());
+        let to = outlook.sources_of("to").unwrap();
+        assert!(outlook.sources_of("cc").unwrap().iter().all(|source| !to.contains(source)));
+    }
+}
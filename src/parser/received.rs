@@ -0,0 +1,167 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use super::{
+    headers::{strip_comments, Headers},
+    outlook::Outlook,
+};
+
+// Words starting the clauses of a Received field.
+// Refer to RFC 5321 4.4
+const CLAUSES: [&str; 6] = ["from", "by", "via", "with", "id", "for"];
+
+// ReceivedHop is one relay of the message,
+// as told by a Received field.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ReceivedHop {
+    pub from: Option<String>,             // host that handed the message over
+    pub by: Option<String>,               // host that received it
+    pub protocol: Option<String>,         // e.g. "ESMTPS" or "HTTPS"
+    pub timestamp: Option<DateTime<Utc>>, // date following the clauses
+    pub delay: Option<i64>,               // seconds since the previous hop
+}
+
+impl ReceivedHop {
+    pub fn parse(value: &str) -> Self {
+        // The date follows the last semicolon.
+        let (clauses, date) = match value.rfind(';') {
+            Some(pos) => (&value[..pos], Some(&value[pos + 1..])),
+            None => (value, None),
+        };
+        let timestamp = date
+            .map(strip_comments)
+            .and_then(|date| DateTime::parse_from_rfc2822(date.trim()).ok())
+            .map(|date| date.with_timezone(&Utc));
+
+        let mut from = None;
+        let mut by = None;
+        let mut protocol = None;
+        let clauses = strip_comments(clauses);
+        let mut words = clauses.split_whitespace().peekable();
+        while let Some(word) = words.next() {
+            let clause = word.to_ascii_lowercase();
+            if !CLAUSES.contains(&clause.as_str()) {
+                continue;
+            }
+            let mut args = vec![];
+            while let Some(arg) = words.peek() {
+                if CLAUSES.contains(&arg.to_ascii_lowercase().as_str()) {
+                    break;
+                }
+                args.push(*arg);
+                words.next();
+            }
+            match clause.as_str() {
+                "from" if from.is_none() => from = args.first().map(|host| host.to_string()),
+                "by" if by.is_none() => by = args.first().map(|host| host.to_string()),
+                "with" if protocol.is_none() && !args.is_empty() => protocol = Some(args.join(" ")),
+                _ => {}
+            }
+        }
+        Self {
+            from,
+            by,
+            protocol,
+            timestamp,
+            delay: None,
+        }
+    }
+}
+
+impl Headers {
+    // Hops of the Received fields in the order the message
+    // went through them, the first being where it was sent.
+    // Each relay prepends its own field, so they are read
+    // from the bottom up.
+    pub fn received_chain(&self) -> Vec<ReceivedHop> {
+        let mut chain: Vec<ReceivedHop> = self
+            .get_all("Received")
+            .into_iter()
+            .rev()
+            .map(ReceivedHop::parse)
+            .collect();
+        for i in 1..chain.len() {
+            chain[i].delay = match (chain[i - 1].timestamp, chain[i].timestamp) {
+                (Some(previous), Some(current)) => Some((current - previous).num_seconds()),
+                _ => None,
+            };
+        }
+        chain
+    }
+}
+
+impl Outlook {
+    // Received chain of "TransportMessageHeaders".
+    pub fn received_chain(&self) -> Vec<ReceivedHop> {
+        self.transport_headers().received_chain()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{TimeZone as _, Utc};
+
+    use super::{super::headers::Headers, Outlook, ReceivedHop};
+
+    #[test]
+    fn test_parse_hop() {
+        let hop = ReceivedHop::parse(
+            "from mail.example.com (mail.example.com [192.0.2.1]) by mx.example.org \
+             (Postfix) with ESMTPS id 4F1B2 for <jane@example.org>; \
+             Tue, 2 Mar 2021 10:00:00 +0100 (CET)",
+        );
+        assert_eq!(
+            hop,
+            ReceivedHop {
+                from: Some("mail.example.com".to_string()),
+                by: Some("mx.example.org".to_string()),
+                protocol: Some("ESMTPS".to_string()),
+                timestamp: Some(Utc.with_ymd_and_hms(2021, 3, 2, 9, 0, 0).unwrap()),
+                delay: None,
+            }
+        );
+
+        let hop = ReceivedHop::parse("by localhost; not a date");
+        assert_eq!(hop.from, None);
+        assert_eq!(hop.by, Some("localhost".to_string()));
+        assert_eq!(hop.timestamp, None);
+    }
+
+    #[test]
+    fn test_received_chain() {
+        let headers = Headers::parse(
+            "Received: by c; Tue, 2 Mar 2021 10:00:30 +0000\r\n\
+             Received: from a by b; Tue, 2 Mar 2021 10:00:00 +0000\r\n\
+             Received: from x by a\r\n",
+        );
+        let chain = headers.received_chain();
+        assert_eq!(
+            chain.iter().map(|hop| hop.by.as_deref()).collect::<Vec<_>>(),
+            vec![Some("a"), Some("b"), Some("c")]
+        );
+        assert_eq!(
+            chain.iter().map(|hop| hop.delay).collect::<Vec<_>>(),
+            vec![None, None, Some(30)]
+        );
+    }
+
+    #[test]
+    fn test_outlook_received_chain() {
+        let outlook = Outlook::from_path("data/test_email_4.msg").unwrap();
+        let chain = outlook.received_chain();
+        assert_eq!(chain.len(), 3);
+        assert_eq!(chain[0].from, Some("mail-40140.protonmail.ch".to_string()));
+        assert_eq!(
+            chain[0].by,
+            Some("DB8EUR05FT020.mail.protection.outlook.com".to_string())
+        );
+        assert_eq!(chain[0].protocol, Some("Microsoft SMTP Server".to_string()));
+        assert_eq!(
+            chain[0].timestamp,
+            Some(Utc.with_ymd_and_hms(2021, 3, 14, 4, 3, 55).unwrap())
+        );
+        assert_eq!(chain[1].delay, Some(1));
+        assert_eq!(chain[2].protocol, Some("HTTPS".to_string()));
+        assert_eq!(chain[2].delay, Some(1));
+    }
+}
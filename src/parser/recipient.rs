@@ -0,0 +1,221 @@
+use serde::{Deserialize, Serialize};
+
+use super::{
+    decode::DataType,
+    entry_id::EntryId,
+    meeting::ResponseStatus,
+    storage::{Properties, Recipients},
+};
+
+// Bits of the recipient flags.
+// Refer to MS-OXOCAL 2.2.4.10.1
+const RECIP_SENDABLE: i64 = 0x0000_0001;
+const RECIP_ORGANIZER: i64 = 0x0000_0002;
+
+// RecipientType tells which field a recipient was given in.
+// Refer to MS-OXOMSG 2.2.3.1
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub enum RecipientType {
+    Originator,
+    To,
+    Cc,
+    Bcc,
+}
+
+impl RecipientType {
+    pub fn from_value(value: i64) -> Self {
+        // The upper bits are flags, e.g. a recipient to resend to.
+        match value & 0x0F {
+            0 => RecipientType::Originator,
+            2 => RecipientType::Cc,
+            3 => RecipientType::Bcc,
+            _ => RecipientType::To,
+        }
+    }
+
+    pub fn to_value(&self) -> i64 {
+        match self {
+            RecipientType::Originator => 0,
+            RecipientType::To => 1,
+            RecipientType::Cc => 2,
+            RecipientType::Bcc => 3,
+        }
+    }
+}
+
+// AttendeeRole is the part a recipient of a meeting
+// takes in it.
+// Refer to MS-OXOCAL 2.2.4.10.1.3
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum AttendeeRole {
+    Required,
+    Optional,
+    Resource,
+}
+
+// Recipient is a row of the recipient table, keeping
+// what collapsing it to a Person would lose.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct Recipient {
+    pub display_name: String,          // "DisplayName"
+    pub email_address: String,         // "EmailAddress"
+    pub smtp_address: String,          // "SmtpAddress"
+    pub address_type: String,          // "AddressType"
+    pub entry_id: String,              // "EntryId"
+    pub recipient_type: RecipientType, // "RecipientType"
+    pub responsible: bool,             // "Responsibility"
+    pub flags: i64,                    // "RecipientFlags"
+    pub track_status: ResponseStatus,  // "RecipientTrackStatus"
+}
+
+impl Recipient {
+    pub fn create_from_props(props: &Properties) -> Self {
+        let string = |key: &str| props.get(key).map_or(String::new(), |x| x.into());
+        Self {
+            display_name: string("DisplayName"),
+            email_address: string("EmailAddress"),
+            smtp_address: string("SmtpAddress"),
+            address_type: string("AddressType"),
+            entry_id: string("EntryId"),
+            // Recipients without a type are taken for primary ones.
+            recipient_type: props
+                .get("RecipientType")
+                .and_then(DataType::as_i64)
+                .map_or(RecipientType::To, RecipientType::from_value),
+            responsible: props
+                .get("Responsibility")
+                .and_then(DataType::as_bool)
+                .unwrap_or(false),
+            flags: props
+                .get("RecipientFlags")
+                .and_then(DataType::as_i64)
+                .unwrap_or(0),
+            track_status: props
+                .get("RecipientTrackStatus")
+                .and_then(DataType::as_i64)
+                .map_or(ResponseStatus::None, ResponseStatus::from_value),
+        }
+    }
+
+    pub fn create_all(recipients: &Recipients) -> Vec<Self> {
+        recipients.iter().map(Self::create_from_props).collect()
+    }
+
+    // The SMTP address when known, the address of
+    // the recipient's own type otherwise.
+    pub fn email(&self) -> &str {
+        if self.smtp_address.is_empty() {
+            &self.email_address
+        } else {
+            &self.smtp_address
+        }
+    }
+
+    pub fn parse_entry_id(&self) -> Option<EntryId> {
+        let buff = hex::decode(&self.entry_id).ok()?;
+        EntryId::from_bytes(&buff).ok()
+    }
+
+    pub fn is_sendable(&self) -> bool {
+        self.flags & RECIP_SENDABLE != 0
+    }
+
+    pub fn is_organizer(&self) -> bool {
+        self.flags & RECIP_ORGANIZER != 0
+    }
+
+    // Role of the recipient when the message is a meeting
+    // object, resources being kept as Bcc recipients.
+    pub fn attendee_role(&self) -> Option<AttendeeRole> {
+        match self.recipient_type {
+            RecipientType::To => Some(AttendeeRole::Required),
+            RecipientType::Cc => Some(AttendeeRole::Optional),
+            RecipientType::Bcc => Some(AttendeeRole::Resource),
+            RecipientType::Originator => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        super::{
+            decode::DataType,
+            entry_id::{tests::one_off_entry_id, EntryId},
+            meeting::ResponseStatus,
+            outlook::Outlook,
+            storage::Properties,
+        },
+        AttendeeRole, Recipient, RecipientType,
+    };
+
+    #[test]
+    fn test_create_recipient() {
+        let recipient = Recipient::create_from_props(&Properties::new());
+        assert_eq!(recipient.recipient_type, RecipientType::To);
+        assert_eq!(recipient.track_status, ResponseStatus::None);
+        assert_eq!(recipient.parse_entry_id(), None);
+        assert!(!recipient.is_sendable());
+
+        let mut props = Properties::new();
+        props.insert("DisplayName".to_string(), DataType::PtypString("Room 1".to_string()));
+        props.insert("EmailAddress".to_string(), DataType::PtypString("room1@x.com".to_string()));
+        props.insert("AddressType".to_string(), DataType::PtypString("SMTP".to_string()));
+        props.insert(
+            "EntryId".to_string(),
+            DataType::PtypBinary(one_off_entry_id("Room 1", "SMTP", "room1@x.com")),
+        );
+        props.insert("RecipientType".to_string(), DataType::PtypInteger32(0x1000_0003));
+        props.insert("Responsibility".to_string(), DataType::PtypBoolean(true));
+        props.insert("RecipientFlags".to_string(), DataType::PtypInteger32(0x3));
+        props.insert("RecipientTrackStatus".to_string(), DataType::PtypInteger32(3));
+        let recipient = Recipient::create_from_props(&props);
+        assert_eq!(recipient.recipient_type, RecipientType::Bcc);
+        assert_eq!(RecipientType::Bcc.to_value(), 3);
+        assert_eq!(recipient.attendee_role(), Some(AttendeeRole::Resource));
+        assert_eq!(recipient.email(), "room1@x.com");
+        assert!(recipient.responsible);
+        assert!(recipient.is_sendable());
+        assert!(recipient.is_organizer());
+        assert_eq!(recipient.track_status, ResponseStatus::Accepted);
+        assert_eq!(
+            recipient.parse_entry_id(),
+            Some(EntryId::OneOff {
+                display_name: "Room 1".to_string(),
+                address_type: "SMTP".to_string(),
+                email_address: "room1@x.com".to_string(),
+            })
+        );
+    }
+
+    #[test]
+    fn test_outlook_recipients() {
+        let outlook = Outlook::from_path("data/test_email.msg").unwrap();
+        let types: Vec<RecipientType> = outlook
+            .recipients
+            .iter()
+            .map(|recipient| recipient.recipient_type)
+            .collect();
+        assert_eq!(
+            types,
+            vec![
+                RecipientType::To,
+                RecipientType::Cc,
+                RecipientType::Cc,
+                RecipientType::Bcc,
+                RecipientType::Bcc,
+                RecipientType::Bcc,
+            ]
+        );
+        let first = &outlook.recipients[0];
+        assert_eq!(first.address_type, "SMTP".to_string());
+        assert_eq!(first.email_address, "marirs@gmail.com".to_string());
+        assert_eq!(first.email(), "marirs@gmail.com");
+        assert!(!first.responsible);
+        assert!(first.is_sendable());
+        assert_eq!(outlook.recipients[1].display_name, "Sriram Govindan".to_string());
+        assert_eq!(outlook.recipients[1].attendee_role(), Some(AttendeeRole::Optional));
+    }
+}
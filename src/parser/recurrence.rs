@@ -0,0 +1,456 @@
+use chrono::{Duration, NaiveDate, NaiveDateTime, Weekday};
+use serde::{Deserialize, Serialize};
+
+use super::{blob::BlobReader, error::DataTypeError};
+
+// Recurrence dates are stored as minutes since 1601-01-01,
+// in the time zone of the appointment.
+fn minutes_to_datetime(minutes: u32) -> Option<NaiveDateTime> {
+    NaiveDate::from_ymd_opt(1601, 1, 1)?
+        .and_hms_opt(0, 0, 0)?
+        .checked_add_signed(Duration::minutes(minutes as i64))
+}
+
+fn read_datetime(reader: &mut BlobReader) -> Result<NaiveDateTime, DataTypeError> {
+    let minutes = reader.read_u32()?;
+    minutes_to_datetime(minutes)
+        .ok_or_else(|| DataTypeError::MalformedBlob(format!("invalid date {}", minutes)))
+}
+
+const WEEKDAYS: [Weekday; 7] = [
+    Weekday::Sun,
+    Weekday::Mon,
+    Weekday::Tue,
+    Weekday::Wed,
+    Weekday::Thu,
+    Weekday::Fri,
+    Weekday::Sat,
+];
+
+// Days of the week set in a day mask, bit 0 being Sunday.
+fn weekdays_from_mask(mask: u32) -> Vec<Weekday> {
+    WEEKDAYS
+        .iter()
+        .enumerate()
+        .filter(|(i, _)| mask & (1 << i) != 0)
+        .map(|(_, &day)| day)
+        .collect()
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub enum RecurrenceFrequency {
+    Daily,
+    Weekly,
+    Monthly,
+    Yearly,
+}
+
+// RecurrenceEnd tells when a series stops.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub enum RecurrenceEnd {
+    Date(NaiveDate),
+    Count(u32),
+    Never,
+}
+
+// RecurrenceException is a modified occurrence of the
+// series. Subject and location are only present when
+// they differ from the series.
+// Refer to MS-OXOCAL 2.2.1.44.2
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct RecurrenceException {
+    pub original_start: NaiveDateTime,
+    pub start: NaiveDateTime,
+    pub end: NaiveDateTime,
+    pub subject: Option<String>,
+    pub location: Option<String>,
+}
+
+impl RecurrenceException {
+    const ARO_SUBJECT: u16 = 0x0001;
+    const ARO_MEETINGTYPE: u16 = 0x0002;
+    const ARO_REMINDERDELTA: u16 = 0x0004;
+    const ARO_REMINDER: u16 = 0x0008;
+    const ARO_LOCATION: u16 = 0x0010;
+    const ARO_BUSYSTATUS: u16 = 0x0020;
+    const ARO_ATTACHMENT: u16 = 0x0040;
+    const ARO_SUBTYPE: u16 = 0x0080;
+    const ARO_APPTCOLOR: u16 = 0x0100;
+
+    // Strings in ExceptionInfo are 8-bit, prefixed by
+    // their length twice.
+    fn read_string(reader: &mut BlobReader) -> Result<String, DataTypeError> {
+        reader.skip(2)?;
+        let len = reader.read_u16()? as usize;
+        Ok(String::from_utf8_lossy(reader.read_bytes(len)?).to_string())
+    }
+
+    fn read(reader: &mut BlobReader) -> Result<Self, DataTypeError> {
+        let start = read_datetime(reader)?;
+        let end = read_datetime(reader)?;
+        let original_start = read_datetime(reader)?;
+        let flags = reader.read_u16()?;
+        let mut subject = None;
+        let mut location = None;
+        if flags & Self::ARO_SUBJECT != 0 {
+            subject = Some(Self::read_string(reader)?);
+        }
+        for flag in [Self::ARO_MEETINGTYPE, Self::ARO_REMINDERDELTA, Self::ARO_REMINDER] {
+            if flags & flag != 0 {
+                reader.skip(4)?;
+            }
+        }
+        if flags & Self::ARO_LOCATION != 0 {
+            location = Some(Self::read_string(reader)?);
+        }
+        for flag in [
+            Self::ARO_BUSYSTATUS,
+            Self::ARO_ATTACHMENT,
+            Self::ARO_SUBTYPE,
+            Self::ARO_APPTCOLOR,
+        ] {
+            if flags & flag != 0 {
+                reader.skip(4)?;
+            }
+        }
+        Ok(Self {
+            original_start,
+            start,
+            end,
+            subject,
+            location,
+        })
+    }
+}
+
+// RecurrencePattern is the decoded "AppointmentRecur"
+// property of a recurring appointment. Dates and times are
+// local to the appointment's time zone.
+// Refer to MS-OXOCAL 2.2.1.44
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct RecurrencePattern {
+    pub frequency: RecurrenceFrequency,
+    // Every `interval` days, weeks, months or years.
+    pub interval: u32,
+    // Days of the week for weekly patterns and for
+    // monthly/yearly patterns on the nth weekday.
+    pub days: Vec<Weekday>,
+    // Day of the month for monthly/yearly patterns,
+    // 31 standing for the last day.
+    pub day_of_month: Option<u32>,
+    // Occurrence (1 to 5, 5 meaning last) of `days` in the
+    // month for monthly/yearly patterns on the nth weekday.
+    pub nth: Option<u32>,
+    pub first_day_of_week: Weekday,
+    pub start_date: NaiveDate,
+    pub end: RecurrenceEnd,
+    // Minutes since midnight.
+    pub start_time_offset: u32,
+    pub end_time_offset: u32,
+    // Original start dates of deleted or modified occurrences.
+    pub deleted_dates: Vec<NaiveDateTime>,
+    pub exceptions: Vec<RecurrenceException>,
+}
+
+impl RecurrencePattern {
+    const VERSION: u16 = 0x3004;
+    const MINUTES_PER_DAY: u32 = 24 * 60;
+
+    const PATTERN_DAY: u16 = 0x0000;
+    const PATTERN_WEEK: u16 = 0x0001;
+    const PATTERN_MONTH_NTH: u16 = 0x0003;
+    const PATTERN_HJ_MONTH_NTH: u16 = 0x000B;
+
+    const END_AFTER_DATE: u32 = 0x2021;
+    const END_AFTER_N_OCCURRENCES: u32 = 0x2022;
+
+    // Reads a list of dates prefixed by its count.
+    fn read_dates(reader: &mut BlobReader) -> Result<Vec<NaiveDateTime>, DataTypeError> {
+        let count = reader.read_u32()? as usize;
+        let mut dates = Vec::with_capacity(count.min(1024));
+        for _ in 0..count {
+            dates.push(read_datetime(reader)?);
+        }
+        Ok(dates)
+    }
+
+    pub fn from_appointment_recur(buff: &[u8]) -> Result<Self, DataTypeError> {
+        let mut reader = BlobReader::new(buff);
+        let reader_version = reader.read_u16()?;
+        if reader_version != Self::VERSION {
+            return Err(DataTypeError::MalformedBlob(format!(
+                "unsupported recurrence version 0x{:04X}",
+                reader_version
+            )));
+        }
+        // WriterVersion
+        reader.skip(2)?;
+        let frequency = match reader.read_u16()? {
+            0x200A => RecurrenceFrequency::Daily,
+            0x200B => RecurrenceFrequency::Weekly,
+            0x200C => RecurrenceFrequency::Monthly,
+            0x200D => RecurrenceFrequency::Yearly,
+            other => {
+                return Err(DataTypeError::MalformedBlob(format!(
+                    "unknown recurrence frequency 0x{:04X}",
+                    other
+                )))
+            }
+        };
+        let pattern_type = reader.read_u16()?;
+        // CalendarType, FirstDateTime
+        reader.skip(6)?;
+        let period = reader.read_u32()?;
+        // SlidingFlag
+        reader.skip(4)?;
+
+        let (mut days, mut day_of_month, mut nth) = (vec![], None, None);
+        match pattern_type {
+            Self::PATTERN_DAY => {}
+            Self::PATTERN_WEEK => days = weekdays_from_mask(reader.read_u32()?),
+            Self::PATTERN_MONTH_NTH | Self::PATTERN_HJ_MONTH_NTH => {
+                days = weekdays_from_mask(reader.read_u32()?);
+                nth = Some(reader.read_u32()?);
+            }
+            _ => day_of_month = Some(reader.read_u32()?),
+        }
+
+        let end_type = reader.read_u32()?;
+        let occurrence_count = reader.read_u32()?;
+        let first_day_of_week = WEEKDAYS[reader.read_u32()? as usize % 7];
+        let deleted_dates = Self::read_dates(&mut reader)?;
+        // ModifiedInstanceDates, the exceptions carry them as well.
+        Self::read_dates(&mut reader)?;
+        let start_date = read_datetime(&mut reader)?.date();
+        let end_date = read_datetime(&mut reader)?.date();
+
+        // ReaderVersion2, WriterVersion2
+        reader.skip(8)?;
+        let start_time_offset = reader.read_u32()?;
+        let end_time_offset = reader.read_u32()?;
+        let exception_count = reader.read_u16()? as usize;
+        let mut exceptions = Vec::with_capacity(exception_count);
+        for _ in 0..exception_count {
+            exceptions.push(RecurrenceException::read(&mut reader)?);
+        }
+
+        let interval = match frequency {
+            // Daily periods are in minutes, yearly ones in months.
+            RecurrenceFrequency::Daily if pattern_type == Self::PATTERN_DAY => {
+                period / Self::MINUTES_PER_DAY
+            }
+            RecurrenceFrequency::Yearly => period / 12,
+            _ => period,
+        };
+        let end = match end_type {
+            Self::END_AFTER_DATE => RecurrenceEnd::Date(end_date),
+            Self::END_AFTER_N_OCCURRENCES => RecurrenceEnd::Count(occurrence_count),
+            _ => RecurrenceEnd::Never,
+        };
+        Ok(Self {
+            frequency,
+            interval,
+            days,
+            day_of_month,
+            nth,
+            first_day_of_week,
+            start_date,
+            end,
+            start_time_offset,
+            end_time_offset,
+            deleted_dates,
+            exceptions,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{NaiveDate, NaiveDateTime, Weekday};
+
+    use super::{RecurrenceEnd, RecurrenceFrequency, RecurrencePattern};
+
+    fn minutes(date: NaiveDateTime) -> u32 {
+        let epoch = NaiveDate::from_ymd_opt(1601, 1, 1)
+            .unwrap()
+            .and_hms_opt(0, 0, 0)
+            .unwrap();
+        (date - epoch).num_minutes() as u32
+    }
+
+    fn datetime(year: i32, month: u32, day: u32, hour: u32) -> NaiveDateTime {
+        NaiveDate::from_ymd_opt(year, month, day)
+            .unwrap()
+            .and_hms_opt(hour, 0, 0)
+            .unwrap()
+    }
+
+    fn push_u16(buff: &mut Vec<u8>, value: u16) {
+        buff.extend_from_slice(&value.to_le_bytes());
+    }
+
+    fn push_u32(buff: &mut Vec<u8>, value: u32) {
+        buff.extend_from_slice(&value.to_le_bytes());
+    }
+
+    // Weekly on Monday and Wednesday every 2 weeks, from
+    // 2021-06-07 for 10 occurrences, 09:00 to 10:00, with
+    // one deleted and one moved occurrence.
+    fn weekly_recur() -> Vec<u8> {
+        let mut buff = vec![];
+        push_u16(&mut buff, 0x3004);
+        push_u16(&mut buff, 0x3004);
+        push_u16(&mut buff, 0x200B);
+        push_u16(&mut buff, 0x0001);
+        push_u16(&mut buff, 0x0000);
+        push_u32(&mut buff, 0);
+        push_u32(&mut buff, 2);
+        push_u32(&mut buff, 0);
+        // Monday | Wednesday
+        push_u32(&mut buff, 0x02 | 0x08);
+        push_u32(&mut buff, 0x2022);
+        push_u32(&mut buff, 10);
+        // Monday
+        push_u32(&mut buff, 1);
+        // Deleted instances
+        push_u32(&mut buff, 2);
+        push_u32(&mut buff, minutes(datetime(2021, 6, 9, 0)));
+        push_u32(&mut buff, minutes(datetime(2021, 6, 21, 0)));
+        // Modified instances
+        push_u32(&mut buff, 1);
+        push_u32(&mut buff, minutes(datetime(2021, 6, 21, 0)));
+        push_u32(&mut buff, minutes(datetime(2021, 6, 7, 0)));
+        push_u32(&mut buff, minutes(datetime(2021, 7, 28, 0)));
+        push_u32(&mut buff, 0x3006);
+        push_u32(&mut buff, 0x3009);
+        push_u32(&mut buff, 9 * 60);
+        push_u32(&mut buff, 10 * 60);
+        // One exception, moved an hour later in another room.
+        push_u16(&mut buff, 1);
+        push_u32(&mut buff, minutes(datetime(2021, 6, 21, 10)));
+        push_u32(&mut buff, minutes(datetime(2021, 6, 21, 11)));
+        push_u32(&mut buff, minutes(datetime(2021, 6, 21, 9)));
+        // ARO_LOCATION | ARO_BUSYSTATUS
+        push_u16(&mut buff, 0x0010 | 0x0020);
+        push_u16(&mut buff, 5);
+        push_u16(&mut buff, 4);
+        buff.extend_from_slice(b"R101");
+        push_u32(&mut buff, 2);
+        // ReservedBlock1Size
+        push_u32(&mut buff, 0);
+        buff
+    }
+
+    #[test]
+    fn test_weekly_pattern() {
+        let pattern = RecurrencePattern::from_appointment_recur(&weekly_recur()).unwrap();
+        assert_eq!(pattern.frequency, RecurrenceFrequency::Weekly);
+        assert_eq!(pattern.interval, 2);
+        assert_eq!(pattern.days, vec![Weekday::Mon, Weekday::Wed]);
+        assert_eq!(pattern.day_of_month, None);
+        assert_eq!(pattern.nth, None);
+        assert_eq!(pattern.first_day_of_week, Weekday::Mon);
+        assert_eq!(pattern.start_date, NaiveDate::from_ymd_opt(2021, 6, 7).unwrap());
+        assert_eq!(pattern.end, RecurrenceEnd::Count(10));
+        assert_eq!(pattern.start_time_offset, 540);
+        assert_eq!(pattern.end_time_offset, 600);
+        assert_eq!(
+            pattern.deleted_dates,
+            vec![datetime(2021, 6, 9, 0), datetime(2021, 6, 21, 0)]
+        );
+        assert_eq!(pattern.exceptions.len(), 1);
+        let exception = &pattern.exceptions[0];
+        assert_eq!(exception.original_start, datetime(2021, 6, 21, 9));
+        assert_eq!(exception.start, datetime(2021, 6, 21, 10));
+        assert_eq!(exception.end, datetime(2021, 6, 21, 11));
+        assert_eq!(exception.subject, None);
+        assert_eq!(exception.location, Some("R101".to_string()));
+    }
+
+    #[test]
+    fn test_daily_and_monthly_patterns() {
+        // Every 3 days, no end date.
+        let mut buff = vec![];
+        push_u16(&mut buff, 0x3004);
+        push_u16(&mut buff, 0x3004);
+        push_u16(&mut buff, 0x200A);
+        push_u16(&mut buff, 0x0000);
+        push_u16(&mut buff, 0x0000);
+        push_u32(&mut buff, 0);
+        push_u32(&mut buff, 3 * 1440);
+        push_u32(&mut buff, 0);
+        push_u32(&mut buff, 0x2023);
+        push_u32(&mut buff, 10);
+        push_u32(&mut buff, 0);
+        push_u32(&mut buff, 0);
+        push_u32(&mut buff, 0);
+        push_u32(&mut buff, minutes(datetime(2021, 6, 7, 0)));
+        push_u32(&mut buff, 0x5AE980DF);
+        push_u32(&mut buff, 0x3006);
+        push_u32(&mut buff, 0x3008);
+        push_u32(&mut buff, 0);
+        push_u32(&mut buff, 30);
+        push_u16(&mut buff, 0);
+        let pattern = RecurrencePattern::from_appointment_recur(&buff).unwrap();
+        assert_eq!(pattern.frequency, RecurrenceFrequency::Daily);
+        assert_eq!(pattern.interval, 3);
+        assert_eq!(pattern.end, RecurrenceEnd::Never);
+        assert_eq!(pattern.first_day_of_week, Weekday::Sun);
+
+        // Second Tuesday of every month until 2021-12-31.
+        let mut buff = vec![];
+        push_u16(&mut buff, 0x3004);
+        push_u16(&mut buff, 0x3004);
+        push_u16(&mut buff, 0x200C);
+        push_u16(&mut buff, 0x0003);
+        push_u16(&mut buff, 0x0000);
+        push_u32(&mut buff, 0);
+        push_u32(&mut buff, 1);
+        push_u32(&mut buff, 0);
+        push_u32(&mut buff, 0x04);
+        push_u32(&mut buff, 2);
+        push_u32(&mut buff, 0x2021);
+        push_u32(&mut buff, 7);
+        push_u32(&mut buff, 0);
+        push_u32(&mut buff, 0);
+        push_u32(&mut buff, 0);
+        push_u32(&mut buff, minutes(datetime(2021, 6, 8, 0)));
+        push_u32(&mut buff, minutes(datetime(2021, 12, 31, 0)));
+        push_u32(&mut buff, 0x3006);
+        push_u32(&mut buff, 0x3008);
+        push_u32(&mut buff, 0);
+        push_u32(&mut buff, 30);
+        push_u16(&mut buff, 0);
+        let pattern = RecurrencePattern::from_appointment_recur(&buff).unwrap();
+        assert_eq!(pattern.frequency, RecurrenceFrequency::Monthly);
+        assert_eq!(pattern.interval, 1);
+        assert_eq!(pattern.days, vec![Weekday::Tue]);
+        assert_eq!(pattern.nth, Some(2));
+        assert_eq!(
+            pattern.end,
+            RecurrenceEnd::Date(NaiveDate::from_ymd_opt(2021, 12, 31).unwrap())
+        );
+    }
+
+    #[test]
+    fn test_malformed_recur() {
+        let err = RecurrencePattern::from_appointment_recur(&[0x04, 0x30, 0x04]).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "DataTypeError: Malformed blob: expected 2 bytes at offset 2, 1 left"
+        );
+        let err = RecurrencePattern::from_appointment_recur(&[0x06, 0x30]).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "DataTypeError: Malformed blob: unsupported recurrence version 0x3006"
+        );
+        // Truncated right after the exception count.
+        let mut buff = weekly_recur();
+        buff.truncate(buff.len() - 30);
+        assert!(RecurrencePattern::from_appointment_recur(&buff).is_err());
+    }
+}
@@ -0,0 +1,456 @@
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+use super::{
+    builder::set_display_fields,
+    decode::DataType,
+    eml::{fold_field, format_mailboxes},
+    error::Error,
+    extract::AttachmentFilter,
+    flags::MessageFlags,
+    headers::addresses,
+    limits::Limits,
+    outlook::{Attachment, Outlook, Person},
+    storage::Properties,
+    verbose::MessageProperties,
+};
+
+// Properties the RTF body is kept in sync with. It is
+// dropped once any of them changes, so that no copy of
+// redacted text survives in it.
+const BODY_KEYS: [&str; 2] = ["Body", "Html"];
+const RTF_KEYS: [&str; 2] = ["RtfCompressed", "RtfInSync"];
+
+// Transport header fields listing recipients.
+const RECIPIENT_FIELDS: [&str; 3] = ["To", "Cc", "Bcc"];
+
+// Whether bytes hold the address, in ASCII or UTF-16,
+// whatever its case.
+fn holds_address(bytes: &[u8], address: &str) -> bool {
+    let bytes = bytes.to_ascii_lowercase();
+    let address = address.to_ascii_lowercase();
+    let utf16: Vec<u8> = address.encode_utf16().flat_map(u16::to_le_bytes).collect();
+    [address.into_bytes(), utf16]
+        .iter()
+        .filter(|needle| !needle.is_empty())
+        .any(|needle| bytes.windows(needle.len()).any(|window| window == needle.as_slice()))
+}
+
+// Text with every occurrence of the address taken out,
+// whatever its case.
+fn remove_address(text: &str, address: &str) -> String {
+    if address.is_empty() {
+        return text.to_string();
+    }
+    // Lowercasing ASCII keeps the offsets.
+    let lower = text.to_ascii_lowercase();
+    let mut kept = String::with_capacity(text.len());
+    let mut start = 0;
+    for (index, _) in lower.match_indices(&address.to_ascii_lowercase()) {
+        kept.push_str(&text[start..index]);
+        start = index + address.len();
+    }
+    kept.push_str(&text[start..]);
+    kept
+}
+
+// Redaction lists what to take out of a message before
+// saving it again, e.g. for production in legal matters.
+// Binary properties other than the HTML body are left
+// untouched by replace_text(), so entry ids holding
+// addresses should be removed by name. The addresses of
+// removed recipients are taken out of every property, the
+// binary ones holding them being removed.
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Redaction {
+    pub properties: Vec<String>,             // Properties removed from the message, its recipients and attachments
+    pub replacements: Vec<(String, String)>, // Properties given a text in place of their value
+    pub recipients: Vec<String>,             // Addresses of recipients removed
+    pub attachments: Vec<AttachmentFilter>,  // Attachments removed when any filter matches
+    pub text: Vec<(String, String)>,         // Text replaced in every string
+}
+
+impl Redaction {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn remove_property(mut self, name: &str) -> Self {
+        self.properties.push(name.to_string());
+        self
+    }
+
+    pub fn replace_property(mut self, name: &str, value: &str) -> Self {
+        self.replacements.push((name.to_string(), value.to_string()));
+        self
+    }
+
+    pub fn remove_recipient(mut self, email: &str) -> Self {
+        self.recipients.push(email.to_string());
+        self
+    }
+
+    pub fn remove_attachments(mut self, filter: AttachmentFilter) -> Self {
+        self.attachments.push(filter);
+        self
+    }
+
+    pub fn replace_text(mut self, text: &str, with: &str) -> Self {
+        self.text.push((text.to_string(), with.to_string()));
+        self
+    }
+
+    fn removes_address(&self, address: &str) -> bool {
+        self.recipients
+            .iter()
+            .any(|email| email.eq_ignore_ascii_case(address))
+    }
+
+    fn removes_recipient(&self, props: &Properties) -> bool {
+        ["SmtpAddress", "EmailAddress"]
+            .iter()
+            .filter_map(|key| props.get(*key).and_then(DataType::as_str))
+            .any(|address| self.removes_address(address))
+    }
+
+    fn holds_removed_address(&self, bytes: &[u8]) -> bool {
+        self.recipients
+            .iter()
+            .any(|email| holds_address(bytes, email))
+    }
+
+    fn without_removed_addresses(&self, text: &str) -> String {
+        self.recipients
+            .iter()
+            .fold(text.to_string(), |text, email| remove_address(&text, email))
+    }
+
+    // Transport headers less the mailboxes of removed
+    // recipients in the fields listing recipients, those
+    // left empty being dropped.
+    fn remove_mailboxes(&self, headers: &str) -> String {
+        let mut kept = String::with_capacity(headers.len());
+        let mut lines = headers.split_inclusive('\n').peekable();
+        while let Some(line) = lines.next() {
+            // The body, if any, is kept as it is.
+            if line.trim_end().is_empty() {
+                kept.push_str(line);
+                lines.for_each(|line| kept.push_str(line));
+                break;
+            }
+            let mut field = line.to_string();
+            while let Some(line) = lines.next_if(|line| line.starts_with([' ', '\t'])) {
+                field.push_str(line);
+            }
+            let (name, value) = field.split_once(':').unwrap_or((&field, ""));
+            let name = name.trim();
+            if !RECIPIENT_FIELDS.iter().any(|field| field.eq_ignore_ascii_case(name)) {
+                kept.push_str(&field);
+                continue;
+            }
+            let mailboxes = addresses(&value.replace(['\r', '\n'], ""));
+            let left: Vec<Person> = mailboxes
+                .iter()
+                .filter(|(_, address)| !self.removes_address(address))
+                .map(|(name, address)| Person::new(name.to_string(), address.to_string()))
+                .collect();
+            if left.len() == mailboxes.len() {
+                kept.push_str(&field);
+            } else if !left.is_empty() {
+                kept.push_str(&fold_field(name, &format_mailboxes(&left)));
+            }
+        }
+        kept
+    }
+
+    // Takes the addresses of removed recipients out of the
+    // properties. Binary ones holding them, e.g. entry ids,
+    // cannot be rewritten and are removed.
+    fn scrub_addresses(&self, props: &mut Properties, is_root: bool) {
+        if self.recipients.is_empty() {
+            return;
+        }
+        props.retain(|key, value| match value {
+            DataType::PtypBinary(_) if is_root && key == "Html" => true,
+            DataType::PtypBinary(bytes) => !self.holds_removed_address(bytes),
+            DataType::PtypMultipleBinary(values) => {
+                !values.iter().any(|bytes| self.holds_removed_address(bytes))
+            }
+            _ => true,
+        });
+        for (key, value) in props.iter_mut() {
+            match value {
+                DataType::PtypString(string) if is_root && key == "TransportMessageHeaders" => {
+                    *string = self.without_removed_addresses(&self.remove_mailboxes(string));
+                }
+                DataType::PtypString(string) => *string = self.without_removed_addresses(string),
+                DataType::PtypMultipleString(strings) => {
+                    for string in strings.iter_mut() {
+                        *string = self.without_removed_addresses(string);
+                    }
+                }
+                DataType::PtypBinary(bytes) if self.holds_removed_address(bytes) => {
+                    *bytes = self
+                        .without_removed_addresses(&String::from_utf8_lossy(bytes))
+                        .into_bytes();
+                }
+                _ => {}
+            }
+        }
+    }
+
+    fn removes_attachment(&self, props: &Properties) -> bool {
+        let attachment = Attachment::create_from_props(props, &Limits::default());
+        self.attachments
+            .iter()
+            .any(|filter| filter.matches(&attachment))
+    }
+
+    fn replaced(&self, value: &str) -> String {
+        self.text
+            .iter()
+            .filter(|(text, _)| !text.is_empty())
+            .fold(value.to_string(), |value, (text, with)| value.replace(text, with))
+    }
+
+    fn redact_props(&self, props: &mut Properties, is_root: bool) {
+        for name in &self.properties {
+            props.remove(name);
+        }
+        for (name, value) in &self.replacements {
+            if let Some(old) = props.get_mut(name) {
+                *old = match old {
+                    DataType::PtypBinary(_) => DataType::PtypBinary(value.as_bytes().to_vec()),
+                    _ => DataType::PtypString(value.to_string()),
+                };
+            }
+        }
+        if self.text.is_empty() {
+            return;
+        }
+        for (key, value) in props.iter_mut() {
+            match value {
+                DataType::PtypString(string) => *string = self.replaced(string),
+                DataType::PtypMultipleString(strings) => {
+                    for string in strings.iter_mut() {
+                        *string = self.replaced(string);
+                    }
+                }
+                DataType::PtypBinary(bytes) if is_root && key == "Html" => {
+                    *bytes = self.replaced(&String::from_utf8_lossy(bytes)).into_bytes();
+                }
+                _ => {}
+            }
+        }
+    }
+}
+
+impl MessageProperties {
+    // Copy of the properties with the redaction applied,
+    // embedded messages included. Recipients and attachments
+    // left are numbered anew.
+    pub fn redact(&self, redaction: &Redaction) -> Self {
+        let mut props = self.clone();
+        props
+            .recipients
+            .retain(|recipient| !redaction.removes_recipient(recipient));
+        let (attachments, embedded) = self
+            .attachments
+            .iter()
+            .enumerate()
+            .filter(|(_, attachment)| !redaction.removes_attachment(attachment))
+            .map(|(index, attachment)| {
+                let embedded = self.embedded.get(index).and_then(Option::as_ref);
+                (attachment.clone(), embedded.map(|message| message.redact(redaction)))
+            })
+            .unzip();
+        props.attachments = attachments;
+        props.embedded = embedded;
+        for (row, recipient) in props.recipients.iter_mut().enumerate() {
+            recipient.insert("Rowid".to_string(), DataType::PtypInteger32(row as i32));
+        }
+        for (index, attachment) in props.attachments.iter_mut().enumerate() {
+            attachment.insert("AttachNumber".to_string(), DataType::PtypInteger32(index as i32));
+        }
+        if props.recipients.len() != self.recipients.len() {
+            set_display_fields(&mut props);
+        }
+        if props.attachments.is_empty() && props.root.contains_key("MessageFlags") {
+            let flags = MessageFlags {
+                has_attachments: false,
+                ..MessageFlags::create_from_props(&props.root)
+            };
+            props.root.insert(
+                "MessageFlags".to_string(),
+                DataType::PtypInteger32(flags.to_value() as i32),
+            );
+        }
+
+        redaction.redact_props(&mut props.root, true);
+        redaction.scrub_addresses(&mut props.root, true);
+        for recipient in props.recipients.iter_mut() {
+            redaction.redact_props(recipient, false);
+            redaction.scrub_addresses(recipient, false);
+        }
+        for attachment in props.attachments.iter_mut() {
+            redaction.redact_props(attachment, false);
+            redaction.scrub_addresses(attachment, false);
+        }
+        let body_changed = BODY_KEYS
+            .iter()
+            .any(|key| props.root.get(*key) != self.root.get(*key));
+        if body_changed {
+            for key in RTF_KEYS {
+                props.root.remove(key);
+            }
+        }
+        props
+    }
+}
+
+impl Outlook {
+    // Bytes of a new MSG file holding the message with
+    // the redaction applied.
+    pub fn to_redacted_msg(&self, redaction: &Redaction) -> Vec<u8> {
+        self.message_properties().redact(redaction).to_msg()
+    }
+
+    pub fn write_redacted<P: AsRef<Path>>(&self, path: P, redaction: &Redaction) -> Result<(), Error> {
+        std::fs::write(path, self.to_redacted_msg(redaction))?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        super::{decode::DataType, extract::AttachmentFilter, outlook::Outlook},
+        holds_address, Redaction,
+    };
+
+    #[test]
+    fn test_redact_recipients_and_text() {
+        let outlook = Outlook::from_path("data/test_email.msg").unwrap();
+        let redaction = Redaction::new()
+            .remove_recipient("MARIRS@aol.in")
+            .replace_text("marirs", "[REDACTED]")
+            .replace_property("Subject", "Redacted");
+        let redacted = Outlook::from_slice(&outlook.to_redacted_msg(&redaction)).unwrap();
+        assert_eq!(redacted.subject, "Redacted".to_string());
+        assert_eq!(redacted.to[0].email, "[REDACTED]@gmail.com".to_string());
+        assert_eq!(redacted.cc.len(), 1);
+        assert_eq!(redacted.cc[0].email, "[REDACTED]@outlook.in".to_string());
+        // The address is listed twice among the recipients.
+        assert_eq!(redacted.recipients.len(), outlook.recipients.len() - 2);
+        assert_eq!(redacted.body, outlook.body);
+        assert_eq!(redacted.rtf_compressed, outlook.rtf_compressed);
+        assert_eq!(redacted.attachments.len(), outlook.attachments.len());
+
+        let props = outlook.properties.redact(&redaction);
+        assert_eq!(
+            props.root.get("DisplayCc").and_then(DataType::as_str),
+            Some("[REDACTED]@outlook.in")
+        );
+    }
+
+    #[test]
+    fn test_removed_addresses_absent() {
+        for name in ["test_email", "test_email_1", "test_email_4", "unicode"] {
+            let outlook = Outlook::from_path(format!("data/{}.msg", name)).unwrap();
+            let emails: Vec<&str> = outlook
+                .recipients
+                .iter()
+                .map(|recipient| recipient.email())
+                .filter(|email| email.contains('@'))
+                .collect();
+            assert!(!emails.is_empty(), "{}", name);
+            for email in emails {
+                let msg = outlook.to_redacted_msg(&Redaction::new().remove_recipient(email));
+                assert!(!holds_address(&msg, email), "{} {}", name, email);
+                let redacted = Outlook::from_slice(&msg).unwrap();
+                assert!(redacted
+                    .recipients
+                    .iter()
+                    .all(|recipient| !recipient.email().eq_ignore_ascii_case(email)));
+            }
+        }
+
+        let headers = "To: Jane <jane@contoso.com>,\r\n\tBob <bob@contoso.com>\r\n\
+            Cc: bob@contoso.com\r\n\
+            Received: by mx.contoso.com for <Bob@Contoso.com>\r\n";
+        let redaction = Redaction::new().remove_recipient("bob@contoso.com");
+        assert_eq!(
+            redaction.remove_mailboxes(headers),
+            "To: Jane <jane@contoso.com>\r\n\
+             Received: by mx.contoso.com for <Bob@Contoso.com>\r\n"
+                .to_string()
+        );
+        assert_eq!(
+            redaction.without_removed_addresses("for <Bob@Contoso.com>"),
+            "for <>".to_string()
+        );
+    }
+
+    #[test]
+    fn test_redact_attachments_and_body() {
+        let outlook = Outlook::from_path("data/attachment.msg").unwrap();
+        assert!(outlook.flags.has_attachments);
+        let redaction = Redaction::new()
+            .remove_attachments(AttachmentFilter::new().pattern("*.doc"))
+            .replace_text("Satya Nagisetti", "[NAME]")
+            .remove_property("HeaderBodyFragmentList");
+        let redacted = Outlook::from_slice(&outlook.to_redacted_msg(&redaction)).unwrap();
+        let names: Vec<&str> = redacted
+            .attachments
+            .iter()
+            .map(|attachment| attachment.display_name.as_str())
+            .collect();
+        assert_eq!(names, ["image001.png", "image002.jpg"]);
+        assert!(redacted.body.contains("[NAME]"));
+        assert!(!redacted.body.contains("Satya Nagisetti"));
+        assert!(redacted.rtf_compressed.is_empty());
+        assert!(!redacted.properties.root.contains_key("HeaderBodyFragmentList"));
+        assert!(redacted.flags.has_attachments);
+
+        let redaction = Redaction::new().remove_attachments(AttachmentFilter::new());
+        let props = outlook.properties.redact(&redaction);
+        assert!(props.attachments.is_empty());
+        let redacted = Outlook::from_slice(&props.to_msg()).unwrap();
+        assert!(!redacted.flags.has_attachments);
+        assert_eq!(redacted.body, outlook.body);
+    }
+
+    #[test]
+    fn test_redact_embedded() {
+        let outlook = Outlook::from_path("data/test_email_1.msg").unwrap();
+        let embedded = |outlook: &Outlook| -> Vec<String> {
+            outlook
+                .attachments
+                .iter()
+                .filter_map(|attachment| Some(attachment.embedded.as_ref()?.subject.clone()))
+                .collect()
+        };
+        let subjects = embedded(&outlook);
+        assert_eq!(subjects.len(), 3);
+        let redacted = Outlook::from_slice(&outlook.to_redacted_msg(&Redaction::new())).unwrap();
+        assert_eq!(embedded(&redacted), subjects);
+
+        let redaction = Redaction::new().replace_property("Subject", "Redacted");
+        let redacted = Outlook::from_slice(&outlook.to_redacted_msg(&redaction)).unwrap();
+        assert_eq!(embedded(&redacted), vec!["Redacted".to_string(); 3]);
+    }
+
+    #[test]
+    fn test_write_redacted() {
+        let outlook = Outlook::from_path("data/test_email.msg").unwrap();
+        let path = std::env::temp_dir().join(format!("redacted-{}.msg", std::process::id()));
+        outlook
+            .write_redacted(&path, &Redaction::new().remove_property("Subject"))
+            .unwrap();
+        let redacted = Outlook::from_path(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert!(redacted.subject.is_empty());
+        assert_eq!(redacted.body, outlook.body);
+    }
+}
@@ -0,0 +1,122 @@
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+
+use super::{clock::Clock, decode::DataType, storage::Properties};
+
+// Reminder describes the reminder of a message,
+// appointment or task.
+// Refer to MS-OXORMDR 2.2.1
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct Reminder {
+    pub set: bool,                          // "ReminderSet"
+    pub time: Option<DateTime<Utc>>,        // "ReminderTime"
+    pub signal_time: Option<DateTime<Utc>>, // "ReminderSignalTime"
+    pub delta_minutes: i64,                 // "ReminderDelta"
+    pub override_default: bool,             // "ReminderOverride"
+    pub play_sound: bool,                   // "ReminderPlaySound"
+    pub sound_file: String,                 // "ReminderFileParameter"
+}
+
+impl Reminder {
+    // Returns None when no reminder was ever set on the item.
+    pub fn create_from_props(props: &Properties) -> Option<Self> {
+        let set = props.get("ReminderSet").and_then(DataType::as_bool)?;
+        let bool_or_default = |key: &str| props.get(key).and_then(DataType::as_bool).unwrap_or(false);
+        Some(Self {
+            set,
+            time: props.get("ReminderTime").and_then(DataType::as_time),
+            signal_time: props.get("ReminderSignalTime").and_then(DataType::as_time),
+            delta_minutes: props
+                .get("ReminderDelta")
+                .and_then(DataType::as_i64)
+                .unwrap_or(0),
+            override_default: bool_or_default("ReminderOverride"),
+            play_sound: bool_or_default("ReminderPlaySound"),
+            sound_file: props
+                .get("ReminderFileParameter")
+                .and_then(DataType::as_str)
+                .unwrap_or_default()
+                .to_string(),
+        })
+    }
+
+    // The signal time is the reminder time less the delta,
+    // unless the reminder was snoozed in which case it is
+    // moved to when the reminder fires again.
+    pub fn snoozed_until(&self) -> Option<DateTime<Utc>> {
+        let time = self.time?;
+        let signal_time = self.signal_time?;
+        (signal_time > time - Duration::minutes(self.delta_minutes)).then_some(signal_time)
+    }
+
+    pub fn is_due(&self, clock: &dyn Clock) -> bool {
+        self.set && self.signal_time.is_some_and(|signal_time| signal_time <= clock.now())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{DateTime, TimeZone, Utc};
+
+    use super::{
+        super::{
+            clock::FixedClock,
+            decode::DataType,
+            storage::{props, string, Properties},
+        },
+        Reminder,
+    };
+
+    fn utc(hour: u32, min: u32) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2021, 6, 1, hour, min, 0).unwrap()
+    }
+
+    fn reminder_props(signal_time: DateTime<Utc>) -> Properties {
+        props(&[
+            ("ReminderSet", DataType::PtypBoolean(true)),
+            ("ReminderTime", DataType::PtypTime(utc(14, 0))),
+            ("ReminderSignalTime", DataType::PtypTime(signal_time)),
+            ("ReminderDelta", DataType::PtypInteger32(15)),
+            ("ReminderPlaySound", DataType::PtypBoolean(true)),
+            ("ReminderFileParameter", string("reminder.wav")),
+        ])
+    }
+
+    #[test]
+    fn test_create_reminder() {
+        let reminder = Reminder::create_from_props(&reminder_props(utc(13, 45))).unwrap();
+        assert_eq!(
+            reminder,
+            Reminder {
+                set: true,
+                time: Some(utc(14, 0)),
+                signal_time: Some(utc(13, 45)),
+                delta_minutes: 15,
+                override_default: false,
+                play_sound: true,
+                sound_file: "reminder.wav".to_string(),
+            }
+        );
+        assert_eq!(reminder.snoozed_until(), None);
+        assert!(!reminder.is_due(&FixedClock(utc(13, 44))));
+        assert!(reminder.is_due(&FixedClock(utc(13, 45))));
+    }
+
+    #[test]
+    fn test_snoozed_reminder() {
+        let reminder = Reminder::create_from_props(&reminder_props(utc(13, 55))).unwrap();
+        assert_eq!(reminder.snoozed_until(), Some(utc(13, 55)));
+        assert!(!reminder.is_due(&FixedClock(utc(13, 50))));
+    }
+
+    #[test]
+    fn test_no_reminder() {
+        assert_eq!(Reminder::create_from_props(&Properties::new()), None);
+
+        let mut props = reminder_props(utc(13, 45));
+        props.insert("ReminderSet".to_string(), DataType::PtypBoolean(false));
+        let reminder = Reminder::create_from_props(&props).unwrap();
+        assert!(!reminder.is_due(&FixedClock(utc(15, 0))));
+    }
+}
@@ -0,0 +1,312 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use super::{
+    decode::DataType,
+    entry_id::EntryId,
+    outlook::Person,
+    storage::{Properties, Recipients},
+};
+
+// OriginalEnvelope describes the message a report
+// was generated for.
+// Refer to MS-OXOMSG 2.2.2
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct OriginalEnvelope {
+    pub subject: String,                    // "OriginalSubject"
+    pub sender: Person,                     // "OriginalSenderName", "OriginalSenderEmailAddress"
+    pub display_to: String,                 // "OriginalDisplayTo"
+    pub display_cc: String,                 // "OriginalDisplayCc"
+    pub submit_time: Option<DateTime<Utc>>, // "OriginalSubmitTime"
+}
+
+impl OriginalEnvelope {
+    fn create_from_props(props: &Properties) -> Self {
+        let string = |key: &str| props.get(key).map(String::from).unwrap_or_default();
+        Self {
+            subject: string("OriginalSubject"),
+            sender: Person::create_from_props(
+                props,
+                "OriginalSenderName",
+                vec!["OriginalSenderEmailAddress"],
+            ),
+            display_to: string("OriginalDisplayTo"),
+            display_cc: string("OriginalDisplayCc"),
+            submit_time: props.get("OriginalSubmitTime").and_then(DataType::as_time),
+        }
+    }
+}
+
+// NdrRecipient is a recipient the original message
+// could not be delivered to, along with why.
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct NdrRecipient {
+    pub recipient: Person,            // "DisplayName", "SmtpAddress"/"EmailAddress"
+    pub reason_code: Option<i64>,     // "NonDeliveryReportReasonCode"
+    pub diagnostic_code: Option<i64>, // "NonDeliveryReportDiagCode"
+    pub status_code: Option<i64>,     // "NonDeliveryReportStatusCode"
+    pub supplementary_info: String,   // "SupplementaryInfo"
+    pub remote_mta: String,           // "RemoteMessageTransferAgent"
+}
+
+impl NdrRecipient {
+    fn create_from_props(props: &Properties) -> Self {
+        let code = |key: &str| props.get(key).and_then(DataType::as_i64);
+        let string = |key: &str| props.get(key).map(String::from).unwrap_or_default();
+        Self {
+            recipient: Person::create_from_props(
+                props,
+                "DisplayName",
+                vec!["SmtpAddress", "EmailAddress"],
+            ),
+            reason_code: code("NonDeliveryReportReasonCode"),
+            diagnostic_code: code("NonDeliveryReportDiagCode"),
+            status_code: code("NonDeliveryReportStatusCode"),
+            supplementary_info: string("SupplementaryInfo"),
+            remote_mta: string("RemoteMessageTransferAgent"),
+        }
+    }
+}
+
+// NonDeliveryReport holds the properties of a
+// REPORT.IPM.Note.NDR message, its recipients being
+// the ones delivery failed for.
+// Refer to MS-OXOMSG 2.2.2.22 and 2.2.3
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct NonDeliveryReport {
+    pub report_text: String,           // "ReportText"
+    pub recipients: Vec<NdrRecipient>, // See NdrRecipient struct
+    pub original: OriginalEnvelope,    // See OriginalEnvelope struct
+}
+
+impl NonDeliveryReport {
+    // Report classes are made up from the class of the
+    // original message, e.g. REPORT.IPM.Schedule.Meeting.Request.NDR.
+    pub fn is_ndr_class(message_class: &str) -> bool {
+        let message_class = message_class.to_ascii_lowercase();
+        message_class.starts_with("report.") && message_class.ends_with(".ndr")
+    }
+
+    pub fn create_from_props(props: &Properties, recipients: &Recipients) -> Option<Self> {
+        let message_class = props.get("MessageClass").and_then(DataType::as_str)?;
+        if !Self::is_ndr_class(message_class) {
+            return None;
+        }
+        Some(Self {
+            report_text: props
+                .get("ReportText")
+                .and_then(DataType::as_str)
+                .unwrap_or_default()
+                .to_string(),
+            recipients: recipients.iter().map(NdrRecipient::create_from_props).collect(),
+            original: OriginalEnvelope::create_from_props(props),
+        })
+    }
+}
+
+// ReceiptKind is the kind of receipt, as told
+// by the suffix of its message class.
+// Refer to MS-OXOMSG 2.2.1.1
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub enum ReceiptKind {
+    Read,     // ".IPNRN"
+    NotRead,  // ".IPNNRN"
+    Delivery, // ".DR"
+}
+
+impl ReceiptKind {
+    pub fn from_message_class(message_class: &str) -> Option<Self> {
+        let message_class = message_class.to_ascii_lowercase();
+        if !message_class.starts_with("report.") {
+            return None;
+        }
+        match message_class.rsplit('.').next() {
+            Some("ipnrn") => Some(ReceiptKind::Read),
+            Some("ipnnrn") => Some(ReceiptKind::NotRead),
+            Some("dr") => Some(ReceiptKind::Delivery),
+            _ => None,
+        }
+    }
+}
+
+// Receipt holds the properties of a read, not read
+// or delivery receipt.
+// Refer to MS-OXOMSG 2.2.2
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct Receipt {
+    pub kind: ReceiptKind,                   // "MessageClass"
+    pub original_message_id: String,         // "InReplyToId", "InternetReferences"
+    pub receipt_time: Option<DateTime<Utc>>, // "ReportTime"
+    pub reporter: Person,                    // "ReportName", "ReportEntryId"
+    pub original: OriginalEnvelope,          // See OriginalEnvelope struct
+}
+
+impl Receipt {
+    // Address of the recipient that generated the receipt.
+    fn reporter_email(props: &Properties) -> String {
+        let entry_id = props
+            .get("ReportEntryId")
+            .and_then(DataType::as_bytes)
+            .and_then(|bytes| EntryId::from_bytes(bytes).ok());
+        match entry_id.as_ref().map(EntryId::unwrap_entry_id) {
+            Some(EntryId::OneOff { email_address, .. }) => email_address.to_string(),
+            Some(EntryId::AddressBook { x500_dn }) => x500_dn.to_string(),
+            _ => String::new(),
+        }
+    }
+
+    pub fn create_from_props(props: &Properties) -> Option<Self> {
+        let kind = props
+            .get("MessageClass")
+            .and_then(DataType::as_str)
+            .and_then(ReceiptKind::from_message_class)?;
+        let string = |key: &str| {
+            props
+                .get(key)
+                .and_then(DataType::as_str)
+                .unwrap_or_default()
+                .trim()
+                .to_string()
+        };
+        // Receipts reply to the original message, which is
+        // also the last of the references when there are any.
+        let mut original_message_id = string("InReplyToId");
+        if original_message_id.is_empty() {
+            original_message_id = string("InternetReferences")
+                .split_whitespace()
+                .last()
+                .unwrap_or_default()
+                .to_string();
+        }
+        Some(Self {
+            kind,
+            original_message_id,
+            receipt_time: props.get("ReportTime").and_then(DataType::as_time),
+            reporter: Person::new(string("ReportName"), Self::reporter_email(props)),
+            original: OriginalEnvelope::create_from_props(props),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{TimeZone, Utc};
+
+    use super::{
+        super::{decode::DataType, entry_id::tests::one_off_entry_id, storage::Properties},
+        NdrRecipient, NonDeliveryReport, OriginalEnvelope, Person, Receipt, ReceiptKind,
+    };
+
+    fn string(value: &str) -> DataType {
+        DataType::PtypString(value.to_string())
+    }
+
+    #[test]
+    fn test_create_ndr() {
+        let submit_time = Utc.with_ymd_and_hms(2021, 6, 1, 9, 30, 0).unwrap();
+        let mut props = Properties::new();
+        props.insert("MessageClass".to_string(), string("REPORT.IPM.Note.NDR"));
+        props.insert("ReportText".to_string(), string("Delivery has failed."));
+        props.insert("OriginalSubject".to_string(), string("Hello"));
+        props.insert("OriginalSenderName".to_string(), string("Jane Doe"));
+        props.insert("OriginalSenderEmailAddress".to_string(), string("jane@contoso.com"));
+        props.insert("OriginalDisplayTo".to_string(), string("nobody@example.org"));
+        props.insert("OriginalSubmitTime".to_string(), DataType::PtypTime(submit_time));
+
+        let mut recipient = Properties::new();
+        recipient.insert("DisplayName".to_string(), string("nobody@example.org"));
+        recipient.insert("EmailAddress".to_string(), string("nobody@example.org"));
+        recipient.insert("NonDeliveryReportReasonCode".to_string(), DataType::PtypInteger32(0));
+        recipient.insert("NonDeliveryReportDiagCode".to_string(), DataType::PtypInteger32(0));
+        recipient.insert("NonDeliveryReportStatusCode".to_string(), DataType::PtypInteger32(550));
+        recipient.insert(
+            "SupplementaryInfo".to_string(),
+            string("550 5.1.1 User unknown"),
+        );
+        recipient.insert("RemoteMessageTransferAgent".to_string(), string("dns;mx.example.org"));
+
+        let report = NonDeliveryReport::create_from_props(&props, &vec![recipient]).unwrap();
+        assert_eq!(
+            report,
+            NonDeliveryReport {
+                report_text: "Delivery has failed.".to_string(),
+                recipients: vec![NdrRecipient {
+                    recipient: Person::new(
+                        "nobody@example.org".to_string(),
+                        "nobody@example.org".to_string()
+                    ),
+                    reason_code: Some(0),
+                    diagnostic_code: Some(0),
+                    status_code: Some(550),
+                    supplementary_info: "550 5.1.1 User unknown".to_string(),
+                    remote_mta: "dns;mx.example.org".to_string(),
+                }],
+                original: OriginalEnvelope {
+                    subject: "Hello".to_string(),
+                    sender: Person::new("Jane Doe".to_string(), "jane@contoso.com".to_string()),
+                    display_to: "nobody@example.org".to_string(),
+                    display_cc: String::new(),
+                    submit_time: Some(submit_time),
+                },
+            }
+        );
+    }
+
+    #[test]
+    fn test_not_an_ndr() {
+        assert!(NonDeliveryReport::is_ndr_class("Report.IPM.Schedule.Meeting.Request.NDR"));
+        assert!(!NonDeliveryReport::is_ndr_class("REPORT.IPM.Note.IPNRN"));
+        assert!(!NonDeliveryReport::is_ndr_class("IPM.Note.NDR"));
+
+        let mut props = Properties::new();
+        props.insert("MessageClass".to_string(), string("IPM.Note"));
+        assert_eq!(NonDeliveryReport::create_from_props(&props, &vec![]), None);
+    }
+
+    #[test]
+    fn test_create_receipt() {
+        let report_time = Utc.with_ymd_and_hms(2021, 6, 1, 10, 0, 0).unwrap();
+        let mut props = Properties::new();
+        props.insert("MessageClass".to_string(), string("REPORT.IPM.Note.IPNRN"));
+        props.insert("ReportTime".to_string(), DataType::PtypTime(report_time));
+        props.insert("ReportName".to_string(), string("Bob"));
+        props.insert(
+            "ReportEntryId".to_string(),
+            DataType::PtypBinary(one_off_entry_id("Bob", "SMTP", "bob@contoso.com")),
+        );
+        props.insert(
+            "InternetReferences".to_string(),
+            string("<a@contoso.com> <b@contoso.com>"),
+        );
+        props.insert("OriginalSubject".to_string(), string("Hello"));
+
+        let receipt = Receipt::create_from_props(&props).unwrap();
+        assert_eq!(receipt.kind, ReceiptKind::Read);
+        assert_eq!(receipt.original_message_id, "<b@contoso.com>".to_string());
+        assert_eq!(receipt.receipt_time, Some(report_time));
+        assert_eq!(
+            receipt.reporter,
+            Person::new("Bob".to_string(), "bob@contoso.com".to_string())
+        );
+        assert_eq!(receipt.original.subject, "Hello".to_string());
+
+        props.insert("InReplyToId".to_string(), string("<a@contoso.com>"));
+        let receipt = Receipt::create_from_props(&props).unwrap();
+        assert_eq!(receipt.original_message_id, "<a@contoso.com>".to_string());
+    }
+
+    #[test]
+    fn test_receipt_kind() {
+        let kind = ReceiptKind::from_message_class;
+        assert_eq!(kind("REPORT.IPM.Note.IPNRN"), Some(ReceiptKind::Read));
+        assert_eq!(kind("Report.IPM.Note.IPNNRN"), Some(ReceiptKind::NotRead));
+        assert_eq!(kind("REPORT.IPM.Schedule.Meeting.Request.DR"), Some(ReceiptKind::Delivery));
+        assert_eq!(kind("REPORT.IPM.Note.NDR"), None);
+        assert_eq!(kind("IPM.Note.DR"), None);
+    }
+}
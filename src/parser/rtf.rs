@@ -0,0 +1,113 @@
+use std::convert::TryInto;
+
+use super::outlook::Outlook;
+
+// Types of compressed RTF, "LZFu" and "MELA".
+// Refer to MS-OXRTFCP 2.1.3.1.1
+const COMPRESSED: u32 = 0x7546_5A4C;
+const UNCOMPRESSED: u32 = 0x414C_454D;
+
+const HEADER_SIZE: usize = 16;
+const DICTIONARY_SIZE: usize = 4096;
+
+// Text the dictionary starts with.
+// Refer to MS-OXRTFCP 2.1.3.1.2
+const PREBUFFER: &[u8] = b"{\\rtf1\\ansi\\mac\\deff0\\deftab720{\\fonttbl;}{\\f0\\fnil \\froman \\fswiss \\fmodern \\fscript \
+\\fdecor MS Sans SerifSymbolArialTimes New RomanCourier{\\colortbl\\red0\\green0\\blue0\r\n\\par \
+\\pard\\plain\\f0\\fs20\\b\\i\\u\\tab\\tx";
+
+// Decompresses the "RtfCompressed" property, None when
+// it is neither compressed nor stored as is.
+// Refer to MS-OXRTFCP 2.2.3
+pub(crate) fn decompress(bytes: &[u8]) -> Option<Vec<u8>> {
+    let word = |offset: usize| Some(u32::from_le_bytes(bytes.get(offset..offset + 4)?.try_into().ok()?));
+    let size = (word(0)? as usize).saturating_add(4).min(bytes.len());
+    let raw_size = word(4)? as usize;
+    match word(8)? {
+        UNCOMPRESSED => return Some(bytes.get(HEADER_SIZE..size)?.iter().copied().take(raw_size).collect()),
+        COMPRESSED => {}
+        _ => return None,
+    }
+    let mut dictionary = [0u8; DICTIONARY_SIZE];
+    dictionary[..PREBUFFER.len()].copy_from_slice(PREBUFFER);
+    let mut write = PREBUFFER.len();
+    // The raw size is only trusted as far as the input
+    // could expand to.
+    let mut output = Vec::with_capacity(raw_size.min(size * 8));
+    let mut input = bytes.get(HEADER_SIZE..size)?.iter().copied();
+    while let Some(control) = input.next() {
+        for bit in 0..8 {
+            if control & (1 << bit) == 0 {
+                let byte = match input.next() {
+                    Some(byte) => byte,
+                    None => return Some(output),
+                };
+                output.push(byte);
+                dictionary[write] = byte;
+                write = (write + 1) % DICTIONARY_SIZE;
+                continue;
+            }
+            let token = match (input.next(), input.next()) {
+                (Some(high), Some(low)) => u16::from_be_bytes([high, low]) as usize,
+                _ => return Some(output),
+            };
+            let (offset, length) = (token >> 4, (token & 0xF) + 2);
+            if offset == write {
+                output.truncate(raw_size);
+                return Some(output);
+            }
+            for index in 0..length {
+                let byte = dictionary[(offset + index) % DICTIONARY_SIZE];
+                output.push(byte);
+                dictionary[write] = byte;
+                write = (write + 1) % DICTIONARY_SIZE;
+            }
+        }
+    }
+    output.truncate(raw_size);
+    Some(output)
+}
+
+impl Outlook {
+    // RTF body, decompressed from rtf_compressed. Bytes
+    // past ASCII are kept as Latin-1, RTF escaping others.
+    pub fn rtf_body(&self) -> String {
+        hex::decode(&self.rtf_compressed)
+            .ok()
+            .and_then(|bytes| decompress(&bytes))
+            .map(|rtf| rtf.iter().map(|byte| char::from(*byte)).collect())
+            .unwrap_or_default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{super::outlook::Outlook, decompress, PREBUFFER};
+
+    #[test]
+    fn test_decompress() {
+        assert_eq!(PREBUFFER.len(), 207);
+
+        // Example of MS-OXRTFCP 3.1.1
+        let compressed = [
+            0x2d, 0x00, 0x00, 0x00, 0x2b, 0x00, 0x00, 0x00, 0x4c, 0x5a, 0x46, 0x75, 0xf1, 0xc5, 0xc7, 0xa7, 0x03, 0x00,
+            0x0a, 0x00, 0x72, 0x63, 0x70, 0x67, 0x31, 0x32, 0x35, 0x42, 0x32, 0x0a, 0xf3, 0x20, 0x68, 0x65, 0x6c, 0x09,
+            0x00, 0x20, 0x62, 0x77, 0x05, 0xb0, 0x6c, 0x64, 0x7d, 0x0a, 0x80, 0x0f, 0xa0,
+        ];
+        assert_eq!(
+            decompress(&compressed).unwrap(),
+            b"{\\rtf1\\ansi\\ansicpg1252\\pard hello world}\r\n".to_vec()
+        );
+        assert_eq!(decompress(&compressed[..8]), None);
+
+        let mut stored = vec![0x13, 0, 0, 0, 0x07, 0, 0, 0, 0x4d, 0x45, 0x4c, 0x41, 0, 0, 0, 0];
+        stored.extend_from_slice(b"{\\rtf1}");
+        assert_eq!(decompress(&stored).unwrap(), b"{\\rtf1}".to_vec());
+    }
+
+    #[test]
+    fn test_rtf_body() {
+        let outlook = Outlook::from_path("data/test_email.msg").unwrap();
+        assert!(outlook.rtf_body().starts_with("{\\rtf1"));
+    }
+}
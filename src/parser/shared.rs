@@ -0,0 +1,99 @@
+use std::{ops::Deref, sync::Arc};
+
+use super::{
+    error::Error,
+    outlook::{Attachment, Outlook},
+    warning::ParseReport,
+};
+
+// Parsed messages are meant to be shared across threads,
+// which a field neither Send nor Sync would break, failing
+// the build here rather than in the code of users.
+const _: fn() = || {
+    fn assert_send_sync<T: Send + Sync>() {}
+    assert_send_sync::<Outlook>();
+    assert_send_sync::<ParseReport>();
+    assert_send_sync::<Error>();
+    assert_send_sync::<SharedAttachment>();
+};
+
+// SharedAttachment is an attachment of a message shared
+// through an Arc, which a thread can hold on its own, e.g.
+// one thread hashing the attachments while another renders
+// the body, none of them copying the message.
+#[derive(Debug, Clone)]
+pub struct SharedAttachment {
+    message: Arc<Outlook>,
+    index: usize, // Index of the attachment in the message
+}
+
+impl SharedAttachment {
+    pub fn message(&self) -> &Arc<Outlook> {
+        &self.message
+    }
+
+    pub fn index(&self) -> usize {
+        self.index
+    }
+}
+
+impl Deref for SharedAttachment {
+    type Target = Attachment;
+
+    fn deref(&self) -> &Attachment {
+        &self.message.attachments[self.index]
+    }
+}
+
+impl PartialEq for SharedAttachment {
+    fn eq(&self, other: &Self) -> bool {
+        self.index == other.index && Arc::ptr_eq(&self.message, &other.message)
+    }
+}
+
+impl Outlook {
+    // Attachments of a message shared through an Arc, each
+    // holding a reference to the message.
+    pub fn shared_attachments(message: &Arc<Self>) -> Vec<SharedAttachment> {
+        (0..message.attachments.len())
+            .map(|index| SharedAttachment {
+                message: Arc::clone(message),
+                index,
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{sync::Arc, thread};
+
+    use sha2::{Digest, Sha256};
+
+    use super::super::outlook::Outlook;
+
+    #[test]
+    fn test_shared_attachments() {
+        let message = Arc::new(Outlook::from_path("data/attachment.msg").unwrap());
+        let attachments = Outlook::shared_attachments(&message);
+        assert_eq!(attachments.len(), 3);
+        assert_eq!(attachments[1].index(), 1);
+        assert_eq!(attachments[1].display_name, message.attachments[1].display_name);
+        assert_eq!(attachments[0], attachments[0].clone());
+        assert_ne!(attachments[0], attachments[1]);
+
+        let hashing = thread::spawn(move || {
+            attachments
+                .iter()
+                .map(|attachment| hex::encode(Sha256::digest(hex::decode(&attachment.payload).unwrap())))
+                .collect::<Vec<String>>()
+        });
+        let body = {
+            let message = Arc::clone(&message);
+            thread::spawn(move || message.body_text())
+        };
+        assert_eq!(hashing.join().unwrap().len(), 3);
+        assert_eq!(body.join().unwrap(), message.body_text());
+        assert_eq!(Arc::strong_count(&message), 1);
+    }
+}
@@ -0,0 +1,535 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha224, Sha256, Sha384, Sha512};
+
+use super::{
+    decode::DataType,
+    der::{DerReader, Tlv, TAG_BOOLEAN, TAG_INTEGER, TAG_OCTET_STRING, TAG_OID, TAG_SEQUENCE, TAG_SET},
+    error::DataTypeError,
+    mime::MimePart,
+    storage::{Attachments, Properties},
+};
+
+// Object identifiers of CMS content types and attributes.
+// Refer to RFC 5652 and RFC 5280
+pub(crate) const OID_SIGNED_DATA: &str = "1.2.840.113549.1.7.2";
+const OID_MESSAGE_DIGEST: &str = "1.2.840.113549.1.9.4";
+const OID_SIGNING_TIME: &str = "1.2.840.113549.1.9.5";
+const OID_SUBJECT_ALT_NAME: &str = "2.5.29.17";
+const OID_EMAIL_ADDRESS: &str = "1.2.840.113549.1.9.1";
+
+// Short names of the attributes of a distinguished name.
+const NAME_ATTRIBUTES: [(&str, &str); 10] = [
+    ("2.5.4.3", "CN"),
+    ("2.5.4.4", "SN"),
+    ("2.5.4.5", "serialNumber"),
+    ("2.5.4.6", "C"),
+    ("2.5.4.7", "L"),
+    ("2.5.4.8", "ST"),
+    ("2.5.4.10", "O"),
+    ("2.5.4.11", "OU"),
+    ("2.5.4.42", "GN"),
+    (OID_EMAIL_ADDRESS, "emailAddress"),
+];
+
+const DIGEST_ALGORITHMS: [(&str, &str); 6] = [
+    ("1.2.840.113549.2.5", "md5"),
+    ("1.3.14.3.2.26", "sha1"),
+    ("2.16.840.1.101.3.4.2.1", "sha256"),
+    ("2.16.840.1.101.3.4.2.2", "sha384"),
+    ("2.16.840.1.101.3.4.2.3", "sha512"),
+    ("2.16.840.1.101.3.4.2.4", "sha224"),
+];
+
+// General name tag of an email address.
+const RFC822_NAME: u8 = 0x81;
+
+// Content type and content of a CMS ContentInfo.
+pub(crate) fn content_info(buff: &[u8]) -> Result<(String, Tlv<'_>), DataTypeError> {
+    let mut info = DerReader::new(buff).read_tag(TAG_SEQUENCE)?.children();
+    let content_type = info.read_tag(TAG_OID)?.oid()?;
+    let content = info.read_tag(0xA0)?.children().read()?;
+    Ok((content_type, content))
+}
+
+// MIME entity, multiparts nested too deep being malformed.
+fn parse_entity(data: &[u8]) -> Result<MimePart, DataTypeError> {
+    MimePart::parse(data).map_err(|err| DataTypeError::MalformedBlob(err.to_string()))
+}
+
+// DER of an "application/pkcs7-mime" entity, which
+// may be kept along with its MIME header fields.
+pub(crate) fn pkcs7_der(data: &[u8]) -> Vec<u8> {
+    if data.first() == Some(&TAG_SEQUENCE) {
+        data.to_vec()
+    } else {
+        MimePart::parse(data).map(|part| part.body).unwrap_or_default()
+    }
+}
+
+// Attributes of a distinguished name, in order.
+pub(crate) fn name_attributes(tlv: Tlv) -> Result<Vec<(String, String)>, DataTypeError> {
+    let mut attributes = vec![];
+    let mut rdns = tlv.children();
+    while !rdns.is_empty() {
+        let mut rdn = rdns.read_tag(TAG_SET)?.children();
+        while !rdn.is_empty() {
+            let mut attribute = rdn.read_tag(TAG_SEQUENCE)?.children();
+            let oid = attribute.read_tag(TAG_OID)?.oid()?;
+            let value = attribute.read()?.string();
+            let key = NAME_ATTRIBUTES
+                .iter()
+                .find(|(name_oid, _)| *name_oid == oid)
+                .map_or(oid.clone(), |(_, key)| key.to_string());
+            attributes.push((key, value));
+        }
+    }
+    Ok(attributes)
+}
+
+// Distinguished name written the way OpenSSL does,
+// e.g. "C=US, O=Contoso, CN=Jane Doe".
+pub(crate) fn format_name(attributes: &[(String, String)]) -> String {
+    attributes
+        .iter()
+        .map(|(key, value)| format!("{}={}", key, value))
+        .collect::<Vec<String>>()
+        .join(", ")
+}
+
+// Serial number in hex, without the sign byte.
+pub(crate) fn serial_number(tlv: Tlv) -> String {
+    match tlv.content {
+        [0, rest @ ..] if !rest.is_empty() => hex::encode_upper(rest),
+        content => hex::encode_upper(content),
+    }
+}
+
+fn digest(algorithm: &str, data: &[u8]) -> Option<Vec<u8>> {
+    match algorithm {
+        "sha224" => Some(Sha224::digest(data).to_vec()),
+        "sha256" => Some(Sha256::digest(data).to_vec()),
+        "sha384" => Some(Sha384::digest(data).to_vec()),
+        "sha512" => Some(Sha512::digest(data).to_vec()),
+        _ => None,
+    }
+}
+
+// Signed text has its lines ending with CRLF.
+// Refer to RFC 8551 3.1.1
+fn canonicalize(data: &[u8]) -> Vec<u8> {
+    let mut canonical = Vec::with_capacity(data.len());
+    for (i, &byte) in data.iter().enumerate() {
+        if byte == b'\n' && (i == 0 || data[i - 1] != b'\r') {
+            canonical.push(b'\r');
+        }
+        canonical.push(byte);
+    }
+    canonical
+}
+
+// Certificate is an X.509 certificate carried along
+// with a signed message.
+// Refer to RFC 5280 4.1
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct Certificate {
+    pub subject: String,                   // e.g. "C=US, O=Contoso, CN=Jane Doe"
+    pub issuer: String,                    // distinguished name of the issuer
+    pub serial_number: String,             // in hex
+    pub not_before: Option<DateTime<Utc>>, // start of the validity period
+    pub not_after: Option<DateTime<Utc>>,  // end of the validity period
+    pub email_addresses: Vec<String>,      // subject alternative names, or else of the subject
+}
+
+impl Certificate {
+    pub fn from_der(tlv: Tlv) -> Result<Self, DataTypeError> {
+        let mut tbs = tlv.children().read_tag(TAG_SEQUENCE)?.children();
+        if tbs.peek_tag() == Some(0xA0) {
+            // Version
+            tbs.read()?;
+        }
+        let serial_number = serial_number(tbs.read_tag(TAG_INTEGER)?);
+        // Signature algorithm
+        tbs.read_tag(TAG_SEQUENCE)?;
+        let issuer = format_name(&name_attributes(tbs.read_tag(TAG_SEQUENCE)?)?);
+        let mut validity = tbs.read_tag(TAG_SEQUENCE)?.children();
+        let not_before = validity.read()?.time();
+        let not_after = validity.read()?.time();
+        let subject = name_attributes(tbs.read_tag(TAG_SEQUENCE)?)?;
+        // Subject public key
+        tbs.read_tag(TAG_SEQUENCE)?;
+
+        let mut email_addresses = vec![];
+        while !tbs.is_empty() {
+            let field = tbs.read()?;
+            if field.tag != 0xA3 {
+                continue;
+            }
+            let mut extensions = field.children().read_tag(TAG_SEQUENCE)?.children();
+            while !extensions.is_empty() {
+                let mut extension = extensions.read_tag(TAG_SEQUENCE)?.children();
+                if extension.read_tag(TAG_OID)?.oid()? != OID_SUBJECT_ALT_NAME {
+                    continue;
+                }
+                if extension.peek_tag() == Some(TAG_BOOLEAN) {
+                    extension.read()?;
+                }
+                let value = extension.read_tag(TAG_OCTET_STRING)?.content;
+                let mut names = DerReader::new(value).read_tag(TAG_SEQUENCE)?.children();
+                while !names.is_empty() {
+                    let name = names.read()?;
+                    if name.tag == RFC822_NAME {
+                        email_addresses.push(name.string());
+                    }
+                }
+            }
+        }
+        if email_addresses.is_empty() {
+            email_addresses = subject
+                .iter()
+                .filter(|(key, _)| key == "emailAddress")
+                .map(|(_, value)| value.to_string())
+                .collect();
+        }
+        Ok(Self {
+            subject: format_name(&subject),
+            issuer,
+            serial_number,
+            not_before,
+            not_after,
+            email_addresses,
+        })
+    }
+}
+
+// SmimeSigner is a signer of an S/MIME message. Only the
+// digest of the content is checked against the one that was
+// signed: the signature itself is not verified.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct SmimeSigner {
+    pub issuer: String,                      // issuer of the signer's certificate
+    pub serial_number: String,               // serial number of the signer's certificate
+    pub digest_algorithm: String,            // e.g. "sha256"
+    pub signing_time: Option<DateTime<Utc>>, // signingTime attribute
+    pub digest_matches: Option<bool>,        // None when the digest could not be computed
+}
+
+// SmimeKind tells how the content was signed.
+// Refer to RFC 8551 3.5
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub enum SmimeKind {
+    // "multipart/signed", the content being readable
+    // without S/MIME support.
+    ClearSigned,
+    // "application/pkcs7-mime", the content being
+    // wrapped in the signature.
+    OpaqueSigned,
+}
+
+// Smime is an S/MIME signed message, the signed MIME entity
+// being kept in the "smime.p7m" attachment of the .msg.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct Smime {
+    pub kind: SmimeKind,                 // See SmimeKind enum
+    pub content: MimePart,               // signed MIME entity
+    pub signers: Vec<SmimeSigner>,       // See SmimeSigner struct
+    pub certificates: Vec<Certificate>,  // certificates carried along, the signers' included
+}
+
+// Content, certificates and signers of a CMS SignedData, each
+// signer along with the digest it signed.
+type SignedData = (Option<Vec<u8>>, Vec<Certificate>, Vec<(SmimeSigner, Option<Vec<u8>>)>);
+
+// Refer to RFC 5652 5.1
+fn parse_signed_data(buff: &[u8]) -> Result<SignedData, DataTypeError> {
+    let (content_type, content) = content_info(buff)?;
+    if content_type != OID_SIGNED_DATA {
+        return Err(DataTypeError::MalformedBlob(format!(
+            "content type {} is not signed data",
+            content_type
+        )));
+    }
+    let mut signed_data = content.children();
+    // Version and digest algorithms
+    signed_data.read_tag(TAG_INTEGER)?;
+    signed_data.read_tag(TAG_SET)?;
+    let mut encapsulated = signed_data.read_tag(TAG_SEQUENCE)?.children();
+    encapsulated.read_tag(TAG_OID)?;
+    let content = if encapsulated.is_empty() {
+        None
+    } else {
+        Some(encapsulated.read_tag(0xA0)?.children().read()?.octets()?)
+    };
+
+    let mut certificates = vec![];
+    if signed_data.peek_tag() == Some(0xA0) {
+        let mut choices = signed_data.read()?.children();
+        while !choices.is_empty() {
+            let choice = choices.read()?;
+            // Attribute certificates and other formats are left out.
+            if choice.tag == TAG_SEQUENCE {
+                certificates.push(Certificate::from_der(choice)?);
+            }
+        }
+    }
+    if signed_data.peek_tag() == Some(0xA1) {
+        // Revocation lists
+        signed_data.read()?;
+    }
+
+    let mut signers = vec![];
+    let mut signer_infos = signed_data.read_tag(TAG_SET)?.children();
+    while !signer_infos.is_empty() {
+        let mut info = signer_infos.read_tag(TAG_SEQUENCE)?.children();
+        info.read_tag(TAG_INTEGER)?;
+        // The signer is known by a subject key identifier
+        // rather than by the issuer and serial number of
+        // its certificate when the identifier is tagged.
+        let signer_id = info.read()?;
+        let (issuer, serial) = if signer_id.tag == TAG_SEQUENCE {
+            let mut signer_id = signer_id.children();
+            let issuer = format_name(&name_attributes(signer_id.read_tag(TAG_SEQUENCE)?)?);
+            (issuer, serial_number(signer_id.read_tag(TAG_INTEGER)?))
+        } else {
+            (String::new(), String::new())
+        };
+        let digest_oid = info.read_tag(TAG_SEQUENCE)?.children().read_tag(TAG_OID)?.oid()?;
+        let digest_algorithm = DIGEST_ALGORITHMS
+            .iter()
+            .find(|(oid, _)| *oid == digest_oid)
+            .map_or(digest_oid.clone(), |(_, name)| name.to_string());
+
+        let (mut signing_time, mut message_digest) = (None, None);
+        if info.peek_tag() == Some(0xA0) {
+            let mut attributes = info.read()?.children();
+            while !attributes.is_empty() {
+                let mut attribute = attributes.read_tag(TAG_SEQUENCE)?.children();
+                let oid = attribute.read_tag(TAG_OID)?.oid()?;
+                let value = attribute.read_tag(TAG_SET)?.children().read()?;
+                match oid.as_str() {
+                    OID_MESSAGE_DIGEST => message_digest = Some(value.octets()?),
+                    OID_SIGNING_TIME => signing_time = value.time(),
+                    _ => {}
+                }
+            }
+        }
+        signers.push((
+            SmimeSigner {
+                issuer,
+                serial_number: serial,
+                digest_algorithm,
+                signing_time,
+                digest_matches: None,
+            },
+            message_digest,
+        ));
+    }
+    Ok((content, certificates, signers))
+}
+
+// Checks the digest each signer signed against the content.
+// Without signed attributes the signature is over the content
+// itself, which can't be told apart without checking it.
+fn check_digests(signers: Vec<(SmimeSigner, Option<Vec<u8>>)>, content: &[u8]) -> Vec<SmimeSigner> {
+    signers
+        .into_iter()
+        .map(|(mut signer, message_digest)| {
+            signer.digest_matches = message_digest.and_then(|message_digest| {
+                digest(&signer.digest_algorithm, content).map(|digest| digest == message_digest)
+            });
+            signer
+        })
+        .collect()
+}
+
+impl Smime {
+    // Reads a "multipart/signed" entity. Its header fields
+    // may be left out, the boundary being found in the body.
+    pub fn from_multipart_signed(data: &[u8]) -> Result<Self, DataTypeError> {
+        let mut entity = parse_entity(data)?;
+        let mut with_header = vec![];
+        if !entity.is_multipart() && data.starts_with(b"--") {
+            let line = data.split(|&b| b == b'\n').next().unwrap_or_default();
+            let boundary = String::from_utf8_lossy(&line[2..]).trim().to_string();
+            with_header =
+                format!("Content-Type: multipart/signed; boundary=\"{}\"\r\n\r\n", boundary).into_bytes();
+            with_header.extend(data);
+            entity = parse_entity(&with_header)?;
+        }
+        // Data the entity was parsed from.
+        let data = if with_header.is_empty() { data } else { &with_header };
+        let signature = entity
+            .parts
+            .iter()
+            .find(|part| part.content_type().ends_with("pkcs7-signature"))
+            .ok_or_else(|| DataTypeError::MalformedBlob("no S/MIME signature part".to_string()))?;
+        let (_, certificates, signers) = parse_signed_data(&signature.body)?;
+        let content = entity.parts.swap_remove(0);
+        Ok(Self {
+            kind: SmimeKind::ClearSigned,
+            signers: check_digests(signers, &canonicalize(&data[content.raw.clone()])),
+            certificates,
+            content,
+        })
+    }
+
+    // Reads an "application/pkcs7-mime" signed entity,
+    // either DER or a MIME entity holding it.
+    pub fn from_pkcs7(data: &[u8]) -> Result<Self, DataTypeError> {
+        let (content, certificates, signers) = parse_signed_data(&pkcs7_der(data))?;
+        let content = content
+            .ok_or_else(|| DataTypeError::MalformedBlob("no encapsulated content".to_string()))?;
+        Ok(Self {
+            kind: SmimeKind::OpaqueSigned,
+            signers: check_digests(signers, &content),
+            certificates,
+            content: parse_entity(&content)?,
+        })
+    }
+
+    // Returns None unless the message is an S/MIME one
+    // that was signed rather than encrypted.
+    pub fn create_from_props(props: &Properties, attachments: &Attachments) -> Option<Self> {
+        let message_class = props
+            .get("MessageClass")
+            .and_then(DataType::as_str)
+            .unwrap_or_default()
+            .to_ascii_lowercase();
+        if !message_class.starts_with("ipm.note.smime") {
+            return None;
+        }
+        attachments.iter().find_map(|attachment| {
+            let data = attachment.get("AttachDataObject").and_then(DataType::as_bytes)?;
+            let mime_tag = attachment
+                .get("AttachMimeTag")
+                .and_then(DataType::as_str)
+                .unwrap_or_default()
+                .to_ascii_lowercase();
+            if mime_tag == "multipart/signed" || message_class.ends_with(".multipartsigned") {
+                Self::from_multipart_signed(data).ok()
+            } else {
+                Self::from_pkcs7(data).ok()
+            }
+        })
+    }
+
+    // Whether the content is the one every signer signed.
+    pub fn is_verified(&self) -> bool {
+        !self.signers.is_empty()
+            && self
+                .signers
+                .iter()
+                .all(|signer| signer.digest_matches == Some(true))
+    }
+
+    // Certificate of the given signer, when it was carried along.
+    pub fn signer_certificate(&self, signer: &SmimeSigner) -> Option<&Certificate> {
+        self.certificates.iter().find(|certificate| {
+            certificate.issuer == signer.issuer && certificate.serial_number == signer.serial_number
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{TimeZone as _, Utc};
+
+    use super::{
+        super::{decode::DataType, outlook::Outlook, storage::Properties},
+        canonicalize, Smime, SmimeKind,
+    };
+
+    const SUBJECT: &str = "C=US, O=Contoso, CN=Jane Doe, emailAddress=jane@contoso.com";
+
+    fn check_signature(smime: &Smime) {
+        assert_eq!(smime.certificates.len(), 1);
+        let certificate = &smime.certificates[0];
+        assert_eq!(certificate.subject, SUBJECT.to_string());
+        assert_eq!(certificate.issuer, SUBJECT.to_string());
+        assert_eq!(
+            certificate.serial_number,
+            "04866DE3F703615A2217BF3516B3463A4685C405".to_string()
+        );
+        assert_eq!(
+            certificate.not_before,
+            Some(Utc.with_ymd_and_hms(2026, 10, 15, 5, 6, 34).unwrap())
+        );
+        assert_eq!(certificate.email_addresses, vec!["jane@contoso.com".to_string()]);
+
+        assert_eq!(smime.signers.len(), 1);
+        let signer = &smime.signers[0];
+        assert_eq!(signer.digest_algorithm, "sha256".to_string());
+        assert!(signer.signing_time.is_some());
+        assert_eq!(smime.signer_certificate(signer), Some(certificate));
+        assert!(smime.is_verified());
+        assert_eq!(smime.content.text(), "Signed hello\r\n".to_string());
+    }
+
+    #[test]
+    fn test_multipart_signed() {
+        let data = std::fs::read("data/smime_signed.eml").unwrap();
+        let smime = Smime::from_multipart_signed(&data).unwrap();
+        assert_eq!(smime.kind, SmimeKind::ClearSigned);
+        check_signature(&smime);
+
+        // Without the header fields of the entity.
+        let start = data.windows(4).position(|window| window == b"\r\n\r\n").unwrap() + 4;
+        let start = start + data[start..].iter().position(|&b| b == b'-').unwrap();
+        check_signature(&Smime::from_multipart_signed(&data[start..]).unwrap());
+
+        // A tampered content no longer matches its digest.
+        let tampered = String::from_utf8(data)
+            .unwrap()
+            .replace("Signed hello", "Signed hellO");
+        let smime = Smime::from_multipart_signed(tampered.as_bytes()).unwrap();
+        assert_eq!(smime.signers[0].digest_matches, Some(false));
+        assert!(!smime.is_verified());
+
+        assert!(Smime::from_multipart_signed(b"Content-Type: text/plain\r\n\r\nhello").is_err());
+    }
+
+    #[test]
+    fn test_opaque_signed() {
+        // Indefinite lengths, as written when streaming.
+        let data = std::fs::read("data/smime_opaque.p7m").unwrap();
+        let smime = Smime::from_pkcs7(&data).unwrap();
+        assert_eq!(smime.kind, SmimeKind::OpaqueSigned);
+        check_signature(&smime);
+        assert!(Smime::from_pkcs7(&data[..data.len() / 2]).is_err());
+    }
+
+    #[test]
+    fn test_create_from_props() {
+        let mut props = Properties::new();
+        let mut attachment = Properties::new();
+        attachment.insert(
+            "AttachMimeTag".to_string(),
+            DataType::PtypString("multipart/signed".to_string()),
+        );
+        attachment.insert(
+            "AttachDataObject".to_string(),
+            DataType::PtypBinary(std::fs::read("data/smime_signed.eml").unwrap()),
+        );
+        let attachments = vec![attachment];
+        assert_eq!(Smime::create_from_props(&props, &attachments), None);
+
+        props.insert(
+            "MessageClass".to_string(),
+            DataType::PtypString("IPM.Note.SMIME.MultipartSigned".to_string()),
+        );
+        let smime = Smime::create_from_props(&props, &attachments).unwrap();
+        assert!(smime.is_verified());
+
+        let outlook = Outlook::from_path("data/test_email.msg").unwrap();
+        assert_eq!(outlook.smime, None);
+    }
+
+    #[test]
+    fn test_canonicalize() {
+        assert_eq!(canonicalize(b"a\nb\r\nc\n"), b"a\r\nb\r\nc\r\n".to_vec());
+    }
+}
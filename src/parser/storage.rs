@@ -1,18 +1,46 @@
-use std::{
-    collections::HashMap,
-    u32::MAX,
-};
+use std::collections::HashMap;
 
 use hex::decode;
 
-use crate::ole::{Entry, EntryType, Reader};
+use crate::ole::{Entry, EntrySlice, EntryType, Error as OleError, Reader};
 
 use super::{
     constants::PropIdNameMap,
-    decode::DataType,
-    stream::Stream
+    decode::{DataType, DEFAULT_CODEPAGE},
+    error::Error,
+    limits::decoded_len,
+    mode::ParseMode,
+    named::NamedPropMap,
+    options::ParserOptions,
+    provenance::{PropertySource, Provenance, SourceKind, Sources},
+    stream::Stream,
+    warning::{SkipReason, Warning},
 };
 
+// Storage of an attachment holding an embedded message.
+// Refer to MS-OXMSG 2.2.2.1
+pub(super) const EMBEDDED_MESSAGE_STORAGE: &str = "__substg1.0_3701000D";
+
+// Streams of "Body", "RtfCompressed" and "Html",
+// whatever their type.
+const BODY_STREAMS: [&str; 3] = ["__substg1.0_1000", "__substg1.0_1009", "__substg1.0_1013"];
+
+// Stream of "AttachDataObject".
+const ATTACHMENT_DATA_STREAM: &str = "__substg1.0_3701";
+
+// Streams given to each thread of the rayon pool at a
+// time, see Storages::create_streams_parallel.
+#[cfg(feature = "rayon")]
+const STREAMS_PER_THREAD: usize = 4;
+
+// What a stream holds once read, before it is decoded.
+enum StreamData<'s> {
+    Done(Vec<Result<Stream, Warning>>), // Nothing to decode, e.g. a skipped stream
+    Single(EntrySlice<'s>),
+    Multiple(Vec<EntrySlice<'s>>), // Values of a multiple valued property, in order
+    Truncated(EntrySlice<'s>, Warning), // Read up to the end of a truncated file
+}
+
 // StorageType refers to major components in Message object.
 // Refer to MS-OXPROPS 1.3.3
 #[derive(Debug, Clone, PartialEq)]
@@ -35,8 +63,8 @@ impl StorageType {
         let mut base = 1u32;
         let mut sum = 0u32;
         for &num in decoded.iter().rev() {
-            sum = sum + num as u32 * base;
-            if base >= MAX / 256 {
+            sum += num as u32 * base;
+            if base >= u32::MAX / 256 {
                 break;
             }
             base *= 256;
@@ -44,16 +72,25 @@ impl StorageType {
         Some(sum)
     }
 
+    // Name of the storage, None for the root.
+    pub fn name(&self) -> Option<String> {
+        match self {
+            StorageType::Recipient(id) => Some(format!("__recip_version1.0_#{:08X}", id)),
+            StorageType::Attachment(id) => Some(format!("__attach_version1.0_#{:08X}", id)),
+            StorageType::RootEntry => None,
+        }
+    }
+
     pub fn create(name: &str) -> Option<Self> {
         if name.starts_with("__recip_version1.0_") {
             // Extract the digits after '#' in __recip_version1.0_#00000000
             // Remaining digits is the index of Recipient.
-            let id = name.split("#").collect::<Vec<&str>>()[1];
+            let (_, id) = name.split_once('#')?;
             let id_as_num = StorageType::convert_id_to_u32(id)?;
             return Some(StorageType::Recipient(id_as_num));
         }
         if name.starts_with("__attach_version1.0_") {
-            let id = name.split("#").collect::<Vec<&str>>()[1];
+            let (_, id) = name.split_once('#')?;
             let id_as_num = StorageType::convert_id_to_u32(id)?;
             return Some(StorageType::Attachment(id_as_num));
         }
@@ -62,29 +99,40 @@ impl StorageType {
 }
 
 // EntryStorageMap represents HashMap of ole::Entry id and its StorageType
+// for the storages of one message, being the whole file or
+// an embedded message. Storages of the messages embedded in
+// it are only recorded by the attachment they belong to.
 #[derive(Debug)]
 struct EntryStorageMap {
     map: HashMap<u32, StorageType>,
+    embedded: HashMap<u32, u32>, // Attachment index to embedded message storage
 }
 
 impl EntryStorageMap {
-    pub fn new(parser: &Reader) -> Self {
+    pub fn new(parser: &Reader, root: u32) -> Self {
         let mut storage_map: HashMap<u32, StorageType> = HashMap::new();
+        storage_map.insert(root, StorageType::RootEntry);
         for entry in parser.iterate() {
-            match entry._type() {
-                EntryType::RootStorage => {
-                    storage_map.insert(entry.id(), StorageType::RootEntry);
-                }
-                EntryType::UserStorage => {
+            if let EntryType::UserStorage = entry._type() {
+                if entry.parent_node() == Some(root) {
                     StorageType::create(entry.name())
                         .and_then(|storage| storage_map.insert(entry.id(), storage));
                 }
-                _ => {
-                    continue;
-                }
             }
         }
-        Self { map: storage_map }
+        let embedded = parser
+            .iterate()
+            .filter(|entry| entry.name() == EMBEDDED_MESSAGE_STORAGE)
+            .filter(|entry| matches!(entry._type(), EntryType::UserStorage))
+            .filter_map(|entry| match storage_map.get(&entry.parent_node()?) {
+                Some(StorageType::Attachment(id)) => Some((*id, entry.id())),
+                _ => None,
+            })
+            .collect();
+        Self {
+            map: storage_map,
+            embedded,
+        }
     }
 
     pub fn get_storage_type(&self, parent_id: Option<u32>) -> Option<&StorageType> {
@@ -95,12 +143,49 @@ impl EntryStorageMap {
 // Properties is a Map is a collection of Message object elements.
 pub type Properties = HashMap<String, DataType>;
 
+// Properties holding the given entries, for the tests
+// building fixtures by hand.
+#[cfg(test)]
+pub(crate) fn props(entries: &[(&str, DataType)]) -> Properties {
+    entries
+        .iter()
+        .map(|(key, value)| (key.to_string(), value.clone()))
+        .collect()
+}
+
+#[cfg(test)]
+pub(crate) fn string(value: &str) -> DataType {
+    DataType::PtypString(value.to_string())
+}
+
 // Recipients represent array of Recipient objects in Message.
 pub type Recipients = Vec<Properties>;
 
 // Attachments represent array of Attachment object in Message
 pub type Attachments = Vec<Properties>;
 
+// Entry id of the root storage of a file.
+pub const ROOT_ID: u32 = 0;
+
+// Path of an entry from the root of the file, e.g.
+// "__attach_version1.0_#00000000/__substg1.0_37010102",
+// as errors locate it.
+pub fn entry_path(parser: &Reader, id: u32) -> String {
+    // A corrupt tree may loop, bounding the walk.
+    let max = parser.iterate().count();
+    let mut names = vec![];
+    let mut current = Some(id);
+    while let Some(entry) = current
+        .filter(|id| *id != ROOT_ID && names.len() < max)
+        .and_then(|id| parser.iterate().find(|entry| entry.id() == id))
+    {
+        names.push(entry.name().to_string());
+        current = entry.parent_node();
+    }
+    names.reverse();
+    names.join("/")
+}
+
 // Storages is a collection of Storage
 // object containing their decoded stream
 // values for respective properties.
@@ -108,6 +193,18 @@ pub type Attachments = Vec<Properties>;
 pub struct Storages {
     storage_map: EntryStorageMap,
     prop_map: PropIdNameMap,
+    options: ParserOptions,
+    root_id: u32,
+    // Storage of the embedded message of each attachment
+    embedded: Vec<Option<u32>>,
+    // Bytes decoded, see Limits.max_decoded_bytes
+    pub decoded: usize,
+    // What decoding dropped, see ParseReport
+    pub warnings: Vec<Warning>,
+    // Source of each property, see ParserOptions.provenance
+    pub provenance: Provenance,
+    recipient_sources: HashMap<u32, Sources>,
+    attachment_sources: HashMap<u32, Sources>,
     pub attachments: Attachments,
     pub recipients: Recipients,
     // Mail properties
@@ -115,87 +212,373 @@ pub struct Storages {
 }
 
 impl Storages {
-    fn to_arr(map: HashMap<u32, Properties>) -> Vec<Properties> {
-        let mut tuples: Vec<(u32, Properties)> = map
+    fn to_arr<T>(map: HashMap<u32, T>) -> Vec<T> {
+        let mut tuples: Vec<(u32, T)> = map
             .into_iter()
-            .map(|(k, v)| (k, v))
-            .collect::<Vec<(u32, Properties)>>();
-        tuples.sort_by(|a, b| a.0.cmp(&b.0));
-        tuples.into_iter().map(|x| x.1).collect::<Vec<Properties>>()
+            .collect::<Vec<(u32, T)>>();
+        tuples.sort_by_key(|a| a.0);
+        tuples.into_iter().map(|x| x.1).collect::<Vec<T>>()
+    }
+
+    // Whether the options leave the stream out.
+    fn is_skipped(&self, entry: &Entry, parent: &StorageType) -> bool {
+        if self.options.max_stream_size.is_some_and(|max| entry.len() > max) {
+            return true;
+        }
+        let name = entry.name();
+        match parent {
+            StorageType::RootEntry => {
+                !self.options.bodies && BODY_STREAMS.iter().any(|prefix| name.starts_with(prefix))
+            }
+            StorageType::Attachment(_) => {
+                !self.options.attachments && name.starts_with(ATTACHMENT_DATA_STREAM)
+            }
+            StorageType::Recipient(_) => false,
+        }
     }
 
-    fn create_stream(&self, parser: &Reader, entry: &Entry) -> Option<Stream> {
-        let parent = self.storage_map.get_storage_type(entry.parent_node())?;
-        let mut slice = parser.get_entry_slice(entry).ok()?;
-        Stream::create(entry.name(), &mut slice, &self.prop_map, parent)
+    // Reads what a stream holds, to be decoded by
+    // create_streams.
+    fn read_stream<'s>(&self, parser: &'s Reader, entry: &Entry) -> Result<StreamData<'s>, Error> {
+        let parent = match self.storage_map.get_storage_type(entry.parent_node()) {
+            Some(parent) => parent,
+            None => return Ok(StreamData::Done(vec![])),
+        };
+        let name = entry.name();
+        let skipped = |reason| {
+            Ok(StreamData::Done(vec![Err(Warning::SkippedStream {
+                stream: name.to_string(),
+                reason,
+            })]))
+        };
+        if Stream::is_value_stream(name) {
+            return Ok(StreamData::Done(vec![]));
+        }
+        if self.is_skipped(entry, parent) {
+            return skipped(SkipReason::Options);
+        }
+        let failed = |error: String| Warning::ReadFailed {
+            stream: name.to_string(),
+            error,
+        };
+        // Streams that cannot be read are left out, unless
+        // they go over a limit. Empty ones hold no value. In
+        // salvage mode, they are read as far as they can be.
+        let salvage = self.options.mode == ParseMode::Salvage;
+        let read = match salvage {
+            true => parser.get_readable_entry_slice(entry),
+            false => parser.get_entry_slice(entry),
+        };
+        let slice = match read.map_err(Error::from) {
+            Ok(slice) => slice,
+            Err(err @ Error::LimitExceeded { .. }) => return Err(err.in_entry(&entry_path(parser, entry.id()))),
+            Err(Error::OleError {
+                source: OleError::EmptyEntry,
+            }) => return Ok(StreamData::Done(vec![])),
+            Err(err) => return Ok(StreamData::Done(vec![Err(failed(err.to_string()))])),
+        };
+        if slice.len() < entry.len() {
+            let warning = failed(format!("{} of {} bytes read", slice.len(), entry.len()));
+            return Ok(match slice.is_empty() {
+                true => StreamData::Done(vec![Err(warning)]),
+                false => StreamData::Truncated(slice, warning),
+            });
+        }
+        if Stream::is_multiple_valued_stream(name) {
+            let mut values = parser
+                .iterate()
+                .filter(|sibling| sibling.parent_node() == entry.parent_node())
+                .filter_map(|sibling| Some((Stream::value_index(sibling.name(), name)?, sibling)))
+                .collect::<Vec<(u32, &Entry)>>();
+            values.sort_by_key(|value| value.0);
+            return match values
+                .into_iter()
+                .map(|(_, sibling)| parser.get_entry_slice(sibling))
+                .collect::<Result<Vec<_>, _>>()
+                .map_err(Error::from)
+            {
+                Ok(slices) => Ok(StreamData::Multiple(slices)),
+                Err(err @ Error::LimitExceeded { .. }) => Err(err.in_entry(&entry_path(parser, entry.id()))),
+                Err(err) => Ok(StreamData::Done(vec![Err(failed(err.to_string()))])),
+            };
+        }
+        Ok(StreamData::Single(slice))
     }
 
-    pub fn process_streams(&mut self, parser: &Reader) {
-        let mut recipients_map: HashMap<u32, Properties> = HashMap::new();
-        let mut attachments_map: HashMap<u32, Properties> = HashMap::new();
-        for entry in parser.iterate() {
-            if let EntryType::UserStream = entry._type() {
-                // Decode stream from slice.
-                // Skip if failed.
-                let stream_res = self.create_stream(&parser, &entry);
-                if stream_res.is_none() {
-                    continue;
-                }
-                let stream = stream_res.unwrap();
+    // Decodes a stream into the properties it holds, or
+    // the warnings of those it could not.
+    #[cfg_attr(feature = "tracing", tracing::instrument(level = "trace", skip_all, fields(stream = entry.name())))]
+    fn create_streams(&self, entry: &Entry, data: StreamData, codepage: u32) -> Vec<Result<Stream, Warning>> {
+        let parent = match self.storage_map.get_storage_type(entry.parent_node()) {
+            Some(parent) => parent,
+            None => return vec![],
+        };
+        let name = entry.name();
+        match data {
+            StreamData::Done(streams) => streams,
+            StreamData::Truncated(slice, warning) => {
+                let mut streams = self.create_streams(entry, StreamData::Single(slice), codepage);
+                streams.push(Err(warning));
+                streams
+            }
+            StreamData::Multiple(mut slices) => {
+                vec![Stream::create_multiple(name, &mut slices, &self.prop_map, parent)]
+            }
+            StreamData::Single(mut slice) if Stream::is_property_stream(name) => {
+                let embedded = self.root_id != ROOT_ID;
+                Stream::create_from_property_stream(&mut slice, &self.prop_map, parent, embedded)
+            }
+            StreamData::Single(mut slice) if Stream::is_string8_stream(name) => {
+                vec![Stream::create_string8(name, &mut slice, &self.prop_map, parent, codepage)]
+            }
+            StreamData::Single(mut slice) => vec![Stream::create(name, &mut slice, &self.prop_map, parent)],
+        }
+    }
 
-                // Populate maps accordingly
-                match stream.parent {
-                    StorageType::RootEntry => {
-                        self.root.insert(stream.key, stream.value);
-                    }
-                    StorageType::Recipient(id) => {
-                        let recipient_map = recipients_map.entry(id).or_insert(HashMap::new());
-                        (*recipient_map).insert(stream.key, stream.value);
+    // Reads and decodes the streams on the threads of the
+    // rayon pool, a few at a time so that the limits are
+    // checked as the properties are added. Errors are the
+    // same, in the same order, as decoding one at a time.
+    #[cfg(feature = "rayon")]
+    fn create_streams_parallel(
+        &mut self,
+        parser: &Reader,
+        entries: &[&Entry],
+        codepage: u32,
+        recipients_map: &mut HashMap<u32, Properties>,
+        attachments_map: &mut HashMap<u32, Properties>,
+    ) -> Result<(), Error> {
+        use rayon::prelude::*;
+
+        let chunk_size = rayon::current_num_threads() * STREAMS_PER_THREAD;
+        for chunk in entries.chunks(chunk_size) {
+            let data: Vec<Result<StreamData, Error>> =
+                chunk.iter().map(|entry| self.read_stream(parser, entry)).collect();
+            let storages = &*self;
+            // Spans of the threads of the pool belong to the
+            // one of process_streams, and go to its subscriber.
+            #[cfg(feature = "tracing")]
+            let (dispatch, span) = (tracing::dispatcher::get_default(Clone::clone), tracing::Span::current());
+            let streams: Vec<Result<Vec<Result<Stream, Warning>>, Error>> = chunk
+                .par_iter()
+                .zip(data)
+                .map(|(entry, data)| {
+                    let create = || Ok(storages.create_streams(entry, data?, codepage));
+                    #[cfg(feature = "tracing")]
+                    let create = || tracing::dispatcher::with_default(&dispatch, || span.in_scope(create));
+                    create()
+                })
+                .collect();
+            for (entry, streams) in chunk.iter().zip(streams) {
+                self.add_streams(parser, streams?, entry, recipients_map, attachments_map)?;
+            }
+        }
+        Ok(())
+    }
+
+    // Adds the decoded properties of a stream, or its
+    // warnings located by the storage it belongs to.
+    fn add_streams(
+        &mut self,
+        parser: &Reader,
+        streams: Vec<Result<Stream, Warning>>,
+        entry: &Entry,
+        recipients_map: &mut HashMap<u32, Properties>,
+        attachments_map: &mut HashMap<u32, Properties>,
+    ) -> Result<(), Error> {
+        for stream in streams {
+            match stream {
+                Ok(stream) => {
+                    if self.options.provenance {
+                        self.add_source(parser, &stream, entry);
                     }
-                    StorageType::Attachment(id) => {
-                        let attachment_map = attachments_map.entry(id).or_insert(HashMap::new());
-                        (*attachment_map).insert(stream.key, stream.value);
+                    self.insert_stream(stream, recipients_map, attachments_map)
+                        .map_err(|err| err.in_entry(&entry_path(parser, entry.id())))?
+                }
+                Err(warning) => {
+                    #[cfg(feature = "tracing")]
+                    match &warning {
+                        Warning::SkippedStream { .. } | Warning::ReadFailed { .. } => {
+                            tracing::info!(%warning, "stream skipped")
+                        }
+                        _ => tracing::debug!(%warning, "property dropped"),
                     }
+                    let parent = self.storage_map.get_storage_type(entry.parent_node());
+                    let path = match parent.and_then(StorageType::name) {
+                        Some(storage) => format!("{}/{}", storage, warning.stream()),
+                        None => warning.stream().to_string(),
+                    };
+                    self.warnings.push(warning.with_stream(path));
                 }
             }
         }
+        Ok(())
+    }
+
+    // Records the stream a property was decoded from.
+    fn add_source(&mut self, parser: &Reader, stream: &Stream, entry: &Entry) {
+        let source = PropertySource {
+            stream: entry_path(parser, entry.id()),
+            tag: stream.tag,
+            kind: match Stream::is_property_stream(entry.name()) {
+                true => SourceKind::PropertyStream,
+                false => SourceKind::Substream,
+            },
+        };
+        let sources = match stream.parent {
+            StorageType::RootEntry => &mut self.provenance.root,
+            StorageType::Recipient(id) => self.recipient_sources.entry(id).or_default(),
+            StorageType::Attachment(id) => self.attachment_sources.entry(id).or_default(),
+        };
+        sources.insert(stream.key.clone(), source);
+    }
+
+    // Code page of the 8-bit strings, which is only known
+    // once the property stream of the root is read.
+    fn codepage(&self) -> u32 {
+        self.options
+            .codepage
+            .or_else(|| {
+                ["MessageCodepage", "InternetCodepage"]
+                    .iter()
+                    .filter_map(|key| self.root.get(*key).and_then(DataType::as_i64))
+                    .find(|codepage| *codepage > 0)
+                    .map(|codepage| codepage as u32)
+            })
+            .unwrap_or(DEFAULT_CODEPAGE)
+    }
+
+    fn insert_stream(
+        &mut self,
+        stream: Stream,
+        recipients_map: &mut HashMap<u32, Properties>,
+        attachments_map: &mut HashMap<u32, Properties>,
+    ) -> Result<(), Error> {
+        self.decoded += decoded_len(&stream.value);
+        self.options.limits.check_decoded(self.decoded)?;
+        // Populate maps accordingly
+        match stream.parent {
+            StorageType::RootEntry => {
+                self.root.insert(stream.key, stream.value);
+            }
+            StorageType::Recipient(id) => {
+                let recipient_map = recipients_map.entry(id).or_default();
+                (*recipient_map).insert(stream.key, stream.value);
+            }
+            StorageType::Attachment(id) => {
+                let attachment_map = attachments_map.entry(id).or_default();
+                (*attachment_map).insert(stream.key, stream.value);
+            }
+        }
+        Ok(())
+    }
+
+    #[cfg_attr(feature = "tracing", tracing::instrument(level = "debug", skip_all, fields(root_id = self.root_id)))]
+    pub fn process_streams(&mut self, parser: &Reader) -> Result<(), Error> {
+        let mut recipients_map: HashMap<u32, Properties> = HashMap::new();
+        let mut attachments_map: HashMap<u32, Properties> = HashMap::new();
+        // 8-bit strings are decoded last, in the code page
+        // the other streams tell.
+        let (string8, others): (Vec<&Entry>, Vec<&Entry>) = parser
+            .iterate()
+            .filter(|entry| matches!(entry._type(), EntryType::UserStream))
+            .partition(|entry| Stream::is_string8_stream(entry.name()));
+        #[cfg(feature = "rayon")]
+        self.create_streams_parallel(parser, &others, DEFAULT_CODEPAGE, &mut recipients_map, &mut attachments_map)?;
+        #[cfg(not(feature = "rayon"))]
+        for entry in others {
+            // Decode stream from slice.
+            // Skip if failed.
+            let data = self.read_stream(parser, entry)?;
+            let streams = self.create_streams(entry, data, DEFAULT_CODEPAGE);
+            self.add_streams(parser, streams, entry, &mut recipients_map, &mut attachments_map)?;
+        }
+        let codepage = self.codepage();
+        for entry in string8 {
+            let data = self.read_stream(parser, entry)?;
+            let streams = self.create_streams(entry, data, codepage);
+            self.add_streams(parser, streams, entry, &mut recipients_map, &mut attachments_map)?;
+        }
         // Update storages
+        let mut ids: Vec<&u32> = attachments_map.keys().collect();
+        ids.sort();
+        self.embedded = ids
+            .into_iter()
+            .map(|id| self.storage_map.embedded.get(id).copied())
+            .collect();
         self.recipients = Self::to_arr(recipients_map);
         self.attachments = Self::to_arr(attachments_map);
+        self.provenance.recipients = Self::to_arr(std::mem::take(&mut self.recipient_sources));
+        self.provenance.attachments = Self::to_arr(std::mem::take(&mut self.attachment_sources));
+        Ok(())
     }
 
-    pub fn new(parser: &Reader) -> Self {
+    // Storages of the message whose root storage has the
+    // given entry id, the root of the file or the storage
+    // of an embedded message.
+    pub fn create(parser: &Reader, root_id: u32, options: &ParserOptions) -> Self {
         let root: Properties = HashMap::new();
         let recipients: Recipients = vec![];
         let attachments: Attachments = vec![];
-        let storage_map = EntryStorageMap::new(parser);
-        let prop_map = PropIdNameMap::init();
+        let storage_map = EntryStorageMap::new(parser, root_id);
+        let mut prop_map = PropIdNameMap::init();
+        // Embedded messages share the named properties of
+        // the message file.
+        let named = NamedPropMap::new(parser, &prop_map);
+        prop_map.set_named(&named);
         Self {
             storage_map,
             prop_map,
+            options: options.clone(),
+            root_id,
+            embedded: vec![],
+            decoded: 0,
+            warnings: vec![],
+            provenance: Provenance::default(),
+            recipient_sources: HashMap::new(),
+            attachment_sources: HashMap::new(),
             root,
             recipients,
             attachments,
         }
     }
 
-    pub fn get_val_from_root_or_default(&self, key: &str) -> String {
-        self.root.get(key).map_or(String::new(), |x| x.into())
+    // Storage of the message a stream belongs to, None
+    // for streams of other messages.
+    pub fn storage_type(&self, entry: &Entry) -> Option<&StorageType> {
+        self.storage_map.get_storage_type(entry.parent_node())
     }
 
-    pub fn get_val_from_attachment_or_default(&self, idx: usize, key: &str) -> String {
-        self.attachments
-            .iter()
-            .nth(idx)
-            .map(|attach| attach.get(key).map_or(String::from(""), |x| x.into()))
-            .unwrap_or(String::new())
+    // Name of the property held by a stream.
+    pub fn stream_key(&self, entry: &Entry) -> Option<String> {
+        Stream::key(entry.name(), &self.prop_map)
+    }
+
+    // Decodes one stream of the message on its own, 8-bit
+    // strings in the code page the root properties
+    // decoded so far tell.
+    pub fn decode_stream(&self, parser: &Reader, entry: &Entry) -> Result<Vec<Stream>, Error> {
+        let data = self.read_stream(parser, entry)?;
+        let streams = self.create_streams(entry, data, self.codepage());
+        Ok(streams.into_iter().filter_map(Result::ok).collect())
+    }
+
+    // Entry id of the storage of the message embedded
+    // in the attachment at index.
+    pub fn embedded_message(&self, index: usize) -> Option<u32> {
+        self.embedded.get(index).copied().flatten()
+    }
+
+    pub fn get_val_from_root_or_default(&self, key: &str) -> String {
+        self.root.get(key).map_or(String::new(), |x| x.into())
     }
 }
 
 #[cfg(test)]
+#[allow(clippy::legacy_numeric_constants)]
 mod tests {
-    use super::super::decode::DataType;
-    use super::{EntryStorageMap, Properties, StorageType, Storages};
+    use super::super::{decode::DataType, options::ParserOptions};
+    use super::{EntryStorageMap, Properties, StorageType, Storages, ROOT_ID};
     use crate::ole::Reader;
     use std::collections::HashMap;
 
@@ -232,12 +615,15 @@ mod tests {
 
         let unknown_storage = StorageType::create("");
         assert_eq!(unknown_storage, None);
+
+        assert_eq!(StorageType::create("__recip_version1.0_"), None);
+        assert_eq!(StorageType::create("__attach_version1.0_"), None);
     }
 
     #[test]
     fn test_storage_map() {
         let parser = Reader::from_path("data/test_email.msg").unwrap();
-        let storage_map = EntryStorageMap::new(&parser);
+        let storage_map = EntryStorageMap::new(&parser, ROOT_ID);
 
         let mut expected_map = HashMap::new();
         expected_map.insert(0, StorageType::RootEntry);
@@ -248,10 +634,13 @@ mod tests {
         expected_map.insert(120, StorageType::Recipient(4));
         expected_map.insert(132, StorageType::Recipient(5));
         expected_map.insert(143, StorageType::Attachment(0));
-        expected_map.insert(260, StorageType::Recipient(0));
         expected_map.insert(310, StorageType::Attachment(1));
         expected_map.insert(323, StorageType::Attachment(2));
         assert_eq!(storage_map.map, expected_map);
+        // The recipient of the message embedded in the first
+        // attachment is not one of the message itself.
+        assert_eq!(storage_map.get_storage_type(Some(260)), None);
+        assert!(storage_map.embedded.contains_key(&0));
     }
 
     #[test]
@@ -279,8 +668,8 @@ mod tests {
     #[test]
     fn test_create_storage_test_email() {
         let parser = Reader::from_path("data/test_email.msg").unwrap();
-        let mut storages = Storages::new(&parser);
-        storages.process_streams(&parser);
+        let mut storages = Storages::create(&parser, ROOT_ID, &ParserOptions::default());
+        storages.process_streams(&parser).unwrap();
 
         let sender = storages.root.get("SenderEmailAddress");
         assert!(sender.is_none());
@@ -295,15 +684,15 @@ mod tests {
         let display_name = storages.recipients[0].get("DisplayName").unwrap();
         assert_eq!(
             display_name,
-            &DataType::PtypString("marirs@outlook.com".to_string())
+            &DataType::PtypString("Sriram Govindan".to_string())
         );
     }
 
     #[test]
     fn test_create_storage_outlook_attachments() {
         let parser = Reader::from_path("data/test_email.msg").unwrap();
-        let mut storages = Storages::new(&parser);
-        storages.process_streams(&parser);
+        let mut storages = Storages::create(&parser, ROOT_ID, &ParserOptions::default());
+        storages.process_streams(&parser).unwrap();
 
 
         // Check attachment
@@ -332,4 +721,126 @@ mod tests {
         let display_name = storages.recipients[1].get("DisplayName").unwrap();
         assert_eq!(display_name, &DataType::PtypString("Sriram Govindan".to_string()));
     }
+
+    #[test]
+    fn test_create_storage_multiple_valued() {
+        let parser = Reader::from_path("data/test_email_4.msg").unwrap();
+        let mut storages = Storages::create(&parser, ROOT_ID, &ParserOptions::default());
+        storages.process_streams(&parser).unwrap();
+
+        assert_eq!(
+            storages.root.get("KeyPhrases"),
+            Some(&DataType::PtypMultipleString(vec![
+                "protonmail secure email".to_string()
+            ]))
+        );
+    }
+
+    #[cfg(feature = "rayon")]
+    #[test]
+    fn test_create_streams_parallel() {
+        use crate::ole::EntryType;
+
+        for path in ["data/attachment.msg", "data/test_email_4.msg", "data/unicode.msg"] {
+            let parser = Reader::from_path(path).unwrap();
+            let options = ParserOptions::new().attachments(false);
+            let mut parallel = Storages::create(&parser, ROOT_ID, &options);
+            parallel.process_streams(&parser).unwrap();
+
+            // The same streams decoded one at a time.
+            let mut sequential = Storages::create(&parser, ROOT_ID, &options);
+            let mut recipients_map = HashMap::new();
+            let mut attachments_map = HashMap::new();
+            for entry in parser.iterate().filter(|entry| matches!(entry._type(), EntryType::UserStream)) {
+                let data = sequential.read_stream(&parser, entry).unwrap();
+                let streams = sequential.create_streams(entry, data, parallel.codepage());
+                sequential
+                    .add_streams(&parser, streams, entry, &mut recipients_map, &mut attachments_map)
+                    .unwrap();
+            }
+            assert_eq!(parallel.root, sequential.root, "{}", path);
+            assert_eq!(parallel.recipients, Storages::to_arr(recipients_map), "{}", path);
+            assert_eq!(parallel.attachments, Storages::to_arr(attachments_map), "{}", path);
+            let mut warnings = sequential.warnings.clone();
+            warnings.sort_by_key(|warning| warning.stream().to_string());
+            let mut parallel_warnings = parallel.warnings.clone();
+            parallel_warnings.sort_by_key(|warning| warning.stream().to_string());
+            assert_eq!(parallel_warnings, warnings, "{}", path);
+            assert!(!parallel.warnings.is_empty());
+        }
+    }
+
+    #[cfg(feature = "tracing")]
+    #[test]
+    fn test_tracing() {
+        use super::super::outlook::Outlook;
+        use std::{
+            fmt::Debug,
+            sync::{Arc, Mutex},
+        };
+        use tracing::{
+            field::{Field, Visit},
+            span, Event, Metadata, Subscriber,
+        };
+
+        // Records the names of the spans and the messages of
+        // the events.
+        #[derive(Default, Clone)]
+        struct Recorder(Arc<Mutex<Vec<String>>>);
+
+        struct Message(Option<String>);
+
+        impl Visit for Message {
+            fn record_debug(&mut self, field: &Field, value: &dyn Debug) {
+                if field.name() == "message" {
+                    self.0 = Some(format!("{:?}", value));
+                }
+            }
+        }
+
+        impl Subscriber for Recorder {
+            fn enabled(&self, _: &Metadata) -> bool {
+                true
+            }
+
+            fn new_span(&self, attributes: &span::Attributes) -> span::Id {
+                self.0.lock().unwrap().push(attributes.metadata().name().to_string());
+                span::Id::from_u64(1)
+            }
+
+            fn record(&self, _: &span::Id, _: &span::Record) {}
+
+            fn record_follows_from(&self, _: &span::Id, _: &span::Id) {}
+
+            fn event(&self, event: &Event) {
+                let mut message = Message(None);
+                event.record(&mut message);
+                self.0.lock().unwrap().extend(message.0);
+            }
+
+            fn enter(&self, _: &span::Id) {}
+
+            fn exit(&self, _: &span::Id) {}
+        }
+
+        let recorder = Recorder::default();
+        tracing::subscriber::with_default(recorder.clone(), || {
+            let options = ParserOptions::new().bodies(false);
+            Outlook::from_path_with("data/attachment.msg", &options).unwrap();
+        });
+        let records = recorder.0.lock().unwrap();
+        for name in [
+            "build_sat",
+            "read_sector",
+            "build_directory_entries",
+            "directory entries read",
+            "create_counted",
+            "process_streams",
+            "create_streams",
+            "get_entry_slice",
+            "stream skipped",
+        ] {
+            assert!(records.iter().any(|record| record == name), "{}", name);
+        }
+    }
 }
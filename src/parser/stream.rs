@@ -1,11 +1,22 @@
+use std::io::Read;
+
 use crate::ole::EntrySlice;
 
 use super::{
     constants::PropIdNameMap,
     decode::{DataType, PtypDecoder},
+    error::{DataTypeError, Error},
     storage::StorageType,
+    warning::{SkipReason, Warning},
 };
 
+// Type codes of the properties whose value lives in a stream
+// of its own rather than in the property stream: strings,
+// binaries, objects and GUIDs, plus multiple valued ones.
+// Refer to MS-OXMSG 2.4.2.2
+const VARIABLE_TYPE_CODES: [u16; 6] = [0x001E, 0x001F, 0x0102, 0x000D, 0x0048, 0x00FB];
+const MULTIPLE_VALUED: u16 = 0x1000;
+
 // Stream refer to an element in Message object.
 #[derive(Debug, PartialEq)]
 pub struct Stream {
@@ -13,22 +24,196 @@ pub struct Stream {
     pub parent: StorageType,
     pub key: String,
     pub value: DataType,
+    // Property id followed by its type code
+    pub tag: u32,
 }
 
 impl Stream {
-    // __substg1.0__AAAABBBB where AAAA is property id and BBBB is property datatype
-    fn extract_id_and_datatype(name: &str) -> (String, String) {
-        let tag = name
-            .split("_")
-            .filter(|&x| x.len() > 0)
-            .collect::<Vec<&str>>()[1];
-        let prop_id = String::from("0x") + &tag[..4];
-        let prop_datatype = String::from("0x") + &tag[tag.len() - 4..];
-        return (prop_id, prop_datatype);
+    // __substg1.0__AAAABBBB where AAAA is property id and BBBB is property datatype.
+    // None for names not made that way, e.g. in damaged files.
+    fn extract_id_and_datatype(name: &str) -> Option<(String, String)> {
+        let tag = name.strip_prefix("__substg1.0_")?;
+        let tag = tag.split('-').next().unwrap_or_default();
+        if tag.len() != 8 || !tag.chars().all(|c| c.is_ascii_hexdigit()) {
+            return None;
+        }
+        let prop_id = String::from("0x") + tag.get(..4)?;
+        let prop_datatype = String::from("0x") + tag.get(4..)?;
+        Some((prop_id, prop_datatype))
     }
 
     fn is_stream(name: &str) -> bool {
-        return name.starts_with("__substg1.0");
+        name.starts_with("__substg1.0")
+    }
+
+    // Multiple valued properties of variable length have one
+    // stream holding the lengths of their values, named as any
+    // other property stream, plus one stream per value suffixed
+    // with its index, e.g. __substg1.0_80551102-00000000.
+    // Refer to MS-OXMSG 2.1.4.2.2
+    pub fn is_multiple_valued_stream(name: &str) -> bool {
+        if !Self::is_stream(name) || name.contains('-') {
+            return false;
+        }
+        matches!(
+            Self::extract_id_and_datatype(name),
+            Some((_, prop_datatype)) if prop_datatype == "0x101F" || prop_datatype == "0x1102"
+        )
+    }
+
+    // Index of a value stream of the multiple valued
+    // property whose lengths are in `lengths_stream`.
+    pub fn value_index(name: &str, lengths_stream: &str) -> Option<u32> {
+        let index = name.strip_prefix(lengths_stream)?.strip_prefix('-')?;
+        u32::from_str_radix(index, 16).ok()
+    }
+
+    // Strings of the message code page rather than UTF-16.
+    pub fn is_string8_stream(name: &str) -> bool {
+        if !Self::is_stream(name) || name.contains('-') {
+            return false;
+        }
+        matches!(Self::extract_id_and_datatype(name), Some((_, prop_datatype)) if prop_datatype == "0x001E")
+    }
+
+    // Value streams of multiple valued properties, decoded
+    // along with the stream holding their lengths.
+    pub fn is_value_stream(name: &str) -> bool {
+        Self::is_stream(name) && name.contains('-')
+    }
+
+    fn unknown_name(name: &str) -> Warning {
+        Warning::SkippedStream {
+            stream: name.to_string(),
+            reason: SkipReason::UnknownName,
+        }
+    }
+
+    // Property tag of the stream, its id followed by its
+    // type code.
+    fn tag(prop_id: &str, prop_datatype: &str) -> u32 {
+        let parse = |hex: &str| u32::from_str_radix(hex.trim_start_matches("0x"), 16).unwrap_or_default();
+        parse(prop_id) << 16 | parse(prop_datatype)
+    }
+
+    fn key_or_warning(name: &str, prop_id: &str, prop_map: &PropIdNameMap) -> Result<String, Warning> {
+        prop_map
+            .get_canonical_name(prop_id)
+            .ok_or_else(|| Warning::UnknownProperty {
+                stream: name.to_string(),
+                id: prop_id.to_string(),
+            })
+    }
+
+    fn decode_warning(name: &str, key: String, err: Error) -> Warning {
+        match err.root() {
+            Error::DataTypeError(DataTypeError::UnknownCode(code)) => Warning::UnknownTypeCode {
+                stream: name.to_string(),
+                property: key,
+                code: format!("0x{}", code.trim_start_matches("0x")),
+            },
+            _ => Warning::DecodeFailed {
+                stream: name.to_string(),
+                property: key,
+                error: err.to_string(),
+            },
+        }
+    }
+
+    // Name of the property a stream holds, found without
+    // decoding it. Value streams of multiple valued
+    // properties have none of their own.
+    pub fn key(name: &str, prop_map: &PropIdNameMap) -> Option<String> {
+        if !Self::is_stream(name) || name.contains('-') {
+            return None;
+        }
+        let (prop_id, _) = Self::extract_id_and_datatype(name)?;
+        prop_map.get_canonical_name(&prop_id)
+    }
+
+    // Property tag an entry is named after, e.g. 0x3701000D
+    // for "__substg1.0_3701000D", value streams having the
+    // one of their property.
+    pub fn name_tag(name: &str) -> Option<u32> {
+        let tag = name.strip_prefix("__substg1.0_")?;
+        let tag = tag.split('-').next().unwrap_or_default();
+        match tag.len() {
+            8 => u32::from_str_radix(tag, 16).ok(),
+            _ => None,
+        }
+    }
+
+    pub fn is_property_stream(name: &str) -> bool {
+        name == "__properties_version1.0"
+    }
+
+    // Size of the header preceding property entries,
+    // which depends on the storage the stream is in.
+    // Refer to MS-OXMSG 2.4.1
+    fn property_stream_header_size(parent: &StorageType, embedded: bool) -> usize {
+        match parent {
+            StorageType::RootEntry if embedded => 24,
+            StorageType::RootEntry => 32,
+            StorageType::Recipient(_) | StorageType::Attachment(_) => 8,
+        }
+    }
+
+    // Decodes the fixed length properties found in the
+    // property stream of a storage. Entries of variable
+    // length properties only hold their size, their value
+    // lives in a stream of its own.
+    // The root of an embedded message has a shorter
+    // header than the one of the message file.
+    // Refer to MS-OXMSG 2.4.2
+    pub fn create_from_property_stream(
+        entry_slice: &mut EntrySlice,
+        prop_map: &PropIdNameMap,
+        parent: &StorageType,
+        embedded: bool,
+    ) -> Vec<Result<Self, Warning>> {
+        const NAME: &str = "__properties_version1.0";
+        let mut buff = vec![0u8; entry_slice.len()];
+        if let Err(err) = entry_slice.read_exact(&mut buff) {
+            return vec![Err(Self::decode_warning(NAME, String::new(), err.into()))];
+        }
+        let header_size = Self::property_stream_header_size(parent, embedded);
+        if buff.len() < header_size {
+            return vec![];
+        }
+        buff[header_size..]
+            .chunks_exact(16)
+            .enumerate()
+            .filter_map(|(index, entry)| {
+                let code = u16::from_le_bytes([entry[0], entry[1]]);
+                if code & MULTIPLE_VALUED != 0 || VARIABLE_TYPE_CODES.contains(&code) {
+                    return None;
+                }
+                let prop_datatype = format!("0x{:04X}", code);
+                let prop_id = format!("0x{:04X}", u16::from_le_bytes([entry[2], entry[3]]));
+                let key = match Self::key_or_warning(NAME, &prop_id, prop_map) {
+                    Ok(key) => key,
+                    Err(warning) => return Some(Err(warning)),
+                };
+                let tag = Self::tag(&prop_id, &prop_datatype);
+                let mut value = [0u8; 8];
+                value.copy_from_slice(&entry[8..16]);
+                Some(
+                    PtypDecoder::decode_fixed(&value, &prop_datatype)
+                        .map(|value| Self {
+                            parent: parent.clone(),
+                            key: key.clone(),
+                            value,
+                            tag,
+                        })
+                        .map_err(|err| {
+                            let err = err
+                                .at_tag(tag)
+                                .at_offset(header_size + index * 16 + 8);
+                            Self::decode_warning(NAME, key, err)
+                        }),
+                )
+            })
+            .collect()
     }
 
     pub fn create(
@@ -36,27 +221,79 @@ impl Stream {
         entry_slice: &mut EntrySlice,
         prop_map: &PropIdNameMap,
         parent: &StorageType,
-    ) -> Option<Self> {
+    ) -> Result<Self, Warning> {
         if !Self::is_stream(name) {
-            return None;
+            return Err(Self::unknown_name(name));
         }
         // Split name up into property id and datatype
-        let (prop_id, prop_datatype) = Self::extract_id_and_datatype(name);
-        let key = prop_map.get_canonical_name(&prop_id)?;
-        let value_res = PtypDecoder::decode(entry_slice, &prop_datatype);
-        if value_res.is_err() {
-            return None;
+        let (prop_id, prop_datatype) =
+            Self::extract_id_and_datatype(name).ok_or_else(|| Self::unknown_name(name))?;
+        let key = Self::key_or_warning(name, &prop_id, prop_map)?;
+        match PtypDecoder::decode(entry_slice, &prop_datatype) {
+            Ok(value) => Ok(Self {
+                parent: parent.clone(),
+                key,
+                value,
+                tag: Self::tag(&prop_id, &prop_datatype),
+            }),
+            Err(err) => Err(Self::decode_warning(name, key, err.at_tag(Self::tag(&prop_id, &prop_datatype)))),
+        }
+    }
+
+    // Same as create for 8-bit strings, decoded from
+    // the code page of the message.
+    pub fn create_string8(
+        name: &str,
+        entry_slice: &mut EntrySlice,
+        prop_map: &PropIdNameMap,
+        parent: &StorageType,
+        codepage: u32,
+    ) -> Result<Self, Warning> {
+        if !Self::is_string8_stream(name) {
+            return Err(Self::unknown_name(name));
+        }
+        let (prop_id, prop_datatype) =
+            Self::extract_id_and_datatype(name).ok_or_else(|| Self::unknown_name(name))?;
+        let key = Self::key_or_warning(name, &prop_id, prop_map)?;
+        match PtypDecoder::decode_string8(entry_slice, codepage) {
+            Ok(value) => Ok(Self {
+                parent: parent.clone(),
+                key,
+                value,
+                tag: Self::tag(&prop_id, &prop_datatype),
+            }),
+            Err(err) => Err(Self::decode_warning(name, key, err.at_tag(Self::tag(&prop_id, &prop_datatype)))),
+        }
+    }
+
+    // Same as create for multiple valued properties,
+    // given the slices of their values in order.
+    pub fn create_multiple(
+        name: &str,
+        entry_slices: &mut [EntrySlice],
+        prop_map: &PropIdNameMap,
+        parent: &StorageType,
+    ) -> Result<Self, Warning> {
+        if !Self::is_multiple_valued_stream(name) {
+            return Err(Self::unknown_name(name));
+        }
+        let (prop_id, prop_datatype) =
+            Self::extract_id_and_datatype(name).ok_or_else(|| Self::unknown_name(name))?;
+        let key = Self::key_or_warning(name, &prop_id, prop_map)?;
+        match PtypDecoder::decode_multiple(entry_slices, &prop_datatype) {
+            Ok(value) => Ok(Self {
+                parent: parent.clone(),
+                key,
+                value,
+                tag: Self::tag(&prop_id, &prop_datatype),
+            }),
+            Err(err) => Err(Self::decode_warning(name, key, err.at_tag(Self::tag(&prop_id, &prop_datatype)))),
         }
-        let value = value_res.unwrap();
-        Some(Self {
-            parent: parent.clone(),
-            key,
-            value,
-        })
     }
 }
 
 #[cfg(test)]
+#[allow(clippy::bool_assert_comparison, clippy::iter_nth_zero)]
 mod tests {
     use super::{
         super::constants::PropIdNameMap, super::decode::DataType, super::storage::StorageType,
@@ -66,13 +303,39 @@ mod tests {
 
     #[test]
     fn test_extract_id_and_datatype() {
-        let (prop_id, prop_datatype) = Stream::extract_id_and_datatype("__substg1.0_3701000D");
+        let (prop_id, prop_datatype) = Stream::extract_id_and_datatype("__substg1.0_3701000D").unwrap();
         assert_eq!(prop_id, "0x3701");
         assert_eq!(prop_datatype, "0x000D");
 
-        let (prop_id, prop_datatype) = Stream::extract_id_and_datatype("__substg1.0_1016102F");
+        let (prop_id, prop_datatype) = Stream::extract_id_and_datatype("__substg1.0_1016102F").unwrap();
         assert_eq!(prop_id, "0x1016");
         assert_eq!(prop_datatype, "0x102F");
+
+        for name in ["__substg1.0_", "__substg1.0_AB", "__substg1.0_3701000G", "__substg1.0_é701000D"] {
+            assert_eq!(Stream::extract_id_and_datatype(name), None, "{}", name);
+            assert!(!Stream::is_string8_stream(name));
+            assert!(!Stream::is_multiple_valued_stream(name));
+        }
+    }
+
+    #[test]
+    fn test_multiple_valued_stream() {
+        assert!(Stream::is_multiple_valued_stream("__substg1.0_80551102"));
+        assert!(Stream::is_multiple_valued_stream("__substg1.0_8009101F"));
+        assert!(!Stream::is_multiple_valued_stream("__substg1.0_80551102-00000000"));
+        assert!(!Stream::is_multiple_valued_stream("__substg1.0_3701000D"));
+        assert_eq!(
+            Stream::value_index("__substg1.0_80551102-0000000A", "__substg1.0_80551102"),
+            Some(10)
+        );
+        assert_eq!(
+            Stream::value_index("__substg1.0_80551102", "__substg1.0_80551102"),
+            None
+        );
+        assert_eq!(
+            Stream::value_index("__substg1.0_80541102-00000000", "__substg1.0_80551102"),
+            None
+        );
     }
 
     #[test]
@@ -81,6 +344,14 @@ mod tests {
         assert_eq!(Stream::is_stream("__substg1.0_3701000D"), true);
     }
 
+    #[test]
+    fn test_name_tag() {
+        assert_eq!(Stream::name_tag("__substg1.0_3701000D"), Some(0x3701_000D));
+        assert_eq!(Stream::name_tag("__substg1.0_80551102-00000001"), Some(0x8055_1102));
+        assert_eq!(Stream::name_tag("__properties_version1.0"), None);
+        assert_eq!(Stream::name_tag("__substg1.0_37"), None);
+    }
+
     #[test]
     fn test_create_stream() {
         let parser = Reader::from_path("data/test_email.msg").unwrap();
@@ -102,10 +373,11 @@ mod tests {
         );
         assert_eq!(
             stream,
-            Some(Stream {
+            Ok(Stream {
                 key: "SenderEmailAddress".to_string(),
                 value: DataType::PtypString("upgrade@asuswebstorage.com".to_string()),
                 parent: StorageType::RootEntry,
+                tag: 0x0C1F_001F,
             })
         );
 
@@ -124,14 +396,48 @@ mod tests {
         );
         assert_eq!(
             stream,
-            Some(Stream {
+            Ok(Stream {
                 key: "DisplayName".to_string(),
                 value: DataType::PtypString("Sriram Govindan".to_string()),
-                parent: StorageType::Recipient(1)
+                parent: StorageType::Recipient(1),
+                tag: 0x3001_001F,
             })
         )
     }
 
+    #[test]
+    fn test_create_from_property_stream() {
+        let parser = Reader::from_path("data/unicode.msg").unwrap();
+        let prop_map = PropIdNameMap::init();
+
+        let mut slice = parser
+            .entry_by_path("__properties_version1.0")
+            .and_then(|entry| parser.get_entry_slice(entry).ok())
+            .unwrap();
+        let streams: Vec<Stream> =
+            Stream::create_from_property_stream(&mut slice, &prop_map, &StorageType::RootEntry, false)
+                .into_iter()
+                .filter_map(Result::ok)
+                .collect();
+        let importance = streams.iter().find(|stream| stream.key == "Importance");
+        assert_eq!(
+            importance,
+            Some(&Stream {
+                key: "Importance".to_string(),
+                value: DataType::PtypInteger32(1),
+                parent: StorageType::RootEntry,
+                tag: 0x0017_0003,
+            })
+        );
+        let submit_time = streams
+            .iter()
+            .find(|stream| stream.key == "ClientSubmitTime")
+            .map(|stream| String::from(&stream.value));
+        assert_eq!(submit_time, Some("2013-11-18T08:26:24Z".to_string()));
+        // Variable length properties are left to their own streams.
+        assert!(streams.iter().all(|stream| stream.key != "Subject"));
+    }
+
     #[test]
     fn test_create_attachment() {
         let parser = Reader::from_path("data/attachment.msg").unwrap();
@@ -139,8 +445,7 @@ mod tests {
 
         // Attachment object.
         let mut attachment = parser
-            .iterate()
-            .find(|x| x.name() == "__substg1.0_3703001F" && x.parent_node() == Some(7u32))
+            .entry_by_path("__attach_version1.0_#00000000/__substg1.0_3703001F")
             .and_then(|entry| parser.get_entry_slice(entry).ok())
             .unwrap();
         let stream = Stream::create(
@@ -151,10 +456,11 @@ mod tests {
         );
         assert_eq!(
             stream,
-            Some(Stream {
+            Ok(Stream {
                 key: "AttachExtension".to_string(),
                 value: DataType::PtypString(".doc".to_string()),
-                parent: StorageType::Attachment(0)
+                parent: StorageType::Attachment(0),
+                tag: 0x3703_001F,
             })
         )
     }
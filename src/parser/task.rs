@@ -0,0 +1,189 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use super::{decode::DataType, storage::Properties};
+
+// TaskStatus is the progress the user made on a task.
+// Refer to MS-OXOTASK 2.2.2.2.2
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub enum TaskStatus {
+    NotStarted,
+    InProgress,
+    Complete,
+    WaitingOnOthers,
+    Deferred,
+}
+
+impl TaskStatus {
+    pub fn from_value(value: i64) -> Self {
+        match value {
+            1 => TaskStatus::InProgress,
+            2 => TaskStatus::Complete,
+            3 => TaskStatus::WaitingOnOthers,
+            4 => TaskStatus::Deferred,
+            _ => TaskStatus::NotStarted,
+        }
+    }
+}
+
+// TaskRequestKind is the kind of task communication
+// sent while assigning a task, as told by its message class.
+// Refer to MS-OXOTASK 2.2.3.1
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub enum TaskRequestKind {
+    Request,
+    Accepted,
+    Declined,
+    Update,
+}
+
+impl TaskRequestKind {
+    pub fn from_message_class(message_class: &str) -> Option<Self> {
+        let message_class = message_class.to_ascii_lowercase();
+        let kind = message_class.strip_prefix("ipm.taskrequest")?;
+        if kind.is_empty() {
+            return Some(TaskRequestKind::Request);
+        }
+        // Custom forms may append their own suffix.
+        match kind.strip_prefix('.')?.split('.').next() {
+            Some("accept") => Some(TaskRequestKind::Accepted),
+            Some("decline") => Some(TaskRequestKind::Declined),
+            Some("update") => Some(TaskRequestKind::Update),
+            _ => Some(TaskRequestKind::Request),
+        }
+    }
+}
+
+// Task holds the properties of an IPM.Task item or
+// of a task request, acceptance or decline.
+// Refer to MS-OXOTASK 2.2.2.2
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct Task {
+    pub status: TaskStatus,                    // "TaskStatus"
+    pub percent_complete: f64,                 // "PercentComplete", from 0.0 to 1.0
+    pub start_date: Option<DateTime<Utc>>,     // "TaskStartDate"
+    pub due_date: Option<DateTime<Utc>>,       // "TaskDueDate"
+    pub date_completed: Option<DateTime<Utc>>, // "TaskDateCompleted"
+    pub complete: bool,                        // "TaskComplete"
+    pub owner: String,                         // "TaskOwner"
+    pub assigner: String,                      // "TaskAssigner"
+    pub request: Option<TaskRequestKind>,      // "MessageClass"
+}
+
+impl Task {
+    pub fn is_task_class(message_class: &str) -> bool {
+        let message_class = message_class.to_ascii_lowercase();
+        message_class == "ipm.task" || message_class.starts_with("ipm.task.")
+    }
+
+    pub fn create_from_props(props: &Properties) -> Option<Self> {
+        let message_class = props.get("MessageClass").and_then(DataType::as_str)?;
+        let request = TaskRequestKind::from_message_class(message_class);
+        if request.is_none() && !Self::is_task_class(message_class) {
+            return None;
+        }
+        let string = |key: &str| {
+            props
+                .get(key)
+                .and_then(DataType::as_str)
+                .unwrap_or_default()
+                .to_string()
+        };
+        Some(Self {
+            status: props
+                .get("TaskStatus")
+                .and_then(DataType::as_i64)
+                .map_or(TaskStatus::NotStarted, TaskStatus::from_value),
+            percent_complete: props
+                .get("PercentComplete")
+                .and_then(DataType::as_f64)
+                .unwrap_or(0.0),
+            start_date: props.get("TaskStartDate").and_then(DataType::as_time),
+            due_date: props.get("TaskDueDate").and_then(DataType::as_time),
+            date_completed: props.get("TaskDateCompleted").and_then(DataType::as_time),
+            complete: props
+                .get("TaskComplete")
+                .and_then(DataType::as_bool)
+                .unwrap_or(false),
+            owner: string("TaskOwner"),
+            assigner: string("TaskAssigner"),
+            request,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{TimeZone, Utc};
+
+    use super::{
+        super::{
+            decode::DataType,
+            storage::{props, string, Properties},
+        },
+        Task, TaskRequestKind, TaskStatus,
+    };
+
+    fn task_props(message_class: &str) -> Properties {
+        props(&[
+            ("MessageClass", string(message_class)),
+            ("TaskStatus", DataType::PtypInteger32(1)),
+            ("PercentComplete", DataType::PtypFloating64(0.25)),
+            (
+                "TaskStartDate",
+                DataType::PtypTime(Utc.with_ymd_and_hms(2021, 6, 1, 0, 0, 0).unwrap()),
+            ),
+            (
+                "TaskDueDate",
+                DataType::PtypTime(Utc.with_ymd_and_hms(2021, 6, 4, 0, 0, 0).unwrap()),
+            ),
+            ("TaskComplete", DataType::PtypBoolean(false)),
+            ("TaskOwner", string("Jane Doe")),
+        ])
+    }
+
+    #[test]
+    fn test_create_task() {
+        let task = Task::create_from_props(&task_props("IPM.Task")).unwrap();
+        assert_eq!(
+            task,
+            Task {
+                status: TaskStatus::InProgress,
+                percent_complete: 0.25,
+                start_date: Some(Utc.with_ymd_and_hms(2021, 6, 1, 0, 0, 0).unwrap()),
+                due_date: Some(Utc.with_ymd_and_hms(2021, 6, 4, 0, 0, 0).unwrap()),
+                date_completed: None,
+                complete: false,
+                owner: "Jane Doe".to_string(),
+                assigner: String::new(),
+                request: None,
+            }
+        );
+    }
+
+    #[test]
+    fn test_task_request() {
+        let task = Task::create_from_props(&task_props("IPM.TaskRequest.Accept")).unwrap();
+        assert_eq!(task.request, Some(TaskRequestKind::Accepted));
+        assert_eq!(task.status, TaskStatus::InProgress);
+
+        let kind = TaskRequestKind::from_message_class;
+        assert_eq!(kind("IPM.TaskRequest"), Some(TaskRequestKind::Request));
+        assert_eq!(kind("IPM.TaskRequest.Custom"), Some(TaskRequestKind::Request));
+        assert_eq!(kind("ipm.taskrequest.decline"), Some(TaskRequestKind::Declined));
+        assert_eq!(kind("IPM.TaskRequest.Update"), Some(TaskRequestKind::Update));
+        assert_eq!(kind("IPM.TaskRequestX"), None);
+        assert_eq!(kind("IPM.Task"), None);
+    }
+
+    #[test]
+    fn test_not_a_task() {
+        assert_eq!(Task::create_from_props(&task_props("IPM.Note")), None);
+        assert_eq!(Task::create_from_props(&task_props("IPM.Tasks")), None);
+        assert!(Task::is_task_class("IPM.Task.Custom"));
+        assert_eq!(Task::create_from_props(&Properties::new()), None);
+    }
+}
@@ -0,0 +1,303 @@
+use std::{collections::HashMap, iter::FromIterator};
+
+use serde::{Deserialize, Serialize};
+
+use super::{conversation::ConversationIndex, decode::DataType, outlook::Outlook};
+
+// ThreadNode is a message of a conversation along with
+// the replies to it.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ThreadNode {
+    pub message: usize,           // position of the message given to the Threader
+    pub replies: Vec<ThreadNode>, // in the order the messages were given
+}
+
+// Thread is a conversation, messages that don't
+// reply to any other message of it being its roots.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Thread {
+    pub topic: String,          // "ConversationTopic", or else the subject without prefixes
+    pub roots: Vec<ThreadNode>, // See ThreadNode struct
+}
+
+// Threader groups messages into conversations.
+// Messages are grouped by the GUID of their conversation
+// index, or else by their conversation topic, and linked
+// by their Message-ID, In-Reply-To and References.
+#[derive(Debug, Default)]
+pub struct Threader<'a> {
+    messages: Vec<&'a Outlook>,
+}
+
+// Topic of a message, e.g. "Lunch" for "RE: Fwd: Lunch".
+fn topic(outlook: &Outlook) -> String {
+    let topic = outlook
+        .properties
+        .root
+        .get("ConversationTopic")
+        .and_then(DataType::as_str)
+        .unwrap_or_default();
+    if !topic.is_empty() {
+        return topic.to_string();
+    }
+    let mut subject = outlook.subject.trim();
+    while let Some((prefix, rest)) = subject.split_once(':') {
+        match prefix.trim().to_ascii_lowercase().as_str() {
+            "re" | "fw" | "fwd" => subject = rest.trim(),
+            _ => break,
+        }
+    }
+    subject.to_string()
+}
+
+// Whether `parent` comes before `child` in the same conversation.
+fn is_ancestor(parent: &ConversationIndex, child: &ConversationIndex) -> bool {
+    parent.guid == child.guid
+        && parent.time == child.time
+        && parent.replies.len() < child.replies.len()
+        && child.replies.starts_with(&parent.replies)
+}
+
+impl<'a> Threader<'a> {
+    pub fn new() -> Self {
+        Self { messages: vec![] }
+    }
+
+    // Adds a message, returning its position in the threads.
+    pub fn add(&mut self, outlook: &'a Outlook) -> usize {
+        self.messages.push(outlook);
+        self.messages.len() - 1
+    }
+
+    fn find(groups: &mut [usize], i: usize) -> usize {
+        let mut root = i;
+        while groups[root] != root {
+            root = groups[root];
+        }
+        groups[i] = root;
+        root
+    }
+
+    fn union(groups: &mut [usize], a: usize, b: usize) {
+        let (a, b) = (Self::find(groups, a), Self::find(groups, b));
+        groups[a.max(b)] = a.min(b);
+    }
+
+    // Message the given one replies to, if it was given.
+    fn parent(&self, i: usize, by_id: &HashMap<&str, usize>) -> Option<usize> {
+        let message = self.messages[i];
+        // The longest conversation index the message's one
+        // starts with is the one of the message it replies to.
+        if let Some(index) = &message.conversation_index {
+            let parent = self
+                .messages
+                .iter()
+                .enumerate()
+                .filter_map(|(j, other)| Some((j, other.conversation_index.as_ref()?)))
+                .filter(|(_, other)| is_ancestor(other, index))
+                .max_by_key(|(_, other)| other.replies.len());
+            if let Some((j, _)) = parent {
+                return Some(j);
+            }
+        }
+        std::iter::once(&message.in_reply_to)
+            .chain(message.references.iter().rev())
+            .filter_map(|id| by_id.get(id.as_str()).copied())
+            .find(|&j| j != i)
+    }
+
+    fn node(i: usize, children: &HashMap<usize, Vec<usize>>) -> ThreadNode {
+        ThreadNode {
+            message: i,
+            replies: children
+                .get(&i)
+                .map(|replies| replies.iter().map(|&j| Self::node(j, children)).collect())
+                .unwrap_or_default(),
+        }
+    }
+
+    pub fn threads(&self) -> Vec<Thread> {
+        let count = self.messages.len();
+        let mut by_id: HashMap<&str, usize> = HashMap::new();
+        for (i, message) in self.messages.iter().enumerate() {
+            if !message.message_id.is_empty() {
+                by_id.entry(message.message_id.as_str()).or_insert(i);
+            }
+        }
+
+        // Links each message to the one it replies to, leaving
+        // out links that would make a cycle.
+        let mut parents: Vec<Option<usize>> = vec![None; count];
+        for i in 0..count {
+            let parent = match self.parent(i, &by_id) {
+                Some(parent) => parent,
+                None => continue,
+            };
+            let mut ancestor = Some(parent);
+            while let Some(j) = ancestor {
+                if j == i {
+                    break;
+                }
+                ancestor = parents[j];
+            }
+            if ancestor.is_none() {
+                parents[i] = Some(parent);
+            }
+        }
+
+        let mut groups: Vec<usize> = (0..count).collect();
+        let mut by_key: HashMap<String, usize> = HashMap::new();
+        for (i, message) in self.messages.iter().enumerate() {
+            let key = match &message.conversation_index {
+                Some(index) => format!("guid:{}", index.guid),
+                None => format!("topic:{}", topic(message).to_lowercase()),
+            };
+            if key == "topic:" {
+                continue;
+            }
+            match by_key.get(&key) {
+                Some(&j) => Self::union(&mut groups, i, j),
+                None => {
+                    by_key.insert(key, i);
+                }
+            }
+        }
+        for (i, parent) in parents.iter().enumerate() {
+            if let Some(parent) = parent {
+                Self::union(&mut groups, i, *parent);
+            }
+        }
+
+        let mut children: HashMap<usize, Vec<usize>> = HashMap::new();
+        for (i, parent) in parents.iter().enumerate() {
+            if let Some(parent) = parent {
+                children.entry(*parent).or_default().push(i);
+            }
+        }
+        let mut threads: Vec<Thread> = vec![];
+        let mut thread_of: HashMap<usize, usize> = HashMap::new();
+        for (i, parent) in parents.iter().enumerate() {
+            let group = Self::find(&mut groups, i);
+            let thread = *thread_of.entry(group).or_insert_with(|| {
+                threads.push(Thread {
+                    topic: topic(self.messages[i]),
+                    roots: vec![],
+                });
+                threads.len() - 1
+            });
+            if parent.is_none() {
+                threads[thread].roots.push(Self::node(i, &children));
+            }
+        }
+        threads
+    }
+}
+
+impl<'a> FromIterator<&'a Outlook> for Threader<'a> {
+    fn from_iter<I: IntoIterator<Item = &'a Outlook>>(iter: I) -> Self {
+        Self {
+            messages: iter.into_iter().collect(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{super::conversation::ConversationIndex, Outlook, Thread, ThreadNode, Threader};
+
+    fn message(subject: &str, message_id: &str, in_reply_to: &str) -> Outlook {
+        let mut outlook = Outlook::from_path("data/test_email.msg").unwrap();
+        outlook.properties.root.remove("ConversationTopic");
+        outlook.conversation_index = None;
+        outlook.subject = subject.to_string();
+        outlook.message_id = message_id.to_string();
+        outlook.in_reply_to = in_reply_to.to_string();
+        outlook
+    }
+
+    fn node(message: usize, replies: Vec<ThreadNode>) -> ThreadNode {
+        ThreadNode { message, replies }
+    }
+
+    #[test]
+    fn test_thread_by_message_id() {
+        let messages = [
+            message("RE: Lunch", "b@x", "a@x"),
+            message("Lunch", "a@x", ""),
+            message("Other", "c@x", ""),
+            message("Re: Fwd: lunch", "d@x", "b@x"),
+            // Replies to a message that wasn't given.
+            message("Re: Report", "e@x", "z@x"),
+        ];
+        let threads = messages.iter().collect::<Threader>().threads();
+        assert_eq!(
+            threads,
+            vec![
+                Thread {
+                    topic: "Lunch".to_string(),
+                    roots: vec![node(1, vec![node(0, vec![node(3, vec![])])])],
+                },
+                Thread {
+                    topic: "Other".to_string(),
+                    roots: vec![node(2, vec![])],
+                },
+                Thread {
+                    topic: "Report".to_string(),
+                    roots: vec![node(4, vec![])],
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_thread_cycle() {
+        let messages = [message("a", "a@x", "b@x"), message("b", "b@x", "a@x")];
+        let threads = messages.iter().collect::<Threader>().threads();
+        assert_eq!(threads.len(), 1);
+        assert_eq!(threads[0].roots, vec![node(1, vec![node(0, vec![])])]);
+    }
+
+    #[test]
+    fn test_thread_by_conversation_index() {
+        let index = |levels: &[u8]| {
+            let mut buff = hex::decode("01d6e30ea221d1898e06dfce4b9da3b90f8456cc8342").unwrap();
+            buff.extend(levels);
+            ConversationIndex::from_bytes(&buff).ok()
+        };
+        let mut messages = [
+            message("Reply", "", ""),
+            message("Start", "", ""),
+            message("Reply to reply", "", ""),
+            message("Unrelated", "", ""),
+        ];
+        messages[0].conversation_index = index(&[0, 0, 1, 0, 0]);
+        messages[1].conversation_index = index(&[]);
+        messages[2].conversation_index = index(&[0, 0, 1, 0, 0, 0, 0, 2, 0, 0]);
+
+        let mut threader = Threader::new();
+        for message in messages.iter() {
+            threader.add(message);
+        }
+        let threads = threader.threads();
+        assert_eq!(threads.len(), 2);
+        assert_eq!(threads[0].topic, "Reply".to_string());
+        assert_eq!(
+            threads[0].roots,
+            vec![node(1, vec![node(0, vec![node(2, vec![])])])]
+        );
+        assert_eq!(threads[1].roots, vec![node(3, vec![])]);
+    }
+
+    #[test]
+    fn test_thread_test_emails() {
+        let messages = ["data/test_email_1.msg", "data/test_email_2.msg", "data/test_email_4.msg"]
+            .iter()
+            .map(|path| Outlook::from_path(path).unwrap())
+            .collect::<Vec<Outlook>>();
+        let threads = messages.iter().collect::<Threader>().threads();
+        assert_eq!(threads.len(), 2);
+        assert_eq!(threads[0].topic, "Testing Email".to_string());
+        assert_eq!(threads[0].roots.len(), 2);
+        assert_eq!(threads[1].topic, "test".to_string());
+    }
+}
@@ -0,0 +1,519 @@
+use chrono::{DateTime, Datelike, Duration, FixedOffset, NaiveDate, NaiveDateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use super::{
+    blob::BlobReader, clock::Clock, decode::DataType, error::DataTypeError, storage::Properties,
+};
+
+// TransitionDate mirrors the SYSTEMTIME structure
+// time zone rules use to describe when standard
+// and daylight time begin.
+// When year is 0 the date is relative: day is the
+// occurrence (1 to 5, 5 meaning last) of day_of_week
+// (0 being Sunday) within month.
+// A month of 0 means the zone has no daylight time.
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct TransitionDate {
+    pub year: u16,
+    pub month: u16,
+    pub day_of_week: u16,
+    pub day: u16,
+    pub hour: u16,
+    pub minute: u16,
+    pub second: u16,
+    pub milliseconds: u16,
+}
+
+impl TransitionDate {
+    fn read(reader: &mut BlobReader) -> Result<Self, DataTypeError> {
+        Ok(Self {
+            year: reader.read_u16()?,
+            month: reader.read_u16()?,
+            day_of_week: reader.read_u16()?,
+            day: reader.read_u16()?,
+            hour: reader.read_u16()?,
+            minute: reader.read_u16()?,
+            second: reader.read_u16()?,
+            milliseconds: reader.read_u16()?,
+        })
+    }
+
+    fn last_day_of_month(year: i32, month: u32) -> Option<u32> {
+        let next = if month == 12 {
+            NaiveDate::from_ymd_opt(year + 1, 1, 1)?
+        } else {
+            NaiveDate::from_ymd_opt(year, month + 1, 1)?
+        };
+        Some(next.pred_opt()?.day())
+    }
+
+    // Local wall-clock time of the transition in a given year.
+    pub fn resolve(&self, year: i32) -> Option<NaiveDateTime> {
+        if self.month == 0 {
+            return None;
+        }
+        let month = self.month as u32;
+        let date = if self.year == 0 {
+            let first = NaiveDate::from_ymd_opt(year, month, 1)?;
+            let first_weekday = first.weekday().num_days_from_sunday();
+            let occurrence = self.day.clamp(1, 5) as u32;
+            let mut day = 1 + (7 + self.day_of_week as u32 - first_weekday) % 7 + (occurrence - 1) * 7;
+            let last_day = Self::last_day_of_month(year, month)?;
+            while day > last_day {
+                day -= 7;
+            }
+            NaiveDate::from_ymd_opt(year, month, day)?
+        } else {
+            NaiveDate::from_ymd_opt(year, month, self.day as u32)?
+        };
+        date.and_hms_milli_opt(
+            self.hour as u32,
+            self.minute as u32,
+            self.second as u32,
+            self.milliseconds as u32,
+        )
+    }
+}
+
+// TimeZoneRule holds the biases and daylight saving
+// transitions of a time zone, effective from year.
+// Biases are in minutes, UTC = local time + bias.
+// Refer to MS-OXOCAL 2.2.1.41.1 (TZRULE)
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct TimeZoneRule {
+    pub year: u16,
+    // TZRULE_FLAG_EFFECTIVE_TZREG
+    pub effective: bool,
+    // TZRULE_FLAG_RECUR_CURRENT_TZREG
+    pub recur_current: bool,
+    pub bias: i32,
+    pub standard_bias: i32,
+    pub daylight_bias: i32,
+    pub standard_date: TransitionDate,
+    pub daylight_date: TransitionDate,
+}
+
+impl TimeZoneRule {
+    const FLAG_RECUR_CURRENT_TZREG: u16 = 0x0001;
+    const FLAG_EFFECTIVE_TZREG: u16 = 0x0002;
+
+    fn read(reader: &mut BlobReader) -> Result<Self, DataTypeError> {
+        // bMajorVersion, bMinorVersion, wReserved
+        reader.skip(4)?;
+        let flags = reader.read_u16()?;
+        let year = reader.read_u16()?;
+        // Unused X field
+        reader.skip(14)?;
+        Self {
+            year,
+            effective: flags & Self::FLAG_EFFECTIVE_TZREG != 0,
+            recur_current: flags & Self::FLAG_RECUR_CURRENT_TZREG != 0,
+            bias: reader.read_i32()?,
+            standard_bias: reader.read_i32()?,
+            daylight_bias: reader.read_i32()?,
+            standard_date: TransitionDate::read(reader)?,
+            daylight_date: TransitionDate::read(reader)?,
+        }
+        .checked()
+    }
+
+    // Offsets east of UTC, in minutes, in standard and
+    // daylight time. None when they, or the same in seconds,
+    // overflow.
+    fn offsets(&self) -> Option<(i32, i32)> {
+        let offset = |bias: i32| {
+            let minutes = self.bias.checked_add(bias)?.checked_neg()?;
+            minutes.checked_mul(60).map(|_| minutes)
+        };
+        Some((offset(self.standard_bias)?, offset(self.daylight_bias)?))
+    }
+
+    // Rejects rules whose biases add up past what an offset
+    // can hold.
+    fn checked(self) -> Result<Self, DataTypeError> {
+        match self.offsets() {
+            Some(_) => Ok(self),
+            None => Err(DataTypeError::MalformedBlob(format!(
+                "biases {}, {} and {} overflow",
+                self.bias, self.standard_bias, self.daylight_bias
+            ))),
+        }
+    }
+
+    // Offset east of UTC, in minutes, at a given UTC time.
+    fn offset_minutes(&self, utc: &NaiveDateTime) -> i32 {
+        let (standard, daylight) = self.offsets().unwrap_or_default();
+        let year = utc.year();
+        let transitions = (
+            self.daylight_date.resolve(year),
+            self.standard_date.resolve(year),
+        );
+        if let (Some(daylight_start), Some(standard_start)) = transitions {
+            // Each transition is expressed in the wall-clock
+            // time of the period it ends.
+            let daylight_start = daylight_start - Duration::minutes(standard as i64);
+            let standard_start = standard_start - Duration::minutes(daylight as i64);
+            let in_daylight = if daylight_start < standard_start {
+                *utc >= daylight_start && *utc < standard_start
+            } else {
+                // Southern hemisphere, daylight time spans new year.
+                *utc >= daylight_start || *utc < standard_start
+            };
+            if in_daylight {
+                return daylight;
+            }
+        }
+        standard
+    }
+}
+
+// TimeZone is the time zone an appointment was
+// scheduled in, decoded either from a TZDEFINITION
+// ("AppointmentTimeZoneDefinitionStartDisplay")
+// or a legacy TZSTRUCT ("TimeZoneStruct").
+// Refer to MS-OXOCAL 2.2.1.39 and 2.2.1.41
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct TimeZone {
+    // Windows registry key name, e.g. "Pacific Standard Time".
+    // Empty when decoded from a TZSTRUCT.
+    pub key_name: String,
+    pub rules: Vec<TimeZoneRule>,
+}
+
+impl TimeZone {
+    const TZDEFINITION_MAJOR_VERSION: u8 = 0x02;
+    const TZSTRUCT_SIZE: usize = 48;
+
+    pub fn from_tz_struct(buff: &[u8]) -> Result<Self, DataTypeError> {
+        if buff.len() < Self::TZSTRUCT_SIZE {
+            return Err(DataTypeError::MalformedBlob(format!(
+                "TZSTRUCT is {} bytes long, expected {}",
+                buff.len(),
+                Self::TZSTRUCT_SIZE
+            )));
+        }
+        let mut reader = BlobReader::new(buff);
+        let bias = reader.read_i32()?;
+        let standard_bias = reader.read_i32()?;
+        let daylight_bias = reader.read_i32()?;
+        // wStandardYear, also carried by stStandardDate.
+        reader.skip(2)?;
+        let standard_date = TransitionDate::read(&mut reader)?;
+        // wDaylightYear
+        reader.skip(2)?;
+        let daylight_date = TransitionDate::read(&mut reader)?;
+        Ok(Self {
+            key_name: String::new(),
+            rules: vec![TimeZoneRule {
+                year: standard_date.year,
+                effective: true,
+                recur_current: false,
+                bias,
+                standard_bias,
+                daylight_bias,
+                standard_date,
+                daylight_date,
+            }
+            .checked()?],
+        })
+    }
+
+    pub fn from_tz_definition(buff: &[u8]) -> Result<Self, DataTypeError> {
+        let mut reader = BlobReader::new(buff);
+        let major_version = reader.read_u8()?;
+        if major_version != Self::TZDEFINITION_MAJOR_VERSION {
+            return Err(DataTypeError::MalformedBlob(format!(
+                "unsupported TZDEFINITION version {}",
+                major_version
+            )));
+        }
+        // bMinorVersion
+        reader.skip(1)?;
+        let header_size = reader.read_u16()? as usize;
+        let header_start = reader.offset();
+        // wReserved
+        reader.skip(2)?;
+        let key_name_len = reader.read_u16()? as usize;
+        let key_name = reader.read_utf16(key_name_len)?;
+        let rules_count = reader.read_u16()? as usize;
+        // Skip whatever newer versions add to the header.
+        let header_read = reader.offset() - header_start;
+        if header_size > header_read {
+            reader.skip(header_size - header_read)?;
+        }
+        let mut rules = Vec::with_capacity(rules_count.min(1024));
+        for _ in 0..rules_count {
+            rules.push(TimeZoneRule::read(&mut reader)?);
+        }
+        Ok(Self { key_name, rules })
+    }
+
+    pub fn create_from_props(props: &Properties) -> Option<Self> {
+        let binary = |key: &str| props.get(key).and_then(DataType::as_bytes);
+        binary("AppointmentTimeZoneDefinitionStartDisplay")
+            .and_then(|buff| Self::from_tz_definition(buff).ok())
+            .or_else(|| binary("TimeZoneStruct").and_then(|buff| Self::from_tz_struct(buff).ok()))
+    }
+
+    // Picks the rule in effect for a given year: the latest
+    // one starting on or before it, or the earliest overall.
+    fn rule_for_year(&self, year: i32) -> Option<&TimeZoneRule> {
+        let mut rules = self.rules.iter().collect::<Vec<&TimeZoneRule>>();
+        rules.sort_by_key(|rule| rule.year);
+        rules
+            .iter()
+            .rev()
+            .find(|rule| rule.year as i32 <= year)
+            .or_else(|| rules.first())
+            .copied()
+    }
+
+    // Offset from UTC observed at a given instant.
+    pub fn offset_at(&self, utc: &DateTime<Utc>) -> FixedOffset {
+        let minutes = self
+            .rule_for_year(utc.year())
+            .map_or(0, |rule| rule.offset_minutes(&utc.naive_utc()));
+        minutes
+            .checked_mul(60)
+            .and_then(FixedOffset::east_opt)
+            .unwrap_or_else(|| FixedOffset::east_opt(0).unwrap())
+    }
+
+    // Offset from UTC currently observed.
+    pub fn offset_now(&self, clock: &dyn Clock) -> FixedOffset {
+        self.offset_at(&clock.now())
+    }
+
+    // Renders a UTC instant in this time zone.
+    pub fn to_local(&self, utc: &DateTime<Utc>) -> DateTime<FixedOffset> {
+        utc.with_timezone(&self.offset_at(utc))
+    }
+
+    // Converts a wall-clock time of this time zone to UTC.
+    // The offset is looked up twice so that times close to a
+    // transition pick the offset in effect on their side of it.
+    pub fn to_utc(&self, local: &NaiveDateTime) -> DateTime<Utc> {
+        let guess = local.and_utc();
+        let guess = guess - Duration::seconds(self.offset_at(&guess).local_minus_utc() as i64);
+        local.and_utc() - Duration::seconds(self.offset_at(&guess).local_minus_utc() as i64)
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use chrono::{DateTime, NaiveDate, TimeZone as _, Utc};
+
+    use super::{super::decode::DataType, super::storage::Properties, TimeZone, TransitionDate};
+
+    fn push_transition(buff: &mut Vec<u8>, month: u16, day_of_week: u16, day: u16, hour: u16) {
+        for value in [0, month, day_of_week, day, hour, 0, 0, 0].iter() {
+            buff.extend_from_slice(&value.to_le_bytes());
+        }
+    }
+
+    // Eastern time: UTC-5, daylight from the second Sunday of
+    // March to the first Sunday of November, at 2 AM.
+    pub(crate) fn eastern_tz_struct() -> Vec<u8> {
+        let mut buff = vec![];
+        buff.extend_from_slice(&300i32.to_le_bytes());
+        buff.extend_from_slice(&0i32.to_le_bytes());
+        buff.extend_from_slice(&(-60i32).to_le_bytes());
+        buff.extend_from_slice(&[0, 0]);
+        push_transition(&mut buff, 11, 0, 1, 2);
+        buff.extend_from_slice(&[0, 0]);
+        push_transition(&mut buff, 3, 0, 2, 2);
+        buff
+    }
+
+    fn tz_rule(year: u16, bias: i32, standard: (u16, u16), daylight: (u16, u16)) -> Vec<u8> {
+        let mut buff = vec![0x02, 0x01, 0x3E, 0x00];
+        buff.extend_from_slice(&0x0002u16.to_le_bytes());
+        buff.extend_from_slice(&year.to_le_bytes());
+        buff.extend_from_slice(&[0u8; 14]);
+        buff.extend_from_slice(&bias.to_le_bytes());
+        buff.extend_from_slice(&0i32.to_le_bytes());
+        buff.extend_from_slice(&(-60i32).to_le_bytes());
+        push_transition(&mut buff, standard.0, 0, standard.1, 3);
+        push_transition(&mut buff, daylight.0, 0, daylight.1, 2);
+        buff
+    }
+
+    fn tz_definition(key_name: &str, rules: Vec<Vec<u8>>) -> Vec<u8> {
+        let name = key_name.encode_utf16().collect::<Vec<u16>>();
+        let mut buff = vec![0x02, 0x01];
+        buff.extend_from_slice(&(6 + name.len() as u16 * 2).to_le_bytes());
+        buff.extend_from_slice(&0x0002u16.to_le_bytes());
+        buff.extend_from_slice(&(name.len() as u16).to_le_bytes());
+        for unit in name {
+            buff.extend_from_slice(&unit.to_le_bytes());
+        }
+        buff.extend_from_slice(&(rules.len() as u16).to_le_bytes());
+        for rule in rules {
+            buff.extend(rule);
+        }
+        buff
+    }
+
+    fn utc(year: i32, month: u32, day: u32, hour: u32, min: u32) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(year, month, day, hour, min, 0).unwrap()
+    }
+
+    #[test]
+    fn test_transition_resolve() {
+        let second_sunday_of_march = TransitionDate {
+            month: 3,
+            day: 2,
+            hour: 2,
+            ..Default::default()
+        };
+        assert_eq!(
+            second_sunday_of_march.resolve(2021),
+            NaiveDate::from_ymd_opt(2021, 3, 14).unwrap().and_hms_opt(2, 0, 0)
+        );
+        let last_sunday_of_october = TransitionDate {
+            month: 10,
+            day: 5,
+            hour: 3,
+            ..Default::default()
+        };
+        assert_eq!(
+            last_sunday_of_october.resolve(2021),
+            NaiveDate::from_ymd_opt(2021, 10, 31).unwrap().and_hms_opt(3, 0, 0)
+        );
+        assert_eq!(TransitionDate::default().resolve(2021), None);
+    }
+
+    #[test]
+    fn test_tz_struct() {
+        let tz = TimeZone::from_tz_struct(&eastern_tz_struct()).unwrap();
+        assert_eq!(tz.rules.len(), 1);
+        assert_eq!(tz.rules[0].bias, 300);
+        assert_eq!(tz.rules[0].daylight_bias, -60);
+        assert_eq!(tz.rules[0].standard_date.month, 11);
+
+        let winter = tz.to_local(&utc(2021, 1, 15, 12, 0));
+        assert_eq!(winter.to_rfc3339(), "2021-01-15T07:00:00-05:00");
+        let summer = tz.to_local(&utc(2021, 7, 1, 12, 0));
+        assert_eq!(summer.to_rfc3339(), "2021-07-01T08:00:00-04:00");
+        // Daylight time starts at 2021-03-14 07:00 UTC.
+        assert_eq!(tz.offset_at(&utc(2021, 3, 14, 6, 59)).local_minus_utc(), -5 * 3600);
+        assert_eq!(tz.offset_at(&utc(2021, 3, 14, 7, 0)).local_minus_utc(), -4 * 3600);
+        // And ends at 2021-11-07 06:00 UTC.
+        assert_eq!(tz.offset_at(&utc(2021, 11, 7, 5, 59)).local_minus_utc(), -4 * 3600);
+        assert_eq!(tz.offset_at(&utc(2021, 11, 7, 6, 0)).local_minus_utc(), -5 * 3600);
+    }
+
+    #[test]
+    fn test_to_utc() {
+        let tz = TimeZone::from_tz_struct(&eastern_tz_struct()).unwrap();
+        let local = |month, day, hour| {
+            NaiveDate::from_ymd_opt(2021, month, day)
+                .unwrap()
+                .and_hms_opt(hour, 0, 0)
+                .unwrap()
+        };
+        assert_eq!(tz.to_utc(&local(1, 15, 7)), utc(2021, 1, 15, 12, 0));
+        assert_eq!(tz.to_utc(&local(7, 1, 8)), utc(2021, 7, 1, 12, 0));
+        // Right after daylight time starts, and on the day it ends.
+        assert_eq!(tz.to_utc(&local(3, 14, 3)), utc(2021, 3, 14, 7, 0));
+        assert_eq!(tz.to_utc(&local(11, 7, 12)), utc(2021, 11, 7, 17, 0));
+        assert_eq!(TimeZone::default().to_utc(&local(1, 15, 7)), utc(2021, 1, 15, 7, 0));
+    }
+
+    #[test]
+    fn test_tz_struct_too_short() {
+        let err = TimeZone::from_tz_struct(&[0u8; 20]).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "DataTypeError: Malformed blob: TZSTRUCT is 20 bytes long, expected 48"
+        );
+    }
+
+    #[test]
+    fn test_tz_struct_bias_overflow() {
+        let mut buff = eastern_tz_struct();
+        buff[..4].copy_from_slice(&i32::MIN.to_le_bytes());
+        let err = TimeZone::from_tz_struct(&buff).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            format!("DataTypeError: Malformed blob: biases {}, 0 and -60 overflow", i32::MIN)
+        );
+        // Adding up without overflowing, but past an offset
+        // in seconds.
+        buff[..4].copy_from_slice(&(i32::MAX / 30).to_le_bytes());
+        assert!(TimeZone::from_tz_struct(&buff).is_err());
+    }
+
+    #[test]
+    fn test_tz_definition() {
+        // AUS Eastern: UTC+10, daylight from the first Sunday of
+        // October to the first Sunday of April.
+        let buff = tz_definition(
+            "AUS Eastern Standard Time",
+            vec![tz_rule(0, -600, (4, 1), (10, 1))],
+        );
+        let tz = TimeZone::from_tz_definition(&buff).unwrap();
+        assert_eq!(tz.key_name, "AUS Eastern Standard Time".to_string());
+        assert_eq!(tz.rules.len(), 1);
+        assert!(tz.rules[0].effective);
+        assert!(!tz.rules[0].recur_current);
+
+        let summer = tz.to_local(&utc(2021, 1, 15, 12, 0));
+        assert_eq!(summer.to_rfc3339(), "2021-01-15T23:00:00+11:00");
+        let winter = tz.to_local(&utc(2021, 7, 1, 12, 0));
+        assert_eq!(winter.to_rfc3339(), "2021-07-01T22:00:00+10:00");
+    }
+
+    #[test]
+    fn test_tz_definition_rule_per_year() {
+        let buff = tz_definition(
+            "Test Standard Time",
+            vec![
+                tz_rule(2010, -120, (0, 0), (0, 0)),
+                tz_rule(2000, -60, (0, 0), (0, 0)),
+            ],
+        );
+        let tz = TimeZone::from_tz_definition(&buff).unwrap();
+        assert_eq!(tz.offset_at(&utc(1990, 6, 1, 0, 0)).local_minus_utc(), 3600);
+        assert_eq!(tz.offset_at(&utc(2005, 6, 1, 0, 0)).local_minus_utc(), 3600);
+        assert_eq!(tz.offset_at(&utc(2015, 6, 1, 0, 0)).local_minus_utc(), 7200);
+    }
+
+    #[test]
+    fn test_tz_definition_bad_version() {
+        let mut buff = tz_definition("Test Standard Time", vec![]);
+        buff[0] = 0x01;
+        assert!(TimeZone::from_tz_definition(&buff).is_err());
+        // Truncated rule
+        let mut buff = tz_definition("Test Standard Time", vec![tz_rule(0, 0, (0, 0), (0, 0))]);
+        buff.truncate(buff.len() - 10);
+        assert!(TimeZone::from_tz_definition(&buff).is_err());
+    }
+
+    #[test]
+    fn test_create_from_props() {
+        let mut props: Properties = Properties::new();
+        assert_eq!(TimeZone::create_from_props(&props), None);
+
+        props.insert(
+            "TimeZoneStruct".to_string(),
+            DataType::PtypBinary(eastern_tz_struct()),
+        );
+        let tz = TimeZone::create_from_props(&props).unwrap();
+        assert_eq!(tz.key_name, String::new());
+
+        // The time zone definition takes precedence.
+        props.insert(
+            "AppointmentTimeZoneDefinitionStartDisplay".to_string(),
+            DataType::PtypBinary(tz_definition(
+                "AUS Eastern Standard Time",
+                vec![tz_rule(0, -600, (4, 1), (10, 1))],
+            )),
+        );
+        let tz = TimeZone::create_from_props(&props).unwrap();
+        assert_eq!(tz.key_name, "AUS Eastern Standard Time".to_string());
+    }
+}
@@ -0,0 +1,124 @@
+use serde_json::Value;
+
+use super::{json::is_binary_field, outlook::Outlook};
+
+// Type of a value of the schema, binary fields, named by
+// key, being Uint8Array as parseToObject writes them.
+fn to_type(schema: &Value, key: &str) -> String {
+    if let Some(reference) = schema["$ref"].as_str() {
+        return reference.rsplit('/').next().unwrap_or_default().to_string();
+    }
+    if let Some(values) = schema["enum"].as_array() {
+        return union(values.iter().map(|value| value.to_string()));
+    }
+    if let Some(schemas) = schema["anyOf"].as_array().or_else(|| schema["oneOf"].as_array()) {
+        return union(schemas.iter().map(|schema| to_type(schema, key)));
+    }
+    match &schema["type"] {
+        Value::Array(types) => union(types.iter().map(|name| {
+            let mut schema = schema.clone();
+            schema["type"] = name.clone();
+            to_type(&schema, key)
+        })),
+        Value::String(name) => match name.as_str() {
+            "string" if is_binary_field(key) => "Uint8Array".to_string(),
+            "string" => "string".to_string(),
+            "integer" | "number" => "number".to_string(),
+            "boolean" => "boolean".to_string(),
+            "null" => "null".to_string(),
+            "array" => match schema["prefixItems"].as_array() {
+                Some(items) => {
+                    let items: Vec<String> = items.iter().map(|item| to_type(item, key)).collect();
+                    format!("[{}]", items.join(", "))
+                }
+                None => match to_type(&schema["items"], key) {
+                    item if item.contains(' ') => format!("({})[]", item),
+                    item => format!("{}[]", item),
+                },
+            },
+            "object" => match &schema["additionalProperties"] {
+                Value::Object(_) => format!("Record<string, {}>", to_type(&schema["additionalProperties"], key)),
+                _ => format!("{{ {}}}", fields(schema).iter().map(|field| format!("{} ", field)).collect::<String>()),
+            },
+            _ => "unknown".to_string(),
+        },
+        _ => "unknown".to_string(),
+    }
+}
+
+fn union<I: Iterator<Item = String>>(types: I) -> String {
+    types.collect::<Vec<String>>().join(" | ")
+}
+
+// Fields of an object, those it may lack being optional.
+fn fields(schema: &Value) -> Vec<String> {
+    let required = |key: &str| {
+        schema["required"]
+            .as_array()
+            .is_some_and(|keys| keys.iter().any(|required| required == key))
+    };
+    schema["properties"]
+        .as_object()
+        .map(|properties| {
+            properties
+                .iter()
+                .map(|(key, value)| {
+                    let optional = if required(key) { "" } else { "?" };
+                    format!("{}{}: {};", key, optional, to_type(value, key))
+                })
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+// Declaration of a named type, an interface for objects.
+fn declare(name: &str, schema: &Value) -> String {
+    match schema["properties"].is_object() && schema["type"] == "object" {
+        true => {
+            let fields: String = fields(schema).iter().map(|field| format!("  {}\n", field)).collect();
+            format!("export interface {} {{\n{}}}\n", name, fields)
+        }
+        false => format!("export type {} = {};\n", name, to_type(schema, name)),
+    }
+}
+
+impl Outlook {
+    // TypeScript definitions of the objects parseToObject
+    // of the WASM build returns, from the JSON schema, as
+    // schema/outlook.d.ts holds them.
+    pub fn typescript_definitions() -> String {
+        let schema = Self::json_schema();
+        let mut definitions = String::from(
+            "// Types of the objects parseToObject returns, written by\n\
+             // Outlook::typescript_definitions.\n\n",
+        );
+        definitions.push_str(&declare("Outlook", &schema));
+        if let Some(types) = schema["$defs"].as_object() {
+            for (name, schema) in types {
+                definitions.push('\n');
+                definitions.push_str(&declare(name, schema));
+            }
+        }
+        definitions
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::outlook::Outlook;
+
+    #[test]
+    fn test_typescript_definitions() {
+        let definitions = Outlook::typescript_definitions();
+        assert!(definitions.contains("\nexport interface Outlook {\n  appointment?: Appointment | null;\n"));
+        assert!(definitions.contains("\n  attachments: Attachment[];\n"));
+        assert!(definitions.contains("\n  payload: Uint8Array;\n"));
+        assert!(definitions.contains("\nexport type Importance = \"Low\" | \"Normal\" | \"High\";\n"));
+
+        // Definitions changing along with the model need the
+        // file written again.
+        let path = "schema/outlook.d.ts";
+        let written = std::fs::read_to_string(path).unwrap();
+        assert_eq!(definitions, written, "{} is out of date", path);
+    }
+}
@@ -0,0 +1,256 @@
+use base64::{engine::general_purpose::STANDARD, Engine};
+
+use serde::{Deserialize, Serialize};
+
+use super::{
+    contact::{Contact, ContactPhoto},
+    ics::{escape_text, fold_line},
+};
+
+// VCardVersion is the version of the vCard
+// format to render a contact in.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum VCardVersion {
+    V3, // RFC 2426
+    V4, // RFC 6350
+}
+
+impl VCardVersion {
+    fn as_str(&self) -> &'static str {
+        match self {
+            VCardVersion::V3 => "3.0",
+            VCardVersion::V4 => "4.0",
+        }
+    }
+
+    // vCard 3.0 parameter values are customarily upper case,
+    // vCard 4.0 ones lower case.
+    fn param(&self, value: &str) -> String {
+        match self {
+            VCardVersion::V3 => value.to_ascii_uppercase(),
+            VCardVersion::V4 => value.to_ascii_lowercase(),
+        }
+    }
+}
+
+// Telephone types of a PhoneNumber kind.
+// Refer to RFC 2426 3.3.1 and RFC 6350 6.4.1
+fn phone_types(kind: &str, version: VCardVersion) -> Vec<&'static str> {
+    match kind {
+        "Primary" => vec!["voice", "pref"],
+        "Business" | "Business2" | "CompanyMain" => vec!["work", "voice"],
+        "Home" | "Home2" => vec!["home", "voice"],
+        "Mobile" => vec!["cell"],
+        // vCard 4.0 dropped the "car" type.
+        "Car" if version == VCardVersion::V3 => vec!["car"],
+        "Pager" => vec!["pager"],
+        "BusinessFax" => vec!["work", "fax"],
+        "HomeFax" => vec!["home", "fax"],
+        "PrimaryFax" => vec!["fax", "pref"],
+        _ => vec!["voice"],
+    }
+}
+
+impl ContactPhoto {
+    // Outlook saves contact pictures as JPEG
+    // unless told otherwise.
+    fn media_type(&self) -> String {
+        if self.mime_tag.starts_with("image/") {
+            return self.mime_tag.to_ascii_lowercase();
+        }
+        match self.extension.to_ascii_lowercase().trim_start_matches('.') {
+            "png" => "image/png",
+            "gif" => "image/gif",
+            "bmp" => "image/bmp",
+            _ => "image/jpeg",
+        }
+        .to_string()
+    }
+
+    fn line(&self, version: VCardVersion) -> Option<String> {
+        let data = hex::decode(&self.payload).ok().filter(|data| !data.is_empty())?;
+        let media_type = self.media_type();
+        let line = match version {
+            VCardVersion::V3 => format!(
+                "PHOTO;ENCODING=b;TYPE={}:{}",
+                version.param(media_type.trim_start_matches("image/")),
+                STANDARD.encode(data)
+            ),
+            VCardVersion::V4 => format!("PHOTO:data:{};base64,{}", media_type, STANDARD.encode(data)),
+        };
+        Some(line)
+    }
+}
+
+impl Contact {
+    // Formatted name, made up from the name parts
+    // when the display name is missing.
+    fn formatted_name(&self) -> String {
+        if !self.display_name.is_empty() {
+            return self.display_name.clone();
+        }
+        [
+            &self.prefix,
+            &self.given_name,
+            &self.middle_name,
+            &self.surname,
+            &self.suffix,
+        ]
+        .iter()
+        .filter(|part| !part.is_empty())
+        .map(|part| part.as_str())
+        .collect::<Vec<&str>>()
+        .join(" ")
+    }
+
+    fn vcard_lines(&self, version: VCardVersion) -> Vec<String> {
+        let mut lines = vec![
+            "BEGIN:VCARD".to_string(),
+            format!("VERSION:{}", version.as_str()),
+            format!("FN:{}", escape_text(&self.formatted_name())),
+            format!(
+                "N:{};{};{};{};{}",
+                escape_text(&self.surname),
+                escape_text(&self.given_name),
+                escape_text(&self.middle_name),
+                escape_text(&self.prefix),
+                escape_text(&self.suffix)
+            ),
+        ];
+        if !self.nickname.is_empty() {
+            lines.push(format!("NICKNAME:{}", escape_text(&self.nickname)));
+        }
+        if !self.company.is_empty() || !self.department.is_empty() {
+            lines.push(format!(
+                "ORG:{};{}",
+                escape_text(&self.company),
+                escape_text(&self.department)
+            ));
+        }
+        if !self.job_title.is_empty() {
+            lines.push(format!("TITLE:{}", escape_text(&self.job_title)));
+        }
+        for phone in &self.phone_numbers {
+            let types = phone_types(&phone.kind, version)
+                .iter()
+                .map(|t| version.param(t))
+                .collect::<Vec<String>>()
+                .join(",");
+            lines.push(format!("TEL;TYPE={}:{}", types, escape_text(&phone.number)));
+        }
+        for person in &self.emails {
+            let line = match version {
+                VCardVersion::V3 => format!("EMAIL;TYPE=INTERNET:{}", escape_text(&person.email)),
+                VCardVersion::V4 => format!("EMAIL:{}", escape_text(&person.email)),
+            };
+            lines.push(line);
+        }
+        if let Some(line) = self.photo.as_ref().and_then(|photo| photo.line(version)) {
+            lines.push(line);
+        }
+        lines.push("END:VCARD".to_string());
+        lines
+    }
+
+    // Renders the contact as a vCard 4.0.
+    pub fn to_vcard(&self) -> String {
+        self.to_vcard_with_version(VCardVersion::V4)
+    }
+
+    pub fn to_vcard_with_version(&self, version: VCardVersion) -> String {
+        self.vcard_lines(version).iter().map(|line| fold_line(line)).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        super::{
+            contact::{ContactPhoto, PhoneNumber},
+            outlook::Person,
+        },
+        Contact, VCardVersion,
+    };
+
+    fn contact() -> Contact {
+        Contact {
+            display_name: "Jane Doe".to_string(),
+            prefix: String::new(),
+            given_name: "Jane".to_string(),
+            middle_name: String::new(),
+            surname: "Doe".to_string(),
+            suffix: String::new(),
+            nickname: String::new(),
+            company: "Contoso, Ltd.".to_string(),
+            job_title: "Engineer".to_string(),
+            department: String::new(),
+            phone_numbers: vec![
+                PhoneNumber {
+                    kind: "Mobile".to_string(),
+                    number: "+1 555 0100".to_string(),
+                },
+                PhoneNumber {
+                    kind: "Car".to_string(),
+                    number: "+1 555 0101".to_string(),
+                },
+            ],
+            emails: vec![Person::new("Jane".to_string(), "jane@contoso.com".to_string())],
+            photo: Some(ContactPhoto {
+                mime_tag: String::new(),
+                extension: ".png".to_string(),
+                payload: "89504e47".to_string(),
+            }),
+        }
+    }
+
+    #[test]
+    fn test_to_vcard() {
+        assert_eq!(
+            contact().to_vcard(),
+            "BEGIN:VCARD\r\n\
+             VERSION:4.0\r\n\
+             FN:Jane Doe\r\n\
+             N:Doe;Jane;;;\r\n\
+             ORG:Contoso\\, Ltd.;\r\n\
+             TITLE:Engineer\r\n\
+             TEL;TYPE=cell:+1 555 0100\r\n\
+             TEL;TYPE=voice:+1 555 0101\r\n\
+             EMAIL:jane@contoso.com\r\n\
+             PHOTO:data:image/png;base64,iVBORw==\r\n\
+             END:VCARD\r\n"
+        );
+    }
+
+    #[test]
+    fn test_to_vcard_v3() {
+        let mut contact = contact();
+        contact.display_name = String::new();
+        contact.prefix = "Dr.".to_string();
+        assert_eq!(
+            contact.to_vcard_with_version(VCardVersion::V3),
+            "BEGIN:VCARD\r\n\
+             VERSION:3.0\r\n\
+             FN:Dr. Jane Doe\r\n\
+             N:Doe;Jane;;Dr.;\r\n\
+             ORG:Contoso\\, Ltd.;\r\n\
+             TITLE:Engineer\r\n\
+             TEL;TYPE=CELL:+1 555 0100\r\n\
+             TEL;TYPE=CAR:+1 555 0101\r\n\
+             EMAIL;TYPE=INTERNET:jane@contoso.com\r\n\
+             PHOTO;ENCODING=b;TYPE=PNG:iVBORw==\r\n\
+             END:VCARD\r\n"
+        );
+    }
+
+    #[test]
+    fn test_photo_folded() {
+        let mut contact = contact();
+        contact.photo.as_mut().unwrap().payload = "00".repeat(120);
+        let vcard = contact.to_vcard();
+        assert!(vcard.contains("PHOTO:data:image/png;base64,AAAA"));
+        assert!(vcard.lines().all(|line| line.len() <= 75));
+
+        contact.photo = None;
+        assert!(!contact.to_vcard().contains("PHOTO"));
+    }
+}
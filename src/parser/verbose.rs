@@ -0,0 +1,228 @@
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Map, Value};
+
+use super::{
+    constants::PropEnumMap,
+    decode::DataType,
+    error::Error,
+    outlook::Outlook,
+    storage::{Properties, Storages},
+};
+
+// MessageProperties keeps every decoded property of the
+// message, its recipients and its attachments. In
+// Outlook::properties, the data of attachments and the
+// messages embedded in them are left out, Attachment::payload
+// and Attachment::embedded holding them already.
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MessageProperties {
+    pub root: Properties,
+    pub recipients: Vec<Properties>,
+    pub attachments: Vec<Properties>,
+    pub embedded: Vec<Option<MessageProperties>>, // Message embedded in each attachment, by index
+}
+
+impl MessageProperties {
+    pub(crate) fn create(storages: &Storages) -> Self {
+        Self {
+            root: storages.root.clone(),
+            recipients: storages.recipients.clone(),
+            attachments: storages
+                .attachments
+                .iter()
+                .map(|props| {
+                    props
+                        .iter()
+                        .filter(|(key, value)| !Self::is_attachment_data(key, value))
+                        .map(|(key, value)| (key.clone(), value.clone()))
+                        .collect()
+                })
+                .collect(),
+            embedded: vec![],
+        }
+    }
+
+    fn is_attachment_data(key: &str, value: &DataType) -> bool {
+        key == "AttachDataObject" && matches!(value, DataType::PtypBinary(_))
+    }
+
+    // Numbers and booleans are kept as such, enumerated
+    // values get their symbolic name alongside.
+    fn value_to_json(enums: &PropEnumMap, key: &str, value: &DataType) -> Value {
+        match value {
+            DataType::PtypBoolean(value) => json!(value),
+            DataType::PtypMultipleString(strings) => json!(strings),
+            DataType::PtypMultipleBinary(values) => {
+                json!(values.iter().map(hex::encode).collect::<Vec<String>>())
+            }
+            _ => match value.as_i64() {
+                Some(number) if enums.is_enum(key) => json!({
+                    "value": number,
+                    "name": enums.get_symbolic_name(key, number),
+                }),
+                Some(number) => json!(number),
+                None => json!(String::from(value)),
+            },
+        }
+    }
+
+    fn props_to_json(enums: &PropEnumMap, props: &Properties) -> Value {
+        let map = props
+            .iter()
+            // Attachment data is already in Outlook::attachments.
+            .filter(|(key, _)| key.as_str() != "AttachDataObject")
+            .map(|(key, value)| (key.to_string(), Self::value_to_json(enums, key, value)))
+            .collect::<Map<String, Value>>();
+        Value::Object(map)
+    }
+
+    fn to_json_value(&self) -> Value {
+        let enums = PropEnumMap::init();
+        let list = |props: &Vec<Properties>| {
+            props
+                .iter()
+                .map(|props| Self::props_to_json(&enums, props))
+                .collect::<Vec<Value>>()
+        };
+        json!({
+            "message": Self::props_to_json(&enums, &self.root),
+            "recipients": list(&self.recipients),
+            "attachments": list(&self.attachments),
+        })
+    }
+}
+
+impl Outlook {
+    // Properties of the message with the data of its
+    // attachments and the messages embedded in them put
+    // back, for writing it.
+    pub(crate) fn message_properties(&self) -> MessageProperties {
+        let mut properties = self.properties.clone();
+        for (props, attachment) in properties.attachments.iter_mut().zip(self.attachments.iter()) {
+            if !attachment.payload.is_empty() && !props.contains_key("AttachDataObject") {
+                let data = hex::decode(&attachment.payload).unwrap_or_default();
+                props.insert("AttachDataObject".to_string(), DataType::PtypBinary(data));
+            }
+        }
+        properties.embedded = self
+            .attachments
+            .iter()
+            .map(|attachment| attachment.embedded.as_ref().map(|message| message.message_properties()))
+            .collect();
+        properties
+    }
+
+    // Same as to_json with every decoded property added
+    // under "properties".
+    pub fn to_json_verbose(&self) -> Result<String, Error> {
+        let mut value = serde_json::to_value(self)?;
+        if let Value::Object(ref mut map) = value {
+            map.insert("properties".to_string(), self.properties.to_json_value());
+        }
+        Ok(serde_json::to_string(&value)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::{json, Value};
+
+    use super::{
+        super::{constants::PropEnumMap, decode::DataType},
+        MessageProperties, Outlook,
+    };
+
+    #[test]
+    fn test_symbolic_names() {
+        let enums = PropEnumMap::init();
+        assert_eq!(enums.get_symbolic_name("Importance", 2), Some("High".to_string()));
+        assert_eq!(enums.get_symbolic_name("Priority", -1), Some("NonUrgent".to_string()));
+        assert_eq!(enums.get_symbolic_name("Importance", 7), None);
+        assert_eq!(enums.get_symbolic_name("Subject", 0), None);
+        assert_eq!(
+            enums.get_symbolic_name("MessageStatus", 0x1800),
+            Some("InConflict | RemoteDownload".to_string())
+        );
+        assert_eq!(
+            enums.get_symbolic_name("MessageStatus", 0x1001),
+            Some("0x00000001 | RemoteDownload".to_string())
+        );
+        assert_eq!(enums.get_symbolic_name("MessageStatus", 0), None);
+        assert_eq!(
+            enums.get_symbolic_name("MessageFlags", 0x29),
+            Some("Read | Unsent | FromMe".to_string())
+        );
+        assert!(enums.is_enum("AttachMethod"));
+        assert!(!enums.is_enum("MessageSize"));
+    }
+
+    #[test]
+    fn test_value_to_json() {
+        let enums = PropEnumMap::init();
+        let value = |key: &str, data: DataType| MessageProperties::value_to_json(&enums, key, &data);
+        assert_eq!(
+            value("Sensitivity", DataType::PtypInteger32(3)),
+            json!({"value": 3, "name": "Confidential"})
+        );
+        assert_eq!(
+            value("Importance", DataType::PtypInteger32(9)),
+            json!({"value": 9, "name": null})
+        );
+        assert_eq!(value("MessageSize", DataType::PtypInteger32(9)), json!(9));
+        assert_eq!(value("HasAttachments", DataType::PtypBoolean(true)), json!(true));
+        assert_eq!(
+            value("KeyPhrases", DataType::PtypMultipleString(vec!["a".to_string()])),
+            json!(["a"])
+        );
+        assert_eq!(
+            value("Subject", DataType::PtypString("Hi".to_string())),
+            json!("Hi")
+        );
+    }
+
+    #[test]
+    fn test_to_json_verbose() {
+        let outlook = Outlook::from_path("data/attachment.msg").unwrap();
+        let json: Value = serde_json::from_str(&outlook.to_json_verbose().unwrap()).unwrap();
+        assert_eq!(json["subject"], json!(outlook.subject));
+        let properties = &json["properties"];
+        assert_eq!(
+            properties["message"]["Importance"],
+            json!({"value": 1, "name": "Normal"})
+        );
+        assert_eq!(
+            properties["attachments"][0]["AttachMethod"],
+            json!({"value": 1, "name": "ByValue"})
+        );
+        assert_eq!(properties["attachments"][0]["AttachDataObject"], Value::Null);
+        assert_eq!(
+            properties["recipients"][0]["RecipientType"],
+            json!({"value": 1, "name": "To"})
+        );
+    }
+
+    #[test]
+    fn test_serde() {
+        let outlook = Outlook::from_path("data/test_email.msg").unwrap();
+        let json = serde_json::to_string(&outlook.properties).unwrap();
+        let properties: MessageProperties = serde_json::from_str(&json).unwrap();
+        assert_eq!(properties, outlook.properties);
+    }
+
+    #[test]
+    fn test_attachment_data_kept_once() {
+        let outlook = Outlook::from_path("data/attachment.msg").unwrap();
+        assert!(outlook
+            .properties
+            .attachments
+            .iter()
+            .all(|props| !props.contains_key("AttachDataObject")));
+        let properties = outlook.message_properties();
+        for (props, attachment) in properties.attachments.iter().zip(outlook.attachments.iter()) {
+            assert_eq!(
+                props.get("AttachDataObject"),
+                Some(&DataType::PtypBinary(hex::decode(&attachment.payload).unwrap()))
+            );
+        }
+    }
+}
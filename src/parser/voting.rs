@@ -0,0 +1,177 @@
+use serde::{Deserialize, Serialize};
+
+use super::{blob::BlobReader, decode::DataType, error::DataTypeError, storage::Properties};
+
+// Versions of the two parts of a verb stream.
+const VERB_STREAM_VERSION: u16 = 0x0102;
+const VERB_STREAM_EXTRAS_VERSION: u16 = 0x0104;
+
+// Verbs of this type are voting options, the others
+// being the reply and forward actions of the form.
+const VOTING_OPTION_VERB: u32 = 0x0000_0004;
+
+// Voting holds the voting buttons of a message, or the
+// option a reply to such a message votes for.
+// Refer to MS-OXOMSG 2.2.1.73 and 2.2.1.74
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct Voting {
+    pub options: Vec<String>,     // "VerbStream"
+    pub response: Option<String>, // "VerbResponse"
+}
+
+// Reads a string prefixed with its length in bytes.
+fn read_ansi(reader: &mut BlobReader) -> Result<String, DataTypeError> {
+    let len = reader.read_u8()? as usize;
+    Ok(reader.read_bytes(len)?.iter().map(|&b| b as char).collect())
+}
+
+// Reads a string prefixed with its length in characters.
+fn read_unicode(reader: &mut BlobReader) -> Result<String, DataTypeError> {
+    let len = reader.read_u8()? as usize;
+    reader.read_utf16(len)
+}
+
+impl Voting {
+    // Labels of the voting options of a verb stream.
+    pub fn parse_verb_stream(buff: &[u8]) -> Result<Vec<String>, DataTypeError> {
+        let mut reader = BlobReader::new(buff);
+        let version = reader.read_u16()?;
+        if version != VERB_STREAM_VERSION {
+            return Err(DataTypeError::MalformedBlob(format!(
+                "verb stream version 0x{:04X}",
+                version
+            )));
+        }
+        let count = reader.read_u32()?;
+        let mut verbs = vec![];
+        for _ in 0..count {
+            let verb_type = reader.read_u32()?;
+            let display_name = read_ansi(&mut reader)?;
+            // Message class, an unused string and the display name again
+            for _ in 0..3 {
+                read_ansi(&mut reader)?;
+            }
+            // Flags, send behavior and the verb ID
+            reader.skip(29)?;
+            verbs.push((verb_type, display_name));
+        }
+        // Unicode names follow in the same order, when they were written.
+        if reader.remaining() >= 2 && reader.read_u16()? == VERB_STREAM_EXTRAS_VERSION {
+            for (_, display_name) in verbs.iter_mut() {
+                *display_name = read_unicode(&mut reader)?;
+                read_unicode(&mut reader)?;
+            }
+        }
+        Ok(verbs
+            .into_iter()
+            .filter(|(verb_type, _)| *verb_type == VOTING_OPTION_VERB)
+            .map(|(_, display_name)| display_name)
+            .collect())
+    }
+
+    // Returns None when the message neither has voting
+    // buttons nor votes.
+    pub fn create_from_props(props: &Properties) -> Option<Self> {
+        let options = props
+            .get("VerbStream")
+            .and_then(DataType::as_bytes)
+            .and_then(|buff| Self::parse_verb_stream(buff).ok())
+            .unwrap_or_default();
+        let response = props
+            .get("VerbResponse")
+            .and_then(DataType::as_str)
+            .filter(|response| !response.is_empty())
+            .map(String::from);
+        if options.is_empty() && response.is_none() {
+            return None;
+        }
+        Some(Self { options, response })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        super::{decode::DataType, outlook::Outlook, storage::Properties},
+        Voting,
+    };
+
+    fn verb(buff: &mut Vec<u8>, verb_type: u32, name: &str, id: u32) {
+        buff.extend(verb_type.to_le_bytes());
+        buff.push(name.len() as u8);
+        buff.extend(name.as_bytes());
+        buff.extend([8]);
+        buff.extend(b"IPM.Note");
+        buff.push(0);
+        buff.push(name.len() as u8);
+        buff.extend(name.as_bytes());
+        buff.extend([0u8; 4 + 1 + 4 + 4 + 4 + 4]);
+        buff.extend(id.to_le_bytes());
+        buff.extend([0xFF, 0xFF, 0xFF, 0xFF]);
+    }
+
+    fn unicode(buff: &mut Vec<u8>, name: &str) {
+        for _ in 0..2 {
+            buff.push(name.encode_utf16().count() as u8);
+            buff.extend(name.encode_utf16().flat_map(u16::to_le_bytes));
+        }
+    }
+
+    fn verb_stream(extras: bool) -> Vec<u8> {
+        let mut buff = vec![0x02, 0x01];
+        buff.extend(3u32.to_le_bytes());
+        verb(&mut buff, 1, "Reply", 102);
+        verb(&mut buff, 4, "Approve", 1);
+        verb(&mut buff, 4, "Reject", 2);
+        if extras {
+            buff.extend([0x04, 0x01]);
+            unicode(&mut buff, "Reply");
+            unicode(&mut buff, "Approuvé");
+            unicode(&mut buff, "Rejeté");
+        }
+        buff
+    }
+
+    #[test]
+    fn test_parse_verb_stream() {
+        assert_eq!(
+            Voting::parse_verb_stream(&verb_stream(false)).unwrap(),
+            vec!["Approve".to_string(), "Reject".to_string()]
+        );
+        assert_eq!(
+            Voting::parse_verb_stream(&verb_stream(true)).unwrap(),
+            vec!["Approuvé".to_string(), "Rejeté".to_string()]
+        );
+        let buff = verb_stream(false);
+        assert!(Voting::parse_verb_stream(&buff[..buff.len() - 1]).is_err());
+        assert!(Voting::parse_verb_stream(&[0x04, 0x01, 0, 0, 0, 0]).is_err());
+    }
+
+    #[test]
+    fn test_create_voting() {
+        let mut props = Properties::new();
+        assert_eq!(Voting::create_from_props(&props), None);
+        props.insert("VerbStream".to_string(), DataType::PtypBinary(verb_stream(false)));
+        assert_eq!(
+            Voting::create_from_props(&props),
+            Some(Voting {
+                options: vec!["Approve".to_string(), "Reject".to_string()],
+                response: None,
+            })
+        );
+
+        let mut props = Properties::new();
+        props.insert(
+            "VerbResponse".to_string(),
+            DataType::PtypString("Approve".to_string()),
+        );
+        assert_eq!(
+            Voting::create_from_props(&props).unwrap().response,
+            Some("Approve".to_string())
+        );
+
+        let outlook = Outlook::from_path("data/test_email.msg").unwrap();
+        assert_eq!(outlook.voting, None);
+    }
+}
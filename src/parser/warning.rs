@@ -0,0 +1,182 @@
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+// SkipReason tells why a stream was not decoded.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum SkipReason {
+    Options,    // Left out by ParserOptions, e.g. bodies or attachments
+    UnknownName, // Not named as a property stream
+}
+
+// Warning is something parsing a message dropped, without
+// failing, from its streams.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum Warning {
+    // Stream left undecoded.
+    SkippedStream { stream: String, reason: SkipReason },
+    // Property id neither MS-OXPROPS nor the named
+    // properties of the message give a name to.
+    UnknownProperty { stream: String, id: String },
+    // Property type code the decoder does not know.
+    UnknownTypeCode { stream: String, property: String, code: String },
+    // Property whose value could not be decoded.
+    DecodeFailed { stream: String, property: String, error: String },
+    // Stream whose sectors could not be read, wholly or
+    // past the end of a truncated file, see ParseMode::Salvage.
+    ReadFailed { stream: String, error: String },
+}
+
+impl Warning {
+    // Path of the stream, from the root of the message.
+    pub fn stream(&self) -> &str {
+        match self {
+            Warning::SkippedStream { stream, .. }
+            | Warning::UnknownProperty { stream, .. }
+            | Warning::UnknownTypeCode { stream, .. }
+            | Warning::DecodeFailed { stream, .. }
+            | Warning::ReadFailed { stream, .. } => stream,
+        }
+    }
+
+    pub(crate) fn with_stream(mut self, path: String) -> Self {
+        match &mut self {
+            Warning::SkippedStream { stream, .. }
+            | Warning::UnknownProperty { stream, .. }
+            | Warning::UnknownTypeCode { stream, .. }
+            | Warning::DecodeFailed { stream, .. }
+            | Warning::ReadFailed { stream, .. } => *stream = path,
+        }
+        self
+    }
+}
+
+impl fmt::Display for Warning {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Warning::SkippedStream { stream, reason } => {
+                let reason = match reason {
+                    SkipReason::Options => "left out by the options",
+                    SkipReason::UnknownName => "not a property stream",
+                };
+                write!(f, "{}: skipped, {}", stream, reason)
+            }
+            Warning::UnknownProperty { stream, id } => write!(f, "{}: unknown property {}", stream, id),
+            Warning::UnknownTypeCode { stream, property, code } => {
+                write!(f, "{}: unknown type code {} of {}", stream, code, property)
+            }
+            Warning::DecodeFailed { stream, property, error } => {
+                write!(f, "{}: {} not decoded, {}", stream, property, error)
+            }
+            Warning::ReadFailed { stream, error } => write!(f, "{}: not read, {}", stream, error),
+        }
+    }
+}
+
+// ParseReport lists what parsing a message dropped, for
+// users who need to know what the Outlook lacks.
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ParseReport {
+    pub warnings: Vec<Warning>, // In the order of the streams
+    pub salvaged: bool,         // The file was damaged, see ParseMode::Salvage
+}
+
+impl ParseReport {
+    pub fn is_empty(&self) -> bool {
+        self.warnings.is_empty() && !self.salvaged
+    }
+
+    // Streams which could not be read, the message lacking
+    // what they hold, wholly or in part.
+    pub fn errors(&self) -> impl Iterator<Item = &Warning> {
+        self.warnings
+            .iter()
+            .filter(|warning| matches!(warning, Warning::ReadFailed { .. }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        super::{mode::ParseMode, options::ParserOptions, outlook::Outlook},
+        SkipReason, Warning,
+    };
+
+    #[test]
+    fn test_display() {
+        let warning = Warning::UnknownTypeCode {
+            stream: "__attach_version1.0_#00000000/__substg1.0_37010048".to_string(),
+            property: "AttachDataObject".to_string(),
+            code: "0x0048".to_string(),
+        };
+        assert_eq!(
+            warning.to_string(),
+            "__attach_version1.0_#00000000/__substg1.0_37010048: unknown type code 0x0048 of AttachDataObject"
+                .to_string()
+        );
+        assert_eq!(warning.stream(), "__attach_version1.0_#00000000/__substg1.0_37010048");
+    }
+
+    #[test]
+    fn test_parse_report() {
+        let path = "data/attachment.msg";
+        let outlook = Outlook::from_path(path).unwrap();
+        assert!(outlook
+            .report
+            .warnings
+            .iter()
+            .all(|warning| !matches!(warning, Warning::SkippedStream { .. })));
+        // GUIDs are not decoded.
+        assert!(outlook.report.warnings.contains(&Warning::UnknownTypeCode {
+            stream: "__substg1.0_80090048".to_string(),
+            property: "BigFunnelCorrelationId".to_string(),
+            code: "0x0048".to_string(),
+        }));
+
+        let options = ParserOptions::new().attachments(false).bodies(false);
+        let outlook = Outlook::from_path_with(path, &options).unwrap();
+        let skipped: Vec<&str> = outlook
+            .report
+            .warnings
+            .iter()
+            .filter(|warning| {
+                matches!(
+                    warning,
+                    Warning::SkippedStream {
+                        reason: SkipReason::Options,
+                        ..
+                    }
+                )
+            })
+            .map(Warning::stream)
+            .collect();
+        assert!(skipped.contains(&"__substg1.0_1000001F"));
+        assert!(skipped.contains(&"__attach_version1.0_#00000000/__substg1.0_37010102"));
+        assert_eq!(outlook.report.errors().count(), 0);
+    }
+
+    #[test]
+    fn test_read_failed() {
+        let path = "data/attachment.msg";
+        let bytes = std::fs::read(path).unwrap();
+        let outlook = Outlook::from_slice(&bytes).unwrap();
+
+        // Half of the file is lost, and so the streams stored
+        // in its sectors, wholly or in part.
+        let truncated = &bytes[..bytes.len() / 2];
+        let options = ParserOptions::new().mode(ParseMode::Salvage);
+        let partial = Outlook::from_slice_with(truncated, &options).unwrap();
+        assert_eq!(partial.subject, outlook.subject);
+        assert_eq!(partial.body, outlook.body);
+        let data = "__attach_version1.0_#00000001/__substg1.0_37010102";
+        let error = partial.report.errors().find(|warning| warning.stream() == data).unwrap();
+        assert_eq!(error.to_string(), format!("{}: not read, 18944 of 86993 bytes read", data));
+        let image = partial
+            .attachments
+            .iter()
+            .find(|attachment| attachment.display_name == "image001.png")
+            .unwrap();
+        assert!(outlook.attachments[1].payload.starts_with(&image.payload));
+        assert!(partial.report.errors().all(|warning| matches!(warning, Warning::ReadFailed { .. })));
+    }
+}
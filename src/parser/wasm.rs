@@ -0,0 +1,269 @@
+use serde::Serialize;
+use std::io::{self, Read, Seek, SeekFrom};
+use wasm_bindgen::prelude::*;
+
+use crate::ole::constants::IDENTIFIER;
+
+use super::{
+    error::Error,
+    js::{ErrorInfo, JsModel},
+    options::ParserOptions,
+    outlook::Outlook,
+};
+
+// Types of the objects parseToObject returns, as
+// Outlook::typescript_definitions writes them.
+#[wasm_bindgen(typescript_custom_section)]
+const TYPESCRIPT: &'static str = include_str!("../../schema/outlook.d.ts");
+
+#[wasm_bindgen(typescript_custom_section)]
+const ATTACHMENT_INFO: &'static str = r#"
+export interface AttachmentInfo {
+  index: number;
+  file_name: string;
+  mime_tag: string;
+  size: number;
+  embedded: boolean;
+}
+"#;
+
+#[wasm_bindgen(typescript_custom_section)]
+const PARSE_ERROR: &'static str = r#"
+export type ParseErrorKind =
+  "not_ole" | "truncated" | "corrupt" | "incomplete" | "limit_exceeded" | "io" | "encode";
+
+// Properties of the Error the bindings throw on failure.
+export interface ParseError extends Error {
+  code: number;
+  kind: ParseErrorKind;
+  path?: string;
+  tag?: number;
+  offset?: number;
+}
+"#;
+
+// Error with the message, and the other fields of info
+// as properties of it.
+fn to_js_error(info: ErrorInfo) -> JsValue {
+    let error = js_sys::Error::new(&info.message);
+    if let Ok(properties) = info.serialize(&serde_wasm_bindgen::Serializer::json_compatible()) {
+        js_sys::Object::assign(&error, &properties.into());
+    }
+    error.into()
+}
+
+// Parses a message, failures being thrown as ParseError.
+fn parse(slice: &[u8]) -> Result<Outlook, JsValue> {
+    Outlook::from_slice(slice).map_err(|err| to_js_error(ErrorInfo::new(&err, slice)))
+}
+
+// Parses a message into a JavaScript object holding the
+// fields of Outlook::to_json, binary ones, e.g. the data
+// of attachments, being Uint8Array rather than hex.
+#[wasm_bindgen(js_name = parseToObject, unchecked_return_type = "Outlook")]
+pub fn parse_to_object(slice: &[u8]) -> Result<JsValue, JsValue> {
+    to_object(&parse(slice)?)
+}
+
+fn to_object(outlook: &Outlook) -> Result<JsValue, JsValue> {
+    let value = serde_json::to_value(outlook).map_err(|err| JsError::new(&err.to_string()))?;
+    JsModel(&value)
+        .serialize(&serde_wasm_bindgen::Serializer::json_compatible().serialize_bytes_as_arrays(false))
+        .map_err(|err| JsError::new(&err.to_string()).into())
+}
+
+// Same as parseToObject, as the JSON of Outlook::to_json.
+#[wasm_bindgen(js_name = jsonFromUint8Array)]
+pub fn json_from_uint8_array(slice: &[u8]) -> Result<String, JsValue> {
+    parse(slice)?.to_json().map_err(|err| JsError::new(&err.to_string()).into())
+}
+
+// Message keeps a parsed message on the JavaScript side,
+// its attachments being fetched one at a time rather than
+// all of them written into an object.
+#[wasm_bindgen]
+pub struct Message {
+    outlook: Outlook,
+}
+
+#[wasm_bindgen]
+impl Message {
+    #[wasm_bindgen(constructor)]
+    pub fn new(slice: &[u8]) -> Result<Message, JsValue> {
+        Ok(Self { outlook: parse(slice)? })
+    }
+
+    // Same object as parseToObject returns.
+    #[wasm_bindgen(js_name = toObject, unchecked_return_type = "Outlook")]
+    pub fn to_object(&self) -> Result<JsValue, JsValue> {
+        to_object(&self.outlook)
+    }
+
+    // Index, name, MIME type, size and whether it is an
+    // embedded message of each attachment.
+    #[wasm_bindgen(unchecked_return_type = "AttachmentInfo[]")]
+    pub fn attachments(&self) -> Result<JsValue, JsError> {
+        self.outlook.attachment_infos()
+            .serialize(&serde_wasm_bindgen::Serializer::json_compatible())
+            .map_err(|err| JsError::new(&err.to_string()))
+    }
+
+    // Data of the attachment at index, as a Uint8Array.
+    pub fn attachment(&self, index: usize) -> Result<Vec<u8>, JsError> {
+        self.outlook.attachment_bytes(index).map_err(|err| JsError::new(&err))
+    }
+}
+
+// ChunkReader reads a file pushed in chunks, e.g. those of
+// a ReadableStream, as one, without copying them into one
+// contiguous buffer.
+#[derive(Debug, Default)]
+struct ChunkReader {
+    chunks: Vec<Vec<u8>>,
+    starts: Vec<u64>, // Offset of each chunk in the file
+    len: u64,
+    position: u64,
+}
+
+impl ChunkReader {
+    fn push(&mut self, chunk: Vec<u8>) {
+        if chunk.is_empty() {
+            return;
+        }
+        self.starts.push(self.len);
+        self.len += chunk.len() as u64;
+        self.chunks.push(chunk);
+    }
+
+    // Copies the bytes from offset into buf, as many as the
+    // file holds.
+    fn read_at(&self, offset: u64, buf: &mut [u8]) -> usize {
+        let mut index = self.starts.partition_point(|&start| start <= offset).saturating_sub(1);
+        let mut offset = offset;
+        let mut read = 0;
+        while read < buf.len() && index < self.chunks.len() && offset < self.len {
+            let chunk = &self.chunks[index][(offset - self.starts[index]) as usize..];
+            let size = chunk.len().min(buf.len() - read);
+            buf[read..read + size].copy_from_slice(&chunk[..size]);
+            read += size;
+            offset += size as u64;
+            index += 1;
+        }
+        read
+    }
+}
+
+impl Read for ChunkReader {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let read = self.read_at(self.position, buf);
+        self.position += read as u64;
+        Ok(read)
+    }
+}
+
+impl Seek for ChunkReader {
+    fn seek(&mut self, position: SeekFrom) -> io::Result<u64> {
+        let position = match position {
+            SeekFrom::Start(offset) => Some(offset),
+            SeekFrom::End(offset) => self.len.checked_add_signed(offset),
+            SeekFrom::Current(offset) => self.position.checked_add_signed(offset),
+        };
+        self.position = position.ok_or_else(|| {
+            io::Error::new(io::ErrorKind::InvalidInput, "seek before the start of the file")
+        })?;
+        Ok(self.position)
+    }
+}
+
+// MessageReader parses a message pushed in chunks, e.g.
+// those of File.stream(), sectors being read from them as
+// they are needed, so that a large file is never one
+// Uint8Array in WASM memory.
+#[wasm_bindgen]
+#[derive(Default)]
+pub struct MessageReader {
+    reader: ChunkReader,
+}
+
+impl MessageReader {
+    fn parse(self, attachments: bool) -> Result<Outlook, (Error, Vec<u8>)> {
+        let mut head = vec![0u8; IDENTIFIER.len()];
+        let read = self.reader.read_at(0, &mut head);
+        head.truncate(read);
+        let options = ParserOptions::new().attachments(attachments);
+        Outlook::from_reader_with(self.reader, &options).map_err(|err| (err, head))
+    }
+}
+
+#[wasm_bindgen]
+impl MessageReader {
+    #[wasm_bindgen(constructor)]
+    pub fn new() -> MessageReader {
+        Self::default()
+    }
+
+    // Adds the next chunk of the file.
+    pub fn push(&mut self, chunk: Vec<u8>) {
+        self.reader.push(chunk);
+    }
+
+    // Bytes pushed so far.
+    #[wasm_bindgen(getter)]
+    pub fn len(&self) -> f64 {
+        self.reader.len as f64
+    }
+
+    #[wasm_bindgen(js_name = isEmpty)]
+    pub fn is_empty(&self) -> bool {
+        self.reader.len == 0
+    }
+
+    // Parses the chunks pushed, which are freed afterwards.
+    // Attachment data is left out unless asked for, e.g. by
+    // a previewer listing attachments only.
+    pub fn finish(self, attachments: bool) -> Result<Message, JsValue> {
+        self.parse(attachments)
+            .map(|outlook| Message { outlook })
+            .map_err(|(err, head)| to_js_error(ErrorInfo::new(&err, &head)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{super::outlook::Outlook, ChunkReader, ErrorInfo, MessageReader};
+
+    #[test]
+    fn test_chunk_reader() {
+        use std::io::{Read, Seek, SeekFrom};
+
+        let mut reader = ChunkReader::default();
+        for chunk in [&b"abc"[..], b"", b"d", b"efgh"] {
+            reader.push(chunk.to_vec());
+        }
+        let mut buf = [0u8; 5];
+        assert_eq!(reader.seek(SeekFrom::Start(2)).unwrap(), 2);
+        assert_eq!(reader.read(&mut buf).unwrap(), 5);
+        assert_eq!(&buf, b"cdefg");
+        assert_eq!(reader.read(&mut buf).unwrap(), 1);
+        assert_eq!(reader.read(&mut buf).unwrap(), 0);
+        assert_eq!(reader.seek(SeekFrom::End(-8)).unwrap(), 0);
+        assert!(reader.seek(SeekFrom::Current(-1)).is_err());
+    }
+
+    #[test]
+    fn test_message_reader() {
+        let bytes = std::fs::read("data/attachment.msg").unwrap();
+        let mut reader = MessageReader::new();
+        for chunk in bytes.chunks(1000) {
+            reader.push(chunk.to_vec());
+        }
+        assert_eq!(reader.len(), bytes.len() as f64);
+        let outlook = reader.parse(true).unwrap();
+        assert_eq!(outlook, Outlook::from_slice(&bytes).unwrap());
+
+        let mut reader = MessageReader::new();
+        reader.push(bytes[..3000].to_vec());
+        let (err, head) = reader.parse(false).unwrap_err();
+        assert_eq!(ErrorInfo::new(&err, &head).kind, "truncated");
+    }
+}
@@ -0,0 +1,454 @@
+use crate::ole::{writer::ROOT, Writer};
+
+use super::{
+    constants::{LidNameMap, PropIdNameMap, PS_INTERNET_HEADERS, PS_MAPI, PS_PUBLIC_STRINGS},
+    decode::{datetime_to_filetime, DataType},
+    named::{ENTRY_STREAM, GUID_STREAM, NAMEID_STORAGE, STRING_STREAM},
+    outlook::Outlook,
+    storage::{Properties, EMBEDDED_MESSAGE_STORAGE},
+    verbose::MessageProperties,
+};
+
+const PROPERTY_STREAM: &str = "__properties_version1.0";
+
+// Class of the root storage of a message file.
+const MESSAGE_CLSID: [u8; 16] = [
+    0x0B, 0x0D, 0x02, 0x00, 0x00, 0x00, 0x00, 0x00, 0xC0, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x46,
+];
+
+// Properties are written readable and writable.
+// Refer to MS-OXMSG 2.4.2.1
+const PROPATTR_READABLE_WRITABLE: u32 = 0x2 | 0x4;
+
+// Number of streams named properties are hashed into.
+// Refer to MS-OXMSG 2.2.3.1.2
+const NAMEID_BUCKETS: u32 = 0x1F;
+
+// Property type of a value. Refer to MS-OXCDATA 2.11.1
+fn type_code(value: &DataType) -> u16 {
+    match value {
+        DataType::PtypInteger16(_) => 0x0002,
+        DataType::PtypInteger32(_) => 0x0003,
+        DataType::PtypFloating32(_) => 0x0004,
+        DataType::PtypFloating64(_) => 0x0005,
+        DataType::PtypCurrency(_) => 0x0006,
+        DataType::PtypFloatingTime(_) => 0x0007,
+        DataType::PtypErrorCode(_) => 0x000A,
+        DataType::PtypBoolean(_) => 0x000B,
+        DataType::PtypInteger64(_) => 0x0014,
+        DataType::PtypString(_) => 0x001F,
+        DataType::PtypTime(_) => 0x0040,
+        DataType::PtypBinary(_) => 0x0102,
+        DataType::PtypMultipleString(_) => 0x101F,
+        DataType::PtypMultipleBinary(_) => 0x1102,
+    }
+}
+
+// Value of a fixed length property as held in its
+// property stream entry, if it is one.
+fn fixed_value(value: &DataType) -> Option<[u8; 8]> {
+    let value = match *value {
+        DataType::PtypInteger16(value) => value as i64,
+        DataType::PtypInteger32(value) => value as u32 as i64,
+        DataType::PtypFloating32(value) => value.to_bits() as i64,
+        DataType::PtypFloating64(value) => value.to_bits() as i64,
+        DataType::PtypCurrency(value) => value,
+        DataType::PtypFloatingTime(value) => value.to_bits() as i64,
+        DataType::PtypErrorCode(value) => value as i64,
+        DataType::PtypBoolean(value) => value as i64,
+        DataType::PtypInteger64(value) => value,
+        DataType::PtypTime(ref time) => datetime_to_filetime(time) as i64,
+        _ => return None,
+    };
+    Some(value.to_le_bytes())
+}
+
+fn utf16_bytes(text: &str) -> Vec<u8> {
+    text.encode_utf16().flat_map(u16::to_le_bytes).collect()
+}
+
+// Inverse of BlobReader::read_guid.
+fn guid_bytes(guid: &str) -> [u8; 16] {
+    let hex = hex::decode(guid.replace('-', "")).unwrap_or_default();
+    let mut bytes = [0u8; 16];
+    if hex.len() == 16 {
+        bytes.copy_from_slice(&hex);
+        bytes[0..4].reverse();
+        bytes[4..6].reverse();
+        bytes[6..8].reverse();
+    }
+    bytes
+}
+
+// CRC-32 of the names of string named properties, as
+// used to pick their hash stream.
+// Refer to MS-OXMSG 2.2.3.1.2.2
+fn crc32(bytes: &[u8]) -> u32 {
+    let mut crc = 0u32;
+    for &byte in bytes {
+        crc ^= byte as u32;
+        for _ in 0..8 {
+            crc = if crc & 1 == 1 { (crc >> 1) ^ 0xEDB8_8320 } else { crc >> 1 };
+        }
+    }
+    crc
+}
+
+// Name of a named property, either a long ID (LID)
+// or a string.
+#[derive(Debug, Clone, PartialEq)]
+enum PropName {
+    Lid(u32),
+    String(String),
+}
+
+// PropTagMap assigns the ids of the properties to be
+// written, the ones of named properties being given in
+// the order they are first met.
+// Refer to MS-OXMSG 2.2.3
+struct PropTagMap {
+    prop_map: PropIdNameMap,
+    lid_map: LidNameMap,
+    guids: Vec<String>,
+    named: Vec<(u32, PropName)>,
+}
+
+impl PropTagMap {
+    fn new() -> Self {
+        Self {
+            prop_map: PropIdNameMap::init(),
+            lid_map: LidNameMap::init(),
+            guids: vec![],
+            named: vec![],
+        }
+    }
+
+    fn guid_index(&mut self, guid: &str) -> u32 {
+        match guid {
+            PS_MAPI => 1,
+            PS_PUBLIC_STRINGS => 2,
+            _ => {
+                let position = self.guids.iter().position(|known| known == guid);
+                let position = position.unwrap_or_else(|| {
+                    self.guids.push(guid.to_string());
+                    self.guids.len() - 1
+                });
+                position as u32 + 3
+            }
+        }
+    }
+
+    // Id of a property given its canonical name. Names
+    // that are no known property are taken for string
+    // named properties, as kept for Internet headers.
+    // Properties left under a hex identifier by the
+    // reader have no name to be written with.
+    fn prop_id(&mut self, name: &str) -> Option<u16> {
+        if let Some(id) = self.prop_map.get_id(name).filter(|&id| id < 0x8000) {
+            return Some(id);
+        }
+        if name.starts_with("0x") {
+            return None;
+        }
+        let (guid, prop_name) = match self.lid_map.get_lid(name) {
+            Some((guid, lid)) => (guid, PropName::Lid(lid)),
+            None => (PS_INTERNET_HEADERS.to_string(), PropName::String(name.to_string())),
+        };
+        let guid_index = self.guid_index(&guid);
+        let named = (guid_index, prop_name);
+        let position = self.named.iter().position(|known| *known == named);
+        let position = position.unwrap_or_else(|| {
+            self.named.push(named);
+            self.named.len() - 1
+        });
+        // Ids run up to 0xFFFE.
+        (position < 0x7FFF).then(|| 0x8000 + position as u16)
+    }
+
+    // Properties to write, in the order of their ids.
+    fn tagged<'a>(&mut self, props: &'a Properties) -> Vec<(u16, &'a DataType)> {
+        let mut names: Vec<&String> = props.keys().collect();
+        names.sort();
+        let mut tagged: Vec<(u16, &DataType)> = names
+            .into_iter()
+            .filter_map(|name| Some((self.prop_id(name)?, &props[name])))
+            .collect();
+        tagged.sort_by_key(|(id, _)| *id);
+        tagged
+    }
+
+    // Streams of the named property mapping storage.
+    // Refer to MS-OXMSG 2.2.3.1
+    fn write_nameid(&self, writer: &mut Writer) {
+        let storage = writer.add_storage(ROOT, NAMEID_STORAGE);
+        let guids: Vec<u8> = self.guids.iter().flat_map(|guid| guid_bytes(guid)).collect();
+        let mut entries = vec![];
+        let mut strings = vec![];
+        let mut buckets: Vec<Vec<u8>> = vec![vec![]; NAMEID_BUCKETS as usize];
+        for (index, (guid_index, name)) in self.named.iter().enumerate() {
+            let (name_id, hash, kind) = match name {
+                PropName::Lid(lid) => (*lid, *lid, 0),
+                PropName::String(name) => {
+                    let name = utf16_bytes(name);
+                    let offset = strings.len() as u32;
+                    strings.extend((name.len() as u32).to_le_bytes());
+                    strings.extend(&name);
+                    strings.resize(strings.len().next_multiple_of(4), 0);
+                    (offset, crc32(&name), 1)
+                }
+            };
+            let index_and_kind = ((index as u32) << 16) | (guid_index << 1) | kind;
+            entries.extend(name_id.to_le_bytes());
+            entries.extend(index_and_kind.to_le_bytes());
+            let bucket = (hash ^ ((guid_index << 1) | kind)) % NAMEID_BUCKETS;
+            buckets[bucket as usize].extend(hash.to_le_bytes());
+            buckets[bucket as usize].extend(index_and_kind.to_le_bytes());
+        }
+        writer.add_stream(storage, GUID_STREAM, guids);
+        writer.add_stream(storage, ENTRY_STREAM, entries);
+        writer.add_stream(storage, STRING_STREAM, strings);
+        for (bucket, stream) in buckets.into_iter().enumerate() {
+            if !stream.is_empty() {
+                let name = format!("__substg1.0_{:04X}0102", 0x1000 + bucket);
+                writer.add_stream(storage, &name, stream);
+            }
+        }
+    }
+}
+
+// Writes the properties of a storage: one stream per
+// variable length value, and the property stream listing
+// them all after the header of the storage.
+// Refer to MS-OXMSG 2.1 and 2.4
+fn write_properties(
+    writer: &mut Writer,
+    storage: usize,
+    tagged: Vec<(u16, &DataType)>,
+    header: Vec<u8>,
+) {
+    let mut property_stream = header;
+    for (id, value) in tagged {
+        let code = type_code(value);
+        let stream = format!("__substg1.0_{:04X}{:04X}", id, code);
+        let entry_value = match fixed_value(value) {
+            Some(fixed) => fixed,
+            None => {
+                let size = match value {
+                    DataType::PtypString(string) => {
+                        let bytes = utf16_bytes(string);
+                        let size = bytes.len() + 2;
+                        writer.add_stream(storage, &stream, bytes);
+                        size
+                    }
+                    DataType::PtypBinary(bytes) => {
+                        writer.add_stream(storage, &stream, bytes.to_vec());
+                        bytes.len()
+                    }
+                    DataType::PtypMultipleString(strings) => {
+                        let mut lengths = vec![];
+                        for (index, string) in strings.iter().enumerate() {
+                            let mut bytes = utf16_bytes(string);
+                            bytes.extend([0, 0]);
+                            lengths.extend((bytes.len() as u32).to_le_bytes());
+                            writer.add_stream(storage, &format!("{}-{:08X}", stream, index), bytes);
+                        }
+                        let size = lengths.len();
+                        writer.add_stream(storage, &stream, lengths);
+                        size
+                    }
+                    DataType::PtypMultipleBinary(values) => {
+                        let mut lengths = vec![];
+                        for (index, bytes) in values.iter().enumerate() {
+                            lengths.extend((bytes.len() as u64).to_le_bytes());
+                            writer.add_stream(storage, &format!("{}-{:08X}", stream, index), bytes.to_vec());
+                        }
+                        let size = lengths.len();
+                        writer.add_stream(storage, &stream, lengths);
+                        size
+                    }
+                    _ => 0,
+                };
+                (size as u64).to_le_bytes()
+            }
+        };
+        property_stream.extend(code.to_le_bytes());
+        property_stream.extend(id.to_le_bytes());
+        property_stream.extend(PROPATTR_READABLE_WRITABLE.to_le_bytes());
+        property_stream.extend(entry_value);
+    }
+    writer.add_stream(storage, PROPERTY_STREAM, property_stream);
+}
+
+impl MessageProperties {
+    // Lays out the properties as an MSG file: the message
+    // at the root, one storage per recipient and attachment.
+    // Refer to MS-OXMSG 2.2
+    pub fn to_msg(&self) -> Vec<u8> {
+        let mut writer = Writer::new();
+        writer.set_clsid(ROOT, MESSAGE_CLSID);
+        let mut tags = PropTagMap::new();
+        self.write_message(&mut writer, ROOT, &mut tags);
+        tags.write_nameid(&mut writer);
+        writer.to_bytes()
+    }
+
+    // Writes the message in a storage, the root one or that
+    // of an attachment embedding it, whose header lacks the
+    // reserved bytes the root one ends with. Embedded messages
+    // share the named property mapping of the file.
+    // Refer to MS-OXMSG 2.2.2.1 and 2.4.1
+    fn write_message(&self, writer: &mut Writer, storage: usize, tags: &mut PropTagMap) {
+        let recipients = self.recipients.len() as u32;
+        let attachments = self.attachments.len() as u32;
+        let mut header = vec![0u8; 8];
+        for value in [recipients, attachments, recipients, attachments] {
+            header.extend(value.to_le_bytes());
+        }
+        if storage == ROOT {
+            header.extend([0u8; 8]);
+        }
+        write_properties(writer, storage, tags.tagged(&self.root), header);
+
+        for (index, props) in self.recipients.iter().enumerate() {
+            let recipient = writer.add_storage(storage, &format!("__recip_version1.0_#{:08X}", index));
+            write_properties(writer, recipient, tags.tagged(props), vec![0u8; 8]);
+        }
+        for (index, props) in self.attachments.iter().enumerate() {
+            let attachment = writer.add_storage(storage, &format!("__attach_version1.0_#{:08X}", index));
+            write_properties(writer, attachment, tags.tagged(props), vec![0u8; 8]);
+            if let Some(Some(message)) = self.embedded.get(index) {
+                let embedded = writer.add_storage(attachment, EMBEDDED_MESSAGE_STORAGE);
+                writer.set_clsid(embedded, MESSAGE_CLSID);
+                message.write_message(writer, embedded, tags);
+            }
+        }
+    }
+}
+
+impl Outlook {
+    // Writes the message back as an MSG file, every
+    // decoded property included.
+    pub fn to_msg(&self) -> Vec<u8> {
+        self.message_properties().to_msg()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{TimeZone as _, Utc};
+
+    use super::{
+        super::{
+            decode::DataType,
+            outlook::{Attachment, Outlook},
+            storage::Properties,
+            verbose::MessageProperties,
+        },
+        crc32, guid_bytes,
+    };
+    use crate::parser::constants::PSETID_APPOINTMENT;
+
+    // Properties the reader leaves under a hex identifier
+    // have no name to be written with.
+    fn named(props: &Properties) -> Properties {
+        props
+            .iter()
+            .filter(|(key, _)| !key.starts_with("0x"))
+            .map(|(key, value)| (key.to_string(), value.clone()))
+            .collect()
+    }
+
+    #[test]
+    fn test_guid_bytes() {
+        assert_eq!(
+            guid_bytes(PSETID_APPOINTMENT),
+            [
+                0x02, 0x20, 0x06, 0x00, 0x00, 0x00, 0x00, 0x00, 0xC0, 0x00, 0x00, 0x00, 0x00, 0x00,
+                0x00, 0x46
+            ]
+        );
+    }
+
+    #[test]
+    fn test_crc32() {
+        assert_eq!(crc32(b""), 0);
+        assert_eq!(crc32(b"123456789"), 0x2DFD_2D88);
+    }
+
+    #[test]
+    fn test_round_trip() {
+        for path in [
+            "data/test_email.msg",
+            "data/test_email_1.msg",
+            "data/test_email_2.msg",
+            "data/test_email_3.msg",
+            "data/test_email_4.msg",
+            "data/attachment.msg",
+            "data/unicode.msg",
+        ] {
+            let outlook = Outlook::from_path(path).unwrap();
+            let written = Outlook::from_slice(&outlook.to_msg()).unwrap();
+            assert_eq!(named(&written.properties.root), named(&outlook.properties.root), "{}", path);
+            assert_eq!(written.properties.recipients.len(), outlook.properties.recipients.len());
+            for (written, original) in written
+                .properties
+                .recipients
+                .iter()
+                .zip(outlook.properties.recipients.iter())
+                .chain(written.properties.attachments.iter().zip(outlook.properties.attachments.iter()))
+            {
+                assert_eq!(named(written), named(original), "{}", path);
+            }
+            assert_eq!(written.subject, outlook.subject);
+            assert_eq!(written.sender, outlook.sender);
+            for (written, original) in written.attachments.iter().zip(outlook.attachments.iter()) {
+                assert_eq!(written.payload, original.payload, "{}", path);
+                let subject = |attachment: &Attachment| attachment.embedded.as_ref().map(|message| message.subject.clone());
+                assert_eq!(subject(written), subject(original), "{}", path);
+                if let (Some(written), Some(original)) = (&written.embedded, &original.embedded) {
+                    assert_eq!(named(&written.properties.root), named(&original.properties.root), "{}", path);
+                    assert_eq!(written.recipients.len(), original.recipients.len(), "{}", path);
+                    assert_eq!(written.attachments.len(), original.attachments.len(), "{}", path);
+                }
+            }
+        }
+        let embedded = |outlook: &Outlook| outlook.attachments.iter().filter(|a| a.embedded.is_some()).count();
+        for (path, count) in [("data/test_email_1.msg", 3), ("data/test_email_2.msg", 3)] {
+            let outlook = Outlook::from_path(path).unwrap();
+            assert_eq!(embedded(&outlook), count, "{}", path);
+            assert_eq!(embedded(&Outlook::from_slice(&outlook.to_msg()).unwrap()), count, "{}", path);
+        }
+    }
+
+    #[test]
+    fn test_value_types() {
+        let mut root = Properties::new();
+        let values = [
+            ("Subject", DataType::PtypString("Olá".to_string())),
+            ("Importance", DataType::PtypInteger32(2)),
+            ("Read", DataType::PtypBoolean(true)),
+            (
+                "ClientSubmitTime",
+                DataType::PtypTime(Utc.with_ymd_and_hms(2021, 3, 14, 4, 3, 57).unwrap()),
+            ),
+            ("ConversationIndex", DataType::PtypBinary(vec![1, 2, 3])),
+            (
+                "Keywords",
+                DataType::PtypMultipleString(vec!["a".to_string(), "bc".to_string()]),
+            ),
+            ("Location", DataType::PtypString("Room 1".to_string())),
+            ("content-class", DataType::PtypString("urn:content-classes:message".to_string())),
+        ];
+        for (key, value) in values.iter() {
+            root.insert(key.to_string(), value.clone());
+        }
+        let props = MessageProperties {
+            root,
+            ..MessageProperties::default()
+        };
+        let written = Outlook::from_slice(&props.to_msg()).unwrap();
+        for (key, value) in values.iter() {
+            assert_eq!(written.properties.root.get(*key), Some(value), "{}", key);
+        }
+    }
+}